@@ -0,0 +1,242 @@
+//! cgroup v2 resource limits for native builds.
+//! 原生构建的 cgroup v2 资源限制。
+//!
+//! Each limited build gets its own cgroup below the one Neve runs in, with
+//! `memory.max` and `cpu.max` set. The build process joins it before it
+//! executes the builder, so everything the builder spawns is limited too.
+//! Afterwards `memory.events` tells whether the kernel killed anything for
+//! running out of memory.
+//! 每个受限构建在 Neve 所在 cgroup 之下拥有自己的 cgroup，并设置 `memory.max` 和
+//! `cpu.max`。构建进程在执行构建器之前加入该 cgroup，因此构建器派生的所有进程也受到限制。
+//! 之后通过 `memory.events` 判断内核是否因内存不足而杀死了进程。
+//!
+//! A cgroup with processes of its own cannot hand controllers to its
+//! children, so before the first build Neve moves itself into a leaf cgroup
+//! next to the build cgroups. That needs Neve to be the only process in its
+//! cgroup, as in a delegated scope such as
+//! `systemd-run --user --scope -p Delegate=yes neve build`.
+//! 拥有自身进程的 cgroup 无法将控制器交给其子 cgroup，因此在第一次构建之前，Neve 会将
+//! 自身移入与构建 cgroup 并列的叶子 cgroup。这要求 Neve 是其 cgroup 中唯一的进程，
+//! 例如在 `systemd-run --user --scope -p Delegate=yes neve build` 这样的委派作用域中。
+
+use crate::BuildError;
+use std::ffi::{CStr, CString};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Mount point of the cgroup v2 hierarchy.
+/// cgroup v2 层级的挂载点。
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// Period of `cpu.max`, in microseconds.
+/// `cpu.max` 的周期（微秒）。
+const CPU_PERIOD: u64 = 100_000;
+
+/// Smallest quota the kernel accepts, in microseconds.
+/// 内核接受的最小配额（微秒）。
+const MIN_CPU_QUOTA: u64 = 1_000;
+
+/// Name of the leaf cgroup Neve moves itself into.
+/// Neve 将自身移入的叶子 cgroup 的名称。
+const SUPERVISOR_CGROUP: &str = "neve-supervisor";
+
+/// The cgroup build cgroups are created in, once Neve has left it.
+/// 创建构建 cgroup 的父 cgroup（Neve 离开它之后）。
+static PARENT: OnceLock<Result<PathBuf, String>> = OnceLock::new();
+
+/// Counter keeping the cgroups of concurrent builds apart.
+/// 用于区分并发构建 cgroup 的计数器。
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Check whether the unified cgroup v2 hierarchy is mounted.
+/// 检查是否挂载了统一的 cgroup v2 层级。
+pub fn cgroup_v2_available() -> bool {
+    cfg!(target_os = "linux") && Path::new(CGROUP_ROOT).join("cgroup.controllers").exists()
+}
+
+/// The `cpu.max` setting allowing `cpus` CPUs' worth of time.
+/// 允许使用 `cpus` 个 CPU 时间的 `cpu.max` 设置。
+pub fn cpu_max(cpus: f64) -> String {
+    let quota = ((cpus * CPU_PERIOD as f64).round() as u64).max(MIN_CPU_QUOTA);
+    format!("{} {}", quota, CPU_PERIOD)
+}
+
+/// The cgroup v2 path of a process, from the contents of `/proc/<pid>/cgroup`.
+/// 根据 `/proc/<pid>/cgroup` 的内容获取进程的 cgroup v2 路径。
+pub fn parse_proc_cgroup(content: &str) -> Option<PathBuf> {
+    content
+        .lines()
+        .find_map(|line| line.strip_prefix("0::"))
+        .map(|path| PathBuf::from(path.trim()))
+}
+
+/// The number of OOM kills recorded in the contents of `memory.events`.
+/// `memory.events` 内容中记录的 OOM 终止次数。
+pub fn oom_kills(events: &str) -> u64 {
+    events
+        .lines()
+        .find_map(|line| line.strip_prefix("oom_kill "))
+        .and_then(|count| count.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+/// A cgroup holding one build, removed when dropped.
+/// 容纳一个构建的 cgroup，在丢弃时删除。
+#[derive(Debug)]
+pub struct BuildCgroup {
+    /// Directory of the cgroup. / cgroup 的目录。
+    path: PathBuf,
+    /// Path of its `cgroup.procs` file. / 其 `cgroup.procs` 文件的路径。
+    procs: CString,
+}
+
+impl BuildCgroup {
+    /// Create a cgroup for a build with the given limits.
+    /// 为构建创建具有给定限制的 cgroup。
+    pub fn create(memory_max: Option<u64>, cpu_quota: Option<f64>) -> Result<Self, BuildError> {
+        let cgroup_error = |what: &str, e: std::io::Error| {
+            BuildError::Sandbox(format!("cannot {} cgroup: {}", what, e))
+        };
+
+        let parent = PARENT
+            .get_or_init(|| delegate_own_cgroup().map_err(|e| e.to_string()))
+            .clone()
+            .map_err(BuildError::Sandbox)?;
+
+        // Controllers must be enabled in the parent to be usable in the child
+        // 控制器必须在父 cgroup 中启用才能在子 cgroup 中使用
+        let mut controllers = Vec::new();
+        if memory_max.is_some() {
+            controllers.push("+memory");
+        }
+        if cpu_quota.is_some() {
+            controllers.push("+cpu");
+        }
+        fs::write(parent.join("cgroup.subtree_control"), controllers.join(" "))
+            .map_err(|e| cgroup_error("enable controllers for the build", e))?;
+
+        let name = format!(
+            "neve-build-{}-{}",
+            std::process::id(),
+            NEXT_ID.fetch_add(1, Ordering::Relaxed)
+        );
+        let path = parent.join(name);
+        fs::create_dir(&path).map_err(|e| cgroup_error("create the build", e))?;
+        let procs = CString::new(path.join("cgroup.procs").to_string_lossy().into_owned())
+            .map_err(|_| BuildError::Sandbox("cgroup path contains a null byte".into()))?;
+        let cgroup = Self { path, procs };
+
+        if let Some(bytes) = memory_max {
+            cgroup.write("memory.max", &bytes.to_string())?;
+            // Without swap the limit is hit instead of paging out
+            // 禁用交换空间，使限制生效而不是换出内存
+            let _ = fs::write(cgroup.path.join("memory.swap.max"), "0");
+        }
+        if let Some(cpus) = cpu_quota {
+            cgroup.write("cpu.max", &cpu_max(cpus))?;
+        }
+
+        Ok(cgroup)
+    }
+
+    /// Write a cgroup control file.
+    /// 写入 cgroup 控制文件。
+    fn write(&self, file: &str, value: &str) -> Result<(), BuildError> {
+        fs::write(self.path.join(file), value)
+            .map_err(|e| BuildError::Sandbox(format!("cannot set {} of build cgroup: {}", file, e)))
+    }
+
+    /// The directory of the cgroup.
+    /// cgroup 的目录。
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The `cgroup.procs` file that processes join the cgroup through.
+    /// 进程加入 cgroup 所用的 `cgroup.procs` 文件。
+    pub fn procs(&self) -> &CStr {
+        &self.procs
+    }
+
+    /// Whether a process in the cgroup was killed for exceeding the memory
+    /// limit.
+    /// cgroup 中是否有进程因超出内存限制而被杀死。
+    pub fn oom_killed(&self) -> bool {
+        fs::read_to_string(self.path.join("memory.events"))
+            .map(|events| oom_kills(&events) > 0)
+            .unwrap_or(false)
+    }
+}
+
+impl Drop for BuildCgroup {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir(&self.path);
+    }
+}
+
+/// Move Neve out of its own cgroup into a leaf below it, returning the
+/// cgroup that build cgroups can now be created in.
+/// 将 Neve 从其自身的 cgroup 移入其下的叶子 cgroup，并返回现在可以在其中创建构建 cgroup
+/// 的 cgroup。
+///
+/// Fails if any other process shares the cgroup, since it would keep the
+/// controllers from being enabled for the builds.
+/// 如果有其他进程共享该 cgroup 则失败，因为它会阻止为构建启用控制器。
+fn delegate_own_cgroup() -> Result<PathBuf, BuildError> {
+    let cgroup_error = |what: &str, e: std::io::Error| {
+        BuildError::Sandbox(format!("cannot {} cgroup: {}", what, e))
+    };
+
+    let own =
+        fs::read_to_string("/proc/self/cgroup").map_err(|e| cgroup_error("find the current", e))?;
+    let own = parse_proc_cgroup(&own)
+        .ok_or_else(|| BuildError::Sandbox("not running in a cgroup v2 hierarchy".into()))?;
+    let parent = Path::new(CGROUP_ROOT).join(own.strip_prefix("/").unwrap_or(&own));
+
+    let procs = fs::read_to_string(parent.join("cgroup.procs"))
+        .map_err(|e| cgroup_error("list the processes of the current", e))?;
+    let pid = std::process::id().to_string();
+    if procs.lines().any(|p| p.trim() != pid) {
+        return Err(BuildError::Sandbox(format!(
+            "cgroup {} is shared with other processes; run neve in a delegated cgroup",
+            own.display()
+        )));
+    }
+
+    let supervisor = parent.join(SUPERVISOR_CGROUP);
+    if let Err(e) = fs::create_dir(&supervisor)
+        && e.kind() != std::io::ErrorKind::AlreadyExists
+    {
+        return Err(cgroup_error("create the supervisor", e));
+    }
+    fs::write(supervisor.join("cgroup.procs"), &pid)
+        .map_err(|e| cgroup_error("move neve into the supervisor", e))?;
+    Ok(parent)
+}
+
+/// Move the calling process into the cgroup whose `cgroup.procs` is `procs`.
+/// 将调用进程移入 `cgroup.procs` 为 `procs` 的 cgroup。
+///
+/// Only async-signal-safe calls are made, so this may run between `fork`
+/// and `exec`.
+/// 仅使用异步信号安全的调用，因此可以在 `fork` 与 `exec` 之间运行。
+#[cfg(unix)]
+pub(crate) fn join(procs: &CStr) -> std::io::Result<()> {
+    // `procs` is a valid C string, and the descriptor is closed before returning
+    // `procs` 是有效的 C 字符串，且描述符在返回前关闭
+    unsafe {
+        let fd = libc::open(procs.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC);
+        if fd < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        let written = libc::write(fd, b"0".as_ptr().cast(), 1);
+        let error = std::io::Error::last_os_error();
+        libc::close(fd);
+        if written != 1 {
+            return Err(error);
+        }
+    }
+    Ok(())
+}
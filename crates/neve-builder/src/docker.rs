@@ -9,7 +9,9 @@
 //! 平台（macOS、Windows）上进行沙箱化、可复现的构建。
 
 use crate::BuildError;
+use crate::sandbox;
 use neve_derive::Derivation;
+use neve_fetch::CancelToken;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
@@ -75,6 +77,11 @@ pub struct DockerConfig {
     pub cpu_limit: Option<String>,
     /// Network mode ("none", "bridge", etc.). / 网络模式（"none"、"bridge" 等）。
     pub network_mode: String,
+    /// Build timeout in seconds (0 = no timeout). / 构建超时（秒，0 表示无超时）。
+    pub timeout: u64,
+    /// Token that kills the running build once cancelled.
+    /// 取消后终止运行中构建的令牌。
+    pub cancel: CancelToken,
 }
 
 impl Default for DockerConfig {
@@ -87,10 +94,30 @@ impl Default for DockerConfig {
             memory_limit: None,
             cpu_limit: None,
             network_mode: "none".to_string(),
+            timeout: 0,
+            cancel: CancelToken::new(),
         }
     }
 }
 
+impl DockerConfig {
+    /// Apply a builder's memory limit in bytes and CPU quota in CPUs.
+    /// 应用构建器的内存限制（字节）和 CPU 配额（以 CPU 个数计）。
+    pub fn with_resource_limits(
+        mut self,
+        memory_limit_bytes: Option<u64>,
+        cpu_quota: Option<f64>,
+    ) -> Self {
+        if let Some(bytes) = memory_limit_bytes {
+            self.memory_limit = Some(bytes.to_string());
+        }
+        if let Some(cpus) = cpu_quota {
+            self.cpu_limit = Some(cpus.to_string());
+        }
+        self
+    }
+}
+
 /// Docker build executor.
 /// Docker 构建执行器。
 pub struct DockerExecutor {
@@ -211,6 +238,41 @@ impl DockerExecutor {
         drv: &Derivation,
         build_dir: &Path,
         output_dir: &Path,
+    ) -> Result<std::process::Output, BuildError> {
+        self.execute_with_log(drv, build_dir, output_dir, &mut |_| {})
+    }
+
+    /// Execute a build in Docker, passing each line the container writes to
+    /// `on_line` as soon as it is written.
+    /// 在 Docker 中执行构建，并将容器写出的每一行在写出后立即传给 `on_line`。
+    pub fn execute_with_log(
+        &self,
+        drv: &Derivation,
+        build_dir: &Path,
+        output_dir: &Path,
+        on_line: &mut dyn FnMut(&str),
+    ) -> Result<std::process::Output, BuildError> {
+        let mut env: HashMap<String, String> = drv.env.clone().into_iter().collect();
+        env.insert("HOME".to_string(), "/build".to_string());
+        env.insert("TMPDIR".to_string(), "/tmp".to_string());
+        env.insert("out".to_string(), "/output".to_string());
+        env.insert("NIX_BUILD_CORES".to_string(), num_cpus::get().to_string());
+        self.run_with_log(drv, &drv.args, &env, build_dir, output_dir, on_line)
+    }
+
+    /// Run a derivation's builder in Docker with the given arguments and
+    /// environment, which must use the container paths `/neve/store`,
+    /// `/build` and `/output`.
+    /// 使用给定的参数和环境在 Docker 中运行推导的构建器，它们必须使用容器路径
+    /// `/neve/store`、`/build` 和 `/output`。
+    pub fn run_with_log(
+        &self,
+        drv: &Derivation,
+        builder_args: &[String],
+        env: &HashMap<String, String>,
+        build_dir: &Path,
+        output_dir: &Path,
+        on_line: &mut dyn FnMut(&str),
     ) -> Result<std::process::Output, BuildError> {
         // Ensure image exists
         // 确保镜像存在
@@ -230,7 +292,19 @@ impl DockerExecutor {
 
         // Build docker run arguments
         // 构建 docker run 参数
-        let mut args = vec!["run".to_string(), "--rm".to_string()];
+        // Name the container so it can be stopped on timeout
+        // 为容器命名，以便超时时将其停止
+        let name = format!(
+            "neve-build-{}-{}",
+            drv.hash().to_short_hex(),
+            std::process::id()
+        );
+        let mut args = vec![
+            "run".to_string(),
+            "--rm".to_string(),
+            "--name".to_string(),
+            name.clone(),
+        ];
 
         // Add volumes
         // 添加卷
@@ -258,22 +332,11 @@ impl DockerExecutor {
 
         // Add environment variables
         // 添加环境变量
-        for (key, value) in &drv.env {
+        for (key, value) in env {
             args.push("-e".to_string());
             args.push(format!("{}={}", key, value));
         }
 
-        // Standard environment
-        // 标准环境变量
-        args.push("-e".to_string());
-        args.push("HOME=/build".to_string());
-        args.push("-e".to_string());
-        args.push("TMPDIR=/tmp".to_string());
-        args.push("-e".to_string());
-        args.push("out=/output".to_string());
-        args.push("-e".to_string());
-        args.push(format!("NIX_BUILD_CORES={}", num_cpus::get()));
-
         // Add working directory
         // 添加工作目录
         args.push("-w".to_string());
@@ -286,19 +349,25 @@ impl DockerExecutor {
         // Add command
         // 添加命令
         args.push(drv.builder.clone());
-        for arg in &drv.args {
-            args.push(arg.clone());
-        }
+        args.extend(builder_args.iter().cloned());
 
         // Execute
         // 执行
-        let output = Command::new("docker")
-            .args(&args)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .output()?;
-
-        Ok(output)
+        let mut cmd = Command::new("docker");
+        cmd.args(&args);
+        match sandbox::run_with_log(&mut cmd, on_line, self.config.timeout, &self.config.cancel)? {
+            Some(output) => Ok(output),
+            None => {
+                // Killing the client leaves the container running
+                // 终止客户端不会停止容器
+                let _ = Command::new("docker")
+                    .args(["kill", &name])
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .status();
+                Err(sandbox::stopped(self.config.timeout, &self.config.cancel))
+            }
+        }
     }
 }
 
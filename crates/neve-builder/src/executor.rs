@@ -3,14 +3,55 @@
 //!
 //! Executes derivation builds in sandboxed environments.
 //! 在沙箱环境中执行派生构建。
-
+//!
+//! Besides the derivation's own `env`, the builder sees one variable per
+//! output and per input, holding the path as visible inside the sandbox:
+//! 除推导自身的 `env` 外，构建器还能看到每个输出和每个输入对应的变量，
+//! 其值为沙箱内可见的路径：
+//!
+//! - each output by its name, e.g. `$out`, `$dev`
+//!   每个输出以其名称命名，例如 `$out`、`$dev`
+//! - each input source by its store name, e.g. `$src`
+//!   每个输入源以其存储名称命名，例如 `$src`
+//! - each output of an input derivation by the derivation name, with a
+//!   `_<output>` suffix for outputs other than `out`, e.g. `$zlib`, `$zlib_dev`
+//!   输入推导的每个输出以推导名称命名，`out` 以外的输出带 `_<output>` 后缀，
+//!   例如 `$zlib`、`$zlib_dev`
+//!
+//! Characters that cannot appear in a shell variable name are replaced by
+//! `_`, and names already set in the derivation's `env` are left alone.
+//! Input paths written in `env` as `/neve/store/...` are rewritten to where
+//! the store is actually visible.
+//! 不能出现在 shell 变量名中的字符被替换为 `_`，推导 `env` 中已设置的名称保持不变。
+//! `env` 中以 `/neve/store/...` 形式写出的输入路径会被改写为存储实际可见的位置。
+//!
+//! Outputs are content-addressed, so `env` and `args` cannot name an
+//! output's store path up front. They use [`Derivation::placeholder`]
+//! instead, which the builder sees as the output directory; after the build
+//! the stored output has it rewritten to the realized store path.
+//! 输出是内容寻址的，因此 `env` 和 `args` 无法预先写出输出的存储路径，
+//! 而是使用 [`Derivation::placeholder`]。构建器将其视为输出目录；
+//! 构建完成后，存储中的输出会将其改写为实际的存储路径。
+
+use crate::docker::{DockerConfig, DockerExecutor};
 use crate::sandbox::{Sandbox, SandboxConfig};
-use crate::{BuildError, BuilderConfig};
-use neve_derive::{Derivation, Hash, StorePath};
-use neve_store::Store;
-use std::collections::HashMap;
+use crate::{BuildBackend, BuildError, BuilderConfig};
+use neve_derive::{Derivation, Hash, HashAlgo, StorePath};
+use neve_fetch::verify::hash_dir_with;
+use neve_store::{Store, rewrite_references};
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+/// Realized outputs of an input derivation.
+/// 输入推导的已实现输出。
+#[derive(Debug, Clone)]
+pub struct RealizedInput {
+    /// Name of the input derivation. / 输入推导的名称。
+    pub name: String,
+    /// Output store paths by output name. / 按输出名称索引的输出存储路径。
+    pub outputs: HashMap<String, StorePath>,
+}
 
 /// Build executor.
 /// 构建执行器。
@@ -34,36 +75,138 @@ impl<'a> BuildExecutor<'a> {
         &self,
         drv: &Derivation,
     ) -> Result<(HashMap<String, StorePath>, String), BuildError> {
-        // Create temporary build directory
-        // 创建临时构建目录
-        let build_id = format!("{}-{}", drv.name, uuid_simple());
-        let build_root = self.config.temp_dir.join(&build_id);
-        fs::create_dir_all(&build_root)?;
+        self.execute_with_log(drv, &BTreeMap::new(), &mut |_| {})
+    }
 
-        // Set up sandbox
-        // 设置沙箱
-        let sandbox_config = SandboxConfig::new(build_root.clone());
-        let sandbox = Sandbox::new(sandbox_config)?;
+    /// Execute a derivation build with the realized outputs of its input
+    /// derivations, keyed by derivation path.
+    /// 使用按推导路径索引的输入推导已实现输出执行派生构建。
+    ///
+    /// The build runs in a directory claimed by [`claim_build_dir`]. It is
+    /// removed once the build succeeds, and on failure too unless
+    /// `keep_failed` is set, in which case the failure names the kept
+    /// directory. Only fixed-output derivations may use the network; their
+    /// outputs must match the declared hash, or the build fails with
+    /// [`BuildError::OutputHashMismatch`]. A build still running after the
+    /// configured `timeout` is killed and fails as timed out.
+    /// 构建在由 [`claim_build_dir`] 占用的目录中运行。构建成功后删除该目录；
+    /// 失败时除非设置了 `keep_failed`，否则同样删除，此时失败信息会指明保留的目录。
+    /// 只有固定输出推导可以使用网络；其输出必须与声明的哈希一致，否则构建以
+    /// [`BuildError::OutputHashMismatch`] 失败。超过配置的 `timeout` 仍在运行的构建
+    /// 会被终止并以超时失败。
+    pub fn execute_with_inputs(
+        &self,
+        drv: &Derivation,
+        inputs: &BTreeMap<StorePath, RealizedInput>,
+    ) -> Result<(HashMap<String, StorePath>, String), BuildError> {
+        self.execute_with_log(drv, inputs, &mut |_| {})
+    }
+
+    /// Execute a derivation build like
+    /// [`execute_with_inputs`](Self::execute_with_inputs), passing each line
+    /// the builder writes to stdout or stderr to `on_line` as it arrives.
+    /// 像 [`execute_with_inputs`](Self::execute_with_inputs) 一样执行派生构建，
+    /// 并在构建器写到 stdout 或 stderr 的每一行到达时将其传给 `on_line`。
+    ///
+    /// The returned log still holds the complete output.
+    /// 返回的日志仍包含完整的输出。
+    pub fn execute_with_log(
+        &self,
+        drv: &Derivation,
+        inputs: &BTreeMap<StorePath, RealizedInput>,
+        on_line: &mut dyn FnMut(&str),
+    ) -> Result<(HashMap<String, StorePath>, String), BuildError> {
+        let build_root = claim_build_dir(&self.config.temp_dir, drv)?;
+
+        // Set up sandbox, with network access only for fixed-output
+        // derivations, whose outputs are checked against their hash
+        // 设置沙箱，仅固定输出推导可以访问网络，其输出会根据哈希进行检查
+        let mut sandbox_config = if drv.is_fixed_output() {
+            SandboxConfig::for_fixed_output(build_root.clone())
+        } else {
+            SandboxConfig::new(build_root.clone())
+        };
+        sandbox_config.store_dir = self.store.root().to_path_buf();
+        sandbox_config.env_allowlist = self.config.env_allowlist.clone();
+        sandbox_config.limits.memory_max = self.config.memory_limit_bytes;
+        sandbox_config.limits.cpu_quota = self.config.cpu_quota;
+        sandbox_config.limits.timeout = self.config.timeout;
+        sandbox_config.cancel = self.config.cancel.clone();
+        sandbox_config.container = self.config.backend == BuildBackend::Docker;
+        sandbox_config
+            .env
+            .extend(self.config.env_extra.iter().cloned());
+        let sandbox = match Sandbox::new(sandbox_config) {
+            Ok(sandbox) => sandbox,
+            Err(e) => {
+                let _ = fs::remove_dir_all(&build_root);
+                return Err(e);
+            }
+        };
+
+        let result = self.run_in_sandbox(drv, inputs, &sandbox, on_line);
+        if result.is_ok() || !self.config.keep_failed {
+            let _ = sandbox.cleanup();
+            return result;
+        }
+
+        eprintln!(
+            "Build failed. Keeping build directory: {}",
+            build_root.display()
+        );
+        result.map_err(|e| match e {
+            BuildError::BuildFailed(msg) => {
+                BuildError::BuildFailed(kept_dir_message(&msg, &build_root))
+            }
+            e => e,
+        })
+    }
 
+    /// Run the builder inside a prepared sandbox and collect its outputs.
+    /// 在准备好的沙箱中运行构建器并收集其输出。
+    fn run_in_sandbox(
+        &self,
+        drv: &Derivation,
+        inputs: &BTreeMap<StorePath, RealizedInput>,
+        sandbox: &Sandbox,
+        on_line: &mut dyn FnMut(&str),
+    ) -> Result<(HashMap<String, StorePath>, String), BuildError> {
         // Create tmp directory inside build
         // 在构建目录内创建 tmp 目录
         fs::create_dir_all(sandbox.build_dir().join("tmp"))?;
 
         // Prepare environment
         // 准备环境变量
-        let env = self.prepare_env(drv, &sandbox)?;
+        let placeholders = output_placeholders(drv, sandbox);
+        let env = self.prepare_env(drv, inputs, sandbox, &placeholders)?;
 
         // Set up input symlinks
         // 设置输入符号链接
-        self.setup_inputs(drv, &sandbox)?;
+        self.setup_inputs(drv, inputs, sandbox)?;
 
         // Create output directories
         // 创建输出目录
-        let output_dirs = self.create_output_dirs(drv, &sandbox)?;
+        let output_dirs = self.create_output_dirs(drv, sandbox)?;
 
         // Execute the builder
         // 执行构建器
-        let output = sandbox.execute(&drv.builder, &drv.args, &env)?;
+        let args: Vec<String> = drv
+            .args
+            .iter()
+            .map(|arg| expand_placeholders(arg, &placeholders))
+            .collect();
+        let output = if self.config.backend == BuildBackend::Docker {
+            self.docker(drv).run_with_log(
+                drv,
+                &args,
+                &env,
+                sandbox.build_dir(),
+                sandbox.output_dir(),
+                on_line,
+            )?
+        } else {
+            sandbox.execute_with_log(&drv.builder, &args, &env, on_line)?
+        };
 
         let log = format!(
             "=== stdout ===\n{}\n=== stderr ===\n{}",
@@ -72,14 +215,6 @@ impl<'a> BuildExecutor<'a> {
         );
 
         if !output.status.success() {
-            if self.config.keep_failed {
-                eprintln!(
-                    "Build failed. Keeping build directory: {}",
-                    build_root.display()
-                );
-            } else {
-                let _ = sandbox.cleanup();
-            }
             return Err(BuildError::BuildFailed(format!(
                 "builder exited with status {}\n{}",
                 output.status, log
@@ -88,78 +223,93 @@ impl<'a> BuildExecutor<'a> {
 
         // Collect outputs
         // 收集输出
-        let outputs = self.collect_outputs(drv, &output_dirs)?;
-
-        // Clean up
-        // 清理
-        if !self.config.keep_failed {
-            let _ = sandbox.cleanup();
-        }
+        let outputs = self.collect_outputs(drv, &output_dirs, &placeholders)?;
 
         Ok((outputs, log))
     }
 
+    /// Create the Docker executor for a build, with the builder's timeout and
+    /// resource limits and network access only for fixed-output derivations.
+    /// 为构建创建 Docker 执行器，使用构建器的超时和资源限制，且仅固定输出推导可以访问网络。
+    fn docker(&self, drv: &Derivation) -> DockerExecutor {
+        let mut config = DockerConfig {
+            timeout: self.config.timeout,
+            cancel: self.config.cancel.clone(),
+            ..DockerConfig::default()
+        }
+        .with_resource_limits(self.config.memory_limit_bytes, self.config.cpu_quota);
+        if drv.is_fixed_output() {
+            config.network_mode = "bridge".to_string();
+        }
+        DockerExecutor::with_config(
+            self.store.root().to_path_buf(),
+            self.config.temp_dir.clone(),
+            config,
+        )
+    }
+
     /// Prepare environment variables for the build.
     /// 为构建准备环境变量。
     fn prepare_env(
         &self,
         drv: &Derivation,
+        inputs: &BTreeMap<StorePath, RealizedInput>,
         sandbox: &Sandbox,
+        placeholders: &[(String, String)],
     ) -> Result<HashMap<String, String>, BuildError> {
-        // Convert BTreeMap to HashMap
-        // 将 BTreeMap 转换为 HashMap
+        let visible = |path: &Path| sandbox.inner_path(path).to_string_lossy().into_owned();
+
+        // Input paths as visible to the builder
+        // 构建器可见的输入路径
+        let mut input_vars = Vec::new();
+        for src in &drv.input_srcs {
+            input_vars.push((env_var_name(src.name()), src));
+        }
+        for (drv_path, output_names) in &drv.input_drvs {
+            let Some(input) = inputs.get(drv_path) else {
+                continue;
+            };
+            for output_name in output_names {
+                if let Some(path) = input.outputs.get(output_name) {
+                    let var = if output_name == "out" {
+                        input.name.clone()
+                    } else {
+                        format!("{}_{}", input.name, output_name)
+                    };
+                    input_vars.push((env_var_name(&var), path));
+                }
+            }
+        }
+
+        // Copy the derivation environment, pointing input paths at the real store
+        // and output placeholders at the output directories
+        // 复制推导环境变量，将输入路径指向实际存储，将输出占位符指向输出目录
         let mut env: HashMap<String, String> = drv
             .env
             .iter()
-            .map(|(k, v)| (k.clone(), v.clone()))
+            .map(|(k, v)| {
+                let mut value = expand_placeholders(v, placeholders);
+                for (_, path) in &input_vars {
+                    let canonical = path.to_string();
+                    if value.contains(&canonical) {
+                        value = value.replace(&canonical, &visible(&self.store.to_path(path)));
+                    }
+                }
+                (k.clone(), value)
+            })
             .collect();
 
         // Standard build environment variables
         // 标准构建环境变量
-        env.insert(
-            "NIX_BUILD_TOP".to_string(),
-            sandbox.build_dir().to_string_lossy().into_owned(),
-        );
-        env.insert(
-            "TMPDIR".to_string(),
-            sandbox
-                .build_dir()
-                .join("tmp")
-                .to_string_lossy()
-                .into_owned(),
-        );
-        env.insert(
-            "TEMPDIR".to_string(),
-            sandbox
-                .build_dir()
-                .join("tmp")
-                .to_string_lossy()
-                .into_owned(),
-        );
-        env.insert(
-            "TMP".to_string(),
-            sandbox
-                .build_dir()
-                .join("tmp")
-                .to_string_lossy()
-                .into_owned(),
-        );
-        env.insert(
-            "TEMP".to_string(),
-            sandbox
-                .build_dir()
-                .join("tmp")
-                .to_string_lossy()
-                .into_owned(),
-        );
-        env.insert(
-            "HOME".to_string(),
-            sandbox.build_dir().to_string_lossy().into_owned(),
-        );
-        env.insert(
-            "PWD".to_string(),
-            sandbox.build_dir().to_string_lossy().into_owned(),
-        );
+        let build_dir = visible(sandbox.build_dir());
+        let tmp_dir = visible(&sandbox.build_dir().join("tmp"));
+        env.insert("NIX_BUILD_TOP".to_string(), build_dir.clone());
+        env.insert("TMPDIR".to_string(), tmp_dir.clone());
+        env.insert("TEMPDIR".to_string(), tmp_dir.clone());
+        env.insert("TMP".to_string(), tmp_dir.clone());
+        env.insert("TEMP".to_string(), tmp_dir);
+        env.insert("HOME".to_string(), build_dir.clone());
+        env.insert("PWD".to_string(), build_dir);
 
         // Build info
         // 构建信息
@@ -172,12 +322,14 @@ impl<'a> BuildExecutor<'a> {
         // 输出路径
         for name in drv.outputs.keys() {
             let out_dir = sandbox.output_dir().join(name);
-            let var_name = if name == "out" {
-                "out".to_string()
-            } else {
-                name.clone()
-            };
-            env.insert(var_name, out_dir.to_string_lossy().into_owned());
+            env.insert(name.clone(), visible(&out_dir));
+        }
+
+        // Input paths, unless the derivation sets the name itself
+        // 输入路径，除非推导自身设置了该名称
+        for (var, path) in input_vars {
+            env.entry(var)
+                .or_insert_with(|| visible(&self.store.to_path(path)));
         }
 
         Ok(env)
@@ -185,52 +337,41 @@ impl<'a> BuildExecutor<'a> {
 
     /// Set up input paths in the sandbox.
     /// 在沙箱中设置输入路径。
-    fn setup_inputs(&self, drv: &Derivation, sandbox: &Sandbox) -> Result<(), BuildError> {
+    fn setup_inputs(
+        &self,
+        drv: &Derivation,
+        inputs: &BTreeMap<StorePath, RealizedInput>,
+        sandbox: &Sandbox,
+    ) -> Result<(), BuildError> {
         let inputs_dir = sandbox.build_dir().join("inputs");
         fs::create_dir_all(&inputs_dir)?;
 
-        // Link input derivation outputs
-        // 链接输入派生的输出
+        // Link realized outputs of input derivations
+        // 链接输入推导的已实现输出
         for (input_drv_path, output_names) in &drv.input_drvs {
-            let input_store_path = self.store.to_path(input_drv_path);
+            let Some(input) = inputs.get(input_drv_path) else {
+                continue;
+            };
 
             for output_name in output_names {
+                let Some(output_path) = input.outputs.get(output_name) else {
+                    continue;
+                };
                 let link_name = format!("{}-{}", input_drv_path.name(), output_name);
-                let link_path = inputs_dir.join(&link_name);
-
-                // In a real implementation, we would link to the actual output path
-                // 在实际实现中，我们会链接到实际的输出路径
-                // For now, just link to the derivation file
-                // 目前，只链接到派生文件
-                if input_store_path.exists() {
-                    #[cfg(unix)]
-                    std::os::unix::fs::symlink(&input_store_path, &link_path)?;
-
-                    #[cfg(not(unix))]
-                    fs::copy(&input_store_path, &link_path)?;
-                }
+                link_input(
+                    &self.store.to_path(output_path),
+                    &inputs_dir.join(link_name),
+                )?;
             }
         }
 
         // Link input sources
         // 链接输入源
         for input_src in &drv.input_srcs {
-            let src_path = self.store.to_path(input_src);
-            let link_path = inputs_dir.join(input_src.name());
-
-            if src_path.exists() {
-                #[cfg(unix)]
-                std::os::unix::fs::symlink(&src_path, &link_path)?;
-
-                #[cfg(not(unix))]
-                {
-                    if src_path.is_dir() {
-                        copy_dir_recursive(&src_path, &link_path)?;
-                    } else {
-                        fs::copy(&src_path, &link_path)?;
-                    }
-                }
-            }
+            link_input(
+                &self.store.to_path(input_src),
+                &inputs_dir.join(input_src.name()),
+            )?;
         }
 
         Ok(())
@@ -256,12 +397,30 @@ impl<'a> BuildExecutor<'a> {
 
     /// Collect outputs and register them in the store.
     /// 收集输出并将其注册到存储中。
+    ///
+    /// References to the output directories are first turned back into
+    /// placeholders, so the store hash does not depend on where the build
+    /// ran. Each output is then stored with its own placeholder rewritten to
+    /// its store path, and placeholders of outputs stored before it are
+    /// rewritten as well. Fixed outputs are stored as built.
+    /// 首先将对输出目录的引用还原为占位符，使存储哈希不依赖于构建位置。
+    /// 然后存储每个输出，并将其自身的占位符改写为其存储路径，
+    /// 在它之前存储的输出的占位符也会被改写。固定输出按构建结果原样存储。
     fn collect_outputs(
         &self,
         drv: &Derivation,
         output_dirs: &HashMap<String, std::path::PathBuf>,
+        placeholders: &[(String, String)],
     ) -> Result<HashMap<String, StorePath>, BuildError> {
         let mut outputs = HashMap::new();
+        // Longest directories first, so `out` does not clobber `out2`
+        // 先处理最长的目录，避免 `out` 覆盖 `out2`
+        let mut to_placeholders: Vec<(String, String)> = placeholders
+            .iter()
+            .map(|(placeholder, dir)| (dir.clone(), placeholder.clone()))
+            .collect();
+        to_placeholders.sort_by_key(|(dir, _)| std::cmp::Reverse(dir.len()));
+        let mut realized: Vec<(String, String)> = Vec::new();
 
         for (name, output) in &drv.outputs {
             let out_dir = output_dirs.get(name).ok_or_else(|| {
@@ -272,20 +431,10 @@ impl<'a> BuildExecutor<'a> {
             // 在收集之前验证输出
             crate::output::validate_output(out_dir)?;
 
-            // Compute hash of output
-            // 计算输出的哈希
-            let hash = hash_path(out_dir)?;
-
             // Verify hash if expected (for fixed-output derivations)
             // 如果有预期哈希则验证（用于固定输出派生）
-            if let Some(ref expected_hash) = output.expected_hash
-                && hash != *expected_hash
-            {
-                return Err(BuildError::OutputHashMismatch {
-                    output: name.clone(),
-                    expected: expected_hash.to_hex(),
-                    actual: hash.to_hex(),
-                });
+            if let Some(ref expected_hash) = output.expected_hash {
+                verify_output(name, out_dir, expected_hash)?;
             }
 
             // Create store path name
@@ -298,7 +447,19 @@ impl<'a> BuildExecutor<'a> {
 
             // Add output to store
             // 将输出添加到存储
-            let store_path = self.store.add_dir(out_dir, &store_name)?;
+            let store_path = if output.is_fixed() {
+                self.store.add_dir(out_dir, &store_name)?
+            } else {
+                rewrite_references(out_dir, &to_placeholders)?;
+                rewrite_references(out_dir, &realized)?;
+                let placeholder = drv.placeholder(name);
+                let store_path =
+                    self.store
+                        .add_dir_with_self_references(out_dir, &store_name, &placeholder)?;
+                let path = self.store.to_path(&store_path);
+                realized.push((placeholder, path.to_string_lossy().into_owned()));
+                store_path
+            };
 
             outputs.insert(name.clone(), store_path);
         }
@@ -307,44 +468,123 @@ impl<'a> BuildExecutor<'a> {
     }
 }
 
-/// Generate a simple unique ID.
-/// 生成简单的唯一 ID。
-fn uuid_simple() -> String {
-    use std::time::{SystemTime, UNIX_EPOCH};
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default();
-    format!("{:x}{:x}", now.as_secs(), now.subsec_nanos())
+/// Note the kept build directory on the first line of a failure message.
+/// 在失败消息的第一行注明保留的构建目录。
+fn kept_dir_message(msg: &str, build_root: &Path) -> String {
+    let (first, rest) = match msg.split_once('\n') {
+        Some((first, rest)) => (first, Some(rest)),
+        None => (msg, None),
+    };
+    let mut message = format!(
+        "{} (build directory kept at {})",
+        first,
+        build_root.display()
+    );
+    if let Some(rest) = rest {
+        message.push('\n');
+        message.push_str(rest);
+    }
+    message
+}
+
+/// Claim a fresh build directory for `drv` under `temp_dir`.
+/// 在 `temp_dir` 下为 `drv` 占用一个新的构建目录。
+///
+/// Directories are named `<drv-hash>-<attempt>`. Creating the directory is
+/// what claims it, so concurrent builds of the same derivation, and failed
+/// builds kept around by `keep_failed`, simply move on to the next attempt
+/// number instead of sharing a directory.
+/// 目录命名为 `<推导哈希>-<尝试次数>`。创建目录即表示占用，因此同一推导的并发构建
+/// 以及因 `keep_failed` 保留的失败构建会直接使用下一个尝试编号，而不会共享目录。
+pub fn claim_build_dir(temp_dir: &Path, drv: &Derivation) -> Result<PathBuf, BuildError> {
+    fs::create_dir_all(temp_dir)?;
+    let hash = drv.hash().to_short_hex();
+    let mut attempt = 0u32;
+    loop {
+        let dir = temp_dir.join(format!("{}-{}", hash, attempt));
+        match fs::create_dir(&dir) {
+            Ok(()) => return Ok(dir),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => attempt += 1,
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// Verify a fixed output against its expected hash.
+/// 根据预期哈希验证固定输出。
+fn verify_output(name: &str, path: &Path, expected: &Hash) -> Result<(), BuildError> {
+    let actual = hash_path(path, expected.algo())?;
+    if actual != *expected {
+        return Err(BuildError::OutputHashMismatch {
+            output: name.to_string(),
+            expected: expected.to_hex(),
+            actual: actual.to_hex(),
+        });
+    }
+    Ok(())
 }
 
 /// Hash a path (file or directory).
 /// 哈希路径（文件或目录）。
-fn hash_path(path: &Path) -> Result<Hash, BuildError> {
-    use neve_derive::Hasher;
+///
+/// Directories use [`hash_dir_with`], the same hashing as
+/// [`neve_fetch::verify::verify_dir`].
+/// 目录使用 [`hash_dir_with`]，与 [`neve_fetch::verify::verify_dir`] 的哈希方式相同。
+fn hash_path(path: &Path, algo: HashAlgo) -> Result<Hash, BuildError> {
+    if path.is_dir() {
+        Ok(hash_dir_with(path, algo)?)
+    } else {
+        Ok(Hash::of_with(algo, &fs::read(path)?))
+    }
+}
+
+/// Pair each output's placeholder with its directory as seen by the builder.
+/// 将每个输出的占位符与构建器所见的输出目录配对。
+fn output_placeholders(drv: &Derivation, sandbox: &Sandbox) -> Vec<(String, String)> {
+    drv.outputs
+        .keys()
+        .map(|name| {
+            let dir = sandbox.inner_path(&sandbox.output_dir().join(name));
+            (drv.placeholder(name), dir.to_string_lossy().into_owned())
+        })
+        .collect()
+}
 
-    let mut hasher = Hasher::new();
-    hash_path_recursive(path, &mut hasher)?;
-    Ok(hasher.finalize())
+/// Replace output placeholders in `value` with the output directories.
+/// 将 `value` 中的输出占位符替换为输出目录。
+fn expand_placeholders(value: &str, placeholders: &[(String, String)]) -> String {
+    placeholders
+        .iter()
+        .fold(value.to_string(), |value, (placeholder, dir)| {
+            value.replace(placeholder, dir)
+        })
 }
 
-/// Recursively hash a path.
-/// 递归哈希路径。
-fn hash_path_recursive(path: &Path, hasher: &mut neve_derive::Hasher) -> Result<(), BuildError> {
-    if path.is_file() {
-        let content = fs::read(path)?;
-        hasher.update(&content);
-    } else if path.is_dir() {
-        let mut entries: Vec<_> = fs::read_dir(path)?.filter_map(|e| e.ok()).collect();
-        entries.sort_by_key(|e| e.file_name());
-
-        for entry in entries {
-            let name = entry.file_name();
-            hasher.update(name.as_encoded_bytes());
-            hash_path_recursive(&entry.path(), hasher)?;
+/// Turn a store name into an environment variable name.
+/// 将存储名称转换为环境变量名称。
+fn env_var_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// Link an input path into the build directory, if it exists.
+/// 如果输入路径存在，则将其链接到构建目录中。
+fn link_input(src: &Path, link: &Path) -> Result<(), BuildError> {
+    if !src.exists() {
+        return Ok(());
+    }
+
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(src, link)?;
+
+    #[cfg(not(unix))]
+    {
+        if src.is_dir() {
+            copy_dir_recursive(src, link)?;
+        } else {
+            fs::copy(src, link)?;
         }
-    } else if path.is_symlink() {
-        let target = fs::read_link(path)?;
-        hasher.update(target.as_os_str().as_encoded_bytes());
     }
 
     Ok(())
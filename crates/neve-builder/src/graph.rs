@@ -0,0 +1,212 @@
+//! Build dependency graphs.
+//! 构建依赖图。
+//!
+//! A `BuildGraph` is the input closure of a derivation as data: one node per
+//! derivation or source path and one edge per dependency. It can be rendered
+//! to Graphviz DOT for visualization.
+//! `BuildGraph` 以数据形式表示推导的输入闭包：每个推导或源路径一个节点，
+//! 每个依赖一条边。它可以渲染为 Graphviz DOT 以供可视化。
+
+use crate::BuildError;
+use neve_derive::{Derivation, StorePath};
+use neve_store::Store;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Kind of a node in the build graph.
+/// 构建图中节点的类型。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    /// A derivation that has to be built. / 需要构建的推导。
+    Derivation,
+    /// A source path already in the store. / 已在存储中的源路径。
+    Source,
+}
+
+/// A node in the build graph.
+/// 构建图中的节点。
+#[derive(Debug, Clone)]
+pub struct GraphNode {
+    /// Store path of the derivation or source. / 推导或源的存储路径。
+    pub path: StorePath,
+    /// Node kind. / 节点类型。
+    pub kind: NodeKind,
+    /// Human-readable label. / 可读标签。
+    pub label: String,
+}
+
+/// Visit state used while building the graph.
+/// 构建图时使用的访问状态。
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Visit {
+    InProgress,
+    Done,
+}
+
+/// Dependency graph of a derivation's input closure.
+/// 推导输入闭包的依赖图。
+///
+/// Edges point from a derivation to the inputs it depends on. Shared
+/// dependencies appear once, and construction fails on cycles.
+/// 边从推导指向其依赖的输入。共享依赖只出现一次，遇到循环时构建失败。
+#[derive(Debug, Clone, Default)]
+pub struct BuildGraph {
+    /// All nodes; index 0 is the root derivation. / 所有节点；索引 0 为根推导。
+    nodes: Vec<GraphNode>,
+    /// Edges as (dependent, dependency) node indices. / 以（依赖方，被依赖方）节点索引表示的边。
+    edges: Vec<(usize, usize)>,
+    /// Node index by store path. / 按存储路径索引的节点。
+    index: HashMap<StorePath, usize>,
+}
+
+impl BuildGraph {
+    /// Build the graph of `drv` and everything it depends on.
+    /// 构建 `drv` 及其所有依赖的图。
+    ///
+    /// Input derivations are read from the store.
+    /// 输入推导从存储中读取。
+    pub fn from_derivation(store: &mut Store, drv: &Derivation) -> Result<Self, BuildError> {
+        let mut graph = Self::default();
+        let mut visits = HashMap::new();
+        graph.visit(store, drv.drv_path(), drv, &mut visits)?;
+        Ok(graph)
+    }
+
+    /// Add a derivation node and, recursively, its inputs.
+    /// 添加推导节点并递归添加其输入。
+    fn visit(
+        &mut self,
+        store: &mut Store,
+        path: StorePath,
+        drv: &Derivation,
+        visits: &mut HashMap<StorePath, Visit>,
+    ) -> Result<usize, BuildError> {
+        visits.insert(path.clone(), Visit::InProgress);
+        let node = self.add_node(path.clone(), NodeKind::Derivation, drv_label(drv));
+
+        for input_path in drv.input_drvs.keys() {
+            let dep = match visits.get(input_path) {
+                Some(Visit::InProgress) => {
+                    return Err(BuildError::DependencyCycle(input_path.display_name()));
+                }
+                Some(Visit::Done) => self.index[input_path],
+                None => {
+                    if !store.path_exists(input_path) {
+                        return Err(BuildError::MissingInput(input_path.display_name()));
+                    }
+                    let input = store.read_derivation(input_path)?;
+                    self.visit(store, input_path.clone(), &input, visits)?
+                }
+            };
+            self.edges.push((node, dep));
+        }
+
+        for src in &drv.input_srcs {
+            let dep = match self.index.get(src) {
+                Some(&index) => index,
+                None => self.add_node(src.clone(), NodeKind::Source, src.name().to_string()),
+            };
+            if !self.edges.contains(&(node, dep)) {
+                self.edges.push((node, dep));
+            }
+        }
+
+        visits.insert(path, Visit::Done);
+        Ok(node)
+    }
+
+    /// Add a node and return its index.
+    /// 添加节点并返回其索引。
+    fn add_node(&mut self, path: StorePath, kind: NodeKind, label: String) -> usize {
+        let index = self.nodes.len();
+        self.index.insert(path.clone(), index);
+        self.nodes.push(GraphNode { path, kind, label });
+        index
+    }
+
+    /// Get the root derivation node.
+    /// 获取根推导节点。
+    pub fn root(&self) -> Option<&GraphNode> {
+        self.nodes.first()
+    }
+
+    /// Get all nodes.
+    /// 获取所有节点。
+    pub fn nodes(&self) -> &[GraphNode] {
+        &self.nodes
+    }
+
+    /// Get all edges as (dependent, dependency) node indices.
+    /// 获取以（依赖方，被依赖方）节点索引表示的所有边。
+    pub fn edges(&self) -> &[(usize, usize)] {
+        &self.edges
+    }
+
+    /// Find the node for a store path.
+    /// 查找存储路径对应的节点。
+    pub fn node(&self, path: &StorePath) -> Option<&GraphNode> {
+        self.index.get(path).map(|&i| &self.nodes[i])
+    }
+
+    /// Get the direct dependencies of a node.
+    /// 获取节点的直接依赖。
+    pub fn dependencies(&self, path: &StorePath) -> Vec<&GraphNode> {
+        let Some(&from) = self.index.get(path) else {
+            return Vec::new();
+        };
+        self.edges
+            .iter()
+            .filter(|(dependent, _)| *dependent == from)
+            .map(|&(_, dep)| &self.nodes[dep])
+            .collect()
+    }
+
+    /// Render the graph in Graphviz DOT format.
+    /// 以 Graphviz DOT 格式渲染图。
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph build {\n");
+        out.push_str("    rankdir=LR;\n");
+
+        for node in &self.nodes {
+            let shape = match node.kind {
+                NodeKind::Derivation => "box",
+                NodeKind::Source => "ellipse",
+            };
+            let _ = writeln!(
+                out,
+                "    \"{}\" [label=\"{}\", shape={}];",
+                escape_dot(&node.path.display_name()),
+                escape_dot(&node.label),
+                shape
+            );
+        }
+
+        for &(from, to) in &self.edges {
+            let _ = writeln!(
+                out,
+                "    \"{}\" -> \"{}\";",
+                escape_dot(&self.nodes[from].path.display_name()),
+                escape_dot(&self.nodes[to].path.display_name())
+            );
+        }
+
+        out.push_str("}\n");
+        out
+    }
+}
+
+/// Label a derivation node as `name-version`.
+/// 将推导节点标记为 `name-version`。
+fn drv_label(drv: &Derivation) -> String {
+    if drv.version.is_empty() {
+        drv.name.clone()
+    } else {
+        format!("{}-{}", drv.name, drv.version)
+    }
+}
+
+/// Escape a string for use inside a quoted DOT identifier.
+/// 转义字符串以用于带引号的 DOT 标识符。
+fn escape_dot(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -8,16 +8,24 @@
 //! - Build execution / 构建执行
 //! - Output collection and registration / 输出收集和注册
 //! - Docker-based builds for cross-platform support / 基于 Docker 的跨平台构建支持
+//! - Dependency graphs for visualization / 用于可视化的依赖图
+//! - Substitution from binary caches / 从二进制缓存替代
+//! - Dry-run realization plans / 试运行实现计划
 
 pub mod analytics;
+pub mod cgroup;
 pub mod docker;
 pub mod executor;
+pub mod graph;
 pub mod output;
+pub mod plan;
 pub mod sandbox;
+mod schedule;
+pub mod substitute;
 
 use neve_derive::{Derivation, StorePath};
 use neve_store::Store;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
 use thiserror::Error;
 
@@ -32,7 +40,10 @@ pub enum BuildError {
     Store(#[from] neve_store::StoreError),
 
     #[error("fetch error: {0}")]
-    Fetch(#[from] neve_fetch::FetchError),
+    Fetch(neve_fetch::FetchError),
+
+    #[error("build cancelled")]
+    Cancelled,
 
     #[error("sandbox error: {0}")]
     Sandbox(String),
@@ -43,6 +54,15 @@ pub enum BuildError {
     #[error("missing input: {0}")]
     MissingInput(String),
 
+    #[error("dependency cycle through {0}")]
+    DependencyCycle(String),
+
+    #[error("dependency failed to build: {0}")]
+    DependencyFailed(String),
+
+    #[error("resolution error: {0}")]
+    Resolve(#[from] neve_derive::resolve::ResolveError),
+
     #[error("output hash mismatch for {output}: expected {expected}, got {actual}")]
     OutputHashMismatch {
         output: String,
@@ -51,6 +71,15 @@ pub enum BuildError {
     },
 }
 
+impl From<neve_fetch::FetchError> for BuildError {
+    fn from(e: neve_fetch::FetchError) -> Self {
+        match e {
+            neve_fetch::FetchError::Cancelled => BuildError::Cancelled,
+            e => BuildError::Fetch(e),
+        }
+    }
+}
+
 /// Build result.
 /// 构建结果。
 #[derive(Debug, Clone)]
@@ -63,6 +92,29 @@ pub struct BuildResult {
     pub log: String,
     /// Build duration in seconds. / 构建耗时（秒）。
     pub duration_secs: f64,
+    /// Whether the outputs were downloaded from a substituter instead of
+    /// built locally. / 输出是否从替代源下载而非本地构建。
+    pub substituted: bool,
+}
+
+/// Outcome of building several derivations.
+/// 构建多个派生的结果。
+#[derive(Debug, Default)]
+pub struct BuildSummary {
+    /// Successful builds, in target order. / 成功的构建，按目标顺序排列。
+    pub built: Vec<BuildResult>,
+    /// Failed and skipped targets, in target order. / 失败和跳过的目标，按目标顺序排列。
+    pub failed: Vec<BuildError>,
+    /// Targets never attempted after a failure. / 失败后未尝试的目标数。
+    pub not_attempted: usize,
+}
+
+impl BuildSummary {
+    /// Whether every target was built.
+    /// 是否所有目标都已构建。
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty() && self.not_attempted == 0
+    }
 }
 
 /// Build backend type.
@@ -99,6 +151,36 @@ pub struct BuilderConfig {
     pub timeout: u64,
     /// Build backend to use. / 使用的构建后端。
     pub backend: BuildBackend,
+    /// Keep building independent targets after a failure. / 失败后继续构建独立的目标。
+    pub keep_going: bool,
+    /// Substituter URLs to try before building, in order. / 构建前按顺序尝试的替代源 URL。
+    pub substituters: Vec<String>,
+    /// Forbid network access: a substituter lookup fails the build instead
+    /// of downloading.
+    /// 禁止网络访问：查询替代源会使构建失败而不是下载。
+    pub offline: bool,
+    /// Variables passed through from the parent environment into builds.
+    /// Every other variable is scrubbed; allowlisting an impure one such as
+    /// `TZ` or a proxy makes builds depend on the machine, so they are no
+    /// longer reproducible.
+    /// 从父环境传入构建的变量。其他变量都会被清除；将 `TZ` 或代理等不纯变量加入允许列表
+    /// 会使构建依赖于所在机器，从而不再可复现。
+    pub env_allowlist: Vec<String>,
+    /// Variables set in every build, overriding the derivation's own.
+    /// 在每个构建中设置的变量，覆盖推导自身的变量。
+    pub env_extra: Vec<(String, String)>,
+    /// Memory limit of each native build in bytes, enforced through cgroup v2
+    /// when available. A build exceeding it fails.
+    /// 每个原生构建的内存限制（字节），在 cgroup v2 可用时强制执行。超出限制的构建会失败。
+    pub memory_limit_bytes: Option<u64>,
+    /// CPU quota of each native build in CPUs (e.g. `1.5`), enforced through
+    /// cgroup v2 when available.
+    /// 每个原生构建的 CPU 配额（以 CPU 个数计，例如 `1.5`），在 cgroup v2 可用时强制执行。
+    pub cpu_quota: Option<f64>,
+    /// Token that stops building, and any download a build started, once
+    /// cancelled (e.g. on Ctrl-C).
+    /// 取消后停止构建及构建发起的任何下载的令牌（例如在按下 Ctrl-C 时）。
+    pub cancel: neve_fetch::CancelToken,
 }
 
 impl Default for BuilderConfig {
@@ -120,6 +202,14 @@ impl Default for BuilderConfig {
             keep_failed: false,
             timeout: 0,
             backend,
+            keep_going: false,
+            substituters: Vec::new(),
+            offline: false,
+            env_allowlist: Vec::new(),
+            env_extra: Vec::new(),
+            memory_limit_bytes: None,
+            cpu_quota: None,
+            cancel: neve_fetch::CancelToken::new(),
         }
     }
 }
@@ -145,10 +235,7 @@ impl Builder {
     /// Create a new builder.
     /// 创建新的构建器。
     pub fn new(store: Store) -> Self {
-        Self {
-            store,
-            config: BuilderConfig::default(),
-        }
+        Self::with_config(store, BuilderConfig::default())
     }
 
     /// Create a new builder with configuration.
@@ -173,8 +260,34 @@ impl Builder {
 
     /// Build a derivation.
     /// 构建一个派生。
+    ///
+    /// Input derivations are built first, one at a time; use
+    /// [`build_all`](Self::build_all) to build independent inputs in parallel.
+    /// The configured substituters are asked for the outputs first; inputs
+    /// are only realized when no substituter has them.
+    /// 先逐个构建输入派生；使用 [`build_all`](Self::build_all) 可并行构建相互独立的输入。
+    /// 会先向配置的替代源请求输出；只有没有替代源拥有它们时才实现输入。
     pub fn build(&mut self, drv: &Derivation) -> Result<BuildResult, BuildError> {
+        self.build_with_log(drv, &mut |_| {})
+    }
+
+    /// Build a derivation like [`build`](Self::build), passing each line of
+    /// builder output to `on_line` as it is written, including the output of
+    /// input derivations built along the way.
+    /// 像 [`build`](Self::build) 一样构建派生，并在构建器输出的每一行写出时将其传给
+    /// `on_line`，包括途中构建的输入派生的输出。
+    ///
+    /// [`BuildResult::log`] still holds the complete log of `drv` itself.
+    /// [`BuildResult::log`] 仍包含 `drv` 自身的完整日志。
+    pub fn build_with_log(
+        &mut self,
+        drv: &Derivation,
+        on_line: &mut dyn FnMut(&str),
+    ) -> Result<BuildResult, BuildError> {
         let start = std::time::Instant::now();
+        if self.config.cancel.is_cancelled() {
+            return Err(BuildError::Cancelled);
+        }
 
         // Check if already built
         let drv_path = drv.drv_path();
@@ -184,14 +297,28 @@ impl Builder {
                 outputs,
                 log: String::new(),
                 duration_secs: 0.0,
+                substituted: false,
+            });
+        }
+
+        // Try the substituters, before any input is realized
+        if let Some(outputs) =
+            substitute::Substituter::new(&self.store, &self.config).substitute(drv)?
+        {
+            return Ok(BuildResult {
+                derivation: drv_path,
+                outputs,
+                log: String::new(),
+                duration_secs: start.elapsed().as_secs_f64(),
+                substituted: true,
             });
         }
 
         // Ensure all inputs are available
-        self.ensure_inputs(drv)?;
+        let inputs = self.ensure_inputs(drv, on_line)?;
 
         // Execute the build
-        let (outputs, log) = self.execute_build(drv)?;
+        let (outputs, log) = self.execute_build(drv, &inputs, on_line)?;
 
         let duration = start.elapsed().as_secs_f64();
 
@@ -200,11 +327,15 @@ impl Builder {
             outputs,
             log,
             duration_secs: duration,
+            substituted: false,
         })
     }
 
     /// Check if all outputs already exist.
-    fn check_outputs_exist(&self, drv: &Derivation) -> Option<HashMap<String, StorePath>> {
+    pub(crate) fn check_outputs_exist(
+        &self,
+        drv: &Derivation,
+    ) -> Option<HashMap<String, StorePath>> {
         let mut outputs = HashMap::new();
 
         for (name, output) in &drv.outputs {
@@ -223,8 +354,15 @@ impl Builder {
         Some(outputs)
     }
 
-    /// Ensure all inputs are available.
-    fn ensure_inputs(&mut self, drv: &Derivation) -> Result<(), BuildError> {
+    /// Ensure all inputs are available, returning the realized outputs of
+    /// input derivations.
+    fn ensure_inputs(
+        &mut self,
+        drv: &Derivation,
+        on_line: &mut dyn FnMut(&str),
+    ) -> Result<BTreeMap<StorePath, executor::RealizedInput>, BuildError> {
+        let mut inputs = BTreeMap::new();
+
         // Check input derivations
         for input_drv_path in drv.input_drvs.keys() {
             if !self.store.path_exists(input_drv_path) {
@@ -233,9 +371,17 @@ impl Builder {
 
             // Read and build the input derivation if its outputs don't exist
             let input_drv = self.store.read_derivation(input_drv_path)?;
-            if self.check_outputs_exist(&input_drv).is_none() {
-                self.build(&input_drv)?;
-            }
+            let outputs = match self.check_outputs_exist(&input_drv) {
+                Some(outputs) => outputs,
+                None => self.build_with_log(&input_drv, on_line)?.outputs,
+            };
+            inputs.insert(
+                input_drv_path.clone(),
+                executor::RealizedInput {
+                    name: input_drv.name.clone(),
+                    outputs,
+                },
+            );
         }
 
         // Check input sources
@@ -245,17 +391,19 @@ impl Builder {
             }
         }
 
-        Ok(())
+        Ok(inputs)
     }
 
     /// Execute the build.
     fn execute_build(
         &mut self,
         drv: &Derivation,
+        inputs: &BTreeMap<StorePath, executor::RealizedInput>,
+        on_line: &mut dyn FnMut(&str),
     ) -> Result<(HashMap<String, StorePath>, String), BuildError> {
         use executor::BuildExecutor;
 
         let executor = BuildExecutor::new(&self.store, &self.config);
-        executor.execute(drv)
+        executor.execute_with_log(drv, inputs, on_line)
     }
 }
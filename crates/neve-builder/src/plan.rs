@@ -0,0 +1,273 @@
+//! Realization plans.
+//! 实现计划。
+//!
+//! A plan says what realizing a derivation would do without doing it: which
+//! derivations in its closure are already in the store, which can be fetched
+//! from a binary cache, and which have to be built.
+//! 计划描述实现一个推导将要做什么而不实际执行：其闭包中哪些推导已在存储中，
+//! 哪些可以从二进制缓存获取，哪些需要构建。
+
+use crate::{BuildError, Builder};
+use neve_derive::resolve::{
+    Dependency, MemoryRegistry, PackageId, PackageLock, PackageMetadata, PackageRegistry, Resolver,
+    Version, VersionConstraint,
+};
+use neve_derive::{Derivation, StorePath};
+use std::collections::HashSet;
+
+/// What realizing a derivation requires.
+/// 实现推导所需的操作。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlanAction {
+    /// All outputs are already in the store. / 所有输出已在存储中。
+    Cached,
+    /// All outputs can be fetched from a binary cache. / 所有输出可从二进制缓存获取。
+    Fetch,
+    /// The derivation has to be built. / 推导需要构建。
+    Build,
+}
+
+impl std::fmt::Display for PlanAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlanAction::Cached => write!(f, "cached"),
+            PlanAction::Fetch => write!(f, "fetch"),
+            PlanAction::Build => write!(f, "build"),
+        }
+    }
+}
+
+/// One derivation in a plan.
+/// 计划中的一个推导。
+#[derive(Debug, Clone)]
+pub struct PlanStep {
+    /// Store path of the derivation. / 推导的存储路径。
+    pub drv_path: StorePath,
+    /// Human-readable `name-version` label. / 可读的 `name-version` 标签。
+    pub label: String,
+    /// What has to happen. / 需要执行的操作。
+    pub action: PlanAction,
+}
+
+/// Plan for realizing a derivation, dependencies first.
+/// 实现推导的计划，依赖在前。
+///
+/// Inputs of cached or fetchable derivations are not visited, since they are
+/// not needed to realize them.
+/// 不访问已缓存或可获取推导的输入，因为实现它们不需要这些输入。
+#[derive(Debug, Clone, Default)]
+pub struct BuildPlan {
+    steps: Vec<PlanStep>,
+}
+
+impl BuildPlan {
+    /// Get all steps in realization order.
+    /// 按实现顺序获取所有步骤。
+    pub fn steps(&self) -> &[PlanStep] {
+        &self.steps
+    }
+
+    /// Get the steps with the given action.
+    /// 获取具有给定操作的步骤。
+    pub fn with_action(&self, action: PlanAction) -> impl Iterator<Item = &PlanStep> {
+        self.steps.iter().filter(move |s| s.action == action)
+    }
+
+    /// Whether realizing the derivation needs no fetching or building.
+    /// 实现推导是否无需获取或构建。
+    pub fn is_noop(&self) -> bool {
+        self.steps.iter().all(|s| s.action == PlanAction::Cached)
+    }
+}
+
+/// Plan for installing a package into a profile.
+/// 将软件包安装到配置文件的计划。
+#[derive(Debug, Clone)]
+pub struct InstallPlan {
+    /// Resolved packages in build order. / 按构建顺序解析的软件包。
+    pub packages: Vec<PackageId>,
+    /// Store path of the package derivation. / 软件包推导的存储路径。
+    pub drv_path: StorePath,
+    /// Realization plan of the package derivation. / 软件包推导的实现计划。
+    pub build: BuildPlan,
+    /// Output paths that would be added to the profile. / 将添加到配置文件的输出路径。
+    pub profile_add: Vec<StorePath>,
+    /// The resolved versions, to lock later installs to. / 解析出的版本，用于锁定之后的安装。
+    pub lock: PackageLock,
+}
+
+impl Builder {
+    /// Plan the realization of a derivation without a binary cache.
+    /// 在没有二进制缓存的情况下规划推导的实现。
+    pub fn plan(&mut self, drv: &Derivation) -> Result<BuildPlan, BuildError> {
+        self.plan_with(drv, |_| false)
+    }
+
+    /// Plan the realization of a derivation.
+    /// 规划推导的实现。
+    ///
+    /// `substitutable` tells whether an output path can be fetched from a
+    /// binary cache. Nothing is built, fetched or written to the store.
+    /// `substitutable` 指示输出路径是否可从二进制缓存获取。不会构建、获取或写入存储。
+    pub fn plan_with(
+        &mut self,
+        drv: &Derivation,
+        substitutable: impl Fn(&StorePath) -> bool,
+    ) -> Result<BuildPlan, BuildError> {
+        let mut plan = BuildPlan::default();
+        let mut seen = HashSet::new();
+        self.plan_visit(drv, &substitutable, &mut seen, &mut plan)?;
+        Ok(plan)
+    }
+
+    /// Add a derivation and, if it has to be built, its inputs to the plan.
+    /// 将推导添加到计划中；若需要构建，则同时添加其输入。
+    fn plan_visit(
+        &mut self,
+        drv: &Derivation,
+        substitutable: &dyn Fn(&StorePath) -> bool,
+        seen: &mut HashSet<StorePath>,
+        plan: &mut BuildPlan,
+    ) -> Result<(), BuildError> {
+        let drv_path = drv.drv_path();
+        if !seen.insert(drv_path.clone()) {
+            return Ok(());
+        }
+
+        let action = if self.check_outputs_exist(drv).is_some() {
+            PlanAction::Cached
+        } else if !drv.outputs.is_empty()
+            && drv
+                .outputs
+                .values()
+                .all(|o| o.path.as_ref().is_some_and(substitutable))
+        {
+            PlanAction::Fetch
+        } else {
+            PlanAction::Build
+        };
+
+        if action == PlanAction::Build {
+            for input_path in drv.input_drvs.keys() {
+                if !self.store.path_exists(input_path) {
+                    return Err(BuildError::MissingInput(input_path.display_name()));
+                }
+                let input = self.store.read_derivation(input_path)?;
+                self.plan_visit(&input, substitutable, seen, plan)?;
+            }
+        }
+
+        plan.steps.push(PlanStep {
+            drv_path,
+            label: format!("{}-{}", drv.name, drv.version),
+            action,
+        });
+        Ok(())
+    }
+}
+
+/// Resolve a package from a registry and plan its installation.
+/// 从注册表解析软件包并规划其安装。
+///
+/// The package's derivation must already be in the builder's store. With a
+/// `lock`, the package resolves only to the versions it pins; see
+/// [`Resolver::locked`].
+/// 软件包的推导必须已在构建器的存储中。提供 `lock` 时，软件包只会解析到其固定的版本；
+/// 参见 [`Resolver::locked`]。
+pub fn plan_install<R: PackageRegistry>(
+    builder: &mut Builder,
+    registry: &R,
+    package: &str,
+    lock: Option<&PackageLock>,
+) -> Result<InstallPlan, BuildError> {
+    let mut resolver = Resolver::new(registry);
+    if let Some(lock) = lock {
+        resolver = resolver.locked(lock);
+    }
+    let resolution = resolver.resolve(&[Dependency::new(package, VersionConstraint::Any)])?;
+
+    let id = &resolution.packages[package];
+    let drv_path = registry
+        .get_metadata(&id.name, &id.version)
+        .and_then(|m| m.derivation_path)
+        .ok_or_else(|| BuildError::MissingInput(format!("{} has no derivation", package)))?;
+    if !builder.store().path_exists(&drv_path) {
+        return Err(BuildError::MissingInput(drv_path.display_name()));
+    }
+
+    let drv = builder.store_mut().read_derivation(&drv_path)?;
+    let build = builder.plan(&drv)?;
+    let profile_add = drv
+        .outputs
+        .values()
+        .filter_map(|o| o.path.clone())
+        .collect();
+
+    Ok(InstallPlan {
+        lock: PackageLock::from_resolution(&resolution),
+        packages: resolution.build_order,
+        drv_path,
+        build,
+        profile_add,
+    })
+}
+
+/// Build a registry of `package` and its dependencies from the derivations
+/// in the builder's store.
+/// 由构建器存储中的推导构建 `package` 及其依赖的注册表。
+///
+/// Every `<package>-<version>.drv` is a version of the package; its input
+/// derivations are its dependencies, pinned to their exact versions and
+/// registered in turn. Derivations whose version is not a valid version
+/// number are left out, and no other derivation is read.
+/// 每个 `<package>-<version>.drv` 都是该软件包的一个版本；其输入推导是它的依赖，
+/// 固定为确切版本并依次注册。版本不是有效版本号的推导会被忽略，其他推导不会被读取。
+pub fn store_registry(builder: &mut Builder, package: &str) -> Result<MemoryRegistry, BuildError> {
+    let prefix = format!("{}-", package);
+    let mut pending: Vec<StorePath> = builder
+        .store()
+        .list_paths()?
+        .into_iter()
+        .filter(|p| {
+            p.name()
+                .strip_suffix(".drv")
+                .and_then(|n| n.strip_prefix(&prefix))
+                .is_some_and(|v| Version::parse(v).is_ok())
+        })
+        .collect();
+
+    let mut registry = MemoryRegistry::new();
+    let mut seen = HashSet::new();
+    while let Some(drv_path) = pending.pop() {
+        if !seen.insert(drv_path.clone()) {
+            continue;
+        }
+        let drv = builder.store_mut().read_derivation(&drv_path)?;
+        let Ok(version) = Version::parse(&drv.version) else {
+            continue;
+        };
+
+        let mut dependencies = Vec::new();
+        for input_path in drv.input_drvs.keys() {
+            if !builder.store().path_exists(input_path) {
+                continue;
+            }
+            let input = builder.store_mut().read_derivation(input_path)?;
+            if let Ok(input_version) = Version::parse(&input.version) {
+                dependencies.push(Dependency::new(
+                    input.name.clone(),
+                    VersionConstraint::Exact(input_version),
+                ));
+                pending.push(input_path.clone());
+            }
+        }
+
+        registry.add(PackageMetadata {
+            id: PackageId::new(drv.name.clone(), version),
+            dependencies,
+            derivation_path: Some(drv.drv_path()),
+        });
+    }
+
+    Ok(registry)
+}
@@ -35,8 +35,13 @@
 //! 在其他平台上，构建在没有完全隔离的情况下运行。
 
 use crate::BuildError;
+use crate::cgroup::{self, BuildCgroup};
+use neve_fetch::CancelToken;
 use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, BufReader, Read};
 use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
 
 /// Resource limits for builds.
 /// 构建的资源限制。
@@ -52,6 +57,15 @@ pub struct ResourceLimits {
     pub max_fds: u32,
     /// Maximum file size in bytes. / 最大文件大小（字节）。
     pub max_file_size: u64,
+    /// Memory limit of the whole build in bytes, enforced through cgroup v2.
+    /// 整个构建的内存限制（字节），通过 cgroup v2 强制执行。
+    pub memory_max: Option<u64>,
+    /// CPU quota of the whole build in CPUs, enforced through cgroup v2.
+    /// 整个构建的 CPU 配额（以 CPU 个数计），通过 cgroup v2 强制执行。
+    pub cpu_quota: Option<f64>,
+    /// Wall-clock time in seconds before the build is killed (0 = no timeout).
+    /// 构建被终止前的墙钟时间（秒，0 = 无超时）。
+    pub timeout: u64,
 }
 
 impl Default for ResourceLimits {
@@ -62,6 +76,9 @@ impl Default for ResourceLimits {
             max_processes: 1024, // Reasonable default / 合理的默认值
             max_fds: 1024,       // Reasonable default / 合理的默认值
             max_file_size: 0,    // Unlimited / 无限制
+            memory_max: None,
+            cpu_quota: None,
+            timeout: 0,
         }
     }
 }
@@ -366,8 +383,10 @@ pub struct SandboxConfig {
     pub rw_paths: Vec<PathBuf>,
     /// Allowed network access. / 是否允许网络访问。
     pub network: bool,
-    /// Environment variables. / 环境变量。
+    /// Environment variables, overriding those of the build. / 环境变量，覆盖构建自身的变量。
     pub env: HashMap<String, String>,
+    /// Variables passed through from the parent environment. / 从父环境传入的变量。
+    pub env_allowlist: Vec<String>,
     /// Resource limits. / 资源限制。
     pub limits: ResourceLimits,
     /// Security profile. / 安全配置文件。
@@ -379,6 +398,14 @@ pub struct SandboxConfig {
     /// Whether this is a fixed-output derivation (can have network).
     /// 是否为固定输出推导（可以有网络）。
     pub fixed_output: bool,
+    /// Token that kills the running build once cancelled.
+    /// 取消后终止运行中构建的令牌。
+    pub cancel: CancelToken,
+    /// Whether the builder runs in a container that mounts the store, build
+    /// and output directories at the same paths as namespace isolation.
+    /// 构建器是否在容器中运行，该容器将存储、构建和输出目录挂载到与命名空间隔离
+    /// 相同的路径。
+    pub container: bool,
 }
 
 impl SandboxConfig {
@@ -396,10 +423,13 @@ impl SandboxConfig {
             rw_paths: Vec::new(),
             network: false,
             env: HashMap::new(),
+            env_allowlist: Vec::new(),
             limits: ResourceLimits::default(),
             security: SecurityProfile::default(),
             log_file: None,
             fixed_output: false,
+            cancel: CancelToken::new(),
+            container: false,
         }
     }
 
@@ -441,6 +471,17 @@ impl SandboxConfig {
         self
     }
 
+    /// Pass a variable through from the parent environment.
+    /// 从父环境传入一个变量。
+    ///
+    /// The build then depends on the environment it was started from, so it
+    /// is no longer reproducible.
+    /// 构建因此依赖于启动它的环境，不再可复现。
+    pub fn with_allowed_env(mut self, name: impl Into<String>) -> Self {
+        self.env_allowlist.push(name.into());
+        self
+    }
+
     /// Set resource limits.
     /// 设置资源限制。
     pub fn with_limits(mut self, limits: ResourceLimits) -> Self {
@@ -553,6 +594,36 @@ impl Sandbox {
         &self.config.output_dir
     }
 
+    /// Get the path under which a host path is visible to the builder.
+    /// 获取主机路径在构建器中可见的路径。
+    ///
+    /// With namespace isolation or in a container the store, build and output
+    /// directories are mounted at `/neve/store`, `/build` and `/output`;
+    /// otherwise the builder sees host paths unchanged.
+    /// 使用命名空间隔离或在容器中时，存储、构建和输出目录分别挂载在 `/neve/store`、
+    /// `/build` 和 `/output`；否则构建器看到的是未改变的主机路径。
+    pub fn inner_path(&self, host_path: &Path) -> PathBuf {
+        if !self.config.container && !namespace_available() {
+            return host_path.to_path_buf();
+        }
+
+        let mounts = [
+            (&self.config.store_dir, "/neve/store"),
+            (&self.config.build_dir, "/build"),
+            (&self.config.output_dir, "/output"),
+        ];
+        for (host, inner) in mounts {
+            if let Ok(rest) = host_path.strip_prefix(host) {
+                return if rest.as_os_str().is_empty() {
+                    PathBuf::from(inner)
+                } else {
+                    Path::new(inner).join(rest)
+                };
+            }
+        }
+        host_path.to_path_buf()
+    }
+
     /// Check if the sandbox is currently active.
     /// 检查沙箱当前是否处于活动状态。
     pub fn is_active(&self) -> bool {
@@ -575,21 +646,73 @@ impl Sandbox {
         self.active = false;
     }
 
+    /// Create the cgroup enforcing the build's memory and CPU limits.
+    /// 创建强制执行构建内存和 CPU 限制的 cgroup。
+    ///
+    /// Without cgroup v2 the limits can't be enforced; the build then runs
+    /// without them after a warning.
+    /// 没有 cgroup v2 时无法强制执行限制；构建会在警告后不受限制地运行。
+    fn build_cgroup(&self) -> Option<BuildCgroup> {
+        let limits = &self.config.limits;
+        if limits.memory_max.is_none() && limits.cpu_quota.is_none() {
+            return None;
+        }
+        if !cgroup::cgroup_v2_available() {
+            eprintln!(
+                "warning: cgroup v2 is not available; memory and CPU limits are not enforced"
+            );
+            return None;
+        }
+        match BuildCgroup::create(limits.memory_max, limits.cpu_quota) {
+            Ok(cgroup) => Some(cgroup),
+            Err(e) => {
+                eprintln!("warning: {}; memory and CPU limits are not enforced", e);
+                None
+            }
+        }
+    }
+
+    /// Values of the allowlisted variables in the parent environment.
+    /// 父环境中允许列表内变量的值。
+    fn inherited_env(&self) -> Vec<(&str, std::ffi::OsString)> {
+        self.config
+            .env_allowlist
+            .iter()
+            .filter_map(|name| std::env::var_os(name).map(|value| (name.as_str(), value)))
+            .collect()
+    }
+
     /// Execute a command in the sandbox.
     /// 在沙箱中执行命令。
-    #[cfg(target_os = "linux")]
     pub fn execute(
         &self,
         program: &str,
         args: &[String],
         env: &HashMap<String, String>,
+    ) -> Result<std::process::Output, BuildError> {
+        self.execute_with_log(program, args, env, &mut |_| {})
+    }
+
+    /// Execute a command in the sandbox, passing each line it writes to
+    /// stdout or stderr to `on_line` as soon as it is written.
+    /// 在沙箱中执行命令，并将其写到 stdout 或 stderr 的每一行在写出后立即传给 `on_line`。
+    ///
+    /// The returned output still holds everything the command wrote.
+    /// 返回的输出仍包含命令写出的全部内容。
+    #[cfg(target_os = "linux")]
+    pub fn execute_with_log(
+        &self,
+        program: &str,
+        args: &[String],
+        env: &HashMap<String, String>,
+        on_line: &mut dyn FnMut(&str),
     ) -> Result<std::process::Output, BuildError> {
         // Check if we can use namespace isolation
         // 检查是否可以使用命名空间隔离
         if namespace_available() {
-            self.execute_with_namespaces(program, args, env)
+            self.execute_with_namespaces(program, args, env, on_line)
         } else {
-            self.execute_simple(program, args, env)
+            self.execute_simple(program, args, env, on_line)
         }
     }
 
@@ -601,13 +724,20 @@ impl Sandbox {
         program: &str,
         args: &[String],
         env: &HashMap<String, String>,
+        on_line: &mut dyn FnMut(&str),
     ) -> Result<std::process::Output, BuildError> {
         use nix::mount::{MntFlags, MsFlags, mount, umount2};
         use nix::sched::{CloneFlags, unshare};
-        use nix::sys::wait::waitpid;
-        use nix::unistd::{ForkResult, chdir, chroot, fork, sethostname};
+        use nix::sys::wait::{WaitPidFlag, WaitStatus, waitpid};
+        use nix::unistd::{ForkResult, Pid, chdir, chroot, dup2, fork, pipe, sethostname, setpgid};
+        use std::os::fd::AsRawFd;
         use std::os::unix::process::ExitStatusExt;
 
+        // Read the parent environment and set up the cgroup before forking
+        // 在 fork 之前读取父环境并设置 cgroup
+        let inherited = self.inherited_env();
+        let cgroup = self.build_cgroup();
+
         // Create a new root for the sandbox
         // 为沙箱创建新的根目录
         let newroot = self.config.root.join("rootfs");
@@ -644,25 +774,69 @@ impl Sandbox {
             clone_flags |= CloneFlags::CLONE_NEWNET;
         }
 
+        // Pipes carrying the builder's stdout and stderr back to the parent
+        // 将构建器的 stdout 和 stderr 传回父进程的管道
+        let pipe_err = |e| BuildError::Sandbox(format!("pipe failed: {}", e));
+        let (stdout_read, stdout_write) = pipe().map_err(pipe_err)?;
+        let (stderr_read, stderr_write) = pipe().map_err(pipe_err)?;
+
         // Fork a child process
         // fork 子进程
         match unsafe { fork() } {
             Ok(ForkResult::Parent { child }) => {
-                // Wait for the child
-                // 等待子进程
-                let status = waitpid(child, None)
-                    .map_err(|e| BuildError::Sandbox(format!("waitpid failed: {}", e)))?;
+                // Lead a process group of its own, so a timeout can kill the
+                // whole build; the child does the same to avoid a race
+                // 领导独立的进程组，以便超时时终止整个构建；子进程也会这样做以避免竞争
+                let _ = setpgid(child, child);
+
+                // Forward the output until the builder closes its end
+                // 转发输出，直到构建器关闭其一端
+                drop(stdout_write);
+                drop(stderr_write);
+                let timeout = self.config.limits.timeout;
+                let stop = Stop::new(timeout, &self.config.cancel);
+                let output = forward_lines(
+                    std::fs::File::from(stdout_read),
+                    std::fs::File::from(stderr_read),
+                    on_line,
+                    &stop,
+                );
+
+                // Wait for the child, killing it if it runs out of time or
+                // the build is cancelled
+                // 等待子进程，超时或构建被取消时将其终止
+                let status = match output {
+                    Some(_) => {
+                        wait_until(&stop, || match waitpid(child, Some(WaitPidFlag::WNOHANG)) {
+                            Ok(WaitStatus::StillAlive) => Ok(None),
+                            Ok(status) => Ok(Some(status)),
+                            Err(e) => Err(BuildError::Sandbox(format!("waitpid failed: {}", e))),
+                        })?
+                    }
+                    None => None,
+                };
+                if status.is_none() {
+                    kill_process_group(child.as_raw());
+                    let _ = waitpid(child, None);
+                }
 
                 // Clean up
                 // 清理
                 let _ = std::fs::remove_dir_all(&newroot);
 
-                use nix::sys::wait::WaitStatus;
+                let (Some((stdout, stderr)), Some(status)) = (output, status) else {
+                    return Err(stopped(timeout, &self.config.cancel));
+                };
+
+                if cgroup.as_ref().is_some_and(BuildCgroup::oom_killed) {
+                    return Err(BuildError::BuildFailed("exceeded memory limit".into()));
+                }
+
                 match status {
                     WaitStatus::Exited(_, code) => Ok(std::process::Output {
-                        status: std::process::ExitStatus::from_raw(code),
-                        stdout: Vec::new(),
-                        stderr: Vec::new(),
+                        status: std::process::ExitStatus::from_raw(code << 8),
+                        stdout,
+                        stderr,
                     }),
                     _ => Err(BuildError::Sandbox(
                         "child process did not exit normally".into(),
@@ -670,6 +844,29 @@ impl Sandbox {
                 }
             }
             Ok(ForkResult::Child) => {
+                let _ = setpgid(Pid::from_raw(0), Pid::from_raw(0));
+
+                // Send the builder's output through the pipes
+                // 通过管道发送构建器的输出
+                drop(stdout_read);
+                drop(stderr_read);
+                if dup2(stdout_write.as_raw_fd(), 1).is_err()
+                    || dup2(stderr_write.as_raw_fd(), 2).is_err()
+                {
+                    std::process::exit(1);
+                }
+                drop(stdout_write);
+                drop(stderr_write);
+
+                // Join the build cgroup while still in the parent's namespaces
+                // 在仍处于父命名空间时加入构建 cgroup
+                if let Some(cgroup) = &cgroup
+                    && let Err(e) = cgroup::join(cgroup.procs())
+                {
+                    eprintln!("Failed to join build cgroup: {}", e);
+                    std::process::exit(1);
+                }
+
                 // Enter new namespaces
                 // 进入新的命名空间
                 if let Err(e) = unshare(clone_flags) {
@@ -844,6 +1041,12 @@ impl Sandbox {
                 cmd.env("NIX_BUILD_TOP", "/build");
                 cmd.env("out", "/output");
 
+                // Allowlisted parent environment
+                // 允许列表中的父环境变量
+                for (key, value) in &inherited {
+                    cmd.env(key, value);
+                }
+
                 // User-specified environment
                 // 用户指定的环境变量
                 for (key, value) in env {
@@ -876,6 +1079,7 @@ impl Sandbox {
         program: &str,
         args: &[String],
         env: &HashMap<String, String>,
+        on_line: &mut dyn FnMut(&str),
     ) -> Result<std::process::Output, BuildError> {
         use std::process::Command;
 
@@ -892,6 +1096,12 @@ impl Sandbox {
         cmd.env("NIX_BUILD_TOP", &self.config.build_dir);
         cmd.env("out", &self.config.output_dir);
 
+        // Allowlisted parent environment
+        // 允许列表中的父环境变量
+        for (key, value) in self.inherited_env() {
+            cmd.env(key, value);
+        }
+
         // User environment
         // 用户环境变量
         for (key, value) in env {
@@ -901,18 +1111,37 @@ impl Sandbox {
             cmd.env(key, value);
         }
 
-        let output = cmd.output()?;
-        Ok(output)
+        // Join the build cgroup before the builder starts
+        // 在构建器启动前加入构建 cgroup
+        let cgroup = self.build_cgroup();
+        if let Some(cgroup) = &cgroup {
+            use std::os::unix::process::CommandExt;
+
+            let procs = cgroup.procs().to_owned();
+            // Only async-signal-safe calls are made after the fork
+            // fork 之后只进行异步信号安全的调用
+            unsafe {
+                cmd.pre_exec(move || cgroup::join(&procs));
+            }
+        }
+
+        let timeout = self.config.limits.timeout;
+        let output = run_with_log(&mut cmd, on_line, timeout, &self.config.cancel)?;
+        if cgroup.as_ref().is_some_and(BuildCgroup::oom_killed) {
+            return Err(BuildError::BuildFailed("exceeded memory limit".into()));
+        }
+        output.ok_or_else(|| stopped(timeout, &self.config.cancel))
     }
 
-    /// Execute a command in the sandbox (non-Linux).
-    /// 在沙箱中执行命令（非 Linux）。
+    /// Execute a command in the sandbox, passing each output line to `on_line` (non-Linux).
+    /// 在沙箱中执行命令，并将每一行输出传给 `on_line`（非 Linux）。
     #[cfg(not(target_os = "linux"))]
-    pub fn execute(
+    pub fn execute_with_log(
         &self,
         program: &str,
         args: &[String],
         env: &HashMap<String, String>,
+        on_line: &mut dyn FnMut(&str),
     ) -> Result<std::process::Output, BuildError> {
         use std::process::Command;
 
@@ -927,6 +1156,12 @@ impl Sandbox {
         cmd.env("TMPDIR", self.config.build_dir.join("tmp"));
         cmd.env("out", &self.config.output_dir);
 
+        // Allowlisted parent environment
+        // 允许列表中的父环境变量
+        for (key, value) in self.inherited_env() {
+            cmd.env(key, value);
+        }
+
         // User environment
         // 用户环境变量
         for (key, value) in env {
@@ -936,8 +1171,9 @@ impl Sandbox {
             cmd.env(key, value);
         }
 
-        let output = cmd.output()?;
-        Ok(output)
+        let timeout = self.config.limits.timeout;
+        run_with_log(&mut cmd, on_line, timeout, &self.config.cancel)?
+            .ok_or_else(|| stopped(timeout, &self.config.cancel))
     }
 
     /// Clean up the sandbox.
@@ -952,6 +1188,200 @@ impl Sandbox {
     }
 }
 
+/// How often a running build is checked for having exited.
+/// 检查运行中的构建是否已退出的间隔。
+const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// How often a build waiting for output checks whether it was cancelled.
+/// 等待输出的构建检查自身是否被取消的间隔。
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The error of a build that ran longer than `timeout` seconds.
+/// 运行超过 `timeout` 秒的构建的错误。
+pub(crate) fn timed_out(timeout: u64) -> BuildError {
+    BuildError::BuildFailed(format!("timed out after {} seconds", timeout))
+}
+
+/// The error of a build that was stopped early, either cancelled through
+/// `cancel` or out of time.
+/// 提前停止的构建的错误：通过 `cancel` 取消，或超时。
+pub(crate) fn stopped(timeout: u64, cancel: &CancelToken) -> BuildError {
+    if cancel.is_cancelled() {
+        BuildError::Cancelled
+    } else {
+        timed_out(timeout)
+    }
+}
+
+/// When a running build has to be stopped.
+/// 运行中的构建何时必须停止。
+struct Stop<'a> {
+    /// The instant the build must finish by, if it has a timeout.
+    /// 构建必须完成的时刻（如果有超时）。
+    deadline: Option<Instant>,
+    /// Token cancelling the build. / 取消构建的令牌。
+    cancel: &'a CancelToken,
+}
+
+impl<'a> Stop<'a> {
+    /// Stop after `timeout` seconds (0 = no timeout) or once `cancel` is cancelled.
+    /// 在 `timeout` 秒后（0 = 无超时）或 `cancel` 被取消后停止。
+    fn new(timeout: u64, cancel: &'a CancelToken) -> Self {
+        Self {
+            deadline: (timeout > 0).then(|| Instant::now() + Duration::from_secs(timeout)),
+            cancel,
+        }
+    }
+
+    /// Whether the build has to be stopped now.
+    /// 构建现在是否必须停止。
+    fn reached(&self) -> bool {
+        self.cancel.is_cancelled() || self.deadline.is_some_and(|d| Instant::now() >= d)
+    }
+
+    /// How long to wait for output before checking again.
+    /// 再次检查之前等待输出的时长。
+    fn poll_interval(&self) -> Duration {
+        match self.deadline {
+            Some(deadline) => deadline
+                .saturating_duration_since(Instant::now())
+                .min(CANCEL_POLL_INTERVAL),
+            None => CANCEL_POLL_INTERVAL,
+        }
+    }
+}
+
+/// Run `cmd` to completion, passing each line of its output to `on_line` as it arrives.
+/// 运行 `cmd` 直至完成，并在每一行输出到达时将其传给 `on_line`。
+///
+/// The command runs in its own process group. If it is still running after
+/// `timeout` seconds (0 = no timeout), or once `cancel` is cancelled, the
+/// whole group is killed and `None` is returned.
+/// 命令在独立的进程组中运行。如果 `timeout` 秒（0 = 无超时）后仍在运行，
+/// 或 `cancel` 被取消，则终止整个进程组并返回 `None`。
+pub(crate) fn run_with_log(
+    cmd: &mut std::process::Command,
+    on_line: &mut dyn FnMut(&str),
+    timeout: u64,
+    cancel: &CancelToken,
+) -> Result<Option<std::process::Output>, BuildError> {
+    use std::process::Stdio;
+
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(cmd, 0);
+
+    let stop = Stop::new(timeout, cancel);
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let stderr = child.stderr.take().expect("stderr is piped");
+    let output = forward_lines(stdout, stderr, on_line, &stop);
+    let status = match output {
+        Some(_) => wait_until(&stop, || Ok(child.try_wait()?))?,
+        None => None,
+    };
+
+    let (Some((stdout, stderr)), Some(status)) = (output, status) else {
+        #[cfg(unix)]
+        kill_process_group(child.id() as i32);
+        let _ = child.kill();
+        let _ = child.wait();
+        return Ok(None);
+    };
+    Ok(Some(std::process::Output {
+        status,
+        stdout,
+        stderr,
+    }))
+}
+
+/// Poll `try_wait` until it yields the exit status of a process. Returns
+/// `None` if the build has to be stopped first.
+/// 轮询 `try_wait`，直到其给出进程的退出状态。如果构建需要先停止则返回 `None`。
+fn wait_until<T>(
+    stop: &Stop,
+    mut try_wait: impl FnMut() -> Result<Option<T>, BuildError>,
+) -> Result<Option<T>, BuildError> {
+    loop {
+        if let Some(status) = try_wait()? {
+            return Ok(Some(status));
+        }
+        if stop.reached() {
+            return Ok(None);
+        }
+        std::thread::sleep(WAIT_POLL_INTERVAL);
+    }
+}
+
+/// Kill every process in the process group led by `pid`.
+/// 终止由 `pid` 领导的进程组中的所有进程。
+#[cfg(unix)]
+fn kill_process_group(pid: i32) {
+    unsafe {
+        libc::kill(-pid, libc::SIGKILL);
+    }
+}
+
+/// Pass each line read from `stdout` and `stderr` to `on_line` in the order
+/// they arrive, until both are closed. Returns everything read from each,
+/// or `None` if the build has to be stopped first.
+/// 按到达顺序将从 `stdout` 和 `stderr` 读取的每一行传给 `on_line`，直到两者都关闭。
+/// 返回从两者读取的全部内容；如果构建需要先停止则返回 `None`。
+fn forward_lines(
+    stdout: impl Read + Send + 'static,
+    stderr: impl Read + Send + 'static,
+    on_line: &mut dyn FnMut(&str),
+    stop: &Stop,
+) -> Option<(Vec<u8>, Vec<u8>)> {
+    /// Send each line of `reader` tagged with `is_stderr` until it closes.
+    /// 发送 `reader` 的每一行并标记 `is_stderr`，直到其关闭。
+    fn pump(reader: impl Read, is_stderr: bool, tx: mpsc::Sender<(bool, Vec<u8>)>) {
+        let mut reader = BufReader::new(reader);
+        loop {
+            let mut line = Vec::new();
+            match reader.read_until(b'\n', &mut line) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {
+                    if tx.send((is_stderr, line)).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    // The readers are not joined: after a stop they only finish once the
+    // killed build's pipes close
+    // 不等待读取线程结束：停止后它们只会在被终止的构建的管道关闭时结束
+    let (tx, rx) = mpsc::channel();
+    let stderr_tx = tx.clone();
+    std::thread::spawn(move || pump(stdout, false, tx));
+    std::thread::spawn(move || pump(stderr, true, stderr_tx));
+
+    let mut out = Vec::new();
+    let mut err = Vec::new();
+    loop {
+        let received = match rx.recv_timeout(stop.poll_interval()) {
+            Ok(received) => received,
+            Err(mpsc::RecvTimeoutError::Timeout) if stop.reached() => return None,
+            Err(mpsc::RecvTimeoutError::Timeout) => continue,
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        };
+        let (is_stderr, line) = received;
+        let text = String::from_utf8_lossy(&line);
+        on_line(text.trim_end_matches(['\n', '\r']));
+        if is_stderr {
+            err.extend_from_slice(&line);
+        } else {
+            out.extend_from_slice(&line);
+        }
+    }
+    Some((out, err))
+}
+
 /// Check if sandboxing with namespaces is available on this system.
 /// 检查此系统上是否支持使用命名空间的沙箱。
 pub fn sandbox_available() -> bool {
@@ -0,0 +1,361 @@
+//! Parallel building of several derivations.
+//! 多个派生的并行构建。
+//!
+//! The targets and every input derivation they still need are collected into
+//! one graph. The substituters are asked for each derivation while the graph
+//! is collected, so the inputs of a substituted derivation are never visited.
+//! Derivations whose inputs are all built are then handed to up to
+//! `max_jobs` worker threads at a time, while the scheduler itself stays on
+//! the calling thread.
+//! 目标及其仍需要的所有输入派生被收集到一张图中。收集图时会为每个派生询问替代源，
+//! 因此不会访问被替代派生的输入。随后输入均已构建的派生每次最多交给 `max_jobs` 个
+//! 工作线程，而调度器本身留在调用线程上。
+
+use crate::executor::{BuildExecutor, RealizedInput};
+use crate::substitute::Substituter;
+use crate::{BuildError, BuildResult, BuildSummary, Builder};
+use neve_derive::{Derivation, StorePath};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::sync::mpsc;
+use std::time::Instant;
+
+/// The combined build graph of a set of targets.
+/// 一组目标的合并构建图。
+#[derive(Default)]
+struct Graph {
+    /// Derivations to build, inputs before the derivations using them.
+    /// 要构建的派生，输入排在使用它们的派生之前。
+    order: Vec<StorePath>,
+    /// Derivations by path. / 按路径索引的派生。
+    drvs: HashMap<StorePath, Derivation>,
+    /// Number of inputs still to be built. / 仍需构建的输入数量。
+    pending: HashMap<StorePath, usize>,
+    /// Derivations waiting on each derivation. / 等待每个派生的派生。
+    dependents: HashMap<StorePath, Vec<StorePath>>,
+    /// Outcome of each finished derivation. / 每个已完成派生的结果。
+    outcomes: HashMap<StorePath, Result<BuildResult, BuildError>>,
+}
+
+impl Graph {
+    /// Whether a derivation has finished, successfully or not.
+    /// 派生是否已完成（无论成功与否）。
+    fn finished(&self, path: &StorePath) -> bool {
+        self.outcomes.contains_key(path)
+    }
+
+    /// Record a failure and fail everything waiting on it.
+    /// 记录失败，并使等待它的所有派生失败。
+    fn fail(&mut self, path: StorePath, error: BuildError) {
+        let mut queue = vec![(path, error)];
+        while let Some((path, error)) = queue.pop() {
+            let reason = format!("{}: {}", path.display_name(), error);
+            self.outcomes.insert(path.clone(), Err(error));
+            for dependent in self.dependents.get(&path).into_iter().flatten() {
+                if !self.finished(dependent) && !queue.iter().any(|(p, _)| p == dependent) {
+                    queue.push((
+                        dependent.clone(),
+                        BuildError::DependencyFailed(reason.clone()),
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Record a success, returning the derivations it made ready.
+    /// 记录成功，并返回因此就绪的派生。
+    fn succeed(&mut self, path: StorePath, result: BuildResult) -> Vec<StorePath> {
+        self.outcomes.insert(path.clone(), Ok(result));
+        let mut ready = Vec::new();
+        for dependent in self.dependents.get(&path).into_iter().flatten() {
+            if let Some(count) = self.pending.get_mut(dependent) {
+                *count -= 1;
+                if *count == 0 && !self.outcomes.contains_key(dependent) {
+                    ready.push(dependent.clone());
+                }
+            }
+        }
+        ready
+    }
+
+    /// Realized outputs of the input derivations of `drv`.
+    /// `drv` 的输入派生的已实现输出。
+    fn realized_inputs(&self, drv: &Derivation) -> BTreeMap<StorePath, RealizedInput> {
+        drv.input_drvs
+            .keys()
+            .filter_map(|path| match self.outcomes.get(path) {
+                Some(Ok(result)) => Some((
+                    path.clone(),
+                    RealizedInput {
+                        name: self.drvs[path].name.clone(),
+                        outputs: result.outputs.clone(),
+                    },
+                )),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+impl Builder {
+    /// Build several derivations.
+    /// 构建多个派生。
+    pub fn build_all(&mut self, drvs: &[Derivation]) -> BuildSummary {
+        self.build_all_with_progress(drvs, &mut |_, _| {})
+    }
+
+    /// Build several derivations, reporting each target's outcome as soon as
+    /// it is known.
+    /// 构建多个派生，并在每个目标的结果可知时立即报告。
+    ///
+    /// The targets and their unbuilt inputs are built in dependency order,
+    /// with up to `max_jobs` independent derivations running at once. A
+    /// failure stops new builds from starting, and the targets left over are
+    /// counted as not attempted. With `keep_going`, building continues; only
+    /// derivations that depend on a failed one are skipped, failing with
+    /// [`BuildError::DependencyFailed`].
+    /// 目标及其未构建的输入按依赖顺序构建，最多同时运行 `max_jobs` 个相互独立的派生。
+    /// 失败会阻止新的构建开始，剩余目标计为未尝试。启用 `keep_going` 时继续构建；
+    /// 仅跳过依赖已失败派生的派生，它们以 [`BuildError::DependencyFailed`] 失败。
+    pub fn build_all_with_progress(
+        &mut self,
+        drvs: &[Derivation],
+        on_result: &mut dyn FnMut(&Derivation, &Result<BuildResult, BuildError>),
+    ) -> BuildSummary {
+        let mut targets: Vec<(StorePath, &Derivation)> = Vec::new();
+        for drv in drvs {
+            let path = drv.drv_path();
+            if !targets.iter().any(|(p, _)| *p == path) {
+                targets.push((path, drv));
+            }
+        }
+
+        let mut graph = self.collect_graph(drvs);
+        let keep_going = self.config.keep_going;
+        let mut stopped = !keep_going && graph.outcomes.values().any(|r| r.is_err());
+
+        let mut ready: VecDeque<StorePath> = graph
+            .order
+            .iter()
+            .filter(|path| graph.pending[*path] == 0 && !graph.finished(path))
+            .cloned()
+            .collect();
+        let mut reported: HashSet<StorePath> = HashSet::new();
+        let mut report = |graph: &Graph| {
+            for (path, drv) in &targets {
+                if let Some(outcome) = graph.outcomes.get(path)
+                    && reported.insert(path.clone())
+                {
+                    on_result(drv, outcome);
+                }
+            }
+        };
+        report(&graph);
+
+        let store = &self.store;
+        let config = &self.config;
+        let max_jobs = config.max_jobs.max(1);
+        std::thread::scope(|scope| {
+            let (tx, rx) = mpsc::channel();
+            let mut running = 0;
+            loop {
+                // A cancelled build starts nothing new
+                // 被取消的构建不再开始新的派生
+                stopped |= config.cancel.is_cancelled();
+                while !stopped && running < max_jobs {
+                    let Some(path) = ready.pop_front() else {
+                        break;
+                    };
+                    let drv = graph.drvs[&path].clone();
+                    let inputs = graph.realized_inputs(&drv);
+                    let tx = tx.clone();
+                    scope.spawn(move || {
+                        let start = Instant::now();
+                        let result = BuildExecutor::new(store, config)
+                            .execute_with_inputs(&drv, &inputs)
+                            .map(|(outputs, log)| BuildResult {
+                                derivation: path.clone(),
+                                outputs,
+                                log,
+                                duration_secs: start.elapsed().as_secs_f64(),
+                                substituted: false,
+                            });
+                        let _ = tx.send((path, result));
+                    });
+                    running += 1;
+                }
+                if running == 0 {
+                    break;
+                }
+
+                let (path, result) = rx.recv().expect("a running build always reports back");
+                running -= 1;
+                match result {
+                    Ok(result) => ready.extend(graph.succeed(path, result)),
+                    Err(e) if keep_going => graph.fail(path, e),
+                    Err(e) => {
+                        graph.outcomes.insert(path, Err(e));
+                        stopped = true;
+                    }
+                }
+                report(&graph);
+            }
+        });
+
+        // Anything left unfinished without a stop is waiting on a cycle
+        // 未停止却仍未完成的派生在等待一个循环
+        if !stopped {
+            let stuck: Vec<StorePath> = graph
+                .order
+                .iter()
+                .filter(|path| !graph.finished(path))
+                .cloned()
+                .collect();
+            for path in stuck {
+                let name = path.display_name();
+                graph
+                    .outcomes
+                    .insert(path, Err(BuildError::DependencyCycle(name)));
+            }
+            report(&graph);
+        }
+
+        let mut summary = BuildSummary::default();
+        for (path, _) in &targets {
+            match graph.outcomes.remove(path) {
+                Some(Ok(result)) => summary.built.push(result),
+                Some(Err(e)) => summary.failed.push(e),
+                None => summary.not_attempted += 1,
+            }
+        }
+        summary
+    }
+
+    /// Collect the targets and the input derivations they still need.
+    /// 收集目标及其仍需要的输入派生。
+    ///
+    /// Derivations whose outputs already exist or can be substituted are
+    /// finished up front, as are those with a missing or unreadable input.
+    /// 输出已存在或可被替代的派生以及缺少或无法读取输入的派生会预先完成。
+    fn collect_graph(&mut self, targets: &[Derivation]) -> Graph {
+        let mut graph = Graph::default();
+        let mut visiting: HashSet<StorePath> = HashSet::new();
+        let mut stack: Vec<(Derivation, bool)> =
+            targets.iter().rev().map(|d| (d.clone(), false)).collect();
+
+        // Post-order walk, so inputs come before the derivations using them
+        // 后序遍历，使输入排在使用它们的派生之前
+        while let Some((drv, expanded)) = stack.pop() {
+            let path = drv.drv_path();
+            if graph.drvs.contains_key(&path) || graph.finished(&path) {
+                continue;
+            }
+            if expanded {
+                visiting.remove(&path);
+                graph.order.push(path.clone());
+                graph.drvs.insert(path, drv);
+                continue;
+            }
+            if !visiting.insert(path.clone()) {
+                continue;
+            }
+            if let Some(outputs) = self.check_outputs_exist(&drv) {
+                visiting.remove(&path);
+                let result = BuildResult {
+                    derivation: path.clone(),
+                    outputs,
+                    log: String::new(),
+                    duration_secs: 0.0,
+                    substituted: false,
+                };
+                graph.outcomes.insert(path, Ok(result));
+                continue;
+            }
+
+            // Substituted derivations need none of their inputs
+            // 被替代的派生不需要其任何输入
+            let start = Instant::now();
+            match Substituter::new(&self.store, &self.config).substitute(&drv) {
+                Ok(Some(outputs)) => {
+                    visiting.remove(&path);
+                    let result = BuildResult {
+                        derivation: path.clone(),
+                        outputs,
+                        log: String::new(),
+                        duration_secs: start.elapsed().as_secs_f64(),
+                        substituted: true,
+                    };
+                    graph.outcomes.insert(path, Ok(result));
+                    continue;
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    visiting.remove(&path);
+                    graph.outcomes.insert(path, Err(e.into()));
+                    continue;
+                }
+            }
+
+            match self.read_inputs(&drv) {
+                Ok(inputs) => {
+                    stack.push((drv, true));
+                    stack.extend(inputs.into_iter().rev().map(|d| (d, false)));
+                }
+                Err(e) => {
+                    visiting.remove(&path);
+                    graph.outcomes.insert(path, Err(e));
+                }
+            }
+        }
+
+        // Wire up the edges between unfinished derivations
+        // 连接未完成派生之间的边
+        let mut failed_inputs = Vec::new();
+        for path in &graph.order {
+            let mut pending = 0;
+            for input in graph.drvs[path].input_drvs.keys() {
+                match graph.outcomes.get(input) {
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => {
+                        let reason = format!("{}: {}", input.display_name(), e);
+                        failed_inputs.push((path.clone(), reason));
+                    }
+                    None => {
+                        pending += 1;
+                        graph
+                            .dependents
+                            .entry(input.clone())
+                            .or_default()
+                            .push(path.clone());
+                    }
+                }
+            }
+            graph.pending.insert(path.clone(), pending);
+        }
+        for (path, reason) in failed_inputs {
+            if !graph.finished(&path) {
+                graph.fail(path, BuildError::DependencyFailed(reason));
+            }
+        }
+
+        graph
+    }
+
+    /// Read the input derivations of `drv`, checking its input sources exist.
+    /// 读取 `drv` 的输入派生，并检查其输入源是否存在。
+    fn read_inputs(&mut self, drv: &Derivation) -> Result<Vec<Derivation>, BuildError> {
+        for input_src in &drv.input_srcs {
+            if !self.store.path_exists(input_src) {
+                return Err(BuildError::MissingInput(input_src.display_name()));
+            }
+        }
+        drv.input_drvs
+            .keys()
+            .map(|path| {
+                if !self.store.path_exists(path) {
+                    return Err(BuildError::MissingInput(path.display_name()));
+                }
+                Ok(self.store.read_derivation(path)?)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,257 @@
+//! Substitution of build outputs from binary caches.
+//! 从二进制缓存替代构建输出。
+//!
+//! Before building a derivation locally, the builder asks each configured
+//! substituter whether it already has the outputs. A substituter is a base
+//! URL serving one `.narinfo` file per derivation output, named
+//! `<derivation hash>-<output>.narinfo`:
+//! 在本地构建派生之前，构建器会询问每个配置的替代源是否已有其输出。替代源是一个
+//! 基础 URL，为每个派生输出提供一个 `.narinfo` 文件，命名为
+//! `<派生哈希>-<输出>.narinfo`：
+//!
+//! ```text
+//! StorePath: /neve/store/<hash>-hello-1.0
+//! URL: nar/<hash>.nar.xz
+//! Compression: xz
+//! NarHash: <hex hash of the uncompressed NAR>
+//! References: /neve/store/<hash>-glibc-2.38
+//! ```
+//!
+//! `URL` may be relative to the substituter. The NAR is checked against
+//! `NarHash` before it is unpacked into the store.
+//! `URL` 可以相对于替代源。NAR 在解包到存储之前会根据 `NarHash` 进行校验。
+
+use crate::BuilderConfig;
+use neve_derive::{Derivation, Hash, StorePath};
+use neve_fetch::{FetchError, Fetcher};
+use neve_store::{CacheError, CompressionFormat, Store, StoreError};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// A parsed `.narinfo` file.
+/// 解析后的 `.narinfo` 文件。
+#[derive(Debug, Clone)]
+pub struct NarInfo {
+    /// Store path of the output. / 输出的存储路径。
+    pub store_path: StorePath,
+    /// Location of the NAR, possibly relative. / NAR 的位置，可能是相对的。
+    pub url: String,
+    /// Compression of the NAR file. / NAR 文件的压缩格式。
+    pub compression: CompressionFormat,
+    /// Hash of the uncompressed NAR. / 未压缩 NAR 的哈希。
+    pub nar_hash: Hash,
+    /// Store paths the output refers to. / 输出引用的存储路径。
+    pub references: Vec<StorePath>,
+}
+
+impl NarInfo {
+    /// Parse the contents of a `.narinfo` file.
+    /// 解析 `.narinfo` 文件的内容。
+    pub fn parse(content: &str) -> Result<Self, CacheError> {
+        let mut store_path = None;
+        let mut url = None;
+        let mut compression = CompressionFormat::Xz;
+        let mut nar_hash = None;
+        let mut references = Vec::new();
+
+        for line in content.lines() {
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            match key.trim() {
+                "StorePath" => store_path = StorePath::parse(Path::new(value)),
+                "URL" => url = Some(value.to_string()),
+                "Compression" => {
+                    compression = CompressionFormat::from_name(value).ok_or_else(|| {
+                        CacheError::InvalidManifest(format!("unknown compression: {}", value))
+                    })?;
+                }
+                "NarHash" => {
+                    nar_hash = Some(Hash::from_hex(value).map_err(|e| {
+                        CacheError::InvalidManifest(format!("invalid NarHash: {}", e))
+                    })?);
+                }
+                "References" => {
+                    references = value
+                        .split_whitespace()
+                        .map(|s| {
+                            StorePath::parse(Path::new(s)).ok_or_else(|| {
+                                CacheError::InvalidManifest(format!("invalid reference: {}", s))
+                            })
+                        })
+                        .collect::<Result<_, _>>()?;
+                }
+                _ => {}
+            }
+        }
+
+        let missing = |field: &str| CacheError::InvalidManifest(format!("missing {}", field));
+        Ok(Self {
+            store_path: store_path.ok_or_else(|| missing("StorePath"))?,
+            url: url.ok_or_else(|| missing("URL"))?,
+            compression,
+            nar_hash: nar_hash.ok_or_else(|| missing("NarHash"))?,
+            references,
+        })
+    }
+}
+
+/// Name of the `.narinfo` file describing an output of a derivation,
+/// without the extension.
+/// 描述派生某个输出的 `.narinfo` 文件名（不含扩展名）。
+pub fn narinfo_name(drv: &Derivation, output: &str) -> String {
+    format!("{}-{}", drv.hash().digest_hex(), output)
+}
+
+/// Looks up and downloads derivation outputs from substituters.
+/// 从替代源查找并下载派生输出。
+pub struct Substituter<'a> {
+    /// Store reference. / 存储引用。
+    store: &'a Store,
+    /// Builder configuration. / 构建器配置。
+    config: &'a BuilderConfig,
+}
+
+impl<'a> Substituter<'a> {
+    /// Create a new substituter.
+    /// 创建新的替代器。
+    pub fn new(store: &'a Store, config: &'a BuilderConfig) -> Self {
+        Self { store, config }
+    }
+
+    /// Fetch all outputs of `drv` from the first substituter that has them.
+    /// 从第一个拥有 `drv` 全部输出的替代源获取这些输出。
+    ///
+    /// Returns `None` when no substituter can provide every output. Errors
+    /// are reported and treated like a miss, so the caller can build locally,
+    /// except that an offline builder fails with [`FetchError::Offline`]
+    /// rather than skip a substituter it may not contact, and a cancelled one
+    /// with [`FetchError::Cancelled`].
+    /// 没有替代源能提供所有输出时返回 `None`。错误会被报告并视为未命中，
+    /// 以便调用者在本地构建；但离线构建器会以 [`FetchError::Offline`] 失败，
+    /// 而不是跳过它不能联系的替代源；被取消的构建器以 [`FetchError::Cancelled`] 失败。
+    pub fn substitute(
+        &self,
+        drv: &Derivation,
+    ) -> Result<Option<HashMap<String, StorePath>>, FetchError> {
+        for url in &self.config.substituters {
+            match self.substitute_from(url, drv) {
+                Ok(Some(outputs)) => return Ok(Some(outputs)),
+                Ok(None) => {}
+                Err(SubstituteError::Offline(e)) => return Err(e),
+                Err(SubstituteError::Cache(e)) => eprintln!(
+                    "warning: substituter {} failed for {}: {}",
+                    url, drv.name, e
+                ),
+            }
+        }
+        Ok(None)
+    }
+
+    /// Fetch all outputs of `drv` from one substituter.
+    /// 从一个替代源获取 `drv` 的所有输出。
+    fn substitute_from(
+        &self,
+        base: &str,
+        drv: &Derivation,
+    ) -> Result<Option<HashMap<String, StorePath>>, SubstituteError> {
+        let base = base.trim_end_matches('/');
+        let download_dir = tempfile::tempdir_in(self.download_root()?)?;
+        let fetcher = Fetcher::new(download_dir.path().to_path_buf())
+            .map_err(fetch_error)?
+            .with_offline(self.config.offline)
+            .with_cancel(self.config.cancel.clone());
+
+        // Look up every output before downloading any of them
+        // 在下载之前先查找所有输出
+        let mut infos = Vec::new();
+        for name in drv.outputs.keys() {
+            let url = format!("{}/{}.narinfo", base, narinfo_name(drv, name));
+            let content = match fetcher.fetch_text(&url) {
+                Ok(content) => content,
+                Err(FetchError::Http(e)) if e.status().is_some_and(|s| s.as_u16() == 404) => {
+                    return Ok(None);
+                }
+                Err(e) => return Err(fetch_error(e)),
+            };
+            let info = NarInfo::parse(&content)?;
+
+            // The cache must serve the path the derivation expects
+            // 缓存必须提供推导所期望的路径
+            if let Some(expected) = drv.output_path(name)
+                && expected.display_name() != info.store_path.display_name()
+            {
+                return Err(CacheError::InvalidManifest(format!(
+                    "{} is not the output {} of {}",
+                    info.store_path.display_name(),
+                    expected.display_name(),
+                    drv.name
+                ))
+                .into());
+            }
+            infos.push((name.clone(), info));
+        }
+
+        let mut outputs = HashMap::new();
+        for (name, info) in infos {
+            if !self.store.path_exists(&info.store_path) {
+                let url = if info.url.contains("://") {
+                    info.url.clone()
+                } else {
+                    format!("{}/{}", base, info.url.trim_start_matches('/'))
+                };
+                let file = download_dir.path().join(format!("{}.nar", name));
+                fetcher.fetch_file(&url, &file).map_err(fetch_error)?;
+                let nar = info.compression.decompress(&fs::read(&file)?)?;
+
+                let actual = Hash::of_with(info.nar_hash.algo(), &nar);
+                if actual != info.nar_hash {
+                    return Err(CacheError::from(StoreError::HashMismatch {
+                        expected: info.nar_hash,
+                        actual,
+                    })
+                    .into());
+                }
+                self.store
+                    .add_nar(&nar, &info.store_path, &info.references)?;
+            }
+            outputs.insert(name, info.store_path);
+        }
+
+        Ok(Some(outputs))
+    }
+
+    /// Directory that downloads are staged in.
+    /// 暂存下载内容的目录。
+    fn download_root(&self) -> Result<&Path, CacheError> {
+        fs::create_dir_all(&self.config.temp_dir)?;
+        Ok(&self.config.temp_dir)
+    }
+}
+
+/// Why a substituter could not provide the outputs.
+/// 替代源无法提供输出的原因。
+enum SubstituteError {
+    /// The substituter may not be contacted, or the fetch was cancelled.
+    /// 不能联系该替代源，或获取已被取消。
+    Offline(FetchError),
+    /// Any other failure, treated as a miss. / 其他失败，视为未命中。
+    Cache(CacheError),
+}
+
+impl<E: Into<CacheError>> From<E> for SubstituteError {
+    fn from(e: E) -> Self {
+        SubstituteError::Cache(e.into())
+    }
+}
+
+/// Convert a fetch error into a substitution error.
+/// 将获取错误转换为替代错误。
+fn fetch_error(e: FetchError) -> SubstituteError {
+    match e {
+        FetchError::Offline(_) | FetchError::Cancelled => SubstituteError::Offline(e),
+        e => SubstituteError::Cache(CacheError::Fetch(e.to_string())),
+    }
+}
@@ -1,11 +1,13 @@
 //! String interning for efficient symbol handling.
 //! 字符串驻留，用于高效的符号处理。
 
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 /// An interned string symbol.
 /// 驻留的字符串符号。
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
 pub struct Symbol(u32);
 
 impl Symbol {
@@ -53,4 +55,56 @@ impl Interner {
     pub fn get(&self, sym: Symbol) -> &str {
         &self.strings[sym.0 as usize]
     }
+
+    /// Get the number of interned strings.
+    /// 获取驻留字符串的数量。
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    /// Check whether no strings have been interned.
+    /// 检查是否尚未驻留任何字符串。
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    /// Take a snapshot of the interned strings for persistence.
+    /// 获取驻留字符串的快照以便持久化。
+    pub fn snapshot(&self) -> InternerData {
+        InternerData {
+            strings: self.strings.clone(),
+        }
+    }
+
+    /// Rebuild an interner from a snapshot.
+    /// 从快照重建驻留器。
+    ///
+    /// Every symbol keeps the value it had when the snapshot was taken, so
+    /// cached data referring to those symbols stays valid.
+    /// 每个符号保持快照时的值，因此引用这些符号的缓存数据仍然有效。
+    pub fn from_snapshot(data: InternerData) -> Self {
+        let map = data
+            .strings
+            .iter()
+            .enumerate()
+            .map(|(i, s)| (s.clone(), Symbol(i as u32)))
+            .collect();
+        Self {
+            map,
+            strings: data.strings,
+        }
+    }
+}
+
+/// Persistent form of an [`Interner`].
+/// [`Interner`] 的持久化形式。
+///
+/// A symbol is its index in `strings`, so the snapshot serializes as a plain
+/// list of strings.
+/// 符号即其在 `strings` 中的索引，因此快照序列化为一个简单的字符串列表。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct InternerData {
+    /// Interned strings in symbol order. / 按符号顺序排列的驻留字符串。
+    pub strings: Vec<String>,
 }
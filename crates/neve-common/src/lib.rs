@@ -7,9 +7,12 @@
 //! - `Span`: Source code location tracking / 源码位置跟踪
 //! - `Interner`: String interning for efficient symbol handling / 字符串驻留，用于高效的符号处理
 //! - `Arena`: Memory arena for AST allocation / 内存池，用于 AST 分配
+//! - `find_similar_name`: "Did you mean?" suggestions / "你是不是想要？"建议
 
 mod interner;
 mod span;
+mod suggest;
 
-pub use interner::{Interner, Symbol};
+pub use interner::{Interner, InternerData, Symbol};
 pub use span::{BytePos, Span};
+pub use suggest::{find_similar_name, levenshtein_distance};
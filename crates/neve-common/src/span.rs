@@ -63,17 +63,41 @@ impl Span {
 
     /// Create a span that covers both `self` and `other`.
     /// 创建一个覆盖 `self` 和 `other` 的范围。
+    ///
+    /// `DUMMY` acts as the identity, and reversed bounds are normalized so
+    /// the result always has `start <= end`.
+    /// `DUMMY` 作为单位元，反向的边界会被规范化，使结果始终满足 `start <= end`。
     pub fn merge(self, other: Span) -> Span {
-        Span {
-            start: std::cmp::min(self.start, other.start),
-            end: std::cmp::max(self.end, other.end),
+        match (self == Span::DUMMY, other == Span::DUMMY) {
+            (true, _) => other.normalized(),
+            (false, true) => self.normalized(),
+            (false, false) => {
+                let (a, b) = (self.normalized(), other.normalized());
+                Span {
+                    start: std::cmp::min(a.start, b.start),
+                    end: std::cmp::max(a.end, b.end),
+                }
+            }
+        }
+    }
+
+    /// Returns this span with its bounds ordered so that `start <= end`.
+    /// 返回边界有序（`start <= end`）的范围。
+    pub fn normalized(self) -> Span {
+        if self.start <= self.end {
+            self
+        } else {
+            Span {
+                start: self.end,
+                end: self.start,
+            }
         }
     }
 
     /// Returns the length of this span in bytes.
     /// 返回此范围的字节长度。
     pub fn len(&self) -> usize {
-        (self.end.0 - self.start.0) as usize
+        self.end.0.saturating_sub(self.start.0) as usize
     }
 
     /// Returns true if this span has zero length.
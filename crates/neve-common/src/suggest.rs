@@ -0,0 +1,58 @@
+//! "Did you mean?" suggestions based on edit distance.
+//! 基于编辑距离的"你是不是想要？"建议。
+
+/// Find the most similar name to a given name from a list of candidates.
+/// 从候选列表中找到与给定名称最相似的名称。
+///
+/// Uses Levenshtein distance for fuzzy matching. Ties are broken by picking the
+/// lexicographically smallest candidate so the result does not depend on iteration order.
+/// 使用 Levenshtein 距离进行模糊匹配。距离相同时选择字典序最小的候选，
+/// 使结果不依赖于迭代顺序。
+pub fn find_similar_name<'a, I>(name: &str, candidates: I) -> Option<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let max_distance = match name.chars().count() {
+        0..=2 => 0, // Very short names: exact match only / 非常短的名称：仅精确匹配
+        3..=5 => 1, // Short names: 1 edit distance / 短名称：1 编辑距离
+        _ => 2,     // Longer names: 2 edit distance / 较长名称：2 编辑距离
+    };
+
+    candidates
+        .into_iter()
+        .filter(|candidate| *candidate != name)
+        .filter_map(|candidate| {
+            let distance = levenshtein_distance(name, candidate);
+            (distance <= max_distance).then_some((candidate, distance))
+        })
+        .min_by(|(a, da), (b, db)| da.cmp(db).then_with(|| a.cmp(b)))
+        .map(|(s, _)| s)
+}
+
+/// Calculate the Levenshtein distance between two strings.
+/// 计算两个字符串之间的 Levenshtein 距离。
+pub fn levenshtein_distance(a: &str, b: &str) -> usize {
+    let a_chars: Vec<char> = a.chars().collect();
+    let b_chars: Vec<char> = b.chars().collect();
+
+    if a_chars.is_empty() {
+        return b_chars.len();
+    }
+    if b_chars.is_empty() {
+        return a_chars.len();
+    }
+
+    // Single-row dynamic programming / 单行动态规划
+    let mut row: Vec<usize> = (0..=b_chars.len()).collect();
+    for (i, &ca) in a_chars.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b_chars.iter().enumerate() {
+            let cost = usize::from(ca != cb);
+            let next = (row[j + 1] + 1).min(row[j] + 1).min(diagonal + cost);
+            diagonal = row[j + 1];
+            row[j + 1] = next;
+        }
+    }
+    row[b_chars.len()]
+}
@@ -5,7 +5,7 @@
 //! 处理系统配置之间的切换。
 
 use crate::ConfigError;
-use crate::generate::GeneratedConfig;
+use crate::generate::{GeneratedConfig, GeneratedUnit, SYSTEMD_UNIT_DIR};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
@@ -110,24 +110,53 @@ impl Activator {
             }
         }
 
-        // Enable services
-        // 启用服务
-        for service in &generated.services {
+        // Link and enable service units
+        // 链接并启用服务单元
+        for unit in &generated.units {
             if self.verbose {
-                println!("Enabling service: {}", service);
+                println!("Enabling service: {}", unit.name);
             }
 
             if !self.dry_run {
-                // In a real implementation, this would call systemctl
-                // 在实际实现中，这会调用 systemctl
+                self.link_unit(unit)?;
                 result.services_enabled += 1;
             }
         }
 
+        // Let systemd pick up the new units, unless activating another root
+        // 让 systemd 加载新单元，除非激活的是其他根目录
+        if !generated.units.is_empty() && !self.dry_run && self.root == Path::new("/") {
+            let output = Command::new("systemctl").arg("daemon-reload").output()?;
+            if !output.status.success() {
+                return Err(ConfigError::Activation(format!(
+                    "systemctl daemon-reload failed: {}",
+                    String::from_utf8_lossy(&output.stderr)
+                )));
+            }
+            result.daemon_reloaded = true;
+        }
+
         result.success = true;
         Ok(result)
     }
 
+    /// Link a unit into the systemd unit directory and the `.wants`
+    /// directories of the targets that want it.
+    /// 将单元链接到 systemd 单元目录以及需要它的目标的 `.wants` 目录中。
+    fn link_unit(&self, unit: &GeneratedUnit) -> Result<(), ConfigError> {
+        let unit_dir = self.root.join(SYSTEMD_UNIT_DIR.trim_start_matches('/'));
+        fs::create_dir_all(&unit_dir)?;
+        replace_symlink(&unit.source, &unit_dir.join(&unit.name))?;
+
+        let target = Path::new(SYSTEMD_UNIT_DIR).join(&unit.name);
+        for wanted_by in &unit.wanted_by {
+            let wants_dir = unit_dir.join(format!("{}.wants", wanted_by));
+            fs::create_dir_all(&wants_dir)?;
+            replace_symlink(&target, &wants_dir.join(&unit.name))?;
+        }
+        Ok(())
+    }
+
     /// Switch to a new configuration.
     /// 切换到新配置。
     pub fn switch(
@@ -209,6 +238,8 @@ pub struct ActivationResult {
     pub files_installed: usize,
     /// Number of services enabled. / 已启用的服务数。
     pub services_enabled: usize,
+    /// Whether systemd was told to reload its units. / 是否已通知 systemd 重新加载单元。
+    pub daemon_reloaded: bool,
     /// Output from activation script. / 激活脚本的输出。
     pub script_output: Option<String>,
 }
@@ -221,6 +252,7 @@ impl ActivationResult {
             success: false,
             files_installed: 0,
             services_enabled: 0,
+            daemon_reloaded: false,
             script_output: None,
         }
     }
@@ -265,6 +297,22 @@ impl Default for TestResult {
     }
 }
 
+/// Point `link` at `target`, replacing whatever is there.
+/// 使 `link` 指向 `target`，替换已有的内容。
+fn replace_symlink(target: &Path, link: &Path) -> std::io::Result<()> {
+    if link.symlink_metadata().is_ok() {
+        fs::remove_file(link)?;
+    }
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(target, link)
+    }
+    #[cfg(not(unix))]
+    {
+        fs::write(link, target.to_string_lossy().as_bytes())
+    }
+}
+
 /// Rollback to a previous configuration.
 /// 回滚到之前的配置。
 pub fn rollback(generation: u64, generations_dir: &Path) -> Result<PathBuf, ConfigError> {
@@ -4,7 +4,7 @@
 //! Generates system files and derivations from configuration.
 //! 从配置生成系统文件和推导。
 
-use crate::{ConfigError, SystemConfig};
+use crate::{ConfigError, ConfigOptions, ServiceDef, SystemConfig};
 use neve_derive::{Derivation, StorePath};
 use std::collections::BTreeMap;
 use std::fs;
@@ -156,38 +156,40 @@ impl Generator {
         let services_dir = self.output_dir.join("etc/systemd/system");
         fs::create_dir_all(&services_dir)?;
 
+        // Services with a definition are enabled even if not listed
+        // 有定义的服务即使未列出也会被启用
+        let mut services = config.options.services.clone();
+        for def in &config.options.service_defs {
+            if !services.contains(&def.name) {
+                services.push(def.name.clone());
+            }
+        }
+
         // Generate systemd units for each service
         // 为每个服务生成 systemd 单元
-        for service_name in &config.options.services {
-            let unit = self.create_service_unit(service_name);
+        for service_name in &services {
+            let mut unit = self.create_service_unit(service_name);
+            if let Some(def) = config
+                .options
+                .service_defs
+                .iter()
+                .find(|d| d.name == *service_name)
+            {
+                apply_service_def(&mut unit, def);
+            }
             let unit_content = self.render_service_unit(&unit);
 
             let unit_path = services_dir.join(format!("{}.service", service_name));
             fs::write(&unit_path, &unit_content)?;
 
-            generated.files.push(GeneratedFile {
+            generated.units.push(GeneratedUnit {
+                name: format!("{}.service", service_name),
                 source: unit_path,
-                target: PathBuf::from(format!("/etc/systemd/system/{}.service", service_name)),
-                mode: 0o644,
+                wanted_by: unit.wanted_by,
             });
-
-            // Create symlink for multi-user.target.wants
-            // 为 multi-user.target.wants 创建符号链接
-            let wants_dir = services_dir.join("multi-user.target.wants");
-            fs::create_dir_all(&wants_dir)?;
-
-            #[cfg(unix)]
-            {
-                let link_path = wants_dir.join(format!("{}.service", service_name));
-                let target = format!("/etc/systemd/system/{}.service", service_name);
-                // Remove existing symlink if it exists
-                // 如果存在则删除现有符号链接
-                let _ = fs::remove_file(&link_path);
-                std::os::unix::fs::symlink(&target, &link_path)?;
-            }
         }
 
-        generated.services = config.options.services.clone();
+        generated.services = services;
 
         Ok(())
     }
@@ -322,15 +324,13 @@ impl Generator {
 
         // User-defined users (starting from UID 1000)
         // 用户定义的用户（从 UID 1000 开始）
-        let mut uid = 1000;
-        for user in &config.options.users {
+        for (uid, user) in (1000..).zip(&config.options.users) {
             let shell = user.shell.as_deref().unwrap_or("/bin/sh");
             let home = user.home.display();
             passwd_content.push_str(&format!(
                 "{}:x:{}:{}:{}:{}:{}\n",
                 user.name, uid, uid, user.name, home, shell
             ));
-            uid += 1;
         }
 
         let passwd_path = etc_dir.join("passwd");
@@ -351,11 +351,9 @@ impl Generator {
 
         // Add user groups
         // 添加用户组
-        let mut gid = 1000;
-        for user in &config.options.users {
+        for (gid, user) in (1000..).zip(&config.options.users) {
             // Primary group / 主组
             group_content.push_str(&format!("{}:x:{}:\n", user.name, gid));
-            gid += 1;
         }
 
         // Add users to supplementary groups
@@ -450,9 +448,15 @@ impl Generator {
     ) -> Result<(), ConfigError> {
         let env_path = self.output_dir.join("environment");
 
+        let mut options = ConfigOptions {
+            environment: config.options.environment.clone(),
+            ..Default::default()
+        };
+        options.normalize_environment()?;
+
         let mut content = String::new();
-        for (key, value) in &config.options.environment {
-            content.push_str(&format!("export {}=\"{}\"\n", key, value));
+        for (key, value) in &options.environment {
+            content.push_str(&format!("export {}={}\n", key, shell_quote(value)));
         }
 
         fs::write(&env_path, content)?;
@@ -495,11 +499,28 @@ impl Generator {
             ));
         }
 
-        // Enable services
-        // 启用服务
+        // Link and enable service units
+        // 链接并启用服务单元
         script.push_str("\n# Enable services / 启用服务\n");
-        for service in &generated.services {
-            script.push_str(&format!("# systemctl enable {}\n", service));
+        for unit in &generated.units {
+            let target = format!("{}/{}", SYSTEMD_UNIT_DIR, unit.name);
+            script.push_str(&format!(
+                "ln -sfn {} {}\n",
+                shell_quote(&unit.source.to_string_lossy()),
+                shell_quote(&target)
+            ));
+            for wanted_by in &unit.wanted_by {
+                let wants_dir = format!("{}/{}.wants", SYSTEMD_UNIT_DIR, wanted_by);
+                script.push_str(&format!("mkdir -p {}\n", shell_quote(&wants_dir)));
+                script.push_str(&format!(
+                    "ln -sfn {} {}\n",
+                    shell_quote(&target),
+                    shell_quote(&format!("{}/{}", wants_dir, unit.name))
+                ));
+            }
+        }
+        if !generated.units.is_empty() {
+            script.push_str("command -v systemctl >/dev/null && systemctl daemon-reload\n");
         }
 
         script.push_str("\necho 'Configuration activated.'\n");
@@ -534,6 +555,10 @@ impl Generator {
 
         env.insert("packages".to_string(), config.options.packages.join(" "));
         env.insert("services".to_string(), config.options.services.join(" "));
+        if !config.options.service_defs.is_empty() {
+            let defs = serde_json::to_string(&config.options.service_defs).unwrap_or_default();
+            env.insert("serviceDefs".to_string(), defs);
+        }
 
         Derivation::builder(&config.name, "1.0")
             .system(&self.system)
@@ -542,12 +567,46 @@ impl Generator {
     }
 }
 
+/// Directory that systemd loads system units from.
+/// systemd 加载系统单元的目录。
+pub const SYSTEMD_UNIT_DIR: &str = "/etc/systemd/system";
+
+/// Fill in the settings a service definition overrides.
+/// 填入服务定义所覆盖的设置。
+fn apply_service_def(unit: &mut ServiceUnit, def: &ServiceDef) {
+    if let Some(ref description) = def.description {
+        unit.description = description.clone();
+    }
+    if let Some(ref exec_start) = def.exec_start {
+        unit.exec_start = exec_start.clone();
+    }
+    for after in &def.after {
+        if !unit.after.contains(after) {
+            unit.after.push(after.clone());
+        }
+    }
+    for requires in &def.requires {
+        if !unit.requires.contains(requires) {
+            unit.requires.push(requires.clone());
+        }
+    }
+    if let Some(restart) = def.restart {
+        unit.restart = restart.as_str().to_string();
+    }
+    if let Some(ref user) = def.user {
+        unit.user = Some(user.clone());
+    }
+}
+
 /// Generated configuration.
 /// 生成的配置。
 #[derive(Debug, Clone)]
 pub struct GeneratedConfig {
     /// Generated files. / 生成的文件。
     pub files: Vec<GeneratedFile>,
+    /// Generated systemd units, linked rather than copied on activation.
+    /// 生成的 systemd 单元，激活时链接而不是复制。
+    pub units: Vec<GeneratedUnit>,
     /// Enabled services. / 启用的服务。
     pub services: Vec<String>,
     /// Activation script path. / 激活脚本路径。
@@ -562,6 +621,7 @@ impl GeneratedConfig {
     pub fn new() -> Self {
         Self {
             files: Vec::new(),
+            units: Vec::new(),
             services: Vec::new(),
             activation_script: None,
             store_path: None,
@@ -587,6 +647,24 @@ pub struct GeneratedFile {
     pub mode: u32,
 }
 
+/// A generated systemd unit.
+/// 生成的 systemd 单元。
+#[derive(Debug, Clone)]
+pub struct GeneratedUnit {
+    /// Unit file name, e.g. `sshd.service`. / 单元文件名，例如 `sshd.service`。
+    pub name: String,
+    /// Unit file (in build directory). / 单元文件（在构建目录中）。
+    pub source: PathBuf,
+    /// Targets that want the unit. / 需要该单元的目标。
+    pub wanted_by: Vec<String>,
+}
+
+/// Quote `word` for a POSIX shell, so spaces and metacharacters stay literal.
+/// 为 POSIX shell 引用 `word`，使空格和元字符保持字面含义。
+fn shell_quote(word: &str) -> String {
+    format!("'{}'", word.replace('\'', "'\\''"))
+}
+
 /// Get the current system architecture.
 /// 获取当前系统架构。
 fn current_system() -> String {
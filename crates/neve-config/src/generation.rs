@@ -1,14 +1,18 @@
 //! Configuration generations.
 //! 配置代。
 //!
-//! Manages configuration history for rollback support.
-//! 管理配置历史以支持回滚。
+//! Manages configuration history for rollback support, and reports what
+//! changed between two configurations.
+//! 管理配置历史以支持回滚，并报告两个配置之间的变化。
 
-use crate::ConfigError;
+use crate::{ConfigError, ConfigOptions, ServiceDef, SystemConfig};
 use neve_derive::StorePath;
+use neve_store::{GarbageCollector, Store};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Generations directory name.
 /// 代目录名称。
@@ -75,10 +79,12 @@ impl GenerationManager {
         }
     }
 
-    /// Get the next generation number.
-    /// 获取下一个代号。
+    /// Get the next generation number, one past the newest generation, which
+    /// after a rollback is not the current one.
+    /// 获取下一个代号，即最新代之后的代号；回滚后最新代并非当前代。
     pub fn next_generation(&self) -> Result<u64, ConfigError> {
-        Ok(self.current_generation()?.unwrap_or(0) + 1)
+        let newest = self.list_generations()?.last().map(|g| g.number);
+        Ok(newest.max(self.current_generation()?).unwrap_or(0) + 1)
     }
 
     /// Create a new generation.
@@ -86,8 +92,9 @@ impl GenerationManager {
     pub fn create_generation(
         &self,
         store_path: &StorePath,
-        metadata: GenerationMetadata,
+        mut metadata: GenerationMetadata,
     ) -> Result<Generation, ConfigError> {
+        metadata.previous = self.current_generation()?;
         let gen_num = self.next_generation()?;
         let gen_path = self.generation_path(gen_num);
 
@@ -115,15 +122,7 @@ impl GenerationManager {
 
         // Update current link
         // 更新当前链接
-        let current = self.current_link();
-        if current.exists() || current.is_symlink() {
-            fs::remove_file(&current)?;
-        }
-
-        #[cfg(unix)]
-        std::os::unix::fs::symlink(&gen_path, &current)?;
-        #[cfg(not(unix))]
-        fs::write(&current, gen_path.to_string_lossy().as_bytes())?;
+        replace_link(&gen_path, &self.current_link())?;
 
         Ok(Generation {
             number: gen_num,
@@ -202,6 +201,27 @@ impl GenerationManager {
         })
     }
 
+    /// Record the options a generation was built from.
+    /// 记录构建某个代所用的选项。
+    pub fn save_options(&self, number: u64, options: &ConfigOptions) -> Result<(), ConfigError> {
+        let gen_path = self.generation_path(number);
+        if !gen_path.exists() {
+            return Err(ConfigError::NotFound(format!("generation {}", number)));
+        }
+        fs::write(gen_path.join("options.json"), options.to_json_string()?)?;
+        Ok(())
+    }
+
+    /// Load the options a generation was built from, if they were recorded.
+    /// 加载构建某个代所用的选项（如果已记录）。
+    pub fn load_options(&self, number: u64) -> Result<Option<ConfigOptions>, ConfigError> {
+        let path = self.generation_path(number).join("options.json");
+        if !path.exists() {
+            return Ok(None);
+        }
+        ConfigOptions::from_json_str(&fs::read_to_string(&path)?).map(Some)
+    }
+
     /// Switch to a specific generation.
     /// 切换到特定的代。
     pub fn switch_to(&self, number: u64) -> Result<Generation, ConfigError> {
@@ -209,19 +229,46 @@ impl GenerationManager {
 
         // Update current link
         // 更新当前链接
-        let current = self.current_link();
-        if current.exists() || current.is_symlink() {
-            fs::remove_file(&current)?;
-        }
+        replace_link(&generation.path, &self.current_link())?;
 
-        #[cfg(unix)]
-        std::os::unix::fs::symlink(&generation.path, &current)?;
-        #[cfg(not(unix))]
-        fs::write(&current, generation.path.to_string_lossy().as_bytes())?;
+        Ok(generation)
+    }
 
+    /// Roll back to a past generation by its number.
+    /// 按代号回滚到过去的某一代。
+    ///
+    /// Fails with [`ConfigError::NotFound`] if the generation doesn't exist
+    /// or its configuration was garbage collected from `store`.
+    /// 如果该代不存在或其配置已从 `store` 中被垃圾回收，则以 [`ConfigError::NotFound`] 失败。
+    pub fn rollback_to(&self, number: u64, store: &Store) -> Result<Generation, ConfigError> {
+        let generation = self.load_generation(number)?;
+        if !store.path_exists(&generation.store_path) {
+            return Err(ConfigError::NotFound(format!(
+                "store path {} of generation {}; it was garbage collected, so rebuild the configuration with 'neve config build'",
+                generation.store_path.display_name(),
+                number
+            )));
+        }
+
+        replace_link(&generation.path, &self.current_link())?;
         Ok(generation)
     }
 
+    /// Protect a generation's store path from garbage collection.
+    /// 保护某一代的存储路径不被垃圾回收。
+    ///
+    /// Hold [`Store::lock_shared`] from adding the path until this returns.
+    /// 从添加路径到此函数返回期间，请持有 [`Store::lock_shared`]。
+    pub fn add_gc_root(
+        &self,
+        generation: &Generation,
+        store: &mut Store,
+    ) -> Result<(), ConfigError> {
+        GarbageCollector::new(store)
+            .add_root(&gc_root_name(generation.number), &generation.store_path)?;
+        Ok(())
+    }
+
     /// Delete old generations, keeping the last N.
     /// 删除旧的代，保留最后 N 个。
     pub fn collect_garbage(&self, keep: usize) -> Result<usize, ConfigError> {
@@ -249,6 +296,36 @@ impl GenerationManager {
     }
 }
 
+/// Name of the GC root that keeps generation `number` in the store.
+/// 使第 `number` 代保留在存储中的 GC 根名称。
+fn gc_root_name(number: u64) -> String {
+    format!("system-generation-{}", number)
+}
+
+/// Atomically point `link` at `target`.
+/// 原子地将 `link` 指向 `target`。
+///
+/// The new link is created under a temporary name and renamed over the old
+/// one, so `link` always resolves to either the old or the new target.
+/// 新链接先以临时名称创建，再重命名覆盖旧链接，因此 `link` 总是解析为旧目标或新目标。
+pub(crate) fn replace_link(target: &Path, link: &Path) -> std::io::Result<()> {
+    let name = link
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let tmp = link.with_file_name(format!(".{}.tmp", name));
+    if tmp.exists() || tmp.is_symlink() {
+        fs::remove_file(&tmp)?;
+    }
+
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(target, &tmp)?;
+    #[cfg(not(unix))]
+    fs::write(&tmp, target.to_string_lossy().as_bytes())?;
+
+    fs::rename(&tmp, link)
+}
+
 /// A configuration generation.
 /// 配置代。
 #[derive(Debug, Clone)]
@@ -275,6 +352,10 @@ pub struct GenerationMetadata {
     pub description: Option<String>,
     /// Git commit (if applicable). / Git 提交（如果适用）。
     pub git_commit: Option<String>,
+    /// Generation that was current when this one was created.
+    /// 创建此代时的当前代。
+    #[serde(default)]
+    pub previous: Option<u64>,
 }
 
 impl Default for GenerationMetadata {
@@ -284,6 +365,7 @@ impl Default for GenerationMetadata {
             name: None,
             description: None,
             git_commit: None,
+            previous: None,
         }
     }
 }
@@ -310,6 +392,230 @@ impl GenerationMetadata {
     }
 }
 
+/// A setting that differs between two configurations.
+/// 两个配置之间不同的设置。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValueChange {
+    /// The old value. / 旧值。
+    pub old: Option<String>,
+    /// The new value. / 新值。
+    pub new: Option<String>,
+}
+
+/// Packages added to and removed from one user.
+/// 某个用户添加和删除的包。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserPackagesDiff {
+    /// Username. / 用户名。
+    pub user: String,
+    /// Packages added. / 添加的包。
+    pub added: Vec<String>,
+    /// Packages removed. / 删除的包。
+    pub removed: Vec<String>,
+}
+
+/// What changed between two configurations.
+/// 两个配置之间的变化。
+///
+/// Lists are compared as sets, so reordering or repeating entries is not a
+/// change; every list is sorted.
+/// 列表按集合比较，因此重新排序或重复条目不算变化；所有列表均已排序。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigDiff {
+    /// Hostname change. / 主机名变化。
+    pub hostname: Option<ValueChange>,
+    /// Timezone change. / 时区变化。
+    pub timezone: Option<ValueChange>,
+    /// Locale change. / 语言区域变化。
+    pub locale: Option<ValueChange>,
+    /// Services enabled. / 启用的服务。
+    pub services_added: Vec<String>,
+    /// Services disabled. / 禁用的服务。
+    pub services_removed: Vec<String>,
+    /// Services whose unit definition was added, removed or changed.
+    /// 单元定义被添加、删除或更改的服务。
+    pub service_defs_changed: Vec<String>,
+    /// System packages added. / 添加的系统包。
+    pub packages_added: Vec<String>,
+    /// System packages removed. / 删除的系统包。
+    pub packages_removed: Vec<String>,
+    /// Users added. / 添加的用户。
+    pub users_added: Vec<String>,
+    /// Users removed. / 删除的用户。
+    pub users_removed: Vec<String>,
+    /// Package changes of each user whose packages changed.
+    /// 包发生变化的每个用户的包变化。
+    pub user_packages: Vec<UserPackagesDiff>,
+}
+
+impl ConfigDiff {
+    /// Whether the configurations are equivalent.
+    /// 配置是否等价。
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Compare two configurations.
+/// 比较两个配置。
+pub fn diff(old: &SystemConfig, new: &SystemConfig) -> ConfigDiff {
+    let (old, new) = (&old.options, &new.options);
+    let (services_added, services_removed) = set_diff(&old.services, &new.services);
+    let (old_defs, new_defs) = (service_defs_by_name(old), service_defs_by_name(new));
+    let service_defs_changed = old_defs
+        .keys()
+        .chain(new_defs.keys())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .filter(|name| old_defs.get(*name) != new_defs.get(*name))
+        .map(|name| name.to_string())
+        .collect();
+    let (packages_added, packages_removed) = set_diff(&old.packages, &new.packages);
+
+    // Users missing on one side count as having no packages there
+    // 某一侧缺失的用户在该侧视为没有包
+    let (old_users, new_users) = (packages_by_user(old), packages_by_user(new));
+    let names: BTreeSet<&str> = old_users.keys().chain(new_users.keys()).copied().collect();
+    let none = Vec::new();
+    let user_packages = names
+        .iter()
+        .filter_map(|name| {
+            let (added, removed) = set_diff(
+                old_users.get(name).unwrap_or(&none),
+                new_users.get(name).unwrap_or(&none),
+            );
+            (!added.is_empty() || !removed.is_empty()).then(|| UserPackagesDiff {
+                user: name.to_string(),
+                added,
+                removed,
+            })
+        })
+        .collect();
+
+    ConfigDiff {
+        hostname: value_change(&old.hostname, &new.hostname),
+        timezone: value_change(&old.timezone, &new.timezone),
+        locale: value_change(&old.locale, &new.locale),
+        services_added,
+        services_removed,
+        service_defs_changed,
+        packages_added,
+        packages_removed,
+        users_added: names
+            .iter()
+            .filter(|name| !old_users.contains_key(*name))
+            .map(|name| name.to_string())
+            .collect(),
+        users_removed: names
+            .iter()
+            .filter(|name| !new_users.contains_key(*name))
+            .map(|name| name.to_string())
+            .collect(),
+        user_packages,
+    }
+}
+
+/// The unit definition of each service, by service name.
+/// 按服务名称索引的每个服务的单元定义。
+fn service_defs_by_name(options: &ConfigOptions) -> BTreeMap<&str, &ServiceDef> {
+    options
+        .service_defs
+        .iter()
+        .map(|def| (def.name.as_str(), def))
+        .collect()
+}
+
+/// The packages of each user, by username.
+/// 按用户名索引的每个用户的包。
+fn packages_by_user(options: &ConfigOptions) -> BTreeMap<&str, Vec<String>> {
+    let mut users: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for user in &options.users {
+        users
+            .entry(user.name.as_str())
+            .or_default()
+            .extend(user.packages.iter().cloned());
+    }
+    users
+}
+
+/// Entries only in `new` and entries only in `old`, de-duplicated and sorted.
+/// 仅在 `new` 中和仅在 `old` 中的条目，已去重并排序。
+fn set_diff(old: &[String], new: &[String]) -> (Vec<String>, Vec<String>) {
+    let old: BTreeSet<&String> = old.iter().collect();
+    let new: BTreeSet<&String> = new.iter().collect();
+    (
+        new.difference(&old).map(|s| s.to_string()).collect(),
+        old.difference(&new).map(|s| s.to_string()).collect(),
+    )
+}
+
+/// The change between two values of a setting, if they differ.
+/// 设置的两个值之间的变化（如果不同）。
+fn value_change(old: &Option<String>, new: &Option<String>) -> Option<ValueChange> {
+    (old != new).then(|| ValueChange {
+        old: old.clone(),
+        new: new.clone(),
+    })
+}
+
+impl fmt::Display for ValueChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let show = |value: &Option<String>| value.clone().unwrap_or_else(|| "(unset)".into());
+        write!(f, "{} -> {}", show(&self.old), show(&self.new))
+    }
+}
+
+impl fmt::Display for ConfigDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "no changes");
+        }
+        let settings = [
+            ("hostname", &self.hostname),
+            ("timezone", &self.timezone),
+            ("locale", &self.locale),
+        ];
+        for (name, change) in settings {
+            if let Some(change) = change {
+                writeln!(f, "{}: {}", name, change)?;
+            }
+        }
+        let lists = [
+            ("services added", &self.services_added),
+            ("services removed", &self.services_removed),
+            ("service definitions changed", &self.service_defs_changed),
+            ("packages added", &self.packages_added),
+            ("packages removed", &self.packages_removed),
+            ("users added", &self.users_added),
+            ("users removed", &self.users_removed),
+        ];
+        for (label, entries) in lists {
+            if !entries.is_empty() {
+                writeln!(f, "{}: {}", label, entries.join(", "))?;
+            }
+        }
+        for user in &self.user_packages {
+            if !user.added.is_empty() {
+                writeln!(
+                    f,
+                    "user {} packages added: {}",
+                    user.user,
+                    user.added.join(", ")
+                )?;
+            }
+            if !user.removed.is_empty() {
+                writeln!(
+                    f,
+                    "user {} packages removed: {}",
+                    user.user,
+                    user.removed.join(", ")
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
 /// Get current Unix timestamp.
 /// 获取当前 Unix 时间戳。
 fn current_timestamp() -> u64 {
@@ -0,0 +1,151 @@
+//! Importing configuration options from TOML and JSON files.
+//! 从 TOML 和 JSON 文件导入配置选项。
+//!
+//! A host configuration file holds the fields of [`ConfigOptions`]:
+//! 主机配置文件包含 [`ConfigOptions`] 的字段：
+//!
+//! ```toml
+//! hostname = "web-1"
+//! timezone = "UTC"
+//! services = ["nginx"]
+//! packages = ["git"]
+//!
+//! [environment]
+//! EDITOR = "vim"
+//!
+//! [[users]]
+//! name = "alice"
+//! groups = ["wheel"]
+//! ```
+
+use crate::{ConfigError, ConfigOptions, SystemConfig};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+impl SystemConfig {
+    /// Load a configuration from a TOML file, named after the file.
+    /// 从 TOML 文件加载配置，以文件名命名。
+    pub fn from_toml(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let options = ConfigOptions::from_toml_str(&fs::read_to_string(path)?)
+            .map_err(|e| in_file(path, e))?;
+        Ok(Self::from_file_options(path, options))
+    }
+
+    /// Load a configuration from a JSON file, named after the file.
+    /// 从 JSON 文件加载配置，以文件名命名。
+    pub fn from_json(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let options = ConfigOptions::from_json_str(&fs::read_to_string(path)?)
+            .map_err(|e| in_file(path, e))?;
+        Ok(Self::from_file_options(path, options))
+    }
+
+    /// A configuration named after the file its options came from.
+    /// 以选项来源文件命名的配置。
+    fn from_file_options(path: &Path, options: ConfigOptions) -> Self {
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let mut config = Self::new(name);
+        config.options = options;
+        config
+    }
+}
+
+impl ConfigOptions {
+    /// Parse options from TOML.
+    /// 从 TOML 解析选项。
+    pub fn from_toml_str(content: &str) -> Result<Self, ConfigError> {
+        let options = toml::from_str(content)
+            .map_err(|e| ConfigError::Invalid(e.to_string().trim_end().to_string()))?;
+        Self::finish_import(options)
+    }
+
+    /// Parse options from JSON.
+    /// 从 JSON 解析选项。
+    pub fn from_json_str(content: &str) -> Result<Self, ConfigError> {
+        let options =
+            serde_json::from_str(content).map_err(|e| ConfigError::Invalid(e.to_string()))?;
+        Self::finish_import(options)
+    }
+
+    /// Serialize the options as TOML.
+    /// 将选项序列化为 TOML。
+    pub fn to_toml_string(&self) -> Result<String, ConfigError> {
+        toml::to_string(self).map_err(|e| ConfigError::Invalid(e.to_string()))
+    }
+
+    /// Serialize the options as pretty-printed JSON.
+    /// 将选项序列化为格式化的 JSON。
+    pub fn to_json_string(&self) -> Result<String, ConfigError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Validate imported options and fill in defaults a file may leave out.
+    /// 验证导入的选项并填充文件可能省略的默认值。
+    fn finish_import(mut options: Self) -> Result<Self, ConfigError> {
+        options.normalize_environment()?;
+        options.validate_services()?;
+        for user in &mut options.users {
+            if user.home.as_os_str().is_empty() {
+                user.home = PathBuf::from(format!("/home/{}", user.name));
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// Prefix an import error with the file it came from.
+/// 为导入错误加上其来源文件的前缀。
+fn in_file(path: &Path, e: ConfigError) -> ConfigError {
+    match e {
+        ConfigError::Invalid(msg) => ConfigError::Invalid(format!("{}: {}", path.display(), msg)),
+        e => e,
+    }
+}
+
+/// (De)serialization of environment variables as a table, keeping their order.
+/// 将环境变量作为表进行（反）序列化，并保持其顺序。
+pub(crate) mod environment {
+    use serde::de::{MapAccess, Visitor};
+    use serde::ser::SerializeMap;
+    use serde::{Deserializer, Serializer};
+    use std::fmt;
+
+    pub fn serialize<S: Serializer>(
+        environment: &[(String, String)],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(environment.len()))?;
+        for (name, value) in environment {
+            map.serialize_entry(name, value)?;
+        }
+        map.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<(String, String)>, D::Error> {
+        struct EnvironmentVisitor;
+
+        impl<'de> Visitor<'de> for EnvironmentVisitor {
+            type Value = Vec<(String, String)>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a table of environment variables")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut environment = Vec::new();
+                while let Some(entry) = map.next_entry()? {
+                    environment.push(entry);
+                }
+                Ok(environment)
+            }
+        }
+
+        deserializer.deserialize_map(EnvironmentVisitor)
+    }
+}
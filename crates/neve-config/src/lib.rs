@@ -8,14 +8,19 @@
 //! - Generating system configurations / 生成系统配置
 //! - Activating and switching configurations / 激活和切换配置
 //! - Managing configuration generations / 管理配置代
+//! - Managing package profiles / 管理软件包配置文件
+//! - Importing configurations from TOML and JSON files / 从 TOML 和 JSON 文件导入配置
 
 pub mod activate;
 pub mod flake;
 pub mod generate;
 pub mod generation;
+mod import;
 pub mod module;
+pub mod profile;
 
 use neve_derive::StorePath;
+use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use thiserror::Error;
 
@@ -70,7 +75,12 @@ pub struct SystemConfig {
 
 /// Configuration options.
 /// 配置选项。
-#[derive(Debug, Clone, Default)]
+///
+/// In TOML and JSON files every field is optional, `environment` is a table
+/// of variables and unknown keys are rejected.
+/// 在 TOML 和 JSON 文件中所有字段都是可选的，`environment` 是变量表，未知的键会被拒绝。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct ConfigOptions {
     /// Hostname. / 主机名。
     pub hostname: Option<String>,
@@ -80,30 +90,172 @@ pub struct ConfigOptions {
     pub locale: Option<String>,
     /// Enabled services. / 启用的服务。
     pub services: Vec<String>,
+    /// Definitions customizing the units of services; services without one
+    /// use a default unit. A definition also enables its service.
+    /// 自定义服务单元的定义；没有定义的服务使用默认单元。定义同时会启用其服务。
+    pub service_defs: Vec<ServiceDef>,
     /// System packages. / 系统包。
     pub packages: Vec<String>,
     /// User configurations. / 用户配置。
     pub users: Vec<UserConfig>,
     /// Environment variables. / 环境变量。
+    #[serde(with = "import::environment")]
     pub environment: Vec<(String, String)>,
 }
 
 /// User configuration.
 /// 用户配置。
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct UserConfig {
     /// Username. / 用户名。
     pub name: String,
-    /// User's home directory. / 用户主目录。
+    /// User's home directory, `/home/<name>` when not given in a file.
+    /// 用户主目录，文件中未指定时为 `/home/<name>`。
+    #[serde(default)]
     pub home: PathBuf,
     /// User's shell. / 用户 shell。
+    #[serde(default)]
     pub shell: Option<String>,
     /// User's groups. / 用户组。
+    #[serde(default)]
     pub groups: Vec<String>,
     /// User's packages. / 用户包。
+    #[serde(default)]
     pub packages: Vec<String>,
 }
 
+/// Definition of a service's systemd unit.
+/// 服务的 systemd 单元定义。
+///
+/// Settings left unset keep the values of the default unit for the service.
+/// 未设置的项保留该服务默认单元的值。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ServiceDef {
+    /// Service name, also the unit name. / 服务名称，也是单元名称。
+    pub name: String,
+    /// Description. / 描述。
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Command starting the service (ExecStart=). / 启动服务的命令（ExecStart=）。
+    #[serde(default)]
+    pub exec_start: Option<String>,
+    /// Units to start after (After=). / 在其之后启动的单元（After=）。
+    #[serde(default)]
+    pub after: Vec<String>,
+    /// Units required to run (Requires=). / 运行所需的单元（Requires=）。
+    #[serde(default)]
+    pub requires: Vec<String>,
+    /// Restart policy (Restart=). / 重启策略（Restart=）。
+    #[serde(default)]
+    pub restart: Option<RestartPolicy>,
+    /// User to run as (User=). / 运行的用户（User=）。
+    #[serde(default)]
+    pub user: Option<String>,
+}
+
+/// When systemd restarts a service.
+/// systemd 何时重启服务。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RestartPolicy {
+    /// Never restart. / 从不重启。
+    No,
+    /// Restart when the service fails. / 服务失败时重启。
+    OnFailure,
+    /// Always restart. / 总是重启。
+    Always,
+}
+
+impl RestartPolicy {
+    /// The value of `Restart=` for this policy.
+    /// 此策略对应的 `Restart=` 值。
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::No => "no",
+            Self::OnFailure => "on-failure",
+            Self::Always => "always",
+        }
+    }
+}
+
+impl ServiceDef {
+    /// Create a service definition using the default unit.
+    /// 创建使用默认单元的服务定义。
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            description: None,
+            exec_start: None,
+            after: Vec::new(),
+            requires: Vec::new(),
+            restart: None,
+            user: None,
+        }
+    }
+
+    /// Set the description.
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Set the command starting the service.
+    pub fn exec_start(mut self, command: impl Into<String>) -> Self {
+        self.exec_start = Some(command.into());
+        self
+    }
+
+    /// Start after another unit.
+    pub fn after(mut self, unit: impl Into<String>) -> Self {
+        self.after.push(unit.into());
+        self
+    }
+
+    /// Require another unit.
+    pub fn requires(mut self, unit: impl Into<String>) -> Self {
+        self.requires.push(unit.into());
+        self
+    }
+
+    /// Set the restart policy.
+    pub fn restart(mut self, restart: RestartPolicy) -> Self {
+        self.restart = Some(restart);
+        self
+    }
+
+    /// Set the user to run as.
+    pub fn user(mut self, user: impl Into<String>) -> Self {
+        self.user = Some(user.into());
+        self
+    }
+
+    /// Check that the definition can be written as a unit file.
+    /// 检查该定义能否写为单元文件。
+    ///
+    /// The name must be a valid unit name, and no setting may contain a line
+    /// break, which would start another directive.
+    /// 名称必须是有效的单元名称，且任何设置都不能包含换行符，否则会开始另一条指令。
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        validate_unit_name(&self.name)?;
+        let settings = [&self.description, &self.exec_start, &self.user]
+            .into_iter()
+            .flatten()
+            .chain(&self.after)
+            .chain(&self.requires);
+        for value in settings {
+            if value.contains(['\r', '\n']) {
+                return Err(ConfigError::Invalid(format!(
+                    "service {} has a setting containing a line break: {:?}",
+                    self.name, value
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
 impl SystemConfig {
     /// Create a new system configuration.
     pub fn new(name: impl Into<String>) -> Self {
@@ -133,6 +285,17 @@ impl SystemConfig {
         self
     }
 
+    /// Add a service with a customized unit, replacing any earlier
+    /// definition of the same service.
+    pub fn service_def(mut self, def: ServiceDef) -> Self {
+        if !self.options.services.contains(&def.name) {
+            self.options.services.push(def.name.clone());
+        }
+        self.options.service_defs.retain(|d| d.name != def.name);
+        self.options.service_defs.push(def);
+        self
+    }
+
     /// Add a package.
     pub fn package(mut self, package: impl Into<String>) -> Self {
         self.options.packages.push(package.into());
@@ -146,6 +309,113 @@ impl SystemConfig {
     }
 }
 
+impl ConfigOptions {
+    /// Set an environment variable, replacing any previous value.
+    /// 设置环境变量，替换任何先前的值。
+    pub fn set_env(
+        &mut self,
+        name: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Result<(), ConfigError> {
+        let (name, value) = (name.into(), value.into());
+        validate_env_var(&name, &value)?;
+        match self.environment.iter_mut().find(|(k, _)| *k == name) {
+            Some(entry) => entry.1 = value,
+            None => self.environment.push((name, value)),
+        }
+        Ok(())
+    }
+
+    /// Layer `overlay` on top of this environment.
+    /// 将 `overlay` 叠加到此环境之上。
+    ///
+    /// Variables from `overlay` override existing ones with the same name;
+    /// new variables are appended in order.
+    /// `overlay` 中的变量覆盖同名的已有变量；新变量按顺序追加。
+    pub fn merge_environment(&mut self, overlay: &[(String, String)]) -> Result<(), ConfigError> {
+        self.normalize_environment()?;
+        for (name, value) in overlay {
+            self.set_env(name.as_str(), value.as_str())?;
+        }
+        Ok(())
+    }
+
+    /// Validate the names of the enabled services and their definitions.
+    /// 验证启用的服务名称及其定义。
+    pub fn validate_services(&self) -> Result<(), ConfigError> {
+        for name in &self.services {
+            validate_unit_name(name)?;
+        }
+        self.service_defs.iter().try_for_each(ServiceDef::validate)
+    }
+
+    /// Validate the environment and collapse duplicate names.
+    /// 验证环境并合并重复的名称。
+    ///
+    /// Duplicates resolve last-wins: the final value for a name is kept at
+    /// the position where the name first appeared.
+    /// 重复项以最后一个为准：名称的最终值保留在该名称首次出现的位置。
+    pub fn normalize_environment(&mut self) -> Result<(), ConfigError> {
+        let mut normalized: Vec<(String, String)> = Vec::with_capacity(self.environment.len());
+        for (name, value) in self.environment.drain(..) {
+            validate_env_var(&name, &value)?;
+            match normalized.iter_mut().find(|(k, _)| *k == name) {
+                Some(entry) => entry.1 = value,
+                None => normalized.push((name, value)),
+            }
+        }
+        self.environment = normalized;
+        Ok(())
+    }
+}
+
+/// Check that a service name is a valid systemd unit name.
+/// 检查服务名称是否为有效的 systemd 单元名称。
+///
+/// Names are non-empty and made of ASCII letters, digits and `:-_.\@`, so
+/// they can't leave the unit directory.
+/// 名称非空，由 ASCII 字母、数字和 `:-_.\@` 组成，因此不会离开单元目录。
+pub fn validate_unit_name(name: &str) -> Result<(), ConfigError> {
+    let valid = |c: char| c.is_ascii_alphanumeric() || ":-_.\\@".contains(c);
+    if name.is_empty() || !name.chars().all(valid) {
+        return Err(ConfigError::Invalid(format!(
+            "invalid service name: {:?}",
+            name
+        )));
+    }
+    Ok(())
+}
+
+/// Check that an environment variable is well-formed.
+/// 检查环境变量格式是否正确。
+///
+/// Names must be shell identifiers, `[A-Za-z_][A-Za-z0-9_]*`, as they are
+/// written into a sourced shell script; values must not contain null bytes.
+/// 名称必须是 shell 标识符 `[A-Za-z_][A-Za-z0-9_]*`，因为它们会被写入被 source 的
+/// shell 脚本；值不能包含空字节。
+pub fn validate_env_var(name: &str, value: &str) -> Result<(), ConfigError> {
+    if name.is_empty() {
+        return Err(ConfigError::Invalid(
+            "empty environment variable name".into(),
+        ));
+    }
+    let identifier = !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !identifier {
+        return Err(ConfigError::Invalid(format!(
+            "invalid environment variable name: {:?}",
+            name
+        )));
+    }
+    if value.contains('\0') {
+        return Err(ConfigError::Invalid(format!(
+            "environment variable {} contains a null byte",
+            name
+        )));
+    }
+    Ok(())
+}
+
 impl UserConfig {
     /// Create a new user configuration.
     pub fn new(name: impl Into<String>) -> Self {
@@ -215,6 +215,7 @@ impl Module {
             }
         }
 
+        config.options.validate_services()?;
         Ok(config)
     }
 }
@@ -279,8 +280,7 @@ pub fn merge_modules(modules: &[Module]) -> Result<SystemConfig, ConfigError> {
         merged.options.users.extend(config.options.users);
         merged
             .options
-            .environment
-            .extend(config.options.environment);
+            .merge_environment(&config.options.environment)?;
     }
 
     // Deduplicate
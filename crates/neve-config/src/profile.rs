@@ -0,0 +1,228 @@
+//! Package profiles.
+//! 软件包配置文件。
+//!
+//! A profile is a list of generations, each holding a manifest of installed
+//! store paths and a `bin` directory of links into them, plus a `current`
+//! link to the active generation. Generations are written under a temporary
+//! name and renamed into place once complete, and `current` is swapped with
+//! an atomic rename, so an interrupted install never leaves a broken profile.
+//! 配置文件由若干代组成，每一代包含已安装存储路径的清单和链接到它们的 `bin` 目录，
+//! 以及指向活动代的 `current` 链接。代先以临时名称写入，完成后再重命名到位，
+//! `current` 通过原子重命名切换，因此中断的安装永远不会留下损坏的配置文件。
+
+use crate::ConfigError;
+use crate::generation::replace_link;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Manifest file name inside a generation.
+/// 代中清单文件的名称。
+const MANIFEST: &str = "manifest";
+
+/// Prefix of generation directory names.
+/// 代目录名称的前缀。
+const GENERATION_PREFIX: &str = "generation-";
+
+/// A user package profile.
+/// 用户软件包配置文件。
+pub struct Profile {
+    /// Profile directory. / 配置文件目录。
+    dir: PathBuf,
+}
+
+impl Profile {
+    /// Open a profile, creating its directory if needed.
+    /// 打开配置文件，必要时创建其目录。
+    pub fn new(dir: PathBuf) -> Result<Self, ConfigError> {
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    /// Open a profile for reading without creating anything.
+    /// 以只读方式打开配置文件，不创建任何内容。
+    ///
+    /// A missing directory reads as a profile with no generations.
+    /// 缺失的目录视为没有任何代的配置文件。
+    pub fn open(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Get the profile directory.
+    /// 获取配置文件目录。
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Get the `current` link path.
+    /// 获取 `current` 链接路径。
+    fn current_link(&self) -> PathBuf {
+        self.dir.join("current")
+    }
+
+    /// Get the path for a specific generation.
+    /// 获取特定代的路径。
+    pub fn generation_path(&self, num: u64) -> PathBuf {
+        self.dir.join(format!("{}{}", GENERATION_PREFIX, num))
+    }
+
+    /// Get the current generation number.
+    /// 获取当前代号。
+    pub fn current_generation(&self) -> Result<Option<u64>, ConfigError> {
+        let current = self.current_link();
+        if !current.is_symlink() {
+            return Ok(None);
+        }
+
+        let target = fs::read_link(&current)?;
+        target
+            .file_name()
+            .and_then(|n| n.to_str())
+            .and_then(parse_generation)
+            .map(Some)
+            .ok_or_else(|| ConfigError::Invalid("invalid profile generation link".to_string()))
+    }
+
+    /// List all complete generations in ascending order.
+    /// 按升序列出所有完整的代。
+    pub fn generations(&self) -> Result<Vec<u64>, ConfigError> {
+        let mut generations = Vec::new();
+        if !self.dir.exists() {
+            return Ok(generations);
+        }
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            if let Some(num) = entry.file_name().to_str().and_then(parse_generation) {
+                generations.push(num);
+            }
+        }
+        generations.sort_unstable();
+        Ok(generations)
+    }
+
+    /// Get the next generation number.
+    /// 获取下一个代号。
+    pub fn next_generation(&self) -> Result<u64, ConfigError> {
+        Ok(self.generations()?.last().copied().unwrap_or(0) + 1)
+    }
+
+    /// Read the manifest of a generation.
+    /// 读取某一代的清单。
+    pub fn manifest(&self, num: u64) -> Result<Vec<PathBuf>, ConfigError> {
+        let path = self.generation_path(num).join(MANIFEST);
+        if !path.exists() {
+            return Err(ConfigError::NotFound(format!("generation {}", num)));
+        }
+        Ok(fs::read_to_string(path)?
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(PathBuf::from)
+            .collect())
+    }
+
+    /// Read the manifest of the current generation, empty if there is none.
+    /// 读取当前代的清单，若没有则为空。
+    pub fn current_manifest(&self) -> Result<Vec<PathBuf>, ConfigError> {
+        match self.current_generation()? {
+            Some(num) => self.manifest(num),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Write a complete new generation without activating it.
+    /// 写入一个完整的新代但不激活它。
+    ///
+    /// The generation is assembled in a temporary directory and renamed into
+    /// place only when complete, so a partially written generation is never
+    /// visible under its final name.
+    /// 代先在临时目录中组装，完成后才重命名到位，因此部分写入的代永远不会以最终名称出现。
+    pub fn build_generation(&self, packages: &[PathBuf]) -> Result<u64, ConfigError> {
+        let num = self.next_generation()?;
+        let staging = self.dir.join(format!(".{}{}.tmp", GENERATION_PREFIX, num));
+        if staging.exists() {
+            fs::remove_dir_all(&staging)?;
+        }
+        fs::create_dir_all(&staging)?;
+
+        let mut manifest = String::new();
+        for package in packages {
+            manifest.push_str(&package.to_string_lossy());
+            manifest.push('\n');
+        }
+        fs::write(staging.join(MANIFEST), manifest)?;
+
+        // Link binaries; later packages win on name clashes
+        // 链接二进制文件；名称冲突时后出现的软件包优先
+        let bin_dir = staging.join("bin");
+        fs::create_dir_all(&bin_dir)?;
+        for package in packages {
+            let pkg_bin = package.join("bin");
+            if !pkg_bin.is_dir() {
+                continue;
+            }
+            for entry in fs::read_dir(&pkg_bin)? {
+                let entry = entry?;
+                let dst = bin_dir.join(entry.file_name());
+                if dst.is_symlink() || dst.exists() {
+                    fs::remove_file(&dst)?;
+                }
+                link(&entry.path(), &dst)?;
+            }
+        }
+
+        fs::rename(&staging, self.generation_path(num))?;
+        Ok(num)
+    }
+
+    /// Atomically make a generation current.
+    /// 原子地将某一代设为当前代。
+    pub fn switch_to(&self, num: u64) -> Result<(), ConfigError> {
+        let path = self.generation_path(num);
+        if !path.join(MANIFEST).exists() {
+            return Err(ConfigError::NotFound(format!("generation {}", num)));
+        }
+        replace_link(&path, &self.current_link())?;
+        Ok(())
+    }
+
+    /// Write a new generation and make it current.
+    /// 写入新代并将其设为当前代。
+    pub fn create_generation(&self, packages: &[PathBuf]) -> Result<u64, ConfigError> {
+        let num = self.build_generation(packages)?;
+        self.switch_to(num)?;
+        Ok(num)
+    }
+
+    /// Re-point `current` at the generation before it.
+    /// 将 `current` 重新指向其前一代。
+    pub fn rollback(&self) -> Result<u64, ConfigError> {
+        let current = self
+            .current_generation()?
+            .ok_or_else(|| ConfigError::NotFound("current generation".to_string()))?;
+        let previous = self
+            .generations()?
+            .into_iter()
+            .rfind(|&num| num < current)
+            .ok_or_else(|| ConfigError::NotFound("previous generation".to_string()))?;
+        self.switch_to(previous)?;
+        Ok(previous)
+    }
+}
+
+/// Parse a generation number from a directory name.
+/// 从目录名称解析代号。
+fn parse_generation(name: &str) -> Option<u64> {
+    name.strip_prefix(GENERATION_PREFIX)?.parse().ok()
+}
+
+/// Create a link to a binary.
+/// 创建指向二进制文件的链接。
+fn link(target: &Path, link: &Path) -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(target, link)
+    }
+    #[cfg(not(unix))]
+    {
+        fs::write(link, target.to_string_lossy().as_bytes())
+    }
+}
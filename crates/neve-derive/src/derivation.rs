@@ -13,7 +13,7 @@
 //! - 如何构建包
 //! - 产生哪些输出
 
-use crate::{Hash, Hasher, Output, StorePath};
+use crate::{Hash, HashAlgo, HashMode, Hasher, Output, StorePath};
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 
@@ -34,6 +34,7 @@ pub struct Derivation {
     /// Environment variables for the build. / 构建的环境变量。
     pub env: BTreeMap<String, String>,
     /// Input derivations (dependencies). / 输入推导（依赖）。
+    #[serde(with = "input_drvs_serde")]
     pub input_drvs: BTreeMap<StorePath, Vec<String>>,
     /// Input sources (already in store). / 输入源（已在存储中）。
     pub input_srcs: Vec<StorePath>,
@@ -83,6 +84,12 @@ impl Derivation {
         for (name, output) in &self.outputs {
             hasher.update_str(name);
             if let Some(hash) = &output.expected_hash {
+                // Only non-default algorithms are mixed in, so blake3
+                // fixed-output derivations keep their hashes
+                // 仅混入非默认算法，使 blake3 固定输出推导保持原有哈希
+                if hash.algo() != HashAlgo::Blake3 {
+                    hasher.update_str(hash.algo().name());
+                }
                 hasher.update(hash.as_bytes());
             }
         }
@@ -108,12 +115,31 @@ impl Derivation {
         self.output_path("out")
     }
 
+    /// Get the placeholder standing for an output's final path.
+    /// 获取代表某个输出最终路径的占位符。
+    ///
+    /// See [`output_placeholder`].
+    /// 参见 [`output_placeholder`]。
+    pub fn placeholder(&self, output: &str) -> String {
+        output_placeholder(&self.name, &self.version, output)
+    }
+
     /// Check if this is a fixed-output derivation.
     /// 检查是否为固定输出推导。
+    ///
+    /// Fixed-output derivations may use the network while building, since
+    /// their result is checked against the declared hash.
+    /// 固定输出推导在构建时可以使用网络，因为其结果会根据声明的哈希进行检查。
     pub fn is_fixed_output(&self) -> bool {
         self.outputs.values().any(|o| o.is_fixed())
     }
 
+    /// The declared hash of the output of a fixed-output derivation.
+    /// 固定输出推导的输出所声明的哈希。
+    pub fn output_hash(&self) -> Option<Hash> {
+        self.outputs.values().find_map(|o| o.expected_hash)
+    }
+
     /// Serialize to JSON.
     /// 序列化为 JSON。
     pub fn to_json(&self) -> Result<String, serde_json::Error> {
@@ -221,6 +247,28 @@ impl DerivationBuilder {
         self
     }
 
+    /// Get the placeholder standing for an output's final path.
+    /// 获取代表某个输出最终路径的占位符。
+    pub fn placeholder(&self, output: &str) -> String {
+        output_placeholder(&self.name, &self.version, output)
+    }
+
+    /// Make this a fixed-output derivation whose single `out` output must
+    /// have the given hash.
+    /// 将其设为固定输出推导，其唯一的 `out` 输出必须具有给定的哈希。
+    ///
+    /// This is how fetchers such as `curl` or `git` are pinned inside the
+    /// build graph: the build gets network access, and its output is rejected
+    /// unless it hashes to `hash`.
+    /// 这是在构建图中固定 `curl` 或 `git` 等获取器的方式：构建可以访问网络，
+    /// 但除非其输出的哈希为 `hash`，否则会被拒绝。
+    pub fn output_hash(mut self, hash: Hash, mode: HashMode) -> Self {
+        let output = Output::fixed("out", hash, mode);
+        self.outputs.clear();
+        self.outputs.insert(output.name.clone(), output);
+        self
+    }
+
     /// Add an output.
     /// 添加输出。
     pub fn output(mut self, output: Output) -> Self {
@@ -245,6 +293,46 @@ impl DerivationBuilder {
     }
 }
 
+/// Get the placeholder for `output` of the derivation `name`-`version`.
+/// 获取推导 `name`-`version` 的 `output` 的占位符。
+///
+/// An output's store path is only known once it has been built, so `env`
+/// and `args` refer to it through this placeholder instead. The builder
+/// replaces it with the output directory while building, and with the
+/// realized store path afterwards. It depends only on the name, version and
+/// output, never on the derivation hash, so a derivation can embed it
+/// without changing its own hash in a loop.
+/// 输出的存储路径只有在构建后才能知道，因此 `env` 和 `args` 通过此占位符引用它。
+/// 构建器在构建期间将其替换为输出目录，构建后替换为实际存储路径。
+/// 它只依赖于名称、版本和输出名，而不依赖推导哈希，因此推导可以嵌入它而不会循环改变自身哈希。
+pub fn output_placeholder(name: &str, version: &str, output: &str) -> String {
+    let hash = Hash::of_str(&format!("neve-output:{}-{}:{}", name, version, output));
+    format!("/{}", hash.digest_hex())
+}
+
+/// Serialize input derivations as a list of pairs, since JSON object keys
+/// must be strings and store paths are structured.
+/// 将输入推导序列化为键值对列表，因为 JSON 对象的键必须是字符串，而存储路径是结构化的。
+mod input_drvs_serde {
+    use crate::StorePath;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::collections::BTreeMap;
+
+    pub fn serialize<S: Serializer>(
+        map: &BTreeMap<StorePath, Vec<String>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(map.iter())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<BTreeMap<StorePath, Vec<String>>, D::Error> {
+        let pairs = Vec::<(StorePath, Vec<String>)>::deserialize(deserializer)?;
+        Ok(pairs.into_iter().collect())
+    }
+}
+
 /// Get the current system identifier.
 /// 获取当前系统标识符。
 pub fn current_system() -> &'static str {
@@ -1,33 +1,59 @@
 //! Content hashing for derivations.
 //! 推导的内容哈希。
 //!
-//! Neve uses BLAKE3 for all content hashing due to its speed and security.
-//! Neve 使用 BLAKE3 进行所有内容哈希，因为其速度快且安全。
+//! Neve uses BLAKE3 for content hashing due to its speed and security.
+//! SHA-256 hashes are supported for interoperating with systems that pin them.
+//! Neve 使用 BLAKE3 进行内容哈希，因为其速度快且安全。
+//! 支持 SHA-256 哈希，以便与固定使用它的系统互操作。
 
 use serde::{Deserialize, Serialize};
+use sha2::Digest;
 use std::fmt;
+use std::str::FromStr;
 
-/// A content hash using BLAKE3.
-/// 使用 BLAKE3 的内容哈希。
+/// Length of a full hex-encoded hash.
+/// 完整十六进制编码哈希的长度。
+const HEX_LEN: usize = 64;
+
+/// A content hash, tagged with the algorithm that produced it.
+/// 内容哈希，带有生成它的算法标记。
+///
+/// Hashes of the same bytes under different algorithms never compare equal.
+/// 同一字节在不同算法下的哈希永远不相等。
 #[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct Hash {
+    // Omitted for BLAKE3 so existing serialized hashes are unchanged
+    // 对 BLAKE3 省略，使已有的序列化哈希保持不变
+    #[serde(default, skip_serializing_if = "HashAlgo::is_default")]
+    algo: HashAlgo,
     bytes: [u8; 32],
 }
 
 impl Hash {
-    /// Create a hash from raw bytes.
-    /// 从原始字节创建哈希。
+    /// Create a BLAKE3 hash from raw bytes.
+    /// 从原始字节创建 BLAKE3 哈希。
     pub fn from_bytes(bytes: [u8; 32]) -> Self {
-        Self { bytes }
+        Self::from_bytes_with(HashAlgo::Blake3, bytes)
+    }
+
+    /// Create a hash from the raw bytes of a digest made with `algo`.
+    /// 从使用 `algo` 生成的摘要的原始字节创建哈希。
+    pub fn from_bytes_with(algo: HashAlgo, bytes: [u8; 32]) -> Self {
+        Self { algo, bytes }
     }
 
-    /// Hash arbitrary data.
-    /// 哈希任意数据。
+    /// Hash arbitrary data with BLAKE3.
+    /// 使用 BLAKE3 哈希任意数据。
     pub fn of(data: &[u8]) -> Self {
-        let hash = blake3::hash(data);
-        Self {
-            bytes: *hash.as_bytes(),
-        }
+        Self::of_with(HashAlgo::Blake3, data)
+    }
+
+    /// Hash arbitrary data with `algo`.
+    /// 使用 `algo` 哈希任意数据。
+    pub fn of_with(algo: HashAlgo, data: &[u8]) -> Self {
+        let mut hasher = Hasher::with_algo(algo);
+        hasher.update(data);
+        hasher.finalize()
     }
 
     /// Hash a string.
@@ -36,6 +62,12 @@ impl Hash {
         Self::of(s.as_bytes())
     }
 
+    /// The algorithm that produced this hash.
+    /// 生成此哈希的算法。
+    pub fn algo(&self) -> HashAlgo {
+        self.algo
+    }
+
     /// Get the raw bytes.
     /// 获取原始字节。
     pub fn as_bytes(&self) -> &[u8; 32] {
@@ -48,28 +80,54 @@ impl Hash {
         hex::encode(&self.bytes[..16])
     }
 
-    /// Convert to a full hex string.
-    /// 转换为完整的十六进制字符串。
+    /// Convert to a full hex string with an SRI-style algorithm prefix,
+    /// such as `blake3-…` or `sha256-…`.
+    /// 转换为带有 SRI 风格算法前缀的完整十六进制字符串，例如 `blake3-…` 或 `sha256-…`。
     pub fn to_hex(&self) -> String {
+        format!("{}-{}", self.algo, self.digest_hex())
+    }
+
+    /// Convert the digest alone to a full hex string, without the algorithm.
+    /// 仅将摘要转换为完整的十六进制字符串，不含算法。
+    ///
+    /// Used where the hex form names something on disk or on the wire.
+    /// 用于十六进制形式命名磁盘上或传输中的某物的地方。
+    pub fn digest_hex(&self) -> String {
         hex::encode(&self.bytes)
     }
 
-    /// Parse from a hex string.
-    /// 从十六进制字符串解析。
+    /// Parse from a full hex string, the inverse of [`Hash::to_hex`].
+    /// 从完整的十六进制字符串解析，是 [`Hash::to_hex`] 的逆操作。
+    ///
+    /// The digest must be exactly 64 hex digits; truncated or padded hashes
+    /// are rejected rather than silently accepted. Without an `algo-` prefix
+    /// the digest is taken to be BLAKE3.
+    /// 摘要必须恰好是 64 个十六进制数字；截断或填充的哈希会被拒绝而不是被静默接受。
+    /// 没有 `algo-` 前缀时，摘要被视为 BLAKE3。
     pub fn from_hex(s: &str) -> Result<Self, HashError> {
-        let bytes = hex::decode(s).map_err(|_| HashError::InvalidHex)?;
-        if bytes.len() != 32 {
-            return Err(HashError::InvalidLength);
+        let (algo, digest, offset) = match s.split_once('-') {
+            Some((algo, digest)) => (algo.parse()?, digest, algo.len() + 1),
+            None => (HashAlgo::Blake3, s, 0),
+        };
+        if let Some((position, ch)) = digest.char_indices().find(|(_, c)| !c.is_ascii_hexdigit()) {
+            return Err(HashError::InvalidHex {
+                ch,
+                position: offset + position,
+            });
         }
+        if digest.len() != HEX_LEN {
+            return Err(HashError::InvalidLength(digest.len()));
+        }
+        let bytes = hex::decode(digest).map_err(|_| HashError::InvalidLength(digest.len()))?;
         let mut arr = [0u8; 32];
         arr.copy_from_slice(&bytes);
-        Ok(Self { bytes: arr })
+        Ok(Self { algo, bytes: arr })
     }
 
     /// The null hash (all zeros).
     /// 空哈希（全零）。
     pub fn null() -> Self {
-        Self { bytes: [0u8; 32] }
+        Self::from_bytes([0u8; 32])
     }
 
     /// Check if this is the null hash.
@@ -85,9 +143,17 @@ impl fmt::Debug for Hash {
     }
 }
 
+impl FromStr for Hash {
+    type Err = HashError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_hex(s)
+    }
+}
+
 impl fmt::Display for Hash {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.to_short_hex())
+        write!(f, "{}", self.to_hex())
     }
 }
 
@@ -95,33 +161,110 @@ impl fmt::Display for Hash {
 /// 与哈希操作相关的错误。
 #[derive(Debug, Clone, thiserror::Error)]
 pub enum HashError {
-    /// Invalid hex string. / 无效的十六进制字符串。
-    #[error("invalid hex string")]
-    InvalidHex,
-    /// Invalid hash length. / 无效的哈希长度。
-    #[error("invalid hash length")]
-    InvalidLength,
+    /// A character that is not a hex digit. / 非十六进制数字的字符。
+    #[error("invalid hex digit {ch:?} at position {position}")]
+    InvalidHex { ch: char, position: usize },
+    /// Wrong number of hex digits. / 十六进制数字的个数错误。
+    #[error("invalid hash length: expected {HEX_LEN} hex digits, got {0}")]
+    InvalidLength(usize),
+    /// A hash algorithm Neve does not support. / Neve 不支持的哈希算法。
+    #[error("unsupported hash algorithm: {0}")]
+    UnsupportedAlgo(String),
+}
+
+/// A hash algorithm.
+/// 哈希算法。
+///
+/// Naming the algorithm keeps fixed-output declarations and pinned hashes
+/// explicit about how their hash was computed.
+/// 显式指定算法可以让固定输出声明和固定的哈希明确其哈希的计算方式。
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgo {
+    /// BLAKE3, the default. / BLAKE3，默认算法。
+    #[default]
+    Blake3,
+    /// SHA-256. / SHA-256。
+    Sha256,
+}
+
+impl HashAlgo {
+    /// The name of the algorithm.
+    /// 算法名称。
+    pub fn name(&self) -> &'static str {
+        match self {
+            HashAlgo::Blake3 => "blake3",
+            HashAlgo::Sha256 => "sha256",
+        }
+    }
+
+    /// Whether this is the default algorithm.
+    /// 是否为默认算法。
+    pub fn is_default(&self) -> bool {
+        *self == HashAlgo::default()
+    }
+}
+
+impl FromStr for HashAlgo {
+    type Err = HashError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "blake3" => Ok(HashAlgo::Blake3),
+            "sha256" => Ok(HashAlgo::Sha256),
+            other => Err(HashError::UnsupportedAlgo(other.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for HashAlgo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
 }
 
 /// A hasher for incrementally building hashes.
 /// 用于增量构建哈希的哈希器。
 pub struct Hasher {
-    inner: blake3::Hasher,
+    inner: HasherState,
+}
+
+/// The running state of a [`Hasher`] for each algorithm.
+/// 每种算法下 [`Hasher`] 的运行状态。
+#[derive(Clone)]
+enum HasherState {
+    Blake3(Box<blake3::Hasher>),
+    Sha256(sha2::Sha256),
 }
 
 impl Hasher {
-    /// Create a new hasher.
-    /// 创建新的哈希器。
+    /// Create a new BLAKE3 hasher.
+    /// 创建新的 BLAKE3 哈希器。
     pub fn new() -> Self {
-        Self {
-            inner: blake3::Hasher::new(),
-        }
+        Self::with_algo(HashAlgo::Blake3)
+    }
+
+    /// Create a new hasher for `algo`.
+    /// 为 `algo` 创建新的哈希器。
+    pub fn with_algo(algo: HashAlgo) -> Self {
+        let inner = match algo {
+            HashAlgo::Blake3 => HasherState::Blake3(Box::new(blake3::Hasher::new())),
+            HashAlgo::Sha256 => HasherState::Sha256(sha2::Sha256::new()),
+        };
+        Self { inner }
     }
 
     /// Update the hasher with data.
     /// 用数据更新哈希器。
     pub fn update(&mut self, data: &[u8]) -> &mut Self {
-        self.inner.update(data);
+        match &mut self.inner {
+            HasherState::Blake3(hasher) => {
+                hasher.update(data);
+            }
+            HasherState::Sha256(hasher) => hasher.update(data),
+        }
         self
     }
 
@@ -134,9 +277,11 @@ impl Hasher {
     /// Finalize and return the hash.
     /// 完成并返回哈希值。
     pub fn finalize(&self) -> Hash {
-        let hash = self.inner.finalize();
-        Hash {
-            bytes: *hash.as_bytes(),
+        match &self.inner {
+            HasherState::Blake3(hasher) => Hash::from_bytes(*hasher.finalize().as_bytes()),
+            HasherState::Sha256(hasher) => {
+                Hash::from_bytes_with(HashAlgo::Sha256, hasher.clone().finalize().into())
+            }
         }
     }
 }
@@ -16,6 +16,7 @@ pub use derivation::*;
 pub use hash::*;
 pub use output::*;
 pub use resolve::{
-    Dependency, MemoryRegistry, PackageId, PackageMetadata, PackageRegistry, Resolution,
-    ResolveError, Resolver, Version, VersionConstraint, VersionParseError,
+    Dependency, MemoryRegistry, PackageId, PackageLock, PackageMetadata, PackageRegistry,
+    Resolution, ResolveError, Resolver, Version, VersionChange, VersionConstraint,
+    VersionParseError, diff_versions,
 };
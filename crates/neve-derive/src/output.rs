@@ -125,7 +125,8 @@ pub struct Output {
     pub path: Option<StorePath>,
     /// Hash mode for fixed-output derivations. / 固定输出推导的哈希模式。
     pub hash_mode: Option<HashMode>,
-    /// Expected hash for fixed-output derivations. / 固定输出推导的预期哈希。
+    /// Expected hash for fixed-output derivations, which also names its
+    /// algorithm. / 固定输出推导的预期哈希，同时指明其算法。
     pub expected_hash: Option<Hash>,
 }
 
@@ -6,7 +6,8 @@
 //! 本模块实现了一个基于 SAT 的依赖解析器，用于找到满足所有约束的一致包版本集合。
 
 use crate::StorePath;
-use std::collections::{HashMap, HashSet, VecDeque};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 
 /// A package identifier with name and version.
 /// 带有名称和版本的包标识符。
@@ -120,6 +121,19 @@ impl std::fmt::Display for Version {
     }
 }
 
+impl Serialize for Version {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Version {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Version::parse(&s).map_err(|_| serde::de::Error::custom(format!("invalid version '{}'", s)))
+    }
+}
+
 /// Version parsing error.
 /// 版本解析错误。
 #[derive(Debug, Clone)]
@@ -340,6 +354,142 @@ pub struct Resolution {
     pub build_order: Vec<PackageId>,
 }
 
+impl Resolution {
+    /// The resolved version of every package.
+    /// 每个包的解析版本。
+    pub fn versions(&self) -> BTreeMap<String, Version> {
+        self.packages
+            .iter()
+            .map(|(name, id)| (name.clone(), id.version.clone()))
+            .collect()
+    }
+}
+
+/// A change in a package's locked version.
+/// 包锁定版本的变化。
+///
+/// `V` is whatever identifies a locked version: a [`Version`] for packages,
+/// or e.g. a revision for flake inputs.
+/// `V` 是标识锁定版本的任意类型：包使用 [`Version`]，flake 输入可以使用修订版本等。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VersionChange<V = Version> {
+    /// A package that was not locked before. / 之前未锁定的包。
+    Added { name: String, version: V },
+    /// A package that is no longer needed. / 不再需要的包。
+    Removed { name: String, version: V },
+    /// A package that moved to another version. / 移动到另一个版本的包。
+    Updated { name: String, from: V, to: V },
+}
+
+impl<V> VersionChange<V> {
+    /// The name of the changed package.
+    /// 发生变化的包名称。
+    pub fn name(&self) -> &str {
+        match self {
+            VersionChange::Added { name, .. }
+            | VersionChange::Removed { name, .. }
+            | VersionChange::Updated { name, .. } => name,
+        }
+    }
+}
+
+impl<V: std::fmt::Display> std::fmt::Display for VersionChange<V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VersionChange::Added { name, version } => write!(f, "+ {} {}", name, version),
+            VersionChange::Removed { name, version } => write!(f, "- {} {}", name, version),
+            VersionChange::Updated { name, from, to } => {
+                write!(f, "  {} {} -> {}", name, from, to)
+            }
+        }
+    }
+}
+
+/// List how the locked versions moved from `old` to `new`.
+/// 列出锁定版本从 `old` 到 `new` 的变化。
+///
+/// Changes are ordered by package name.
+/// 变化按包名称排序。
+pub fn diff_versions<V: PartialEq + Clone>(
+    old: &BTreeMap<String, V>,
+    new: &BTreeMap<String, V>,
+) -> Vec<VersionChange<V>> {
+    let mut changes = Vec::new();
+    for (name, from) in old {
+        match new.get(name) {
+            Some(to) if to != from => changes.push(VersionChange::Updated {
+                name: name.clone(),
+                from: from.clone(),
+                to: to.clone(),
+            }),
+            Some(_) => {}
+            None => changes.push(VersionChange::Removed {
+                name: name.clone(),
+                version: from.clone(),
+            }),
+        }
+    }
+    for (name, version) in new {
+        if !old.contains_key(name) {
+            changes.push(VersionChange::Added {
+                name: name.clone(),
+                version: version.clone(),
+            });
+        }
+    }
+    changes.sort_by(|a, b| a.name().cmp(b.name()));
+    changes
+}
+
+/// Locked package versions, as written to a lock file.
+/// 锁定的包版本，即写入锁文件的内容。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackageLock {
+    /// Locked version of each package. / 每个包的锁定版本。
+    pub packages: BTreeMap<String, Version>,
+}
+
+impl PackageLock {
+    /// Lock the versions of a resolution.
+    /// 锁定解析结果中的版本。
+    pub fn from_resolution(resolution: &Resolution) -> Self {
+        Self {
+            packages: resolution.versions(),
+        }
+    }
+
+    /// List what would change by moving this lock to `resolution`.
+    /// 列出将此锁移动到 `resolution` 时的变化。
+    ///
+    /// Changes are ordered by package name.
+    /// 变化按包名称排序。
+    pub fn diff(&self, resolution: &Resolution) -> Vec<VersionChange> {
+        diff_versions(&self.packages, &resolution.versions())
+    }
+
+    /// Move this lock to `resolution`, returning what changed.
+    /// 将此锁移动到 `resolution`，并返回变化。
+    pub fn update(&mut self, resolution: &Resolution) -> Vec<VersionChange> {
+        let changes = self.diff(resolution);
+        if !changes.is_empty() {
+            self.packages = resolution.versions();
+        }
+        changes
+    }
+
+    /// Serialize to JSON.
+    /// 序列化为 JSON。
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// Deserialize from JSON.
+    /// 从 JSON 反序列化。
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+}
+
 /// Dependency resolution error.
 /// 依赖解析错误。
 #[derive(Debug, Clone)]
@@ -360,6 +510,8 @@ pub enum ResolveError {
     },
     /// Cyclic dependency detected. / 检测到循环依赖。
     CyclicDependency(Vec<String>),
+    /// A locked-only resolution would change the lock. / 仅锁定解析将改变锁。
+    LockOutOfDate(Vec<VersionChange>),
 }
 
 impl std::fmt::Display for ResolveError {
@@ -393,6 +545,14 @@ impl std::fmt::Display for ResolveError {
             ResolveError::CyclicDependency(cycle) => {
                 write!(f, "cyclic dependency detected: {}", cycle.join(" -> "))
             }
+            ResolveError::LockOutOfDate(changes) => {
+                let changes: Vec<String> = changes.iter().map(|c| c.to_string()).collect();
+                write!(
+                    f,
+                    "lock file is out of date and may not be changed: {}",
+                    changes.join(", ")
+                )
+            }
         }
     }
 }
@@ -403,13 +563,30 @@ impl std::error::Error for ResolveError {}
 /// 依赖解析器。
 pub struct Resolver<'a, R: PackageRegistry> {
     registry: &'a R,
+    lock: Option<&'a PackageLock>,
 }
 
 impl<'a, R: PackageRegistry> Resolver<'a, R> {
     /// Create a new resolver with the given registry.
     /// 使用给定的注册表创建新的解析器。
     pub fn new(registry: &'a R) -> Self {
-        Self { registry }
+        Self {
+            registry,
+            lock: None,
+        }
+    }
+
+    /// Resolve only to the versions pinned by `lock`.
+    /// 仅解析到 `lock` 固定的版本。
+    ///
+    /// Locked versions are preferred over newer ones, and a resolution that
+    /// would add, remove or move any locked package fails with
+    /// [`ResolveError::LockOutOfDate`] instead of changing the lock.
+    /// 锁定版本优先于较新版本；若解析会添加、删除或移动任何锁定的包，则以
+    /// [`ResolveError::LockOutOfDate`] 失败而不是改变锁。
+    pub fn locked(mut self, lock: &'a PackageLock) -> Self {
+        self.lock = Some(lock);
+        self
     }
 
     /// Resolve dependencies for a root package.
@@ -471,6 +648,12 @@ impl<'a, R: PackageRegistry> Resolver<'a, R> {
                 .collect();
             matching.sort();
             matching.reverse();
+            if let Some(pinned) = self.lock.and_then(|lock| lock.packages.get(&name))
+                && let Some(pos) = matching.iter().position(|v| *v == pinned)
+            {
+                let pinned = matching.remove(pos);
+                matching.insert(0, pinned);
+            }
 
             let version =
                 matching
@@ -507,11 +690,18 @@ impl<'a, R: PackageRegistry> Resolver<'a, R> {
         // 计算构建顺序（拓扑排序）
         let build_order = self.topological_sort(&resolved, &graph)?;
 
-        Ok(Resolution {
+        let resolution = Resolution {
             packages: resolved,
             graph,
             build_order,
-        })
+        };
+        if let Some(lock) = self.lock {
+            let changes = lock.diff(&resolution);
+            if !changes.is_empty() {
+                return Err(ResolveError::LockOutOfDate(changes));
+            }
+        }
+        Ok(resolution)
     }
 
     /// Topological sort for build order.
@@ -45,6 +45,7 @@ pub enum ErrorCode {
     UnreachablePattern,
     PrivateAccess,
     CyclicDependency,
+    ConflictingImport,
 
     // ===== Eval errors (E0300 - E0399) 求值错误 =====
     DivisionByZero,
@@ -53,6 +54,50 @@ pub enum ErrorCode {
 }
 
 impl ErrorCode {
+    /// Every error code, in code order.
+    /// 所有错误代码，按代码顺序排列。
+    pub const ALL: &'static [ErrorCode] = &[
+        ErrorCode::UnexpectedCharacter,
+        ErrorCode::UnterminatedString,
+        ErrorCode::UnterminatedComment,
+        ErrorCode::InvalidEscape,
+        ErrorCode::InvalidNumber,
+        ErrorCode::UnexpectedToken,
+        ErrorCode::ExpectedExpression,
+        ErrorCode::ExpectedPattern,
+        ErrorCode::ExpectedType,
+        ErrorCode::UnclosedDelimiter,
+        ErrorCode::MissingSemicolon,
+        ErrorCode::TypeMismatch,
+        ErrorCode::UnboundVariable,
+        ErrorCode::UnboundType,
+        ErrorCode::InfiniteType,
+        ErrorCode::NotAFunction,
+        ErrorCode::WrongArity,
+        ErrorCode::MissingField,
+        ErrorCode::UnknownField,
+        ErrorCode::TraitNotImplemented,
+        ErrorCode::MissingMethod,
+        ErrorCode::MissingAssocType,
+        ErrorCode::IfBranchMismatch,
+        ErrorCode::MatchArmMismatch,
+        ErrorCode::ReturnTypeMismatch,
+        ErrorCode::ArgumentTypeMismatch,
+        ErrorCode::BinaryOpTypeMismatch,
+        ErrorCode::UnaryOpTypeMismatch,
+        ErrorCode::CannotInferType,
+        ErrorCode::RecursiveType,
+        ErrorCode::AmbiguousType,
+        ErrorCode::NonExhaustiveMatch,
+        ErrorCode::UnreachablePattern,
+        ErrorCode::PrivateAccess,
+        ErrorCode::CyclicDependency,
+        ErrorCode::ConflictingImport,
+        ErrorCode::DivisionByZero,
+        ErrorCode::AssertionFailed,
+        ErrorCode::PatternMatchFailed,
+    ];
+
     pub fn as_str(&self) -> &'static str {
         match self {
             // Lexer
@@ -95,6 +140,7 @@ impl ErrorCode {
             ErrorCode::UnreachablePattern => "E0221",
             ErrorCode::PrivateAccess => "E0222",
             ErrorCode::CyclicDependency => "E0223",
+            ErrorCode::ConflictingImport => "E0224",
 
             // Eval
             ErrorCode::DivisionByZero => "E0300",
@@ -149,6 +195,7 @@ impl ErrorCode {
             ErrorCode::UnreachablePattern => "unreachable pattern in match",
             ErrorCode::PrivateAccess => "cannot access private binding",
             ErrorCode::CyclicDependency => "cyclic dependency detected",
+            ErrorCode::ConflictingImport => "name is imported from more than one source",
 
             // Eval
             ErrorCode::DivisionByZero => "division by zero",
@@ -191,6 +238,9 @@ impl ErrorCode {
             ErrorCode::CyclicDependency => {
                 Some("break the cycle by restructuring the dependencies")
             }
+            ErrorCode::ConflictingImport => {
+                Some("import one of the modules under an alias with `as`")
+            }
             _ => None,
         }
     }
@@ -0,0 +1,53 @@
+//! When to color rendered diagnostics.
+//! 何时为渲染的诊断着色。
+
+use std::io::IsTerminal;
+use std::str::FromStr;
+
+/// Whether diagnostics are rendered with colors.
+/// 诊断是否以彩色渲染。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorChoice {
+    /// Color when stderr is a terminal and `NO_COLOR` is not set.
+    /// 当 stderr 是终端且未设置 `NO_COLOR` 时着色。
+    #[default]
+    Auto,
+    /// Always color. / 总是着色。
+    Always,
+    /// Never color. / 从不着色。
+    Never,
+}
+
+impl ColorChoice {
+    /// Resolve the choice to whether colors are used.
+    /// 将选择解析为是否使用颜色。
+    pub fn enabled(self) -> bool {
+        match self {
+            Self::Always => true,
+            Self::Never => false,
+            Self::Auto => !no_color() && std::io::stderr().is_terminal(),
+        }
+    }
+}
+
+impl FromStr for ColorChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "always" => Ok(Self::Always),
+            "never" => Ok(Self::Never),
+            _ => Err(format!(
+                "invalid color choice '{}', expected auto, always or never",
+                s
+            )),
+        }
+    }
+}
+
+/// Whether `NO_COLOR` is set to a non-empty value (see <https://no-color.org>).
+/// `NO_COLOR` 是否被设置为非空值（见 <https://no-color.org>）。
+fn no_color() -> bool {
+    std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty())
+}
@@ -1,7 +1,7 @@
 //! Diagnostic types and builders.
 //! 诊断类型和构建器。
 
-use crate::ErrorCode;
+use crate::{ErrorCode, Suggestion};
 use neve_common::Span;
 
 /// Severity level of a diagnostic.
@@ -71,6 +71,8 @@ pub struct Diagnostic {
     pub notes: Vec<String>,
     /// Help suggestion. / 帮助建议。
     pub help: Option<String>,
+    /// Mechanical fixes. / 机械式修复。
+    pub suggestions: Vec<Suggestion>,
 }
 
 impl Diagnostic {
@@ -84,6 +86,7 @@ impl Diagnostic {
             labels: vec![],
             notes: vec![],
             help: None,
+            suggestions: vec![],
         }
     }
 
@@ -97,6 +100,7 @@ impl Diagnostic {
             labels: vec![],
             notes: vec![],
             help: None,
+            suggestions: vec![],
         }
     }
 
@@ -119,4 +123,22 @@ impl Diagnostic {
         self.help = Some(help.into());
         self
     }
+
+    pub fn with_suggestion(mut self, suggestion: Suggestion) -> Self {
+        self.suggestions.push(suggestion);
+        self
+    }
+
+    /// Whether this diagnostic should fail the command that produced it.
+    /// 此诊断是否应使产生它的命令失败。
+    ///
+    /// Errors are always fatal; warnings are fatal only with `deny_warnings`.
+    /// 错误总是致命的；警告仅在 `deny_warnings` 时致命。
+    pub fn is_fatal(&self, deny_warnings: bool) -> bool {
+        match self.severity {
+            Severity::Error => true,
+            Severity::Warning => deny_warnings,
+            Severity::Note => false,
+        }
+    }
 }
@@ -0,0 +1,81 @@
+//! Machine-readable diagnostics.
+//! 机器可读的诊断。
+//!
+//! Spans are kept as byte offsets into the source; consumers map them to
+//! lines and columns themselves.
+//! 范围保留为源代码中的字节偏移；由使用者自行映射为行和列。
+
+use crate::{Diagnostic, DiagnosticKind, Severity};
+use neve_common::Span;
+use serde_json::{Value, json};
+
+/// Serialize a diagnostic as a JSON object.
+/// 将诊断序列化为 JSON 对象。
+///
+/// ```json
+/// {
+///   "severity": "error",
+///   "kind": "type",
+///   "code": "E0200",
+///   "message": "type mismatch",
+///   "span": { "start": 8, "end": 12 },
+///   "labels": [{ "span": { "start": 8, "end": 12 }, "message": "expected Int" }],
+///   "notes": [],
+///   "help": null,
+///   "suggestions": [{ "span": { "start": 12, "end": 12 }, "replacement": ";", "message": "insert a semicolon" }]
+/// }
+/// ```
+pub fn emit_json(diagnostic: &Diagnostic) -> Value {
+    let severity = match diagnostic.severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Note => "note",
+    };
+    let kind = match diagnostic.kind {
+        DiagnosticKind::Lexer => "lexer",
+        DiagnosticKind::Parser => "parser",
+        DiagnosticKind::Type => "type",
+        DiagnosticKind::Eval => "eval",
+        DiagnosticKind::Module => "module",
+    };
+    let labels: Vec<Value> = diagnostic
+        .labels
+        .iter()
+        .map(|label| json!({ "span": span_json(label.span), "message": label.message }))
+        .collect();
+    let suggestions: Vec<Value> = diagnostic
+        .suggestions
+        .iter()
+        .map(|s| {
+            json!({
+                "span": span_json(s.span),
+                "replacement": s.replacement,
+                "message": s.message,
+            })
+        })
+        .collect();
+
+    json!({
+        "severity": severity,
+        "kind": kind,
+        "code": diagnostic.code.map(|code| code.as_str()),
+        "message": diagnostic.message,
+        "span": span_json(diagnostic.span),
+        "labels": labels,
+        "notes": diagnostic.notes,
+        "help": diagnostic.help,
+        "suggestions": suggestions,
+    })
+}
+
+/// Serialize diagnostics as a JSON array.
+/// 将多个诊断序列化为 JSON 数组。
+pub fn emit_json_all<'a>(diagnostics: impl IntoIterator<Item = &'a Diagnostic>) -> Value {
+    Value::Array(diagnostics.into_iter().map(emit_json).collect())
+}
+
+/// A span as its start and end byte offsets.
+/// 以起止字节偏移表示的范围。
+fn span_json(span: Span) -> Value {
+    json!({ "start": span.start.0, "end": span.end.0 })
+}
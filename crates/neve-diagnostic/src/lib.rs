@@ -1,20 +1,37 @@
 //! Diagnostic and error reporting for Neve.
 //! Neve 的诊断和错误报告。
 //!
-//! This crate provides beautiful error messages using ariadne.
-//! 本 crate 使用 ariadne 库提供美观的错误信息。
+//! This crate provides beautiful error messages using ariadne, and JSON and
+//! SARIF output for tooling.
+//! 本 crate 使用 ariadne 库提供美观的错误信息，并为工具提供 JSON 和 SARIF 输出。
 
 mod codes;
+mod color;
 mod diagnostic;
+mod json;
+mod sarif;
+mod suggestion;
 
 pub use codes::ErrorCode;
+pub use color::ColorChoice;
 pub use diagnostic::{Diagnostic, DiagnosticKind, Label, Severity};
+pub use json::{emit_json, emit_json_all};
+pub use sarif::to_sarif;
+pub use suggestion::{Suggestion, apply_suggestions};
 
-use ariadne::{ColorGenerator, Label as AriadneLabel, Report, ReportKind, Source};
+use ariadne::{ColorGenerator, Config, Label as AriadneLabel, Report, ReportKind, Source};
 
 /// Render a diagnostic to stderr.
 /// 将诊断信息渲染到标准错误输出。
-pub fn emit(source: &str, filename: &str, diagnostic: &Diagnostic) {
+pub fn emit(source: &str, filename: &str, diagnostic: &Diagnostic, color: ColorChoice) {
+    eprint!("{}", render(source, filename, diagnostic, color));
+}
+
+/// Render a diagnostic to a string, with escape codes only if `color` is
+/// enabled.
+/// 将诊断信息渲染为字符串，仅在启用 `color` 时包含转义码。
+pub fn render(source: &str, filename: &str, diagnostic: &Diagnostic, color: ColorChoice) -> String {
+    let color = color.enabled();
     let kind = match diagnostic.severity {
         Severity::Error => ReportKind::Error,
         Severity::Warning => ReportKind::Warning,
@@ -23,6 +40,7 @@ pub fn emit(source: &str, filename: &str, diagnostic: &Diagnostic) {
 
     let mut colors = ColorGenerator::new();
     let mut report = Report::build(kind, filename, diagnostic.span.start.0 as usize)
+        .with_config(Config::default().with_color(color))
         .with_message(&diagnostic.message);
 
     if let Some(code) = &diagnostic.code {
@@ -30,23 +48,34 @@ pub fn emit(source: &str, filename: &str, diagnostic: &Diagnostic) {
     }
 
     for label in &diagnostic.labels {
-        let color = colors.next();
-        let ariadne_label = AriadneLabel::new((filename, label.span.range()))
-            .with_message(&label.message)
-            .with_color(color);
+        let mut ariadne_label =
+            AriadneLabel::new((filename, label.span.range())).with_message(&label.message);
+        if color {
+            ariadne_label = ariadne_label.with_color(colors.next());
+        }
         report = report.with_label(ariadne_label);
     }
 
-    for note in &diagnostic.notes {
-        report = report.with_note(note);
+    // A report holds one note and one help, so multiple ones share a line each
+    // 一个报告只有一条注释和一条帮助，因此多条时各占一行
+    if !diagnostic.notes.is_empty() {
+        report = report.with_note(diagnostic.notes.join("\n"));
     }
 
-    if let Some(help) = &diagnostic.help {
-        report = report.with_help(help);
+    let help: Vec<String> = diagnostic
+        .help
+        .iter()
+        .cloned()
+        .chain(diagnostic.suggestions.iter().map(|s| s.describe(source)))
+        .collect();
+    if !help.is_empty() {
+        report = report.with_help(help.join("\n"));
     }
 
+    let mut out = Vec::new();
     report
         .finish()
-        .eprint((filename, Source::from(source)))
-        .expect("failed to print diagnostic to stderr");
+        .write((filename, Source::from(source)), &mut out)
+        .expect("writing to a Vec cannot fail");
+    String::from_utf8_lossy(&out).into_owned()
 }
@@ -0,0 +1,149 @@
+//! SARIF 2.1.0 output for code-scanning tools.
+//! 用于代码扫描工具的 SARIF 2.1.0 输出。
+//!
+//! Every [`ErrorCode`] is a rule whose ID is its code string (`E0200`), so
+//! findings keep the same rule across versions. Columns count Unicode code
+//! points and are 1-based, as declared in the run's `columnKind`.
+//! 每个 [`ErrorCode`] 都是一条规则，其 ID 为代码字符串（`E0200`），因此各版本间的发现保持
+//! 相同的规则。列按 Unicode 码点计数且从 1 开始，与运行的 `columnKind` 声明一致。
+
+use crate::{Diagnostic, ErrorCode, Severity};
+use neve_common::Span;
+use serde_json::{Value, json};
+
+/// The SARIF schema the output conforms to.
+/// 输出所遵循的 SARIF 模式。
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Build a SARIF log with one run holding the diagnostics of `file`.
+/// 构建包含一次运行的 SARIF 日志，其中包含 `file` 的诊断。
+///
+/// Diagnostics without an error code are reported without a `ruleId`.
+/// 没有错误代码的诊断不带 `ruleId` 报告。
+pub fn to_sarif(file: &str, source: &str, diags: &[Diagnostic]) -> Value {
+    let rules: Vec<Value> = ErrorCode::ALL
+        .iter()
+        .map(|code| {
+            let mut rule = json!({
+                "id": code.as_str(),
+                "name": format!("{:?}", code),
+                "shortDescription": { "text": code.description() },
+            });
+            if let Some(help) = code.suggestion() {
+                rule["help"] = json!({ "text": help });
+            }
+            rule
+        })
+        .collect();
+
+    let results: Vec<Value> = diags
+        .iter()
+        .map(|diag| sarif_result(file, source, diag))
+        .collect();
+
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "neve",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                }
+            },
+            "columnKind": "unicodeCodePoints",
+            "results": results,
+        }]
+    })
+}
+
+/// A diagnostic as a SARIF result.
+/// 作为 SARIF 结果的诊断。
+fn sarif_result(file: &str, source: &str, diag: &Diagnostic) -> Value {
+    let level = match diag.severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Note => "note",
+    };
+    let location = |span: Span| {
+        json!({
+            "physicalLocation": {
+                "artifactLocation": { "uri": file },
+                "region": region(source, span),
+            }
+        })
+    };
+
+    let mut result = json!({
+        "level": level,
+        "message": { "text": diag.message },
+        "locations": [location(diag.span)],
+    });
+    if let Some(code) = diag.code {
+        result["ruleId"] = json!(code.as_str());
+        if let Some(index) = ErrorCode::ALL.iter().position(|c| *c == code) {
+            result["ruleIndex"] = json!(index);
+        }
+    }
+    if !diag.labels.is_empty() {
+        result["relatedLocations"] = diag
+            .labels
+            .iter()
+            .enumerate()
+            .map(|(id, label)| {
+                let mut related = location(label.span);
+                related["id"] = json!(id);
+                related["message"] = json!({ "text": label.message });
+                related
+            })
+            .collect();
+    }
+    if !diag.suggestions.is_empty() {
+        result["fixes"] = diag
+            .suggestions
+            .iter()
+            .map(|suggestion| {
+                json!({
+                    "description": { "text": suggestion.message },
+                    "artifactChanges": [{
+                        "artifactLocation": { "uri": file },
+                        "replacements": [{
+                            "deletedRegion": region(source, suggestion.span),
+                            "insertedContent": { "text": suggestion.replacement },
+                        }]
+                    }]
+                })
+            })
+            .collect();
+    }
+    result
+}
+
+/// The SARIF region of a span: 1-based lines and code point columns, the
+/// end column just past the last character.
+/// 范围对应的 SARIF 区域：从 1 开始的行和码点列，结束列位于最后一个字符之后。
+fn region(source: &str, span: Span) -> Value {
+    let (start_line, start_column) = line_column(source, span.start.0 as usize);
+    let (end_line, end_column) = line_column(source, span.end.0 as usize);
+    json!({
+        "startLine": start_line,
+        "startColumn": start_column,
+        "endLine": end_line,
+        "endColumn": end_column,
+    })
+}
+
+/// The 1-based line and column of a byte offset, clamped to the source.
+/// 字节偏移对应的从 1 开始的行和列，超出源码时截断到末尾。
+fn line_column(source: &str, offset: usize) -> (usize, usize) {
+    let mut offset = offset.min(source.len());
+    while !source.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let before = &source[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let line = before.matches('\n').count() + 1;
+    let column = before[line_start..].chars().count() + 1;
+    (line, column)
+}
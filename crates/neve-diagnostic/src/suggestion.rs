@@ -0,0 +1,80 @@
+//! Fix-it suggestions attached to diagnostics.
+//! 附加在诊断上的修复建议。
+
+use crate::Diagnostic;
+use neve_common::Span;
+
+/// A mechanical fix: replace the text of a span.
+/// 机械式修复：替换某个范围的文本。
+///
+/// An empty span inserts the replacement; an empty replacement removes the
+/// span.
+/// 空范围表示插入替换文本；空替换文本表示删除该范围。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    /// The span to replace. / 要替换的范围。
+    pub span: Span,
+    /// The replacement text. / 替换文本。
+    pub replacement: String,
+    /// What the fix does. / 修复的作用。
+    pub message: String,
+}
+
+impl Suggestion {
+    pub fn new(span: Span, replacement: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            span,
+            replacement: replacement.into(),
+            message: message.into(),
+        }
+    }
+
+    /// Describe the edit, quoting the text it replaces from `source`.
+    /// 描述该编辑，并引用 `source` 中被替换的文本。
+    pub fn describe(&self, source: &str) -> String {
+        let original = source.get(self.span.range()).unwrap_or("");
+        if original.is_empty() {
+            format!("{}: insert `{}`", self.message, self.replacement)
+        } else if self.replacement.is_empty() {
+            format!("{}: remove `{}`", self.message, original)
+        } else {
+            format!(
+                "{}: replace `{}` with `{}`",
+                self.message, original, self.replacement
+            )
+        }
+    }
+}
+
+/// Apply the suggestions of `diagnostics` to `source`.
+/// 将 `diagnostics` 中的建议应用到 `source`。
+///
+/// Suggestions are applied in source order. One overlapping an earlier
+/// suggestion, or starting at the same position, is skipped, as are
+/// suggestions whose span lies outside the source.
+/// 建议按源码顺序应用。与之前的建议重叠或起始位置相同的建议会被跳过，
+/// 范围超出源码的建议也会被跳过。
+pub fn apply_suggestions(source: &str, diagnostics: &[Diagnostic]) -> String {
+    let mut suggestions: Vec<&Suggestion> = diagnostics
+        .iter()
+        .flat_map(|d| &d.suggestions)
+        .filter(|s| source.get(s.span.range()).is_some())
+        .collect();
+    suggestions.sort_by_key(|s| (s.span.start, s.span.end));
+
+    let mut result = String::with_capacity(source.len());
+    let mut copied = 0;
+    let mut last: Option<Span> = None;
+    for suggestion in suggestions {
+        let span = suggestion.span;
+        if last.is_some_and(|last| span.start < last.end || span.start == last.start) {
+            continue;
+        }
+        result.push_str(&source[copied..span.start.0 as usize]);
+        result.push_str(&suggestion.replacement);
+        copied = span.end.0 as usize;
+        last = Some(span);
+    }
+    result.push_str(&source[copied..]);
+    result
+}
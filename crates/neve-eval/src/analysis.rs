@@ -0,0 +1,427 @@
+//! Match expression analysis reports.
+//! 匹配表达式分析报告。
+//!
+//! Runs the [`pattern`](crate::pattern) analysis over every `match` in a file
+//! and reports, per match, whether a catch-all arm makes it exhaustive, which
+//! arms can never be reached, which arms are shadowed by less specific ones,
+//! which literal arms wait on an earlier guard, and which fast paths the
+//! evaluator will take.
+//! 对文件中的每个 `match` 运行 [`pattern`](crate::pattern) 分析，并为每个匹配报告：
+//! 是否有全捕获分支使其穷尽、哪些分支永远无法到达、哪些分支被不太具体的分支遮蔽、
+//! 哪些字面量分支需等待较早的守卫，以及求值器将采用哪些快速路径。
+
+use crate::pattern::{
+    DECISION_TREE_MIN_ARMS, LiteralValue, MatchHints, PatternClass, Specificity, analyze_match,
+    classify_pattern, compile_match, extract_literal, is_irrefutable, pattern_specificity,
+};
+use neve_syntax::{
+    Expr, ExprKind, ItemKind, MatchArm, Pattern, PatternKind, SourceFile, StmtKind, StringPart,
+};
+use std::fmt;
+
+/// Analysis of one match arm.
+/// 单个匹配分支的分析。
+#[derive(Debug, Clone)]
+pub struct ArmReport {
+    /// Source text of the pattern. / 模式的源代码文本。
+    pub pattern: String,
+    /// Fast-path class of the pattern. / 模式的快速路径分类。
+    pub class: PatternClass,
+    /// Specificity of the pattern. / 模式的特异性。
+    pub specificity: Specificity,
+    /// Whether the arm has a guard. / 分支是否有守卫。
+    pub guarded: bool,
+    /// Earlier arm (0-based) that makes this one unreachable.
+    /// 使此分支无法到达的较早分支（从 0 开始）。
+    pub unreachable_after: Option<usize>,
+}
+
+/// Analysis of one match expression.
+/// 单个匹配表达式的分析。
+#[derive(Debug, Clone)]
+pub struct MatchReport {
+    /// 1-based line of the `match` keyword. / `match` 关键字所在行（从 1 开始）。
+    pub line: usize,
+    /// Per-arm analysis. / 每个分支的分析。
+    pub arms: Vec<ArmReport>,
+    /// Hints from [`analyze_match`]. / 来自 [`analyze_match`] 的提示。
+    pub hints: MatchHints,
+    /// First unguarded irrefutable arm (0-based), which makes the match
+    /// exhaustive. / 第一个无守卫的不可反驳分支（从 0 开始），它使匹配穷尽。
+    pub catch_all: Option<usize>,
+    /// Pairs `(arm, earlier)` where `arm` is more specific than the earlier
+    /// arm that comes before it, and could be moved ahead of it.
+    /// `(arm, earlier)` 对，其中 `arm` 比位于其前的较早分支更具体，可以移到它前面。
+    pub reorder: Vec<(usize, usize)>,
+    /// Pairs `(arm, guarded)` where the literal `arm` comes after a guarded
+    /// arm whose pattern also accepts its value, so the guard runs first.
+    /// `(arm, guarded)` 对，其中字面量分支 `arm` 位于一个模式也接受其值的
+    /// 有守卫分支之后，因此守卫会先被求值。
+    pub guard_before_literal: Vec<(usize, usize)>,
+    /// Number of switches if the match compiles to a decision tree.
+    /// 如果匹配编译为决策树，则为 switch 的数量。
+    pub decision_tree: Option<usize>,
+}
+
+impl MatchReport {
+    /// Whether any arm can never be reached.
+    /// 是否有分支永远无法到达。
+    pub fn has_unreachable_arms(&self) -> bool {
+        self.arms.iter().any(|a| a.unreachable_after.is_some())
+    }
+}
+
+impl fmt::Display for MatchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "match at line {}: {}, ",
+            self.line,
+            plural(self.arms.len(), "arm")
+        )?;
+        match self.catch_all {
+            Some(i) => writeln!(f, "exhaustive (catch-all arm {})", i + 1)?,
+            None => writeln!(f, "no catch-all arm")?,
+        }
+
+        for (i, arm) in self.arms.iter().enumerate() {
+            write!(
+                f,
+                "  arm {}: {}  [{:?}, specificity {}{}]",
+                i + 1,
+                arm.pattern,
+                arm.class,
+                arm.specificity.score(),
+                if arm.guarded { ", guarded" } else { "" }
+            )?;
+            if let Some(earlier) = arm.unreachable_after {
+                write!(f, " unreachable after arm {}", earlier + 1)?;
+            }
+            writeln!(f)?;
+        }
+
+        if let Some(catch_all) = self.catch_all {
+            let shadowed = self.arms.len() - catch_all - 1;
+            if shadowed > 0 {
+                writeln!(
+                    f,
+                    "  hint: catch-all arm {} shadows the {} after it; consider moving it last",
+                    catch_all + 1,
+                    plural(shadowed, "arm")
+                )?;
+            }
+        }
+
+        for (arm, earlier) in &self.reorder {
+            writeln!(
+                f,
+                "  hint: arm {} is more specific than arm {}; consider moving it earlier",
+                arm + 1,
+                earlier + 1
+            )?;
+        }
+
+        for (arm, guarded) in &self.guard_before_literal {
+            writeln!(
+                f,
+                "  hint: literal arm {} comes after guarded arm {}; if the guard cannot hold for it, moving it earlier skips the guard",
+                arm + 1,
+                guarded + 1
+            )?;
+        }
+
+        if let Some(switches) = self.decision_tree {
+            writeln!(
+                f,
+                "  fast path: decision tree with {}",
+                plural(switches, "switch")
+            )?;
+        } else if self.hints.literal_count > 0 {
+            writeln!(
+                f,
+                "  fast path: direct comparison for {}",
+                plural(self.hints.literal_count, "literal arm")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Analyze every match expression in a parsed file, in source order.
+/// 按源代码顺序分析已解析文件中的每个匹配表达式。
+pub fn analyze_file(file: &SourceFile, source: &str) -> Vec<MatchReport> {
+    let mut collector = Collector {
+        source,
+        reports: Vec::new(),
+    };
+    for item in &file.items {
+        match &item.kind {
+            ItemKind::Let(def) => collector.expr(&def.value),
+            ItemKind::Fn(def) => collector.expr(&def.body),
+            ItemKind::Trait(def) => {
+                for item in &def.items {
+                    if let Some(default) = &item.default {
+                        collector.expr(default);
+                    }
+                }
+            }
+            ItemKind::Impl(def) => {
+                for item in &def.items {
+                    collector.expr(&item.body);
+                }
+            }
+            _ => {}
+        }
+    }
+    collector.reports
+}
+
+/// Analyze the arms of one match expression.
+/// 分析单个匹配表达式的分支。
+pub fn analyze_arms(arms: &[MatchArm], source: &str, line: usize) -> MatchReport {
+    let patterns: Vec<_> = arms.iter().map(|a| &a.pattern).collect();
+    let hints = analyze_match(&patterns);
+
+    let mut reports: Vec<ArmReport> = Vec::with_capacity(arms.len());
+    let mut catch_all = None;
+    let mut seen_literals: Vec<(LiteralValue, usize)> = Vec::new();
+    for (i, arm) in arms.iter().enumerate() {
+        let guarded = arm.guard.is_some();
+        let literal = extract_literal(&arm.pattern);
+
+        let unreachable_after = catch_all.or_else(|| {
+            let literal = literal.as_ref()?;
+            seen_literals
+                .iter()
+                .find(|(seen, _)| seen == literal)
+                .map(|(_, j)| *j)
+        });
+
+        if !guarded {
+            if catch_all.is_none() && is_irrefutable(&arm.pattern) {
+                catch_all = Some(i);
+            }
+            if let Some(literal) = literal {
+                seen_literals.push((literal, i));
+            }
+        }
+
+        reports.push(ArmReport {
+            pattern: slice(source, arm.pattern.span).to_string(),
+            class: classify_pattern(&arm.pattern),
+            specificity: pattern_specificity(&arm.pattern),
+            guarded,
+            unreachable_after,
+        });
+    }
+
+    // A reachable arm placed after a less specific unguarded arm of the same shape
+    // 放在相同形状但不太具体的无守卫分支之后的可达分支
+    let heads: Vec<_> = arms.iter().map(|a| pattern_head(&a.pattern)).collect();
+    let mut reorder = Vec::new();
+    for (i, arm) in reports.iter().enumerate() {
+        if arm.unreachable_after.is_some() || heads[i].is_none() {
+            continue;
+        }
+        let earlier = reports[..i].iter().enumerate().position(|(j, prev)| {
+            !prev.guarded && heads[j] == heads[i] && prev.specificity < arm.specificity
+        });
+        if let Some(earlier) = earlier {
+            reorder.push((i, earlier));
+        }
+    }
+
+    // A literal arm that a guarded arm before it would also accept
+    // 其前的有守卫分支也会接受的字面量分支
+    let mut guard_before_literal = Vec::new();
+    for (i, arm) in arms.iter().enumerate() {
+        let Some(literal) = extract_literal(&arm.pattern) else {
+            continue;
+        };
+        if arm.guard.is_some() || reports[i].unreachable_after.is_some() {
+            continue;
+        }
+        let guarded = arms[..i].iter().position(|prev| {
+            prev.guard.is_some()
+                && (is_irrefutable(&prev.pattern)
+                    || extract_literal(&prev.pattern).as_ref() == Some(&literal))
+        });
+        if let Some(guarded) = guarded {
+            guard_before_literal.push((i, guarded));
+        }
+    }
+
+    // Same rule as the evaluator for using a decision tree
+    // 与求值器使用决策树的规则相同
+    let decision_tree = if arms.len() >= DECISION_TREE_MIN_ARMS {
+        let tree = compile_match(arms);
+        tree.is_beneficial().then(|| tree.switch_count())
+    } else {
+        None
+    };
+
+    MatchReport {
+        line,
+        arms: reports,
+        hints,
+        catch_all,
+        reorder,
+        guard_before_literal,
+        decision_tree,
+    }
+}
+
+/// Format a count with a noun, adding an English plural suffix when needed.
+/// 将数量与名词一起格式化，必要时添加英文复数后缀。
+fn plural(count: usize, noun: &str) -> String {
+    match (count, noun.ends_with("ch")) {
+        (1, _) => format!("{} {}", count, noun),
+        (_, true) => format!("{} {}es", count, noun),
+        (_, false) => format!("{} {}s", count, noun),
+    }
+}
+
+/// Describe the outermost shape of a pattern, if it has one.
+/// 描述模式的最外层形状（如果有）。
+///
+/// Arms with the same head can shadow each other.
+/// 具有相同头部的分支可能相互遮蔽。
+fn pattern_head(pattern: &Pattern) -> Option<String> {
+    match &pattern.kind {
+        PatternKind::Constructor { path, .. } => Some(
+            path.iter()
+                .map(|i| i.name.as_str())
+                .collect::<Vec<_>>()
+                .join("."),
+        ),
+        PatternKind::Tuple(items) => Some(format!("({})", items.len())),
+        PatternKind::List(_) | PatternKind::ListRest { .. } => Some("[]".to_string()),
+        PatternKind::Record { .. } => Some("#{}".to_string()),
+        PatternKind::Binding { pattern, .. } => pattern_head(pattern),
+        _ => None,
+    }
+}
+
+/// Get the source text of a span.
+/// 获取跨度的源代码文本。
+fn slice(source: &str, span: neve_common::Span) -> &str {
+    let start = (span.start.0 as usize).min(source.len());
+    let end = (span.end.0 as usize).clamp(start, source.len());
+    source.get(start..end).unwrap_or("")
+}
+
+/// Walks expressions and collects a report per match.
+/// 遍历表达式并为每个匹配收集报告。
+struct Collector<'a> {
+    source: &'a str,
+    reports: Vec<MatchReport>,
+}
+
+impl Collector<'_> {
+    fn exprs<'e>(&mut self, exprs: impl IntoIterator<Item = &'e Expr>) {
+        for expr in exprs {
+            self.expr(expr);
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::Int(_)
+            | ExprKind::Float(_)
+            | ExprKind::String(_)
+            | ExprKind::Char(_)
+            | ExprKind::Bool(_)
+            | ExprKind::Unit
+            | ExprKind::Var(_)
+            | ExprKind::Path(_)
+            | ExprKind::PathLit(_) => {}
+            ExprKind::Interpolated(parts) => {
+                for part in parts {
+                    if let StringPart::Expr(e) = part {
+                        self.expr(e);
+                    }
+                }
+            }
+            ExprKind::Record(fields) => self.exprs(fields.iter().filter_map(|f| f.value.as_ref())),
+            ExprKind::RecordUpdate { base, fields } => {
+                self.expr(base);
+                self.exprs(fields.iter().filter_map(|f| f.value.as_ref()));
+            }
+            ExprKind::List(items) | ExprKind::Tuple(items) => self.exprs(items),
+            ExprKind::ListComp { body, generators } => {
+                for generator in generators {
+                    self.expr(&generator.iter);
+                    if let Some(condition) = &generator.condition {
+                        self.expr(condition);
+                    }
+                }
+                self.expr(body);
+            }
+            ExprKind::Lambda { body, .. } => self.expr(body),
+            ExprKind::Call { func, args } => {
+                self.expr(func);
+                self.exprs(args);
+            }
+            ExprKind::MethodCall { receiver, args, .. } => {
+                self.expr(receiver);
+                self.exprs(args);
+            }
+            ExprKind::Field { base, .. }
+            | ExprKind::TupleIndex { base, .. }
+            | ExprKind::SafeField { base, .. } => self.expr(base),
+            ExprKind::Index { base, index } => {
+                self.expr(base);
+                self.expr(index);
+            }
+            ExprKind::Binary { left, right, .. } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            ExprKind::Unary { operand, .. } => self.expr(operand),
+            ExprKind::Try(inner) | ExprKind::Lazy(inner) => self.expr(inner),
+            ExprKind::Coalesce { value, default } => {
+                self.expr(value);
+                self.expr(default);
+            }
+            ExprKind::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.expr(condition);
+                self.expr(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.expr(else_branch);
+                }
+            }
+            ExprKind::Match { scrutinee, arms } => {
+                let start = (expr.span.start.0 as usize).min(self.source.len());
+                let line = self.source[..start].matches('\n').count() + 1;
+                self.reports.push(analyze_arms(arms, self.source, line));
+
+                self.expr(scrutinee);
+                for arm in arms.iter() {
+                    if let Some(guard) = &arm.guard {
+                        self.expr(guard);
+                    }
+                    self.expr(&arm.body);
+                }
+            }
+            ExprKind::Block { stmts, expr } => {
+                for stmt in stmts {
+                    match &stmt.kind {
+                        StmtKind::Let { value, .. } => self.expr(value),
+                        StmtKind::Expr(e) => self.expr(e),
+                    }
+                }
+                if let Some(expr) = expr {
+                    self.expr(expr);
+                }
+            }
+            ExprKind::Let { value, body, .. } => {
+                self.expr(value);
+                self.expr(body);
+            }
+        }
+    }
+}
@@ -15,13 +15,19 @@
 //! - List comprehensions / 列表推导
 
 use crate::EvalError;
-use crate::builtin::builtins;
-use crate::value::{Thunk, ThunkState, Value};
-use neve_hir::{ModuleLoader, ModulePath};
+use crate::builtin::{BuiltinSet, builtins};
+use crate::eval::with_suggestion;
+use crate::pattern::{DECISION_TREE_MIN_ARMS, DecisionTree, compile_match};
+use crate::value::{Constructor, PartialBuiltin, Thunk, ThunkState, Value, fn_signature};
+use neve_common::{Span, find_similar_name};
+use neve_diagnostic::{Diagnostic, DiagnosticKind, ErrorCode, Label};
+use neve_hir::{ModuleLoader, ModulePath, ResolutionTrace};
 use neve_syntax::*;
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
+use std::sync::Arc;
 
 // Re-import StringPart from syntax since we use it here
 use neve_syntax::StringPart;
@@ -43,6 +49,21 @@ struct Binding {
     value: Value,
     /// Whether this binding is public / 此绑定是否公开
     is_public: bool,
+    /// Whether the value is a lazy parameter's thunk, forced on lookup
+    /// 值是否为惰性参数的 thunk，在查找时强制求值
+    is_lazy: bool,
+}
+
+/// Where an imported name came from.
+/// 导入名称的来源。
+#[derive(Clone)]
+struct ImportOrigin {
+    /// The file of the module the name was imported from / 名称所来自模块的文件
+    module: PathBuf,
+    /// The name of the binding inside that module / 该绑定在模块内的名称
+    item: String,
+    /// The import statement / 导入语句
+    span: Span,
 }
 
 /// Environment for AST evaluation.
@@ -70,15 +91,28 @@ impl AstEnv {
     /// Create an environment with all built-in functions.
     /// 创建一个包含所有内置函数的环境。
     pub fn with_builtins() -> Self {
+        Self::with_builtins_filtered(|_| true)
+    }
+
+    /// Create an environment with the built-in functions of `set`.
+    /// 创建一个包含 `set` 中内置函数的环境。
+    pub fn with_builtin_set(set: BuiltinSet) -> Self {
+        Self::with_builtins_filtered(|name| set.allows(name))
+    }
+
+    /// Create an environment with the built-in functions accepted by `keep`.
+    /// 创建一个包含 `keep` 所接受的内置函数的环境。
+    pub fn with_builtins_filtered(keep: impl Fn(&str) -> bool) -> Self {
         let mut env = Self::new();
-        // Load all builtins from the central registry - all are public
-        // 从中央注册表加载所有内置函数 - 全部为公开
-        for (name, value) in builtins() {
+        // Load builtins from the central registry - all are public
+        // 从中央注册表加载内置函数 - 全部为公开
+        for (name, value) in builtins().into_iter().filter(|(name, _)| keep(name)) {
             env.bindings.insert(
                 name.to_string(),
                 Binding {
                     value,
                     is_public: true,
+                    is_lazy: false,
                 },
             );
         }
@@ -102,6 +136,7 @@ impl AstEnv {
             Binding {
                 value,
                 is_public: false,
+                is_lazy: false,
             },
         );
     }
@@ -114,6 +149,7 @@ impl AstEnv {
             Binding {
                 value,
                 is_public: true,
+                is_lazy: false,
             },
         );
     }
@@ -121,17 +157,47 @@ impl AstEnv {
     /// Define a binding with explicit visibility.
     /// 定义一个具有显式可见性的绑定。
     pub fn define_with_visibility(&mut self, name: String, value: Value, is_public: bool) {
-        self.bindings.insert(name, Binding { value, is_public });
+        self.bindings.insert(
+            name,
+            Binding {
+                value,
+                is_public,
+                is_lazy: false,
+            },
+        );
+    }
+
+    /// Define a lazy parameter binding.
+    /// 定义一个惰性参数绑定。
+    ///
+    /// If `value` is a thunk, it is forced when the variable is evaluated
+    /// rather than handed out as a thunk.
+    /// 如果 `value` 是 thunk，则在对变量求值时强制求值，而不是作为 thunk 返回。
+    pub fn define_lazy(&mut self, name: String, value: Value) {
+        self.bindings.insert(
+            name,
+            Binding {
+                value,
+                is_public: false,
+                is_lazy: true,
+            },
+        );
     }
 
     /// Look up a variable by name.
     /// 按名称查找变量。
     pub fn get(&self, name: &str) -> Option<Value> {
+        self.lookup(name).map(|binding| binding.value.clone())
+    }
+
+    /// Look up a variable's binding by name.
+    /// 按名称查找变量的绑定。
+    fn lookup(&self, name: &str) -> Option<&Binding> {
         if let Some(binding) = self.bindings.get(name) {
-            return Some(binding.value.clone());
+            return Some(binding);
         }
         if let Some(parent) = &self.parent {
-            return parent.get(name);
+            return parent.lookup(name);
         }
         None
     }
@@ -180,6 +246,15 @@ impl AstEnv {
         false
     }
 
+    /// Iterate over every name visible from this scope, including parents.
+    /// Used for "did you mean?" suggestions.
+    /// 遍历此作用域可见的所有名称（包括父环境）。
+    /// 用于"你是不是想要？"建议。
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        std::iter::successors(Some(self), |env| env.parent.as_deref())
+            .flat_map(|env| env.bindings.keys().map(String::as_str))
+    }
+
     /// Get the number of bindings in this scope (not including parent).
     /// 获取此作用域中的绑定数量（不包括父环境）。
     pub fn len(&self) -> usize {
@@ -201,6 +276,7 @@ impl AstEnv {
                 Binding {
                     value,
                     is_public: false,
+                    is_lazy: false,
                 },
             );
         }
@@ -213,7 +289,14 @@ impl AstEnv {
         bindings: impl IntoIterator<Item = (String, Value, bool)>,
     ) {
         for (name, value, is_public) in bindings {
-            self.bindings.insert(name, Binding { value, is_public });
+            self.bindings.insert(
+                name,
+                Binding {
+                    value,
+                    is_public,
+                    is_lazy: false,
+                },
+            );
         }
     }
 }
@@ -236,6 +319,73 @@ pub struct AstEvaluator {
     current_module_path: Vec<String>,
     /// Module loader for advanced module resolution / 高级模块解析的模块加载器
     module_loader: Option<ModuleLoader>,
+    /// Import resolution traces, when tracing is enabled / 启用跟踪时的导入解析记录
+    import_trace: Option<Vec<ResolutionTrace>>,
+    /// Builtins (and imports) this evaluator may use / 此求值器可使用的内置函数（及导入）
+    builtin_set: BuiltinSet,
+    /// Names brought in by imports, for conflict detection / 由导入引入的名称，用于冲突检测
+    imported_names: HashMap<String, ImportOrigin>,
+    /// Call depth and fuel shared with every sub-evaluator / 与所有子求值器共享的调用深度和燃料
+    limits: Rc<EvalLimits>,
+    /// Decision trees of match expressions, shared with every sub-evaluator / 匹配表达式的决策树，与所有子求值器共享
+    match_trees: Rc<RefCell<HashMap<usize, CompiledMatch>>>,
+}
+
+/// A match expression's decision tree, cached by the address of its arms.
+/// 匹配表达式的决策树，按其分支的地址缓存。
+///
+/// Holding the arms keeps that address from being reused by another match
+/// while the entry exists.
+/// 持有分支可确保在条目存在期间该地址不会被另一个匹配重用。
+struct CompiledMatch {
+    /// The match's arms / 匹配的分支
+    _arms: Arc<[MatchArm]>,
+    /// The tree, or `None` if switching doesn't narrow the arms / 决策树；分派无法缩小分支范围时为 `None`
+    tree: Option<Rc<DecisionTree>>,
+}
+
+/// Default maximum depth of nested function calls.
+/// 嵌套函数调用的默认最大深度。
+pub const DEFAULT_RECURSION_LIMIT: usize = 10_000;
+
+/// Stack space that must be left before evaluating an expression.
+/// 求值表达式前必须剩余的栈空间。
+const STACK_RED_ZONE: usize = 256 * 1024;
+
+/// Size of each stack segment allocated once the red zone is reached.
+/// 到达红区后每次分配的栈段大小。
+const STACK_SEGMENT_SIZE: usize = 4 * 1024 * 1024;
+
+/// Call depth and step budget, shared by an evaluator and its sub-evaluators.
+/// 调用深度和步数预算，由求值器及其子求值器共享。
+#[derive(Debug)]
+struct EvalLimits {
+    /// Calls currently in progress / 当前正在进行的调用数
+    depth: Cell<usize>,
+    /// Maximum number of nested calls / 嵌套调用的最大数量
+    limit: Cell<usize>,
+    /// Evaluation steps left, if bounded / 剩余的求值步数（如有限制）
+    fuel: Cell<Option<u64>>,
+}
+
+impl EvalLimits {
+    fn new() -> Self {
+        Self {
+            depth: Cell::new(0),
+            limit: Cell::new(DEFAULT_RECURSION_LIMIT),
+            fuel: Cell::new(None),
+        }
+    }
+}
+
+/// Leaves a call when dropped.
+/// 在被丢弃时退出一次调用。
+struct CallGuard(Rc<EvalLimits>);
+
+impl Drop for CallGuard {
+    fn drop(&mut self) {
+        self.0.depth.set(self.0.depth.get() - 1);
+    }
 }
 
 impl AstEvaluator {
@@ -246,6 +396,11 @@ impl AstEvaluator {
             loaded_modules: HashMap::new(),
             current_module_path: Vec::new(),
             module_loader: None,
+            import_trace: None,
+            builtin_set: BuiltinSet::Full,
+            imported_names: HashMap::new(),
+            limits: Rc::new(EvalLimits::new()),
+            match_trees: Rc::default(),
         }
     }
 
@@ -256,9 +411,121 @@ impl AstEvaluator {
             loaded_modules: HashMap::new(),
             current_module_path: Vec::new(),
             module_loader: None,
+            import_trace: None,
+            builtin_set: BuiltinSet::Full,
+            imported_names: HashMap::new(),
+            limits: Rc::new(EvalLimits::new()),
+            match_trees: Rc::default(),
         }
     }
 
+    /// Create an evaluator restricted to the builtins of `set`.
+    /// 创建一个仅限使用 `set` 中内置函数的求值器。
+    ///
+    /// With [`BuiltinSet::Pure`], impure builtins are left out of the
+    /// environment, refused if they reach `apply` anyway, and imports fail.
+    /// 使用 [`BuiltinSet::Pure`] 时，非纯内置函数不会放入环境；即使它们到达 `apply`
+    /// 也会被拒绝，并且导入会失败。
+    pub fn with_builtin_set(set: BuiltinSet) -> Self {
+        Self::with_env(Rc::new(AstEnv::with_builtin_set(set))).restricted_to(set)
+    }
+
+    /// Refuse builtins and imports outside `set`, keeping the current environment.
+    /// 拒绝 `set` 之外的内置函数和导入，保留当前环境。
+    pub fn restricted_to(mut self, set: BuiltinSet) -> Self {
+        self.builtin_set = set;
+        self
+    }
+
+    /// Fail with [`EvalError::RecursionLimit`] once more than `limit` calls are nested.
+    /// 当嵌套调用超过 `limit` 层时以 [`EvalError::RecursionLimit`] 失败。
+    pub fn with_recursion_limit(self, limit: usize) -> Self {
+        self.limits.limit.set(limit);
+        self
+    }
+
+    /// Fail with [`EvalError::StepLimitExceeded`] after `steps` expressions are evaluated.
+    /// 在求值 `steps` 个表达式后以 [`EvalError::StepLimitExceeded`] 失败。
+    pub fn with_fuel(self, steps: u64) -> Self {
+        self.limits.fuel.set(Some(steps));
+        self
+    }
+
+    /// Evaluation steps left, or `None` if evaluation is unbounded.
+    /// 剩余的求值步数；无限制时为 `None`。
+    pub fn remaining_fuel(&self) -> Option<u64> {
+        self.limits.fuel.get()
+    }
+
+    /// Number of match expressions compiled to a decision tree so far.
+    /// 到目前为止编译为决策树的匹配表达式数量。
+    pub fn decision_tree_count(&self) -> usize {
+        self.match_trees
+            .borrow()
+            .values()
+            .filter(|compiled| compiled.tree.is_some())
+            .count()
+    }
+
+    /// Sub-evaluator for `env` that shares this evaluator's base path, builtin
+    /// set and limits.
+    /// 用于 `env` 的子求值器，共享此求值器的基路径、内置函数集合和限制。
+    fn child(&self, env: Rc<AstEnv>) -> AstEvaluator {
+        let mut eval = AstEvaluator::with_env(env).restricted_to(self.builtin_set);
+        eval.base_path = self.base_path.clone();
+        eval.limits = self.limits.clone();
+        eval.match_trees = self.match_trees.clone();
+        eval
+    }
+
+    /// Enter a function call, failing if the recursion limit is exceeded.
+    /// 进入一次函数调用，超过递归限制时失败。
+    fn enter_call(&self) -> Result<CallGuard, EvalError> {
+        let depth = self.limits.depth.get();
+        let limit = self.limits.limit.get();
+        if depth >= limit {
+            return Err(EvalError::RecursionLimit(limit));
+        }
+        self.limits.depth.set(depth + 1);
+        Ok(CallGuard(self.limits.clone()))
+    }
+
+    /// Spend one evaluation step, failing once the fuel is used up.
+    /// 消耗一个求值步，燃料耗尽时失败。
+    fn consume_fuel(&self) -> Result<(), EvalError> {
+        self.consume_fuel_steps(1)
+    }
+
+    /// Spend `steps` evaluation steps at once, failing without spending any
+    /// if fewer are left.
+    /// 一次消耗 `steps` 个求值步；剩余不足时失败且不消耗。
+    fn consume_fuel_steps(&self, steps: u64) -> Result<(), EvalError> {
+        match self.limits.fuel.get() {
+            Some(left) if left < steps => {
+                self.limits.fuel.set(Some(0));
+                Err(EvalError::StepLimitExceeded)
+            }
+            Some(left) => {
+                self.limits.fuel.set(Some(left - steps));
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Record how every import is resolved, including imports of imported modules.
+    /// 记录每个导入的解析过程，包括被导入模块中的导入。
+    pub fn with_import_trace(mut self) -> Self {
+        self.import_trace = Some(Vec::new());
+        self
+    }
+
+    /// Import resolution traces recorded so far, in resolution order.
+    /// 目前为止记录的导入解析过程，按解析顺序排列。
+    pub fn import_trace(&self) -> &[ResolutionTrace] {
+        self.import_trace.as_deref().unwrap_or_default()
+    }
+
     pub fn with_base_path(mut self, path: PathBuf) -> Self {
         self.base_path = Some(path.clone());
         // Also initialize module loader with this path
@@ -286,23 +553,17 @@ impl AstEvaluator {
     /// Call an AstClosure with the given arguments.
     pub fn call_closure(
         &mut self,
-        closure: &AstClosure,
+        closure: &Rc<AstClosure>,
         args: Vec<Value>,
     ) -> Result<Value, EvalError> {
         if args.len() != closure.params.len() {
-            return Err(EvalError::WrongArity);
+            return self.apply(Value::AstClosure(closure.clone()), args);
         }
 
-        let mut new_env = AstEnv::child(closure.env.clone());
-        for (param, arg) in closure.params.iter().zip(args) {
-            let name = pattern_name(&param.pattern);
-            new_env.define(name, arg);
-        }
+        let _call = self.enter_call()?;
+        let new_env = call_env(closure, args);
 
-        let mut body_eval = AstEvaluator::with_env(Rc::new(new_env));
-        if let Some(ref base) = self.base_path {
-            body_eval.base_path = Some(base.clone());
-        }
+        let mut body_eval = self.child(Rc::new(new_env));
         body_eval.eval_expr(&closure.body)
     }
 
@@ -344,6 +605,7 @@ impl AstEvaluator {
     pub fn eval_fn_def(&mut self, fn_def: &FnDef) -> Result<Value, EvalError> {
         // Create a closure that captures the current environment
         let func = AstClosure {
+            name: Some(fn_def.name.name.clone()),
             params: fn_def.params.clone(),
             body: fn_def.body.clone(),
             env: self.env.clone(),
@@ -361,55 +623,142 @@ impl AstEvaluator {
                 Ok(value)
             }
             ItemKind::Fn(fn_def) => {
-                // For recursive functions, we need to define the function first,
-                // then update the closure to capture the environment that includes itself.
+                // The closure binds itself by name when called, so recursion
+                // sees the function however deep it goes
+                // 闭包在被调用时按名称绑定自身，因此任意深度的递归都能找到该函数
                 let name = fn_def.name.name.clone();
                 let is_pub = fn_def.visibility == Visibility::Public;
-
-                // Create a placeholder closure first
                 let func = AstClosure {
+                    name: Some(name.clone()),
                     params: fn_def.params.clone(),
                     body: fn_def.body.clone(),
-                    env: self.env.clone(), // Will be updated below
-                };
-
-                // Define the function in the environment
-                Rc::make_mut(&mut self.env).define_with_visibility(
-                    name.clone(),
-                    Value::AstClosure(Rc::new(func)),
-                    is_pub,
-                );
-
-                // Now update the closure to have the environment that includes itself
-                let recursive_func = AstClosure {
-                    params: fn_def.params.clone(),
-                    body: fn_def.body.clone(),
-                    env: self.env.clone(), // Now includes the function itself
+                    env: self.env.clone(),
                 };
                 Rc::make_mut(&mut self.env).define_with_visibility(
                     name,
-                    Value::AstClosure(Rc::new(recursive_func)),
+                    Value::AstClosure(Rc::new(func)),
                     is_pub,
                 );
 
                 Ok(Value::Unit)
             }
             ItemKind::Import(import_def) => {
-                self.eval_import(import_def)?;
+                self.eval_import(import_def, item.span)?;
+                Ok(Value::Unit)
+            }
+            ItemKind::Enum(enum_def) => {
+                self.define_enum(enum_def);
+                Ok(Value::Unit)
+            }
+            ItemKind::Struct(struct_def) => {
+                self.define_struct(struct_def)?;
                 Ok(Value::Unit)
             }
             _ => Ok(Value::Unit),
         }
     }
 
-    fn eval_import(&mut self, import_def: &ImportDef) -> Result<(), EvalError> {
+    /// Define the constructors of an enum.
+    /// 定义枚举的构造器。
+    ///
+    /// Each variant is bound under its own name and as a field of a record
+    /// named after the enum, so both `Circle(1.0)` and `Shape.Circle(1.0)` work.
+    /// 每个变体既以自身名称绑定，也作为以枚举命名的记录的字段，
+    /// 因此 `Circle(1.0)` 和 `Shape.Circle(1.0)` 都可用。
+    fn define_enum(&mut self, enum_def: &EnumDef) {
+        let is_pub = enum_def.visibility == Visibility::Public;
+        let mut namespace = HashMap::with_capacity(enum_def.variants.len());
+        for variant in &enum_def.variants {
+            let name = variant.name.name.clone();
+            let constructor = variant_constructor(&name, &variant.kind);
+            namespace.insert(name.clone(), constructor.clone());
+            Rc::make_mut(&mut self.env).define_with_visibility(name, constructor, is_pub);
+        }
+        Rc::make_mut(&mut self.env).define_with_visibility(
+            enum_def.name.name.clone(),
+            Value::Record(Rc::new(namespace)),
+            is_pub,
+        );
+    }
+
+    /// Define the constructor of a struct.
+    /// 定义结构体的构造器。
+    ///
+    /// `Point(#{ x = 1 })` checks the given fields against the declaration,
+    /// fills in defaults and yields a plain record. Defaults are evaluated
+    /// once, at the definition.
+    /// `Point(#{ x = 1 })` 根据声明检查给定字段，填充默认值并返回普通记录。
+    /// 默认值在定义处求值一次。
+    fn define_struct(&mut self, struct_def: &StructDef) -> Result<(), EvalError> {
+        let mut fields = Vec::with_capacity(struct_def.fields.len());
+        for field in &struct_def.fields {
+            let default = match &field.default {
+                Some(expr) => Some(self.eval_expr(expr)?),
+                None => None,
+            };
+            fields.push((field.name.name.clone(), default));
+        }
+
+        let name = struct_def.name.name.clone();
+        let constructor = Value::Constructor(Rc::new(Constructor {
+            name: name.clone(),
+            func: Box::new(move |args: Vec<Value>| {
+                let given = match args.as_slice() {
+                    [Value::Record(given)] => given,
+                    [_] => return Err(format!("{} expects a record of fields", name)),
+                    _ => return Err(format!("{} expects 1 argument, got {}", name, args.len())),
+                };
+                if let Some(unknown) = given.keys().find(|k| !fields.iter().any(|(n, _)| n == *k)) {
+                    return Err(format!("unknown field '{}' in struct {}", unknown, name));
+                }
+
+                let mut record = HashMap::with_capacity(fields.len());
+                for (field, default) in &fields {
+                    let value = match (given.get(field), default) {
+                        (Some(value), _) | (None, Some(value)) => value.clone(),
+                        (None, None) => {
+                            return Err(format!("missing field '{}' in struct {}", field, name));
+                        }
+                    };
+                    record.insert(field.clone(), value);
+                }
+                Ok(Value::Record(Rc::new(record)))
+            }),
+        }));
+
+        let is_pub = struct_def.visibility == Visibility::Public;
+        Rc::make_mut(&mut self.env).define_with_visibility(
+            struct_def.name.name.clone(),
+            constructor,
+            is_pub,
+        );
+        Ok(())
+    }
+
+    fn eval_import(&mut self, import_def: &ImportDef, span: Span) -> Result<(), EvalError> {
+        if !self.builtin_set.allows("import") {
+            return Err(EvalError::TypeError(
+                "imports are not allowed in pure evaluation".to_string(),
+            ));
+        }
+
         // Resolve the module path to a file path
-        let module_path = self.resolve_module_path(import_def)?;
+        let mut trace = self.import_trace.is_some().then(|| ResolutionTrace {
+            path: ModulePath::from_import_def(import_def),
+            from_module: self.current_module_path.clone(),
+            absolute: None,
+            candidates: Vec::new(),
+        });
+        let resolved = self.resolve_module_path(import_def, trace.as_mut());
+        if let (Some(traces), Some(trace)) = (&mut self.import_trace, trace) {
+            traces.push(trace);
+        }
+        let module_path = resolved?;
 
         // Check if module is already loaded
         if let Some(module_env) = self.loaded_modules.get(&module_path).cloned() {
             // Import from cached module
-            self.import_from_env(&module_env, import_def)?;
+            self.import_from_env(&module_env, &module_path, import_def, span)?;
             return Ok(());
         }
 
@@ -442,25 +791,43 @@ impl AstEvaluator {
         }
 
         // Create a new evaluator for the module with its own environment
-        let mut module_eval = AstEvaluator::new();
+        let mut module_eval = AstEvaluator::with_builtin_set(self.builtin_set);
+        module_eval.limits = self.limits.clone();
+        module_eval.match_trees = self.match_trees.clone();
         if let Some(parent) = module_path.parent() {
             module_eval.base_path = Some(parent.to_path_buf());
         }
+        if self.import_trace.is_some() {
+            module_eval.import_trace = Some(Vec::new());
+        }
 
         // Evaluate the module
-        module_eval.eval_file(&file)?;
+        let result = module_eval.eval_file(&file);
+        if let (Some(trace), Some(nested)) =
+            (&mut self.import_trace, module_eval.import_trace.take())
+        {
+            trace.extend(nested);
+        }
+        result?;
 
         // Cache the module environment
         let module_env = module_eval.env.clone();
-        self.loaded_modules.insert(module_path, module_env.clone());
+        self.loaded_modules
+            .insert(module_path.clone(), module_env.clone());
 
         // Import from the module
-        self.import_from_env(&module_env, import_def)?;
+        self.import_from_env(&module_env, &module_path, import_def, span)?;
 
         Ok(())
     }
 
-    fn resolve_module_path(&self, import_def: &ImportDef) -> Result<PathBuf, EvalError> {
+    /// Resolve an import to a file, recording the candidates tried into `trace`.
+    /// 将导入解析为文件，并将尝试过的候选项记录到 `trace` 中。
+    fn resolve_module_path(
+        &self,
+        import_def: &ImportDef,
+        mut trace: Option<&mut ResolutionTrace>,
+    ) -> Result<PathBuf, EvalError> {
         let path = &import_def.path;
         let path_segments: Vec<String> = path.iter().map(|i| i.name.clone()).collect();
 
@@ -473,6 +840,9 @@ impl AstEvaluator {
                 PathPrefix::Crate => ModulePath::crate_(path_segments.clone()),
             };
 
+            if let Some(trace) = trace.as_deref_mut() {
+                *trace = loader.trace_path(&module_path, Some(&self.current_module_path));
+            }
             if let Some(file_path) =
                 loader.resolve_path(&module_path, Some(&self.current_module_path))
             {
@@ -514,8 +884,17 @@ impl AstEvaluator {
             base_dir.join(&module_name).join("mod.neve"),
         ];
 
+        if let Some(trace) = trace.as_deref_mut()
+            && trace.absolute.is_none()
+        {
+            trace.absolute = Some(path_segments.clone());
+        }
         for candidate in &candidates {
-            if candidate.exists() {
+            let found = candidate.exists();
+            if let Some(trace) = trace.as_deref_mut() {
+                trace.candidates.push((candidate.clone(), found));
+            }
+            if found {
                 return Ok(candidate.clone());
             }
         }
@@ -537,9 +916,14 @@ impl AstEvaluator {
     fn import_from_env(
         &mut self,
         module_env: &Rc<AstEnv>,
+        module_path: &Path,
         import_def: &ImportDef,
+        span: Span,
     ) -> Result<(), EvalError> {
-        match &import_def.items {
+        // Collect (name, item in module, value) before binding anything, so a
+        // conflicting import leaves the environment untouched
+        // 在绑定之前收集（名称，模块内项，值），使冲突的导入不会修改环境
+        let bindings: Vec<(String, String, Value)> = match &import_def.items {
             ImportItems::Module => {
                 // Import the module as a namespace
                 // Only include public bindings
@@ -556,10 +940,11 @@ impl AstEvaluator {
                 // Create a record with only public module bindings
                 let bindings = module_env.public_bindings();
                 let record = Value::Record(Rc::new(bindings));
-                Rc::make_mut(&mut self.env).define(module_name, record);
+                vec![(module_name, String::new(), record)]
             }
             ImportItems::Items(items) => {
                 // Import specific items (must be public)
+                let mut bindings = Vec::with_capacity(items.len());
                 for item in items {
                     let name = &item.name;
                     // Check if the item exists and is public
@@ -577,22 +962,76 @@ impl AstEvaluator {
                         }
                     }
                     if let Some(value) = module_env.get(name) {
-                        Rc::make_mut(&mut self.env).define(name.clone(), value);
+                        bindings.push((name.clone(), name.clone(), value));
                     }
                 }
+                bindings
             }
             ImportItems::All => {
                 // Import all public bindings
-                for (name, value) in module_env.public_bindings() {
-                    Rc::make_mut(&mut self.env).define(name, value);
-                }
+                let mut bindings: Vec<_> = module_env
+                    .public_bindings()
+                    .into_iter()
+                    .map(|(name, value)| (name.clone(), name, value))
+                    .collect();
+                bindings.sort_by(|a, b| a.0.cmp(&b.0));
+                bindings
             }
+        };
+
+        for (name, item, _) in &bindings {
+            if let Some(previous) = self.imported_names.get(name)
+                && (previous.module != module_path || previous.item != *item)
+            {
+                return Err(Self::import_conflict(name, previous.span, span));
+            }
+        }
+
+        let env = Rc::make_mut(&mut self.env);
+        for (name, item, value) in bindings {
+            self.imported_names.insert(
+                name.clone(),
+                ImportOrigin {
+                    module: module_path.to_path_buf(),
+                    item,
+                    span,
+                },
+            );
+            env.define(name, value);
         }
         Ok(())
     }
 
+    /// Build the error for `name` being imported by two different imports.
+    /// 构建 `name` 被两个不同导入引入时的错误。
+    fn import_conflict(name: &str, first: Span, second: Span) -> EvalError {
+        let diagnostic = Diagnostic::error(
+            DiagnosticKind::Module,
+            second,
+            format!("`{}` is imported more than once", name),
+        )
+        .with_code(ErrorCode::ConflictingImport)
+        .with_label(Label::new(first, format!("`{}` first imported here", name)))
+        .with_label(Label::new(
+            second,
+            format!("`{}` imported again here", name),
+        ))
+        .with_help("import one of the modules under an alias with `as`, e.g. `import a.b as ab`");
+        EvalError::ImportConflict(Box::new(diagnostic))
+    }
+
     /// Evaluate an expression.
     pub fn eval_expr(&mut self, expr: &Expr) -> Result<Value, EvalError> {
+        // Deep nesting continues on a heap-allocated stack segment instead of
+        // overflowing the thread's stack, so only the recursion limit bounds it
+        // 深层嵌套会在堆分配的栈段上继续，而不是溢出线程栈，因此只受递归限制约束
+        stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT_SIZE, || {
+            self.eval_expr_inner(expr)
+        })
+    }
+
+    fn eval_expr_inner(&mut self, expr: &Expr) -> Result<Value, EvalError> {
+        self.consume_fuel()?;
         match &expr.kind {
             ExprKind::Int(n) => Ok(Value::Int(*n)),
             ExprKind::Float(f) => Ok(Value::Float(*f)),
@@ -601,10 +1040,7 @@ impl AstEvaluator {
             ExprKind::Bool(b) => Ok(Value::Bool(*b)),
             ExprKind::Unit => Ok(Value::Unit),
 
-            ExprKind::Var(ident) => self
-                .env
-                .get(&ident.name)
-                .ok_or_else(|| EvalError::TypeError(format!("undefined variable: {}", ident.name))),
+            ExprKind::Var(ident) => self.lookup_var(&ident.name),
 
             ExprKind::List(items) => {
                 let values: Result<Vec<_>, _> = items.iter().map(|e| self.eval_expr(e)).collect();
@@ -623,9 +1059,7 @@ impl AstEvaluator {
                         self.eval_expr(v)?
                     } else {
                         // Shorthand: #{ x } means #{ x = x }
-                        self.env.get(&field.name.name).ok_or_else(|| {
-                            EvalError::TypeError(format!("undefined variable: {}", field.name.name))
-                        })?
+                        self.lookup_var(&field.name.name)?
                     };
                     map.insert(field.name.name.clone(), value);
                 }
@@ -641,12 +1075,9 @@ impl AstEvaluator {
                             let value = if let Some(ref v) = field.value {
                                 self.eval_expr(v)?
                             } else {
-                                self.env.get(&field.name.name).ok_or_else(|| {
-                                    EvalError::TypeError(format!(
-                                        "undefined variable: {}",
-                                        field.name.name
-                                    ))
-                                })?
+                                self.env
+                                    .get(&field.name.name)
+                                    .ok_or_else(|| self.undefined_variable(&field.name.name))?
                             };
                             map.insert(field.name.name.clone(), value);
                         }
@@ -660,6 +1091,7 @@ impl AstEvaluator {
 
             ExprKind::Lambda { params, body } => {
                 let closure = AstClosure {
+                    name: None,
                     params: params
                         .iter()
                         .map(|p| Param {
@@ -680,8 +1112,8 @@ impl AstEvaluator {
 
             ExprKind::Call { func, args } => {
                 let func_val = self.eval_expr(func)?;
-                let arg_vals: Result<Vec<_>, _> = args.iter().map(|e| self.eval_expr(e)).collect();
-                self.apply(func_val, arg_vals?)
+                let arg_vals = self.eval_args(&func_val, args)?;
+                self.apply(func_val, arg_vals).map_err(|e| e.at(expr.span))
             }
 
             ExprKind::MethodCall {
@@ -709,9 +1141,10 @@ impl AstEvaluator {
             ExprKind::Field { base, field } => {
                 let base_val = self.eval_expr(base)?;
                 match base_val {
-                    Value::Record(fields) => fields.get(&field.name).cloned().ok_or_else(|| {
-                        EvalError::TypeError(format!("no field '{}' in record", field.name))
-                    }),
+                    Value::Record(fields) => fields
+                        .get(&field.name)
+                        .cloned()
+                        .ok_or_else(|| Self::missing_field(&field.name, &fields)),
                     _ => Err(EvalError::TypeError(
                         "field access requires a record".to_string(),
                     )),
@@ -767,35 +1200,20 @@ impl AstEvaluator {
                 let cond = self.eval_expr(condition)?;
                 if cond.is_truthy() {
                     self.eval_expr(then_branch)
-                } else {
+                } else if let Some(else_branch) = else_branch {
                     self.eval_expr(else_branch)
+                } else {
+                    Ok(Value::Unit)
                 }
             }
 
             ExprKind::Match { scrutinee, arms } => {
                 let val = self.eval_expr(scrutinee)?;
-                for arm in arms {
-                    if let Some(bindings) = Self::match_pattern(&arm.pattern, &val) {
-                        // Create new scope with bindings
-                        let mut new_env = AstEnv::child(self.env.clone());
-                        for (name, value) in bindings {
-                            new_env.define(name, value);
-                        }
-
-                        // Check guard
-                        if let Some(guard) = &arm.guard {
-                            let mut guard_eval = AstEvaluator::with_env(Rc::new(new_env.clone()));
-                            let guard_val = guard_eval.eval_expr(guard)?;
-                            if !guard_val.is_truthy() {
-                                continue;
-                            }
-                        }
-
-                        let mut body_eval = AstEvaluator::with_env(Rc::new(new_env));
-                        return body_eval.eval_expr(&arm.body);
-                    }
-                }
-                Err(EvalError::PatternMatchFailed)
+                let (arm, new_env) = self
+                    .select_arm(arms, &val)?
+                    .ok_or(EvalError::PatternMatchFailed)?;
+                let mut body_eval = self.child(Rc::new(new_env));
+                body_eval.eval_expr(&arm.body)
             }
 
             ExprKind::Block { stmts, expr } => {
@@ -804,19 +1222,19 @@ impl AstEvaluator {
                 for stmt in stmts {
                     match &stmt.kind {
                         StmtKind::Let { pattern, value, .. } => {
-                            let mut stmt_eval = AstEvaluator::with_env(Rc::new(new_env.clone()));
+                            let mut stmt_eval = self.child(Rc::new(new_env.clone()));
                             let val = stmt_eval.eval_expr(value)?;
                             self.bind_pattern_to_env(pattern, val, &mut new_env)?;
                         }
                         StmtKind::Expr(e) => {
-                            let mut stmt_eval = AstEvaluator::with_env(Rc::new(new_env.clone()));
+                            let mut stmt_eval = self.child(Rc::new(new_env.clone()));
                             stmt_eval.eval_expr(e)?;
                         }
                     }
                 }
 
                 if let Some(e) = expr {
-                    let mut final_eval = AstEvaluator::with_env(Rc::new(new_env));
+                    let mut final_eval = self.child(Rc::new(new_env));
                     final_eval.eval_expr(e)
                 } else {
                     Ok(Value::Unit)
@@ -853,15 +1271,16 @@ impl AstEvaluator {
                 let mut value = self
                     .env
                     .get(&first.name)
-                    .ok_or_else(|| EvalError::TypeError(format!("undefined: {}", first.name)))?;
+                    .ok_or_else(|| self.undefined_variable(&first.name))?;
 
                 // Traverse remaining parts as field accesses
                 for part in &parts[1..] {
                     match value {
                         Value::Record(ref fields) => {
-                            value = fields.get(&part.name).cloned().ok_or_else(|| {
-                                EvalError::TypeError(format!("no field '{}' in record", part.name))
-                            })?;
+                            value = fields
+                                .get(&part.name)
+                                .cloned()
+                                .ok_or_else(|| Self::missing_field(&part.name, fields))?;
                         }
                         _ => {
                             return Err(EvalError::TypeError(format!(
@@ -933,10 +1352,7 @@ impl AstEvaluator {
                 let val = self.eval_expr(value)?;
                 let mut new_env = AstEnv::child(self.env.clone());
                 self.bind_pattern_to_env(pattern, val, &mut new_env)?;
-                let mut body_eval = AstEvaluator::with_env(Rc::new(new_env));
-                if let Some(ref base) = self.base_path {
-                    body_eval.base_path = Some(base.clone());
-                }
+                let mut body_eval = self.child(Rc::new(new_env));
                 body_eval.eval_expr(body)
             }
         }
@@ -989,10 +1405,7 @@ impl AstEvaluator {
 
             // Check guard condition if present
             if let Some(ref condition) = generator.condition {
-                let mut cond_eval = AstEvaluator::with_env(Rc::new(new_env.clone()));
-                if let Some(ref base) = self.base_path {
-                    cond_eval.base_path = Some(base.clone());
-                }
+                let mut cond_eval = self.child(Rc::new(new_env.clone()));
                 let cond_val = cond_eval.eval_expr(condition)?;
                 if !cond_val.is_truthy() {
                     continue;
@@ -1000,16 +1413,57 @@ impl AstEvaluator {
             }
 
             // Recursively process remaining generators
-            let mut inner_eval = AstEvaluator::with_env(Rc::new(new_env));
-            if let Some(ref base) = self.base_path {
-                inner_eval.base_path = Some(base.clone());
-            }
+            let mut inner_eval = self.child(Rc::new(new_env));
             inner_eval.eval_generators(body, generators, index + 1, results)?;
         }
 
         Ok(())
     }
 
+    /// Evaluate a variable, forcing it if it is bound to a lazy parameter.
+    /// 对变量求值；若其绑定到惰性参数则强制求值。
+    fn lookup_var(&mut self, name: &str) -> Result<Value, EvalError> {
+        let (value, is_lazy) = match self.env.lookup(name) {
+            Some(binding) => (binding.value.clone(), binding.is_lazy),
+            None => return Err(self.undefined_variable(name)),
+        };
+        if is_lazy {
+            self.force_value(&value)
+        } else {
+            Ok(value)
+        }
+    }
+
+    /// Evaluate call arguments for `func`.
+    /// 为 `func` 求值调用参数。
+    ///
+    /// Arguments in the position of a `lazy` parameter are not evaluated but
+    /// wrapped in a thunk over the caller's environment.
+    /// 对应 `lazy` 参数位置的实参不会被求值，而是包装成捕获调用者环境的 thunk。
+    fn eval_args(&mut self, func: &Value, args: &[Expr]) -> Result<Vec<Value>, EvalError> {
+        let params = match func {
+            Value::AstClosure(closure) => closure.params.as_slice(),
+            // tryEval must see the error, so its argument is left unevaluated
+            // tryEval 需要捕获错误，因此其参数保持未求值
+            Value::Builtin(builtin) if builtin.name == "tryEval" => {
+                return Ok(args
+                    .iter()
+                    .map(|arg| Value::Thunk(Thunk::new(arg.clone(), self.env.clone())))
+                    .collect());
+            }
+            _ => &[],
+        };
+        args.iter()
+            .enumerate()
+            .map(|(i, arg)| match params.get(i) {
+                Some(param) if param.is_lazy => {
+                    Ok(Value::Thunk(Thunk::new(arg.clone(), self.env.clone())))
+                }
+                _ => self.eval_expr(arg),
+            })
+            .collect()
+    }
+
     /// Force evaluation of a thunk (used by the `force` builtin).
     pub fn force_thunk(&mut self, thunk: &Thunk) -> Result<Value, EvalError> {
         // Check current state
@@ -1038,10 +1492,7 @@ impl AstEvaluator {
         };
 
         // Evaluate the expression
-        let mut eval = AstEvaluator::with_env(env);
-        if let Some(ref base) = self.base_path {
-            eval.base_path = Some(base.clone());
-        }
+        let mut eval = self.child(env);
 
         let result = eval.eval_expr(&expr);
 
@@ -1167,6 +1618,22 @@ impl AstEvaluator {
         loop {
             match current_func {
                 Value::Builtin(ref builtin) => {
+                    if !self.builtin_set.allows(builtin.name) {
+                        return Err(EvalError::TypeError(format!(
+                            "builtin '{}' is not available in pure evaluation",
+                            builtin.name
+                        )));
+                    }
+
+                    // Too few arguments: return a partially-applied builtin
+                    // 参数不足：返回部分应用的内置函数
+                    if !current_args.is_empty() && current_args.len() < builtin.arity {
+                        return Ok(Value::PartialBuiltin(Rc::new(PartialBuiltin {
+                            builtin: builtin.clone(),
+                            args: current_args,
+                        })));
+                    }
+
                     // Special handling for builtins that need evaluator access
                     match builtin.name {
                         "force" => {
@@ -1175,6 +1642,51 @@ impl AstEvaluator {
                             }
                             return self.force_value(&current_args[0]);
                         }
+                        "typeOf" => {
+                            if current_args.len() != 1 {
+                                return Err(EvalError::WrongArity);
+                            }
+                            let value = self.force_value(&current_args[0])?;
+                            return Ok(Value::String(Rc::new(value.type_of().to_string())));
+                        }
+                        "seq" => {
+                            if current_args.len() != 2 {
+                                return Err(EvalError::WrongArity);
+                            }
+                            self.force_value(&current_args[0])?;
+                            return Ok(current_args[1].clone());
+                        }
+                        "deepSeq" => {
+                            if current_args.len() != 2 {
+                                return Err(EvalError::WrongArity);
+                            }
+                            self.force_deep(&current_args[0])?;
+                            return Ok(current_args[1].clone());
+                        }
+                        "throw" => {
+                            if current_args.len() != 1 {
+                                return Err(EvalError::WrongArity);
+                            }
+                            return Err(thrown(&current_args[0]));
+                        }
+                        "assert" => {
+                            if current_args.len() != 3 {
+                                return Err(EvalError::WrongArity);
+                            }
+                            return match &current_args[0] {
+                                Value::Bool(true) => Ok(current_args[2].clone()),
+                                Value::Bool(false) => Err(thrown(&current_args[1])),
+                                _ => Err(EvalError::TypeError(
+                                    "assert expects a Bool condition".to_string(),
+                                )),
+                            };
+                        }
+                        "tryEval" => {
+                            if current_args.len() != 1 {
+                                return Err(EvalError::WrongArity);
+                            }
+                            return self.builtin_try_eval(&current_args[0]);
+                        }
                         "map" => {
                             if current_args.len() != 2 {
                                 return Err(EvalError::WrongArity);
@@ -1219,6 +1731,28 @@ impl AstEvaluator {
                                 &current_args[2],
                             );
                         }
+                        "foldl1" => {
+                            if current_args.len() != 2 {
+                                return Err(EvalError::WrongArity);
+                            }
+                            return self.builtin_foldl1(&current_args[0], &current_args[1]);
+                        }
+                        "foldr1" => {
+                            if current_args.len() != 2 {
+                                return Err(EvalError::WrongArity);
+                            }
+                            return self.builtin_foldr1(&current_args[0], &current_args[1]);
+                        }
+                        "scanl" => {
+                            if current_args.len() != 3 {
+                                return Err(EvalError::WrongArity);
+                            }
+                            return self.builtin_scanl(
+                                &current_args[0],
+                                &current_args[1],
+                                &current_args[2],
+                            );
+                        }
                         "genList" => {
                             if current_args.len() != 2 {
                                 return Err(EvalError::WrongArity);
@@ -1261,6 +1795,12 @@ impl AstEvaluator {
                             }
                             return self.builtin_sort(&current_args[0], &current_args[1]);
                         }
+                        "range" | "replicate" => {
+                            // Each produced element is a step, charged before
+                            // the list is allocated
+                            // 每个生成的元素算一步，在分配列表之前扣除
+                            self.consume_fuel_steps(generated_len(builtin.name, &current_args))?;
+                        }
                         _ => {}
                     }
 
@@ -1269,6 +1809,16 @@ impl AstEvaluator {
                     }
                     return (builtin.func)(&current_args).map_err(EvalError::TypeError);
                 }
+                Value::PartialBuiltin(ref partial) => {
+                    // Prepend the collected arguments and call the builtin again
+                    // 前置已收集的参数并再次调用内置函数
+                    let builtin = partial.builtin.clone();
+                    let mut args = partial.args.clone();
+                    args.append(&mut current_args);
+                    current_func = Value::Builtin(builtin);
+                    current_args = args;
+                    continue;
+                }
                 Value::BuiltinFn(name, ref func) => {
                     // Special handling for force
                     if name == "force" {
@@ -1279,26 +1829,42 @@ impl AstEvaluator {
                     }
                     return func(current_args).map_err(EvalError::TypeError);
                 }
+                Value::Constructor(ref constructor) => {
+                    return (constructor.func)(current_args).map_err(EvalError::TypeError);
+                }
                 Value::AstClosure(ref closure) => {
-                    if current_args.len() != closure.params.len() {
+                    let arity = closure.params.len();
+                    if current_args.is_empty() && arity > 0 {
                         return Err(EvalError::WrongArity);
                     }
 
+                    // Too few arguments: curry into a closure awaiting the rest
+                    // 参数不足：柯里化为等待剩余参数的闭包
+                    if current_args.len() < arity {
+                        return Ok(partial_closure(closure, current_args));
+                    }
+
+                    // Too many arguments: apply the result to the remaining ones
+                    // 参数过多：将结果应用于剩余参数
+                    if current_args.len() > arity {
+                        let rest = current_args.split_off(arity);
+                        let result =
+                            self.apply(Value::AstClosure(closure.clone()), current_args)?;
+                        current_func = result;
+                        current_args = rest;
+                        continue;
+                    }
+
+                    let _call = self.enter_call()?;
+
                     // Use the closure's captured environment as the parent,
                     // which allows recursive calls to find the function
                     // 使用闭包捕获的环境作为父环境，这允许递归调用找到函数
-                    let mut new_env = AstEnv::child(closure.env.clone());
-                    for (param, arg) in closure.params.iter().zip(current_args) {
-                        let name = pattern_name(&param.pattern);
-                        new_env.define(name, arg);
-                    }
+                    let new_env = call_env(closure, current_args);
 
                     // Evaluate with tail call detection
                     // 使用尾调用检测进行求值
-                    let mut body_eval = AstEvaluator::with_env(Rc::new(new_env));
-                    if let Some(ref base) = self.base_path {
-                        body_eval.base_path = Some(base.clone());
-                    }
+                    let mut body_eval = self.child(Rc::new(new_env));
 
                     match body_eval.eval_expr_tco(&closure.body)? {
                         TcoResult::Value(v) => return Ok(v),
@@ -1331,8 +1897,17 @@ impl AstEvaluator {
             // 尾位置的直接调用 - 作为尾调用返回
             ExprKind::Call { func, args } => {
                 let func_val = self.eval_expr(func)?;
-                let arg_vals: Result<Vec<_>, _> = args.iter().map(|e| self.eval_expr(e)).collect();
-                Ok(TcoResult::TailCall(func_val, arg_vals?))
+                let arg_vals = self.eval_args(&func_val, args)?;
+                // Builtins gain nothing from the trampoline; calling them here
+                // keeps the call span for errors they throw
+                // 内置函数无法从蹦床中获益；在此调用可保留其抛出错误的调用位置
+                if matches!(func_val, Value::Builtin(_)) {
+                    return self
+                        .apply(func_val, arg_vals)
+                        .map(TcoResult::Value)
+                        .map_err(|e| e.at(expr.span));
+                }
+                Ok(TcoResult::TailCall(func_val, arg_vals))
             }
 
             // If-then-else: evaluate condition, then branch with TCO
@@ -1345,8 +1920,10 @@ impl AstEvaluator {
                 let cond = self.eval_expr(condition)?;
                 if cond.is_truthy() {
                     self.eval_expr_tco(then_branch)
-                } else {
+                } else if let Some(else_branch) = else_branch {
                     self.eval_expr_tco(else_branch)
+                } else {
+                    Ok(TcoResult::Value(Value::Unit))
                 }
             }
 
@@ -1361,28 +1938,19 @@ impl AstEvaluator {
                 for stmt in stmts {
                     match &stmt.kind {
                         StmtKind::Let { pattern, value, .. } => {
-                            let mut stmt_eval = AstEvaluator::with_env(Rc::new(new_env.clone()));
-                            if let Some(ref base) = self.base_path {
-                                stmt_eval.base_path = Some(base.clone());
-                            }
+                            let mut stmt_eval = self.child(Rc::new(new_env.clone()));
                             let val = stmt_eval.eval_expr(value)?;
                             self.bind_pattern_to_env(pattern, val, &mut new_env)?;
                         }
                         StmtKind::Expr(e) => {
-                            let mut stmt_eval = AstEvaluator::with_env(Rc::new(new_env.clone()));
-                            if let Some(ref base) = self.base_path {
-                                stmt_eval.base_path = Some(base.clone());
-                            }
+                            let mut stmt_eval = self.child(Rc::new(new_env.clone()));
                             stmt_eval.eval_expr(e)?;
                         }
                     }
                 }
 
                 if let Some(e) = final_expr {
-                    let mut final_eval = AstEvaluator::with_env(Rc::new(new_env));
-                    if let Some(ref base) = self.base_path {
-                        final_eval.base_path = Some(base.clone());
-                    }
+                    let mut final_eval = self.child(Rc::new(new_env));
                     final_eval.eval_expr_tco(e)
                 } else {
                     Ok(TcoResult::Value(Value::Unit))
@@ -1393,34 +1961,11 @@ impl AstEvaluator {
             // Match: 求值被匹配值，匹配模式，然后用 TCO 求值分支
             ExprKind::Match { scrutinee, arms } => {
                 let val = self.eval_expr(scrutinee)?;
-                for arm in arms {
-                    if let Some(bindings) = Self::match_pattern(&arm.pattern, &val) {
-                        // Create new scope with bindings
-                        let mut new_env = AstEnv::child(self.env.clone());
-                        for (name, value) in bindings {
-                            new_env.define(name, value);
-                        }
-
-                        // Check guard
-                        if let Some(guard) = &arm.guard {
-                            let mut guard_eval = AstEvaluator::with_env(Rc::new(new_env.clone()));
-                            if let Some(ref base) = self.base_path {
-                                guard_eval.base_path = Some(base.clone());
-                            }
-                            let guard_val = guard_eval.eval_expr(guard)?;
-                            if !guard_val.is_truthy() {
-                                continue;
-                            }
-                        }
-
-                        let mut body_eval = AstEvaluator::with_env(Rc::new(new_env));
-                        if let Some(ref base) = self.base_path {
-                            body_eval.base_path = Some(base.clone());
-                        }
-                        return body_eval.eval_expr_tco(&arm.body);
-                    }
-                }
-                Err(EvalError::PatternMatchFailed)
+                let (arm, new_env) = self
+                    .select_arm(arms, &val)?
+                    .ok_or(EvalError::PatternMatchFailed)?;
+                let mut body_eval = self.child(Rc::new(new_env));
+                body_eval.eval_expr_tco(&arm.body)
             }
 
             // Let expression: bind value, then evaluate body with TCO
@@ -1434,10 +1979,7 @@ impl AstEvaluator {
                 let val = self.eval_expr(value)?;
                 let mut new_env = AstEnv::child(self.env.clone());
                 self.bind_pattern_to_env(pattern, val, &mut new_env)?;
-                let mut body_eval = AstEvaluator::with_env(Rc::new(new_env));
-                if let Some(ref base) = self.base_path {
-                    body_eval.base_path = Some(base.clone());
-                }
+                let mut body_eval = self.child(Rc::new(new_env));
                 body_eval.eval_expr_tco(body)
             }
 
@@ -1450,6 +1992,103 @@ impl AstEvaluator {
         }
     }
 
+    /// Select the first arm whose pattern and guard match the value.
+    /// 选择模式和守卫都匹配该值的第一个分支。
+    ///
+    /// Matches with many arms are compiled to a decision tree, once per match
+    /// expression, so only the arms that can match the value's head are tried.
+    /// 分支较多的匹配会被编译为决策树（每个匹配表达式一次），只尝试能匹配该值头部的分支。
+    fn select_arm<'a>(
+        &mut self,
+        arms: &'a Arc<[MatchArm]>,
+        val: &Value,
+    ) -> Result<Option<(&'a MatchArm, AstEnv)>, EvalError> {
+        if arms.len() >= DECISION_TREE_MIN_ARMS
+            && let Some(tree) = self.decision_tree(arms)
+        {
+            for &index in tree.candidates(val) {
+                if let Some(env) = self.try_arm(&arms[index], val)? {
+                    return Ok(Some((&arms[index], env)));
+                }
+            }
+            return Ok(None);
+        }
+
+        for arm in arms.iter() {
+            if let Some(env) = self.try_arm(arm, val)? {
+                return Ok(Some((arm, env)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Get the cached decision tree of a match, compiling it on first use.
+    /// 获取匹配的缓存决策树，首次使用时编译。
+    fn decision_tree(&self, arms: &Arc<[MatchArm]>) -> Option<Rc<DecisionTree>> {
+        let key = arms.as_ptr() as usize;
+        let mut trees = self.match_trees.borrow_mut();
+        if let Some(compiled) = trees.get(&key) {
+            return compiled.tree.clone();
+        }
+
+        let tree = compile_match(arms);
+        let tree = tree.is_beneficial().then(|| Rc::new(tree));
+        trees.insert(
+            key,
+            CompiledMatch {
+                _arms: arms.clone(),
+                tree: tree.clone(),
+            },
+        );
+        tree
+    }
+
+    /// Try a single match arm, returning the arm's scope if it matches.
+    /// 尝试单个匹配分支，匹配时返回该分支的作用域。
+    fn try_arm(&mut self, arm: &MatchArm, val: &Value) -> Result<Option<AstEnv>, EvalError> {
+        let Some(bindings) = Self::match_pattern_in(&arm.pattern, val, Some(&self.env)) else {
+            return Ok(None);
+        };
+
+        // Create new scope with bindings
+        // 使用绑定创建新作用域
+        let mut new_env = AstEnv::child(self.env.clone());
+        for (name, value) in bindings {
+            new_env.define(name, value);
+        }
+
+        // Check guard
+        // 检查守卫
+        if let Some(guard) = &arm.guard {
+            let mut guard_eval = self.child(Rc::new(new_env.clone()));
+            if !guard_eval.eval_expr(guard)?.is_truthy() {
+                return Ok(None);
+            }
+        }
+
+        Ok(Some(new_env))
+    }
+
+    /// Build an "undefined variable" error, suggesting a similar name in scope.
+    /// 构造"未定义变量"错误，并建议作用域中相似的名称。
+    fn undefined_variable(&self, name: &str) -> EvalError {
+        let message = format!("undefined variable: {}", name);
+        EvalError::TypeError(with_suggestion(
+            message,
+            find_similar_name(name, self.env.names()),
+        ))
+    }
+
+    /// Build a "no field" error, suggesting a similar field of the record.
+    /// 构造"字段不存在"错误，并建议记录中相似的字段。
+    fn missing_field(name: &str, fields: &HashMap<String, Value>) -> EvalError {
+        let message = format!("no field '{}' in record", name);
+        EvalError::TypeError(with_suggestion(
+            message,
+            find_similar_name(name, fields.keys().map(String::as_str)),
+        ))
+    }
+
     /// Force evaluation of a value (handles both thunks and regular values).
     fn force_value(&mut self, value: &Value) -> Result<Value, EvalError> {
         match value {
@@ -1458,6 +2097,25 @@ impl AstEvaluator {
         }
     }
 
+    /// Force every thunk reachable from `value`, including inside containers.
+    /// 强制求值 `value` 可达的所有 thunk，包括容器内部的。
+    fn force_deep(&mut self, value: &Value) -> Result<(), EvalError> {
+        match value {
+            Value::Thunk(_) => {
+                let forced = self.force_value(value)?;
+                self.force_deep(&forced)
+            }
+            Value::List(items) | Value::Tuple(items) => {
+                items.iter().try_for_each(|item| self.force_deep(item))
+            }
+            Value::Record(fields) => fields.values().try_for_each(|v| self.force_deep(v)),
+            Value::Map(map) => map.values().try_for_each(|v| self.force_deep(v)),
+            Value::Some(inner) | Value::Ok(inner) | Value::Err(inner) => self.force_deep(inner),
+            Value::Variant(_, payload) => self.force_deep(payload),
+            _ => Ok(()),
+        }
+    }
+
     // ========================================================================
     // Higher-order builtin implementations
     // ========================================================================
@@ -1567,6 +2225,72 @@ impl AstEvaluator {
         Ok(acc)
     }
 
+    /// foldl1(op, list) - Left fold seeded with the first element.
+    /// foldl1(操作, 列表) - 以第一个元素为初始值的左折叠。
+    ///
+    /// Fails on an empty list, since there is no value to start from.
+    /// 空列表会报错，因为没有可作为起点的值。
+    fn builtin_foldl1(&mut self, op: &Value, list: &Value) -> Result<Value, EvalError> {
+        let items = match list {
+            Value::List(items) => items,
+            _ => return Err(EvalError::TypeError("foldl1 expects a list".to_string())),
+        };
+
+        let Some((first, rest)) = items.split_first() else {
+            return Err(EvalError::TypeError("foldl1 of an empty list".to_string()));
+        };
+        let mut acc = first.clone();
+        for item in rest {
+            acc = self.apply(op.clone(), vec![acc, item.clone()])?;
+        }
+        Ok(acc)
+    }
+
+    /// foldr1(op, list) - Right fold seeded with the last element.
+    /// foldr1(操作, 列表) - 以最后一个元素为初始值的右折叠。
+    ///
+    /// Fails on an empty list, since there is no value to start from.
+    /// 空列表会报错，因为没有可作为起点的值。
+    fn builtin_foldr1(&mut self, op: &Value, list: &Value) -> Result<Value, EvalError> {
+        let items = match list {
+            Value::List(items) => items,
+            _ => return Err(EvalError::TypeError("foldr1 expects a list".to_string())),
+        };
+
+        let Some((last, rest)) = items.split_last() else {
+            return Err(EvalError::TypeError("foldr1 of an empty list".to_string()));
+        };
+        let mut acc = last.clone();
+        for item in rest.iter().rev() {
+            acc = self.apply(op.clone(), vec![item.clone(), acc])?;
+        }
+        Ok(acc)
+    }
+
+    /// scanl(op, init, list) - Left fold keeping every intermediate accumulator:
+    /// [init, op(init, x1), op(op(init, x1), x2), ...]
+    /// scanl(操作, 初始值, 列表) - 保留每个中间累加值的左折叠
+    fn builtin_scanl(
+        &mut self,
+        op: &Value,
+        init: &Value,
+        list: &Value,
+    ) -> Result<Value, EvalError> {
+        let items = match list {
+            Value::List(items) => items,
+            _ => return Err(EvalError::TypeError("scanl expects a list".to_string())),
+        };
+
+        let mut results = Vec::with_capacity(items.len() + 1);
+        let mut acc = init.clone();
+        results.push(acc.clone());
+        for item in items.iter() {
+            acc = self.apply(op.clone(), vec![acc, item.clone()])?;
+            results.push(acc.clone());
+        }
+        Ok(Value::List(Rc::new(results)))
+    }
+
     /// genList(f, n) - Generate list [f(0), f(1), ..., f(n-1)]
     fn builtin_gen_list(&mut self, func: &Value, count: &Value) -> Result<Value, EvalError> {
         let n = match count {
@@ -1755,8 +2479,37 @@ impl AstEvaluator {
         Ok(Value::List(Rc::new(vec)))
     }
 
+    /// tryEval(expr) - Evaluate the lazy argument, turning errors into a failure record.
+    /// Resource limits are not caught, so they still stop evaluation.
+    /// tryEval(expr) - 求值惰性参数，并将错误转换为失败记录。
+    /// 资源限制不会被捕获，因此仍会终止求值。
+    fn builtin_try_eval(&mut self, arg: &Value) -> Result<Value, EvalError> {
+        let (success, value) = match self.force_value(arg) {
+            Ok(value) => (true, value),
+            Err(e @ (EvalError::RecursionLimit(_) | EvalError::StepLimitExceeded)) => {
+                return Err(e);
+            }
+            Err(_) => (false, Value::None),
+        };
+        let mut fields = HashMap::new();
+        fields.insert("success".to_string(), Value::Bool(success));
+        fields.insert("value".to_string(), value);
+        Ok(Value::Record(Rc::new(fields)))
+    }
+
     fn apply_immut(&self, func: Value, args: Vec<Value>) -> Result<Value, EvalError> {
         match func {
+            Value::Builtin(builtin) if !args.is_empty() && args.len() < builtin.arity => {
+                Ok(Value::PartialBuiltin(Rc::new(PartialBuiltin {
+                    builtin,
+                    args,
+                })))
+            }
+            Value::PartialBuiltin(partial) => {
+                let mut all_args = partial.args.clone();
+                all_args.extend(args);
+                self.apply_immut(Value::Builtin(partial.builtin.clone()), all_args)
+            }
             Value::Builtin(builtin) => {
                 if args.len() != builtin.arity {
                     return Err(EvalError::WrongArity);
@@ -1764,18 +2517,26 @@ impl AstEvaluator {
                 (builtin.func)(&args).map_err(EvalError::TypeError)
             }
             Value::AstClosure(closure) => {
-                if args.len() != closure.params.len() {
+                let arity = closure.params.len();
+                if args.is_empty() && arity > 0 {
                     return Err(EvalError::WrongArity);
                 }
+                if args.len() < arity {
+                    return Ok(partial_closure(&closure, args));
+                }
+                if args.len() > arity {
+                    let mut args = args;
+                    let rest = args.split_off(arity);
+                    let result = self.apply_immut(Value::AstClosure(closure), args)?;
+                    return self.apply_immut(result, rest);
+                }
+
+                let _call = self.enter_call()?;
 
                 // For immutable apply, use the closure's captured environment
-                let mut new_env = AstEnv::child(closure.env.clone());
-                for (param, arg) in closure.params.iter().zip(args) {
-                    let name = pattern_name(&param.pattern);
-                    new_env.define(name, arg);
-                }
+                let new_env = call_env(&closure, args);
 
-                let mut body_eval = AstEvaluator::with_env(Rc::new(new_env));
+                let mut body_eval = self.child(Rc::new(new_env));
                 body_eval.eval_expr(&closure.body)
             }
             _ => Err(EvalError::NotAFunction),
@@ -1783,6 +2544,22 @@ impl AstEvaluator {
     }
 
     fn match_pattern(pattern: &Pattern, value: &Value) -> Option<Vec<(String, Value)>> {
+        Self::match_pattern_in(pattern, value, None)
+    }
+
+    /// Match `value` against `pattern`, returning the bindings on success.
+    /// 将 `value` 与 `pattern` 匹配，成功时返回绑定。
+    ///
+    /// With an `env`, an identifier pattern naming a unit variant in scope
+    /// (such as `Red` after `enum Color { Red, ... }`, or `None`) matches that
+    /// variant instead of binding a variable.
+    /// 提供 `env` 时，命名作用域中单元变体的标识符模式（例如定义 `enum Color { Red, ... }`
+    /// 之后的 `Red`，或 `None`）会匹配该变体，而不是绑定变量。
+    fn match_pattern_in(
+        pattern: &Pattern,
+        value: &Value,
+        env: Option<&AstEnv>,
+    ) -> Option<Vec<(String, Value)>> {
         // Pre-calculate expected binding count to reduce allocations
         // 预先计算预期绑定数量以减少分配
         fn estimate_bindings(pattern: &Pattern) -> usize {
@@ -1811,10 +2588,12 @@ impl AstEvaluator {
             PatternKind::Wildcard => Some(Vec::new()),
             PatternKind::Var(ident) => {
                 if ident.name == "_" {
-                    Some(Vec::new())
-                } else {
-                    Some(vec![(ident.name.clone(), value.clone())])
+                    return Some(Vec::new());
                 }
+                if let Some(variant) = env.and_then(|env| unit_variant(env, &ident.name)) {
+                    return (variant == *value).then(Vec::new);
+                }
+                Some(vec![(ident.name.clone(), value.clone())])
             }
             PatternKind::Literal(lit) => {
                 let matches = match (lit, value) {
@@ -1835,7 +2614,7 @@ impl AstEvaluator {
                     let capacity = patterns.iter().map(estimate_bindings).sum();
                     let mut bindings = Vec::with_capacity(capacity);
                     for (p, v) in patterns.iter().zip(values.iter()) {
-                        bindings.extend(Self::match_pattern(p, v)?);
+                        bindings.extend(Self::match_pattern_in(p, v, env)?);
                     }
                     Some(bindings)
                 } else {
@@ -1850,7 +2629,7 @@ impl AstEvaluator {
                     let capacity = patterns.iter().map(estimate_bindings).sum();
                     let mut bindings = Vec::with_capacity(capacity);
                     for (p, v) in patterns.iter().zip(values.iter()) {
-                        bindings.extend(Self::match_pattern(p, v)?);
+                        bindings.extend(Self::match_pattern_in(p, v, env)?);
                     }
                     Some(bindings)
                 } else {
@@ -1863,7 +2642,7 @@ impl AstEvaluator {
                     for field in fields {
                         let val = record.get(&field.name.name)?;
                         if let Some(ref pat) = field.pattern {
-                            bindings.extend(Self::match_pattern(pat, val)?);
+                            bindings.extend(Self::match_pattern_in(pat, val, env)?);
                         } else {
                             bindings.push((field.name.name.clone(), val.clone()));
                         }
@@ -1876,23 +2655,44 @@ impl AstEvaluator {
             PatternKind::Constructor { path, args } => {
                 let name = path.first().map(|i| i.name.as_str()).unwrap_or("");
                 match (name, value, args.as_slice()) {
-                    ("Some", Value::Some(v), [p]) => Self::match_pattern(p, v),
+                    ("Some", Value::Some(v), [p]) => Self::match_pattern_in(p, v, env),
                     ("None", Value::None, []) => Some(Vec::new()),
-                    ("Ok", Value::Ok(v), [p]) => Self::match_pattern(p, v),
-                    ("Err", Value::Err(v), [p]) => Self::match_pattern(p, v),
+                    ("Ok", Value::Ok(v), [p]) => Self::match_pattern_in(p, v, env),
+                    ("Err", Value::Err(v), [p]) => Self::match_pattern_in(p, v, env),
+                    // User variants: a single sub-pattern matches the whole
+                    // payload, several match the fields of a tuple payload
+                    // 用户变体：单个子模式匹配整个载荷，多个子模式匹配元组载荷的各字段
+                    (_, Value::Variant(tag, payload), _) if tag == name => match args.as_slice() {
+                        [] => matches!(**payload, Value::Unit).then(Vec::new),
+                        [p] => Self::match_pattern_in(p, payload, env),
+                        patterns => {
+                            let Value::Tuple(values) = &**payload else {
+                                return None;
+                            };
+                            if patterns.len() != values.len() {
+                                return None;
+                            }
+                            let capacity = patterns.iter().map(estimate_bindings).sum();
+                            let mut bindings = Vec::with_capacity(capacity);
+                            for (p, v) in patterns.iter().zip(values.iter()) {
+                                bindings.extend(Self::match_pattern_in(p, v, env)?);
+                            }
+                            Some(bindings)
+                        }
+                    },
                     _ => None,
                 }
             }
             PatternKind::Or(patterns) => {
                 for p in patterns {
-                    if let Some(bindings) = Self::match_pattern(p, value) {
+                    if let Some(bindings) = Self::match_pattern_in(p, value, env) {
                         return Some(bindings);
                     }
                 }
                 None
             }
             PatternKind::Binding { name, pattern } => {
-                let mut bindings = Self::match_pattern(pattern, value)?;
+                let mut bindings = Self::match_pattern_in(pattern, value, env)?;
                 bindings.push((name.name.clone(), value.clone()));
                 Some(bindings)
             }
@@ -1979,9 +2779,11 @@ impl AstEvaluator {
                 }
             }
             Value::Builtin(b) => format!("<builtin:{}>", b.name),
+            Value::PartialBuiltin(p) => format!("<builtin:{}>", p.builtin.name),
             Value::BuiltinFn(name, _) => format!("<builtin:{}>", name),
-            Value::AstClosure(_) => "<function>".to_string(),
-            Value::Closure { .. } => "<function>".to_string(),
+            Value::Constructor(c) => format!("<constructor:{}>", c.name),
+            Value::AstClosure(closure) => fn_signature(closure.param_names()),
+            Value::Closure { params, .. } => fn_signature(params.iter().map(|p| p.name.clone())),
             Value::Thunk(thunk) => match &*thunk.state() {
                 ThunkState::Evaluated(v) => Self::value_to_string(v),
                 ThunkState::Evaluating => "<thunk:evaluating>".to_string(),
@@ -1997,6 +2799,67 @@ impl Default for AstEvaluator {
     }
 }
 
+/// Number of elements the list-generating builtin `name` produces from `args`.
+/// 生成列表的内置函数 `name` 由 `args` 产生的元素数量。
+fn generated_len(name: &str, args: &[Value]) -> u64 {
+    let len = match (name, args) {
+        ("range", [Value::Int(start), Value::Int(end)]) => end.saturating_sub(*start),
+        ("replicate", [Value::Int(n), _]) => *n,
+        _ => 0,
+    };
+    len.max(0) as u64
+}
+
+/// The unit variant `name` refers to in `env`, if it names one.
+/// `name` 在 `env` 中所指的单元变体（如果它命名了一个）。
+fn unit_variant(env: &AstEnv, name: &str) -> Option<Value> {
+    match env.get(name)? {
+        Value::None if name == "None" => Some(Value::None),
+        Value::Variant(tag, payload) if tag == name && matches!(*payload, Value::Unit) => {
+            Some(Value::Variant(tag, payload))
+        }
+        _ => None,
+    }
+}
+
+/// Build the constructor value for an enum variant.
+/// 为枚举变体构建构造器值。
+///
+/// Unit variants are values. Tuple variants are functions of their fields,
+/// wrapping several fields in a tuple payload. Record variants take a single
+/// record as their payload.
+/// 单元变体是值。元组变体是以其字段为参数的函数，多个字段被包装为元组载荷。
+/// 记录变体接受单个记录作为载荷。
+fn variant_constructor(tag: &str, kind: &VariantKind) -> Value {
+    let arity = match kind {
+        VariantKind::Unit => return Value::Variant(tag.to_string(), Box::new(Value::Unit)),
+        VariantKind::Tuple(fields) => fields.len(),
+        VariantKind::Record(_) => 1,
+    };
+
+    let name = tag.to_string();
+    let tag = name.clone();
+    Value::Constructor(Rc::new(Constructor {
+        name,
+        func: Box::new(move |mut args: Vec<Value>| {
+            if args.len() != arity {
+                return Err(format!(
+                    "{} expects {} argument(s), got {}",
+                    tag,
+                    arity,
+                    args.len()
+                ));
+            }
+            let payload = match args.len() {
+                0 => Value::Unit,
+                1 => args.pop().unwrap_or(Value::Unit),
+                _ => Value::Tuple(Rc::new(args)),
+            };
+            Ok(Value::Variant(tag.clone(), Box::new(payload)))
+        }),
+    }))
+}
+
 /// Closure for AST evaluation.
 /// 用于 AST 求值的闭包。
 ///
@@ -2008,10 +2871,23 @@ pub struct AstClosure {
     pub params: Vec<Param>,
     /// Function body expression / 函数体表达式
     pub body: Expr,
+    /// Name the closure is bound to while its body runs, for `fn` items / `fn` 项在函数体执行期间绑定到自身的名称
+    pub name: Option<String>,
     /// Captured environment / 捕获的环境
     pub env: Rc<AstEnv>,
 }
 
+impl AstClosure {
+    /// Get the parameter names, `_` for destructuring patterns.
+    /// 获取参数名称，解构模式为 `_`。
+    pub fn param_names(&self) -> Vec<String> {
+        self.params
+            .iter()
+            .map(|p| pattern_name(&p.pattern))
+            .collect()
+    }
+}
+
 fn pattern_name(pattern: &Pattern) -> String {
     match &pattern.kind {
         PatternKind::Var(ident) => ident.name.clone(),
@@ -2019,6 +2895,56 @@ fn pattern_name(pattern: &Pattern) -> String {
     }
 }
 
+/// Error raised by `throw` or a failed `assert` with `message`.
+/// 由 `throw` 或失败的 `assert` 以 `message` 引发的错误。
+fn thrown(message: &Value) -> EvalError {
+    let message = match message {
+        Value::String(s) => s.to_string(),
+        other => format!("{other:?}"),
+    };
+    EvalError::Thrown {
+        message,
+        span: None,
+    }
+}
+
+/// Environment for a call of `closure`: its captured scope, itself under its
+/// own name, and the arguments bound to their parameters.
+/// 调用 `closure` 的环境：其捕获的作用域、以自身名称绑定的闭包，以及绑定到参数的实参。
+fn call_env(closure: &Rc<AstClosure>, args: Vec<Value>) -> AstEnv {
+    let mut env = AstEnv::child(closure.env.clone());
+    if let Some(name) = &closure.name {
+        env.define(name.clone(), Value::AstClosure(closure.clone()));
+    }
+    bind_params(&mut env, &closure.params, args);
+    env
+}
+
+/// Closure that has received the leading `args` of `closure` and awaits the rest.
+/// 已接收 `closure` 的前几个参数 `args`、等待剩余参数的闭包。
+fn partial_closure(closure: &Rc<AstClosure>, args: Vec<Value>) -> Value {
+    let supplied = args.len();
+    Value::AstClosure(Rc::new(AstClosure {
+        name: None,
+        params: closure.params[supplied..].to_vec(),
+        body: closure.body.clone(),
+        env: Rc::new(call_env(closure, args)),
+    }))
+}
+
+/// Bind closure arguments to their parameter names in `env`.
+/// 将闭包实参绑定到 `env` 中对应的参数名。
+fn bind_params(env: &mut AstEnv, params: &[Param], args: Vec<Value>) {
+    for (param, arg) in params.iter().zip(args) {
+        let name = pattern_name(&param.pattern);
+        if param.is_lazy {
+            env.define_lazy(name, arg);
+        } else {
+            env.define(name, arg);
+        }
+    }
+}
+
 fn values_equal(a: &Value, b: &Value) -> bool {
     match (a, b) {
         (Value::Int(x), Value::Int(y)) => x == y,
@@ -2039,13 +2965,5 @@ fn values_equal(a: &Value, b: &Value) -> bool {
 }
 
 fn compare(a: &Value, b: &Value) -> Result<std::cmp::Ordering, EvalError> {
-    match (a, b) {
-        (Value::Int(x), Value::Int(y)) => Ok(x.cmp(y)),
-        (Value::Float(x), Value::Float(y)) => x
-            .partial_cmp(y)
-            .ok_or_else(|| EvalError::TypeError("cannot compare NaN".to_string())),
-        (Value::String(x), Value::String(y)) => Ok(x.cmp(y)),
-        (Value::Char(x), Value::Char(y)) => Ok(x.cmp(y)),
-        _ => Err(EvalError::TypeError("cannot compare".to_string())),
-    }
+    a.compare(b).map_err(EvalError::TypeError)
 }
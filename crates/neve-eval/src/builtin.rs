@@ -11,7 +11,7 @@
 //! - **List operations**: len, head, tail, reverse, etc. / 列表操作
 //! - **String operations**: chars, words, lines, trim, split, join, etc. / 字符串操作
 //! - **Math**: abs, min, max, floor, ceil, round, sqrt, pow / 数学函数
-//! - **Option/Result helpers**: isSome, isNone, unwrap, unwrapOr, isOk, isErr / 可选值/结果辅助函数
+//! - **Option/Result helpers**: Some, None, Ok, Err, isSome, isNone, unwrap, unwrapOr, isOk, isErr / 可选值/结果辅助函数
 //! - **Record operations**: keys, values, hasField, getField, setField / 记录操作
 //! - **Type checking**: typeOf, isInt, isFloat, isBool, isString, etc. / 类型检查
 //! - **Assertion/debugging**: assert, assertEq, trace / 断言/调试
@@ -21,10 +21,51 @@
 //! - **JSON**: toJSON, fromJSON / JSON 操作
 //! - **Bitwise operations**: bitAnd, bitOr, bitXor, etc. / 位运算
 
-use crate::value::{BuiltinFn, Value};
+use crate::value::{BuiltinFn, Value, fn_signature};
 use neve_derive::Derivation;
 use std::rc::Rc;
 
+/// Which built-in functions an evaluator exposes.
+/// 求值器公开哪些内置函数。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BuiltinSet {
+    /// Every builtin, plus imports. / 所有内置函数以及导入。
+    #[default]
+    Full,
+    /// Only builtins without side effects; imports are rejected.
+    /// 仅包含无副作用的内置函数；导入会被拒绝。
+    Pure,
+}
+
+/// Builtins that touch the outside world: I/O, the environment and the
+/// filesystem. `import` stands for module loading.
+/// 与外部世界交互的内置函数：I/O、环境变量和文件系统。`import` 代表模块加载。
+const IMPURE_BUILTINS: &[&str] = &[
+    "print",
+    "import",
+    "readFile",
+    "writeFile",
+    "readDir",
+    "pathExists",
+    "getEnv",
+    "fetchurl",
+];
+
+impl BuiltinSet {
+    /// Check whether the builtin `name` is available in this set.
+    /// 检查内置函数 `name` 是否在此集合中可用。
+    ///
+    /// Names under the `io.` namespace count as impure as well, so the same
+    /// check can filter the standard library.
+    /// `io.` 命名空间下的名称同样视为非纯的，因此同一检查也可用于过滤标准库。
+    pub fn allows(self, name: &str) -> bool {
+        match self {
+            BuiltinSet::Full => true,
+            BuiltinSet::Pure => !IMPURE_BUILTINS.contains(&name) && !name.starts_with("io."),
+        }
+    }
+}
+
 /// Get all built-in functions.
 /// 获取所有内置函数。
 pub fn builtins() -> Vec<(&'static str, Value)> {
@@ -593,6 +634,32 @@ pub fn builtins() -> Vec<(&'static str, Value)> {
                 },
             }),
         ),
+        // === Option/Result constructors ===
+        (
+            "Some",
+            Value::Builtin(BuiltinFn {
+                name: "Some",
+                arity: 1,
+                func: |args| Ok(Value::Some(Box::new(args[0].clone()))),
+            }),
+        ),
+        ("None", Value::None),
+        (
+            "Ok",
+            Value::Builtin(BuiltinFn {
+                name: "Ok",
+                arity: 1,
+                func: |args| Ok(Value::Ok(Box::new(args[0].clone()))),
+            }),
+        ),
+        (
+            "Err",
+            Value::Builtin(BuiltinFn {
+                name: "Err",
+                arity: 1,
+                func: |args| Ok(Value::Err(Box::new(args[0].clone()))),
+            }),
+        ),
         // === Option/Result helpers ===
         (
             "isSome",
@@ -760,49 +827,27 @@ pub fn builtins() -> Vec<(&'static str, Value)> {
             }),
         ),
         // === Type checking ===
+        // `typeOf` forces thunks, so AstEvaluator::apply intercepts it.
         (
             "typeOf",
             Value::Builtin(BuiltinFn {
                 name: "typeOf",
                 arity: 1,
-                func: |args| {
-                    let type_name = match &args[0] {
-                        Value::Unit => "Unit",
-                        Value::Bool(_) => "Bool",
-                        Value::Int(_) => "Int",
-                        Value::Float(_) => "Float",
-                        Value::Char(_) => "Char",
-                        Value::String(_) => "String",
-                        Value::List(_) => "List",
-                        Value::Tuple(_) => "Tuple",
-                        Value::Record(_) => "Record",
-                        Value::Map(_) => "Map",
-                        Value::Set(_) => "Set",
-                        Value::Closure { .. } => "Function",
-                        Value::AstClosure(_) => "Function",
-                        Value::Builtin(_) => "Function",
-                        Value::BuiltinFn(_, _) => "Function",
-                        Value::Variant(tag, _) => tag.as_str(),
-                        Value::Some(_) => "Some",
-                        Value::None => "None",
-                        Value::Ok(_) => "Ok",
-                        Value::Err(_) => "Err",
-                        Value::Thunk(_) => "Thunk",
-                    };
-                    Ok(Value::String(Rc::new(type_name.to_string())))
-                },
+                func: |args| Ok(Value::String(Rc::new(args[0].type_of().to_string()))),
             }),
         ),
         // === Assertion/debugging ===
+        // `assert(cond, msg, value)` is intercepted by AstEvaluator::apply like `throw`.
         (
             "assert",
             Value::Builtin(BuiltinFn {
                 name: "assert",
-                arity: 1,
-                func: |args| match &args[0] {
-                    Value::Bool(true) => Ok(Value::Unit),
-                    Value::Bool(false) => Err("assertion failed".to_string()),
-                    _ => Err("assert expects a Bool".to_string()),
+                arity: 3,
+                func: |args| match (&args[0], &args[1]) {
+                    (Value::Bool(true), _) => Ok(args[2].clone()),
+                    (Value::Bool(false), Value::String(msg)) => Err(msg.to_string()),
+                    (Value::Bool(false), other) => Err(format!("{:?}", other)),
+                    _ => Err("assert expects a Bool condition".to_string()),
                 },
             }),
         ),
@@ -911,7 +956,9 @@ pub fn builtins() -> Vec<(&'static str, Value)> {
                         Value::Closure { .. }
                             | Value::AstClosure(_)
                             | Value::Builtin(_)
+                            | Value::PartialBuiltin(_)
                             | Value::BuiltinFn(_, _)
+                            | Value::Constructor(_)
                     )))
                 },
             }),
@@ -1078,16 +1125,14 @@ pub fn builtins() -> Vec<(&'static str, Value)> {
             }),
         ),
         // === Sequence/error handling ===
+        // `seq` and `deepSeq` force thunks, so AstEvaluator::apply intercepts
+        // them; these fallbacks only order the arguments.
         (
             "seq",
             Value::Builtin(BuiltinFn {
                 name: "seq",
                 arity: 2,
-                func: |args| {
-                    // seq forces evaluation of first arg, returns second
-                    let _ = &args[0]; // Force evaluation
-                    Ok(args[1].clone())
-                },
+                func: |args| Ok(args[1].clone()),
             }),
         ),
         (
@@ -1095,21 +1140,11 @@ pub fn builtins() -> Vec<(&'static str, Value)> {
             Value::Builtin(BuiltinFn {
                 name: "deepSeq",
                 arity: 2,
-                func: |args| {
-                    // deepSeq forces deep evaluation of first arg, returns second
-                    fn force_deep(v: &Value) {
-                        match v {
-                            Value::List(items) => items.iter().for_each(force_deep),
-                            Value::Record(fields) => fields.values().for_each(force_deep),
-                            Value::Tuple(items) => items.iter().for_each(force_deep),
-                            _ => {}
-                        }
-                    }
-                    force_deep(&args[0]);
-                    Ok(args[1].clone())
-                },
+                func: |args| Ok(args[1].clone()),
             }),
         ),
+        // `throw` is intercepted by AstEvaluator::apply, which raises
+        // EvalError::Thrown with the message; this is the fallback.
         (
             "throw",
             Value::Builtin(BuiltinFn {
@@ -1121,13 +1156,23 @@ pub fn builtins() -> Vec<(&'static str, Value)> {
                 },
             }),
         ),
+        // `tryEval` evaluates its argument lazily and catches errors, so it is
+        // intercepted by AstEvaluator::apply.
+        (
+            "tryEval",
+            Value::Builtin(BuiltinFn {
+                name: "tryEval",
+                arity: 1,
+                func: |_| Err("tryEval requires evaluator context".to_string()),
+            }),
+        ),
         // === JSON-like operations ===
         (
             "toJSON",
             Value::Builtin(BuiltinFn {
                 name: "toJSON",
                 arity: 1,
-                func: |args| Ok(Value::String(Rc::new(value_to_json(&args[0])))),
+                func: |args| value_to_json(&args[0]).map(|json| Value::String(Rc::new(json))),
             }),
         ),
         (
@@ -1136,7 +1181,7 @@ pub fn builtins() -> Vec<(&'static str, Value)> {
                 name: "fromJSON",
                 arity: 1,
                 func: |args| match &args[0] {
-                    Value::String(s) => json_to_value(s.as_str()),
+                    Value::String(s) => Value::from_json_str(s),
                     _ => Err("fromJSON expects a string".to_string()),
                 },
             }),
@@ -1256,6 +1301,30 @@ pub fn builtins() -> Vec<(&'static str, Value)> {
                 func: |_| Err("foldr requires evaluator context".to_string()),
             }),
         ),
+        (
+            "foldl1",
+            Value::Builtin(BuiltinFn {
+                name: "foldl1",
+                arity: 2,
+                func: |_| Err("foldl1 requires evaluator context".to_string()),
+            }),
+        ),
+        (
+            "foldr1",
+            Value::Builtin(BuiltinFn {
+                name: "foldr1",
+                arity: 2,
+                func: |_| Err("foldr1 requires evaluator context".to_string()),
+            }),
+        ),
+        (
+            "scanl",
+            Value::Builtin(BuiltinFn {
+                name: "scanl",
+                arity: 3,
+                func: |_| Err("scanl requires evaluator context".to_string()),
+            }),
+        ),
         (
             "genList",
             Value::Builtin(BuiltinFn {
@@ -1503,213 +1572,13 @@ pub fn builtins() -> Vec<(&'static str, Value)> {
     ]
 }
 
-/// Parse JSON string to value.
-fn json_to_value(s: &str) -> Result<Value, String> {
-    let s = s.trim();
-
-    if s.is_empty() {
-        return Err("empty JSON string".to_string());
-    }
-
-    // Parse based on first character
-    // Safe: we just checked s is not empty
-    // 安全：我们刚检查过 s 不为空
-    match s.chars().next().expect("string is non-empty") {
-        'n' if s == "null" => Ok(Value::None),
-        't' if s == "true" => Ok(Value::Bool(true)),
-        'f' if s == "false" => Ok(Value::Bool(false)),
-        '"' => {
-            // Parse string
-            if s.len() < 2 || !s.ends_with('"') {
-                return Err("invalid JSON string".to_string());
-            }
-            let inner = &s[1..s.len() - 1];
-            // Handle escape sequences
-            let mut result = String::new();
-            let mut chars = inner.chars().peekable();
-            while let Some(c) = chars.next() {
-                if c == '\\' {
-                    match chars.next() {
-                        Some('n') => result.push('\n'),
-                        Some('r') => result.push('\r'),
-                        Some('t') => result.push('\t'),
-                        Some('\\') => result.push('\\'),
-                        Some('"') => result.push('"'),
-                        Some('/') => result.push('/'),
-                        Some('u') => {
-                            // Unicode escape
-                            let hex: String = chars.by_ref().take(4).collect();
-                            if let Ok(code) = u32::from_str_radix(&hex, 16)
-                                && let Some(c) = char::from_u32(code)
-                            {
-                                result.push(c);
-                            }
-                        }
-                        _ => return Err("invalid escape sequence".to_string()),
-                    }
-                } else {
-                    result.push(c);
-                }
-            }
-            Ok(Value::String(Rc::new(result)))
-        }
-        '[' => {
-            // Parse array
-            if !s.ends_with(']') {
-                return Err("invalid JSON array".to_string());
-            }
-            let inner = s[1..s.len() - 1].trim();
-            if inner.is_empty() {
-                return Ok(Value::List(Rc::new(Vec::new())));
-            }
-
-            let elements = split_json_elements(inner)?;
-            let values: Result<Vec<_>, _> = elements.iter().map(|e| json_to_value(e)).collect();
-            Ok(Value::List(Rc::new(values?)))
-        }
-        '{' => {
-            // Parse object
-            if !s.ends_with('}') {
-                return Err("invalid JSON object".to_string());
-            }
-            let inner = s[1..s.len() - 1].trim();
-            if inner.is_empty() {
-                return Ok(Value::Record(Rc::new(std::collections::HashMap::new())));
-            }
-
-            let pairs = split_json_elements(inner)?;
-            let mut record = std::collections::HashMap::new();
-            for pair in pairs {
-                let pair = pair.trim();
-                if let Some(colon_pos) = find_json_colon(pair) {
-                    let key = pair[..colon_pos].trim();
-                    let value = pair[colon_pos + 1..].trim();
-
-                    // Key must be a string
-                    if !key.starts_with('"') || !key.ends_with('"') {
-                        return Err("JSON object keys must be strings".to_string());
-                    }
-                    let key_str = &key[1..key.len() - 1];
-                    let value = json_to_value(value)?;
-                    record.insert(key_str.to_string(), value);
-                } else {
-                    return Err("invalid JSON object pair".to_string());
-                }
-            }
-            Ok(Value::Record(Rc::new(record)))
-        }
-        c if c == '-' || c.is_ascii_digit() => {
-            // Parse number
-            if s.contains('.') || s.contains('e') || s.contains('E') {
-                s.parse::<f64>()
-                    .map(Value::Float)
-                    .map_err(|_| "invalid JSON number".to_string())
-            } else {
-                s.parse::<i64>()
-                    .map(Value::Int)
-                    .map_err(|_| "invalid JSON number".to_string())
-            }
-        }
-        _ => Err(format!("unexpected JSON token: {}", s)),
-    }
-}
-
-/// Split JSON array/object elements respecting nesting.
-fn split_json_elements(s: &str) -> Result<Vec<&str>, String> {
-    let mut elements = Vec::new();
-    let mut start = 0;
-    let mut depth = 0;
-    let mut in_string = false;
-    let mut escape = false;
-
-    for (i, c) in s.char_indices() {
-        if escape {
-            escape = false;
-            continue;
-        }
-
-        match c {
-            '\\' if in_string => escape = true,
-            '"' => in_string = !in_string,
-            '[' | '{' if !in_string => depth += 1,
-            ']' | '}' if !in_string => depth -= 1,
-            ',' if !in_string && depth == 0 => {
-                elements.push(s[start..i].trim());
-                start = i + 1;
-            }
-            _ => {}
-        }
-    }
-
-    if start < s.len() {
-        elements.push(s[start..].trim());
-    }
-
-    Ok(elements)
-}
-
-/// Find the colon in a JSON key-value pair respecting strings.
-fn find_json_colon(s: &str) -> Option<usize> {
-    let mut in_string = false;
-    let mut escape = false;
-
-    for (i, c) in s.char_indices() {
-        if escape {
-            escape = false;
-            continue;
-        }
-
-        match c {
-            '\\' if in_string => escape = true,
-            '"' => in_string = !in_string,
-            ':' if !in_string => return Some(i),
-            _ => {}
-        }
-    }
-
-    None
-}
-
-/// Convert a value to JSON string.
-fn value_to_json(v: &Value) -> String {
-    match v {
-        Value::Unit => "null".to_string(),
-        Value::Bool(b) => b.to_string(),
-        Value::Int(n) => n.to_string(),
-        Value::Float(f) => {
-            if f.is_nan() {
-                "null".to_string()
-            } else if f.is_infinite() {
-                if *f > 0.0 {
-                    "1e309".to_string()
-                } else {
-                    "-1e309".to_string()
-                }
-            } else {
-                f.to_string()
-            }
-        }
-        Value::String(s) => format!(
-            "\"{}\"",
-            s.replace('\\', "\\\\")
-                .replace('"', "\\\"")
-                .replace('\n', "\\n")
-        ),
-        Value::List(items) => {
-            let parts: Vec<String> = items.iter().map(value_to_json).collect();
-            format!("[{}]", parts.join(","))
-        }
-        Value::Record(fields) => {
-            let parts: Vec<String> = fields
-                .iter()
-                .map(|(k, v)| format!("\"{}\":{}", k, value_to_json(v)))
-                .collect();
-            format!("{{{}}}", parts.join(","))
-        }
-        Value::None => "null".to_string(),
-        Value::Some(v) => value_to_json(v),
-        _ => "null".to_string(),
-    }
+/// Convert a value to JSON text for the `toJSON` builtins.
+/// 为 `toJSON` 内置函数将值转换为 JSON 文本。
+///
+/// Unlike [`Value::to_json`], `Some(x)` is written as `x` and `None` as `null`.
+/// 与 [`Value::to_json`] 不同，`Some(x)` 写为 `x`，`None` 写为 `null`。
+pub fn value_to_json(v: &Value) -> Result<String, String> {
+    v.json_value(true).map(|json| json.to_string())
 }
 
 /// Format a value for display (user-friendly, not debug).
@@ -1753,10 +1622,12 @@ pub fn format_value(v: &Value) -> String {
             let parts: Vec<String> = set.iter().cloned().collect();
             format!("Set{{ {} }}", parts.join(", "))
         }
-        Value::Closure { .. } => "<function>".to_string(),
-        Value::AstClosure(_) => "<function>".to_string(),
+        Value::Closure { params, .. } => fn_signature(params.iter().map(|p| p.name.clone())),
+        Value::AstClosure(closure) => fn_signature(closure.param_names()),
         Value::Builtin(f) => format!("<builtin:{}>", f.name),
+        Value::PartialBuiltin(p) => format!("<builtin:{}>", p.builtin.name),
         Value::BuiltinFn(name, _) => format!("<builtin:{name}>"),
+        Value::Constructor(c) => format!("<constructor:{}>", c.name),
         Value::Variant(tag, payload) => {
             if matches!(**payload, Value::Unit) {
                 tag.clone()
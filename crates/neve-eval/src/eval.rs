@@ -6,7 +6,10 @@
 //! 本模块实现了高级中间表示（HIR）的求值器。
 //! 它提供了一个带有尾调用优化的树遍历解释器。
 
+use crate::value::{PartialBuiltin, fn_signature};
 use crate::{Environment, Value};
+use neve_common::{Span, find_similar_name};
+use neve_diagnostic::Diagnostic;
 use neve_hir::{
     BinOp, DefId, Expr, ExprKind, FnDef, Item, ItemKind, Literal, LocalId, Module, UnaryOp,
 };
@@ -45,6 +48,48 @@ pub enum EvalError {
     /// Wrong number of arguments error / 参数数量错误
     #[error("wrong number of arguments")]
     WrongArity,
+
+    /// Two imports bind the same name to different sources / 两个导入将同一名称绑定到不同来源
+    #[error("{}", .0.message)]
+    ImportConflict(Box<Diagnostic>),
+
+    /// Error raised on purpose by `throw` or a failed `assert` / 由 `throw` 或失败的 `assert` 主动引发的错误
+    #[error("{message}")]
+    Thrown {
+        /// Message given by the program / 程序给出的消息
+        message: String,
+        /// Span of the call that raised it, if known / 引发错误的调用位置（如已知）
+        span: Option<Span>,
+    },
+
+    /// Too many nested function calls / 嵌套函数调用过多
+    #[error("recursion limit of {0} nested calls exceeded")]
+    RecursionLimit(usize),
+
+    /// Evaluation fuel ran out / 求值燃料耗尽
+    #[error("evaluation step limit exceeded")]
+    StepLimitExceeded,
+
+    /// Source failed to parse / 源码解析失败
+    #[error("parse error: {}", .0.first().map(|d| d.message.as_str()).unwrap_or("invalid syntax"))]
+    ParseError(Vec<Diagnostic>),
+}
+
+impl EvalError {
+    /// Attach `span` to a thrown error that has no location yet.
+    /// 为尚无位置的抛出错误附加 `span`。
+    pub(crate) fn at(self, span: Span) -> Self {
+        match self {
+            EvalError::Thrown {
+                message,
+                span: None,
+            } => EvalError::Thrown {
+                message,
+                span: Some(span),
+            },
+            other => other,
+        }
+    }
 }
 
 /// Result of evaluating an expression with tail call detection.
@@ -210,10 +255,14 @@ impl Evaluator {
             ExprKind::Field(base, field) => {
                 let base_val = self.eval(base)?;
                 match base_val {
-                    Value::Record(fields) => fields
-                        .get(field)
-                        .cloned()
-                        .ok_or_else(|| EvalError::TypeError(format!("no field '{}'", field))),
+                    Value::Record(fields) => fields.get(field).cloned().ok_or_else(|| {
+                        let suggestion =
+                            find_similar_name(field, fields.keys().map(String::as_str));
+                        EvalError::TypeError(with_suggestion(
+                            format!("no field '{}'", field),
+                            suggestion,
+                        ))
+                    }),
                     _ => Err(EvalError::TypeError("not a record".to_string())),
                 }
             }
@@ -274,7 +323,7 @@ impl Evaluator {
                 Ok(result)
             }
 
-            ExprKind::Match(scrutinee, arms) => {
+            ExprKind::Match(scrutinee, arms, _) => {
                 let val = self.eval(scrutinee)?;
                 for arm in arms {
                     if let Some(bindings) = self.match_pattern(&arm.pattern, &val) {
@@ -456,15 +505,7 @@ impl Evaluator {
     }
 
     fn compare(&self, a: &Value, b: &Value) -> Result<std::cmp::Ordering, EvalError> {
-        match (a, b) {
-            (Value::Int(x), Value::Int(y)) => Ok(x.cmp(y)),
-            (Value::Float(x), Value::Float(y)) => x
-                .partial_cmp(y)
-                .ok_or_else(|| EvalError::TypeError("cannot compare NaN".to_string())),
-            (Value::String(x), Value::String(y)) => Ok(x.cmp(y)),
-            (Value::Char(x), Value::Char(y)) => Ok(x.cmp(y)),
-            _ => Err(EvalError::TypeError("cannot compare".to_string())),
-        }
+        a.compare(b).map_err(EvalError::TypeError)
     }
 
     fn apply(&mut self, func: Value, args: Vec<Value>) -> Result<Value, EvalError> {
@@ -503,11 +544,26 @@ impl Evaluator {
                     }
                 }
                 Value::Builtin(builtin) => {
+                    // Too few arguments: return a partially-applied builtin
+                    // 参数不足：返回部分应用的内置函数
+                    if !current_args.is_empty() && current_args.len() < builtin.arity {
+                        return Ok(Value::PartialBuiltin(Rc::new(PartialBuiltin {
+                            builtin,
+                            args: current_args,
+                        })));
+                    }
                     if current_args.len() != builtin.arity {
                         return Err(EvalError::WrongArity);
                     }
                     return (builtin.func)(&current_args).map_err(EvalError::TypeError);
                 }
+                Value::PartialBuiltin(partial) => {
+                    let mut args = partial.args.clone();
+                    args.append(&mut current_args);
+                    current_func = Value::Builtin(partial.builtin.clone());
+                    current_args = args;
+                    continue;
+                }
                 Value::AstClosure(_) => {
                     // AstClosure not supported in HIR evaluator
                     return Err(EvalError::TypeError(
@@ -562,7 +618,7 @@ impl Evaluator {
             }
 
             // Match: evaluate scrutinee, match pattern, then evaluate arm with TCO
-            ExprKind::Match(scrutinee, arms) => {
+            ExprKind::Match(scrutinee, arms, _) => {
                 let scrutinee_val = self.eval(scrutinee)?;
 
                 for arm in arms {
@@ -637,9 +693,17 @@ impl Evaluator {
                 PatternKind::Record(fields) => {
                     fields.iter().map(|(_, pat)| estimate_bindings(pat)).sum()
                 }
-                PatternKind::Constructor(_, patterns) => {
+                PatternKind::Constructor(_, _, patterns) => {
                     patterns.iter().map(estimate_bindings).sum()
                 }
+                PatternKind::ListRest(init, rest, tail) => init
+                    .iter()
+                    .chain(rest.as_deref())
+                    .chain(tail)
+                    .map(estimate_bindings)
+                    .sum(),
+                PatternKind::Or(patterns) => patterns.first().map(estimate_bindings).unwrap_or(0),
+                PatternKind::Binding(_, _, pattern) => 1 + estimate_bindings(pattern),
             }
         }
 
@@ -697,16 +761,61 @@ impl Evaluator {
                     None
                 }
             }
-            PatternKind::Constructor(_, patterns) => {
-                // Match Option/Result constructors
-                match (patterns.as_slice(), value) {
-                    ([p], Value::Some(v)) => self.match_pattern(p, v),
-                    ([], Value::None) => Some(Vec::new()),
-                    ([p], Value::Ok(v)) => self.match_pattern(p, v),
-                    ([p], Value::Err(v)) => self.match_pattern(p, v),
+            PatternKind::ListRest(init, rest, tail) => {
+                let Value::List(values) = value else {
+                    return None;
+                };
+                if values.len() < init.len() + tail.len() {
+                    return None;
+                }
+                let split = values.len() - tail.len();
+                let mut bindings = Vec::new();
+                for (p, v) in init.iter().zip(values.iter()) {
+                    bindings.extend(self.match_pattern(p, v)?);
+                }
+                if let Some(rest) = rest {
+                    let middle = Value::List(Rc::new(values[init.len()..split].to_vec()));
+                    bindings.extend(self.match_pattern(rest, &middle)?);
+                }
+                for (p, v) in tail.iter().zip(values[split..].iter()) {
+                    bindings.extend(self.match_pattern(p, v)?);
+                }
+                Some(bindings)
+            }
+            PatternKind::Constructor(_, name, patterns) => {
+                match (name.as_str(), patterns.as_slice(), value) {
+                    ("Some", [p], Value::Some(v)) => self.match_pattern(p, v),
+                    ("None", [], Value::None) => Some(Vec::new()),
+                    ("Ok", [p], Value::Ok(v)) => self.match_pattern(p, v),
+                    ("Err", [p], Value::Err(v)) => self.match_pattern(p, v),
+                    (_, [], Value::Variant(tag, payload)) if tag == name => {
+                        matches!(**payload, Value::Unit).then(Vec::new)
+                    }
+                    (_, [p], Value::Variant(tag, payload)) if tag == name => {
+                        self.match_pattern(p, payload)
+                    }
+                    (_, patterns, Value::Variant(tag, payload)) if tag == name => {
+                        let Value::Tuple(values) = &**payload else {
+                            return None;
+                        };
+                        if patterns.len() != values.len() {
+                            return None;
+                        }
+                        let mut bindings = Vec::new();
+                        for (p, v) in patterns.iter().zip(values.iter()) {
+                            bindings.extend(self.match_pattern(p, v)?);
+                        }
+                        Some(bindings)
+                    }
                     _ => None,
                 }
             }
+            PatternKind::Or(patterns) => patterns.iter().find_map(|p| self.match_pattern(p, value)),
+            PatternKind::Binding(id, _, pattern) => {
+                let mut bindings = self.match_pattern(pattern, value)?;
+                bindings.push((*id, value.clone()));
+                Some(bindings)
+            }
         }
     }
 
@@ -764,9 +873,11 @@ impl Evaluator {
                 }
             }
             Value::Builtin(b) => format!("<builtin:{}>", b.name),
+            Value::PartialBuiltin(p) => format!("<builtin:{}>", p.builtin.name),
             Value::BuiltinFn(name, _) => format!("<builtin:{}>", name),
-            Value::AstClosure(_) => "<function>".to_string(),
-            Value::Closure { .. } => "<function>".to_string(),
+            Value::Constructor(c) => format!("<constructor:{}>", c.name),
+            Value::AstClosure(closure) => fn_signature(closure.param_names()),
+            Value::Closure { params, .. } => fn_signature(params.iter().map(|p| p.name.clone())),
             Value::Thunk(thunk) => {
                 use crate::value::ThunkState;
                 match &*thunk.state() {
@@ -785,6 +896,15 @@ impl Default for Evaluator {
     }
 }
 
+/// Append a "did you mean?" hint to an error message when a suggestion exists.
+/// 存在建议时，在错误信息后追加"你是不是想要？"提示。
+pub(crate) fn with_suggestion(message: String, suggestion: Option<&str>) -> String {
+    match suggestion {
+        Some(name) => format!("{} (did you mean `{}`?)", message, name),
+        None => message,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -957,6 +1077,7 @@ mod tests {
                 span,
             },
             body,
+            is_let: false,
         };
 
         evaluator
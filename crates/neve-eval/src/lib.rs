@@ -23,7 +23,12 @@
 //! - Specificity scoring for pattern ordering / 用于模式排序的特异性评分
 //! - Fast-path detection for common patterns / 常见模式的快速路径检测
 //! - Match expression analysis hints / 匹配表达式分析提示
+//! - Decision-tree compilation for many-arm matches / 多分支匹配的决策树编译
+//!
+//! The `analysis` module turns these into per-match reports for tooling.
+//! `analysis` 模块将这些结果转换为供工具使用的逐个匹配报告。
 
+pub mod analysis;
 pub mod ast_eval;
 mod builtin;
 mod env;
@@ -31,9 +36,52 @@ mod eval;
 pub mod pattern;
 pub mod value;
 
-pub use ast_eval::{AstEnv, AstEvaluator};
-pub use builtin::builtins;
+pub use ast_eval::{AstEnv, AstEvaluator, DEFAULT_RECURSION_LIMIT};
+pub use builtin::{BuiltinSet, builtins, value_to_json};
 pub use env::Environment;
 pub use eval::{EvalError, Evaluator};
-pub use pattern::{MatchHints, Specificity, analyze_match, is_irrefutable, pattern_specificity};
-pub use value::{AstClosure, BuiltinFn, Value};
+pub use pattern::{
+    DecisionTree, MatchHints, Specificity, analyze_match, compile_match, is_irrefutable,
+    pattern_specificity,
+};
+pub use value::{AstClosure, BuiltinFn, Constructor, PartialBuiltin, Value};
+
+use std::rc::Rc;
+
+/// Parse and evaluate source code with all builtins in scope.
+/// 解析并求值源码，所有内置函数均可用。
+///
+/// Parse errors are returned as [`EvalError::ParseError`].
+/// 解析错误以 [`EvalError::ParseError`] 返回。
+pub fn eval_str(source: &str) -> Result<Value, EvalError> {
+    eval_str_with_env(source, Rc::new(AstEnv::with_builtins()))
+}
+
+/// Parse and evaluate source code in the given environment.
+/// 在给定环境中解析并求值源码。
+///
+/// The source may be a sequence of items or a single bare expression such as `1 + 2`.
+/// 源码可以是一系列项，也可以是单个裸表达式，例如 `1 + 2`。
+pub fn eval_str_with_env(source: &str, env: Rc<AstEnv>) -> Result<Value, EvalError> {
+    let (file, diagnostics) = neve_parser::parse(source);
+    if diagnostics.is_empty() && !file.items.is_empty() {
+        return AstEvaluator::with_env(env).eval_file(&file);
+    }
+
+    // Not a sequence of items; parse it as a single bare expression instead
+    // 不是项序列；改为作为单个裸表达式解析
+    if !source.trim().is_empty() {
+        let (expr, expr_diagnostics) = neve_parser::parse_expr(source);
+        if expr_diagnostics.is_empty() {
+            return AstEvaluator::with_env(env).eval_expr(&expr);
+        }
+        if diagnostics.is_empty() {
+            return Err(EvalError::ParseError(expr_diagnostics));
+        }
+    }
+
+    if !diagnostics.is_empty() {
+        return Err(EvalError::ParseError(diagnostics));
+    }
+    AstEvaluator::with_env(env).eval_file(&file)
+}
@@ -7,8 +7,11 @@
 //! - Pattern specificity calculation / 模式特异性计算
 //! - Fast-path detection for common patterns / 常见模式的快速路径检测
 //! - Match arm ordering hints / 匹配分支排序提示
+//! - Decision trees for many-arm matches / 多分支匹配的决策树
 
-use neve_syntax::{LiteralPattern, Pattern, PatternKind};
+use crate::Value;
+use neve_syntax::{LiteralPattern, MatchArm, Pattern, PatternKind};
+use std::collections::HashMap;
 
 /// Pattern specificity score - higher means more specific.
 /// 模式特异性分数 - 越高表示越具体。
@@ -308,6 +311,154 @@ pub fn analyze_match(patterns: &[&Pattern]) -> MatchHints {
     }
 }
 
+/// Minimum number of arms before a match is compiled to a decision tree.
+/// 将匹配编译为决策树所需的最少分支数。
+///
+/// Below this, trying each arm linearly is cheaper than building the switch.
+/// 低于此值时，线性尝试每个分支比构建 switch 更便宜。
+pub const DECISION_TREE_MIN_ARMS: usize = 8;
+
+/// The head of a value that a decision tree switches on.
+/// 决策树用于分派的值头部。
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SwitchKey {
+    /// Integer literal / 整数字面量
+    Int(i64),
+    /// String literal / 字符串字面量
+    String(String),
+    /// Character literal / 字符字面量
+    Char(char),
+    /// Boolean literal / 布尔字面量
+    Bool(bool),
+    /// Constructor tag (`Some`, `None`, `Ok`, `Err`, or a user variant)
+    /// 构造器标签（`Some`、`None`、`Ok`、`Err` 或用户变体）
+    Constructor(String),
+}
+
+impl SwitchKey {
+    /// Get the switch key of a runtime value, if it has one.
+    /// 获取运行时值的分派键（如果有）。
+    pub fn of_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Int(n) => Some(Self::Int(*n)),
+            Value::String(s) => Some(Self::String(s.to_string())),
+            Value::Char(c) => Some(Self::Char(*c)),
+            Value::Bool(b) => Some(Self::Bool(*b)),
+            Value::Some(_) => Some(Self::Constructor("Some".to_string())),
+            Value::None => Some(Self::Constructor("None".to_string())),
+            Value::Ok(_) => Some(Self::Constructor("Ok".to_string())),
+            Value::Err(_) => Some(Self::Constructor("Err".to_string())),
+            Value::Variant(tag, _) => Some(Self::Constructor(tag.clone())),
+            _ => None,
+        }
+    }
+}
+
+/// Get the switch keys a pattern can match.
+/// 获取模式可以匹配的分派键。
+///
+/// Returns `None` when the pattern may match values with any head
+/// (wildcards, variables, tuples, records, ...).
+/// 当模式可能匹配任意头部的值时返回 `None`（通配符、变量、元组、记录等）。
+pub fn pattern_keys(pattern: &Pattern) -> Option<Vec<SwitchKey>> {
+    match &pattern.kind {
+        PatternKind::Literal(lit) => match lit {
+            LiteralPattern::Int(n) => Some(vec![SwitchKey::Int(*n)]),
+            LiteralPattern::String(s) => Some(vec![SwitchKey::String(s.clone())]),
+            LiteralPattern::Char(c) => Some(vec![SwitchKey::Char(*c)]),
+            LiteralPattern::Bool(b) => Some(vec![SwitchKey::Bool(*b)]),
+            // Floats are not hashable; leave them to linear matching
+            // 浮点数不可哈希；交给线性匹配
+            LiteralPattern::Float(_) => None,
+        },
+        PatternKind::Constructor { path, .. } => path
+            .last()
+            .map(|ident| vec![SwitchKey::Constructor(ident.name.clone())]),
+        PatternKind::Or(patterns) => {
+            let mut keys = Vec::new();
+            for p in patterns {
+                keys.extend(pattern_keys(p)?);
+            }
+            Some(keys)
+        }
+        PatternKind::Binding { pattern, .. } => pattern_keys(pattern),
+        _ => None,
+    }
+}
+
+/// A compiled match expression.
+/// 编译后的匹配表达式。
+///
+/// The tree switches on the scrutinee's head (literal or constructor tag) and
+/// yields the arms that could still match, in their original order. Each
+/// candidate is then sub-matched with its full pattern and guard, so
+/// first-match semantics are preserved.
+/// 决策树根据被匹配值的头部（字面量或构造器标签）进行分派，
+/// 并按原始顺序给出仍可能匹配的分支。随后对每个候选分支进行完整模式和守卫的子匹配，
+/// 因此保留了首次匹配语义。
+#[derive(Debug, Clone, Default)]
+pub struct DecisionTree {
+    /// Candidate arms per switch key / 每个分派键的候选分支
+    switch: HashMap<SwitchKey, Vec<usize>>,
+    /// Arms that may match any head / 可能匹配任意头部的分支
+    default: Vec<usize>,
+}
+
+impl DecisionTree {
+    /// Get the candidate arm indices for a value, in source order.
+    /// 按源代码顺序获取值的候选分支索引。
+    pub fn candidates(&self, value: &Value) -> &[usize] {
+        SwitchKey::of_value(value)
+            .and_then(|key| self.switch.get(&key))
+            .unwrap_or(&self.default)
+    }
+
+    /// Number of distinct switch keys.
+    /// 不同分派键的数量。
+    pub fn switch_count(&self) -> usize {
+        self.switch.len()
+    }
+
+    /// Whether switching narrows the arms enough to be worth using.
+    /// 分派是否足以缩小分支范围而值得使用。
+    pub fn is_beneficial(&self) -> bool {
+        self.switch.len() >= 2
+    }
+}
+
+/// Compile a match expression's arms into a decision tree.
+/// 将匹配表达式的分支编译为决策树。
+pub fn compile_match(arms: &[MatchArm]) -> DecisionTree {
+    let mut tree = DecisionTree::default();
+
+    for (index, arm) in arms.iter().enumerate() {
+        match pattern_keys(&arm.pattern) {
+            Some(keys) => {
+                for key in keys {
+                    let candidates = tree
+                        .switch
+                        .entry(key)
+                        .or_insert_with(|| tree.default.clone());
+                    // Or patterns may repeat a key / Or 模式可能重复同一个键
+                    if candidates.last() != Some(&index) {
+                        candidates.push(index);
+                    }
+                }
+            }
+            None => {
+                // Arm may match any head: it's a candidate for every key
+                // 分支可能匹配任意头部：它是每个键的候选
+                tree.default.push(index);
+                for candidates in tree.switch.values_mut() {
+                    candidates.push(index);
+                }
+            }
+        }
+    }
+
+    tree
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
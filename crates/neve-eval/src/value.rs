@@ -7,7 +7,7 @@
 use crate::Environment;
 use neve_hir::{Expr, Param};
 use std::cell::RefCell;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fmt;
 use std::rc::Rc;
 
@@ -131,10 +131,10 @@ pub enum Value {
     Tuple(Rc<Vec<Value>>),
     /// Record value / 记录值
     Record(Rc<HashMap<String, Value>>),
-    /// Map value (immutable hash map) / 映射值（不可变哈希映射）
-    Map(Rc<HashMap<String, Value>>),
-    /// Set value (immutable hash set) / 集合值（不可变哈希集合）
-    Set(Rc<HashSet<String>>),
+    /// Map value (immutable, ordered by key) / 映射值（不可变，按键排序）
+    Map(Rc<BTreeMap<String, Value>>),
+    /// Set value (immutable, ordered) / 集合值（不可变，有序）
+    Set(Rc<BTreeSet<String>>),
 
     // ===== Function types 函数类型 =====
     /// Closure (for HIR evaluation) / 闭包（用于 HIR 求值）
@@ -147,11 +147,15 @@ pub enum Value {
     AstClosure(Rc<AstClosure>),
     /// Built-in function / 内置函数
     Builtin(BuiltinFn),
+    /// Built-in function applied to fewer arguments than its arity / 参数少于元数的部分应用内置函数
+    PartialBuiltin(Rc<PartialBuiltin>),
     /// Built-in function with Rc closure (for stdlib) / 带 Rc 闭包的内置函数（用于标准库）
     BuiltinFn(
         &'static str,
         Rc<dyn Fn(Vec<Value>) -> Result<Value, String>>,
     ),
+    /// Enum variant or struct constructor / 枚举变体或结构体构造器
+    Constructor(Rc<Constructor>),
 
     // ===== Algebraic data types 代数数据类型 =====
     /// Variant/enum value (tag, payload) / 变体/枚举值（标签，载荷）
@@ -182,6 +186,37 @@ pub struct BuiltinFn {
     pub func: fn(&[Value]) -> Result<Value, String>,
 }
 
+/// The constructor of a user-defined enum variant or struct.
+/// 用户定义的枚举变体或结构体的构造器。
+pub struct Constructor {
+    /// Variant or struct name / 变体或结构体名称
+    pub name: String,
+    /// Constructor implementation / 构造器实现
+    pub func: Box<dyn Fn(Vec<Value>) -> Result<Value, String>>,
+}
+
+/// A built-in function that has been applied to some of its arguments.
+/// 已应用部分参数的内置函数。
+///
+/// Calling a builtin with fewer arguments than its arity produces this value;
+/// applying it to the remaining arguments calls the builtin.
+/// 以少于元数的参数调用内置函数会产生此值；对其应用剩余参数时调用该内置函数。
+#[derive(Clone)]
+pub struct PartialBuiltin {
+    /// The builtin being applied / 被应用的内置函数
+    pub builtin: BuiltinFn,
+    /// Arguments collected so far / 目前已收集的参数
+    pub args: Vec<Value>,
+}
+
+impl PartialBuiltin {
+    /// Number of arguments still needed before the builtin runs.
+    /// 内置函数运行前仍需要的参数数量。
+    pub fn remaining(&self) -> usize {
+        self.builtin.arity.saturating_sub(self.args.len())
+    }
+}
+
 impl fmt::Debug for Value {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -241,10 +276,20 @@ impl fmt::Debug for Value {
                 }
                 write!(f, "}}")
             }
-            Value::Closure { .. } => write!(f, "<closure>"),
-            Value::AstClosure(_) => write!(f, "<function>"),
+            Value::Closure { params, .. } => {
+                write!(f, "{}", fn_signature(params.iter().map(|p| p.name.clone())))
+            }
+            Value::AstClosure(closure) => write!(f, "{}", fn_signature(closure.param_names())),
             Value::Builtin(b) => write!(f, "<builtin:{}>", b.name),
+            Value::PartialBuiltin(p) => write!(
+                f,
+                "<builtin:{} {}/{}>",
+                p.builtin.name,
+                p.args.len(),
+                p.builtin.arity
+            ),
             Value::BuiltinFn(name, _) => write!(f, "<builtin:{}>", name),
+            Value::Constructor(c) => write!(f, "<constructor:{}>", c.name),
             Value::Variant(tag, payload) => {
                 if matches!(**payload, Value::Unit) {
                     write!(f, "{}", tag)
@@ -297,6 +342,13 @@ impl PartialEq for Value {
 
 impl Eq for Value {}
 
+/// Render a function's parameters as `<fn(a, b)>`.
+/// 将函数参数渲染为 `<fn(a, b)>`。
+pub(crate) fn fn_signature(params: impl IntoIterator<Item = String>) -> String {
+    let params: Vec<String> = params.into_iter().collect();
+    format!("<fn({})>", params.join(", "))
+}
+
 impl Value {
     /// Check if the value is truthy.
     /// 检查值是否为真值。
@@ -347,4 +399,222 @@ impl Value {
             _ => None,
         }
     }
+
+    /// Name of the value's runtime type, as returned by `typeOf`.
+    /// 值的运行时类型名称，即 `typeOf` 的返回值。
+    ///
+    /// Thunks report `"thunk"`; `typeOf` forces them first.
+    /// Thunk 报告为 `"thunk"`；`typeOf` 会先强制求值。
+    pub fn type_of(&self) -> &'static str {
+        match self {
+            Value::Int(_) => "int",
+            Value::Float(_) => "float",
+            Value::Bool(_) => "bool",
+            Value::String(_) => "string",
+            Value::Char(_) => "char",
+            Value::Unit => "unit",
+            Value::List(_) => "list",
+            Value::Tuple(_) => "tuple",
+            Value::Record(_) => "record",
+            Value::Map(_) => "map",
+            Value::Set(_) => "set",
+            Value::Closure { .. }
+            | Value::AstClosure(_)
+            | Value::Builtin(_)
+            | Value::PartialBuiltin(_)
+            | Value::BuiltinFn(..)
+            | Value::Constructor(_) => "function",
+            Value::Some(_) | Value::None => "option",
+            Value::Ok(_) | Value::Err(_) => "result",
+            Value::Variant(..) => "variant",
+            Value::Thunk(_) => "thunk",
+        }
+    }
+
+    /// Order two values for comparison operators and sorting.
+    /// 为比较运算符和排序确定两个值的顺序。
+    ///
+    /// Numbers, strings and chars compare naturally, `false < true`, and lists
+    /// and tuples compare element by element, a proper prefix being smaller.
+    /// 数字、字符串和字符按自然顺序比较，`false < true`；列表和元组逐元素比较，
+    /// 真前缀较小。
+    pub fn compare(&self, other: &Value) -> Result<std::cmp::Ordering, String> {
+        match (self, other) {
+            (Value::Int(x), Value::Int(y)) => Ok(x.cmp(y)),
+            (Value::Float(x), Value::Float(y)) => x
+                .partial_cmp(y)
+                .ok_or_else(|| "cannot compare NaN".to_string()),
+            (Value::String(x), Value::String(y)) => Ok(x.cmp(y)),
+            (Value::Char(x), Value::Char(y)) => Ok(x.cmp(y)),
+            (Value::Bool(x), Value::Bool(y)) => Ok(x.cmp(y)),
+            (Value::List(xs), Value::List(ys)) | (Value::Tuple(xs), Value::Tuple(ys)) => {
+                for (x, y) in xs.iter().zip(ys.iter()) {
+                    let ordering = x.compare(y)?;
+                    if ordering.is_ne() {
+                        return Ok(ordering);
+                    }
+                }
+                Ok(xs.len().cmp(&ys.len()))
+            }
+            _ => Err("cannot compare".to_string()),
+        }
+    }
+
+    /// Convert to a canonical JSON value.
+    /// 转换为规范的 JSON 值。
+    ///
+    /// The mapping is stable across releases:
+    /// 映射在各版本之间保持稳定：
+    ///
+    /// - `Int`, `Float` and `Bool` become numbers and booleans; non-finite
+    ///   floats are an error / `Int`、`Float` 和 `Bool` 变为数字和布尔值；非有限浮点数会报错
+    /// - `Char` and `String` become strings, `Unit` becomes `null`
+    ///   / `Char` 和 `String` 变为字符串，`Unit` 变为 `null`
+    /// - lists, tuples and sets become arrays / 列表、元组和集合变为数组
+    /// - records and maps become objects with sorted keys / 记录和映射变为键有序的对象
+    /// - variants, including `Some`, `None`, `Ok` and `Err`, become
+    ///   `{ "tag": name, "value": payload }`, with a `null` payload when there
+    ///   is none / 变体（包括 `Some`、`None`、`Ok` 和 `Err`）变为
+    ///   `{ "tag": name, "value": payload }`，无载荷时为 `null`
+    /// - evaluated thunks are replaced by their value / 已求值的 thunk 被替换为其值
+    ///
+    /// Functions and unevaluated thunks have no JSON form and are an error.
+    /// 函数和未求值的 thunk 没有 JSON 形式，会报错。
+    pub fn to_json(&self) -> Result<serde_json::Value, String> {
+        self.json_value(false)
+    }
+
+    /// Convert to a JSON value as [`to_json`](Self::to_json) does, or with
+    /// `Some(x)` written as `x` and `None` as `null` when `flatten_options`
+    /// is set, as the `toJSON` builtins do.
+    /// 像 [`to_json`](Self::to_json) 一样转换为 JSON 值；当设置 `flatten_options` 时，
+    /// 如 `toJSON` 内置函数那样将 `Some(x)` 写为 `x`，`None` 写为 `null`。
+    pub(crate) fn json_value(&self, flatten_options: bool) -> Result<serde_json::Value, String> {
+        use serde_json::Value as Json;
+
+        let tagged = |tag: &str, value: &Value| -> Result<Json, String> {
+            Ok(serde_json::json!({ "tag": tag, "value": value.json_value(flatten_options)? }))
+        };
+        let array = |items: &mut dyn Iterator<Item = &Value>| -> Result<Json, String> {
+            items
+                .map(|v| v.json_value(flatten_options))
+                .collect::<Result<_, _>>()
+                .map(Json::Array)
+        };
+        let object = |fields: &mut dyn Iterator<Item = (String, &Value)>| -> Result<Json, String> {
+            fields
+                .map(|(k, v)| Ok((k, v.json_value(flatten_options)?)))
+                .collect::<Result<_, String>>()
+                .map(Json::Object)
+        };
+
+        Ok(match self {
+            Value::Int(n) => Json::from(*n),
+            Value::Float(f) => serde_json::Number::from_f64(*f)
+                .map(Json::Number)
+                .ok_or_else(|| format!("cannot represent {} in JSON", f))?,
+            Value::Bool(b) => Json::Bool(*b),
+            Value::Char(c) => Json::String(c.to_string()),
+            Value::String(s) => Json::String(s.to_string()),
+            Value::Unit => Json::Null,
+            Value::List(items) | Value::Tuple(items) => array(&mut items.iter())?,
+            Value::Record(fields) => object(&mut fields.iter().map(|(k, v)| (k.clone(), v)))?,
+            Value::Map(map) => object(&mut map.iter().map(|(k, v)| (key_string(k), v)))?,
+            Value::Set(set) => Json::Array(set.iter().map(|elem| key_json(elem)).collect()),
+            Value::Some(v) if flatten_options => v.json_value(flatten_options)?,
+            Value::None if flatten_options => Json::Null,
+            Value::Variant(tag, payload) => tagged(tag, payload)?,
+            Value::Some(v) => tagged("Some", v)?,
+            Value::None => tagged("None", &Value::Unit)?,
+            Value::Ok(v) => tagged("Ok", v)?,
+            Value::Err(v) => tagged("Err", v)?,
+            Value::Thunk(thunk) => match &*thunk.state() {
+                ThunkState::Evaluated(v) => v.json_value(flatten_options)?,
+                _ => return Err("cannot serialize unevaluated thunk to JSON".to_string()),
+            },
+            Value::Closure { .. }
+            | Value::AstClosure(_)
+            | Value::Builtin(_)
+            | Value::PartialBuiltin(_)
+            | Value::BuiltinFn(..)
+            | Value::Constructor(_) => {
+                return Err("cannot serialize function to JSON".to_string());
+            }
+        })
+    }
+
+    /// Convert a JSON value: `null` becomes `None`, integers that fit become
+    /// `Int` and other numbers `Float`, arrays become lists and objects
+    /// become records.
+    /// 转换 JSON 值：`null` 变为 `None`，可容纳的整数变为 `Int`，其他数字变为 `Float`，
+    /// 数组变为列表，对象变为记录。
+    pub fn from_json(json: &serde_json::Value) -> Value {
+        use serde_json::Value as Json;
+
+        match json {
+            Json::Null => Value::None,
+            Json::Bool(b) => Value::Bool(*b),
+            Json::Number(n) => match n.as_i64() {
+                Some(i) => Value::Int(i),
+                None => Value::Float(n.as_f64().unwrap_or(f64::NAN)),
+            },
+            Json::String(s) => Value::String(Rc::new(s.clone())),
+            Json::Array(items) => {
+                Value::List(Rc::new(items.iter().map(Value::from_json).collect()))
+            }
+            Json::Object(fields) => Value::Record(Rc::new(
+                fields
+                    .iter()
+                    .map(|(k, v)| (k.clone(), Value::from_json(v)))
+                    .collect(),
+            )),
+        }
+    }
+
+    /// Parse JSON text into a value, as [`from_json`](Self::from_json) does.
+    /// 像 [`from_json`](Self::from_json) 一样将 JSON 文本解析为值。
+    pub fn from_json_str(text: &str) -> Result<Value, String> {
+        serde_json::from_str(text)
+            .map(|json| Value::from_json(&json))
+            .map_err(|e| format!("invalid JSON: {}", e))
+    }
+}
+
+/// The text a map key or set element was stored from: the string itself for
+/// a string, otherwise the value as written.
+/// 映射键或集合元素存储前的文本：字符串为其本身，否则为值的书写形式。
+fn key_string(key: &str) -> String {
+    match key_json(key) {
+        serde_json::Value::String(s) => s,
+        other => other.to_string(),
+    }
+}
+
+/// The JSON form of a map key or set element, which are stored as the debug
+/// rendering of the original value.
+/// 映射键或集合元素的 JSON 形式，它们以原始值的调试渲染形式存储。
+fn key_json(key: &str) -> serde_json::Value {
+    use serde_json::Value as Json;
+
+    if let Some(inner) = key
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+    {
+        return Json::String(inner.to_string());
+    }
+    if let Some(inner) = key
+        .strip_prefix('\'')
+        .and_then(|rest| rest.strip_suffix('\''))
+        && inner.chars().count() == 1
+    {
+        return Json::String(inner.to_string());
+    }
+    match key {
+        "true" => Json::Bool(true),
+        "false" => Json::Bool(false),
+        _ => key
+            .parse::<i64>()
+            .map(Json::from)
+            .unwrap_or_else(|_| Json::String(key.to_string())),
+    }
 }
@@ -3,13 +3,20 @@
 //!
 //! Provides functionality for extracting various archive formats.
 //! 提供解压各种归档格式的功能。
+//!
+//! Entries that would land outside the destination directory, either through
+//! `..` components, absolute paths or symlinks pointing elsewhere, are
+//! rejected with [`FetchError::Archive`].
+//! 会落在目标目录之外的条目（通过 `..` 组件、绝对路径或指向别处的符号链接）
+//! 会以 [`FetchError::Archive`] 拒绝。
 
 use crate::FetchError;
 use flate2::read::GzDecoder;
 use std::fs::{self, File};
-use std::io::{self, Read};
-use std::path::Path;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Component, Path, PathBuf};
 use tar::Archive;
+use zip::ZipArchive;
 
 /// Supported archive formats.
 /// 支持的归档格式。
@@ -24,6 +31,9 @@ pub enum ArchiveFormat {
     /// Plain tar archive (.tar).
     /// 普通 tar 归档（.tar）。
     Tar,
+    /// Zip archive (.zip).
+    /// Zip 归档（.zip）。
+    Zip,
 }
 
 impl ArchiveFormat {
@@ -45,48 +55,106 @@ impl ArchiveFormat {
             Some(ArchiveFormat::TarXz)
         } else if name.ends_with(".tar") {
             Some(ArchiveFormat::Tar)
+        } else if name.ends_with(".zip") {
+            Some(ArchiveFormat::Zip)
         } else {
             None
         }
     }
+
+    /// Detect archive format from the leading bytes of its contents.
+    /// 从内容的起始字节检测归档格式。
+    pub fn from_magic(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(&[0x1f, 0x8b]) {
+            Some(ArchiveFormat::TarGz)
+        } else if bytes.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Some(ArchiveFormat::TarXz)
+        } else if bytes.starts_with(b"PK\x03\x04") || bytes.starts_with(b"PK\x05\x06") {
+            Some(ArchiveFormat::Zip)
+        } else if bytes.get(257..262) == Some(b"ustar") {
+            Some(ArchiveFormat::Tar)
+        } else {
+            None
+        }
+    }
+
+    /// Detect archive format from the file extension, falling back to its magic bytes.
+    /// 从文件扩展名检测归档格式，失败时回退到魔数。
+    pub fn detect(path: &Path) -> Result<Self, FetchError> {
+        if let Some(format) = Self::from_path(path) {
+            return Ok(format);
+        }
+        let mut header = Vec::with_capacity(512);
+        File::open(path)?.take(512).read_to_end(&mut header)?;
+        Self::from_magic(&header).ok_or_else(|| {
+            FetchError::Archive(format!("unknown archive format: {}", path.display()))
+        })
+    }
 }
 
 /// Extract an archive to a directory.
 /// 将归档解压到目录。
 pub fn extract(archive_path: &Path, dest_dir: &Path) -> Result<(), FetchError> {
-    let format = ArchiveFormat::from_path(archive_path).ok_or_else(|| {
-        FetchError::Archive(format!(
-            "unknown archive format: {}",
-            archive_path.display()
-        ))
-    })?;
-
+    let format = ArchiveFormat::detect(archive_path)?;
     extract_with_format(archive_path, dest_dir, format)
 }
 
+/// Extract an archive to a directory, reporting the archive bytes read so far.
+/// 将归档解压到目录，并报告目前已读取的归档字节数。
+///
+/// `on_progress` receives the compressed bytes consumed and the archive size.
+/// `on_progress` 接收已消耗的压缩字节数和归档大小。
+pub fn extract_with_progress(
+    archive_path: &Path,
+    dest_dir: &Path,
+    on_progress: &mut dyn FnMut(u64, Option<u64>),
+) -> Result<(), FetchError> {
+    let format = ArchiveFormat::detect(archive_path)?;
+    extract_format_with_progress(archive_path, dest_dir, format, on_progress)
+}
+
 /// Extract an archive with a specific format.
 /// 使用指定格式解压归档。
 pub fn extract_with_format(
     archive_path: &Path,
     dest_dir: &Path,
     format: ArchiveFormat,
+) -> Result<(), FetchError> {
+    extract_format_with_progress(archive_path, dest_dir, format, &mut |_, _| {})
+}
+
+/// Extract an archive with a specific format, reporting progress.
+/// 使用指定格式解压归档，并报告进度。
+pub fn extract_format_with_progress(
+    archive_path: &Path,
+    dest_dir: &Path,
+    format: ArchiveFormat,
+    on_progress: &mut dyn FnMut(u64, Option<u64>),
 ) -> Result<(), FetchError> {
     // Create destination directory
     // 创建目标目录
     fs::create_dir_all(dest_dir)?;
 
     let file = File::open(archive_path)?;
+    let total = file.metadata()?.len();
+    let file = ProgressReader {
+        inner: file,
+        read: 0,
+        total,
+        on_progress,
+    };
 
     match format {
         ArchiveFormat::TarGz => extract_tar_gz(file, dest_dir),
         ArchiveFormat::TarXz => extract_tar_xz(file, dest_dir),
         ArchiveFormat::Tar => extract_tar(file, dest_dir),
+        ArchiveFormat::Zip => extract_zip(file, dest_dir, 0),
     }
 }
 
 /// Extract a .tar.gz archive.
 /// 解压 .tar.gz 归档。
-fn extract_tar_gz(file: File, dest_dir: &Path) -> Result<(), FetchError> {
+fn extract_tar_gz<R: Read>(file: R, dest_dir: &Path) -> Result<(), FetchError> {
     let decoder = GzDecoder::new(file);
     let mut archive = Archive::new(decoder);
     extract_tar_archive(&mut archive, dest_dir)
@@ -94,7 +162,7 @@ fn extract_tar_gz(file: File, dest_dir: &Path) -> Result<(), FetchError> {
 
 /// Extract a .tar.xz archive.
 /// 解压 .tar.xz 归档。
-fn extract_tar_xz(file: File, dest_dir: &Path) -> Result<(), FetchError> {
+fn extract_tar_xz<R: Read>(file: R, dest_dir: &Path) -> Result<(), FetchError> {
     let decoder = xz2::read::XzDecoder::new(file);
     let mut archive = Archive::new(decoder);
     extract_tar_archive(&mut archive, dest_dir)
@@ -102,20 +170,257 @@ fn extract_tar_xz(file: File, dest_dir: &Path) -> Result<(), FetchError> {
 
 /// Extract a plain .tar archive.
 /// 解压普通 .tar 归档。
-fn extract_tar(file: File, dest_dir: &Path) -> Result<(), FetchError> {
+fn extract_tar<R: Read>(file: R, dest_dir: &Path) -> Result<(), FetchError> {
     let mut archive = Archive::new(file);
     extract_tar_archive(&mut archive, dest_dir)
 }
 
+/// A reader that reports the bytes read through it.
+/// 报告经由其读取的字节数的读取器。
+struct ProgressReader<'a, R> {
+    inner: R,
+    read: u64,
+    total: u64,
+    on_progress: &'a mut dyn FnMut(u64, Option<u64>),
+}
+
+impl<R: Read> Read for ProgressReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        if n > 0 {
+            self.read += n as u64;
+            (self.on_progress)(self.read, Some(self.total));
+        }
+        Ok(n)
+    }
+}
+
+impl<R: Seek> Seek for ProgressReader<'_, R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        // Zip archives are read out of order, so report the position instead
+        // Zip 归档不是按顺序读取的，因此报告当前位置
+        self.read = self.inner.seek(pos)?;
+        Ok(self.read)
+    }
+}
+
 /// Extract a tar archive to a directory.
 /// 将 tar 归档解压到目录。
 fn extract_tar_archive<R: Read>(
     archive: &mut Archive<R>,
     dest_dir: &Path,
 ) -> Result<(), FetchError> {
-    archive
-        .unpack(dest_dir)
-        .map_err(|e| FetchError::Archive(format!("failed to extract tar archive: {}", e)))
+    let tar_error =
+        |e: io::Error| FetchError::Archive(format!("failed to extract tar archive: {}", e));
+
+    for entry in archive.entries().map_err(tar_error)? {
+        let mut entry = entry.map_err(tar_error)?;
+        let path = entry.path().map_err(tar_error)?.into_owned();
+        let dest_path = contained_path(dest_dir, &path)?;
+        if entry.header().entry_type().is_symlink()
+            && let Some(target) = entry.link_name().map_err(tar_error)?
+        {
+            check_link_target(dest_dir, &path, &target)?;
+        }
+        check_parent_contained(dest_dir, &dest_path)?;
+        entry.unpack_in(dest_dir).map_err(tar_error)?;
+    }
+
+    Ok(())
+}
+
+/// Extract a zip archive, dropping the first `strip` path components.
+/// 解压 zip 归档，去除前 `strip` 个路径组件。
+fn extract_zip<R: Read + Seek>(reader: R, dest_dir: &Path, strip: usize) -> Result<(), FetchError> {
+    let zip_error = |e: zip::result::ZipError| {
+        FetchError::Archive(format!("failed to extract zip archive: {}", e))
+    };
+    let mut archive = ZipArchive::new(reader).map_err(zip_error)?;
+
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index).map_err(zip_error)?;
+        let name = PathBuf::from(entry.name());
+        let dest_path = contained_path(dest_dir, &name)?;
+
+        // Skip entries with fewer components than we want to strip
+        // 跳过组件数少于要剥离数量的条目
+        let components: Vec<_> = name.components().collect();
+        if components.len() <= strip {
+            continue;
+        }
+        let path: PathBuf = components[strip..].iter().collect();
+        let dest_path = if strip == 0 {
+            dest_path
+        } else {
+            dest_dir.join(&path)
+        };
+
+        check_parent_contained(dest_dir, &dest_path)?;
+        if entry.is_dir() {
+            fs::create_dir_all(&dest_path)?;
+            continue;
+        }
+        if let Some(parent) = dest_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mode = entry.unix_mode();
+        if mode.is_some_and(|mode| mode & 0o170000 == 0o120000) {
+            let mut target = String::new();
+            entry.read_to_string(&mut target)?;
+            check_link_target(dest_dir, &path, Path::new(&target))?;
+            #[cfg(unix)]
+            std::os::unix::fs::symlink(&target, &dest_path)?;
+            continue;
+        }
+
+        let mut file = File::create(&dest_path)?;
+        io::copy(&mut entry, &mut file)?;
+
+        // Preserve permissions on Unix
+        // 在 Unix 上保留权限
+        #[cfg(unix)]
+        if let Some(mode) = mode {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&dest_path, fs::Permissions::from_mode(mode & 0o7777))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Join an archive entry path onto `dest_dir`, rejecting paths that escape it.
+/// 将归档条目路径拼接到 `dest_dir`，拒绝逃逸出该目录的路径。
+fn contained_path(dest_dir: &Path, entry: &Path) -> Result<PathBuf, FetchError> {
+    let mut path = dest_dir.to_path_buf();
+    for component in entry.components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                return Err(FetchError::Archive(format!(
+                    "archive entry escapes the destination: {}",
+                    entry.display()
+                )));
+            }
+        }
+    }
+    Ok(path)
+}
+
+/// Most links followed while resolving one symlink target.
+/// 解析一个符号链接目标时最多跟随的链接数。
+const MAX_LINK_DEPTH: usize = 40;
+
+/// Reject a symlink at `entry` whose `target` resolves outside `dest_dir`.
+/// 拒绝位于 `entry` 且其 `target` 解析到 `dest_dir` 之外的符号链接。
+///
+/// The target is followed through the links extracted so far, so a chain of
+/// links that are each harmless on their own cannot reach outside either.
+/// 目标会经由目前已解压的链接解析，因此各自无害的链接组成的链也无法指向外部。
+fn check_link_target(dest_dir: &Path, entry: &Path, target: &Path) -> Result<(), FetchError> {
+    let outside = || {
+        FetchError::Archive(format!(
+            "symlink {} points outside {}: {}",
+            entry.display(),
+            dest_dir.display(),
+            target.display()
+        ))
+    };
+
+    let root = dest_dir.canonicalize()?;
+    let link_dir = contained_path(dest_dir, entry)?
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| dest_dir.to_path_buf());
+    let mut path = resolve_existing(&link_dir)?;
+    if !path.starts_with(&root) {
+        return Err(outside());
+    }
+
+    // Components still to walk, last first; links expand into their targets
+    // 尚待遍历的组件，最后一个在前；链接会展开为其目标
+    let mut pending: Vec<PathBuf> = target
+        .components()
+        .rev()
+        .map(|c| PathBuf::from(c.as_os_str()))
+        .collect();
+    let mut links_followed = 0;
+    while let Some(part) = pending.pop() {
+        match part.components().next() {
+            Some(Component::Normal(name)) => {
+                path.push(name);
+                if path.is_symlink() {
+                    // A link extracted earlier; follow it as the OS would
+                    // 先前解压的链接；像操作系统一样跟随它
+                    links_followed += 1;
+                    if links_followed > MAX_LINK_DEPTH {
+                        return Err(outside());
+                    }
+                    let next = fs::read_link(&path)?;
+                    path.pop();
+                    pending.extend(
+                        next.components()
+                            .rev()
+                            .map(|c| PathBuf::from(c.as_os_str())),
+                    );
+                }
+            }
+            Some(Component::ParentDir) => {
+                path.pop();
+            }
+            Some(Component::CurDir) | None => {}
+            Some(Component::RootDir | Component::Prefix(_)) => return Err(outside()),
+        }
+        if !path.starts_with(&root) {
+            return Err(outside());
+        }
+    }
+    Ok(())
+}
+
+/// Reject `dest_path` if the part of its parent that exists resolves outside `dest_dir`.
+/// 如果 `dest_path` 父目录中已存在的部分解析到 `dest_dir` 之外，则拒绝它。
+///
+/// This catches entries written through a symlink extracted earlier, and is
+/// checked before any missing parent directory is created.
+/// 这可以捕获通过先前解压的符号链接写入的条目，并在创建任何缺失的父目录之前进行检查。
+fn check_parent_contained(dest_dir: &Path, dest_path: &Path) -> Result<(), FetchError> {
+    let Some(parent) = dest_path.parent() else {
+        return Ok(());
+    };
+    let root = dest_dir.canonicalize()?;
+    let escapes = || {
+        FetchError::Archive(format!(
+            "archive entry escapes the destination through a symlink: {}",
+            dest_path.display()
+        ))
+    };
+    let parent = resolve_existing(parent).map_err(|_| escapes())?;
+    if parent.starts_with(&root) {
+        Ok(())
+    } else {
+        Err(escapes())
+    }
+}
+
+/// Resolve the longest existing prefix of `path`, keeping the missing rest as is.
+/// 解析 `path` 中已存在的最长前缀，其余缺失部分保持不变。
+fn resolve_existing(path: &Path) -> io::Result<PathBuf> {
+    let mut existing = path;
+    let mut missing = Vec::new();
+    while existing.symlink_metadata().is_err() {
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name.to_os_string());
+                existing = parent;
+            }
+            _ => break,
+        }
+    }
+    let mut resolved = existing.canonicalize()?;
+    resolved.extend(missing.iter().rev());
+    Ok(resolved)
 }
 
 /// Extract archive contents from memory.
@@ -142,6 +447,7 @@ pub fn extract_from_bytes(
             let mut archive = Archive::new(io::Cursor::new(data));
             extract_tar_archive(&mut archive, dest_dir)
         }
+        ArchiveFormat::Zip => extract_zip(io::Cursor::new(data), dest_dir, 0),
     }
 }
 
@@ -155,12 +461,7 @@ pub fn extract_stripped(
     dest_dir: &Path,
     strip_components: usize,
 ) -> Result<(), FetchError> {
-    let format = ArchiveFormat::from_path(archive_path).ok_or_else(|| {
-        FetchError::Archive(format!(
-            "unknown archive format: {}",
-            archive_path.display()
-        ))
-    })?;
+    let format = ArchiveFormat::detect(archive_path)?;
 
     fs::create_dir_all(dest_dir)?;
     let file = File::open(archive_path)?;
@@ -175,6 +476,7 @@ pub fn extract_stripped(
             extract_tar_stripped(decoder, dest_dir, strip_components)
         }
         ArchiveFormat::Tar => extract_tar_stripped(file, dest_dir, strip_components),
+        ArchiveFormat::Zip => extract_zip(file, dest_dir, strip_components),
     }
 }
 
@@ -195,6 +497,7 @@ fn extract_tar_stripped<R: Read>(
         let path = entry
             .path()
             .map_err(|e| FetchError::Archive(e.to_string()))?;
+        contained_path(dest_dir, &path)?;
 
         // Skip entries with fewer components than we want to strip
         // 跳过组件数少于要剥离数量的条目
@@ -205,11 +508,12 @@ fn extract_tar_stripped<R: Read>(
 
         // Build new path with stripped components
         // 用剥离后的组件构建新路径
-        let new_path: std::path::PathBuf = components[strip..].iter().collect();
+        let new_path: PathBuf = components[strip..].iter().collect();
         let dest_path = dest_dir.join(&new_path);
 
         // Create parent directories
         // 创建父目录
+        check_parent_contained(dest_dir, &dest_path)?;
         if let Some(parent) = dest_path.parent() {
             fs::create_dir_all(parent)?;
         }
@@ -228,15 +532,17 @@ fn extract_tar_stripped<R: Read>(
             {
                 use std::os::unix::fs::PermissionsExt;
                 if let Ok(mode) = entry.header().mode() {
-                    fs::set_permissions(&dest_path, fs::Permissions::from_mode(mode))?;
+                    fs::set_permissions(&dest_path, fs::Permissions::from_mode(mode & 0o7777))?;
                 }
             }
         } else if entry.header().entry_type().is_symlink() {
             #[cfg(unix)]
-            if let Ok(link_name) = entry.link_name()
-                && let Some(target) = link_name
+            if let Some(target) = entry
+                .link_name()
+                .map_err(|e| FetchError::Archive(e.to_string()))?
             {
-                let _ = std::os::unix::fs::symlink(target, &dest_path);
+                check_link_target(dest_dir, &new_path, &target)?;
+                std::os::unix::fs::symlink(&target, &dest_path)?;
             }
         }
     }
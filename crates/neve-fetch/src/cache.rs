@@ -0,0 +1,202 @@
+//! Cache size accounting and least-recently-used eviction.
+//! 缓存大小统计与最近最少使用淘汰。
+//!
+//! Every content-addressed entry (a downloaded file, a Git checkout or an
+//! unpacked archive) is evicted as a whole. Last-use times are kept in a JSON
+//! sidecar, `access.json`, under the cache directory; entries missing from it
+//! fall back to their modification time.
+//! 每个内容寻址条目（下载的文件、Git 检出或解包的归档）作为整体淘汰。最近使用时间
+//! 保存在缓存目录下的 JSON 附属文件 `access.json` 中；不在其中的条目回退到其修改时间。
+//!
+//! Entries are written under temporary names in the cache root and renamed
+//! into place once complete, so an entry still being written is never seen here.
+//! 条目先以临时名称写入缓存根目录，完成后再重命名到位，因此这里永远不会看到仍在写入的条目。
+
+use crate::FetchError;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Name of the access-time index under the cache directory.
+/// 缓存目录下访问时间索引的文件名。
+const INDEX_FILE: &str = "access.json";
+
+/// Directories holding hash-prefix buckets of entries, relative to the cache root.
+/// 存放条目哈希前缀桶的目录，相对于缓存根目录。
+const ENTRY_ROOTS: [&str; 3] = ["", "git", "unpacked"];
+
+/// The access-time index of a cache directory.
+/// 缓存目录的访问时间索引。
+pub(crate) struct AccessIndex {
+    /// The cache directory. / 缓存目录。
+    cache_dir: PathBuf,
+    /// Serializes read-modify-write cycles on the index file.
+    /// 串行化对索引文件的读-改-写周期。
+    lock: Mutex<()>,
+}
+
+impl AccessIndex {
+    /// Create the index for `cache_dir`.
+    /// 为 `cache_dir` 创建索引。
+    pub(crate) fn new(cache_dir: &Path) -> Self {
+        Self {
+            cache_dir: cache_dir.to_path_buf(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Record that `entry` was used just now.
+    /// 记录 `entry` 刚刚被使用。
+    pub(crate) fn touch(&self, entry: &Path) -> Result<(), FetchError> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut times = self.load();
+        times.insert(self.key(entry), now());
+        self.store(&times)
+    }
+
+    /// Delete least-recently-used entries until the cache fits in `max_size` bytes.
+    /// 删除最近最少使用的条目，直到缓存不超过 `max_size` 字节。
+    ///
+    /// `keep` is never deleted. Returns the number of bytes freed.
+    /// `keep` 永远不会被删除。返回释放的字节数。
+    pub(crate) fn evict(&self, max_size: u64, keep: Option<&Path>) -> Result<u64, FetchError> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut times = self.load();
+
+        let mut entries = Vec::new();
+        let mut total = 0;
+        for path in self.entries()? {
+            let size = disk_usage(&path)?;
+            total += size;
+            let last_used = match times.get(&self.key(&path)) {
+                Some(time) => *time,
+                None => modified(&path),
+            };
+            entries.push((last_used, path, size));
+        }
+        if total <= max_size {
+            return Ok(0);
+        }
+
+        // Oldest first, with the path as a stable tie-breaker
+        // 最旧的优先，以路径作为稳定的次要排序键
+        entries.sort();
+        let mut freed = 0;
+        for (_, path, size) in entries {
+            if total - freed <= max_size {
+                break;
+            }
+            if keep == Some(path.as_path()) {
+                continue;
+            }
+            let removed = if path.is_dir() {
+                fs::remove_dir_all(&path)
+            } else {
+                fs::remove_file(&path)
+            };
+            match removed {
+                Ok(()) => freed += size,
+                // Already removed by someone else
+                // 已被其他人删除
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+            times.remove(&self.key(&path));
+        }
+
+        self.store(&times)?;
+        Ok(freed)
+    }
+
+    /// Every content-addressed entry currently in the cache.
+    /// 缓存中当前的所有内容寻址条目。
+    fn entries(&self) -> Result<Vec<PathBuf>, FetchError> {
+        let mut entries = Vec::new();
+        for root in ENTRY_ROOTS {
+            let Ok(buckets) = fs::read_dir(self.cache_dir.join(root)) else {
+                continue;
+            };
+            for bucket in buckets {
+                let bucket = bucket?;
+                // Buckets are named after the first two characters of the hash
+                // 桶以哈希的前两个字符命名
+                if bucket.file_name().len() != 2 || !bucket.file_type()?.is_dir() {
+                    continue;
+                }
+                for entry in fs::read_dir(bucket.path())? {
+                    entries.push(entry?.path());
+                }
+            }
+        }
+        Ok(entries)
+    }
+
+    /// The index key of `entry`: its path relative to the cache root.
+    /// `entry` 的索引键：其相对于缓存根目录的路径。
+    fn key(&self, entry: &Path) -> String {
+        entry
+            .strip_prefix(&self.cache_dir)
+            .unwrap_or(entry)
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// Read the index, treating a missing or corrupt file as empty.
+    /// 读取索引，将缺失或损坏的文件视为空。
+    fn load(&self) -> BTreeMap<String, u128> {
+        fs::read(self.cache_dir.join(INDEX_FILE))
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default()
+    }
+
+    /// Write the index atomically.
+    /// 原子地写入索引。
+    fn store(&self, times: &BTreeMap<String, u128>) -> Result<(), FetchError> {
+        let json = serde_json::to_vec_pretty(times)
+            .map_err(|e| FetchError::Io(std::io::Error::other(e)))?;
+        let mut temp = tempfile::NamedTempFile::new_in(&self.cache_dir)?;
+        std::io::Write::write_all(&mut temp, &json)?;
+        temp.persist(self.cache_dir.join(INDEX_FILE))
+            .map_err(|e| e.error)?;
+        Ok(())
+    }
+}
+
+/// The current time in nanoseconds since the Unix epoch.
+/// 自 Unix 纪元以来的当前时间（纳秒）。
+fn now() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0)
+}
+
+/// The modification time of `path` in nanoseconds since the Unix epoch.
+/// `path` 的修改时间（自 Unix 纪元以来的纳秒）。
+fn modified(path: &Path) -> u128 {
+    fs::symlink_metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos())
+        .unwrap_or(0)
+}
+
+/// The bytes used by a file or directory tree, without following symlinks.
+/// 文件或目录树占用的字节数，不跟随符号链接。
+fn disk_usage(path: &Path) -> Result<u64, FetchError> {
+    let metadata = fs::symlink_metadata(path)?;
+    if !metadata.is_dir() {
+        return Ok(metadata.len());
+    }
+    let mut total = 0;
+    for entry in fs::read_dir(path)? {
+        total += disk_usage(&entry?.path())?;
+    }
+    Ok(total)
+}
@@ -0,0 +1,48 @@
+//! Fetch cancellation.
+//! 获取取消。
+//!
+//! A [`CancelToken`] is shared between a fetch and whoever may want to stop
+//! it, such as a Ctrl-C handler. Fetches check it between chunks and give up
+//! with [`FetchError::Cancelled`](crate::FetchError::Cancelled).
+//! [`CancelToken`] 在获取操作与可能想要停止它的一方（例如 Ctrl-C 处理程序）之间共享。
+//! 获取操作在各数据块之间检查它，并以 [`FetchError::Cancelled`](crate::FetchError::Cancelled) 放弃。
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// A cloneable flag for cancelling fetches.
+/// 用于取消获取的可克隆标志。
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+    /// Create a token that is not cancelled.
+    /// 创建未取消的令牌。
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel all fetches using this token or its clones.
+    /// 取消使用此令牌或其克隆的所有获取。
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// Check whether the token was cancelled.
+    /// 检查令牌是否已被取消。
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Fail with `Cancelled` if the token was cancelled.
+    /// 如果令牌已被取消，则以 `Cancelled` 失败。
+    pub fn check(&self) -> Result<(), crate::FetchError> {
+        if self.is_cancelled() {
+            Err(crate::FetchError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
@@ -3,16 +3,208 @@
 //!
 //! Provides functionality for cloning and checking out Git repositories.
 //! 提供克隆和检出 Git 仓库的功能。
+//!
+//! Branches, tags and commit SHAs are fetched shallowly (`depth 1`) where the
+//! remote allows it, with a full fetch as the fallback.
+//! 分支、标签和提交 SHA 在远程允许时进行浅获取（`depth 1`），否则回退到完整获取。
 
-use git2::{FetchOptions, Oid, Repository, build::RepoBuilder};
+use git2::{
+    FetchOptions, Oid, RemoteCallbacks, Repository, SubmoduleUpdateOptions, build::RepoBuilder,
+};
 use neve_derive::Hash;
+use std::fs;
 use std::path::Path;
 
-use crate::FetchError;
+use crate::{CancelToken, FetchError};
 
 /// Clone or fetch a Git repository.
 /// 克隆或获取 Git 仓库。
 pub fn clone_repo(url: &str, dest: &Path) -> Result<Repository, FetchError> {
+    clone_repo_with_progress(url, dest, &CancelToken::new(), &mut |_, _| {})
+}
+
+/// Clone or fetch a Git repository, reporting the bytes received so far.
+/// 克隆或获取 Git 仓库，并报告目前已接收的字节数。
+///
+/// The total size of a Git transfer is not known up front, so `on_progress`
+/// always receives `None` as the total. A cancelled token aborts the transfer.
+/// Git 传输的总大小无法预先得知，因此 `on_progress` 收到的总量始终为 `None`。
+/// 被取消的令牌会中止传输。
+pub fn clone_repo_with_progress(
+    url: &str,
+    dest: &Path,
+    cancel: &CancelToken,
+    on_progress: &mut dyn FnMut(u64, Option<u64>),
+) -> Result<Repository, FetchError> {
+    let result = clone_or_fetch(url, dest, fetch_options(cancel, on_progress));
+    cancel.check()?;
+    result
+}
+
+/// Fetch options that report transfer progress and abort once `cancel` is set.
+/// 报告传输进度并在 `cancel` 被设置后中止的获取选项。
+fn fetch_options<'a>(
+    cancel: &'a CancelToken,
+    on_progress: &'a mut dyn FnMut(u64, Option<u64>),
+) -> FetchOptions<'a> {
+    let mut callbacks = RemoteCallbacks::new();
+    callbacks.transfer_progress(move |stats| {
+        on_progress(stats.received_bytes() as u64, None);
+        !cancel.is_cancelled()
+    });
+    let mut options = FetchOptions::new();
+    options.remote_callbacks(callbacks);
+    options
+}
+
+/// Fetch just enough of `url` into a new repository at `dest` to check out `rev`.
+/// 将 `url` 中足以检出 `rev` 的内容获取到 `dest` 处的新仓库。
+///
+/// A branch, tag or full commit SHA is first fetched with `depth 1`. If the
+/// remote cannot serve it that way (for example an unadvertised SHA, or a
+/// transport without shallow support), the whole history is fetched instead.
+/// 分支、标签或完整提交 SHA 首先以 `depth 1` 获取。如果远程无法以这种方式提供
+/// （例如未公布的 SHA，或不支持浅获取的传输），则改为获取完整历史。
+pub fn fetch_rev(
+    url: &str,
+    dest: &Path,
+    rev: &str,
+    cancel: &CancelToken,
+    on_progress: &mut dyn FnMut(u64, Option<u64>),
+) -> Result<Repository, FetchError> {
+    let candidates = if is_commit_sha(rev) {
+        vec![rev.to_string()]
+    } else {
+        vec![
+            format!("+refs/heads/{0}:refs/remotes/origin/{0}", rev),
+            format!("+refs/tags/{0}:refs/tags/{0}", rev),
+        ]
+    };
+
+    let repo = init_with_origin(url, dest)?;
+    for refspec in &candidates {
+        let mut options = fetch_options(cancel, on_progress);
+        options.depth(1);
+        let fetched = repo
+            .find_remote("origin")
+            .and_then(|mut remote| remote.fetch(&[refspec.as_str()], Some(&mut options), None));
+        cancel.check()?;
+        if fetched.is_ok() && resolves(&repo, rev) {
+            return Ok(repo);
+        }
+    }
+
+    // Start over so no shallow state leaks into the full fetch
+    // 重新开始，避免浅获取状态泄漏到完整获取中
+    drop(repo);
+    fs::remove_dir_all(dest)?;
+    let repo = init_with_origin(url, dest)?;
+    repo.find_remote("origin")
+        .and_then(|mut remote| {
+            remote.fetch(
+                &[
+                    "+refs/heads/*:refs/remotes/origin/*",
+                    "+refs/tags/*:refs/tags/*",
+                ],
+                Some(&mut fetch_options(cancel, on_progress)),
+                None,
+            )
+        })
+        .map_err(|e| FetchError::Git(format!("failed to fetch: {}", e)))?;
+    cancel.check()?;
+    Ok(repo)
+}
+
+/// Create an empty repository at `dest` with `url` as its `origin` remote.
+/// 在 `dest` 创建一个以 `url` 作为 `origin` 远程的空仓库。
+fn init_with_origin(url: &str, dest: &Path) -> Result<Repository, FetchError> {
+    let repo = Repository::init(dest)
+        .map_err(|e| FetchError::Git(format!("failed to init repository: {}", e)))?;
+    repo.remote("origin", url)
+        .map_err(|e| FetchError::Git(format!("failed to add remote: {}", e)))?;
+    Ok(repo)
+}
+
+/// Whether `rev` is a full hexadecimal commit SHA.
+/// `rev` 是否为完整的十六进制提交 SHA。
+fn is_commit_sha(rev: &str) -> bool {
+    rev.len() == 40 && rev.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Whether `rev` can be checked out from what has been fetched so far.
+/// 是否可以从目前已获取的内容中检出 `rev`。
+fn resolves(repo: &Repository, rev: &str) -> bool {
+    if is_commit_sha(rev) {
+        return Oid::from_str(rev).is_ok_and(|oid| repo.find_commit(oid).is_ok());
+    }
+    repo.find_reference(&format!("refs/remotes/origin/{}", rev))
+        .is_ok()
+        || repo.find_reference(&format!("refs/tags/{}", rev)).is_ok()
+}
+
+/// Initialize and update every submodule of `repo`, recursively.
+/// 递归地初始化并更新 `repo` 的每个子模块。
+pub fn update_submodules(
+    repo: &Repository,
+    cancel: &CancelToken,
+    on_progress: &mut dyn FnMut(u64, Option<u64>),
+) -> Result<(), FetchError> {
+    let submodules = repo
+        .submodules()
+        .map_err(|e| FetchError::Git(format!("failed to list submodules: {}", e)))?;
+
+    for mut submodule in submodules {
+        cancel.check()?;
+        let name = submodule.name().unwrap_or("<unnamed>").to_string();
+        {
+            let mut options = SubmoduleUpdateOptions::new();
+            options.fetch(fetch_options(cancel, on_progress));
+            submodule.update(true, Some(&mut options)).map_err(|e| {
+                FetchError::Git(format!("failed to update submodule {}: {}", name, e))
+            })?;
+        }
+        cancel.check()?;
+
+        let nested = submodule
+            .open()
+            .map_err(|e| FetchError::Git(format!("failed to open submodule {}: {}", name, e)))?;
+        update_submodules(&nested, cancel, on_progress)?;
+    }
+
+    Ok(())
+}
+
+/// Remove every `.git` directory or file under `dir`, leaving a pure source tree.
+/// 删除 `dir` 下的所有 `.git` 目录或文件，只留下纯源码树。
+///
+/// Submodule checkouts carry a `.git` file of their own, so the whole tree is
+/// walked rather than just its root.
+/// 子模块检出带有各自的 `.git` 文件，因此会遍历整棵树而不仅仅是其根目录。
+pub fn strip_git_metadata(dir: &Path) -> Result<(), FetchError> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+        if entry.file_name() == ".git" {
+            if file_type.is_dir() {
+                fs::remove_dir_all(&path)?;
+            } else {
+                fs::remove_file(&path)?;
+            }
+        } else if file_type.is_dir() {
+            strip_git_metadata(&path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Clone `url` into `dest`, or fetch into an existing clone there.
+/// 将 `url` 克隆到 `dest`，或在已有的克隆中获取更新。
+fn clone_or_fetch(
+    url: &str,
+    dest: &Path,
+    mut fetch_options: FetchOptions<'_>,
+) -> Result<Repository, FetchError> {
     if dest.exists() {
         // Open existing repo and fetch updates
         // 打开现有仓库并获取更新
@@ -26,7 +218,6 @@ pub fn clone_repo(url: &str, dest: &Path) -> Result<Repository, FetchError> {
                 .find_remote("origin")
                 .map_err(|e| FetchError::Git(format!("failed to find remote: {}", e)))?;
 
-            let mut fetch_options = FetchOptions::new();
             remote
                 .fetch(&[] as &[&str], Some(&mut fetch_options), None)
                 .map_err(|e| FetchError::Git(format!("failed to fetch: {}", e)))?;
@@ -37,6 +228,7 @@ pub fn clone_repo(url: &str, dest: &Path) -> Result<Repository, FetchError> {
         // Clone the repository
         // 克隆仓库
         RepoBuilder::new()
+            .fetch_options(fetch_options)
             .clone(url, dest)
             .map_err(|e| FetchError::Git(format!("failed to clone: {}", e)))
     }
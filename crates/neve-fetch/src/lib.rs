@@ -4,22 +4,35 @@
 //! This crate provides functionality for fetching sources from various locations:
 //! 本 crate 提供从各种位置获取源码的功能：
 //!
-//! - URLs (http/https) / URL（http/https）
+//! - URLs (http/https, and s3 with AWS credentials) / URL（http/https，以及使用 AWS 凭证的 s3）
 //! - Local files / 本地文件
 //! - Git repositories / Git 仓库
 //!
-//! All fetched content is verified against expected hashes.
-//! 所有获取的内容都会根据预期哈希进行验证。
+//! All fetched content is verified against expected hashes. Fetches can
+//! report progress and be cancelled; a cancelled fetch leaves nothing behind
+//! in the cache.
+//! 所有获取的内容都会根据预期哈希进行验证。获取可以报告进度并可被取消；
+//! 被取消的获取不会在缓存中留下任何内容。
 
 pub mod archive;
+mod cache;
+pub mod cancel;
 pub mod git;
+pub mod s3;
 pub mod url;
 pub mod verify;
 
-use neve_derive::Hash;
+pub use cancel::CancelToken;
+
+use neve_derive::{Hash, HashAlgo, HashError};
 use std::path::PathBuf;
+use std::sync::Mutex;
 use thiserror::Error;
 
+/// A progress callback receiving the bytes processed so far and the total if known.
+/// 进度回调，接收目前已处理的字节数和已知的总量。
+pub type ProgressFn = Box<dyn FnMut(u64, Option<u64>) + Send>;
+
 /// Errors that can occur during fetching.
 /// 获取过程中可能发生的错误。
 #[derive(Debug, Error)]
@@ -40,6 +53,19 @@ pub enum FetchError {
     #[error("unsupported URL scheme: {0}")]
     UnsupportedScheme(String),
 
+    /// A URL that could not be understood. / 无法理解的 URL。
+    #[error("invalid URL: {0}")]
+    InvalidUrl(String),
+
+    /// The primary URL and every mirror failed. / 主 URL 和所有镜像均失败。
+    #[error("all URLs failed: {}", describe_failures(.0))]
+    AllUrlsFailed(Vec<(String, FetchError)>),
+
+    /// A required credential environment variable is not set.
+    /// 未设置必需的凭证环境变量。
+    #[error("missing credentials: {0} is not set")]
+    MissingCredentials(String),
+
     /// Archive extraction error. / 归档解压错误。
     #[error("archive error: {0}")]
     Archive(String),
@@ -48,9 +74,39 @@ pub enum FetchError {
     #[error("verification failed: {0}")]
     Verification(String),
 
+    /// A detached signature did not verify the content. / 分离签名未能验证内容。
+    #[error("invalid signature: {0}")]
+    SignatureInvalid(String),
+
     /// Git operation error. / Git 操作错误。
     #[error("Git error: {0}")]
     Git(String),
+
+    /// The fetch was cancelled. / 获取已被取消。
+    #[error("fetch cancelled")]
+    Cancelled,
+
+    /// A malformed expected hash. / 格式错误的预期哈希。
+    #[error("invalid hash: {0}")]
+    InvalidHash(#[from] HashError),
+
+    /// Network access was needed while offline. / 离线时需要网络访问。
+    #[error("network access is disabled (hermetic mode): cannot fetch {0}")]
+    Offline(String),
+
+    /// A source was missing from the cache while offline. / 离线时缓存中缺少源。
+    #[error("{url} is not in the cache and network access is disabled (offline mode)")]
+    OfflineMiss { url: String },
+}
+
+/// List each failed URL with the reason it failed.
+/// 列出每个失败的 URL 及其失败原因。
+fn describe_failures(failures: &[(String, FetchError)]) -> String {
+    failures
+        .iter()
+        .map(|(url, err)| format!("{} ({})", url, err))
+        .collect::<Vec<_>>()
+        .join("; ")
 }
 
 /// A source to fetch.
@@ -65,6 +121,10 @@ pub enum Source {
         hash: Option<Hash>,
         /// Optional name for the downloaded file. / 下载文件的可选名称。
         name: Option<String>,
+        /// Fallback URLs tried in order when `url` fails. / 当 `url` 失败时按顺序尝试的备用 URL。
+        mirrors: Vec<String>,
+        /// Detached signature the content must carry. / 内容必须具备的分离签名。
+        signature: Option<verify::SignatureSpec>,
     },
     /// Use a local file. / 使用本地文件。
     Path {
@@ -81,6 +141,8 @@ pub enum Source {
         rev: String,
         /// Expected hash for verification. / 用于验证的预期哈希。
         hash: Option<Hash>,
+        /// Whether to check out submodules recursively. / 是否递归检出子模块。
+        submodules: bool,
     },
 }
 
@@ -92,6 +154,8 @@ impl Source {
             url: url.into(),
             hash: None,
             name: None,
+            mirrors: Vec::new(),
+            signature: None,
         }
     }
 
@@ -102,6 +166,8 @@ impl Source {
             url: url.into(),
             hash: Some(hash),
             name: None,
+            mirrors: Vec::new(),
+            signature: None,
         }
     }
 
@@ -121,6 +187,7 @@ impl Source {
             url: url.into(),
             rev: rev.into(),
             hash: None,
+            submodules: false,
         }
     }
 
@@ -128,31 +195,142 @@ impl Source {
     /// 设置预期哈希。
     pub fn with_hash(self, hash: Hash) -> Self {
         match self {
-            Source::Url { url, name, .. } => Source::Url {
+            Source::Url {
+                url,
+                name,
+                mirrors,
+                signature,
+                ..
+            } => Source::Url {
                 url,
                 hash: Some(hash),
                 name,
+                mirrors,
+                signature,
             },
             Source::Path { path, .. } => Source::Path {
                 path,
                 hash: Some(hash),
             },
-            Source::Git { url, rev, .. } => Source::Git {
+            Source::Git {
+                url,
+                rev,
+                submodules,
+                ..
+            } => Source::Git {
                 url,
                 rev,
                 hash: Some(hash),
+                submodules,
             },
         }
     }
 
+    /// Set the expected hash from its hex form.
+    /// 从十六进制形式设置预期哈希。
+    ///
+    /// Fails on a truncated or otherwise malformed hash instead of fetching
+    /// unverified content.
+    /// 对截断或其他格式错误的哈希返回错误，而不是获取未经验证的内容。
+    pub fn with_hex_hash(self, hex: &str) -> Result<Self, FetchError> {
+        Ok(self.with_hash(Hash::from_hex(hex)?))
+    }
+
     /// Set the name (for URL sources).
     /// 设置名称（用于 URL 源）。
     pub fn with_name(self, name: impl Into<String>) -> Self {
         match self {
-            Source::Url { url, hash, .. } => Source::Url {
+            Source::Url {
+                url,
+                hash,
+                mirrors,
+                signature,
+                ..
+            } => Source::Url {
                 url,
                 hash,
                 name: Some(name.into()),
+                mirrors,
+                signature,
+            },
+            other => other,
+        }
+    }
+
+    /// Set the fallback URLs (for URL sources).
+    /// 设置备用 URL（用于 URL 源）。
+    ///
+    /// Mirrors are tried in order after the primary URL fails to download or
+    /// its content does not match the expected hash.
+    /// 当主 URL 下载失败或其内容与预期哈希不匹配时，按顺序尝试镜像。
+    pub fn with_mirrors<I, S>(self, mirrors: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        match self {
+            Source::Url {
+                url,
+                hash,
+                name,
+                signature,
+                ..
+            } => Source::Url {
+                url,
+                hash,
+                name,
+                mirrors: mirrors.into_iter().map(Into::into).collect(),
+                signature,
+            },
+            other => other,
+        }
+    }
+
+    /// Check out submodules recursively (for Git sources).
+    /// 递归检出子模块（用于 Git 源）。
+    ///
+    /// Submodule content is part of the fetched tree and so of its hash.
+    /// 子模块内容是所获取树的一部分，因此也计入其哈希。
+    pub fn with_submodules(self, submodules: bool) -> Self {
+        match self {
+            Source::Git { url, rev, hash, .. } => Source::Git {
+                url,
+                rev,
+                hash,
+                submodules,
+            },
+            other => other,
+        }
+    }
+
+    /// Require a detached minisign signature (for URL sources).
+    /// 要求分离的 minisign 签名（用于 URL 源）。
+    ///
+    /// The signature is downloaded from `signature_url` and must verify the
+    /// content under `public_key` before it is cached, whichever mirror served it.
+    /// 签名从 `signature_url` 下载，并且在缓存之前必须使用 `public_key` 验证内容，
+    /// 无论内容来自哪个镜像。
+    pub fn with_signature(
+        self,
+        signature_url: impl Into<String>,
+        public_key: impl Into<String>,
+    ) -> Self {
+        match self {
+            Source::Url {
+                url,
+                hash,
+                name,
+                mirrors,
+                ..
+            } => Source::Url {
+                url,
+                hash,
+                name,
+                mirrors,
+                signature: Some(verify::SignatureSpec {
+                    url: signature_url.into(),
+                    public_key: public_key.into(),
+                }),
             },
             other => other,
         }
@@ -176,6 +354,16 @@ pub struct FetchResult {
 pub struct Fetcher {
     /// Cache directory. / 缓存目录。
     cache_dir: PathBuf,
+    /// Whether network access is forbidden. / 是否禁止网络访问。
+    offline: bool,
+    /// Progress callback used by [`Fetcher::fetch`]. / [`Fetcher::fetch`] 使用的进度回调。
+    progress: Mutex<Option<ProgressFn>>,
+    /// Maximum cache size in bytes, if limited. / 缓存大小上限（字节），如有限制。
+    max_size: Option<u64>,
+    /// Last-use times of cache entries. / 缓存条目的最近使用时间。
+    access: cache::AccessIndex,
+    /// Token that stops every fetch of this fetcher. / 停止此获取器所有获取的令牌。
+    cancel: CancelToken,
 }
 
 impl Fetcher {
@@ -183,26 +371,252 @@ impl Fetcher {
     /// 使用给定的缓存目录创建新的获取器。
     pub fn new(cache_dir: PathBuf) -> Result<Self, FetchError> {
         std::fs::create_dir_all(&cache_dir)?;
-        Ok(Self { cache_dir })
+        Ok(Self {
+            access: cache::AccessIndex::new(&cache_dir),
+            cache_dir,
+            offline: false,
+            progress: Mutex::new(None),
+            max_size: None,
+            cancel: CancelToken::new(),
+        })
+    }
+
+    /// Limit the cache to `bytes`, evicting least-recently-used entries.
+    /// 将缓存限制为 `bytes` 字节，淘汰最近最少使用的条目。
+    ///
+    /// The limit is enforced after every new entry is written; the entry just
+    /// fetched is never evicted by its own fetch.
+    /// 每次写入新条目后都会执行该限制；刚获取的条目不会因其自身的获取而被淘汰。
+    pub fn with_max_size(mut self, bytes: u64) -> Self {
+        self.max_size = Some(bytes);
+        self
+    }
+
+    /// Evict least-recently-used entries until the cache fits its size limit.
+    /// 淘汰最近最少使用的条目，直到缓存符合其大小限制。
+    ///
+    /// Returns the number of bytes freed; without a limit nothing is evicted.
+    /// 返回释放的字节数；没有限制时不淘汰任何内容。
+    pub fn prune(&self) -> Result<u64, FetchError> {
+        match self.max_size {
+            Some(max_size) => self.access.evict(max_size, None),
+            None => Ok(0),
+        }
+    }
+
+    /// Record a cache hit on `entry`.
+    /// 记录对 `entry` 的缓存命中。
+    fn cache_hit(&self, entry: PathBuf, hash: Hash) -> Result<FetchResult, FetchError> {
+        self.access.touch(&entry)?;
+        Ok(FetchResult {
+            path: entry,
+            hash,
+            cached: true,
+        })
+    }
+
+    /// Record a newly written `entry`, then enforce the size limit.
+    /// 记录新写入的 `entry`，然后执行大小限制。
+    fn cache_write(&self, entry: PathBuf, hash: Hash) -> Result<FetchResult, FetchError> {
+        self.access.touch(&entry)?;
+        if let Some(max_size) = self.max_size {
+            self.access.evict(max_size, Some(&entry))?;
+        }
+        Ok(FetchResult {
+            path: entry,
+            hash,
+            cached: false,
+        })
+    }
+
+    /// Report the progress of every [`Fetcher::fetch`] to `callback`.
+    /// 将每次 [`Fetcher::fetch`] 的进度报告给 `callback`。
+    ///
+    /// The callback receives the bytes downloaded (or, for Git sources,
+    /// received) so far and the total size if known.
+    /// 回调接收目前已下载（对于 Git 源为已接收）的字节数和已知的总大小。
+    pub fn with_progress(self, callback: ProgressFn) -> Self {
+        Self {
+            progress: Mutex::new(Some(callback)),
+            ..self
+        }
+    }
+
+    /// Forbid or allow network access.
+    /// 禁止或允许网络访问。
+    ///
+    /// An offline fetcher serves URL and Git sources only from its cache and
+    /// fails with [`FetchError::OfflineMiss`] instead of downloading; local
+    /// paths are still read. Uncached requests such as [`Fetcher::fetch_text`]
+    /// fail with [`FetchError::Offline`].
+    /// 离线获取器仅从缓存提供 URL 和 Git 源，并以 [`FetchError::OfflineMiss`] 失败而不是下载；
+    /// 本地路径仍可读取。[`Fetcher::fetch_text`] 等不经缓存的请求以 [`FetchError::Offline`] 失败。
+    pub fn with_offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// Stop every fetch of this fetcher once `cancel` is cancelled.
+    /// 在 `cancel` 被取消后停止此获取器的所有获取。
+    ///
+    /// [`Fetcher::fetch_with_progress`] uses the token it is given instead.
+    /// [`Fetcher::fetch_with_progress`] 改用传给它的令牌。
+    pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Forbid network access; shorthand for `with_offline(true)`.
+    /// 禁止网络访问；`with_offline(true)` 的简写。
+    pub fn offline(self) -> Self {
+        self.with_offline(true)
+    }
+
+    /// Whether network access is forbidden.
+    /// 是否禁止网络访问。
+    pub fn is_offline(&self) -> bool {
+        self.offline
+    }
+
+    /// Fail if the network may not be used to fetch `what`.
+    /// 如果不能使用网络获取 `what` 则失败。
+    fn require_network(&self, what: &str) -> Result<(), FetchError> {
+        if self.offline {
+            return Err(FetchError::Offline(what.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Fail if `url` missed the cache and may not be downloaded.
+    /// 如果 `url` 未命中缓存且不能下载则失败。
+    fn require_download(&self, url: &str) -> Result<(), FetchError> {
+        if self.offline {
+            return Err(FetchError::OfflineMiss {
+                url: url.to_string(),
+            });
+        }
+        Ok(())
     }
 
     /// Fetch a source.
     /// 获取源。
     pub fn fetch(&self, source: &Source) -> Result<FetchResult, FetchError> {
+        let mut progress = self.progress.lock().unwrap_or_else(|e| e.into_inner());
+        match progress.as_mut() {
+            Some(callback) => self.fetch_with_progress(source, &self.cancel, callback),
+            None => self.fetch_with_progress(source, &self.cancel, &mut |_, _| {}),
+        }
+    }
+
+    /// Fetch an archive and unpack it into the cache.
+    /// 获取归档并将其解包到缓存中。
+    ///
+    /// The archive itself is verified against the source's hash, then
+    /// extracted into a directory addressed by that hash. The format is
+    /// detected from the file extension or, failing that, its magic bytes.
+    /// Git sources are already directories and are returned as fetched.
+    /// 归档本身会根据源的哈希进行验证，然后解压到以该哈希寻址的目录中。格式从文件
+    /// 扩展名检测，失败时根据魔数检测。Git 源本身已是目录，按获取结果原样返回。
+    pub fn fetch_and_unpack(&self, source: &Source) -> Result<FetchResult, FetchError> {
+        let archive = self.fetch(source)?;
+        if matches!(source, Source::Git { .. }) || archive.path.is_dir() {
+            return Ok(archive);
+        }
+
+        let unpacked_path = self.unpacked_cache_path(&archive.hash);
+        if unpacked_path.exists() {
+            return self.cache_hit(unpacked_path, archive.hash);
+        }
+
+        // Extract next to the cache entry so a failed extraction leaves nothing behind
+        // 在缓存条目旁解压，使解压失败时不留下任何内容
+        let format = archive::ArchiveFormat::detect(&archive.path)?;
+        let temp_dir = tempfile::tempdir_in(&self.cache_dir)?;
+        {
+            let mut progress = self.progress.lock().unwrap_or_else(|e| e.into_inner());
+            match progress.as_mut() {
+                Some(callback) => archive::extract_format_with_progress(
+                    &archive.path,
+                    temp_dir.path(),
+                    format,
+                    callback,
+                )?,
+                None => archive::extract_with_format(&archive.path, temp_dir.path(), format)?,
+            }
+        }
+
+        if let Some(parent) = unpacked_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        if let Err(err) = std::fs::rename(temp_dir.path(), &unpacked_path) {
+            // Another fetch may have unpacked the same archive concurrently
+            // 另一个获取可能已并发解包了同一归档
+            if !unpacked_path.exists() {
+                return Err(err.into());
+            }
+        }
+
+        self.cache_write(unpacked_path, archive.hash)
+    }
+
+    /// Fetch a source, reporting progress and stopping when `cancel` is set.
+    /// 获取源，报告进度并在 `cancel` 被设置时停止。
+    ///
+    /// `on_progress` receives the bytes fetched so far and the total size if
+    /// known. A cancelled fetch returns [`FetchError::Cancelled`] and removes
+    /// any partially written file.
+    /// `on_progress` 接收目前已获取的字节数和已知的总大小。被取消的获取返回
+    /// [`FetchError::Cancelled`] 并删除任何部分写入的文件。
+    pub fn fetch_with_progress(
+        &self,
+        source: &Source,
+        cancel: &CancelToken,
+        on_progress: &mut dyn FnMut(u64, Option<u64>),
+    ) -> Result<FetchResult, FetchError> {
+        cancel.check()?;
         match source {
-            Source::Url { url, hash, name } => self.fetch_url(url, hash.as_ref(), name.as_deref()),
-            Source::Path { path, hash } => self.fetch_path(path, hash.as_ref()),
-            Source::Git { url, rev, hash } => self.fetch_git(url, rev, hash.as_ref()),
+            Source::Url {
+                url,
+                hash,
+                name,
+                mirrors,
+                signature,
+            } => self.fetch_url(
+                url,
+                mirrors,
+                hash.as_ref(),
+                signature.as_ref(),
+                name.as_deref(),
+                cancel,
+                on_progress,
+            ),
+            Source::Path { path, hash } => {
+                let result = self.fetch_path(path, hash.as_ref())?;
+                let size = std::fs::metadata(&result.path)?.len();
+                on_progress(size, Some(size));
+                Ok(result)
+            }
+            Source::Git {
+                url,
+                rev,
+                hash,
+                submodules,
+            } => self.fetch_git(url, rev, hash.as_ref(), *submodules, cancel, on_progress),
         }
     }
 
-    /// Fetch from a URL.
-    /// 从 URL 获取。
+    /// Fetch from a URL, falling back to its mirrors in order.
+    /// 从 URL 获取，失败时按顺序回退到其镜像。
+    #[allow(clippy::too_many_arguments)]
     fn fetch_url(
         &self,
         url: &str,
+        mirrors: &[String],
         expected_hash: Option<&Hash>,
+        signature: Option<&verify::SignatureSpec>,
         name: Option<&str>,
+        cancel: &CancelToken,
+        on_progress: &mut dyn FnMut(u64, Option<u64>),
     ) -> Result<FetchResult, FetchError> {
         // Derive name from URL if not provided
         // 如果未提供，从 URL 推导名称
@@ -215,43 +629,89 @@ impl Fetcher {
         if let Some(hash) = expected_hash {
             let cached_path = self.cache_path(hash, &file_name);
             if cached_path.exists() {
-                return Ok(FetchResult {
-                    path: cached_path,
-                    hash: *hash,
-                    cached: true,
-                });
+                return self.cache_hit(cached_path, *hash);
             }
         }
 
-        // Download to temp file
-        // 下载到临时文件
-        let content = url::fetch_url(url)?;
-        let actual_hash = Hash::of(&content);
+        self.require_download(url)?;
+
+        // The signature is fetched once and checked against whichever URL serves the content
+        // 签名只获取一次，并用于检查提供内容的任意 URL
+        let signature = match signature {
+            Some(spec) => Some((
+                verify::fetch_signature(&spec.url)?,
+                spec.public_key.as_str(),
+            )),
+            None => None,
+        };
+        let signature = signature
+            .as_ref()
+            .map(|(text, public_key)| (text.as_str(), *public_key));
+
+        // Try the primary URL, then each mirror, until one yields the expected content
+        // 依次尝试主 URL 和每个镜像，直到某个返回预期内容
+        let mut failures = Vec::new();
+        for candidate in std::iter::once(url).chain(mirrors.iter().map(String::as_str)) {
+            match self.download_url(
+                candidate,
+                expected_hash,
+                signature,
+                &file_name,
+                cancel,
+                on_progress,
+            ) {
+                Ok(result) => return Ok(result),
+                Err(FetchError::Cancelled) => return Err(FetchError::Cancelled),
+                Err(err) => failures.push((candidate.to_string(), err)),
+            }
+        }
+
+        if failures.len() == 1 {
+            Err(failures.remove(0).1)
+        } else {
+            Err(FetchError::AllUrlsFailed(failures))
+        }
+    }
+
+    /// Download one URL into the cache, verifying it against `expected_hash`.
+    /// 将一个 URL 下载到缓存中，并根据 `expected_hash` 进行验证。
+    fn download_url(
+        &self,
+        url: &str,
+        expected_hash: Option<&Hash>,
+        signature: Option<(&str, &str)>,
+        file_name: &str,
+        cancel: &CancelToken,
+        on_progress: &mut dyn FnMut(u64, Option<u64>),
+    ) -> Result<FetchResult, FetchError> {
+        // Download to a temp file that is removed unless it is kept
+        // 下载到临时文件，除非保留，否则会被删除
+        let mut temp = tempfile::NamedTempFile::new_in(&self.cache_dir)?;
+        let algo = expected_hash.map_or(HashAlgo::default(), Hash::algo);
+        let actual_hash = url::download(url, temp.as_file_mut(), algo, cancel, on_progress)?;
 
         // Verify hash if expected
         // 如果有预期哈希则验证
-        if let Some(expected) = expected_hash
-            && actual_hash != *expected
-        {
-            return Err(FetchError::HashMismatch {
-                expected: expected.to_hex(),
-                actual: actual_hash.to_hex(),
-            });
+        if let Some(expected) = expected_hash {
+            verify::check_hash(expected, &actual_hash)?;
+        }
+
+        // Verify the detached signature before anything reaches the cache
+        // 在任何内容进入缓存之前验证分离签名
+        if let Some((signature, public_key)) = signature {
+            let content = std::fs::read(temp.path())?;
+            verify::verify_signature(&content, signature, public_key)?;
         }
 
-        // Store in cache
-        // 存储到缓存
-        let cache_path = self.cache_path(&actual_hash, &file_name);
+        // Move into the cache
+        // 移动到缓存
+        let cache_path = self.cache_path(&actual_hash, file_name);
         if let Some(parent) = cache_path.parent() {
             std::fs::create_dir_all(parent)?;
         }
-        std::fs::write(&cache_path, &content)?;
+        temp.persist(&cache_path).map_err(|e| e.error)?;
 
-        Ok(FetchResult {
-            path: cache_path,
-            hash: actual_hash,
-            cached: false,
-        })
+        self.cache_write(cache_path, actual_hash)
     }
 
     /// Fetch from a local path.
@@ -262,15 +722,11 @@ impl Fetcher {
         expected_hash: Option<&Hash>,
     ) -> Result<FetchResult, FetchError> {
         let content = std::fs::read(path)?;
-        let actual_hash = Hash::of(&content);
+        let algo = expected_hash.map_or(HashAlgo::default(), Hash::algo);
+        let actual_hash = Hash::of_with(algo, &content);
 
-        if let Some(expected) = expected_hash
-            && actual_hash != *expected
-        {
-            return Err(FetchError::HashMismatch {
-                expected: expected.to_hex(),
-                actual: actual_hash.to_hex(),
-            });
+        if let Some(expected) = expected_hash {
+            verify::check_hash(expected, &actual_hash)?;
         }
 
         Ok(FetchResult {
@@ -287,6 +743,9 @@ impl Fetcher {
         url: &str,
         rev: &str,
         expected_hash: Option<&Hash>,
+        submodules: bool,
+        cancel: &CancelToken,
+        on_progress: &mut dyn FnMut(u64, Option<u64>),
     ) -> Result<FetchResult, FetchError> {
         // Derive a name from the URL
         // 从 URL 推导名称
@@ -301,47 +760,42 @@ impl Fetcher {
         if let Some(hash) = expected_hash {
             let cached_path = self.git_cache_path(hash, repo_name);
             if cached_path.exists() {
-                return Ok(FetchResult {
-                    path: cached_path,
-                    hash: *hash,
-                    cached: true,
-                });
+                return self.cache_hit(cached_path, *hash);
             }
         }
 
+        self.require_download(url)?;
+
         // Clone to a temporary location first
         // 先克隆到临时位置
         let temp_dir = tempfile::tempdir()?;
         let clone_path = temp_dir.path().join("repo");
 
-        // Clone the repository
-        // 克隆仓库
-        let repo = git::clone_repo(url, &clone_path)?;
+        // Fetch as little history as the revision needs
+        // 只获取该修订版本所需的历史
+        let repo = git::fetch_rev(url, &clone_path, rev, cancel, on_progress)?;
 
         // Checkout the specified revision
         // 检出指定的修订版本
         let _oid = git::checkout_rev(&repo, rev)?;
-
-        // Remove .git directory to make it a pure source tree
-        // 删除 .git 目录使其成为纯源码树
-        let git_dir = clone_path.join(".git");
-        if git_dir.exists() {
-            std::fs::remove_dir_all(&git_dir)?;
+        if submodules {
+            git::update_submodules(&repo, cancel, on_progress)?;
         }
 
+        // Remove Git metadata, including submodules', to make it a pure source tree
+        // 删除 Git 元数据（包括子模块的），使其成为纯源码树
+        drop(repo);
+        git::strip_git_metadata(&clone_path)?;
+
         // Hash the directory contents
         // 哈希目录内容
-        let actual_hash = git::hash_directory(&clone_path)?;
+        let algo = expected_hash.map_or(HashAlgo::default(), Hash::algo);
+        let actual_hash = verify::hash_dir_with(&clone_path, algo)?;
 
         // Verify hash if expected
         // 如果有预期哈希则验证
-        if let Some(expected) = expected_hash
-            && actual_hash != *expected
-        {
-            return Err(FetchError::HashMismatch {
-                expected: expected.to_hex(),
-                actual: actual_hash.to_hex(),
-            });
+        if let Some(expected) = expected_hash {
+            verify::check_hash(expected, &actual_hash)?;
         }
 
         // Move to cache
@@ -351,39 +805,55 @@ impl Fetcher {
             std::fs::create_dir_all(parent)?;
         }
 
-        // Copy the repo to cache (can't move across filesystems)
-        // 复制仓库到缓存（不能跨文件系统移动）
-        copy_dir_all(&clone_path, &cache_path)?;
+        // Copy the repo next to the cache (can't move across filesystems),
+        // then rename it into place so a partial copy is never visible
+        // 将仓库复制到缓存旁（不能跨文件系统移动），再重命名到位，使部分复制永远不可见
+        cancel.check()?;
+        let staging = tempfile::tempdir_in(&self.cache_dir)?;
+        copy_dir_all(&clone_path, staging.path())?;
+        if let Err(err) = std::fs::rename(staging.path(), &cache_path)
+            && !cache_path.exists()
+        {
+            return Err(err.into());
+        }
 
-        Ok(FetchResult {
-            path: cache_path,
-            hash: actual_hash,
-            cached: false,
-        })
+        self.cache_write(cache_path, actual_hash)
     }
 
     /// Get the cache path for a Git repository.
     /// 获取 Git 仓库的缓存路径。
     fn git_cache_path(&self, hash: &Hash, name: &str) -> PathBuf {
-        let hash_prefix = &hash.to_hex()[..2];
+        let hash_prefix = &hash.digest_hex()[..2];
         self.cache_dir
             .join("git")
             .join(hash_prefix)
-            .join(format!("{}-{}", hash.to_hex(), name))
+            .join(format!("{}-{}", hash.digest_hex(), name))
+    }
+
+    /// Get the cache path for an unpacked archive.
+    /// 获取已解包归档的缓存路径。
+    fn unpacked_cache_path(&self, hash: &Hash) -> PathBuf {
+        let hash_prefix = &hash.digest_hex()[..2];
+        self.cache_dir
+            .join("unpacked")
+            .join(hash_prefix)
+            .join(hash.digest_hex())
     }
 
     /// Get the cache path for a hash.
     /// 获取哈希的缓存路径。
     fn cache_path(&self, hash: &Hash, name: &str) -> PathBuf {
-        let hash_prefix = &hash.to_hex()[..2];
+        let hash_prefix = &hash.digest_hex()[..2];
         self.cache_dir
             .join(hash_prefix)
-            .join(format!("{}-{}", hash.to_hex(), name))
+            .join(format!("{}-{}", hash.digest_hex(), name))
     }
 
     /// Fetch text content from a URL.
     /// 从 URL 获取文本内容。
     pub fn fetch_text(&self, url: &str) -> Result<String, FetchError> {
+        self.cancel.check()?;
+        self.require_network(url)?;
         let content = url::fetch_url(url)?;
         String::from_utf8(content)
             .map_err(|e| FetchError::Verification(format!("Invalid UTF-8: {}", e)))
@@ -391,14 +861,29 @@ impl Fetcher {
 
     /// Fetch a file from a URL and save to destination.
     /// 从 URL 获取文件并保存到目标位置。
+    ///
+    /// A failed or cancelled download removes the partially written file.
+    /// 失败或被取消的下载会删除部分写入的文件。
     pub fn fetch_file(&self, url: &str, dest: &std::path::Path) -> Result<(), FetchError> {
-        let content = url::fetch_url(url)?;
+        self.cancel.check()?;
+        self.require_network(url)?;
 
         if let Some(parent) = dest.parent() {
             std::fs::create_dir_all(parent)?;
         }
 
-        std::fs::write(dest, &content)?;
+        let mut file = std::fs::File::create(dest)?;
+        if let Err(e) = url::download(
+            url,
+            &mut file,
+            HashAlgo::default(),
+            &self.cancel,
+            &mut |_, _| {},
+        ) {
+            drop(file);
+            let _ = std::fs::remove_file(dest);
+            return Err(e);
+        }
         Ok(())
     }
 }
@@ -0,0 +1,263 @@
+//! S3 object fetching.
+//! S3 对象获取。
+//!
+//! `s3://bucket/key` URLs are fetched with a GET request signed with AWS
+//! Signature Version 4. Credentials come from `AWS_ACCESS_KEY_ID`,
+//! `AWS_SECRET_ACCESS_KEY` and the optional `AWS_SESSION_TOKEN`; the region
+//! from `AWS_REGION` (default `us-east-1`).
+//! `s3://bucket/key` URL 通过使用 AWS 签名版本 4 签名的 GET 请求获取。凭证来自
+//! `AWS_ACCESS_KEY_ID`、`AWS_SECRET_ACCESS_KEY` 和可选的 `AWS_SESSION_TOKEN`；
+//! 区域来自 `AWS_REGION`（默认 `us-east-1`）。
+
+use crate::FetchError;
+use hmac::{Hmac, Mac};
+use reqwest::blocking::{Client, Response};
+use sha2::{Digest, Sha256};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Region used when `AWS_REGION` is not set.
+/// 未设置 `AWS_REGION` 时使用的区域。
+const DEFAULT_REGION: &str = "us-east-1";
+
+/// SHA-256 of an empty payload, as sent for GET requests.
+/// 空负载的 SHA-256，用于 GET 请求。
+const EMPTY_PAYLOAD_SHA256: &str =
+    "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+/// An object in an S3 bucket.
+/// S3 存储桶中的对象。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct S3Object {
+    /// The bucket name. / 存储桶名称。
+    pub bucket: String,
+    /// The object key, without a leading slash. / 对象键，不含前导斜杠。
+    pub key: String,
+}
+
+impl S3Object {
+    /// Parse an `s3://bucket/key` URL.
+    /// 解析 `s3://bucket/key` URL。
+    pub fn parse(url: &str) -> Result<Self, FetchError> {
+        let rest = url
+            .strip_prefix("s3://")
+            .ok_or_else(|| FetchError::UnsupportedScheme(url.to_string()))?;
+        match rest.split_once('/') {
+            Some((bucket, key)) if !bucket.is_empty() && !key.is_empty() => Ok(Self {
+                bucket: bucket.to_string(),
+                key: key.to_string(),
+            }),
+            _ => Err(FetchError::InvalidUrl(format!(
+                "expected s3://bucket/key, got {}",
+                url
+            ))),
+        }
+    }
+
+    /// The virtual-hosted endpoint of the bucket in `region`.
+    /// 存储桶在 `region` 中的虚拟主机端点。
+    pub fn host(&self, region: &str) -> String {
+        if region == DEFAULT_REGION {
+            format!("{}.s3.amazonaws.com", self.bucket)
+        } else {
+            format!("{}.s3.{}.amazonaws.com", self.bucket, region)
+        }
+    }
+
+    /// The URI-encoded path of the object.
+    /// 对象的 URI 编码路径。
+    fn path(&self) -> String {
+        let mut path = String::from("/");
+        for byte in self.key.bytes() {
+            match byte {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                    path.push(byte as char)
+                }
+                _ => path.push_str(&format!("%{:02X}", byte)),
+            }
+        }
+        path
+    }
+}
+
+/// AWS credentials and region for signing requests.
+/// 用于签名请求的 AWS 凭证和区域。
+#[derive(Debug, Clone)]
+pub struct Credentials {
+    /// The access key ID. / 访问密钥 ID。
+    pub access_key_id: String,
+    /// The secret access key. / 秘密访问密钥。
+    pub secret_access_key: String,
+    /// A session token for temporary credentials. / 临时凭证的会话令牌。
+    pub session_token: Option<String>,
+    /// The bucket's region. / 存储桶所在区域。
+    pub region: String,
+}
+
+impl Credentials {
+    /// Read credentials from the environment.
+    /// 从环境变量读取凭证。
+    pub fn from_env() -> Result<Self, FetchError> {
+        Self::from_lookup(|name| std::env::var(name).ok())
+    }
+
+    /// Read credentials through `lookup`, which maps a variable name to its value.
+    /// 通过 `lookup`（将变量名映射到其值）读取凭证。
+    ///
+    /// Fails with [`FetchError::MissingCredentials`] naming the first
+    /// required variable that is unset or empty.
+    /// 以 [`FetchError::MissingCredentials`] 失败，并指出第一个未设置或为空的必需变量。
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Result<Self, FetchError> {
+        let get = |name: &str| lookup(name).filter(|value| !value.is_empty());
+        let require =
+            |name: &str| get(name).ok_or_else(|| FetchError::MissingCredentials(name.to_string()));
+        Ok(Self {
+            access_key_id: require("AWS_ACCESS_KEY_ID")?,
+            secret_access_key: require("AWS_SECRET_ACCESS_KEY")?,
+            session_token: get("AWS_SESSION_TOKEN"),
+            region: get("AWS_REGION").unwrap_or_else(|| DEFAULT_REGION.to_string()),
+        })
+    }
+}
+
+/// Sign a GET request for `object`, returning every header to send.
+/// 为 `object` 签名 GET 请求，返回所有要发送的请求头。
+///
+/// `amz_date` is the request time as `YYYYMMDD'T'HHMMSS'Z'`. `extra` headers
+/// (with lowercase names) are signed and returned along with `host`,
+/// `x-amz-*` and `authorization`.
+/// `amz_date` 是格式为 `YYYYMMDD'T'HHMMSS'Z'` 的请求时间。`extra` 请求头（名称为小写）
+/// 会被签名，并与 `host`、`x-amz-*` 和 `authorization` 一起返回。
+pub fn sign_get(
+    object: &S3Object,
+    credentials: &Credentials,
+    amz_date: &str,
+    extra: &[(&str, &str)],
+) -> Vec<(String, String)> {
+    let mut headers: Vec<(String, String)> = vec![
+        ("host".to_string(), object.host(&credentials.region)),
+        (
+            "x-amz-content-sha256".to_string(),
+            EMPTY_PAYLOAD_SHA256.to_string(),
+        ),
+        ("x-amz-date".to_string(), amz_date.to_string()),
+    ];
+    if let Some(token) = &credentials.session_token {
+        headers.push(("x-amz-security-token".to_string(), token.clone()));
+    }
+    headers.extend(
+        extra
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string())),
+    );
+    headers.sort();
+
+    let signed_headers = headers
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>()
+        .join(";");
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{}:{}\n", name, value.trim()))
+        .collect();
+    let canonical_request = format!(
+        "GET\n{}\n\n{}\n{}\n{}",
+        object.path(),
+        canonical_headers,
+        signed_headers,
+        EMPTY_PAYLOAD_SHA256
+    );
+
+    let date = &amz_date[..8];
+    let scope = format!("{}/{}/s3/aws4_request", date, credentials.region);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        hex(&Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let key = [date, credentials.region.as_str(), "s3", "aws4_request"]
+        .iter()
+        .fold(
+            format!("AWS4{}", credentials.secret_access_key).into_bytes(),
+            |key, part| hmac(&key, part.as_bytes()),
+        );
+    let signature = hex(&hmac(&key, string_to_sign.as_bytes()));
+
+    headers.push((
+        "authorization".to_string(),
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{},SignedHeaders={},Signature={}",
+            credentials.access_key_id, scope, signed_headers, signature
+        ),
+    ));
+    headers
+}
+
+/// Send a signed GET request for `object`.
+/// 为 `object` 发送已签名的 GET 请求。
+pub(crate) fn get(
+    client: &Client,
+    object: &S3Object,
+    credentials: &Credentials,
+) -> Result<Response, FetchError> {
+    let amz_date = amz_date(SystemTime::now());
+    let headers = sign_get(object, credentials, &amz_date, &[]);
+    let url = format!(
+        "https://{}{}",
+        object.host(&credentials.region),
+        object.path()
+    );
+    let mut request = client.get(url);
+    for (name, value) in headers.iter().filter(|(name, _)| name != "host") {
+        request = request.header(name.as_str(), value.as_str());
+    }
+    Ok(request.send()?)
+}
+
+/// Format a time as an AWS request date, `YYYYMMDD'T'HHMMSS'Z'` in UTC.
+/// 将时间格式化为 AWS 请求日期，即 UTC 的 `YYYYMMDD'T'HHMMSS'Z'`。
+pub fn amz_date(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+
+    // Convert days since the epoch to a civil date
+    // 将自纪元以来的天数转换为公历日期
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}{:02}{:02}T{:02}{:02}{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+/// HMAC-SHA256 of `data` under `key`.
+/// 使用 `key` 计算 `data` 的 HMAC-SHA256。
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Lowercase hex encoding.
+/// 小写十六进制编码。
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
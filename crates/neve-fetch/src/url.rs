@@ -1,11 +1,15 @@
 //! URL fetching utilities.
 //! URL 获取工具。
 //!
-//! Provides functionality for fetching content from HTTP/HTTPS URLs.
-//! 提供从 HTTP/HTTPS URL 获取内容的功能。
-
-use crate::FetchError;
-use reqwest::blocking::Client;
+//! Provides functionality for fetching content from HTTP/HTTPS URLs, and
+//! from `s3://` URLs through [`crate::s3`].
+//! 提供从 HTTP/HTTPS URL 获取内容的功能，并通过 [`crate::s3`] 支持 `s3://` URL。
+
+use crate::s3::{self, Credentials, S3Object};
+use crate::{CancelToken, FetchError};
+use neve_derive::{Hash, HashAlgo, Hasher};
+use reqwest::blocking::{Client, Response};
+use std::io::{Read, Write};
 use std::time::Duration;
 
 /// Default timeout for HTTP requests.
@@ -16,6 +20,26 @@ const DEFAULT_TIMEOUT: Duration = Duration::from_secs(300);
 /// 默认用户代理。
 const USER_AGENT: &str = "neve-fetch/0.1";
 
+/// Send a GET request for `url`, failing on a non-success status.
+/// 为 `url` 发送 GET 请求，状态码非成功时失败。
+///
+/// `s3://` URLs are signed with credentials from the environment; schemes
+/// other than http, https and s3 are rejected.
+/// `s3://` URL 使用环境变量中的凭证签名；http、https 和 s3 以外的方案会被拒绝。
+fn get(client: &Client, url: &str) -> Result<Response, FetchError> {
+    let scheme = url.split_once("://").map_or("", |(scheme, _)| scheme);
+    let response = match scheme {
+        "http" | "https" => client.get(url).send()?,
+        "s3" => s3::get(client, &S3Object::parse(url)?, &Credentials::from_env()?)?,
+        _ => return Err(FetchError::UnsupportedScheme(url.to_string())),
+    };
+
+    if !response.status().is_success() {
+        return Err(FetchError::Http(response.error_for_status().unwrap_err()));
+    }
+    Ok(response)
+}
+
 /// Fetch content from a URL.
 /// 从 URL 获取内容。
 pub fn fetch_url(url: &str) -> Result<Vec<u8>, FetchError> {
@@ -24,12 +48,7 @@ pub fn fetch_url(url: &str) -> Result<Vec<u8>, FetchError> {
         .user_agent(USER_AGENT)
         .build()?;
 
-    let response = client.get(url).send()?;
-
-    if !response.status().is_success() {
-        return Err(FetchError::Http(response.error_for_status().unwrap_err()));
-    }
-
+    let response = get(&client, url)?;
     Ok(response.bytes()?.to_vec())
 }
 
@@ -44,11 +63,7 @@ where
         .user_agent(USER_AGENT)
         .build()?;
 
-    let response = client.get(url).send()?;
-
-    if !response.status().is_success() {
-        return Err(FetchError::Http(response.error_for_status().unwrap_err()));
-    }
+    let response = get(&client, url)?;
 
     let total_size = response.content_length();
     let mut downloaded: u64 = 0;
@@ -64,6 +79,55 @@ where
     Ok(content)
 }
 
+/// Size of the chunks a download is read in.
+/// 下载读取的数据块大小。
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Download a URL into a writer, returning the `algo` hash of the content.
+/// 将 URL 下载到写入器中，返回内容的 `algo` 哈希。
+///
+/// `on_progress` is called after every chunk with the bytes downloaded so far
+/// and the total size if known. The token is checked between chunks, so a
+/// cancelled download stops after at most one more chunk.
+/// 每个数据块之后都会以已下载的字节数和已知的总大小调用 `on_progress`。
+/// 在数据块之间检查令牌，因此被取消的下载最多再读取一个数据块就会停止。
+pub fn download<W: Write>(
+    url: &str,
+    dest: &mut W,
+    algo: HashAlgo,
+    cancel: &CancelToken,
+    on_progress: &mut dyn FnMut(u64, Option<u64>),
+) -> Result<Hash, FetchError> {
+    cancel.check()?;
+
+    let client = Client::builder()
+        .timeout(DEFAULT_TIMEOUT)
+        .user_agent(USER_AGENT)
+        .build()?;
+
+    let mut response = get(&client, url)?;
+
+    let total_size = response.content_length();
+    let mut downloaded: u64 = 0;
+    let mut hasher = Hasher::with_algo(algo);
+    let mut buf = vec![0u8; CHUNK_SIZE];
+
+    loop {
+        cancel.check()?;
+        let n = response.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        dest.write_all(&buf[..n])?;
+        downloaded += n as u64;
+        on_progress(downloaded, total_size);
+    }
+
+    dest.flush()?;
+    Ok(hasher.finalize())
+}
+
 /// Check if a URL is reachable.
 /// 检查 URL 是否可达。
 pub fn check_url(url: &str) -> Result<bool, FetchError> {
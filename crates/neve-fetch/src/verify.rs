@@ -1,14 +1,27 @@
 //! Content verification utilities.
 //! 内容验证工具。
 //!
-//! Provides functionality for verifying content against expected hashes.
-//! 提供根据预期哈希验证内容的功能。
+//! Provides functionality for verifying content against expected hashes and
+//! detached minisign (Ed25519) signatures.
+//! 提供根据预期哈希和分离的 minisign（Ed25519）签名验证内容的功能。
 
 use crate::FetchError;
-use neve_derive::Hash;
+use minisign_verify::{PublicKey, Signature};
+use neve_derive::{Hash, HashAlgo};
 use std::fs;
 use std::path::Path;
 
+/// Where to find a detached signature and the key trusted to have made it.
+/// 分离签名的位置以及被信任的签名公钥。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureSpec {
+    /// URL of the `.minisig` signature file. / `.minisig` 签名文件的 URL。
+    pub url: String,
+    /// The trusted minisign public key, as its base64 line or a whole `.pub` file.
+    /// 受信任的 minisign 公钥，可为其 base64 行或完整的 `.pub` 文件。
+    pub public_key: String,
+}
+
 /// Verify a file against an expected hash.
 /// 根据预期哈希验证文件。
 pub fn verify_file(path: &Path, expected: &Hash) -> Result<(), FetchError> {
@@ -19,39 +32,43 @@ pub fn verify_file(path: &Path, expected: &Hash) -> Result<(), FetchError> {
 /// Verify content against an expected hash.
 /// 根据预期哈希验证内容。
 pub fn verify_content(content: &[u8], expected: &Hash) -> Result<(), FetchError> {
-    let actual = Hash::of(content);
-
-    if actual != *expected {
-        return Err(FetchError::HashMismatch {
-            expected: expected.to_hex(),
-            actual: actual.to_hex(),
-        });
-    }
-
-    Ok(())
+    check_hash(expected, &Hash::of_with(expected.algo(), content))
 }
 
 /// Verify a directory by hashing all its contents.
 /// 通过哈希所有内容来验证目录。
+///
+/// The tree is hashed with [`hash_dir_with`], the same canonical hashing used
+/// for fetched Git sources and fixed outputs.
+/// 目录树使用 [`hash_dir_with`] 哈希，与获取的 Git 源和固定输出使用相同的规范哈希。
 pub fn verify_dir(path: &Path, expected: &Hash) -> Result<(), FetchError> {
-    let actual = hash_dir(path)?;
+    check_hash(expected, &hash_dir_with(path, expected.algo())?)
+}
 
-    if actual != *expected {
+/// Compare an actual hash against the expected one.
+/// 将实际哈希与预期哈希进行比较。
+pub(crate) fn check_hash(expected: &Hash, actual: &Hash) -> Result<(), FetchError> {
+    if actual != expected {
         return Err(FetchError::HashMismatch {
             expected: expected.to_hex(),
             actual: actual.to_hex(),
         });
     }
-
     Ok(())
 }
 
 /// Hash a directory's contents deterministically.
 /// 确定性地哈希目录内容。
 pub fn hash_dir(path: &Path) -> Result<Hash, FetchError> {
+    hash_dir_with(path, HashAlgo::Blake3)
+}
+
+/// Hash a directory's contents deterministically with `algo`.
+/// 使用 `algo` 确定性地哈希目录内容。
+pub fn hash_dir_with(path: &Path, algo: HashAlgo) -> Result<Hash, FetchError> {
     use neve_derive::Hasher;
 
-    let mut hasher = Hasher::new();
+    let mut hasher = Hasher::with_algo(algo);
     hash_dir_recursive(path, &mut hasher)?;
     Ok(hasher.finalize())
 }
@@ -96,6 +113,39 @@ fn hash_dir_recursive(path: &Path, hasher: &mut neve_derive::Hasher) -> Result<(
     Ok(())
 }
 
+/// Verify `content` against a minisign `signature` made by `public_key`.
+/// 验证 `content` 与由 `public_key` 生成的 minisign `signature` 是否匹配。
+///
+/// Only prehashed signatures (minisign's default since 0.8) are accepted.
+/// 仅接受预哈希签名（minisign 自 0.8 起的默认格式）。
+pub fn verify_signature(
+    content: &[u8],
+    signature: &str,
+    public_key: &str,
+) -> Result<(), FetchError> {
+    let public_key = public_key.trim();
+    let public_key = if public_key.contains('\n') {
+        PublicKey::decode(public_key)
+    } else {
+        PublicKey::from_base64(public_key)
+    }
+    .map_err(|e| FetchError::SignatureInvalid(format!("malformed public key: {}", e)))?;
+    let signature = Signature::decode(signature)
+        .map_err(|e| FetchError::SignatureInvalid(format!("malformed signature: {}", e)))?;
+
+    public_key
+        .verify(content, &signature, false)
+        .map_err(|e| FetchError::SignatureInvalid(e.to_string()))
+}
+
+/// Download a detached signature file.
+/// 下载分离签名文件。
+pub(crate) fn fetch_signature(url: &str) -> Result<String, FetchError> {
+    String::from_utf8(crate::url::fetch_url(url)?).map_err(|_| {
+        FetchError::SignatureInvalid(format!("signature at {} is not valid UTF-8", url))
+    })
+}
+
 /// Hash prefetching result - compute hash without storing.
 /// 预取哈希结果 - 计算哈希而不存储。
 pub fn prefetch_hash(content: &[u8]) -> Hash {
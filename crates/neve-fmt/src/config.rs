@@ -10,7 +10,9 @@
 pub struct FormatConfig {
     /// Indentation width (in spaces). / 缩进宽度（空格数）。
     pub indent_width: usize,
-    /// Maximum line width. / 最大行宽。
+    /// Maximum line width; argument, parameter, record and list groups
+    /// longer than this are broken one item per line.
+    /// 最大行宽；超过该宽度的参数、形参、记录和列表组会拆分为每行一项。
     pub max_width: usize,
     /// Use tabs instead of spaces. / 使用制表符代替空格。
     pub use_tabs: bool,
@@ -22,8 +24,19 @@ pub struct FormatConfig {
     pub space_inside_braces: bool,
     /// Break long lists across multiple lines. / 将长列表拆分为多行。
     pub break_long_lists: bool,
-    /// Add blank line between top-level items. / 在顶级项之间添加空行。
-    pub blank_lines_between_items: bool,
+    /// Blank lines between top-level items. / 顶级项之间的空行数。
+    pub blank_lines_between_items: usize,
+    /// Keep a single blank line the user placed between statements in a block.
+    /// 保留用户在块内语句之间放置的单个空行。
+    pub preserve_groups: bool,
+    /// Move imports to the top of the file and sort them by path, merging
+    /// item lists imported from the same module.
+    /// 将导入移到文件顶部并按路径排序，合并从同一模块导入的项列表。
+    pub sort_imports: bool,
+    /// Separate sorted std, package, `crate.`, `super.` and `self.` imports
+    /// with a blank line.
+    /// 用空行分隔排序后的 std、包、`crate.`、`super.` 和 `self.` 导入。
+    pub group_imports: bool,
 }
 
 impl Default for FormatConfig {
@@ -36,7 +49,10 @@ impl Default for FormatConfig {
             space_before_parens: false,
             space_inside_braces: true,
             break_long_lists: true,
-            blank_lines_between_items: false,
+            blank_lines_between_items: 1,
+            preserve_groups: false,
+            sort_imports: false,
+            group_imports: false,
         }
     }
 }
@@ -69,6 +85,34 @@ impl FormatConfig {
         self
     }
 
+    /// Set the number of blank lines between top-level items.
+    /// 设置顶级项之间的空行数。
+    pub fn blank_lines_between_items(mut self, lines: usize) -> Self {
+        self.blank_lines_between_items = lines;
+        self
+    }
+
+    /// Keep blank lines that separate groups of statements in a block.
+    /// 保留块内分隔语句组的空行。
+    pub fn preserve_groups(mut self, preserve: bool) -> Self {
+        self.preserve_groups = preserve;
+        self
+    }
+
+    /// Sort imports and move them to the top of the file.
+    /// 对导入排序并将其移到文件顶部。
+    pub fn sort_imports(mut self, sort: bool) -> Self {
+        self.sort_imports = sort;
+        self
+    }
+
+    /// Separate groups of sorted imports with a blank line.
+    /// 用空行分隔排序后的导入组。
+    pub fn group_imports(mut self, group: bool) -> Self {
+        self.group_imports = group;
+        self
+    }
+
     /// Get the indentation string for one level.
     /// 获取一级缩进的字符串。
     pub fn indent_str(&self) -> String {
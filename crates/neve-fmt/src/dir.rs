@@ -0,0 +1,100 @@
+//! Checking whole directories.
+//! 检查整个目录。
+
+use crate::{FormatError, check};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Result of checking every Neve file under a directory.
+/// 检查目录下所有 Neve 文件的结果。
+#[derive(Debug, Clone, Default)]
+pub struct DirCheck {
+    /// Files that were checked. / 已检查的文件。
+    pub checked: Vec<PathBuf>,
+    /// Files that would be reformatted. / 将被重新格式化的文件。
+    pub unformatted: Vec<PathBuf>,
+    /// Files that could not be read or parsed. / 无法读取或解析的文件。
+    pub errors: Vec<(PathBuf, FormatError)>,
+}
+
+impl DirCheck {
+    /// Whether every file is already formatted.
+    /// 是否所有文件都已格式化。
+    pub fn is_clean(&self) -> bool {
+        self.unformatted.is_empty() && self.errors.is_empty()
+    }
+
+    /// Process exit code for a CI check: 0 if clean, 1 otherwise.
+    /// CI 检查的进程退出码：干净时为 0，否则为 1。
+    pub fn exit_code(&self) -> i32 {
+        if self.is_clean() { 0 } else { 1 }
+    }
+
+    /// One-line summary of the check.
+    /// 检查的单行摘要。
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "{} of {} files would be reformatted",
+            self.unformatted.len(),
+            self.checked.len()
+        );
+        if !self.errors.is_empty() {
+            summary.push_str(&format!(", {} failed to parse", self.errors.len()));
+        }
+        summary
+    }
+}
+
+/// Check every `.neve` file under `dir` without modifying anything.
+/// 检查 `dir` 下的每个 `.neve` 文件，不做任何修改。
+///
+/// Files are visited in sorted order so the report is stable.
+/// 文件按排序顺序访问，使报告保持稳定。
+pub fn check_dir(dir: &Path) -> Result<DirCheck, FormatError> {
+    if !dir.is_dir() {
+        return Err(FormatError::Io(format!(
+            "not a directory: {}",
+            dir.display()
+        )));
+    }
+
+    let mut report = DirCheck::default();
+    check_dir_recursive(dir, &mut report)?;
+    Ok(report)
+}
+
+/// Recursively check the files in a directory.
+/// 递归检查目录中的文件。
+fn check_dir_recursive(dir: &Path, report: &mut DirCheck) -> Result<(), FormatError> {
+    let mut paths = fs::read_dir(dir)
+        .map_err(|e| FormatError::Io(format!("{}: {}", dir.display(), e)))?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| FormatError::Io(format!("{}: {}", dir.display(), e)))?;
+    paths.sort();
+
+    for path in paths {
+        // Symlinked directories are skipped so a link loop cannot recurse forever
+        // 跳过符号链接目录，避免链接环导致无限递归
+        let metadata = fs::symlink_metadata(&path)
+            .map_err(|e| FormatError::Io(format!("{}: {}", path.display(), e)))?;
+        if metadata.is_symlink() && path.is_dir() {
+            continue;
+        }
+        if metadata.is_dir() {
+            check_dir_recursive(&path, report)?;
+        } else if path.extension().is_some_and(|ext| ext == "neve") {
+            let result = fs::read_to_string(&path)
+                .map_err(|e| FormatError::Io(e.to_string()))
+                .and_then(|source| check(&source));
+            match result {
+                Ok(true) => {}
+                Ok(false) => report.unformatted.push(path.clone()),
+                Err(e) => report.errors.push((path.clone(), e)),
+            }
+            report.checked.push(path);
+        }
+    }
+
+    Ok(())
+}
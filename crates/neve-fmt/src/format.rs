@@ -6,12 +6,14 @@
 //! 提供将 AST 节点转换回正确格式化源代码的主要格式化逻辑。
 
 use crate::config::FormatConfig;
+use crate::imports::{Import, sort_imports};
 use crate::printer::Printer;
+use neve_common::{BytePos, Span};
 use neve_syntax::{
     BinOp, EnumDef, Expr, ExprKind, FieldDef, FnDef, Generator, GenericParam, ImplDef, ImplItem,
     ImportDef, ImportItems, Item, ItemKind, LambdaParam, LetDef, LiteralPattern, MatchArm, Param,
-    Pattern, PatternKind, RecordField, RecordPatternField, RecordTypeField, SourceFile, Stmt,
-    StmtKind, StringPart, StructDef, TraitDef, TraitItem, Type, TypeAlias, TypeKind, UnaryOp,
+    PathPrefix, Pattern, PatternKind, RecordField, RecordPatternField, RecordTypeField, SourceFile,
+    Stmt, StmtKind, StringPart, StructDef, TraitDef, TraitItem, Type, TypeAlias, TypeKind, UnaryOp,
     VariantKind, Visibility,
 };
 
@@ -20,29 +22,92 @@ use neve_syntax::{
 pub struct Formatter {
     /// Formatting configuration. / 格式化配置。
     config: FormatConfig,
+    /// The original source, used to find user-placed blank lines.
+    /// 原始源代码，用于查找用户放置的空行。
+    source: Option<String>,
 }
 
 impl Formatter {
     /// Create a new formatter.
     /// 创建新的格式化器。
     pub fn new(config: FormatConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            source: None,
+        }
+    }
+
+    /// Attach the source the AST was parsed from.
+    /// 附加解析出 AST 的源代码。
+    ///
+    /// Needed for [`FormatConfig::preserve_groups`]; without it, statements
+    /// in a block are never separated by blank lines.
+    /// [`FormatConfig::preserve_groups`] 需要它；没有源代码时，块内语句之间从不插入空行。
+    pub fn with_source(mut self, source: &str) -> Self {
+        self.source = Some(source.to_string());
+        self
     }
 
     /// Format a source file.
     /// 格式化源文件。
     pub fn format(&self, file: &SourceFile) -> String {
+        self.format_item_list(&file.items)
+    }
+
+    /// Format a run of top-level items, separated as in a whole file.
+    /// 格式化一串顶级项，按整个文件中的方式分隔。
+    pub(crate) fn format_item_list(&self, items: &[Item]) -> String {
         let mut printer = Printer::new(self.config.clone());
+        let mut rest: Vec<&Item> = items.iter().collect();
+        let mut first = true;
+
+        // Sorted imports go first, one per line
+        // 排序后的导入放在最前面，每行一个
+        if self.config.sort_imports {
+            let imports: Vec<Import> = items
+                .iter()
+                .filter_map(|item| match &item.kind {
+                    ItemKind::Import(def) => Some(self.attach_comments(def, item.span)),
+                    _ => None,
+                })
+                .collect();
+            rest.retain(|item| !matches!(item.kind, ItemKind::Import(_)));
+            for (i, group) in sort_imports(imports, self.config.group_imports)
+                .iter()
+                .enumerate()
+            {
+                if i > 0 {
+                    printer.newline();
+                }
+                for import in group {
+                    for comment in &import.leading {
+                        printer.write(comment);
+                        printer.newline();
+                    }
+                    match &import.trailing {
+                        Some(comment) => {
+                            let mut line = Printer::new(self.config.clone());
+                            self.format_import(&mut line, &import.def);
+                            printer.write(line.finish().trim_end());
+                            printer.write(" ");
+                            printer.writeln(comment);
+                        }
+                        None => self.format_import(&mut printer, &import.def),
+                    }
+                }
+                first = false;
+            }
+        }
 
-        for (i, item) in file.items.iter().enumerate() {
-            if i > 0 {
-                // Add extra blank line between top-level items if configured
-                // 如果配置了，在顶级项之间添加额外的空行
-                if printer.config().blank_lines_between_items {
+        for item in rest {
+            if !first {
+                // Separate top-level items by the configured number of blank lines
+                // 按配置的空行数分隔顶级项
+                for _ in 0..self.config.blank_lines_between_items {
                     printer.newline();
                 }
-                printer.newline();
             }
+            first = false;
             self.format_item(&mut printer, item);
         }
 
@@ -53,6 +118,59 @@ impl Formatter {
         printer.finish()
     }
 
+    /// Pair an import with the comments attached to it in the source: whole
+    /// comment lines just above it, and a comment after it on its line.
+    /// 将导入与源代码中附加到它的注释配对：紧挨在其上方的整行注释，以及其所在行末尾的注释。
+    fn attach_comments(&self, def: &ImportDef, span: Span) -> Import {
+        let mut import = Import {
+            def: def.clone(),
+            leading: Vec::new(),
+            trailing: None,
+        };
+        let Some(source) = &self.source else {
+            return import;
+        };
+        let start = span.start.0 as usize;
+        let end = span.end.0 as usize;
+
+        let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+        if source[line_start..start].trim().is_empty() {
+            let mut above = source[..line_start].lines().rev();
+            while let Some(line) = above.next().map(str::trim)
+                && line.starts_with("--")
+            {
+                import.leading.push(line.to_string());
+            }
+            import.leading.reverse();
+        }
+
+        let line_end = source[end..].find('\n').map_or(source.len(), |i| end + i);
+        let after = source[end..line_end].trim_start_matches(';').trim();
+        if after.starts_with("--") {
+            import.trailing = Some(after.to_string());
+        }
+        import
+    }
+
+    /// Format a run of statements from a block, optionally followed by its
+    /// result expression, as if at the block's indentation.
+    /// 格式化块中的一串语句，后面可以跟着块的结果表达式，如同位于块的缩进处。
+    pub(crate) fn format_stmt_list(&self, stmts: &[Stmt], tail: Option<&Expr>) -> String {
+        let mut printer = Printer::new(self.config.clone());
+        let mut prev_end = None;
+        for stmt in stmts {
+            self.group_break(&mut printer, prev_end, stmt.span);
+            self.format_stmt(&mut printer, stmt);
+            prev_end = Some(stmt.span.end);
+        }
+        if let Some(e) = tail {
+            self.group_break(&mut printer, prev_end, e.span);
+            self.format_expr(&mut printer, e);
+            printer.newline();
+        }
+        printer.finish()
+    }
+
     /// Format an item.
     /// 格式化项。
     fn format_item(&self, p: &mut Printer, item: &Item) {
@@ -100,21 +218,8 @@ impl Formatter {
         // Generics / 泛型
         self.format_generics(p, &def.generics);
 
-        // Parameters / 参数
-        p.write("(");
-        for (i, param) in def.params.iter().enumerate() {
-            if i > 0 {
-                p.write(", ");
-            }
-            self.format_param(p, param);
-        }
-        p.write(")");
-
-        // Return type / 返回类型
-        if let Some(ref ret_ty) = def.return_type {
-            p.write(" -> ");
-            self.format_type(p, ret_ty);
-        }
+        // Parameters and return type / 参数和返回类型
+        self.format_signature(p, &def.params, def.return_type.as_ref());
 
         // Body / 函数体
         p.write(" = ");
@@ -263,7 +368,16 @@ impl Formatter {
     /// Format an import.
     /// 格式化导入。
     fn format_import(&self, p: &mut Printer, def: &ImportDef) {
+        if def.visibility == Visibility::Public {
+            p.write("pub ");
+        }
         p.write("import ");
+        match def.prefix {
+            PathPrefix::Absolute => {}
+            PathPrefix::Self_ => p.write("self."),
+            PathPrefix::Super => p.write("super."),
+            PathPrefix::Crate => p.write("crate."),
+        }
         for (i, part) in def.path.iter().enumerate() {
             if i > 0 {
                 p.write(".");
@@ -321,6 +435,25 @@ impl Formatter {
         }
     }
 
+    /// Format the parameter list and return type of a function, with one
+    /// parameter per line if they don't fit.
+    /// 格式化函数的参数列表和返回类型，放不下时每行一个参数。
+    fn format_signature(&self, p: &mut Printer, params: &[Param], return_type: Option<&Type>) {
+        self.format_items(
+            p,
+            ("(", ")"),
+            false,
+            params,
+            |p, param| self.format_param(p, param),
+            |p| {
+                if let Some(ret_ty) = return_type {
+                    p.write(" -> ");
+                    self.format_type(p, ret_ty);
+                }
+            },
+        );
+    }
+
     /// Format a function parameter.
     /// 格式化函数参数。
     fn format_param(&self, p: &mut Printer, param: &Param) {
@@ -350,19 +483,7 @@ impl Formatter {
         p.write("fn ");
         p.write(&item.name.name);
         self.format_generics(p, &item.generics);
-        p.write("(");
-        for (i, param) in item.params.iter().enumerate() {
-            if i > 0 {
-                p.write(", ");
-            }
-            self.format_param(p, param);
-        }
-        p.write(")");
-
-        if let Some(ref ret_ty) = item.return_type {
-            p.write(" -> ");
-            self.format_type(p, ret_ty);
-        }
+        self.format_signature(p, &item.params, item.return_type.as_ref());
 
         if let Some(ref default) = item.default {
             p.write(" = ");
@@ -379,19 +500,7 @@ impl Formatter {
         p.write("fn ");
         p.write(&item.name.name);
         self.format_generics(p, &item.generics);
-        p.write("(");
-        for (i, param) in item.params.iter().enumerate() {
-            if i > 0 {
-                p.write(", ");
-            }
-            self.format_param(p, param);
-        }
-        p.write(")");
-
-        if let Some(ref ret_ty) = item.return_type {
-            p.write(" -> ");
-            self.format_type(p, ret_ty);
-        }
+        self.format_signature(p, &item.params, item.return_type.as_ref());
 
         p.write(" = ");
         self.format_expr(p, &item.body);
@@ -405,7 +514,7 @@ impl Formatter {
         match &expr.kind {
             // Literals / 字面量
             ExprKind::Int(n) => p.write(&n.to_string()),
-            ExprKind::Float(f) => p.write(&f.to_string()),
+            ExprKind::Float(f) => p.write(&format_float(*f)),
             ExprKind::String(s) => {
                 p.write("\"");
                 p.write(&escape_string(s));
@@ -434,44 +543,14 @@ impl Formatter {
 
             // Record / 记录
             ExprKind::Record(fields) => {
-                if fields.is_empty() {
-                    p.write("#{}");
-                } else {
-                    // Check if we should break to multiple lines
-                    // 检查是否应该拆分为多行
-                    let estimated_len: usize = fields
-                        .iter()
-                        .map(|f| f.name.name.len() + 4) // " = " + ", "
-                        .sum();
-
-                    if p.would_exceed_width(estimated_len + 4) && fields.len() > 1 {
-                        // Multi-line format / 多行格式
-                        p.writeln("#{");
-                        p.indent();
-                        for (i, field) in fields.iter().enumerate() {
-                            self.format_record_field(p, field);
-                            if i < fields.len() - 1 {
-                                p.write(",");
-                            }
-                            p.newline();
-                        }
-                        p.dedent();
-                        p.write("}");
-                    } else {
-                        // Single line format / 单行格式
-                        p.write("#{");
-                        p.space();
-                        for (i, field) in fields.iter().enumerate() {
-                            if i > 0 {
-                                p.write(",");
-                                p.space();
-                            }
-                            self.format_record_field(p, field);
-                        }
-                        p.space();
-                        p.write("}");
-                    }
-                }
+                self.format_items(
+                    p,
+                    ("#{", "}"),
+                    true,
+                    fields,
+                    |p, field| self.format_record_field(p, field),
+                    |_| {},
+                );
             }
 
             // Record update / 记录更新
@@ -490,32 +569,11 @@ impl Formatter {
 
             // List / 列表
             ExprKind::List(elements) => {
-                if elements.is_empty() {
-                    p.write("[]");
-                } else if elements.len() > 3 && p.would_exceed_width(elements.len() * 5) {
-                    // Multi-line list for many elements
-                    // 多元素的多行列表
-                    p.writeln("[");
-                    p.indent();
-                    for (i, elem) in elements.iter().enumerate() {
-                        self.format_expr(p, elem);
-                        if i < elements.len() - 1 {
-                            p.write(",");
-                        }
-                        p.newline();
-                    }
-                    p.dedent();
-                    p.write("]");
+                let format_elem = |p: &mut Printer, elem: &Expr| self.format_expr(p, elem);
+                if self.config.break_long_lists {
+                    self.format_items(p, ("[", "]"), false, elements, format_elem, |_| {});
                 } else {
-                    p.write("[");
-                    for (i, elem) in elements.iter().enumerate() {
-                        if i > 0 {
-                            p.write(",");
-                            p.space();
-                        }
-                        self.format_expr(p, elem);
-                    }
-                    p.write("]");
+                    self.format_flat_items(p, ("[", "]"), false, elements, &format_elem);
                 }
             }
 
@@ -560,15 +618,8 @@ impl Formatter {
 
             // Call / 调用
             ExprKind::Call { func, args } => {
-                self.format_expr(p, func);
-                p.write("(");
-                for (i, arg) in args.iter().enumerate() {
-                    if i > 0 {
-                        p.write(", ");
-                    }
-                    self.format_expr(p, arg);
-                }
-                p.write(")");
+                self.format_operand(p, func, POSTFIX_PREC);
+                self.format_args(p, args);
             }
 
             // Method call / 方法调用
@@ -577,43 +628,36 @@ impl Formatter {
                 method,
                 args,
             } => {
-                self.format_expr(p, receiver);
+                self.format_field_base(p, receiver);
                 p.write(".");
                 p.write(&method.name);
-                p.write("(");
-                for (i, arg) in args.iter().enumerate() {
-                    if i > 0 {
-                        p.write(", ");
-                    }
-                    self.format_expr(p, arg);
-                }
-                p.write(")");
+                self.format_args(p, args);
             }
 
             // Field access / 字段访问
             ExprKind::Field { base, field } => {
-                self.format_expr(p, base);
+                self.format_field_base(p, base);
                 p.write(".");
                 p.write(&field.name);
             }
 
             // Tuple index / 元组索引
             ExprKind::TupleIndex { base, index } => {
-                self.format_expr(p, base);
+                self.format_field_base(p, base);
                 p.write(".");
                 p.write(&index.to_string());
             }
 
             // Safe field access / 安全字段访问
             ExprKind::SafeField { base, field } => {
-                self.format_expr(p, base);
+                self.format_field_base(p, base);
                 p.write("?.");
                 p.write(&field.name);
             }
 
             // Index / 索引
             ExprKind::Index { base, index } => {
-                self.format_expr(p, base);
+                self.format_operand(p, base, POSTFIX_PREC);
                 p.write("[");
                 self.format_expr(p, index);
                 p.write("]");
@@ -621,30 +665,54 @@ impl Formatter {
 
             // Binary / 二元运算
             ExprKind::Binary { op, left, right } => {
-                self.format_expr(p, left);
+                // `^` is right-associative, every other operator left-associative
+                // `^` 是右结合的，其他运算符都是左结合的
+                let prec = binop_prec(*op);
+                let (left_prec, right_prec) = if *op == BinOp::Pow {
+                    (prec + 1, prec)
+                } else {
+                    (prec, prec + 1)
+                };
+                self.format_operand(p, left, left_prec);
                 p.write(" ");
                 p.write(self.binop_str(*op));
                 p.write(" ");
-                self.format_expr(p, right);
+                self.format_operand(p, right, right_prec);
             }
 
             // Unary / 一元运算
             ExprKind::Unary { op, operand } => {
                 p.write(self.unaryop_str(*op));
-                self.format_expr(p, operand);
+                // `--` would start a comment
+                // `--` 会开始一个注释
+                if *op == UnaryOp::Neg && starts_with_minus(operand) {
+                    p.write("(");
+                    self.format_expr(p, operand);
+                    p.write(")");
+                } else {
+                    self.format_operand(p, operand, UNARY_PREC);
+                }
             }
 
             // Try (error propagation) / Try（错误传播）
             ExprKind::Try(inner) => {
-                self.format_expr(p, inner);
+                // `x??` would read as `??`
+                // `x??` 会被读作 `??`
+                if matches!(inner.kind, ExprKind::Try(_)) {
+                    p.write("(");
+                    self.format_expr(p, inner);
+                    p.write(")");
+                } else {
+                    self.format_operand(p, inner, POSTFIX_PREC);
+                }
                 p.write("?");
             }
 
             // Coalesce / 空值合并
             ExprKind::Coalesce { value, default } => {
-                self.format_expr(p, value);
+                self.format_operand(p, value, COALESCE_PREC);
                 p.write(" ?? ");
-                self.format_expr(p, default);
+                self.format_operand(p, default, COALESCE_PREC + 1);
             }
 
             // If / 条件表达式
@@ -656,9 +724,27 @@ impl Formatter {
                 p.write("if ");
                 self.format_expr(p, condition);
                 p.write(" then ");
-                self.format_expr(p, then_branch);
-                p.write(" else ");
-                self.format_expr(p, else_branch);
+                // An `else` after a nested `if` without one would bind to it
+                // 嵌套的无 `else` 的 `if` 之后的 `else` 会绑定到它上面
+                if else_branch.is_some()
+                    && matches!(
+                        then_branch.kind,
+                        ExprKind::If {
+                            else_branch: None,
+                            ..
+                        }
+                    )
+                {
+                    p.write("(");
+                    self.format_expr(p, then_branch);
+                    p.write(")");
+                } else {
+                    self.format_expr(p, then_branch);
+                }
+                if let Some(else_branch) = else_branch {
+                    p.write(" else ");
+                    self.format_expr(p, else_branch);
+                }
             }
 
             // Match / 模式匹配
@@ -668,7 +754,7 @@ impl Formatter {
                 p.write(" {");
                 p.newline();
                 p.indent();
-                for arm in arms {
+                for arm in arms.iter() {
                     self.format_match_arm(p, arm);
                 }
                 p.dedent();
@@ -683,10 +769,14 @@ impl Formatter {
                 } else {
                     p.newline();
                     p.indent();
+                    let mut prev_end = None;
                     for stmt in stmts {
+                        self.group_break(p, prev_end, stmt.span);
                         self.format_stmt(p, stmt);
+                        prev_end = Some(stmt.span.end);
                     }
                     if let Some(e) = expr {
+                        self.group_break(p, prev_end, e.span);
                         self.format_expr(p, e);
                         p.newline();
                     }
@@ -726,17 +816,16 @@ impl Formatter {
                 for part in parts {
                     match part {
                         StringPart::Literal(s) => {
-                            // Escape backticks and braces in literal parts
-                            // 在字面量部分转义反引号和大括号
+                            // Escape braces in literal parts so they don't start
+                            // an interpolation
+                            // 在字面量部分转义大括号，使其不会开始插值
                             for c in s.chars() {
                                 match c {
-                                    '`' => p.write("\\`"),
                                     '{' => p.write("\\{"),
                                     '}' => p.write("\\}"),
-                                    '\n' => p.write("\\n"),
-                                    '\r' => p.write("\\r"),
-                                    '\t' => p.write("\\t"),
-                                    _ => p.write(&c.to_string()),
+                                    _ => {
+                                        p.write(&escape_common(c).unwrap_or_else(|| c.to_string()))
+                                    }
                                 }
                             }
                         }
@@ -758,6 +847,125 @@ impl Formatter {
         }
     }
 
+    /// Format the arguments of a call, one per line if they don't fit.
+    /// 格式化调用的参数，放不下时每行一个。
+    fn format_args(&self, p: &mut Printer, args: &[Expr]) {
+        self.format_items(
+            p,
+            ("(", ")"),
+            false,
+            args,
+            |p, arg| self.format_expr(p, arg),
+            |_| {},
+        );
+    }
+
+    /// Format comma-separated items between delimiters as a group: on one
+    /// line if it fits, otherwise one item per line, indented. `rest` is
+    /// written after the closing delimiter and must fit on the line too.
+    /// 将定界符之间以逗号分隔的项作为一个组格式化：放得下时排在一行，否则每行一项并缩进。
+    /// `rest` 写在结束定界符之后，也必须能放在该行上。
+    fn format_items<T>(
+        &self,
+        p: &mut Printer,
+        delims: (&str, &str),
+        pad: bool,
+        items: &[T],
+        item: impl Fn(&mut Printer, &T),
+        rest: impl Fn(&mut Printer),
+    ) {
+        let flat = |p: &mut Printer| {
+            self.format_flat_items(p, delims, pad, items, &item);
+            rest(p);
+        };
+        if items.is_empty() || p.is_flat() {
+            flat(p);
+            return;
+        }
+
+        let mut probe = Printer::flat(self.config.clone());
+        flat(&mut probe);
+        if p.fits(&probe.finish()) {
+            flat(p);
+            return;
+        }
+
+        p.write(delims.0);
+        p.newline();
+        p.indent();
+        for (i, elem) in items.iter().enumerate() {
+            item(p, elem);
+            if i < items.len() - 1 {
+                p.write(",");
+            }
+            p.newline();
+        }
+        p.dedent();
+        p.write(delims.1);
+        rest(p);
+    }
+
+    /// Format comma-separated items between delimiters on one line, with
+    /// spaces inside the delimiters if `pad` is set.
+    /// 在一行上格式化定界符之间以逗号分隔的项，`pad` 为真时在定界符内侧加空格。
+    fn format_flat_items<T>(
+        &self,
+        p: &mut Printer,
+        delims: (&str, &str),
+        pad: bool,
+        items: &[T],
+        item: &impl Fn(&mut Printer, &T),
+    ) {
+        p.write(delims.0);
+        let pad = pad && !items.is_empty();
+        if pad {
+            p.space();
+        }
+        for (i, elem) in items.iter().enumerate() {
+            if i > 0 {
+                p.write(",");
+                p.space();
+            }
+            item(p, elem);
+        }
+        if pad {
+            p.space();
+        }
+        p.write(delims.1);
+    }
+
+    /// Format an operand, parenthesized if it binds more loosely than
+    /// `min_prec`.
+    /// 格式化操作数，如果其结合比 `min_prec` 更松散则加上括号。
+    fn format_operand(&self, p: &mut Printer, expr: &Expr, min_prec: u8) {
+        if expr_prec(expr) < min_prec {
+            p.write("(");
+            self.format_expr(p, expr);
+            p.write(")");
+        } else {
+            self.format_expr(p, expr);
+        }
+    }
+
+    /// Format the base of a `.` or `?.` access.
+    /// 格式化 `.` 或 `?.` 访问的基础表达式。
+    fn format_field_base(&self, p: &mut Printer, base: &Expr) {
+        // `1.0` would read as a float, `x?.a` as a safe access and `./p.a`
+        // as a single path
+        // `1.0` 会被读作浮点数，`x?.a` 会被读作安全访问，`./p.a` 会被读作单个路径
+        let ambiguous = matches!(
+            base.kind,
+            ExprKind::Int(_) | ExprKind::Float(_) | ExprKind::Try(_) | ExprKind::PathLit(_)
+        );
+        if ambiguous {
+            p.write("(");
+            self.format_expr(p, base);
+            p.write(")");
+        } else {
+            self.format_operand(p, base, POSTFIX_PREC);
+        }
+    }
+
     /// Format a record field in an expression.
     /// 格式化表达式中的记录字段。
     fn format_record_field(&self, p: &mut Printer, field: &RecordField) {
@@ -804,6 +1012,27 @@ impl Formatter {
         p.newline();
     }
 
+    /// Emit a blank line if the user separated `next` from the previous
+    /// statement with one and groups are preserved.
+    /// 如果用户用空行将 `next` 与上一条语句分隔且保留分组，则输出一个空行。
+    fn group_break(&self, p: &mut Printer, prev_end: Option<BytePos>, next: Span) {
+        let (Some(source), Some(prev_end)) = (&self.source, prev_end) else {
+            return;
+        };
+        if !self.config.preserve_groups {
+            return;
+        }
+        let gap = source
+            .get(prev_end.0 as usize..next.start.0 as usize)
+            .unwrap_or("");
+        let mut lines = gap.split('\n');
+        lines.next();
+        lines.next_back();
+        if lines.any(|line| line.trim().is_empty()) {
+            p.newline();
+        }
+    }
+
     /// Format a statement.
     /// 格式化语句。
     fn format_stmt(&self, p: &mut Printer, stmt: &Stmt) {
@@ -1063,18 +1292,95 @@ impl Formatter {
     }
 }
 
+/// Precedence of `??`.
+/// `??` 的优先级。
+const COALESCE_PREC: u8 = 3;
+
+/// Precedence of prefix operators.
+/// 前缀运算符的优先级。
+const UNARY_PREC: u8 = 11;
+
+/// Precedence of calls, field accesses, indexing and `?`.
+/// 调用、字段访问、索引和 `?` 的优先级。
+const POSTFIX_PREC: u8 = 12;
+
+/// Precedence of a binary operator; higher binds tighter.
+/// 二元运算符的优先级；越高结合越紧密。
+fn binop_prec(op: BinOp) -> u8 {
+    match op {
+        BinOp::Pipe => 1,
+        BinOp::Merge => 2,
+        BinOp::Or => 4,
+        BinOp::And => 5,
+        BinOp::Eq | BinOp::Ne | BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge => 6,
+        BinOp::Concat => 7,
+        BinOp::Add | BinOp::Sub => 8,
+        BinOp::Mul | BinOp::Div | BinOp::Mod => 9,
+        BinOp::Pow => 10,
+    }
+}
+
+/// How tightly an expression binds to its neighbours when printed.
+/// 表达式打印时与相邻部分结合的紧密程度。
+///
+/// `if`, `fn`, `let` and `lazy` extend as far right as possible, so they
+/// rank lowest and are parenthesized wherever they are an operand.
+/// `if`、`fn`、`let` 和 `lazy` 会尽可能向右延伸，因此优先级最低，作为操作数时总会加上括号。
+fn expr_prec(expr: &Expr) -> u8 {
+    match &expr.kind {
+        ExprKind::If { .. }
+        | ExprKind::Lambda { .. }
+        | ExprKind::Let { .. }
+        | ExprKind::Lazy(_) => 0,
+        ExprKind::Binary { op, .. } => binop_prec(*op),
+        ExprKind::Coalesce { .. } => COALESCE_PREC,
+        ExprKind::Unary { .. } => UNARY_PREC,
+        ExprKind::Int(n) if *n < 0 => UNARY_PREC,
+        ExprKind::Float(f) if f.is_sign_negative() => UNARY_PREC,
+        ExprKind::Call { .. }
+        | ExprKind::MethodCall { .. }
+        | ExprKind::Field { .. }
+        | ExprKind::TupleIndex { .. }
+        | ExprKind::SafeField { .. }
+        | ExprKind::Index { .. }
+        | ExprKind::Try(_) => POSTFIX_PREC,
+        _ => POSTFIX_PREC + 1,
+    }
+}
+
+/// Whether an expression prints starting with `-`.
+/// 表达式打印时是否以 `-` 开头。
+fn starts_with_minus(expr: &Expr) -> bool {
+    match &expr.kind {
+        ExprKind::Unary { op, .. } => *op == UnaryOp::Neg,
+        ExprKind::Int(n) => *n < 0,
+        ExprKind::Float(f) => f.is_sign_negative(),
+        _ => false,
+    }
+}
+
+/// Print a float so it reads back as a float, keeping a `.0` on whole numbers.
+/// 打印浮点数使其能被读回为浮点数，整数值保留 `.0`。
+fn format_float(f: f64) -> String {
+    let s = f.to_string();
+    if f.is_finite() && !s.contains('.') {
+        format!("{}.0", s)
+    } else {
+        s
+    }
+}
+
 /// Escape special characters in a string.
 /// 转义字符串中的特殊字符。
 fn escape_string(s: &str) -> String {
     let mut result = String::new();
     for c in s.chars() {
         match c {
-            '\\' => result.push_str("\\\\"),
             '"' => result.push_str("\\\""),
-            '\n' => result.push_str("\\n"),
-            '\r' => result.push_str("\\r"),
-            '\t' => result.push_str("\\t"),
-            _ => result.push(c),
+            _ => match escape_common(c) {
+                Some(escaped) => result.push_str(&escaped),
+                None => result.push(c),
+            },
         }
     }
     result
@@ -1084,11 +1390,22 @@ fn escape_string(s: &str) -> String {
 /// 转义字符。
 fn escape_char(c: char) -> String {
     match c {
-        '\\' => "\\\\".to_string(),
         '\'' => "\\'".to_string(),
-        '\n' => "\\n".to_string(),
-        '\r' => "\\r".to_string(),
-        '\t' => "\\t".to_string(),
-        _ => c.to_string(),
+        _ => escape_common(c).unwrap_or_else(|| c.to_string()),
     }
 }
+
+/// The escape sequence of a character that needs one in every kind of
+/// literal.
+/// 在所有字面量中都需要转义的字符的转义序列。
+fn escape_common(c: char) -> Option<String> {
+    let escaped = match c {
+        '\\' => "\\\\",
+        '\n' => "\\n",
+        '\r' => "\\r",
+        '\t' => "\\t",
+        '\0' => "\\0",
+        _ => return None,
+    };
+    Some(escaped.to_string())
+}
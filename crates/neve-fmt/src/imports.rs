@@ -0,0 +1,158 @@
+//! Canonical ordering of imports.
+//! 导入的规范顺序。
+//!
+//! Imports are sorted by path and split into groups: the standard library,
+//! other packages, then `crate.`, `super.` and `self.` imports. Imports of
+//! items from the same module are merged into one list.
+//! 导入按路径排序并分组：标准库、其他包，然后是 `crate.`、`super.` 和 `self.` 导入。
+//! 从同一模块导入的项会合并为一个列表。
+//!
+//! Comments on the lines just above an import, and after it on its line,
+//! move with it.
+//! 紧挨在导入上方的注释行以及其所在行末尾的注释会随导入一起移动。
+
+use neve_syntax::{Ident, ImportDef, ImportItems, PathPrefix, Visibility};
+use std::cmp::Ordering;
+
+/// The group an import belongs to, in output order.
+/// 导入所属的组，按输出顺序排列。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum ImportGroup {
+    Std,
+    External,
+    Crate,
+    Super,
+    Self_,
+}
+
+impl ImportGroup {
+    fn of(def: &ImportDef) -> Self {
+        match def.prefix {
+            PathPrefix::Absolute if def.path.first().is_some_and(|p| p.name == "std") => Self::Std,
+            PathPrefix::Absolute => Self::External,
+            PathPrefix::Crate => Self::Crate,
+            PathPrefix::Super => Self::Super,
+            PathPrefix::Self_ => Self::Self_,
+        }
+    }
+}
+
+/// An import with the comments attached to it.
+/// 带有附加注释的导入。
+#[derive(Debug, Clone)]
+pub(crate) struct Import {
+    /// The import itself. / 导入本身。
+    pub def: ImportDef,
+    /// Comment lines just above the import. / 紧挨在导入上方的注释行。
+    pub leading: Vec<String>,
+    /// Comment after the import on its line. / 导入所在行末尾的注释。
+    pub trailing: Option<String>,
+}
+
+/// Sort and merge imports, returning them in groups separated by a blank
+/// line, or as a single group unless `group` is set.
+/// 排序并合并导入，按以空行分隔的组返回；未设置 `group` 时返回单个组。
+pub(crate) fn sort_imports(imports: Vec<Import>, group: bool) -> Vec<Vec<Import>> {
+    let mut sorted: Vec<Import> = Vec::with_capacity(imports.len());
+    for mut import in imports {
+        if let ImportItems::Items(items) = &mut import.def.items {
+            items.sort_by(|a, b| a.name.cmp(&b.name));
+            items.dedup_by(|a, b| a.name == b.name);
+        }
+        match sorted
+            .iter_mut()
+            .find(|other| same_module(&other.def, &import.def))
+        {
+            Some(other) => merge(other, import),
+            None => sorted.push(import),
+        }
+    }
+    sorted.sort_by(|a, b| compare(&a.def, &b.def));
+    sorted.dedup_by(|a, b| {
+        let same = compare(&a.def, &b.def) == Ordering::Equal;
+        if same {
+            keep_comments(b, std::mem::take(&mut a.leading), a.trailing.take());
+        }
+        same
+    });
+
+    let mut groups: Vec<Vec<Import>> = Vec::new();
+    let mut last_group = None;
+    for import in sorted {
+        let this_group = ImportGroup::of(&import.def);
+        match groups.last_mut() {
+            Some(current) if !group || last_group == Some(this_group) => current.push(import),
+            _ => groups.push(vec![import]),
+        }
+        last_group = Some(this_group);
+    }
+    groups
+}
+
+/// Move the comments of an import merged into `into` over to it. A second
+/// trailing comment goes above the import, so neither is lost.
+/// 将合并进 `into` 的导入的注释移交给它。第二个行尾注释放到导入上方，因此不会丢失任何注释。
+fn keep_comments(into: &mut Import, leading: Vec<String>, trailing: Option<String>) {
+    into.leading.extend(leading);
+    match (&into.trailing, trailing) {
+        (None, trailing) => into.trailing = trailing,
+        (Some(_), Some(trailing)) => into.leading.push(trailing),
+        (Some(_), None) => {}
+    }
+}
+
+/// Whether two imports pick items from the same module and can be merged.
+/// 两个导入是否从同一模块中选取项，从而可以合并。
+fn same_module(a: &ImportDef, b: &ImportDef) -> bool {
+    matches!(
+        (&a.items, &b.items),
+        (ImportItems::Items(_), ImportItems::Items(_))
+    ) && a.alias.is_none()
+        && b.alias.is_none()
+        && a.visibility == b.visibility
+        && a.prefix == b.prefix
+        && names(&a.path).eq(names(&b.path))
+}
+
+/// Add the items of `from` to `into`, keeping them sorted and unique.
+/// 将 `from` 的项加入 `into`，保持有序且唯一。
+fn merge(into: &mut Import, from: Import) {
+    keep_comments(into, from.leading, from.trailing);
+    if let (ImportItems::Items(items), ImportItems::Items(more)) =
+        (&mut into.def.items, from.def.items)
+    {
+        items.extend(more);
+        items.sort_by(|a, b| a.name.cmp(&b.name));
+        items.dedup_by(|a, b| a.name == b.name);
+    }
+}
+
+/// Canonical order of imports: by group, then path, then what is imported.
+/// 导入的规范顺序：先按组，再按路径，最后按导入的内容。
+fn compare(a: &ImportDef, b: &ImportDef) -> Ordering {
+    let kind = |def: &ImportDef| match def.items {
+        ImportItems::Module => 0,
+        ImportItems::Items(_) => 1,
+        ImportItems::All => 2,
+    };
+    let items = |def: &ImportDef| match &def.items {
+        ImportItems::Items(items) => items.iter().map(|i| i.name.clone()).collect(),
+        _ => Vec::new(),
+    };
+    let alias = |def: &ImportDef| def.alias.as_ref().map(|a| a.name.clone());
+    let public = |def: &ImportDef| def.visibility == Visibility::Public;
+
+    ImportGroup::of(a)
+        .cmp(&ImportGroup::of(b))
+        .then_with(|| names(&a.path).cmp(names(&b.path)))
+        .then_with(|| kind(a).cmp(&kind(b)))
+        .then_with(|| items(a).cmp(&items(b)))
+        .then_with(|| alias(a).cmp(&alias(b)))
+        .then_with(|| public(a).cmp(&public(b)))
+}
+
+/// The names of a path's segments.
+/// 路径各段的名称。
+fn names(path: &[Ident]) -> impl Iterator<Item = &str> {
+    path.iter().map(|part| part.name.as_str())
+}
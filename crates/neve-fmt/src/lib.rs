@@ -6,11 +6,18 @@
 //! 本 crate 提供按照一致风格格式化 Neve 源代码的功能。
 
 mod config;
+mod dir;
 mod format;
+mod imports;
+mod normalize;
 pub mod printer;
+mod range;
 
 pub use config::FormatConfig;
+pub use dir::{DirCheck, check_dir};
 pub use format::Formatter;
+pub use normalize::normalize_whitespace;
+pub use range::{RangeEdit, format_range};
 
 use neve_lexer::Lexer;
 use neve_parser::Parser;
@@ -23,6 +30,9 @@ pub fn format(source: &str) -> Result<String, FormatError> {
 
 /// Format Neve source code with custom configuration.
 /// 使用自定义配置格式化 Neve 源代码。
+///
+/// The output always goes through [`normalize_whitespace`].
+/// 输出总是经过 [`normalize_whitespace`] 处理。
 pub fn format_with_config(source: &str, config: &FormatConfig) -> Result<String, FormatError> {
     let lexer = Lexer::new(source);
     let (tokens, errors) = lexer.tokenize();
@@ -34,8 +44,8 @@ pub fn format_with_config(source: &str, config: &FormatConfig) -> Result<String,
     let mut parser = Parser::new(tokens);
     let ast = parser.parse_file();
 
-    let formatter = Formatter::new(config.clone());
-    Ok(formatter.format(&ast))
+    let formatter = Formatter::new(config.clone()).with_source(source);
+    Ok(normalize_whitespace(&formatter.format(&ast), config))
 }
 
 /// Check if source code is already formatted.
@@ -0,0 +1,120 @@
+//! Whitespace normalization.
+//! 空白规范化。
+//!
+//! A lightweight pass that fixes the most common whitespace problems without
+//! reformatting: trailing whitespace, mixed tabs and spaces in indentation,
+//! and a missing or repeated final newline. Whitespace inside string literals
+//! is left untouched.
+//! 一个不重新格式化即可修复最常见空白问题的轻量级处理：行尾空白、缩进中混用的制表符和空格，
+//! 以及缺失或重复的末尾换行符。字符串字面量内的空白保持不变。
+
+use crate::FormatConfig;
+use neve_lexer::{Lexer, TokenKind};
+use std::ops::Range;
+
+/// Normalize whitespace in `source` according to `config`.
+/// 根据 `config` 规范化 `source` 中的空白。
+///
+/// Each line loses its trailing spaces and tabs, and its indentation is
+/// rewritten with the configured indent character, counting a tab as
+/// `indent_width` columns. Trailing blank lines are dropped and, if
+/// `trailing_newline` is set, a single final newline is ensured.
+/// 每行去除行尾的空格和制表符，并使用配置的缩进字符重写缩进，一个制表符按
+/// `indent_width` 列计算。删除末尾的空行；若设置了 `trailing_newline`，则确保只有一个末尾换行符。
+pub fn normalize_whitespace(source: &str, config: &FormatConfig) -> String {
+    let strings = string_ranges(source);
+    let inside_string = |pos: usize| strings.iter().any(|r| r.start < pos && pos < r.end);
+
+    let mut out = String::with_capacity(source.len());
+    let mut line_start = 0;
+    for line in source.split_inclusive('\n') {
+        let content = line.trim_end_matches(['\n', '\r']);
+        let line_end = line_start + content.len();
+
+        // Indentation
+        // 缩进
+        let body = if inside_string(line_start) {
+            content
+        } else {
+            let body = content.trim_start_matches([' ', '\t']);
+            let indent = &content[..content.len() - body.len()];
+            if !body.is_empty() {
+                out.push_str(&reindent(indent, config));
+            }
+            body
+        };
+
+        // Trailing whitespace
+        // 行尾空白
+        if inside_string(line_end) {
+            out.push_str(body);
+        } else {
+            out.push_str(body.trim_end_matches([' ', '\t']));
+        }
+
+        out.push_str(&line[content.len()..]);
+        line_start += line.len();
+    }
+
+    // Final newline
+    // 末尾换行符
+    if !inside_string(source.len()) {
+        let trimmed = out.trim_end_matches(['\n', '\r']).len();
+        out.truncate(trimmed);
+        if config.trailing_newline && !out.is_empty() {
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Rewrite an indentation run with the configured indent character.
+/// 使用配置的缩进字符重写一段缩进。
+fn reindent(indent: &str, config: &FormatConfig) -> String {
+    let width = config.indent_width.max(1);
+    let columns: usize = indent
+        .chars()
+        .map(|c| if c == '\t' { width } else { 1 })
+        .sum();
+
+    if config.use_tabs {
+        let mut s = "\t".repeat(columns / width);
+        s.push_str(&" ".repeat(columns % width));
+        s
+    } else {
+        " ".repeat(columns)
+    }
+}
+
+/// Byte ranges of the string literals in `source`.
+/// `source` 中字符串字面量的字节范围。
+fn string_ranges(source: &str) -> Vec<Range<usize>> {
+    let (tokens, _) = Lexer::new(source).tokenize();
+
+    let mut ranges = Vec::new();
+    let mut interpolated_start = None;
+    let mut depth = 0usize;
+    for token in &tokens {
+        let start = token.span.start.0 as usize;
+        let end = token.span.end.0 as usize;
+        match token.kind {
+            TokenKind::String(_) if depth == 0 => ranges.push(start..end),
+            TokenKind::InterpolatedStart => {
+                if depth == 0 {
+                    interpolated_start = Some(start);
+                }
+                depth += 1;
+            }
+            TokenKind::InterpolatedEnd => {
+                depth = depth.saturating_sub(1);
+                if depth == 0
+                    && let Some(start) = interpolated_start.take()
+                {
+                    ranges.push(start..end);
+                }
+            }
+            _ => {}
+        }
+    }
+    ranges
+}
@@ -20,6 +20,8 @@ pub struct Printer {
     current_line_width: usize,
     /// Whether we're at the start of a line. / 是否在行首。
     at_line_start: bool,
+    /// Whether every group is laid out on one line. / 是否将每个组都排在一行上。
+    flat: bool,
 }
 
 impl Printer {
@@ -32,9 +34,26 @@ impl Printer {
             indent_level: 0,
             current_line_width: 0,
             at_line_start: true,
+            flat: false,
         }
     }
 
+    /// Create a printer that lays out every group on one line, used to
+    /// measure how wide a group would be.
+    /// 创建将每个组都排在一行上的打印器，用于测量组的宽度。
+    pub fn flat(config: FormatConfig) -> Self {
+        Self {
+            flat: true,
+            ..Self::new(config)
+        }
+    }
+
+    /// Whether every group is laid out on one line.
+    /// 是否将每个组都排在一行上。
+    pub fn is_flat(&self) -> bool {
+        self.flat
+    }
+
     /// Get the formatted output.
     /// 获取格式化后的输出。
     pub fn finish(mut self) -> String {
@@ -101,7 +120,19 @@ impl Printer {
     /// Check if adding text would exceed max width.
     /// 检查添加文本是否会超过最大宽度。
     pub fn would_exceed_width(&self, text_len: usize) -> bool {
-        self.current_line_width + text_len > self.config.max_width
+        !self.flat && self.current_line_width + text_len > self.config.max_width
+    }
+
+    /// Check if the first line of `text` fits on the current line.
+    /// 检查 `text` 的第一行是否能放在当前行上。
+    pub fn fits(&self, text: &str) -> bool {
+        let width = if self.at_line_start {
+            self.config.indent_str().len() * self.indent_level
+        } else {
+            self.current_line_width
+        };
+        let first_line = text.lines().next().unwrap_or("");
+        width + first_line.len() <= self.config.max_width
     }
 
     /// Get current indentation level.
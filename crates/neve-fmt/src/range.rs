@@ -0,0 +1,213 @@
+//! Formatting part of a source file.
+//! 格式化源文件的一部分。
+//!
+//! A range is widened to the whole top-level items it overlaps. When it lies
+//! inside a single item, the innermost block around it is used instead and
+//! only the statements it overlaps are formatted, keeping the indentation
+//! they already have so the rest of the file is left untouched.
+//! 范围会扩展到它所重叠的完整顶级项。当范围位于单个项内部时，改为使用包围它的最内层块，
+//! 只格式化它所重叠的语句，并保留这些语句已有的缩进，从而不改动文件的其余部分。
+
+use crate::{FormatConfig, FormatError, Formatter};
+use neve_common::{BytePos, Span};
+use neve_syntax::{Expr, ExprKind, Item, ItemKind, Stmt, StmtKind};
+
+/// Replacement text for a span of a source file.
+/// 源文件中某个跨度的替换文本。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RangeEdit {
+    /// The span to replace. / 要替换的跨度。
+    pub span: Span,
+    /// The formatted text. / 格式化后的文本。
+    pub text: String,
+}
+
+/// Format the items or statements of `source` overlapping `start..end`.
+/// 格式化 `source` 中与 `start..end` 重叠的项或语句。
+///
+/// The returned edit covers exactly the widened range; splicing its text
+/// into the source formats that part and nothing else. A range overlapping
+/// no code yields an edit that changes nothing.
+/// 返回的编辑恰好覆盖扩展后的范围；将其文本拼接回源代码只会格式化该部分。
+/// 不与任何代码重叠的范围会产生不做任何更改的编辑。
+pub fn format_range(
+    source: &str,
+    start: BytePos,
+    end: BytePos,
+    config: &FormatConfig,
+) -> Result<RangeEdit, FormatError> {
+    let (file, diagnostics) = neve_parser::parse(source);
+    if !diagnostics.is_empty() {
+        return Err(FormatError::Parse(diagnostics[0].message.clone()));
+    }
+    let (start, end) = (start.min(end), start.max(end));
+    let unchanged = || {
+        let span = Span::new(start, end);
+        RangeEdit {
+            span,
+            text: source
+                .get(start.0 as usize..end.0 as usize)
+                .unwrap_or("")
+                .to_string(),
+        }
+    };
+
+    let items: Vec<&Item> = file
+        .items
+        .iter()
+        .filter(|item| overlaps(item.span, start, end))
+        .collect();
+    let (Some(first), Some(last)) = (items.first(), items.last()) else {
+        return Ok(unchanged());
+    };
+
+    // A range inside one item formats the statements of the block around it
+    // 位于单个项内部的范围格式化包围它的块中的语句
+    if items.len() == 1 && first.span.start < start && end < first.span.end {
+        let block = item_exprs(first)
+            .into_iter()
+            .find_map(|expr| innermost_block(expr, start, end));
+        if let Some(ExprKind::Block { stmts, expr }) = block.map(|block| &block.kind) {
+            return Ok(
+                format_stmts(source, stmts, expr.as_deref(), start, end, config)
+                    .unwrap_or_else(unchanged),
+            );
+        }
+    }
+
+    let span = Span::new(first.span.start, last.span.end);
+    let formatter = Formatter::new(config.clone()).with_source(source);
+    let items: Vec<Item> = items.into_iter().cloned().collect();
+    Ok(RangeEdit {
+        span,
+        text: formatter
+            .format_item_list(&items)
+            .trim_end_matches('\n')
+            .to_string(),
+    })
+}
+
+/// Format the statements of a block overlapping `start..end`, indented like
+/// the first of them.
+/// 格式化块中与 `start..end` 重叠的语句，缩进与其中第一条相同。
+fn format_stmts(
+    source: &str,
+    stmts: &[Stmt],
+    tail: Option<&Expr>,
+    start: BytePos,
+    end: BytePos,
+    config: &FormatConfig,
+) -> Option<RangeEdit> {
+    let spans: Vec<Span> = stmts
+        .iter()
+        .map(|stmt| stmt_span(source, stmt))
+        .chain(tail.map(|e| e.span))
+        .collect();
+    let first = spans.iter().position(|&span| overlaps(span, start, end))?;
+    let last = spans.iter().rposition(|&span| overlaps(span, start, end))?;
+    let span = Span::new(spans[first].start, spans[last].end);
+
+    let line_start = source[..span.start.0 as usize]
+        .rfind('\n')
+        .map_or(0, |i| i + 1);
+    let indent = &source[line_start..span.start.0 as usize];
+    if !indent.chars().all(|c| c == ' ' || c == '\t') {
+        // Something else shares the line; leave it alone
+        // 该行还有其他内容；保持不变
+        return None;
+    }
+
+    let mut config = config.clone();
+    config.max_width = config.max_width.saturating_sub(indent.len());
+    let formatter = Formatter::new(config).with_source(source);
+    // Index `stmts.len()` is the block's result
+    // 索引 `stmts.len()` 是块的结果
+    let n = stmts.len();
+    let tail = tail.filter(|_| last == n);
+    let formatted = formatter.format_stmt_list(&stmts[first.min(n)..(last + 1).min(n)], tail);
+
+    let text = formatted
+        .trim_end_matches('\n')
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            if i == 0 || line.is_empty() {
+                line.to_string()
+            } else {
+                format!("{}{}", indent, line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    Some(RangeEdit { span, text })
+}
+
+/// Whether `span` overlaps `start..end`; an empty range overlaps the spans
+/// it touches.
+/// `span` 是否与 `start..end` 重叠；空范围与其接触的跨度重叠。
+fn overlaps(span: Span, start: BytePos, end: BytePos) -> bool {
+    if start == end {
+        span.start <= start && start <= span.end
+    } else {
+        span.start < end && start < span.end
+    }
+}
+
+/// The span of a statement including its `;`.
+/// 包括 `;` 在内的语句跨度。
+fn stmt_span(source: &str, stmt: &Stmt) -> Span {
+    let end = stmt.span.end.0 as usize;
+    let rest = source.get(end..).unwrap_or("");
+    let trimmed = rest.trim_start();
+    if matches!(stmt.kind, StmtKind::Expr(_)) && trimmed.starts_with(';') {
+        let semi = end + (rest.len() - trimmed.len()) + 1;
+        Span::new(stmt.span.start, BytePos(semi as u32))
+    } else {
+        stmt.span
+    }
+}
+
+/// The expressions directly inside an item.
+/// 直接位于项内的表达式。
+fn item_exprs(item: &Item) -> Vec<&Expr> {
+    match &item.kind {
+        ItemKind::Let(def) => vec![&def.value],
+        ItemKind::Fn(def) => vec![&def.body],
+        ItemKind::Struct(def) => def
+            .fields
+            .iter()
+            .filter_map(|f| f.default.as_ref())
+            .collect(),
+        ItemKind::Trait(def) => def
+            .items
+            .iter()
+            .filter_map(|i| i.default.as_ref())
+            .collect(),
+        ItemKind::Impl(def) => def.items.iter().map(|i| &i.body).collect(),
+        ItemKind::TypeAlias(_) | ItemKind::Enum(_) | ItemKind::Import(_) => Vec::new(),
+    }
+}
+
+/// The innermost block strictly containing `start..end` with a statement
+/// or result overlapping it.
+/// 严格包含 `start..end` 且有语句或结果与之重叠的最内层块。
+fn innermost_block(expr: &Expr, start: BytePos, end: BytePos) -> Option<&Expr> {
+    if !(expr.span.start < start && end < expr.span.end) {
+        return None;
+    }
+    if let Some(inner) = expr
+        .children()
+        .into_iter()
+        .find_map(|child| innermost_block(child, start, end))
+    {
+        return Some(inner);
+    }
+    match &expr.kind {
+        ExprKind::Block { stmts, expr: tail } => {
+            let overlapping = stmts.iter().any(|stmt| overlaps(stmt.span, start, end))
+                || tail.as_ref().is_some_and(|e| overlaps(e.span, start, end));
+            overlapping.then_some(expr)
+        }
+        _ => None,
+    }
+}
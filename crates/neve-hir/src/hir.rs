@@ -394,6 +394,9 @@ pub struct FnDef {
     pub return_ty: Ty,
     /// Function body. / 函数体。
     pub body: Expr,
+    /// Whether this was lowered from a top-level `let`, making it a value.
+    /// 是否由顶层 `let` 降级而来（即它是一个值）。
+    pub is_let: bool,
 }
 
 /// Function parameter.
@@ -406,7 +409,7 @@ pub struct Param {
     pub name: String,
     /// Parameter type. / 参数类型。
     pub ty: Ty,
-    /// Source location. / 源代码位置。
+    /// Source location of the parameter pattern. / 参数模式的源代码位置。
     pub span: Span,
 }
 
@@ -442,6 +445,8 @@ pub struct FieldDef {
     pub name: String,
     /// Field type. / 字段类型。
     pub ty: Ty,
+    /// Default value, if the field may be omitted. / 默认值（如果该字段可省略）。
+    pub default: Option<Expr>,
     /// Source location. / 源代码位置。
     pub span: Span,
 }
@@ -462,6 +467,8 @@ pub struct EnumDef {
 /// 枚举变体定义。
 #[derive(Debug, Clone)]
 pub struct VariantDef {
+    /// Definition ID of the variant's constructor. / 变体构造器的定义 ID。
+    pub id: DefId,
     /// Variant name. / 变体名称。
     pub name: String,
     /// Variant fields (for tuple variants). / 变体字段（用于元组变体）。
@@ -616,8 +623,10 @@ pub enum ExprKind {
     Unary(UnaryOp, Box<Expr>),
     /// Conditional expression. / 条件表达式。
     If(Box<Expr>, Box<Expr>, Box<Expr>),
-    /// Pattern matching. / 模式匹配。
-    Match(Box<Expr>, Vec<MatchArm>),
+    /// Pattern matching, and whether it was desugared from `??` or `?` and
+    /// so is partial on purpose.
+    /// 模式匹配，以及它是否由 `??` 或 `?` 解糖而来、因而有意不完整。
+    Match(Box<Expr>, Vec<MatchArm>, bool),
     /// Block expression. / 块表达式。
     Block(Vec<Stmt>, Option<Box<Expr>>),
     /// Interpolated string `` `hello {name}` ``. / 插值字符串 `` `hello {name}` ``。
@@ -740,10 +749,18 @@ pub enum PatternKind {
     Tuple(Vec<Pattern>),
     /// List pattern. / 列表模式。
     List(Vec<Pattern>),
+    /// List pattern with a rest: leading patterns, the rest binding and trailing patterns.
+    /// 带剩余部分的列表模式：前部模式、剩余绑定和尾部模式。
+    ListRest(Vec<Pattern>, Option<Box<Pattern>>, Vec<Pattern>),
     /// Record pattern. / 记录模式。
     Record(Vec<(String, Pattern)>),
-    /// Constructor pattern. / 构造器模式。
-    Constructor(DefId, Vec<Pattern>),
+    /// Constructor pattern: the resolved definition, the variant name and its arguments.
+    /// 构造器模式：解析到的定义、变体名称及其参数。
+    Constructor(DefId, String, Vec<Pattern>),
+    /// Or pattern; every alternative binds the same locals. / 或模式；每个选项绑定相同的局部变量。
+    Or(Vec<Pattern>),
+    /// Binding pattern `name @ pattern`. / 绑定模式 `name @ pattern`。
+    Binding(LocalId, String, Box<Pattern>),
 }
 
 /// HIR statement.
@@ -812,6 +829,9 @@ pub enum TyKind {
     /// Record type. / 记录类型。
     Record(Vec<(String, Ty)>),
 
+    /// Open record type: the listed fields plus a row variable standing for the rest (e.g., `#{ name: T, .. }`). / 开放记录类型：所列字段加上代表其余字段的行变量（例如 `#{ name: T, .. }`）。
+    OpenRecord(Vec<(String, Ty)>, Box<Ty>),
+
     /// Forall type (polymorphic type, e.g., `forall a. a -> a`). / Forall 类型（多态类型，例如 `forall a. a -> a`）。
     Forall(Vec<String>, Box<Ty>),
 
@@ -14,6 +14,6 @@ pub use hir::*;
 pub use lower::lower;
 pub use module_loader::{
     ImportResolveError, ModuleInfo, ModuleLoadError, ModuleLoader, ModulePath, ModulePathKind,
-    Visibility,
+    ResolutionTrace, Visibility,
 };
-pub use resolve::Resolver;
+pub use resolve::{Resolver, UnresolvedName, is_variant_pattern_name, variant_names};
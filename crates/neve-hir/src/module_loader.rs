@@ -134,6 +134,65 @@ impl std::fmt::Display for ModulePath {
     }
 }
 
+/// A record of how one module path was resolved, for tracing imports.
+/// 单个模块路径解析过程的记录，用于跟踪导入。
+#[derive(Debug, Clone)]
+pub struct ResolutionTrace {
+    /// The module path as written. / 书写的模块路径。
+    pub path: ModulePath,
+    /// The module the import appears in. / 导入所在的模块。
+    pub from_module: Vec<String>,
+    /// The path after applying the prefix, or `None` if the prefix cannot be applied.
+    /// 应用前缀后的路径；如果无法应用前缀则为 `None`。
+    pub absolute: Option<Vec<String>>,
+    /// Candidate files in the order tried, with whether each exists.
+    /// 按尝试顺序排列的候选文件，以及每个文件是否存在。
+    pub candidates: Vec<(PathBuf, bool)>,
+}
+
+impl ResolutionTrace {
+    /// The file the path resolved to, if any.
+    /// 路径解析到的文件（如果有）。
+    pub fn resolved(&self) -> Option<&Path> {
+        self.candidates
+            .iter()
+            .find(|(_, found)| *found)
+            .map(|(path, _)| path.as_path())
+    }
+}
+
+impl std::fmt::Display for ResolutionTrace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let from = if self.from_module.is_empty() {
+            "<root>".to_string()
+        } else {
+            self.from_module.join(".")
+        };
+
+        writeln!(f, "import {}", self.path)?;
+        match self.path.kind {
+            ModulePathKind::Absolute => writeln!(f, "  prefix: absolute, from the root directory")?,
+            ModulePathKind::Crate => writeln!(f, "  prefix: crate, from the root directory")?,
+            ModulePathKind::Self_ => writeln!(f, "  prefix: self, relative to `{}`", from)?,
+            ModulePathKind::Super => {
+                writeln!(f, "  prefix: super, relative to the parent of `{}`", from)?
+            }
+        }
+        match &self.absolute {
+            Some(absolute) => writeln!(f, "  module: {}", absolute.join("."))?,
+            None => writeln!(f, "  module: prefix cannot be applied from `{}`", from)?,
+        }
+        for (candidate, found) in &self.candidates {
+            let status = if *found { "found" } else { "not found" };
+            writeln!(f, "  tried {}: {}", candidate.display(), status)?;
+        }
+        match self.resolved() {
+            Some(path) => writeln!(f, "  resolved: {}", path.display()),
+            None => writeln!(f, "  unresolved"),
+        }
+    }
+}
+
 // Re-export Visibility from the AST
 // 从 AST 重新导出 Visibility
 pub use neve_syntax::Visibility;
@@ -496,6 +555,29 @@ impl ModuleLoader {
         self.find_module_file(&absolute_path)
     }
 
+    /// Resolve a module path like [`resolve_path`](Self::resolve_path), recording
+    /// every candidate file tried along the way.
+    /// 与 [`resolve_path`](Self::resolve_path) 相同地解析模块路径，并记录途中尝试的每个候选文件。
+    pub fn trace_path(&self, path: &ModulePath, from_module: Option<&[String]>) -> ResolutionTrace {
+        let absolute = self.make_absolute(path, from_module);
+        let mut candidates = Vec::new();
+        if let Some(absolute) = &absolute {
+            for candidate in self.candidate_files(absolute) {
+                let found = candidate.exists();
+                candidates.push((candidate, found));
+                if found {
+                    break;
+                }
+            }
+        }
+        ResolutionTrace {
+            path: path.clone(),
+            from_module: from_module.map(<[String]>::to_vec).unwrap_or_default(),
+            absolute,
+            candidates,
+        }
+    }
+
     /// Convert a relative path to an absolute path.
     /// 将相对路径转换为绝对路径。
     fn make_absolute(
@@ -546,6 +628,20 @@ impl ModuleLoader {
             return Some(self.root_dir.join("lib.neve"));
         }
 
+        self.candidate_files(module_path)
+            .into_iter()
+            .find(|candidate| candidate.exists())
+    }
+
+    /// Candidate files for an absolute module path, in the order they are tried.
+    /// 绝对模块路径的候选文件，按尝试顺序排列。
+    fn candidate_files(&self, module_path: &[String]) -> Vec<PathBuf> {
+        if module_path.is_empty() {
+            return vec![self.root_dir.join("lib.neve")];
+        }
+
+        let mut candidates = Vec::new();
+
         // Check if it's a standard library module
         // 检查是否为标准库模块
         if module_path.first().map(|s| s.as_str()) == Some("std")
@@ -553,51 +649,28 @@ impl ModuleLoader {
         {
             let relative: PathBuf = module_path[1..].iter().collect();
 
-            // Try module_name.neve
-            // 尝试 module_name.neve
-            let file_path = std_path.join(&relative).with_extension("neve");
-            if file_path.exists() {
-                return Some(file_path);
-            }
-
-            // Try module_name/mod.neve
-            // 尝试 module_name/mod.neve
-            let mod_path = std_path.join(&relative).join("mod.neve");
-            if mod_path.exists() {
-                return Some(mod_path);
-            }
+            // Try module_name.neve, then module_name/mod.neve
+            // 尝试 module_name.neve，然后尝试 module_name/mod.neve
+            candidates.push(std_path.join(&relative).with_extension("neve"));
+            candidates.push(std_path.join(&relative).join("mod.neve"));
         }
 
         // Build relative path
         // 构建相对路径
         let relative: PathBuf = module_path.iter().collect();
 
-        // Try module_name.neve
-        // 尝试 module_name.neve
-        let file_path = self.root_dir.join(&relative).with_extension("neve");
-        if file_path.exists() {
-            return Some(file_path);
-        }
-
-        // Try module_name/mod.neve
-        // 尝试 module_name/mod.neve
-        let mod_path = self.root_dir.join(&relative).join("mod.neve");
-        if mod_path.exists() {
-            return Some(mod_path);
-        }
-
-        // Try src/module_name.neve
-        // 尝试 src/module_name.neve
-        let src_path = self
-            .root_dir
-            .join("src")
-            .join(&relative)
-            .with_extension("neve");
-        if src_path.exists() {
-            return Some(src_path);
-        }
+        // Try module_name.neve, module_name/mod.neve and src/module_name.neve
+        // 尝试 module_name.neve、module_name/mod.neve 和 src/module_name.neve
+        candidates.push(self.root_dir.join(&relative).with_extension("neve"));
+        candidates.push(self.root_dir.join(&relative).join("mod.neve"));
+        candidates.push(
+            self.root_dir
+                .join("src")
+                .join(&relative)
+                .with_extension("neve"),
+        );
 
-        None
+        candidates
     }
 
     /// Load a module by path.
@@ -7,10 +7,68 @@ use crate::{
     LocalId, MatchArm, Module, ModuleId, ModuleLoader, Param, Pattern, PatternKind, Stmt, StmtKind,
     StringPart, StructDef, TraitDef, TraitItem, Ty, TyKind, TypeAlias, UnaryOp, VariantDef,
 };
+use neve_common::{Span, find_similar_name};
+use neve_diagnostic::{Diagnostic, DiagnosticKind, ErrorCode, Label};
 use neve_syntax::{self as ast, SourceFile};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 
+/// Names of the enum variants defined by a file's top-level items.
+/// 文件顶层项定义的枚举变体名称。
+pub fn variant_names(file: &SourceFile) -> HashSet<String> {
+    file.items
+        .iter()
+        .filter_map(|item| match &item.kind {
+            ast::ItemKind::Enum(def) => Some(&def.variants),
+            _ => None,
+        })
+        .flatten()
+        .map(|variant| variant.name.name.clone())
+        .collect()
+}
+
+/// Whether a bare name in a pattern matches a variant instead of binding a variable.
+/// 模式中的裸名称是否匹配变体而不是绑定变量。
+pub fn is_variant_pattern_name(name: &str, variants: &HashSet<String>) -> bool {
+    name == "None" || variants.contains(name)
+}
+
+/// A variable reference the resolver could not bind to a local or global.
+/// 解析器无法绑定到局部或全局定义的变量引用。
+///
+/// Builtins are only known at evaluation time, so these are not errors by
+/// themselves; callers decide whether to report them.
+/// 内置函数仅在求值时可知，因此这些本身并不是错误；由调用者决定是否报告。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnresolvedName {
+    /// The name as written. / 书写的名称。
+    pub name: String,
+    /// Where the name was referenced. / 引用该名称的位置。
+    pub span: Span,
+    /// The closest name in scope, if any is close enough. / 作用域中足够接近的名称（如果有）。
+    pub suggestion: Option<String>,
+}
+
+impl UnresolvedName {
+    /// Convert into an "unbound variable" diagnostic.
+    /// 转换为"未绑定变量"诊断。
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let mut diag = Diagnostic::error(
+            DiagnosticKind::Type,
+            self.span,
+            format!("cannot find value `{}` in this scope", self.name),
+        )
+        .with_code(ErrorCode::UnboundVariable)
+        .with_label(Label::new(self.span, "not found in this scope"));
+
+        if let Some(similar) = &self.suggestion {
+            diag = diag.with_help(format!("did you mean `{}`?", similar));
+        }
+
+        diag
+    }
+}
+
 /// Name resolver that builds HIR from AST.
 /// 从 AST 构建 HIR 的名称解析器。
 pub struct Resolver {
@@ -30,6 +88,21 @@ pub struct Resolver {
     current_module_path: Vec<String>,
     /// Module loader for resolving imports. / 用于解析导入的模块加载器。
     module_loader: Option<ModuleLoader>,
+    /// Variable references that could not be resolved. / 无法解析的变量引用。
+    unresolved: Vec<UnresolvedName>,
+    /// Names of enum variants defined in this module. / 本模块中定义的枚举变体名称。
+    variants: HashSet<String>,
+    /// Definitions of each enum's variants, in declaration order.
+    /// 每个枚举的变体定义，按声明顺序排列。
+    variant_ids: HashMap<DefId, Vec<DefId>>,
+    /// Non-local names referenced in the current module. / 当前模块中引用的非局部名称。
+    referenced: HashSet<String>,
+    /// Top-level item whose body is being lowered. / 正在降级其主体的顶层项。
+    current_item: Option<String>,
+    /// Whether to warn about unused imports and bindings. / 是否对未使用的导入和绑定发出警告。
+    warn_unused: bool,
+    /// Warnings collected while resolving. / 解析过程中收集的警告。
+    diagnostics: Vec<Diagnostic>,
 }
 
 impl Resolver {
@@ -45,6 +118,13 @@ impl Resolver {
             imported: HashMap::new(),
             current_module_path: Vec::new(),
             module_loader: None,
+            unresolved: Vec::new(),
+            variants: HashSet::new(),
+            variant_ids: HashMap::new(),
+            referenced: HashSet::new(),
+            current_item: None,
+            warn_unused: false,
+            diagnostics: Vec::new(),
         }
     }
 
@@ -60,6 +140,13 @@ impl Resolver {
             imported: HashMap::new(),
             current_module_path: Vec::new(),
             module_loader: Some(ModuleLoader::new(root_dir)),
+            unresolved: Vec::new(),
+            variants: HashSet::new(),
+            variant_ids: HashMap::new(),
+            referenced: HashSet::new(),
+            current_item: None,
+            warn_unused: false,
+            diagnostics: Vec::new(),
         }
     }
 
@@ -93,6 +180,25 @@ impl Resolver {
         &self.current_module_path
     }
 
+    /// Enable or disable warnings for unused imports and top-level bindings.
+    /// They are disabled by default.
+    /// 启用或禁用对未使用的导入和顶层绑定的警告。默认禁用。
+    pub fn set_warn_unused(&mut self, enabled: bool) {
+        self.warn_unused = enabled;
+    }
+
+    /// Get the warnings reported so far, such as unused imports.
+    /// 获取目前为止报告的警告，例如未使用的导入。
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Get the variable references that could not be resolved so far.
+    /// 获取目前为止无法解析的变量引用。
+    pub fn unresolved_names(&self) -> &[UnresolvedName] {
+        &self.unresolved
+    }
+
     /// Create an unknown type with the given span.
     /// Used during lowering when the type will be inferred later.
     /// 创建具有给定位置的未知类型。
@@ -130,6 +236,7 @@ impl Resolver {
         // Set current module path for relative import resolution
         // 设置当前模块路径以解析相对导入
         self.current_module_path = module_path;
+        self.referenced.clear();
 
         // First pass: collect imports and resolve them
         // 第一遍：收集导入并解析它们
@@ -157,6 +264,10 @@ impl Resolver {
         // 根据可见性收集导出
         let exports = self.collect_exports(file);
 
+        if self.warn_unused {
+            self.report_unused(file);
+        }
+
         Module {
             id: module_id,
             name,
@@ -231,6 +342,75 @@ impl Resolver {
         }
     }
 
+    /// Warn about imports and top-level bindings that are never referenced.
+    /// 对从未被引用的导入和顶层绑定发出警告。
+    ///
+    /// Public items, re-exports, glob imports and `_`-prefixed names are
+    /// skipped, as is a trailing top-level `let`, whose value is the module's.
+    /// Unused local bindings are reported by the type checker.
+    /// 跳过公开项、重新导出、通配导入和以 `_` 开头的名称，以及作为模块值的末尾顶层 `let`。
+    /// 未使用的局部绑定由类型检查器报告。
+    fn report_unused(&mut self, file: &SourceFile) {
+        let last = file.items.len().saturating_sub(1);
+        let mut unused = Vec::new();
+        for (index, item) in file.items.iter().enumerate() {
+            match &item.kind {
+                ast::ItemKind::Import(import_def)
+                    if import_def.visibility == ast::Visibility::Private =>
+                {
+                    match &import_def.items {
+                        ast::ImportItems::Items(items) => {
+                            unused.extend(items.iter().map(|i| ("import", i)));
+                        }
+                        ast::ImportItems::Module => {
+                            if let Some(name) = import_def.alias.as_ref().or(import_def.path.last())
+                            {
+                                unused.push(("import", name));
+                            }
+                        }
+                        ast::ImportItems::All => {}
+                    }
+                }
+                ast::ItemKind::Let(def)
+                    if def.visibility == ast::Visibility::Private && index != last =>
+                {
+                    if let ast::PatternKind::Var(ident) = &def.pattern.kind {
+                        unused.push(("binding", ident));
+                    }
+                }
+                ast::ItemKind::Fn(def) if def.visibility == ast::Visibility::Private => {
+                    unused.push(("function", &def.name));
+                }
+                _ => {}
+            }
+        }
+
+        for (what, ident) in unused {
+            if ident.name.starts_with('_') || self.referenced.contains(&ident.name) {
+                continue;
+            }
+            self.diagnostics.push(
+                Diagnostic::warning(
+                    DiagnosticKind::Module,
+                    ident.span,
+                    format!("unused {}: `{}`", what, ident.name),
+                )
+                .with_label(Label::new(
+                    ident.span,
+                    format!("this {} is never used", what),
+                ))
+                .with_help(if what == "import" {
+                    "remove the import".to_string()
+                } else {
+                    format!(
+                        "if this is intentional, prefix the name with an underscore: `_{}`",
+                        ident.name
+                    )
+                }),
+            );
+        }
+    }
+
     /// Allocate a fresh module ID.
     /// 分配新的模块 ID。
     fn fresh_module_id(&mut self) -> ModuleId {
@@ -340,6 +520,36 @@ impl Resolver {
             .copied()
     }
 
+    /// Look up a non-local name, remembering that it was referenced.
+    /// 查找非局部名称，并记录其已被引用。
+    ///
+    /// An item referring to itself, as a recursive function does, is not a use.
+    /// 项引用自身（如递归函数）不算作使用。
+    fn reference_global(&mut self, name: &str) -> Option<DefId> {
+        if self.current_item.as_deref() != Some(name) {
+            self.referenced.insert(name.to_string());
+        }
+        self.lookup_global(name)
+    }
+
+    /// Record a reference to an unknown name, with the closest visible name as a suggestion.
+    /// 记录对未知名称的引用，并以最接近的可见名称作为建议。
+    fn record_unresolved(&mut self, name: &str, span: Span) {
+        let visible = self
+            .scopes
+            .iter()
+            .flat_map(|scope| scope.keys())
+            .chain(self.globals.keys())
+            .chain(self.imported.keys())
+            .map(String::as_str);
+        let suggestion = find_similar_name(name, visible).map(str::to_string);
+        self.unresolved.push(UnresolvedName {
+            name: name.to_string(),
+            span,
+            suggestion,
+        });
+    }
+
     /// Register an imported name for resolution.
     /// 注册导入的名称以供解析。
     pub fn register_import(&mut self, name: String, def_id: DefId) {
@@ -380,10 +590,14 @@ impl Resolver {
                 self.globals.insert(def.name.name.clone(), id);
                 // Also register variants
                 // 同时注册变体
+                let mut ids = Vec::new();
                 for variant in &def.variants {
                     let vid = self.fresh_def_id();
                     self.globals.insert(variant.name.name.clone(), vid);
+                    self.variants.insert(variant.name.name.clone());
+                    ids.push(vid);
                 }
+                self.variant_ids.insert(id, ids);
             }
             ast::ItemKind::TypeAlias(def) => {
                 let id = self.fresh_def_id();
@@ -427,17 +641,28 @@ impl Resolver {
                 let id = self.lookup_global(&name)?;
 
                 self.push_scope();
+                self.current_item = Some(name.clone());
                 let body = self.lower_expr(&def.value);
+                self.current_item = None;
                 self.pop_scope();
 
+                // The annotation, if any, becomes the return type
+                // 类型注解（如果有）成为返回类型
+                let return_ty = def
+                    .ty
+                    .as_ref()
+                    .map(|t| self.lower_type(t))
+                    .unwrap_or_else(|| Self::unknown_ty(item.span));
+
                 Some(Item {
                     id,
                     kind: ItemKind::Fn(FnDef {
                         name,
                         generics: Vec::new(),
                         params: Vec::new(),
-                        return_ty: Self::unknown_ty(item.span),
+                        return_ty,
                         body,
+                        is_let: true,
                     }),
                     span: item.span,
                 })
@@ -456,7 +681,9 @@ impl Resolver {
                     .map(|t| self.lower_type(t))
                     .unwrap_or_else(|| Self::unknown_ty(def.name.span));
 
+                self.current_item = Some(def.name.name.clone());
                 let body = self.lower_expr(&def.body);
+                self.current_item = None;
 
                 self.pop_scope();
 
@@ -468,6 +695,7 @@ impl Resolver {
                         params,
                         return_ty,
                         body,
+                        is_let: false,
                     }),
                     span: item.span,
                 })
@@ -475,15 +703,19 @@ impl Resolver {
             ast::ItemKind::Struct(def) => {
                 let id = self.lookup_global(&def.name.name)?;
                 let generics = self.lower_generics(&def.generics);
-                let fields = def
-                    .fields
-                    .iter()
-                    .map(|f| FieldDef {
+                let mut fields = Vec::with_capacity(def.fields.len());
+                for f in &def.fields {
+                    let ty = self.lower_type(&f.ty);
+                    self.push_scope();
+                    let default = f.default.as_ref().map(|e| self.lower_expr(e));
+                    self.pop_scope();
+                    fields.push(FieldDef {
                         name: f.name.name.clone(),
-                        ty: self.lower_type(&f.ty),
+                        ty,
+                        default,
                         span: f.span,
-                    })
-                    .collect();
+                    });
+                }
 
                 Some(Item {
                     id,
@@ -498,10 +730,12 @@ impl Resolver {
             ast::ItemKind::Enum(def) => {
                 let id = self.lookup_global(&def.name.name)?;
                 let generics = self.lower_generics(&def.generics);
+                let ids = self.variant_ids.get(&id).cloned().unwrap_or_default();
                 let variants = def
                     .variants
                     .iter()
-                    .map(|v| {
+                    .zip(ids)
+                    .map(|(v, variant_id)| {
                         let fields = match &v.kind {
                             ast::VariantKind::Unit => Vec::new(),
                             ast::VariantKind::Tuple(types) => {
@@ -512,6 +746,7 @@ impl Resolver {
                             }
                         };
                         VariantDef {
+                            id: variant_id,
                             name: v.name.name.clone(),
                             fields,
                             span: v.span,
@@ -609,7 +844,7 @@ impl Resolver {
 
     /// Lower generic parameters.
     /// 降级泛型参数。
-    fn lower_generics(&self, generics: &[ast::GenericParam]) -> Vec<GenericParam> {
+    fn lower_generics(&mut self, generics: &[ast::GenericParam]) -> Vec<GenericParam> {
         generics
             .iter()
             .map(|p| GenericParam {
@@ -633,7 +868,7 @@ impl Resolver {
             id,
             name,
             ty,
-            span: param.span,
+            span: param.pattern.span,
         }
     }
 
@@ -697,7 +932,7 @@ impl Resolver {
 
     /// Lower an associated type definition.
     /// 降级关联类型定义。
-    fn lower_assoc_type_def(&self, assoc_type: &ast::AssocTypeDef) -> AssocTypeDef {
+    fn lower_assoc_type_def(&mut self, assoc_type: &ast::AssocTypeDef) -> AssocTypeDef {
         AssocTypeDef {
             name: assoc_type.name.name.clone(),
             bounds: assoc_type
@@ -712,7 +947,7 @@ impl Resolver {
 
     /// Lower an associated type implementation.
     /// 降级关联类型实现。
-    fn lower_assoc_type_impl(&self, assoc_type_impl: &ast::AssocTypeImpl) -> AssocTypeImpl {
+    fn lower_assoc_type_impl(&mut self, assoc_type_impl: &ast::AssocTypeImpl) -> AssocTypeImpl {
         AssocTypeImpl {
             name: assoc_type_impl.name.name.clone(),
             ty: self.lower_type(&assoc_type_impl.ty),
@@ -738,11 +973,12 @@ impl Resolver {
             ast::ExprKind::Var(ident) => {
                 if let Some(local_id) = self.lookup_local(&ident.name) {
                     ExprKind::Var(local_id)
-                } else if let Some(def_id) = self.lookup_global(&ident.name) {
+                } else if let Some(def_id) = self.reference_global(&ident.name) {
                     ExprKind::Global(def_id)
                 } else {
                     // Unknown variable - will be caught during type checking
                     // 未知变量 - 将在类型检查期间捕获
+                    self.record_unresolved(&ident.name, ident.span);
                     ExprKind::Global(DefId(u32::MAX))
                 }
             }
@@ -758,9 +994,10 @@ impl Resolver {
                     let first = &parts[0];
                     let mut result_kind = if let Some(local_id) = self.lookup_local(&first.name) {
                         ExprKind::Var(local_id)
-                    } else if let Some(def_id) = self.lookup_global(&first.name) {
+                    } else if let Some(def_id) = self.reference_global(&first.name) {
                         ExprKind::Global(def_id)
                     } else {
+                        self.record_unresolved(&first.name, first.span);
                         ExprKind::Global(DefId(u32::MAX))
                     };
 
@@ -807,13 +1044,14 @@ impl Resolver {
                                             ty: Self::unknown_ty(span),
                                             span,
                                         }
-                                    } else if let Some(def_id) = self.lookup_global(name) {
+                                    } else if let Some(def_id) = self.reference_global(name) {
                                         Expr {
                                             kind: ExprKind::Global(def_id),
                                             ty: Self::unknown_ty(span),
                                             span,
                                         }
                                     } else {
+                                        self.record_unresolved(name, f.name.span);
                                         Expr {
                                             kind: ExprKind::Global(DefId(u32::MAX)),
                                             ty: Self::unknown_ty(span),
@@ -856,11 +1094,15 @@ impl Resolver {
                             .pattern_name(&p.pattern)
                             .unwrap_or_else(|| "_".to_string());
                         let id = self.define_local(name.clone());
+                        let ty =
+                            p.ty.as_ref()
+                                .map(|t| self.lower_type(t))
+                                .unwrap_or_else(|| Self::unknown_ty(p.span));
                         Param {
                             id,
                             name,
-                            ty: Self::unknown_ty(p.span),
-                            span: p.span,
+                            ty,
+                            span: p.pattern.span,
                         }
                     })
                     .collect();
@@ -886,6 +1128,9 @@ impl Resolver {
                 let mut all_args = vec![recv];
                 all_args.extend(args.iter().map(|e| self.lower_expr(e)));
 
+                // A method name is looked up without marking a global of that
+                // name as used
+                // 查找方法名时不将同名全局定义标记为已使用
                 let func = if let Some(def_id) = self.lookup_global(&method.name) {
                     Expr {
                         kind: ExprKind::Global(def_id),
@@ -893,6 +1138,7 @@ impl Resolver {
                         span,
                     }
                 } else {
+                    self.record_unresolved(&method.name, method.span);
                     Expr {
                         kind: ExprKind::Global(DefId(u32::MAX)),
                         ty: Self::unknown_ty(span),
@@ -948,14 +1194,26 @@ impl Resolver {
             } => {
                 let cond = self.lower_expr(condition);
                 let then_br = self.lower_expr(then_branch);
-                let else_br = self.lower_expr(else_branch);
+                // A missing `else` yields `()`, spanning nothing at the end of the `if`
+                // 缺少的 `else` 产生 `()`，其跨度为 `if` 末尾的空范围
+                let else_br = match else_branch {
+                    Some(else_branch) => self.lower_expr(else_branch),
+                    None => {
+                        let end = Span::new(span.end, span.end);
+                        Expr {
+                            kind: ExprKind::Literal(Literal::Unit),
+                            ty: Self::unknown_ty(end),
+                            span: end,
+                        }
+                    }
+                };
                 ExprKind::If(Box::new(cond), Box::new(then_br), Box::new(else_br))
             }
 
             ast::ExprKind::Match { scrutinee, arms } => {
                 let scrutinee = self.lower_expr(scrutinee);
                 let arms = arms.iter().map(|arm| self.lower_match_arm(arm)).collect();
-                ExprKind::Match(Box::new(scrutinee), arms)
+                ExprKind::Match(Box::new(scrutinee), arms, false)
             }
 
             ast::ExprKind::Block { stmts, expr } => {
@@ -979,6 +1237,7 @@ impl Resolver {
                         pattern: Pattern {
                             kind: PatternKind::Constructor(
                                 DefId(u32::MAX),
+                                "Some".to_string(),
                                 vec![Pattern {
                                     kind: PatternKind::Var(x_id, "x".to_string()),
                                     span,
@@ -1005,7 +1264,7 @@ impl Resolver {
                     },
                 ];
 
-                ExprKind::Match(Box::new(value), arms)
+                ExprKind::Match(Box::new(value), arms, true)
             }
 
             ast::ExprKind::Try(inner) => {
@@ -1018,6 +1277,7 @@ impl Resolver {
                     pattern: Pattern {
                         kind: PatternKind::Constructor(
                             DefId(u32::MAX),
+                            "Ok".to_string(),
                             vec![Pattern {
                                 kind: PatternKind::Var(x_id, "x".to_string()),
                                 span,
@@ -1034,7 +1294,7 @@ impl Resolver {
                     span,
                 }];
 
-                ExprKind::Match(Box::new(inner), arms)
+                ExprKind::Match(Box::new(inner), arms, true)
             }
 
             ast::ExprKind::Interpolated(parts) => {
@@ -1111,6 +1371,11 @@ impl Resolver {
             ast::PatternKind::Var(ident) => {
                 if ident.name == "_" {
                     PatternKind::Wildcard
+                } else if is_variant_pattern_name(&ident.name, &self.variants) {
+                    // A bare variant name matches that variant instead of binding
+                    // 裸变体名匹配该变体而不是绑定变量
+                    let def_id = self.lookup_global(&ident.name).unwrap_or(DefId(u32::MAX));
+                    PatternKind::Constructor(def_id, ident.name.clone(), Vec::new())
                 } else {
                     let id = self.define_local(ident.name.clone());
                     PatternKind::Var(id, ident.name.clone())
@@ -1138,6 +1403,13 @@ impl Resolver {
                 PatternKind::List(patterns)
             }
 
+            ast::PatternKind::ListRest { init, rest, tail } => {
+                let init = init.iter().map(|p| self.lower_pattern(p)).collect();
+                let rest = rest.as_ref().map(|p| Box::new(self.lower_pattern(p)));
+                let tail = tail.iter().map(|p| self.lower_pattern(p)).collect();
+                PatternKind::ListRest(init, rest, tail)
+            }
+
             ast::PatternKind::Record { fields, .. } => {
                 let fields = fields
                     .iter()
@@ -1162,30 +1434,41 @@ impl Resolver {
             ast::PatternKind::Constructor { path, args } => {
                 let def_id = path
                     .first()
-                    .and_then(|p| self.lookup_global(&p.name))
+                    .and_then(|p| self.reference_global(&p.name))
                     .unwrap_or(DefId(u32::MAX));
+                let name = path.last().map(|p| p.name.clone()).unwrap_or_default();
                 let args = args.iter().map(|p| self.lower_pattern(p)).collect();
-                PatternKind::Constructor(def_id, args)
+                PatternKind::Constructor(def_id, name, args)
             }
 
             ast::PatternKind::Or(patterns) => {
-                // For now, just use the first alternative
-                // 目前，只使用第一个选项
-                if let Some(first) = patterns.first() {
-                    return self.lower_pattern(first);
+                // Every alternative binds the same names, so later alternatives
+                // reuse the locals introduced by the first one
+                // 每个选项绑定相同的名称，因此后续选项复用第一个选项引入的局部变量
+                let mut bound = HashMap::new();
+                let mut alternatives = Vec::with_capacity(patterns.len());
+                for (i, alternative) in patterns.iter().enumerate() {
+                    let mut lowered = self.lower_pattern(alternative);
+                    if i == 0 {
+                        collect_pattern_locals(&lowered, &mut bound);
+                    } else {
+                        rebind_pattern_locals(&mut lowered, &bound);
+                    }
+                    alternatives.push(lowered);
                 }
-                PatternKind::Wildcard
+                if let Some(scope) = self.scopes.last_mut() {
+                    for (name, id) in &bound {
+                        scope.insert(name.clone(), *id);
+                    }
+                }
+                PatternKind::Or(alternatives)
             }
 
             ast::PatternKind::Binding { name, pattern } => {
                 let id = self.define_local(name.name.clone());
-                let _inner = self.lower_pattern(pattern);
-                // For @ patterns, we bind the whole value
-                // 对于 @ 模式，我们绑定整个值
-                PatternKind::Var(id, name.name.clone())
+                let inner = self.lower_pattern(pattern);
+                PatternKind::Binding(id, name.name.clone(), Box::new(inner))
             }
-
-            _ => PatternKind::Wildcard,
         };
 
         Pattern { kind, span }
@@ -1196,7 +1479,7 @@ impl Resolver {
 
     /// Lower an AST type to HIR.
     /// 将 AST 类型降级为 HIR。
-    fn lower_type(&self, ty: &ast::Type) -> Ty {
+    fn lower_type(&mut self, ty: &ast::Type) -> Ty {
         let span = ty.span;
         let kind = match &ty.kind {
             ast::TypeKind::Named { path, args } => {
@@ -1210,7 +1493,7 @@ impl Resolver {
                         "String" => TyKind::String,
                         "Unit" => TyKind::Unit,
                         _ => {
-                            if let Some(def_id) = self.lookup_global(name) {
+                            if let Some(def_id) = self.reference_global(name) {
                                 TyKind::Named(def_id, Vec::new())
                             } else {
                                 TyKind::Unknown
@@ -1218,7 +1501,7 @@ impl Resolver {
                         }
                     }
                 } else if let Some(first) = path.first() {
-                    if let Some(def_id) = self.lookup_global(&first.name) {
+                    if let Some(def_id) = self.reference_global(&first.name) {
                         let lowered_args = args.iter().map(|t| self.lower_type(t)).collect();
                         TyKind::Named(def_id, lowered_args)
                     } else {
@@ -1293,6 +1576,76 @@ impl Resolver {
     }
 }
 
+/// Collect the locals bound by a lowered pattern.
+/// 收集已降级模式绑定的局部变量。
+fn collect_pattern_locals(pattern: &Pattern, out: &mut HashMap<String, LocalId>) {
+    match &pattern.kind {
+        PatternKind::Var(id, name) => {
+            out.insert(name.clone(), *id);
+        }
+        PatternKind::Binding(id, name, inner) => {
+            out.insert(name.clone(), *id);
+            collect_pattern_locals(inner, out);
+        }
+        PatternKind::Tuple(patterns)
+        | PatternKind::List(patterns)
+        | PatternKind::Constructor(_, _, patterns)
+        | PatternKind::Or(patterns) => {
+            for p in patterns {
+                collect_pattern_locals(p, out);
+            }
+        }
+        PatternKind::ListRest(init, rest, tail) => {
+            for p in init.iter().chain(rest.as_deref()).chain(tail) {
+                collect_pattern_locals(p, out);
+            }
+        }
+        PatternKind::Record(fields) => {
+            for (_, p) in fields {
+                collect_pattern_locals(p, out);
+            }
+        }
+        PatternKind::Wildcard | PatternKind::Literal(_) => {}
+    }
+}
+
+/// Rewrite the locals of a pattern to the ids already chosen for their names.
+/// 将模式中的局部变量改写为其名称已选定的 ID。
+fn rebind_pattern_locals(pattern: &mut Pattern, ids: &HashMap<String, LocalId>) {
+    match &mut pattern.kind {
+        PatternKind::Var(id, name) => {
+            if let Some(existing) = ids.get(name) {
+                *id = *existing;
+            }
+        }
+        PatternKind::Binding(id, name, inner) => {
+            if let Some(existing) = ids.get(name) {
+                *id = *existing;
+            }
+            rebind_pattern_locals(inner, ids);
+        }
+        PatternKind::Tuple(patterns)
+        | PatternKind::List(patterns)
+        | PatternKind::Constructor(_, _, patterns)
+        | PatternKind::Or(patterns) => {
+            for p in patterns {
+                rebind_pattern_locals(p, ids);
+            }
+        }
+        PatternKind::ListRest(init, rest, tail) => {
+            for p in init.iter_mut().chain(rest.as_deref_mut()).chain(tail) {
+                rebind_pattern_locals(p, ids);
+            }
+        }
+        PatternKind::Record(fields) => {
+            for (_, p) in fields {
+                rebind_pattern_locals(p, ids);
+            }
+        }
+        PatternKind::Wildcard | PatternKind::Literal(_) => {}
+    }
+}
+
 impl Default for Resolver {
     fn default() -> Self {
         Self::new()
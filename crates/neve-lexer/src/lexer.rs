@@ -1,7 +1,7 @@
 //! The Neve lexer.
 //! Neve 词法分析器。
 
-use crate::token::{Token, TokenKind};
+use crate::token::{Token, TokenKind, Trivia, TriviaKind};
 use neve_common::Span;
 use neve_diagnostic::{Diagnostic, DiagnosticKind, ErrorCode, Label};
 
@@ -25,6 +25,9 @@ enum LexerMode {
 /// Converts source code into a sequence of tokens.
 /// 将源代码转换为 token 序列。
 pub struct Lexer<'src> {
+    /// Source code being lexed
+    /// 正在分析的源代码
+    source: &'src str,
     /// Character iterator with position info
     /// 带位置信息的字符迭代器
     chars: std::iter::Peekable<std::str::CharIndices<'src>>,
@@ -42,13 +45,22 @@ pub struct Lexer<'src> {
 impl<'src> Lexer<'src> {
     /// Create a new lexer for the given source code.
     /// 为给定的源代码创建新的词法分析器。
+    ///
+    /// A leading UTF-8 byte order mark is skipped; spans still count it, so
+    /// they remain byte offsets into `source`.
+    /// 跳过开头的 UTF-8 字节顺序标记；跨度仍将其计算在内，因此仍是 `source` 中的字节偏移。
     pub fn new(source: &'src str) -> Self {
-        Self {
+        let mut lexer = Self {
+            source,
             chars: source.char_indices().peekable(),
             pos: 0,
             diagnostics: Vec::new(),
             mode_stack: vec![LexerMode::Normal],
+        };
+        if source.starts_with('\u{FEFF}') {
+            lexer.advance();
         }
+        lexer
     }
 
     /// Get the current lexer mode.
@@ -77,7 +89,14 @@ impl<'src> Lexer<'src> {
         let mut tokens = Vec::new();
 
         loop {
-            let token = self.next_token();
+            let mut token = self.next_token();
+            // Same-line trivia after a token belongs to it, except inside
+            // string literals where whitespace is content.
+            // token 之后同一行的 trivia 归属于它，但字符串字面量内部的空白是内容。
+            if self.current_mode() != LexerMode::InInterpolatedString {
+                let trailing = self.lex_trivia(true);
+                token = token.with_trailing_trivia(trailing);
+            }
             let is_eof = token.kind == TokenKind::Eof;
             tokens.push(token);
             if is_eof {
@@ -129,9 +148,14 @@ impl<'src> Lexer<'src> {
     /// Get the next token in normal mode.
     /// 在正常模式下获取下一个 token。
     fn next_token_normal(&mut self) -> Token {
-        // Skip whitespace - 跳过空白字符
-        self.skip_whitespace();
+        // Collect whitespace and comments - 收集空白和注释
+        let leading = self.lex_trivia(false);
+        self.scan_token().with_leading_trivia(leading)
+    }
 
+    /// Scan a single token, with any trivia already consumed.
+    /// 扫描单个 token，trivia 已被消耗。
+    fn scan_token(&mut self) -> Token {
         let start = self.pos;
 
         // Check for end of file - 检查是否到达文件末尾
@@ -154,14 +178,9 @@ impl<'src> Lexer<'src> {
             '%' => TokenKind::Percent,
 
             // Record literal #{ - 记录字面量 #{
-            '#' => {
-                if self.peek_char() == Some('{') {
-                    self.advance();
-                    TokenKind::HashLBrace
-                } else {
-                    self.error_unexpected_char(ch, start);
-                    TokenKind::Error
-                }
+            '#' if self.peek_char() == Some('{') => {
+                self.advance();
+                TokenKind::HashLBrace
             }
 
             // Dot, DotDot, or path starting with ./ or ../
@@ -172,6 +191,9 @@ impl<'src> Lexer<'src> {
                     if self.peek_char() == Some('/') {
                         // Path starting with ../ - 以 ../ 开头的路径
                         self.scan_path(start, "..")
+                    } else if self.peek_char() == Some('=') {
+                        self.advance();
+                        TokenKind::DotDotEq
                     } else {
                         TokenKind::DotDot
                     }
@@ -196,31 +218,11 @@ impl<'src> Lexer<'src> {
                 }
             }
 
-            // Minus, Arrow, or Comment - 减号、箭头或注释
+            // Minus or Arrow - 减号或箭头
             '-' => {
                 if self.peek_char() == Some('>') {
                     self.advance();
                     TokenKind::Arrow
-                } else if self.peek_char() == Some('-') {
-                    // Could be line comment (-- ...) or block comment (-- -- ... -- --)
-                    // 可能是行注释 (-- ...) 或块注释 (-- -- ... -- --)
-                    self.advance(); // consume second -
-                    if self.peek_char() == Some(' ')
-                        && self.peek_nth(1) == Some('-')
-                        && self.peek_nth(2) == Some('-')
-                    {
-                        // Block comment: -- -- ... -- --
-                        // 块注释：-- -- ... -- --
-                        self.advance(); // skip space
-                        self.advance(); // skip -
-                        self.advance(); // skip -
-                        self.skip_block_comment();
-                    } else {
-                        // Line comment: -- to end of line
-                        // 行注释：-- 到行尾
-                        self.skip_line_comment();
-                    }
-                    return self.next_token();
                 } else {
                     TokenKind::Minus
                 }
@@ -235,9 +237,10 @@ impl<'src> Lexer<'src> {
                 if self.peek_char() == Some('/') {
                     self.advance();
                     TokenKind::SlashSlash
-                } else if Self::is_path_start_char(self.peek_char()) {
-                    // Absolute path starting with /
-                    // 以 / 开头的绝对路径
+                } else if Self::is_path_start_char(self.peek_char()) && !self.follows_operand(start)
+                {
+                    // Absolute path starting with /; `a/b` is division
+                    // 以 / 开头的绝对路径；`a/b` 是除法
                     self.scan_absolute_path()
                 } else {
                     TokenKind::Slash
@@ -267,11 +270,14 @@ impl<'src> Lexer<'src> {
                 }
             }
 
-            // Less than - 小于号
+            // Less than or generator arrow - 小于号或生成器箭头
             '<' => {
                 if self.peek_char() == Some('=') {
                     self.advance();
                     TokenKind::LtEq
+                } else if self.peek_char() == Some('-') {
+                    self.advance();
+                    TokenKind::LArrow
                 } else {
                     TokenKind::Lt
                 }
@@ -288,14 +294,9 @@ impl<'src> Lexer<'src> {
             }
 
             // Ampersand (logical and) - & 符号（逻辑与）
-            '&' => {
-                if self.peek_char() == Some('&') {
-                    self.advance();
-                    TokenKind::AndAnd
-                } else {
-                    self.error_unexpected_char(ch, start);
-                    TokenKind::Error
-                }
+            '&' if self.peek_char() == Some('&') => {
+                self.advance();
+                TokenKind::AndAnd
             }
 
             // Pipe - 管道符号
@@ -370,25 +371,79 @@ impl<'src> Lexer<'src> {
         self.chars.clone().nth(n).map(|(_, ch)| ch)
     }
 
-    /// Skip whitespace characters.
-    /// 跳过空白字符。
-    fn skip_whitespace(&mut self) {
-        while let Some(ch) = self.peek_char() {
-            if ch.is_whitespace() {
-                self.advance();
-            } else {
-                break;
-            }
+    /// Check whether the next character ends a line, treating `\r\n` as one
+    /// line terminator.
+    /// 检查下一个字符是否结束一行，将 `\r\n` 视为一个行终止符。
+    fn at_line_end(&mut self) -> bool {
+        match self.peek_char() {
+            Some('\n') => true,
+            Some('\r') => self.peek_nth(1) == Some('\n'),
+            _ => false,
+        }
+    }
+
+    /// Consume a character of string content, reading `\r\n` as `\n`.
+    /// 消耗一个字符串内容字符，将 `\r\n` 读作 `\n`。
+    fn push_string_char(&mut self, ch: char, value: &mut String) {
+        if ch == '\r' && self.peek_char() == Some('\n') {
+            return;
         }
+        value.push(ch);
+    }
+
+    /// Consume whitespace and comments as trivia.
+    /// 将空白和注释作为 trivia 消耗。
+    ///
+    /// With `stop_at_newline`, stops before the first newline so that only
+    /// trivia on the current line is taken.
+    /// 若 `stop_at_newline` 为真，则在第一个换行符之前停止，只获取当前行的 trivia。
+    fn lex_trivia(&mut self, stop_at_newline: bool) -> Vec<Trivia> {
+        let mut trivia = Vec::new();
+        loop {
+            let start = self.pos;
+            let kind = match self.peek_char() {
+                _ if stop_at_newline && self.at_line_end() => break,
+                Some(ch) if ch.is_whitespace() => {
+                    while let Some(ch) = self.peek_char() {
+                        if !ch.is_whitespace() || (stop_at_newline && self.at_line_end()) {
+                            break;
+                        }
+                        self.advance();
+                    }
+                    TriviaKind::Whitespace
+                }
+                Some('-') if self.peek_nth(1) == Some('-') => {
+                    self.advance();
+                    self.advance();
+                    if self.peek_char() == Some(' ')
+                        && self.peek_nth(1) == Some('-')
+                        && self.peek_nth(2) == Some('-')
+                    {
+                        // Block comment: -- -- ... -- --
+                        // 块注释：-- -- ... -- --
+                        self.advance(); // skip space
+                        self.advance(); // skip -
+                        self.advance(); // skip -
+                        self.skip_block_comment();
+                        TriviaKind::BlockComment
+                    } else {
+                        // Line comment: -- to end of line
+                        // 行注释：-- 到行尾
+                        self.skip_line_comment();
+                        TriviaKind::LineComment
+                    }
+                }
+                _ => break,
+            };
+            trivia.push(Trivia::new(kind, Span::from_usize(start, self.pos)));
+        }
+        trivia
     }
 
     /// Skip a line comment (-- to end of line).
     /// 跳过行注释（-- 到行尾）。
     fn skip_line_comment(&mut self) {
-        while let Some(ch) = self.peek_char() {
-            if ch == '\n' {
-                break;
-            }
+        while self.peek_char().is_some() && !self.at_line_end() {
             self.advance();
         }
     }
@@ -398,20 +453,18 @@ impl<'src> Lexer<'src> {
     fn skip_block_comment(&mut self) {
         loop {
             match self.advance() {
-                Some((_, '-')) => {
-                    if self.peek_char() == Some('-') {
-                        self.advance();
-                        // Check for closing: -- -- (space then --)
-                        // 检查结束标记：-- --（空格后跟 --）
-                        if self.peek_char() == Some(' ')
-                            && self.peek_nth(1) == Some('-')
-                            && self.peek_nth(2) == Some('-')
-                        {
-                            self.advance(); // skip space
-                            self.advance(); // skip -
-                            self.advance(); // skip -
-                            break;
-                        }
+                Some((_, '-')) if self.peek_char() == Some('-') => {
+                    self.advance();
+                    // Check for closing: -- -- (space then --)
+                    // 检查结束标记：-- --（空格后跟 --）
+                    if self.peek_char() == Some(' ')
+                        && self.peek_nth(1) == Some('-')
+                        && self.peek_nth(2) == Some('-')
+                    {
+                        self.advance(); // skip space
+                        self.advance(); // skip -
+                        self.advance(); // skip -
+                        break;
                     }
                 }
                 None => {
@@ -446,7 +499,7 @@ impl<'src> Lexer<'src> {
                         value.push(escaped);
                     }
                 }
-                Some((_, ch)) => value.push(ch),
+                Some((_, ch)) => self.push_string_char(ch, &mut value),
                 None => {
                     let span = Span::from_usize(start, self.pos);
                     self.diagnostics.push(
@@ -583,7 +636,7 @@ impl<'src> Lexer<'src> {
                 }
                 Some(ch) => {
                     self.advance();
-                    value.push(ch);
+                    self.push_string_char(ch, &mut value);
                 }
                 None => {
                     let span = Span::from_usize(start, self.pos);
@@ -799,6 +852,15 @@ impl<'src> Lexer<'src> {
         );
     }
 
+    /// Check whether the character just before `pos` ends an operand, such
+    /// as an identifier, number, literal or closing delimiter.
+    /// 检查 `pos` 之前的字符是否结束一个操作数，例如标识符、数字、字面量或右定界符。
+    fn follows_operand(&self, pos: usize) -> bool {
+        self.source[..pos].chars().next_back().is_some_and(|c| {
+            c.is_alphanumeric() || matches!(c, '_' | ')' | ']' | '}' | '"' | '\'' | '`')
+        })
+    }
+
     /// Check if a character can start a path component after /.
     /// 检查字符是否可以作为 / 后面路径组件的开始。
     fn is_path_start_char(ch: Option<char>) -> bool {
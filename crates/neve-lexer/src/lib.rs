@@ -8,4 +8,4 @@ mod lexer;
 mod token;
 
 pub use lexer::Lexer;
-pub use token::{Token, TokenKind};
+pub use token::{Token, TokenKind, Trivia, TriviaKind};
@@ -11,14 +11,90 @@ pub struct Token {
     pub kind: TokenKind,
     /// The source location - 源码位置
     pub span: Span,
+    /// Trivia between the previous token's trailing trivia and this token
+    /// 上一个 token 的尾随 trivia 与本 token 之间的 trivia
+    leading_trivia: Vec<Trivia>,
+    /// Trivia after this token up to the end of its line
+    /// 本 token 之后直到行尾的 trivia
+    trailing_trivia: Vec<Trivia>,
 }
 
 impl Token {
     /// Create a new token.
     /// 创建新的 Token。
     pub fn new(kind: TokenKind, span: Span) -> Self {
+        Self {
+            kind,
+            span,
+            leading_trivia: Vec::new(),
+            trailing_trivia: Vec::new(),
+        }
+    }
+
+    /// Attach leading trivia.
+    /// 附加前导 trivia。
+    pub fn with_leading_trivia(mut self, trivia: Vec<Trivia>) -> Self {
+        self.leading_trivia = trivia;
+        self
+    }
+
+    /// Attach trailing trivia.
+    /// 附加尾随 trivia。
+    pub fn with_trailing_trivia(mut self, trivia: Vec<Trivia>) -> Self {
+        self.trailing_trivia = trivia;
+        self
+    }
+
+    /// Whitespace and comments before this token.
+    /// 本 token 之前的空白和注释。
+    pub fn leading_trivia(&self) -> &[Trivia] {
+        &self.leading_trivia
+    }
+
+    /// Whitespace and comments after this token on the same line.
+    /// 本 token 之后同一行上的空白和注释。
+    pub fn trailing_trivia(&self) -> &[Trivia] {
+        &self.trailing_trivia
+    }
+}
+
+/// The kind of a trivia piece.
+/// trivia 片段的类型。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriviaKind {
+    /// Spaces, tabs and newlines - 空格、制表符和换行
+    Whitespace,
+    /// `-- ...` to end of line - `-- ...` 到行尾
+    LineComment,
+    /// `-- -- ... -- --` - 块注释
+    BlockComment,
+}
+
+/// Source text that carries no meaning for the parser: whitespace and comments.
+/// 对解析器没有意义的源文本：空白和注释。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trivia {
+    /// The kind of trivia - trivia 的类型
+    pub kind: TriviaKind,
+    /// The source location - 源码位置
+    pub span: Span,
+}
+
+impl Trivia {
+    /// Create a new trivia piece.
+    /// 创建新的 trivia 片段。
+    pub fn new(kind: TriviaKind, span: Span) -> Self {
         Self { kind, span }
     }
+
+    /// Whether this trivia is a comment.
+    /// 此 trivia 是否为注释。
+    pub fn is_comment(&self) -> bool {
+        matches!(
+            self.kind,
+            TriviaKind::LineComment | TriviaKind::BlockComment
+        )
+    }
 }
 
 /// The kind of a token.
@@ -116,9 +192,11 @@ pub enum TokenKind {
     Pipe,             // | - 管道/模式或
     PipeGt,           // |> - 管道操作符
     Arrow,            // -> - 箭头
+    LArrow,           // <- - 生成器箭头
     FatArrow,         // => - 粗箭头
     At,               // @ - 模式绑定
     DotDot,           // .. - 范围/展开
+    DotDotEq,         // ..= - 闭区间
     Question,         // ? - 错误传播
 
     // ===== Punctuation 标点 =====
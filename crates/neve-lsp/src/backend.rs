@@ -5,16 +5,22 @@
 //! 实现 Neve 的语言服务器协议。
 
 use dashmap::DashMap;
-use tower_lsp::jsonrpc::Result;
+use std::collections::HashMap;
+use std::sync::RwLock;
+use tower_lsp::jsonrpc::{Error, Result};
 use tower_lsp::lsp_types::*;
 use tower_lsp::{Client, LanguageServer};
 
+use neve_common::{BytePos, Span};
 use neve_lexer::Lexer;
 
 use crate::capabilities::server_capabilities;
 use crate::document::{DiagnosticSeverity as DocSeverity, Document};
+use crate::inlay_hints::InlayHintConfig;
+use crate::outline::{OutlineSymbol, document_outline};
 use crate::semantic_tokens::generate_semantic_tokens_with_context;
-use crate::symbol_index::SymbolKind as IndexSymbolKind;
+use crate::stdlib::{StdlibIndex, StdlibItem, member_receiver};
+use crate::symbol_index::{SymbolKind as IndexSymbolKind, imports_from, is_valid_identifier};
 
 /// The LSP backend.
 /// LSP 后端。
@@ -23,6 +29,10 @@ pub struct Backend {
     client: Client,
     /// Open documents. / 打开的文档。
     documents: DashMap<String, Document>,
+    /// Which inlay hints the client wants. / 客户端需要的内嵌提示。
+    inlay_hints: RwLock<InlayHintConfig>,
+    /// Standard library names for completion. / 用于补全的标准库名称。
+    stdlib: StdlibIndex,
 }
 
 impl Backend {
@@ -32,6 +42,8 @@ impl Backend {
         Self {
             client,
             documents: DashMap::new(),
+            inlay_hints: RwLock::new(InlayHintConfig::default()),
+            stdlib: StdlibIndex::load(),
         }
     }
 
@@ -73,11 +85,77 @@ impl Backend {
             .publish_diagnostics(uri.clone(), diagnostics, None)
             .await;
     }
+
+    /// Every occurrence of the symbol at `pos` in open documents, with
+    /// whether it is a binding: all of them in its own document and, for a
+    /// top-level symbol, the uses of its name that documents importing it
+    /// from this module do not define.
+    /// 打开的文档中 `pos` 处符号的每次出现，以及它是否为绑定：包括其所在文档中的全部出现，
+    /// 对于顶级符号，还包括从此模块导入它的文档中对该名称的、未在那些文档中定义的使用。
+    fn workspace_occurrences(&self, uri: &Url, pos: Position) -> Option<Vec<(Url, Range, bool)>> {
+        let (name, top_level, mut occurrences) = {
+            let doc = self.documents.get(uri.as_str())?;
+            let index = doc.symbol_index.as_ref()?;
+            let offset = doc.offset_at(pos.line, pos.character);
+            let reference = index.reference_at(offset)?;
+            let definition = reference.definition?;
+            let occurrences: Vec<(Url, Range, bool)> = index
+                .occurrences_at(offset)?
+                .into_iter()
+                .map(|r| (uri.clone(), span_range(&doc, r.span), r.is_write))
+                .collect();
+            (
+                reference.name.clone(),
+                index.is_top_level(definition),
+                occurrences,
+            )
+        };
+
+        if top_level && let Ok(module) = uri.to_file_path() {
+            for entry in self.documents.iter() {
+                let doc = entry.value();
+                if entry.key() == uri.as_str() {
+                    continue;
+                }
+                let (Ok(other), Some(index), Some(ast)) =
+                    (Url::parse(&doc.uri), &doc.symbol_index, &doc.ast)
+                else {
+                    continue;
+                };
+                if !imports_from(ast, &module, &name) {
+                    continue;
+                }
+                occurrences.extend(
+                    index
+                        .unresolved_references(&name)
+                        .into_iter()
+                        .map(|r| (other.clone(), span_range(doc, r.span), false)),
+                );
+            }
+        }
+
+        Some(occurrences)
+    }
+
+    /// The name of the member at `pos`, if some of its uses could belong to
+    /// other types, so that it cannot be renamed safely.
+    /// `pos` 处成员的名称（如果它的某些使用可能属于其他类型，因而无法安全重命名）。
+    fn ambiguous_member(&self, uri: &Url, pos: Position) -> Option<String> {
+        let doc = self.documents.get(uri.as_str())?;
+        let index = doc.symbol_index.as_ref()?;
+        let reference = index.reference_at(doc.offset_at(pos.line, pos.character))?;
+        index
+            .is_ambiguous_member(reference.definition?)
+            .then(|| reference.name.clone())
+    }
 }
 
 #[tower_lsp::async_trait]
 impl LanguageServer for Backend {
-    async fn initialize(&self, _: InitializeParams) -> Result<InitializeResult> {
+    async fn initialize(&self, params: InitializeParams) -> Result<InitializeResult> {
+        *self.inlay_hints.write().unwrap() =
+            InlayHintConfig::from_initialization_options(params.initialization_options.as_ref());
+
         Ok(InitializeResult {
             server_info: Some(ServerInfo {
                 name: "neve-lsp".to_string(),
@@ -168,6 +246,7 @@ impl LanguageServer for Backend {
                     IndexSymbolKind::Trait => "trait",
                     IndexSymbolKind::Field => "field",
                     IndexSymbolKind::Method => "method",
+                    IndexSymbolKind::Impl => "impl",
                 };
 
                 // Get the full definition text using full_span
@@ -253,9 +332,22 @@ impl LanguageServer for Backend {
             .and_then(|c| c.trigger_character.as_deref());
 
         // Check if we're after a dot (member access) / 检查是否在点后面（成员访问）
-        let is_dot_completion = trigger_char == Some(".");
-
-        if is_dot_completion {
+        let line_prefix = self.documents.get(&uri).map(|doc| {
+            let offset = doc
+                .offset_at(pos.line, pos.character)
+                .min(doc.content.len());
+            let line_start = doc.content[..offset].rfind('\n').map_or(0, |i| i + 1);
+            doc.content[line_start..offset].to_string()
+        });
+        let receiver = line_prefix.as_deref().and_then(member_receiver);
+        let is_dot_completion = trigger_char == Some(".") || receiver.is_some();
+
+        if let Some(namespace) = receiver
+            && !self.stdlib.members(namespace).is_empty()
+        {
+            // Members of a standard library namespace / 标准库命名空间的成员
+            items.extend(self.get_namespace_completions(namespace));
+        } else if is_dot_completion {
             // Method completion based on type / 基于类型的方法补全
             items.extend(self.get_method_completions());
         } else {
@@ -265,6 +357,23 @@ impl LanguageServer for Backend {
             // Standard library functions / 标准库函数
             items.extend(self.get_stdlib_completions());
 
+            // Standard library namespaces and any functions not listed above
+            // 标准库命名空间以及上面未列出的函数
+            let listed: std::collections::HashSet<String> =
+                items.iter().map(|item| item.label.clone()).collect();
+            items.extend(self.stdlib.namespaces().map(|namespace| CompletionItem {
+                label: namespace.to_string(),
+                kind: Some(CompletionItemKind::MODULE),
+                detail: Some("standard library".to_string()),
+                ..Default::default()
+            }));
+            items.extend(
+                self.stdlib
+                    .items()
+                    .filter(|item| !listed.contains(&item.path))
+                    .map(|item| stdlib_completion(item, &item.path)),
+            );
+
             // Types / 类型
             items.extend(self.get_type_completions());
 
@@ -301,6 +410,41 @@ impl LanguageServer for Backend {
         Ok(None)
     }
 
+    async fn range_formatting(
+        &self,
+        params: DocumentRangeFormattingParams,
+    ) -> Result<Option<Vec<TextEdit>>> {
+        let uri = params.text_document.uri.to_string();
+
+        if let Some(doc) = self.documents.get(&uri) {
+            let range = params.range;
+            let start = doc.offset_at(range.start.line, range.start.character);
+            let end = doc.offset_at(range.end.line, range.end.character);
+            if let Ok(edit) = neve_fmt::format_range(
+                &doc.content,
+                BytePos(start as u32),
+                BytePos(end as u32),
+                &neve_fmt::FormatConfig::default(),
+            ) && doc
+                .content
+                .get(edit.span.start.0 as usize..edit.span.end.0 as usize)
+                != Some(edit.text.as_str())
+            {
+                let (start_line, start_col) = doc.position_at(edit.span.start.0 as usize);
+                let (end_line, end_col) = doc.position_at(edit.span.end.0 as usize);
+                return Ok(Some(vec![TextEdit {
+                    range: Range {
+                        start: Position::new(start_line, start_col),
+                        end: Position::new(end_line, end_col),
+                    },
+                    new_text: edit.text,
+                }]));
+            }
+        }
+
+        Ok(None)
+    }
+
     async fn semantic_tokens_full(
         &self,
         params: SemanticTokensParams,
@@ -329,52 +473,47 @@ impl LanguageServer for Backend {
 
         if let Some(doc) = self.documents.get(&uri)
             && let Some(ref ast) = doc.ast
+            && let Some(ref index) = doc.symbol_index
         {
-            let mut symbols = Vec::new();
-
-            for item in &ast.items {
-                use neve_syntax::ItemKind;
-
-                let (name, kind) = match &item.kind {
-                    ItemKind::Let(def) => {
-                        // Extract name from pattern / 从模式中提取名称
-                        let name = format!("{:?}", def.pattern.kind);
-                        (name, SymbolKind::VARIABLE)
-                    }
-                    ItemKind::Fn(def) => (def.name.name.clone(), SymbolKind::FUNCTION),
-                    ItemKind::TypeAlias(def) => (def.name.name.clone(), SymbolKind::TYPE_PARAMETER),
-                    ItemKind::Struct(def) => (def.name.name.clone(), SymbolKind::STRUCT),
-                    ItemKind::Enum(def) => (def.name.name.clone(), SymbolKind::ENUM),
-                    ItemKind::Trait(def) => (def.name.name.clone(), SymbolKind::INTERFACE),
-                    ItemKind::Impl(_) => continue,
-                    ItemKind::Import(_) => continue,
-                };
+            let outline = document_outline(ast, index, &doc.content);
+            let symbols = outline
+                .iter()
+                .map(|node| document_symbol(&doc, node))
+                .collect();
+            return Ok(Some(DocumentSymbolResponse::Nested(symbols)));
+        }
 
-                let start: usize = item.span.start.into();
-                let end: usize = item.span.end.into();
-                let (start_line, start_col) = doc.position_at(start);
-                let (end_line, end_col) = doc.position_at(end);
+        Ok(None)
+    }
 
-                #[allow(deprecated)]
-                symbols.push(DocumentSymbol {
-                    name,
-                    detail: None,
-                    kind,
-                    tags: None,
-                    deprecated: None,
-                    range: Range {
-                        start: Position::new(start_line, start_col),
-                        end: Position::new(end_line, end_col),
-                    },
-                    selection_range: Range {
-                        start: Position::new(start_line, start_col),
-                        end: Position::new(end_line, end_col),
-                    },
-                    children: None,
-                });
-            }
+    async fn inlay_hint(&self, params: InlayHintParams) -> Result<Option<Vec<InlayHint>>> {
+        let uri = params.text_document.uri.to_string();
+        let config = *self.inlay_hints.read().unwrap();
 
-            return Ok(Some(DocumentSymbolResponse::Nested(symbols)));
+        if let Some(doc) = self.documents.get(&uri) {
+            let start = doc.offset_at(params.range.start.line, params.range.start.character);
+            let end = doc.offset_at(params.range.end.line, params.range.end.character);
+            let hints = doc
+                .type_hints
+                .iter()
+                .filter(|hint| {
+                    config.enabled(hint.kind) && start <= hint.offset && hint.offset <= end
+                })
+                .map(|hint| {
+                    let (line, col) = doc.position_at(hint.offset);
+                    InlayHint {
+                        position: Position::new(line, col),
+                        label: InlayHintLabel::String(format!(": {}", hint.ty)),
+                        kind: Some(InlayHintKind::TYPE),
+                        text_edits: None,
+                        tooltip: None,
+                        padding_left: None,
+                        padding_right: None,
+                        data: None,
+                    }
+                })
+                .collect();
+            return Ok(Some(hints));
         }
 
         Ok(None)
@@ -420,132 +559,106 @@ impl LanguageServer for Backend {
     }
 
     async fn references(&self, params: ReferenceParams) -> Result<Option<Vec<Location>>> {
-        let uri = params.text_document_position.text_document.uri.to_string();
+        let uri = params.text_document_position.text_document.uri;
         let pos = params.text_document_position.position;
         let include_declaration = params.context.include_declaration;
 
-        if let Some(doc) = self.documents.get(&uri)
-            && let Some(ref index) = doc.symbol_index
-        {
+        // Names defined elsewhere, such as builtins, are matched by name
+        // 在其他地方定义的名称（例如内置函数）按名称匹配
+        let occurrences = self.workspace_occurrences(&uri, pos).or_else(|| {
+            let doc = self.documents.get(uri.as_str())?;
+            let index = doc.symbol_index.as_ref()?;
             let offset = doc.offset_at(pos.line, pos.character);
-            let refs = index.find_references_at(offset, include_declaration);
-
-            if !refs.is_empty() {
-                let locations: Vec<Location> = refs
-                    .iter()
-                    .map(|r| {
-                        let start: usize = r.span.start.into();
-                        let end: usize = r.span.end.into();
-                        let (start_line, start_col) = doc.position_at(start);
-                        let (end_line, end_col) = doc.position_at(end);
-
-                        Location {
-                            uri: params.text_document_position.text_document.uri.clone(),
-                            range: Range {
-                                start: Position::new(start_line, start_col),
-                                end: Position::new(end_line, end_col),
-                            },
-                        }
-                    })
-                    .collect();
+            Some(
+                index
+                    .find_references_at(offset, true)
+                    .into_iter()
+                    .map(|r| (uri.clone(), span_range(&doc, r.span), r.is_write))
+                    .collect(),
+            )
+        });
+
+        let locations: Vec<Location> = occurrences
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|&(_, _, is_write)| include_declaration || !is_write)
+            .map(|(uri, range, _)| Location { uri, range })
+            .collect();
 
-                return Ok(Some(locations));
-            }
+        if locations.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(locations))
         }
-
-        Ok(None)
     }
 
     async fn rename(&self, params: RenameParams) -> Result<Option<WorkspaceEdit>> {
-        let uri = params.text_document_position.text_document.uri.to_string();
+        let uri = params.text_document_position.text_document.uri;
         let pos = params.text_document_position.position;
         let new_name = params.new_name;
 
-        if let Some(doc) = self.documents.get(&uri)
-            && let Some(ref index) = doc.symbol_index
-        {
-            let offset = doc.offset_at(pos.line, pos.character);
-
-            // Find the symbol name at this position / 在此位置查找符号名称
-            if let Some(name) = index.find_name_at(offset) {
-                // Check if this is a valid symbol that can be renamed
-                // 通过验证是否有定义来检查这是否是可以重命名的有效符号
-                if index.get_definitions(&name).is_none() {
-                    return Ok(None);
-                }
+        if !is_valid_identifier(&new_name) {
+            return Err(Error::invalid_params(format!(
+                "`{}` is not a valid identifier",
+                new_name
+            )));
+        }
 
-                // Get all references to this symbol / 获取此符号的所有引用
-                let refs = index.get_references(&name);
+        if let Some(name) = self.ambiguous_member(&uri, pos) {
+            return Err(Error::invalid_params(format!(
+                "cannot rename `{}`: some of its uses may belong to other types",
+                name
+            )));
+        }
 
-                if !refs.is_empty() {
-                    let edits: Vec<TextEdit> = refs
-                        .iter()
-                        .map(|r| {
-                            let start: usize = r.span.start.into();
-                            let end: usize = r.span.end.into();
-                            let (start_line, start_col) = doc.position_at(start);
-                            let (end_line, end_col) = doc.position_at(end);
+        let Some(occurrences) = self.workspace_occurrences(&uri, pos) else {
+            return Ok(None);
+        };
 
-                            TextEdit {
-                                range: Range {
-                                    start: Position::new(start_line, start_col),
-                                    end: Position::new(end_line, end_col),
-                                },
-                                new_text: new_name.clone(),
-                            }
-                        })
-                        .collect();
-
-                    let mut changes = std::collections::HashMap::new();
-                    changes.insert(
-                        params.text_document_position.text_document.uri.clone(),
-                        edits,
-                    );
-
-                    return Ok(Some(WorkspaceEdit {
-                        changes: Some(changes),
-                        document_changes: None,
-                        change_annotations: None,
-                    }));
-                }
-            }
+        let mut changes: HashMap<Url, Vec<TextEdit>> = HashMap::new();
+        for (uri, range, _) in occurrences {
+            changes.entry(uri).or_default().push(TextEdit {
+                range,
+                new_text: new_name.clone(),
+            });
         }
 
-        Ok(None)
+        Ok(Some(WorkspaceEdit {
+            changes: Some(changes),
+            document_changes: None,
+            change_annotations: None,
+        }))
     }
 
     async fn prepare_rename(
         &self,
         params: TextDocumentPositionParams,
     ) -> Result<Option<PrepareRenameResponse>> {
-        let uri = params.text_document.uri.to_string();
+        // Members with uses on unknown types cannot be renamed safely
+        // 在未知类型上有使用的成员无法安全重命名
         let pos = params.position;
+        if self
+            .ambiguous_member(&params.text_document.uri, pos)
+            .is_some()
+        {
+            return Ok(None);
+        }
 
+        let uri = params.text_document.uri.to_string();
         if let Some(doc) = self.documents.get(&uri)
             && let Some(ref index) = doc.symbol_index
         {
             let offset = doc.offset_at(pos.line, pos.character);
 
-            // Find the symbol at this position / 在此位置查找符号
-            if let Some(name) = index.find_name_at(offset) {
-                // Find the reference at this position to get its span
-                // 查找此位置的引用以获取其范围
-                for r in &index.references {
-                    let start: usize = r.span.start.into();
-                    let end: usize = r.span.end.into();
-                    if start <= offset && offset < end {
-                        let (start_line, start_col) = doc.position_at(start);
-                        let (end_line, end_col) = doc.position_at(end);
-
-                        return Ok(Some(PrepareRenameResponse::RangeWithPlaceholder {
-                            range: Range {
-                                start: Position::new(start_line, start_col),
-                                end: Position::new(end_line, end_col),
-                            },
-                            placeholder: name,
-                        }));
-                    }
-                }
+            // Only symbols defined in this document can be renamed
+            // 只有在此文档中定义的符号才能重命名
+            if let Some(r) = index.reference_at(offset)
+                && r.definition.is_some()
+            {
+                return Ok(Some(PrepareRenameResponse::RangeWithPlaceholder {
+                    range: span_range(&doc, r.span),
+                    placeholder: r.name.clone(),
+                }));
             }
         }
 
@@ -1020,8 +1133,8 @@ impl Backend {
             (
                 "assert",
                 "Assert condition",
-                "assert(${1:cond}, ${2:msg})",
-                "Unit",
+                "assert(${1:cond}, ${2:msg}, ${3:value})",
+                "T",
             ),
             ("force", "Force lazy value", "force(${1:lazy_expr})", "T"),
         ];
@@ -1039,6 +1152,16 @@ impl Backend {
             .collect()
     }
 
+    /// Get completions for the members of a standard library namespace.
+    /// 获取标准库命名空间成员的补全。
+    fn get_namespace_completions(&self, namespace: &str) -> Vec<CompletionItem> {
+        self.stdlib
+            .members(namespace)
+            .iter()
+            .map(|item| stdlib_completion(item, &item.name))
+            .collect()
+    }
+
     /// Get type completions.
     /// 获取类型补全。
     fn get_type_completions(&self) -> Vec<CompletionItem> {
@@ -1202,6 +1325,7 @@ impl Backend {
                         IndexSymbolKind::Trait => CompletionItemKind::INTERFACE,
                         IndexSymbolKind::Field => CompletionItemKind::FIELD,
                         IndexSymbolKind::Method => CompletionItemKind::METHOD,
+                        IndexSymbolKind::Impl => CompletionItemKind::MODULE,
                     };
 
                     items.push(CompletionItem {
@@ -1218,6 +1342,56 @@ impl Backend {
     }
 }
 
+/// The LSP range of a span in a document.
+/// 文档中某个范围对应的 LSP 范围。
+fn span_range(doc: &Document, span: Span) -> Range {
+    let (start_line, start_col) = doc.position_at(span.start.into());
+    let (end_line, end_col) = doc.position_at(span.end.into());
+    Range {
+        start: Position::new(start_line, start_col),
+        end: Position::new(end_line, end_col),
+    }
+}
+
+/// A completion inserting a standard library item as `label`.
+/// 以 `label` 插入标准库项的补全。
+fn stdlib_completion(item: &StdlibItem, label: &str) -> CompletionItem {
+    CompletionItem {
+        label: label.to_string(),
+        kind: Some(if item.is_function {
+            CompletionItemKind::FUNCTION
+        } else {
+            CompletionItemKind::CONSTANT
+        }),
+        detail: Some(item.signature()),
+        insert_text: Some(item.snippet(label)),
+        insert_text_format: Some(InsertTextFormat::SNIPPET),
+        ..Default::default()
+    }
+}
+
+/// Convert an outline node and its children to an LSP document symbol.
+/// 将大纲节点及其子节点转换为 LSP 文档符号。
+fn document_symbol(doc: &Document, node: &OutlineSymbol) -> DocumentSymbol {
+    let children: Vec<DocumentSymbol> = node
+        .children
+        .iter()
+        .map(|child| document_symbol(doc, child))
+        .collect();
+
+    #[allow(deprecated)]
+    DocumentSymbol {
+        name: node.name.clone(),
+        detail: None,
+        kind: convert_symbol_kind(node.kind),
+        tags: None,
+        deprecated: None,
+        range: span_range(doc, node.span),
+        selection_range: span_range(doc, node.selection_span),
+        children: (!children.is_empty()).then_some(children),
+    }
+}
+
 /// Helper function to convert symbol kind.
 /// 转换符号类型的辅助函数。
 fn convert_symbol_kind(kind: IndexSymbolKind) -> SymbolKind {
@@ -1232,5 +1406,6 @@ fn convert_symbol_kind(kind: IndexSymbolKind) -> SymbolKind {
         IndexSymbolKind::Trait => SymbolKind::INTERFACE,
         IndexSymbolKind::Field => SymbolKind::FIELD,
         IndexSymbolKind::Method => SymbolKind::METHOD,
+        IndexSymbolKind::Impl => SymbolKind::OBJECT,
     }
 }
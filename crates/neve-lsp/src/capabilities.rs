@@ -60,6 +60,12 @@ pub fn server_capabilities() -> ServerCapabilities {
         // Document formatting / 文档格式化
         document_formatting_provider: Some(OneOf::Left(true)),
 
+        // Range formatting / 范围格式化
+        document_range_formatting_provider: Some(OneOf::Left(true)),
+
+        // Inlay hints / 内嵌提示
+        inlay_hint_provider: Some(OneOf::Left(true)),
+
         // Workspace symbol / 工作区符号
         workspace_symbol_provider: Some(OneOf::Left(true)),
 
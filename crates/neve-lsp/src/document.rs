@@ -8,8 +8,9 @@ use neve_common::Span;
 use neve_hir::{Module, lower};
 use neve_parser::parse;
 use neve_syntax::SourceFile;
-use neve_typeck::check;
+use neve_typeck::TypeChecker;
 
+use crate::inlay_hints::{TypeHint, type_hints};
 use crate::symbol_index::SymbolIndex;
 
 /// A document being edited.
@@ -28,6 +29,8 @@ pub struct Document {
     pub symbol_index: Option<SymbolIndex>,
     /// Diagnostics for this document. / 此文档的诊断信息。
     pub diagnostics: Vec<Diagnostic>,
+    /// Inferred types to show as inlay hints. / 作为内嵌提示显示的推断类型。
+    pub type_hints: Vec<TypeHint>,
 }
 
 /// A diagnostic message.
@@ -93,6 +96,7 @@ impl Document {
             hir: None,
             symbol_index: None,
             diagnostics: Vec::new(),
+            type_hints: Vec::new(),
         };
         doc.analyze();
         doc
@@ -131,8 +135,10 @@ impl Document {
         self.hir = Some(hir.clone());
 
         // Type checking / 类型检查
-        let type_diagnostics = check(&hir);
-        for diag in type_diagnostics {
+        let mut checker = TypeChecker::new();
+        checker.check(&hir);
+        self.type_hints = type_hints(&ast, &checker);
+        for diag in checker.diagnostics() {
             self.diagnostics.push(Diagnostic {
                 span: diag.span,
                 message: diag.message.clone(),
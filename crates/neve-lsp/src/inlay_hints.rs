@@ -0,0 +1,178 @@
+//! Inlay hints showing inferred types.
+//! 显示推断类型的内嵌提示。
+//!
+//! A hint follows each `let` binding and parameter written without a type
+//! annotation. Types that are not fully inferred are not shown.
+//! 每个未写类型注解的 `let` 绑定和参数后面都有一个提示。未完全推断的类型不会显示。
+
+use neve_hir::{Ty, TyKind};
+use neve_syntax::{Expr, ExprKind, ItemKind, PatternKind, SourceFile, StmtKind, TypeKind};
+use neve_typeck::{TypeChecker, format_type};
+use serde::Deserialize;
+
+/// What a type hint annotates.
+/// 类型提示所注解的对象。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TypeHintKind {
+    /// A `let` binding. / `let` 绑定。
+    Binding,
+    /// A function or lambda parameter. / 函数或 lambda 参数。
+    Parameter,
+}
+
+/// An inferred type to show after a binding.
+/// 显示在绑定之后的推断类型。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeHint {
+    /// Byte offset just past the pattern. / 紧跟在模式之后的字节偏移量。
+    pub offset: usize,
+    /// The formatted type. / 格式化后的类型。
+    pub ty: String,
+    /// What the hint annotates. / 提示所注解的对象。
+    pub kind: TypeHintKind,
+}
+
+/// Which kinds of hints to show, read from the client's initialization
+/// options as `{ "inlayHints": { "bindingTypes": bool, "parameterTypes": bool } }`.
+/// 要显示哪些类型的提示，从客户端的初始化选项
+/// `{ "inlayHints": { "bindingTypes": bool, "parameterTypes": bool } }` 中读取。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct InlayHintConfig {
+    /// Show types of `let` bindings. / 显示 `let` 绑定的类型。
+    pub binding_types: bool,
+    /// Show types of parameters. / 显示参数的类型。
+    pub parameter_types: bool,
+}
+
+impl Default for InlayHintConfig {
+    fn default() -> Self {
+        Self {
+            binding_types: true,
+            parameter_types: true,
+        }
+    }
+}
+
+impl InlayHintConfig {
+    /// Read the configuration from initialization options, enabling every
+    /// kind that is not mentioned.
+    /// 从初始化选项读取配置，未提及的类型均启用。
+    pub fn from_initialization_options(options: Option<&serde_json::Value>) -> Self {
+        options
+            .and_then(|options| options.get("inlayHints"))
+            .and_then(|hints| serde_json::from_value(hints.clone()).ok())
+            .unwrap_or_default()
+    }
+
+    /// Whether hints of this kind are shown.
+    /// 是否显示此类提示。
+    pub fn enabled(&self, kind: TypeHintKind) -> bool {
+        match kind {
+            TypeHintKind::Binding => self.binding_types,
+            TypeHintKind::Parameter => self.parameter_types,
+        }
+    }
+}
+
+/// Collect the type hints of a checked document, in source order.
+/// 收集已检查文档的类型提示，按源码顺序排列。
+pub fn type_hints(ast: &SourceFile, checker: &TypeChecker) -> Vec<TypeHint> {
+    let mut collector = HintCollector {
+        checker,
+        hints: Vec::new(),
+    };
+    for item in &ast.items {
+        match &item.kind {
+            ItemKind::Let(def) => {
+                if def.ty.is_none() && matches!(def.pattern.kind, PatternKind::Var(_)) {
+                    let ty = checker.item_types().get(&item.span).cloned();
+                    collector.push(def.pattern.span.end.into(), ty, TypeHintKind::Binding);
+                }
+                collector.expr(&def.value);
+            }
+            ItemKind::Fn(def) => {
+                for param in &def.params {
+                    if matches!(param.ty.kind, TypeKind::Infer) {
+                        let ty = checker.binding_type(param.pattern.span);
+                        collector.push(param.pattern.span.end.into(), ty, TypeHintKind::Parameter);
+                    }
+                }
+                collector.expr(&def.body);
+            }
+            ItemKind::Impl(def) => {
+                for method in &def.items {
+                    collector.expr(&method.body);
+                }
+            }
+            _ => {}
+        }
+    }
+    collector.hints.sort_by_key(|hint| hint.offset);
+    collector.hints
+}
+
+struct HintCollector<'a> {
+    checker: &'a TypeChecker,
+    hints: Vec<TypeHint>,
+}
+
+impl HintCollector<'_> {
+    fn expr(&mut self, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::Block { stmts, .. } => {
+                for stmt in stmts {
+                    if let StmtKind::Let {
+                        pattern, ty: None, ..
+                    } = &stmt.kind
+                        && matches!(pattern.kind, PatternKind::Var(_))
+                    {
+                        let ty = self.checker.binding_type(stmt.span);
+                        self.push(pattern.span.end.into(), ty, TypeHintKind::Binding);
+                    }
+                }
+            }
+            ExprKind::Lambda { params, .. } => {
+                for param in params.iter().filter(|param| param.ty.is_none()) {
+                    let ty = self.checker.binding_type(param.pattern.span);
+                    self.push(param.pattern.span.end.into(), ty, TypeHintKind::Parameter);
+                }
+            }
+            _ => {}
+        }
+        for child in expr.children() {
+            self.expr(child);
+        }
+    }
+
+    fn push(&mut self, offset: usize, ty: Option<Ty>, kind: TypeHintKind) {
+        if let Some(ty) = ty.filter(is_inferred) {
+            self.hints.push(TypeHint {
+                offset,
+                ty: format_type(&ty),
+                kind,
+            });
+        }
+    }
+}
+
+/// Whether a type has no inference variables or unknown parts left.
+/// 类型中是否不再有推断变量或未知部分。
+fn is_inferred(ty: &Ty) -> bool {
+    match &ty.kind {
+        TyKind::Var(_) | TyKind::Unknown => false,
+        TyKind::Int
+        | TyKind::Float
+        | TyKind::Bool
+        | TyKind::Char
+        | TyKind::String
+        | TyKind::Unit
+        | TyKind::Param(..) => true,
+        TyKind::Named(_, args) | TyKind::Tuple(args) => args.iter().all(is_inferred),
+        TyKind::Fn(params, ret) => params.iter().all(is_inferred) && is_inferred(ret),
+        TyKind::Record(fields) | TyKind::OpenRecord(fields, _) => {
+            fields.iter().all(|(_, ty)| is_inferred(ty))
+        }
+        TyKind::Forall(_, inner) => is_inferred(inner),
+    }
+}
@@ -8,6 +8,8 @@
 //! - Diagnostics (parse and type errors) / 诊断（解析和类型错误）
 //! - Hover information / 悬停信息
 //! - Go to definition / 跳转到定义
+//! - Document outline / 文档大纲
+//! - Inlay hints for inferred types / 推断类型的内嵌提示
 //! - Code completion / 代码补全
 //! - Formatting / 格式化
 
@@ -15,16 +17,24 @@ mod backend;
 mod capabilities;
 
 pub mod document;
+pub mod inlay_hints;
+pub mod outline;
 pub mod semantic_tokens;
+pub mod stdlib;
 pub mod symbol_index;
 
 pub use backend::Backend;
 pub use document::{Diagnostic, DiagnosticSeverity, Document};
+pub use inlay_hints::{InlayHintConfig, TypeHint, TypeHintKind, type_hints};
+pub use outline::{OutlineSymbol, document_outline};
 pub use semantic_tokens::{
     comment_token_type, generate_semantic_tokens, generate_semantic_tokens_with_context,
     parameter_token_type, token_modifiers, token_types,
 };
-pub use symbol_index::{Symbol, SymbolIndex, SymbolKind, SymbolRef};
+pub use stdlib::{StdlibIndex, StdlibItem, member_receiver};
+pub use symbol_index::{
+    Symbol, SymbolIndex, SymbolKind, SymbolRef, imports_from, is_valid_identifier,
+};
 
 use tower_lsp::{LspService, Server};
 
@@ -0,0 +1,177 @@
+//! Document outline for the editor's symbol tree.
+//! 用于编辑器符号树的文档大纲。
+//!
+//! Types and traits are parents of their fields, variants and methods.
+//! Methods of an impl block are nested under the type it implements when
+//! that type is defined in the document, and under the impl block itself
+//! otherwise.
+//! 类型和 trait 是其字段、变体和方法的父节点。当 impl 块所实现的类型定义在文档中时，
+//! 其方法嵌套在该类型之下，否则嵌套在 impl 块本身之下。
+
+use neve_common::Span;
+use neve_syntax::{ImplDef, ItemKind, SourceFile, Type, TypeKind};
+
+use crate::symbol_index::{SymbolIndex, SymbolKind};
+
+/// A node of the document outline.
+/// 文档大纲的节点。
+#[derive(Debug, Clone)]
+pub struct OutlineSymbol {
+    /// The name shown in the outline. / 大纲中显示的名称。
+    pub name: String,
+    /// The kind of symbol. / 符号的类型。
+    pub kind: SymbolKind,
+    /// The span of the whole definition. / 整个定义的范围。
+    pub span: Span,
+    /// The span selected when the symbol is picked, usually its name.
+    /// 选中符号时选择的范围，通常是其名称。
+    pub selection_span: Span,
+    /// Nested symbols. / 嵌套的符号。
+    pub children: Vec<OutlineSymbol>,
+}
+
+impl OutlineSymbol {
+    fn leaf(name: &str, kind: SymbolKind, span: Span, selection_span: Span) -> Self {
+        Self {
+            name: name.to_string(),
+            kind,
+            span,
+            selection_span,
+            children: Vec::new(),
+        }
+    }
+}
+
+/// Build the outline of a document from its AST and symbol index.
+/// 根据文档的 AST 和符号索引构建其大纲。
+pub fn document_outline(ast: &SourceFile, index: &SymbolIndex, source: &str) -> Vec<OutlineSymbol> {
+    let mut outline = Vec::new();
+    let mut impls = Vec::new();
+
+    for item in &ast.items {
+        match &item.kind {
+            ItemKind::Let(def) => {
+                // One leaf per variable the pattern binds
+                // 模式绑定的每个变量对应一个叶子
+                let mut vars: Vec<_> = index
+                    .definitions
+                    .values()
+                    .flatten()
+                    .filter(|sym| {
+                        sym.kind == SymbolKind::Variable
+                            && def.pattern.span.start <= sym.def_span.start
+                            && sym.def_span.end <= def.pattern.span.end
+                    })
+                    .collect();
+                vars.sort_by_key(|sym| sym.def_span.start);
+                outline.extend(vars.into_iter().map(|sym| {
+                    OutlineSymbol::leaf(&sym.name, SymbolKind::Variable, item.span, sym.def_span)
+                }));
+            }
+            ItemKind::Fn(def) => outline.push(OutlineSymbol::leaf(
+                &def.name.name,
+                SymbolKind::Function,
+                item.span,
+                def.name.span,
+            )),
+            ItemKind::TypeAlias(def) => outline.push(OutlineSymbol::leaf(
+                &def.name.name,
+                SymbolKind::TypeAlias,
+                item.span,
+                def.name.span,
+            )),
+            ItemKind::Struct(def) => {
+                let mut node = OutlineSymbol::leaf(
+                    &def.name.name,
+                    SymbolKind::Struct,
+                    item.span,
+                    def.name.span,
+                );
+                node.children = def
+                    .fields
+                    .iter()
+                    .map(|f| {
+                        OutlineSymbol::leaf(&f.name.name, SymbolKind::Field, f.span, f.name.span)
+                    })
+                    .collect();
+                outline.push(node);
+            }
+            ItemKind::Enum(def) => {
+                let mut node =
+                    OutlineSymbol::leaf(&def.name.name, SymbolKind::Enum, item.span, def.name.span);
+                node.children = def
+                    .variants
+                    .iter()
+                    .map(|v| {
+                        OutlineSymbol::leaf(&v.name.name, SymbolKind::Variant, v.span, v.name.span)
+                    })
+                    .collect();
+                outline.push(node);
+            }
+            ItemKind::Trait(def) => {
+                let mut node = OutlineSymbol::leaf(
+                    &def.name.name,
+                    SymbolKind::Trait,
+                    item.span,
+                    def.name.span,
+                );
+                node.children = def
+                    .items
+                    .iter()
+                    .map(|m| {
+                        OutlineSymbol::leaf(&m.name.name, SymbolKind::Method, m.span, m.name.span)
+                    })
+                    .collect();
+                outline.push(node);
+            }
+            ItemKind::Impl(def) => impls.push((item.span, def)),
+            ItemKind::Import(_) => {}
+        }
+    }
+
+    for (span, def) in impls {
+        let methods = def
+            .items
+            .iter()
+            .map(|m| OutlineSymbol::leaf(&m.name.name, SymbolKind::Method, m.span, m.name.span));
+        let target = type_name(&def.target);
+        match outline.iter_mut().find(|node| {
+            matches!(node.kind, SymbolKind::Struct | SymbolKind::Enum)
+                && Some(node.name.as_str()) == target
+        }) {
+            Some(node) => node.children.extend(methods),
+            None => {
+                let mut node = OutlineSymbol::leaf(
+                    &impl_header(def, source),
+                    SymbolKind::Impl,
+                    span,
+                    def.target.span,
+                );
+                node.children = methods.collect();
+                outline.push(node);
+            }
+        }
+    }
+
+    outline.sort_by_key(|node| node.span.start);
+    outline
+}
+
+/// The name of a named type, without its arguments.
+/// 命名类型的名称，不含其参数。
+pub(crate) fn type_name(ty: &Type) -> Option<&str> {
+    match &ty.kind {
+        TypeKind::Named { path, .. } => path.last().map(|part| part.name.as_str()),
+        _ => None,
+    }
+}
+
+/// The header of an impl block as written, e.g. `impl Show for List<Int>`.
+/// 按原文写出的 impl 块头，例如 `impl Show for List<Int>`。
+fn impl_header(def: &ImplDef, source: &str) -> String {
+    let text = |ty: &Type| source.get(ty.span.range()).unwrap_or("_").to_string();
+    match &def.trait_ {
+        Some(trait_) => format!("impl {} for {}", text(trait_), text(&def.target)),
+        None => format!("impl {}", text(&def.target)),
+    }
+}
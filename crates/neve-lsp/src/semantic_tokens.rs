@@ -231,6 +231,7 @@ fn classify_token_with_context(token: &Token, ctx: &ClassifyContext) -> Option<(
         | TokenKind::Pipe
         | TokenKind::PipeGt
         | TokenKind::Arrow
+        | TokenKind::LArrow
         | TokenKind::FatArrow
         | TokenKind::PlusPlus
         | TokenKind::SlashSlash
@@ -0,0 +1,128 @@
+//! Standard library names for code completion.
+//! 用于代码补全的标准库名称。
+//!
+//! Built-in bindings are named `namespace.member`, such as `list.head` or
+//! `Map.insert`; they are loaded once and grouped by namespace.
+//! 内置绑定的名称形如 `namespace.member`，例如 `list.head` 或 `Map.insert`；
+//! 它们只加载一次并按命名空间分组。
+
+use neve_eval::Value;
+use std::collections::BTreeMap;
+
+/// A standard library binding.
+/// 标准库绑定。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StdlibItem {
+    /// The name within its namespace, e.g. `head`. / 命名空间内的名称，例如 `head`。
+    pub name: String,
+    /// The full name, e.g. `list.head`. / 完整名称，例如 `list.head`。
+    pub path: String,
+    /// Whether the binding is a function. / 绑定是否为函数。
+    pub is_function: bool,
+    /// The number of arguments, if known. / 参数数量（如果已知）。
+    pub arity: Option<usize>,
+}
+
+impl StdlibItem {
+    /// A snippet calling the item with a placeholder per argument, or its
+    /// name if it is not a function.
+    /// 为每个参数使用一个占位符调用该项的代码片段；如果不是函数则为其名称。
+    pub fn snippet(&self, name: &str) -> String {
+        match (self.is_function, self.arity) {
+            (false, _) => name.to_string(),
+            (true, None) => format!("{}($0)", name),
+            (true, Some(arity)) => {
+                let args: Vec<String> = (1..=arity)
+                    .map(|i| format!("${{{}:arg{}}}", i, i))
+                    .collect();
+                format!("{}({})", name, args.join(", "))
+            }
+        }
+    }
+
+    /// A signature such as `list.take(arg1, arg2)`.
+    /// 形如 `list.take(arg1, arg2)` 的签名。
+    pub fn signature(&self) -> String {
+        match (self.is_function, self.arity) {
+            (true, Some(arity)) => {
+                let args: Vec<String> = (1..=arity).map(|i| format!("arg{}", i)).collect();
+                format!("{}({})", self.path, args.join(", "))
+            }
+            (true, None) => format!("{}(...)", self.path),
+            (false, _) => self.path.clone(),
+        }
+    }
+}
+
+/// Standard library bindings indexed by namespace.
+/// 按命名空间索引的标准库绑定。
+#[derive(Debug, Default)]
+pub struct StdlibIndex {
+    namespaces: BTreeMap<String, Vec<StdlibItem>>,
+}
+
+impl StdlibIndex {
+    /// Load the bindings of `neve_std::stdlib()`.
+    /// 加载 `neve_std::stdlib()` 的绑定。
+    pub fn load() -> Self {
+        let mut namespaces: BTreeMap<String, Vec<StdlibItem>> = BTreeMap::new();
+        for (path, value) in neve_std::stdlib() {
+            let Some((namespace, name)) = path.split_once('.') else {
+                continue;
+            };
+            let (is_function, arity) = match &value {
+                Value::Builtin(builtin) => (true, Some(builtin.arity)),
+                Value::PartialBuiltin(partial) => (true, Some(partial.remaining())),
+                Value::BuiltinFn(..)
+                | Value::Constructor(_)
+                | Value::Closure { .. }
+                | Value::AstClosure(_) => (true, None),
+                _ => (false, None),
+            };
+            namespaces
+                .entry(namespace.to_string())
+                .or_default()
+                .push(StdlibItem {
+                    name: name.to_string(),
+                    path: path.to_string(),
+                    is_function,
+                    arity,
+                });
+        }
+        for items in namespaces.values_mut() {
+            items.sort_by(|a, b| a.name.cmp(&b.name));
+        }
+        Self { namespaces }
+    }
+
+    /// The namespaces, in order.
+    /// 按顺序排列的命名空间。
+    pub fn namespaces(&self) -> impl Iterator<Item = &str> {
+        self.namespaces.keys().map(String::as_str)
+    }
+
+    /// The members of a namespace, in order.
+    /// 按顺序排列的命名空间成员。
+    pub fn members(&self, namespace: &str) -> &[StdlibItem] {
+        self.namespaces
+            .get(namespace)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Every binding, ordered by full name.
+    /// 所有绑定，按完整名称排序。
+    pub fn items(&self) -> impl Iterator<Item = &StdlibItem> {
+        self.namespaces.values().flatten()
+    }
+}
+
+/// The identifier before the `.` when the cursor ends `text` in a member
+/// access such as `list.` or `list.he`.
+/// 当光标位于 `text` 末尾的成员访问（如 `list.` 或 `list.he`）中时，返回 `.` 之前的标识符。
+pub fn member_receiver(text: &str) -> Option<&str> {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    let before_member = text.trim_end_matches(is_ident).strip_suffix('.')?;
+    let receiver = &before_member[before_member.trim_end_matches(is_ident).len()..];
+    (!receiver.is_empty()).then_some(receiver)
+}
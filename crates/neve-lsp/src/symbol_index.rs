@@ -1,14 +1,22 @@
 //! Symbol indexing for LSP features like go-to-definition and find references.
 //! 用于跳转到定义和查找引用等 LSP 功能的符号索引。
 //!
-//! Builds an index of all symbols and references in a document.
-//! 构建文档中所有符号和引用的索引。
+//! Builds an index of all symbols and references in a document. Every
+//! reference is resolved to the definition it names, following the scopes
+//! of locals. Fields and methods are resolved by the declared type of their
+//! receiver, or by name when only one type defines them.
+//! 构建文档中所有符号和引用的索引。每个引用都按局部变量的作用域解析到它所指的定义。
+//! 字段和方法按其接收者的声明类型解析；当只有一个类型定义它们时按名称解析。
 
+use crate::outline::type_name;
 use neve_common::Span;
+use neve_hir::{is_variant_pattern_name, variant_names};
+use neve_lexer::{Lexer, TokenKind};
 use neve_syntax::{
-    Expr, ExprKind, Item, ItemKind, Pattern, PatternKind, SourceFile, Stmt, StmtKind,
+    Expr, ExprKind, ImportItems, Item, ItemKind, Pattern, PatternKind, SourceFile, Stmt, StmtKind,
 };
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 
 /// A symbol in the source code.
 /// 源代码中的符号。
@@ -49,6 +57,8 @@ pub enum SymbolKind {
     Field,
     /// Method. / 方法。
     Method,
+    /// Impl block, only used in document outlines. / Impl 块，仅用于文档大纲。
+    Impl,
 }
 
 /// A reference to a symbol.
@@ -62,6 +72,10 @@ pub struct SymbolRef {
     /// Whether this is a write (definition) or read (usage).
     /// 这是写入（定义）还是读取（使用）。
     pub is_write: bool,
+    /// The name span of the definition this resolves to, if it is defined
+    /// in this document.
+    /// 此引用解析到的定义的名称范围（如果定义在此文档中）。
+    pub definition: Option<Span>,
 }
 
 /// Index of all symbols and references in a document.
@@ -75,6 +89,28 @@ pub struct SymbolIndex {
     /// Scope-aware symbol table for local variable resolution.
     /// 用于局部变量解析的作用域感知符号表。
     scopes: Vec<HashMap<String, Symbol>>,
+    /// Top-level definitions, visible throughout the document.
+    /// 顶级定义，在整个文档中可见。
+    globals: HashMap<String, Span>,
+    /// Fields and methods by name, with the type or trait owning each; the
+    /// members of one owner share the first one's definition.
+    /// 按名称索引的字段和方法，以及拥有它们的类型或 trait；同一所有者的成员共享第一个成员的定义。
+    members: HashMap<String, Vec<(String, Span)>>,
+    /// Types and the traits they implement in this document.
+    /// 此文档中的类型及其实现的 trait。
+    trait_impls: HashSet<(String, String)>,
+    /// Declared type names of locals, by definition span.
+    /// 局部变量的声明类型名称，按定义范围索引。
+    local_types: HashMap<Span, String>,
+    /// Member names with a use that could belong to more than one owner.
+    /// 存在可能属于多个所有者的使用的成员名称。
+    ambiguous: HashSet<String>,
+    /// Indices of references resolved once the whole document is indexed.
+    /// 在整个文档索引完成后才解析的引用的索引。
+    pending: Vec<(usize, Pending)>,
+    /// Enum variants of the document, which bare names in patterns match.
+    /// 文档的枚举变体，模式中的裸名称会匹配它们。
+    variants: HashSet<String>,
 }
 
 impl SymbolIndex {
@@ -88,41 +124,99 @@ impl SymbolIndex {
     /// 从 AST 构建符号索引。
     pub fn from_ast(ast: &SourceFile) -> Self {
         let mut index = Self::new();
+        index.variants = variant_names(ast);
         index.index_source_file(ast);
+        index.resolve_pending();
         index
     }
 
+    /// The reference or binding occurrence at the given offset.
+    /// 给定偏移量处的引用或绑定。
+    pub fn reference_at(&self, offset: usize) -> Option<&SymbolRef> {
+        self.references.iter().find(|r| {
+            let start: usize = r.span.start.into();
+            let end: usize = r.span.end.into();
+            start <= offset && offset < end
+        })
+    }
+
+    /// All occurrences of the symbol at the given offset, its bindings
+    /// included, or `None` if it is not defined in this document.
+    /// 给定偏移量处符号的所有出现（包括其绑定）；如果该符号未在此文档中定义，则返回 `None`。
+    pub fn occurrences_at(&self, offset: usize) -> Option<Vec<&SymbolRef>> {
+        let definition = self.reference_at(offset)?.definition?;
+        Some(self.references_to(definition))
+    }
+
+    /// All occurrences resolving to the definition at `definition`.
+    /// 解析到 `definition` 处定义的所有出现。
+    pub fn references_to(&self, definition: Span) -> Vec<&SymbolRef> {
+        self.references
+            .iter()
+            .filter(|r| r.definition == Some(definition))
+            .collect()
+    }
+
+    /// Whether some uses of the member defined at `definition` could not be
+    /// told apart from members of the same name on other types, so that
+    /// renaming it could miss or wrongly rename some of them.
+    /// 在 `definition` 处定义的成员的某些使用是否无法与其他类型上的同名成员区分，
+    /// 因此重命名它可能遗漏或错误地重命名其中一些使用。
+    pub fn is_ambiguous_member(&self, definition: Span) -> bool {
+        self.members.iter().any(|(name, owners)| {
+            self.ambiguous.contains(name) && owners.iter().any(|&(_, span)| span == definition)
+        })
+    }
+
+    /// Whether a definition is a top-level one that other modules can import.
+    /// 定义是否为其他模块可以导入的顶级定义。
+    pub fn is_top_level(&self, definition: Span) -> bool {
+        self.globals.values().any(|&global| global == definition)
+    }
+
+    /// References to `name` that are not defined in this document, such as
+    /// imported names.
+    /// 对 `name` 的、未在此文档中定义的引用，例如导入的名称。
+    pub fn unresolved_references(&self, name: &str) -> Vec<&SymbolRef> {
+        self.references
+            .iter()
+            .filter(|r| r.name == name && r.definition.is_none())
+            .collect()
+    }
+
     /// Find the definition of a symbol at the given offset.
     /// 在给定偏移量处查找符号的定义。
     pub fn find_definition_at(&self, offset: usize) -> Option<&Symbol> {
         // First, find what reference is at this offset
         // 首先，查找此偏移量处的引用
-        let ref_at_offset = self.references.iter().find(|r| {
-            let start: usize = r.span.start.into();
-            let end: usize = r.span.end.into();
-            start <= offset && offset < end
-        })?;
+        let ref_at_offset = self.reference_at(offset)?;
 
-        // Then find the definition for this name
-        // 然后查找此名称的定义
-        self.definitions.get(&ref_at_offset.name)?.first()
+        // Then find the definition it resolves to
+        // 然后查找它解析到的定义
+        let definitions = self.definitions.get(&ref_at_offset.name)?;
+        ref_at_offset
+            .definition
+            .and_then(|span| definitions.iter().find(|sym| sym.def_span == span))
+            .or_else(|| definitions.first())
     }
 
     /// Find all references to the symbol at the given offset.
     /// 查找给定偏移量处符号的所有引用。
     pub fn find_references_at(&self, offset: usize, include_declaration: bool) -> Vec<&SymbolRef> {
-        // First, find what symbol is at this offset
-        // 首先，查找此偏移量处的符号
-        let name = self.find_name_at(offset);
+        // First, find what symbol is at this offset; names not defined here
+        // can only be matched by name
+        // 首先，查找此偏移量处的符号；未在此处定义的名称只能按名称匹配
+        let Some(reference) = self.reference_at(offset) else {
+            return Vec::new();
+        };
+        let refs = match reference.definition {
+            Some(definition) => self.references_to(definition),
+            None => self.unresolved_references(&reference.name),
+        };
 
-        if let Some(name) = name {
-            self.references
-                .iter()
-                .filter(|r| r.name == name && (include_declaration || !r.is_write))
-                .collect()
-        } else {
-            Vec::new()
-        }
+        refs.into_iter()
+            .filter(|r| include_declaration || !r.is_write)
+            .collect()
     }
 
     /// Find the name of the symbol at the given offset.
@@ -189,6 +283,7 @@ impl SymbolIndex {
                     name: def.name.name.clone(),
                     span: def.name.span,
                     is_write: true,
+                    definition: None,
                 });
 
                 // Index parameters as Parameter kind
@@ -196,6 +291,7 @@ impl SymbolIndex {
                 self.push_scope();
                 for param in &def.params {
                     self.index_param_pattern(&param.pattern);
+                    self.declare_type(&param.pattern, type_name(&param.ty));
                 }
                 self.index_expr(&def.body);
                 self.pop_scope();
@@ -212,9 +308,11 @@ impl SymbolIndex {
                     name: def.name.name.clone(),
                     span: def.name.span,
                     is_write: true,
+                    definition: None,
                 });
 
                 // Index fields / 索引字段
+                let owner = def.name.name.clone();
                 for field in &def.fields {
                     let field_symbol = Symbol {
                         name: field.name.name.clone(),
@@ -223,6 +321,7 @@ impl SymbolIndex {
                         full_span: field.span,
                     };
                     self.add_definition(field_symbol);
+                    self.add_member_binding(&owner, &field.name.name, field.name.span);
                 }
             }
             ItemKind::Enum(def) => {
@@ -237,6 +336,7 @@ impl SymbolIndex {
                     name: def.name.name.clone(),
                     span: def.name.span,
                     is_write: true,
+                    definition: None,
                 });
 
                 // Index variants / 索引变体
@@ -252,6 +352,7 @@ impl SymbolIndex {
                         name: variant.name.name.clone(),
                         span: variant.name.span,
                         is_write: true,
+                        definition: None,
                     });
                 }
             }
@@ -267,6 +368,7 @@ impl SymbolIndex {
                     name: def.name.name.clone(),
                     span: def.name.span,
                     is_write: true,
+                    definition: None,
                 });
             }
             ItemKind::Trait(def) => {
@@ -281,9 +383,11 @@ impl SymbolIndex {
                     name: def.name.name.clone(),
                     span: def.name.span,
                     is_write: true,
+                    definition: None,
                 });
 
                 // Index trait methods / 索引 trait 方法
+                let owner = def.name.name.clone();
                 for trait_item in &def.items {
                     let method_symbol = Symbol {
                         name: trait_item.name.name.clone(),
@@ -292,9 +396,23 @@ impl SymbolIndex {
                         full_span: trait_item.span,
                     };
                     self.add_definition(method_symbol);
+                    self.add_member_binding(&owner, &trait_item.name.name, trait_item.name.span);
                 }
             }
             ItemKind::Impl(def) => {
+                // Methods of a trait impl belong to the trait
+                // trait 实现的方法属于该 trait
+                let target = type_name(&def.target).map(str::to_string);
+                let trait_name = def.trait_.as_ref().and_then(type_name);
+                if let (Some(target), Some(trait_name)) = (&target, trait_name) {
+                    self.trait_impls
+                        .insert((target.clone(), trait_name.to_string()));
+                }
+                let owner = trait_name
+                    .map(str::to_string)
+                    .or_else(|| target.clone())
+                    .unwrap_or_default();
+
                 // Index impl methods / 索引 impl 方法
                 for impl_item in &def.items {
                     let method_symbol = Symbol {
@@ -304,11 +422,20 @@ impl SymbolIndex {
                         full_span: impl_item.span,
                     };
                     self.add_definition(method_symbol);
+                    self.add_member_binding(&owner, &impl_item.name.name, impl_item.name.span);
 
-                    // Index method body / 索引方法体
+                    // Index method body, where `self` has the impl's type
+                    // 索引方法体，其中 `self` 具有 impl 的类型
                     self.push_scope();
                     for param in &impl_item.params {
                         self.index_pattern(&param.pattern, true);
+                        let ty = match &param.pattern.kind {
+                            PatternKind::Var(ident) if ident.name == "self" => {
+                                type_name(&param.ty).or(target.as_deref())
+                            }
+                            _ => type_name(&param.ty),
+                        };
+                        self.declare_type(&param.pattern, ty);
                     }
                     self.index_expr(&impl_item.body);
                     self.pop_scope();
@@ -328,14 +455,33 @@ impl SymbolIndex {
                     name: ident.name.clone(),
                     span: ident.span,
                     is_write: false,
+                    definition: None,
                 });
             }
             ExprKind::Path(parts) => {
+                // A path starting with a local is a chain of field accesses
+                // 以局部变量开头的路径是字段访问链
+                if let [first, fields @ ..] = parts.as_slice()
+                    && let Some(local) = self.local(&first.name)
+                {
+                    let mut owner = self.local_types.get(&local.def_span).cloned();
+                    self.add_reference(SymbolRef {
+                        name: first.name.clone(),
+                        span: first.span,
+                        is_write: false,
+                        definition: None,
+                    });
+                    for field in fields {
+                        self.add_member_ref(owner.take(), &field.name, field.span);
+                    }
+                    return;
+                }
                 for part in parts {
                     self.add_reference(SymbolRef {
                         name: part.name.clone(),
                         span: part.span,
                         is_write: false,
+                        definition: None,
                     });
                 }
             }
@@ -343,6 +489,7 @@ impl SymbolIndex {
                 self.push_scope();
                 for param in params {
                     self.index_pattern(&param.pattern, true);
+                    self.declare_type(&param.pattern, param.ty.as_ref().and_then(type_name));
                 }
                 self.index_expr(body);
                 self.pop_scope();
@@ -359,22 +506,14 @@ impl SymbolIndex {
                 args,
             } => {
                 self.index_expr(receiver);
-                self.add_reference(SymbolRef {
-                    name: method.name.clone(),
-                    span: method.span,
-                    is_write: false,
-                });
+                self.add_member_use(receiver, &method.name, method.span);
                 for arg in args {
                     self.index_expr(arg);
                 }
             }
             ExprKind::Field { base, field } => {
                 self.index_expr(base);
-                self.add_reference(SymbolRef {
-                    name: field.name.clone(),
-                    span: field.span,
-                    is_write: false,
-                });
+                self.add_member_use(base, &field.name, field.span);
             }
             ExprKind::TupleIndex { base, .. } => {
                 self.index_expr(base);
@@ -404,6 +543,7 @@ impl SymbolIndex {
                             name: field.name.name.clone(),
                             span: field.name.span,
                             is_write: false,
+                            definition: None,
                         });
                     }
                 }
@@ -430,11 +570,13 @@ impl SymbolIndex {
             } => {
                 self.index_expr(condition);
                 self.index_expr(then_branch);
-                self.index_expr(else_branch);
+                if let Some(else_branch) = else_branch {
+                    self.index_expr(else_branch);
+                }
             }
             ExprKind::Match { scrutinee, arms } => {
                 self.index_expr(scrutinee);
-                for arm in arms {
+                for arm in arms.iter() {
                     self.push_scope();
                     self.index_pattern(&arm.pattern, true);
                     if let Some(guard) = &arm.guard {
@@ -477,11 +619,7 @@ impl SymbolIndex {
             }
             ExprKind::SafeField { base, field } => {
                 self.index_expr(base);
-                self.add_reference(SymbolRef {
-                    name: field.name.clone(),
-                    span: field.span,
-                    is_write: false,
-                });
+                self.add_member_use(base, &field.name, field.span);
             }
             ExprKind::Let {
                 pattern,
@@ -518,9 +656,10 @@ impl SymbolIndex {
 
     fn index_stmt(&mut self, stmt: &Stmt) {
         match &stmt.kind {
-            StmtKind::Let { pattern, value, .. } => {
+            StmtKind::Let { pattern, ty, value } => {
                 self.index_expr(value);
                 self.index_pattern(pattern, true);
+                self.declare_type(pattern, ty.as_ref().and_then(type_name));
             }
             StmtKind::Expr(e) => {
                 self.index_expr(e);
@@ -546,6 +685,7 @@ impl SymbolIndex {
                         name: ident.name.clone(),
                         span: ident.span,
                         is_write: true,
+                        definition: None,
                     });
                 }
             }
@@ -571,6 +711,7 @@ impl SymbolIndex {
                             name: field.name.name.clone(),
                             span: field.name.span,
                             is_write: true,
+                            definition: None,
                         });
                     }
                 }
@@ -585,6 +726,16 @@ impl SymbolIndex {
 
     fn index_pattern(&mut self, pattern: &Pattern, is_definition: bool) {
         match &pattern.kind {
+            PatternKind::Var(ident) if is_variant_pattern_name(&ident.name, &self.variants) => {
+                // A bare variant name refers to the variant, as in the resolver
+                // 裸变体名引用该变体，与解析器一致
+                self.add_reference(SymbolRef {
+                    name: ident.name.clone(),
+                    span: ident.span,
+                    is_write: false,
+                    definition: None,
+                });
+            }
             PatternKind::Var(ident) => {
                 if ident.name != "_" {
                     if is_definition {
@@ -601,6 +752,7 @@ impl SymbolIndex {
                         name: ident.name.clone(),
                         span: ident.span,
                         is_write: is_definition,
+                        definition: None,
                     });
                 }
             }
@@ -635,6 +787,7 @@ impl SymbolIndex {
                             name: field.name.name.clone(),
                             span: field.name.span,
                             is_write: is_definition,
+                            definition: None,
                         });
                     }
                 }
@@ -647,6 +800,7 @@ impl SymbolIndex {
                         name: part.name.clone(),
                         span: part.span,
                         is_write: false,
+                        definition: None,
                     });
                 }
                 // Pattern arguments may introduce bindings
@@ -675,6 +829,7 @@ impl SymbolIndex {
                     name: name.name.clone(),
                     span: name.span,
                     is_write: is_definition,
+                    definition: None,
                 });
                 self.index_pattern(pattern, is_definition);
             }
@@ -712,13 +867,178 @@ impl SymbolIndex {
     }
 
     fn add_definition(&mut self, symbol: Symbol) {
+        if self.scopes.is_empty() && !matches!(symbol.kind, SymbolKind::Field | SymbolKind::Method)
+        {
+            self.globals
+                .entry(symbol.name.clone())
+                .or_insert(symbol.def_span);
+        }
         self.definitions
             .entry(symbol.name.clone())
             .or_default()
             .push(symbol);
     }
 
-    fn add_reference(&mut self, reference: SymbolRef) {
+    /// Add a reference, resolving it to the innermost local in scope, or to
+    /// a top-level definition once the whole document is indexed.
+    /// 添加引用，将其解析到作用域中最内层的局部变量，或在整个文档索引完成后解析到顶级定义。
+    fn add_reference(&mut self, mut reference: SymbolRef) {
+        reference.definition = if reference.is_write {
+            Some(reference.span)
+        } else {
+            self.local(&reference.name).map(|symbol| symbol.def_span)
+        };
+        if reference.definition.is_none() {
+            self.pending.push((self.references.len(), Pending::Global));
+        }
         self.references.push(reference);
     }
+
+    /// Record the declared type of a local bound by a variable pattern.
+    /// 记录由变量模式绑定的局部变量的声明类型。
+    fn declare_type(&mut self, pattern: &Pattern, ty: Option<&str>) {
+        if let (PatternKind::Var(ident), Some(ty)) = (&pattern.kind, ty) {
+            self.local_types.insert(ident.span, ty.to_string());
+        }
+    }
+
+    /// The innermost local named `name` in scope.
+    /// 作用域中名为 `name` 的最内层局部变量。
+    fn local(&self, name: &str) -> Option<&Symbol> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    /// The declared type of a receiver, when it is a local with one.
+    /// 接收者的声明类型（当它是具有声明类型的局部变量时）。
+    fn receiver_type(&self, receiver: &Expr) -> Option<String> {
+        let ExprKind::Var(ident) = &receiver.kind else {
+            return None;
+        };
+        let local = self.local(&ident.name)?;
+        self.local_types.get(&local.def_span).cloned()
+    }
+
+    /// Add the name of a field or method definition owned by `owner`.
+    /// 添加由 `owner` 拥有的字段或方法定义的名称。
+    fn add_member_binding(&mut self, owner: &str, name: &str, span: Span) {
+        let owners = self.members.entry(name.to_string()).or_default();
+        let definition = match owners.iter().find(|(o, _)| o == owner) {
+            Some(&(_, definition)) => definition,
+            None => {
+                owners.push((owner.to_string(), span));
+                span
+            }
+        };
+        self.references.push(SymbolRef {
+            name: name.to_string(),
+            span,
+            is_write: true,
+            definition: Some(definition),
+        });
+    }
+
+    /// Add a field access or method call on `receiver`.
+    /// 添加对 `receiver` 的字段访问或方法调用。
+    fn add_member_use(&mut self, receiver: &Expr, name: &str, span: Span) {
+        let owner = self.receiver_type(receiver);
+        self.add_member_ref(owner, name, span);
+    }
+
+    /// Add a use of a member on a receiver of type `owner`, if known.
+    /// 添加对类型为 `owner`（如果已知）的接收者的成员使用。
+    fn add_member_ref(&mut self, owner: Option<String>, name: &str, span: Span) {
+        self.pending
+            .push((self.references.len(), Pending::Member(owner)));
+        self.references.push(SymbolRef {
+            name: name.to_string(),
+            span,
+            is_write: false,
+            definition: None,
+        });
+    }
+
+    /// The member a use on a receiver of type `owner` names: the one that
+    /// type or a trait it implements owns, or the only one of that name.
+    /// 对类型为 `owner` 的接收者的使用所指的成员：该类型或其实现的 trait 拥有的成员，
+    /// 或该名称的唯一成员。
+    fn resolve_member(&self, name: &str, owner: Option<&str>) -> Option<Span> {
+        let owners = self.members.get(name)?;
+        match owner {
+            Some(ty) => owners
+                .iter()
+                .find(|(o, _)| o == ty || self.trait_impls.contains(&(ty.to_string(), o.clone())))
+                .map(|&(_, span)| span),
+            None => match owners.as_slice() {
+                [(_, span)] => Some(*span),
+                _ => None,
+            },
+        }
+    }
+
+    /// Resolve the references left over while indexing.
+    /// 解析索引过程中遗留的引用。
+    fn resolve_pending(&mut self) {
+        for (i, pending) in std::mem::take(&mut self.pending) {
+            let name = &self.references[i].name;
+            let definition = match &pending {
+                Pending::Global => self.globals.get(name).copied(),
+                Pending::Member(owner) => {
+                    let definition = self.resolve_member(name, owner.as_deref());
+                    if definition.is_none() && self.members.contains_key(name) {
+                        self.ambiguous.insert(name.clone());
+                    }
+                    definition
+                }
+            };
+            self.references[i].definition = definition;
+        }
+    }
+}
+
+/// A reference resolved once the whole document is indexed.
+/// 在整个文档索引完成后才解析的引用。
+#[derive(Debug)]
+enum Pending {
+    /// A name that is not a local, resolved to a top-level definition.
+    /// 非局部变量的名称，解析到顶级定义。
+    Global,
+    /// A field or method, with the declared type of its receiver if known.
+    /// 字段或方法，以及其接收者的声明类型（如果已知）。
+    Member(Option<String>),
+}
+
+/// Whether `file` imports `name` from the module in the file `module`, whose
+/// path ends in the import's segments as `a/b.neve` or `a/b/mod.neve`.
+/// `file` 是否从文件 `module` 中的模块导入 `name`，该文件路径以导入的路径段
+/// `a/b.neve` 或 `a/b/mod.neve` 结尾。
+pub fn imports_from(file: &SourceFile, module: &Path, name: &str) -> bool {
+    file.items.iter().any(|item| {
+        let ItemKind::Import(import) = &item.kind else {
+            return false;
+        };
+        if import.path.is_empty() {
+            return false;
+        }
+        let segments: PathBuf = import.path.iter().map(|part| part.name.as_str()).collect();
+        let names_module = module.ends_with(segments.with_extension("neve"))
+            || module.ends_with(segments.join("mod.neve"));
+        names_module
+            && match &import.items {
+                ImportItems::Module | ImportItems::All => true,
+                ImportItems::Items(items) => items.iter().any(|item| item.name == name),
+            }
+    })
+}
+
+/// Whether `name` can be used as an identifier: a single identifier token
+/// that is not a keyword.
+/// `name` 是否可以用作标识符：单个非关键字的标识符 token。
+pub fn is_valid_identifier(name: &str) -> bool {
+    let (tokens, diagnostics) = Lexer::new(name).tokenize();
+    diagnostics.is_empty()
+        && matches!(
+            tokens.as_slice(),
+            [ident, eof] if ident.kind == TokenKind::Ident(name.to_string())
+                && eof.kind == TokenKind::Eof
+        )
 }
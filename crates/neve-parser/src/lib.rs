@@ -16,12 +16,12 @@
 mod parser;
 mod recovery;
 
-pub use parser::Parser;
+pub use parser::{MAX_NESTING_DEPTH, Parser};
 pub use recovery::{DelimiterKind, DelimiterStack, RecoveryMode};
 
 use neve_diagnostic::Diagnostic;
 use neve_lexer::Lexer;
-use neve_syntax::SourceFile;
+use neve_syntax::{Expr, SourceFile};
 
 /// Parse source code into an AST.
 /// 将源代码解析为抽象语法树（AST）。
@@ -45,3 +45,36 @@ pub fn parse(source: &str) -> (SourceFile, Vec<Diagnostic>) {
     diagnostics.extend(parser.diagnostics());
     (file, diagnostics)
 }
+
+/// Parse source code consisting of a single expression.
+/// 将由单个表达式组成的源代码解析为表达式。
+///
+/// Used where a bare expression is accepted instead of a file, such as the REPL.
+/// 用于接受裸表达式而非文件的场景，例如 REPL。
+pub fn parse_expr(source: &str) -> (Expr, Vec<Diagnostic>) {
+    let lexer = Lexer::new(source);
+    let (tokens, mut diagnostics) = lexer.tokenize();
+
+    let mut parser = Parser::new(tokens);
+    let expr = parser.parse_expression();
+
+    diagnostics.extend(parser.diagnostics());
+    (expr, diagnostics)
+}
+
+/// Check whether `source` leaves delimiters open.
+/// 检查 `source` 是否留有未闭合的定界符。
+///
+/// Returns `true` when some `(`, `[`, `{` or `#{` has no matching closer
+/// yet, which means more input is expected. Delimiters inside string
+/// literals and comments are ignored.
+/// 当某个 `(`、`[`、`{` 或 `#{` 尚无匹配的闭合符时返回 `true`，表示还需要更多输入。
+/// 字符串字面量和注释中的定界符会被忽略。
+pub fn has_unclosed_delimiters(source: &str) -> bool {
+    let (tokens, _) = Lexer::new(source).tokenize();
+    let mut stack = DelimiterStack::new();
+    for token in &tokens {
+        stack.update(&token.kind);
+    }
+    !stack.is_empty()
+}
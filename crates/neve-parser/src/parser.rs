@@ -5,8 +5,8 @@
 //! a token stream into an abstract syntax tree (AST).
 //! 本模块实现了一个递归下降解析器，将 token 流转换为抽象语法树（AST）。
 
-use neve_common::Span;
-use neve_diagnostic::{Diagnostic, DiagnosticKind, ErrorCode, Label};
+use neve_common::{BytePos, Span};
+use neve_diagnostic::{Diagnostic, DiagnosticKind, ErrorCode, Label, Suggestion};
 use neve_lexer::{Token, TokenKind};
 use neve_syntax::*;
 
@@ -15,6 +15,13 @@ use crate::recovery::{
     is_sync_token,
 };
 
+/// Maximum nesting depth of expressions, types and patterns.
+/// 表达式、类型和模式的最大嵌套深度。
+///
+/// The parser is recursive, so unbounded nesting would overflow the stack.
+/// 解析器是递归的，无限制的嵌套会导致栈溢出。
+pub const MAX_NESTING_DEPTH: usize = 256;
+
 /// The Neve parser.
 /// Neve 语法解析器。
 ///
@@ -37,6 +44,18 @@ pub struct Parser {
     /// Current recovery mode.
     /// 当前恢复模式。
     recovery_mode: RecoveryMode,
+    /// Whether a `{` after a call's arguments is a trailing block argument.
+    /// Off in positions such as a `match` scrutinee, where `{` starts the arms.
+    /// 调用参数之后的 `{` 是否为尾随块参数。在 `match` 被匹配值等位置关闭，
+    /// 因为那里的 `{` 开始匹配分支。
+    trailing_blocks: bool,
+    /// Current nesting depth of expressions, types and patterns.
+    /// 表达式、类型和模式的当前嵌套深度。
+    depth: usize,
+    /// Set once the nesting limit is hit; the rest of the input is skipped
+    /// and no further errors are reported.
+    /// 达到嵌套限制后置位；跳过剩余输入且不再报告错误。
+    bailed: bool,
 }
 
 impl Parser {
@@ -49,6 +68,9 @@ impl Parser {
             diagnostics: Vec::new(),
             delimiter_stack: DelimiterStack::new(),
             recovery_mode: RecoveryMode::Statement,
+            trailing_blocks: true,
+            depth: 0,
+            bailed: false,
         }
     }
 
@@ -92,6 +114,20 @@ impl Parser {
         }
     }
 
+    /// Parse a single standalone expression, such as `1 + 2`.
+    /// 解析单个独立表达式，例如 `1 + 2`。
+    ///
+    /// A trailing `;` is allowed; any other input after the expression is an error.
+    /// 允许末尾的 `;`；表达式之后的其他输入都是错误。
+    pub fn parse_expression(&mut self) -> Expr {
+        let expr = self.parse_expr();
+        self.eat(TokenKind::Semicolon);
+        if !self.at_end() {
+            self.error("expected end of expression");
+        }
+        expr
+    }
+
     /// Parse a top-level item.
     /// 解析顶层项。
     ///
@@ -802,7 +838,12 @@ impl Parser {
     /// Parse an expression.
     /// 解析表达式。
     fn parse_expr(&mut self) -> Expr {
-        self.parse_pipe_expr()
+        if !self.enter_nested() {
+            return Expr::new(ExprKind::Unit, self.current_span());
+        }
+        let expr = self.parse_pipe_expr();
+        self.depth -= 1;
+        expr
     }
 
     /// Parse pipe expression: expr |> expr
@@ -922,6 +963,12 @@ impl Parser {
                 TokenKind::LtEq => BinOp::Le,
                 TokenKind::Gt => BinOp::Gt,
                 TokenKind::GtEq => BinOp::Ge,
+                TokenKind::LArrow => {
+                    // `x<-1` outside a generator is `x < -1`
+                    // 生成器之外的 `x<-1` 即 `x < -1`
+                    self.split_larrow();
+                    BinOp::Lt
+                }
                 _ => break,
             };
             self.advance();
@@ -1042,6 +1089,15 @@ impl Parser {
     /// Parse unary expression: (! | -) expr
     /// 解析一元表达式：(! | -) expr
     fn parse_unary_expr(&mut self) -> Expr {
+        if !self.enter_nested() {
+            return Expr::new(ExprKind::Unit, self.current_span());
+        }
+        let expr = self.parse_unary_expr_inner();
+        self.depth -= 1;
+        expr
+    }
+
+    fn parse_unary_expr_inner(&mut self) -> Expr {
         let start = self.current_span();
 
         // Logical not: !expr
@@ -1161,8 +1217,15 @@ impl Parser {
                 // Function call: expr(args)
                 // 函数调用：expr(args)
                 self.advance();
-                let args = self.parse_args();
+                let mut args = self.parse_args();
                 self.expect(TokenKind::RParen);
+                // Trailing argument: f(x) #{ ... } or f(x) { ... }
+                // 尾随参数：f(x) #{ ... } 或 f(x) { ... }
+                if self.check(TokenKind::HashLBrace) {
+                    args.push(self.parse_record());
+                } else if self.trailing_blocks && self.check(TokenKind::LBrace) {
+                    args.push(self.parse_block());
+                }
                 let span = expr.span.merge(self.previous_span());
                 expr = Expr::new(
                     ExprKind::Call {
@@ -1240,7 +1303,7 @@ impl Parser {
             }
             // Parenthesized expression or tuple
             // 括号表达式或元组
-            TokenKind::LParen => self.parse_paren_or_tuple(),
+            TokenKind::LParen => self.with_trailing_blocks(true, Self::parse_paren_or_tuple),
             // List literal or comprehension
             // 列表字面量或列表推导
             TokenKind::LBracket => self.parse_list(),
@@ -1249,7 +1312,7 @@ impl Parser {
             TokenKind::HashLBrace => self.parse_record(),
             // Block expression
             // 块表达式
-            TokenKind::LBrace => self.parse_block(),
+            TokenKind::LBrace => self.with_trailing_blocks(true, Self::parse_block),
             // If expression
             // if 表达式
             TokenKind::If => self.parse_if(),
@@ -1409,21 +1472,18 @@ impl Parser {
             let start = self.current_span();
             let pattern = self.parse_pattern();
 
-            // Check for `<-`
-            // 检查 `<-`
-            self.expect(TokenKind::Lt);
-            self.expect(TokenKind::Minus);
+            self.expect(TokenKind::LArrow);
 
             let iter = self.parse_expr();
 
-            // Optional condition
-            // 可选的条件
-            let condition = if self.eat(TokenKind::Comma) {
-                if matches!(self.current_kind(), TokenKind::Ident(_)) {
-                    Some(self.parse_expr())
-                } else {
-                    None
-                }
+            // Optional condition; `, name <- ...` starts the next generator
+            // 可选的条件；`, name <- ...` 开始下一个生成器
+            let condition = if self.check(TokenKind::Comma)
+                && matches!(self.nth_kind(1), TokenKind::Ident(_))
+                && *self.nth_kind(2) != TokenKind::LArrow
+            {
+                self.advance();
+                Some(self.parse_expr())
             } else {
                 None
             };
@@ -1436,7 +1496,7 @@ impl Parser {
                 span: start.merge(end),
             });
 
-            if !self.check(TokenKind::Comma) {
+            if !self.eat(TokenKind::Comma) {
                 break;
             }
         }
@@ -1621,8 +1681,11 @@ impl Parser {
     /// Parse an if expression.
     /// 解析 if 表达式。
     ///
-    /// Syntax: `if condition then then_branch else else_branch`
-    /// 语法：`if 条件 then 真分支 else 假分支`
+    /// Syntax: `if condition then then_branch [else else_branch]`
+    /// 语法：`if 条件 then 真分支 [else 假分支]`
+    ///
+    /// An `else` binds to the nearest `if`.
+    /// `else` 与最近的 `if` 绑定。
     fn parse_if(&mut self) -> Expr {
         let start = self.current_span();
         self.advance(); // if
@@ -1630,15 +1693,19 @@ impl Parser {
         let condition = self.parse_expr();
         self.expect(TokenKind::Then);
         let then_branch = self.parse_expr();
-        self.expect(TokenKind::Else);
-        let else_branch = self.parse_expr();
+        let else_branch = if self.eat(TokenKind::Else) {
+            Some(self.parse_expr())
+        } else {
+            None
+        };
 
-        let span = start.merge(else_branch.span);
+        let end = else_branch.as_ref().unwrap_or(&then_branch).span;
+        let span = start.merge(end);
         Expr::new(
             ExprKind::If {
                 condition: Box::new(condition),
                 then_branch: Box::new(then_branch),
-                else_branch: Box::new(else_branch),
+                else_branch: else_branch.map(Box::new),
             },
             span,
         )
@@ -1653,7 +1720,7 @@ impl Parser {
         let start = self.current_span();
         self.advance(); // match
 
-        let scrutinee = self.parse_expr();
+        let scrutinee = self.with_trailing_blocks(false, Self::parse_expr);
         self.expect(TokenKind::LBrace);
 
         let mut arms = Vec::new();
@@ -1690,7 +1757,7 @@ impl Parser {
         Expr::new(
             ExprKind::Match {
                 scrutinee: Box::new(scrutinee),
-                arms,
+                arms: arms.into(),
             },
             span,
         )
@@ -1806,7 +1873,19 @@ impl Parser {
     /// Parse function call arguments.
     /// 解析函数调用参数。
     fn parse_args(&mut self) -> Vec<Expr> {
-        self.parse_comma_list(TokenKind::RParen, |parser| Some(parser.parse_expr()))
+        self.with_trailing_blocks(true, |parser| {
+            parser.parse_comma_list(TokenKind::RParen, |parser| Some(parser.parse_expr()))
+        })
+    }
+
+    /// Run `f` with trailing block arguments allowed or not, then restore.
+    /// 在允许或禁止尾随块参数的情况下运行 `f`，然后恢复。
+    fn with_trailing_blocks<T>(&mut self, allowed: bool, f: impl FnOnce(&mut Self) -> T) -> T {
+        let prev = self.trailing_blocks;
+        self.trailing_blocks = allowed;
+        let result = f(self);
+        self.trailing_blocks = prev;
+        result
     }
 
     // ========== Pattern Parsing 模式解析 ==========
@@ -1814,7 +1893,12 @@ impl Parser {
     /// Parse a pattern.
     /// 解析模式。
     fn parse_pattern(&mut self) -> Pattern {
-        self.parse_or_pattern()
+        if !self.enter_nested() {
+            return Pattern::new(PatternKind::Wildcard, self.current_span());
+        }
+        let pattern = self.parse_or_pattern();
+        self.depth -= 1;
+        pattern
     }
 
     /// Parse an or-pattern: pattern | pattern
@@ -2111,6 +2195,15 @@ impl Parser {
     /// Parse a function type: Type -> Type
     /// 解析函数类型：类型 -> 类型
     fn parse_function_type(&mut self) -> Type {
+        if !self.enter_nested() {
+            return Type::new(TypeKind::Infer, self.current_span());
+        }
+        let ty = self.parse_function_type_inner();
+        self.depth -= 1;
+        ty
+    }
+
+    fn parse_function_type_inner(&mut self) -> Type {
         let first = self.parse_primary_type();
 
         if self.eat(TokenKind::Arrow) {
@@ -2233,6 +2326,31 @@ impl Parser {
         &self.current().kind
     }
 
+    /// Split the current `<-` token into `<` followed by `-`.
+    /// 将当前的 `<-` token 拆分为 `<` 和其后的 `-`。
+    ///
+    /// The `<` is left current, so advancing past it leaves the `-` to be
+    /// parsed as a prefix minus.
+    /// `<` 保持为当前 token，因此越过它之后 `-` 会被解析为前缀负号。
+    fn split_larrow(&mut self) {
+        let arrow = &self.tokens[self.pos];
+        let mid = BytePos(arrow.span.start.0 + 1);
+        let lt = Token::new(TokenKind::Lt, Span::new(arrow.span.start, mid))
+            .with_leading_trivia(arrow.leading_trivia().to_vec());
+        let minus = Token::new(TokenKind::Minus, Span::new(mid, arrow.span.end))
+            .with_trailing_trivia(arrow.trailing_trivia().to_vec());
+        self.tokens[self.pos] = lt;
+        self.tokens.insert(self.pos + 1, minus);
+    }
+
+    /// Get the kind of the token `n` positions ahead of the current one.
+    /// 获取当前 token 之后第 `n` 个 token 的类型。
+    fn nth_kind(&self, n: usize) -> &TokenKind {
+        self.tokens
+            .get(self.pos + n)
+            .map_or(&self.tokens[self.tokens.len() - 1].kind, |t| &t.kind)
+    }
+
     /// Get the span of the current token.
     /// 获取当前 token 的位置信息。
     fn current_span(&self) -> Span {
@@ -2290,13 +2408,35 @@ impl Parser {
     /// 如果 token 不匹配则报告错误。
     fn expect(&mut self, kind: TokenKind) {
         if !self.eat(kind.clone()) {
+            self.error_expected(kind);
+        }
+    }
+
+    /// Report that `kind` was expected, suggesting a fix for a missing `;`.
+    /// 报告期望 `kind`，并为缺失的 `;` 提供修复建议。
+    fn error_expected(&mut self, kind: TokenKind) {
+        if kind != TokenKind::Semicolon || self.bailed || self.pos == 0 {
             self.error(&format!("expected {:?}", kind));
+            return;
         }
+        // Insert right after the previous token, not wherever the next one is
+        // 插入到前一个 token 之后，而不是下一个 token 所在的位置
+        let at = self.previous_span().end;
+        let span = self.current_span();
+        self.diagnostics.push(
+            Diagnostic::error(DiagnosticKind::Parser, span, "expected `;`")
+                .with_code(ErrorCode::MissingSemicolon)
+                .with_label(Label::new(span, "here"))
+                .with_suggestion(Suggestion::new(Span::new(at, at), ";", "add a semicolon")),
+        );
     }
 
     /// Report a parse error at the current position.
     /// 在当前位置报告解析错误。
     fn error(&mut self, message: &str) {
+        if self.bailed {
+            return;
+        }
         let span = self.current_span();
         self.diagnostics.push(
             Diagnostic::error(DiagnosticKind::Parser, span, message)
@@ -2307,6 +2447,30 @@ impl Parser {
 
     // ========== Error Recovery 错误恢复 ==========
 
+    /// Enter one level of nesting, returning false if the limit is exceeded.
+    /// 进入一层嵌套；若超出限制则返回 false。
+    ///
+    /// On the first overflow a single error is reported and the parser jumps
+    /// to the end of input, so the enclosing frames unwind without cascading
+    /// diagnostics.
+    /// 首次溢出时报告一个错误并跳到输入末尾，使外层调用无级联诊断地返回。
+    fn enter_nested(&mut self) -> bool {
+        if self.bailed {
+            return false;
+        }
+        if self.depth >= MAX_NESTING_DEPTH {
+            self.error(&format!(
+                "nesting too deep (more than {} levels)",
+                MAX_NESTING_DEPTH
+            ));
+            self.bailed = true;
+            self.pos = self.tokens.len().saturating_sub(1);
+            return false;
+        }
+        self.depth += 1;
+        true
+    }
+
     /// Synchronize to the next statement boundary.
     /// 同步到下一个语句边界。
     ///
@@ -2425,7 +2589,7 @@ impl Parser {
         if self.eat(kind.clone()) {
             true
         } else {
-            self.error(&format!("expected {:?}", kind));
+            self.error_expected(kind);
             match recovery {
                 RecoveryMode::Statement => self.synchronize(),
                 RecoveryMode::Expression => {
@@ -0,0 +1,36 @@
+//! JSON operations for the standard library.
+//! 标准库的 JSON 操作。
+
+use neve_eval::value::{BuiltinFn, Value};
+use neve_eval::value_to_json;
+use std::rc::Rc;
+
+/// Returns all JSON builtins.
+/// 返回所有 JSON 内置函数。
+pub fn builtins() -> Vec<(&'static str, Value)> {
+    vec![
+        // toJSON : a -> String
+        // Serializes a value to a JSON string / 将值序列化为 JSON 字符串
+        (
+            "json.toJSON",
+            Value::Builtin(BuiltinFn {
+                name: "json.toJSON",
+                arity: 1,
+                func: |args| value_to_json(&args[0]).map(|json| Value::String(Rc::new(json))),
+            }),
+        ),
+        // fromJSON : String -> a
+        // Parses a JSON string into a value / 将 JSON 字符串解析为值
+        (
+            "json.fromJSON",
+            Value::Builtin(BuiltinFn {
+                name: "json.fromJSON",
+                arity: 1,
+                func: |args| match &args[0] {
+                    Value::String(s) => Value::from_json_str(s),
+                    _ => Err("json.fromJSON expects a string".to_string()),
+                },
+            }),
+        ),
+    ]
+}
@@ -5,6 +5,7 @@
 //! 本 crate 提供 Neve 的内置函数和类型。
 
 mod io;
+mod json;
 mod list;
 mod map;
 mod math;
@@ -21,6 +22,7 @@ use neve_eval::Value;
 pub fn stdlib() -> Vec<(&'static str, Value)> {
     let mut bindings = Vec::new();
     bindings.extend(io::builtins());
+    bindings.extend(json::builtins());
     bindings.extend(list::builtins());
     bindings.extend(map::builtins());
     bindings.extend(math::builtins());
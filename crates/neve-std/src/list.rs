@@ -378,14 +378,19 @@ pub fn builtins() -> Vec<(&'static str, Value)> {
                     match &args[0] {
                         Value::List(items) => {
                             let mut sorted: Vec<_> = items.iter().cloned().collect();
-                            // Only sort if all elements are comparable (integers for now)
-                            // 仅在所有元素可比较时排序（目前支持整数）
-                            sorted.sort_by(|a, b| match (a, b) {
-                                (Value::Int(x), Value::Int(y)) => x.cmp(y),
-                                (Value::String(x), Value::String(y)) => x.cmp(y),
-                                _ => std::cmp::Ordering::Equal,
+                            // Remember the first incomparable pair and report it after sorting
+                            // 记录第一对不可比较的元素，并在排序后报告
+                            let mut error = None;
+                            sorted.sort_by(|a, b| {
+                                a.compare(b).unwrap_or_else(|e| {
+                                    error.get_or_insert(e);
+                                    std::cmp::Ordering::Equal
+                                })
                             });
-                            Ok(Value::List(Rc::new(sorted)))
+                            match error {
+                                Some(e) => Err(format!("list.sort: {e}")),
+                                None => Ok(Value::List(Rc::new(sorted))),
+                            }
                         }
                         _ => Err("list.sort expects a list".to_string()),
                     }
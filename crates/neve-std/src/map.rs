@@ -1,11 +1,12 @@
 //! Map module for Neve standard library.
 //! Neve 标准库的 Map 模块。
 //!
-//! Provides immutable hash map operations.
-//! 提供不可变哈希映射操作。
+//! Provides immutable map operations. Maps are ordered by key, so listing
+//! functions return entries in sorted order.
+//! 提供不可变映射操作。映射按键排序，因此列举函数按排序顺序返回条目。
 
 use neve_eval::Value;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::rc::Rc;
 
 /// Returns all map builtins.
@@ -18,7 +19,7 @@ pub fn builtins() -> Vec<(&'static str, Value)> {
 
         // empty : Map k v
         // Creates an empty map / 创建空映射
-        ("Map.empty", Value::Map(Rc::new(HashMap::new()))),
+        ("Map.empty", Value::Map(Rc::new(BTreeMap::new()))),
         // singleton : k -> v -> Map k v
         // Creates a map with a single key-value pair / 创建包含单个键值对的映射
         (
@@ -30,7 +31,7 @@ pub fn builtins() -> Vec<(&'static str, Value)> {
                         return Err("Map.singleton requires 2 arguments".into());
                     }
                     let key = format!("{:?}", args[0]);
-                    let mut map = HashMap::new();
+                    let mut map = BTreeMap::new();
                     map.insert(key, args[1].clone());
                     Ok(Value::Map(Rc::new(map)))
                 }),
@@ -48,7 +49,7 @@ pub fn builtins() -> Vec<(&'static str, Value)> {
                     }
                     match &args[0] {
                         Value::List(pairs) => {
-                            let mut map = HashMap::new();
+                            let mut map = BTreeMap::new();
                             for pair in pairs.iter() {
                                 match pair {
                                     Value::Tuple(elements) if elements.len() == 2 => {
@@ -278,7 +279,7 @@ pub fn builtins() -> Vec<(&'static str, Value)> {
                     }
                     match (&args[0], &args[1]) {
                         (Value::Map(m1), Value::Map(m2)) => {
-                            let mut result = HashMap::new();
+                            let mut result = BTreeMap::new();
                             for (k, v) in m1.iter() {
                                 if m2.contains_key(k) {
                                     result.insert(k.clone(), v.clone());
@@ -303,7 +304,7 @@ pub fn builtins() -> Vec<(&'static str, Value)> {
                     }
                     match (&args[0], &args[1]) {
                         (Value::Map(m1), Value::Map(m2)) => {
-                            let mut result = HashMap::new();
+                            let mut result = BTreeMap::new();
                             for (k, v) in m1.iter() {
                                 if !m2.contains_key(k) {
                                     result.insert(k.clone(), v.clone());
@@ -332,8 +333,8 @@ pub fn builtins() -> Vec<(&'static str, Value)> {
                     }
                     match &args[0] {
                         Value::Map(map) => {
-                            // Keys are stored as debug strings, return as strings
-                            // 键存储为调试字符串，作为字符串返回
+                            // Keys are stored as debug strings, returned in sorted order
+                            // 键存储为调试字符串，按排序顺序返回
                             let keys: Vec<Value> = map
                                 .keys()
                                 .map(|k| Value::String(Rc::new(k.clone())))
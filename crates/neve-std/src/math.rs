@@ -210,6 +210,100 @@ pub fn builtins() -> Vec<(&'static str, Value)> {
                 },
             }),
         ),
+        // Bitwise / 位运算
+        (
+            "math.band",
+            Value::Builtin(BuiltinFn {
+                name: "math.band",
+                arity: 2,
+                func: |args| int_pair("math.band", args).map(|(a, b)| Value::Int(a & b)),
+            }),
+        ),
+        (
+            "math.bor",
+            Value::Builtin(BuiltinFn {
+                name: "math.bor",
+                arity: 2,
+                func: |args| int_pair("math.bor", args).map(|(a, b)| Value::Int(a | b)),
+            }),
+        ),
+        (
+            "math.bxor",
+            Value::Builtin(BuiltinFn {
+                name: "math.bxor",
+                arity: 2,
+                func: |args| int_pair("math.bxor", args).map(|(a, b)| Value::Int(a ^ b)),
+            }),
+        ),
+        (
+            "math.bnot",
+            Value::Builtin(BuiltinFn {
+                name: "math.bnot",
+                arity: 1,
+                func: |args| match &args[0] {
+                    Value::Int(n) => Ok(Value::Int(!n)),
+                    _ => Err("math.bnot expects an integer".to_string()),
+                },
+            }),
+        ),
+        // Shift counts must be in 0..=63; anything else is an error rather than
+        // being masked. `shr` is arithmetic and keeps the sign.
+        // 移位数必须在 0..=63 范围内，超出范围会报错而不是被截断。`shr` 为算术右移，保留符号。
+        (
+            "math.shl",
+            Value::Builtin(BuiltinFn {
+                name: "math.shl",
+                arity: 2,
+                func: |args| {
+                    let (n, count) = int_pair("math.shl", args)?;
+                    Ok(Value::Int(n << shift_count("math.shl", count)?))
+                },
+            }),
+        ),
+        (
+            "math.shr",
+            Value::Builtin(BuiltinFn {
+                name: "math.shr",
+                arity: 2,
+                func: |args| {
+                    let (n, count) = int_pair("math.shr", args)?;
+                    Ok(Value::Int(n >> shift_count("math.shr", count)?))
+                },
+            }),
+        ),
+        // Number theory / 数论
+        (
+            "math.gcd",
+            Value::Builtin(BuiltinFn {
+                name: "math.gcd",
+                arity: 2,
+                func: |args| {
+                    let (a, b) = int_pair("math.gcd", args)?;
+                    i64::try_from(gcd(a.unsigned_abs(), b.unsigned_abs()))
+                        .map(Value::Int)
+                        .map_err(|_| "math.gcd: result overflows Int".to_string())
+                },
+            }),
+        ),
+        (
+            "math.lcm",
+            Value::Builtin(BuiltinFn {
+                name: "math.lcm",
+                arity: 2,
+                func: |args| {
+                    let (a, b) = int_pair("math.lcm", args)?;
+                    if a == 0 || b == 0 {
+                        return Ok(Value::Int(0));
+                    }
+                    let (a, b) = (a.unsigned_abs(), b.unsigned_abs());
+                    (a / gcd(a, b))
+                        .checked_mul(b)
+                        .and_then(|n| i64::try_from(n).ok())
+                        .map(Value::Int)
+                        .ok_or_else(|| "math.lcm: result overflows Int".to_string())
+                },
+            }),
+        ),
         // Constants / 常量
         ("math.pi", Value::Float(std::f64::consts::PI)),
         ("math.e", Value::Float(std::f64::consts::E)),
@@ -275,3 +369,34 @@ pub fn builtins() -> Vec<(&'static str, Value)> {
         ),
     ]
 }
+
+/// Extract two integer arguments.
+/// 提取两个整数参数。
+fn int_pair(name: &str, args: &[Value]) -> Result<(i64, i64), String> {
+    match (&args[0], &args[1]) {
+        (Value::Int(a), Value::Int(b)) => Ok((*a, *b)),
+        _ => Err(format!("{} expects two integers", name)),
+    }
+}
+
+/// Validate a shift count.
+/// 校验移位数。
+fn shift_count(name: &str, count: i64) -> Result<u32, String> {
+    if (0..64).contains(&count) {
+        Ok(count as u32)
+    } else {
+        Err(format!(
+            "{}: shift count must be between 0 and 63, got {}",
+            name, count
+        ))
+    }
+}
+
+/// Greatest common divisor using Euclid's algorithm.
+/// 使用欧几里得算法计算最大公约数。
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
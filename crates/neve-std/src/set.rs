@@ -1,11 +1,12 @@
 //! Set module for Neve standard library.
 //! Neve 标准库的 Set 模块。
 //!
-//! Provides immutable hash set operations.
-//! 提供不可变哈希集合操作。
+//! Provides immutable set operations. Sets are ordered, so listing
+//! functions return elements in sorted order.
+//! 提供不可变集合操作。集合是有序的，因此列举函数按排序顺序返回元素。
 
 use neve_eval::Value;
-use std::collections::HashSet;
+use std::collections::BTreeSet;
 use std::rc::Rc;
 
 /// Returns all set builtins.
@@ -18,7 +19,7 @@ pub fn builtins() -> Vec<(&'static str, Value)> {
 
         // empty : Set a
         // Creates an empty set / 创建空集合
-        ("Set.empty", Value::Set(Rc::new(HashSet::new()))),
+        ("Set.empty", Value::Set(Rc::new(BTreeSet::new()))),
         // singleton : a -> Set a
         // Creates a set with a single element / 创建包含单个元素的集合
         (
@@ -30,7 +31,7 @@ pub fn builtins() -> Vec<(&'static str, Value)> {
                         return Err("Set.singleton requires 1 argument".into());
                     }
                     let key = format!("{:?}", args[0]);
-                    let mut set = HashSet::new();
+                    let mut set = BTreeSet::new();
                     set.insert(key);
                     Ok(Value::Set(Rc::new(set)))
                 }),
@@ -48,7 +49,7 @@ pub fn builtins() -> Vec<(&'static str, Value)> {
                     }
                     match &args[0] {
                         Value::List(list) => {
-                            let set: HashSet<String> =
+                            let set: BTreeSet<String> =
                                 list.iter().map(|v| format!("{:?}", v)).collect();
                             Ok(Value::Set(Rc::new(set)))
                         }
@@ -177,7 +178,7 @@ pub fn builtins() -> Vec<(&'static str, Value)> {
                     }
                     match (&args[0], &args[1]) {
                         (Value::Set(s1), Value::Set(s2)) => {
-                            let result: HashSet<String> = s1.union(&**s2).cloned().collect();
+                            let result: BTreeSet<String> = s1.union(&**s2).cloned().collect();
                             Ok(Value::Set(Rc::new(result)))
                         }
                         _ => Err("Set.union expects two sets".into()),
@@ -197,7 +198,8 @@ pub fn builtins() -> Vec<(&'static str, Value)> {
                     }
                     match (&args[0], &args[1]) {
                         (Value::Set(s1), Value::Set(s2)) => {
-                            let result: HashSet<String> = s1.intersection(&**s2).cloned().collect();
+                            let result: BTreeSet<String> =
+                                s1.intersection(&**s2).cloned().collect();
                             Ok(Value::Set(Rc::new(result)))
                         }
                         _ => Err("Set.intersection expects two sets".into()),
@@ -217,7 +219,7 @@ pub fn builtins() -> Vec<(&'static str, Value)> {
                     }
                     match (&args[0], &args[1]) {
                         (Value::Set(s1), Value::Set(s2)) => {
-                            let result: HashSet<String> = s1.difference(&**s2).cloned().collect();
+                            let result: BTreeSet<String> = s1.difference(&**s2).cloned().collect();
                             Ok(Value::Set(Rc::new(result)))
                         }
                         _ => Err("Set.difference expects two sets".into()),
@@ -237,7 +239,7 @@ pub fn builtins() -> Vec<(&'static str, Value)> {
                     }
                     match (&args[0], &args[1]) {
                         (Value::Set(s1), Value::Set(s2)) => {
-                            let result: HashSet<String> =
+                            let result: BTreeSet<String> =
                                 s1.symmetric_difference(&**s2).cloned().collect();
                             Ok(Value::Set(Rc::new(result)))
                         }
@@ -118,6 +118,44 @@ impl CompressionFormat {
             CompressionFormat::Zstd => ".nar.zst",
         }
     }
+
+    /// Parse the name used in `.narinfo` files (`none`, `gzip`, `xz`, `zstd`).
+    /// 解析 `.narinfo` 文件中使用的名称（`none`、`gzip`、`xz`、`zstd`）。
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "none" => Some(CompressionFormat::None),
+            "gzip" => Some(CompressionFormat::Gzip),
+            "xz" => Some(CompressionFormat::Xz),
+            "zstd" => Some(CompressionFormat::Zstd),
+            _ => None,
+        }
+    }
+
+    /// Decompress data in this format.
+    /// 解压此格式的数据。
+    pub fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, CacheError> {
+        match self {
+            CompressionFormat::None => Ok(data.to_vec()),
+            CompressionFormat::Gzip => {
+                let mut decoder = flate2::read::GzDecoder::new(data);
+                let mut decompressed = Vec::new();
+                decoder.read_to_end(&mut decompressed).map_err(|e| {
+                    CacheError::Compression(format!("gzip decompression failed: {}", e))
+                })?;
+                Ok(decompressed)
+            }
+            CompressionFormat::Xz => {
+                let mut decompressed = Vec::new();
+                lzma_rs::xz_decompress(&mut std::io::Cursor::new(data), &mut decompressed)
+                    .map_err(|e| {
+                        CacheError::Compression(format!("xz decompression failed: {}", e))
+                    })?;
+                Ok(decompressed)
+            }
+            CompressionFormat::Zstd => zstd::decode_all(std::io::Cursor::new(data))
+                .map_err(|e| CacheError::Compression(format!("zstd decompression failed: {}", e))),
+        }
+    }
 }
 
 /// Configuration for a binary cache.
@@ -196,7 +234,7 @@ impl BinaryCache {
         self.caches.push(config);
         // Sort by priority (descending)
         // 按优先级排序（降序）
-        self.caches.sort_by(|a, b| b.priority.cmp(&a.priority));
+        self.caches.sort_by_key(|c| std::cmp::Reverse(c.priority));
     }
 
     /// Query if a path is available in any cache.
@@ -326,37 +364,15 @@ impl BinaryCache {
     /// 根据文件扩展名解压 NAR 数据。
     fn decompress_nar(&self, data: &[u8], path: &Path) -> Result<Vec<u8>, CacheError> {
         let path_str = path.to_string_lossy();
-
-        if path_str.ends_with(".nar") {
-            // No compression
-            // 无压缩
-            Ok(data.to_vec())
-        } else if path_str.ends_with(".nar.gz") {
-            // gzip decompression
-            // gzip 解压
-            let mut decoder = flate2::read::GzDecoder::new(data);
-            let mut decompressed = Vec::new();
-            decoder.read_to_end(&mut decompressed).map_err(|e| {
-                CacheError::Compression(format!("gzip decompression failed: {}", e))
-            })?;
-            Ok(decompressed)
-        } else if path_str.ends_with(".nar.xz") {
-            // xz decompression
-            // xz 解压
-            let mut decompressed = Vec::new();
-            lzma_rs::xz_decompress(&mut std::io::Cursor::new(data), &mut decompressed)
-                .map_err(|e| CacheError::Compression(format!("xz decompression failed: {}", e)))?;
-            Ok(decompressed)
-        } else if path_str.ends_with(".nar.zst") {
-            // zstd decompression
-            // zstd 解压
-            zstd::decode_all(std::io::Cursor::new(data))
-                .map_err(|e| CacheError::Compression(format!("zstd decompression failed: {}", e)))
-        } else {
-            // Assume uncompressed
-            // 假设未压缩
-            Ok(data.to_vec())
-        }
+        let format = [
+            CompressionFormat::Gzip,
+            CompressionFormat::Xz,
+            CompressionFormat::Zstd,
+        ]
+        .into_iter()
+        .find(|format| path_str.ends_with(format.extension()))
+        .unwrap_or(CompressionFormat::None);
+        format.decompress(data)
     }
 
     /// Compute the hash of a store path using NAR format.
@@ -389,13 +405,8 @@ impl BinaryCache {
                             .collect();
                     }
                     "Compression" => {
-                        compression = match value {
-                            "none" => CompressionFormat::None,
-                            "gzip" => CompressionFormat::Gzip,
-                            "xz" => CompressionFormat::Xz,
-                            "zstd" => CompressionFormat::Zstd,
-                            _ => CompressionFormat::Xz,
-                        };
+                        compression =
+                            CompressionFormat::from_name(value).unwrap_or(CompressionFormat::Xz);
                     }
                     _ => {}
                 }
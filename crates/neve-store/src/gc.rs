@@ -36,6 +36,11 @@ impl<'a> GarbageCollector<'a> {
 
     /// Add a GC root.
     /// 添加 GC 根。
+    ///
+    /// A freshly added path is only safe once rooted, so hold
+    /// [`Store::lock_shared`] from adding it until this returns.
+    /// 新添加的路径只有在获得根之后才是安全的，因此从添加路径到此函数返回期间，
+    /// 请持有 [`Store::lock_shared`]。
     pub fn add_root(&self, name: &str, path: &StorePath) -> Result<(), StoreError> {
         let roots_dir = self.roots_dir();
         fs::create_dir_all(&roots_dir)?;
@@ -149,7 +154,12 @@ impl<'a> GarbageCollector<'a> {
 
     /// Collect garbage and return the number of paths deleted.
     /// 收集垃圾并返回删除的路径数量。
+    ///
+    /// Holds the store's exclusive lock for the whole collection, so paths being
+    /// registered under a shared lock are never deleted.
+    /// 整个回收期间持有存储的排他锁，因此在共享锁下注册的路径永远不会被删除。
     pub fn collect(&mut self) -> Result<GcResult, StoreError> {
+        let _lock = self.store.lock_exclusive()?;
         let live = self.find_live_paths()?;
         let all_paths = self.store.list_paths()?;
 
@@ -176,6 +186,7 @@ impl<'a> GarbageCollector<'a> {
     /// Dry-run garbage collection and return what would be deleted.
     /// 干运行垃圾回收并返回将被删除的内容。
     pub fn dry_run(&mut self) -> Result<Vec<StorePath>, StoreError> {
+        let _lock = self.store.lock_exclusive()?;
         let live = self.find_live_paths()?;
         let all_paths = self.store.list_paths()?;
 
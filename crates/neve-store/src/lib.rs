@@ -10,12 +10,16 @@
 pub mod cache;
 mod db;
 pub mod gc;
+mod lock;
 pub mod nar;
 mod path;
+pub mod stats;
 mod store;
 
 pub use cache::*;
 pub use db::*;
 pub use gc::*;
+pub use lock::StoreLock;
 pub use path::*;
+pub use stats::{PathSize, StoreStats};
 pub use store::*;
@@ -0,0 +1,77 @@
+//! Store-wide advisory locking.
+//! 存储范围的建议锁。
+//!
+//! Garbage collection takes an exclusive lock while it computes the live set
+//! and deletes paths. Adding paths takes a shared lock, so a path can never be
+//! collected between its registration and its becoming reachable from a GC root,
+//! as long as the registering process keeps its shared lock until the root exists.
+//! 垃圾回收在计算存活集合和删除路径时持有排他锁。添加路径持有共享锁，
+//! 因此只要注册进程在 GC 根创建之前一直持有共享锁，
+//! 路径就不会在注册与变为可达之间被回收。
+
+use crate::StoreError;
+use std::fs::{self, File, OpenOptions};
+use std::path::Path;
+
+/// Name of the lock file inside the store root.
+/// 存储根目录中锁文件的名称。
+const LOCK_FILE: &str = ".lock";
+
+/// A held store lock. The lock is released when this value is dropped.
+/// 持有的存储锁。该值被丢弃时释放锁。
+#[derive(Debug)]
+pub struct StoreLock {
+    #[cfg(unix)]
+    _guard: nix::fcntl::Flock<File>,
+    #[cfg(not(unix))]
+    _guard: File,
+}
+
+impl StoreLock {
+    /// Acquire a shared lock on the store, blocking while GC holds it.
+    /// 获取存储的共享锁，GC 持有锁时阻塞。
+    pub fn shared(root: &Path) -> Result<Self, StoreError> {
+        Self::acquire(root, false)
+    }
+
+    /// Acquire an exclusive lock on the store, blocking while any lock is held.
+    /// 获取存储的排他锁，有任何锁被持有时阻塞。
+    pub fn exclusive(root: &Path) -> Result<Self, StoreError> {
+        Self::acquire(root, true)
+    }
+
+    #[cfg(unix)]
+    fn acquire(root: &Path, exclusive: bool) -> Result<Self, StoreError> {
+        use nix::fcntl::{Flock, FlockArg};
+
+        let file = open_lock_file(root)?;
+        let arg = if exclusive {
+            FlockArg::LockExclusive
+        } else {
+            FlockArg::LockShared
+        };
+        let guard = Flock::lock(file, arg).map_err(|(_, errno)| std::io::Error::from(errno))?;
+        Ok(Self { _guard: guard })
+    }
+
+    #[cfg(not(unix))]
+    fn acquire(root: &Path, _exclusive: bool) -> Result<Self, StoreError> {
+        // Advisory locking is only implemented on Unix
+        // 建议锁仅在 Unix 上实现
+        Ok(Self {
+            _guard: open_lock_file(root)?,
+        })
+    }
+}
+
+/// Open (creating if needed) the store's lock file.
+/// 打开（必要时创建）存储的锁文件。
+fn open_lock_file(root: &Path) -> Result<File, StoreError> {
+    fs::create_dir_all(root)?;
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(root.join(LOCK_FILE))?;
+    Ok(file)
+}
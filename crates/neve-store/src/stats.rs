@@ -0,0 +1,82 @@
+//! Store usage statistics.
+//! 存储使用统计。
+//!
+//! Summarizes how much space the store takes and which paths take the most,
+//! to help decide what to clean up.
+//! 汇总存储占用的空间以及占用最多的路径，帮助决定清理哪些内容。
+
+use crate::store::dir_size;
+use crate::{Database, GarbageCollector, Store, StoreError};
+use neve_derive::StorePath;
+
+/// Size of a single store path.
+/// 单个存储路径的大小。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathSize {
+    /// The store path. / 存储路径。
+    pub path: StorePath,
+    /// Size in bytes. / 大小（字节）。
+    pub size: u64,
+}
+
+/// Summary of store usage.
+/// 存储使用情况摘要。
+#[derive(Debug, Clone)]
+pub struct StoreStats {
+    /// Total size of the store in bytes. / 存储的总大小（字节）。
+    pub total_size: u64,
+    /// Number of store paths. / 存储路径数量。
+    pub path_count: usize,
+    /// Number of GC roots. / GC 根数量。
+    pub gc_roots: usize,
+    /// Largest paths, biggest first. / 最大的路径，从大到小。
+    pub largest: Vec<PathSize>,
+}
+
+impl StoreStats {
+    /// Collect statistics for a store, keeping the `top` largest paths.
+    /// 收集存储的统计信息，保留最大的 `top` 个路径。
+    pub fn collect(store: &mut Store, db: &mut Database, top: usize) -> Result<Self, StoreError> {
+        let paths = store.list_paths()?;
+        let total_size = store.size()?;
+        let gc_roots = GarbageCollector::new(store).list_roots()?.len();
+        let largest = largest_paths(store, db, &paths, top)?;
+
+        Ok(Self {
+            total_size,
+            path_count: paths.len(),
+            gc_roots,
+            largest,
+        })
+    }
+}
+
+/// Get the `n` largest of `paths`, biggest first.
+/// 获取 `paths` 中最大的 `n` 个，从大到小。
+///
+/// Sizes come from the registered NAR size, falling back to the size on disk
+/// for unregistered paths. Ties are ordered by path so the result is stable.
+/// 大小取自已注册的 NAR 大小，未注册的路径回退到磁盘上的大小。
+/// 大小相同时按路径排序，使结果稳定。
+pub fn largest_paths(
+    store: &Store,
+    db: &mut Database,
+    paths: &[StorePath],
+    n: usize,
+) -> Result<Vec<PathSize>, StoreError> {
+    let mut sizes = Vec::with_capacity(paths.len());
+    for path in paths {
+        let size = match db.query(path)? {
+            Some(info) => info.nar_size,
+            None => dir_size(&store.to_path(path))?,
+        };
+        sizes.push(PathSize {
+            path: path.clone(),
+            size,
+        });
+    }
+
+    sizes.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+    sizes.truncate(n);
+    Ok(sizes)
+}
@@ -1,8 +1,11 @@
 //! Store operations.
 //! 存储操作。
 
+use crate::nar::{self, NarError};
 use crate::path::store_dir;
+use crate::{Database, PathInfo, StoreLock};
 use neve_derive::{Derivation, Hash, StorePath};
+use neve_fetch::FetchResult;
 use std::collections::HashMap;
 use std::fs;
 use std::io;
@@ -36,6 +39,10 @@ pub enum StoreError {
     /// Serialization error. / 序列化错误。
     #[error("serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
+
+    /// NAR error. / NAR 错误。
+    #[error("NAR error: {0}")]
+    Nar(#[from] NarError),
 }
 
 /// The Neve store.
@@ -73,6 +80,26 @@ impl Store {
         &self.root
     }
 
+    /// Acquire a shared store lock.
+    /// 获取共享存储锁。
+    ///
+    /// Hold this across registering a path and rooting it (or referencing it from
+    /// a rooted path) so a concurrent garbage collection cannot delete it in between.
+    /// 在注册路径并为其创建根（或从已有根的路径引用它）期间持有此锁，
+    /// 以防并发的垃圾回收在此期间删除它。
+    pub fn lock_shared(&self) -> Result<StoreLock, StoreError> {
+        StoreLock::shared(&self.root)
+    }
+
+    /// Acquire an exclusive store lock, as taken by garbage collection.
+    /// 获取排他存储锁（垃圾回收使用）。
+    ///
+    /// Adding paths takes a shared lock, so do not add paths while holding this.
+    /// 添加路径会获取共享锁，因此持有此锁时不要添加路径。
+    pub fn lock_exclusive(&self) -> Result<StoreLock, StoreError> {
+        StoreLock::exclusive(&self.root)
+    }
+
     /// Check if a path exists in the store.
     /// 检查路径是否存在于存储中。
     pub fn path_exists(&self, path: &StorePath) -> bool {
@@ -88,6 +115,7 @@ impl Store {
     /// Add a file to the store with a specific hash.
     /// 将文件添加到存储并使用特定哈希。
     pub fn add_file(&self, source: &Path, name: &str) -> Result<StorePath, StoreError> {
+        let _lock = self.lock_shared()?;
         // Read and hash the file
         // 读取并哈希文件
         let content = fs::read(source)?;
@@ -127,6 +155,7 @@ impl Store {
     /// Add a directory to the store.
     /// 将目录添加到存储。
     pub fn add_dir(&self, source: &Path, name: &str) -> Result<StorePath, StoreError> {
+        let _lock = self.lock_shared()?;
         // Hash the directory contents (simplified: just hash file names and contents)
         // 哈希目录内容（简化：只哈希文件名和内容）
         let hash = hash_dir(source)?;
@@ -142,9 +171,41 @@ impl Store {
         Ok(store_path)
     }
 
+    /// Add a directory whose files refer to its own store path through
+    /// `placeholder`.
+    /// 添加一个目录，其中的文件通过 `placeholder` 引用其自身的存储路径。
+    ///
+    /// The hash is taken while the files still hold the placeholder, so the
+    /// store path does not depend on itself. The copy in the store then has
+    /// every occurrence of the placeholder replaced by that path.
+    /// 哈希在文件仍包含占位符时计算，因此存储路径不依赖于自身。
+    /// 随后存储中的副本会将占位符的每次出现替换为该路径。
+    pub fn add_dir_with_self_references(
+        &self,
+        source: &Path,
+        name: &str,
+        placeholder: &str,
+    ) -> Result<StorePath, StoreError> {
+        let _lock = self.lock_shared()?;
+        let hash = hash_dir(source)?;
+
+        let store_path = StorePath::new(hash, name.to_string());
+        let dest = self.to_path(&store_path);
+
+        if !dest.exists() {
+            copy_dir_recursive(source, &dest)?;
+            let realized = dest.to_string_lossy().into_owned();
+            rewrite_references(&dest, &[(placeholder.to_string(), realized)])?;
+            make_readonly_recursive(&dest)?;
+        }
+
+        Ok(store_path)
+    }
+
     /// Add content directly to the store.
     /// 将内容直接添加到存储。
     pub fn add_content(&self, content: &[u8], name: &str) -> Result<StorePath, StoreError> {
+        let _lock = self.lock_shared()?;
         let hash = Hash::of(content);
         let store_path = StorePath::new(hash, name.to_string());
         let dest = self.to_path(&store_path);
@@ -162,9 +223,120 @@ impl Store {
         Ok(store_path)
     }
 
+    /// Import a fetched file or directory under the hash the fetch computed.
+    /// 以获取时计算的哈希导入获取到的文件或目录。
+    ///
+    /// The content is not hashed again. Fetch cache entries are named
+    /// `<hash>-<name>`, so the check is only that such a prefix agrees with
+    /// `result.hash`; the prefix is dropped from the store name. The path and
+    /// its `references` are recorded in the store database.
+    /// 不会再次哈希内容。获取缓存条目命名为 `<hash>-<name>`，因此只检查该前缀与
+    /// `result.hash` 是否一致；存储名称中会去掉该前缀。路径及其 `references` 会记录到存储数据库中。
+    pub fn register_fetched(
+        &self,
+        result: &FetchResult,
+        references: &[StorePath],
+    ) -> Result<StorePath, StoreError> {
+        let _lock = self.lock_shared()?;
+        let file_name = result
+            .path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .ok_or_else(|| StoreError::InvalidPath(result.path.display().to_string()))?;
+        if !result.path.exists() {
+            return Err(StoreError::PathNotFound(result.path.display().to_string()));
+        }
+
+        // Cheap verification against the cache file name
+        // 根据缓存文件名进行廉价验证
+        let name = match file_name.split_once('-') {
+            Some((prefix, rest)) if prefix.len() == 64 && !rest.is_empty() => {
+                // The prefix is a bare digest in the fetch's own algorithm
+                // 前缀是使用获取所用算法的裸摘要
+                match Hash::from_hex(&format!("{}-{}", result.hash.algo(), prefix)) {
+                    Ok(named) if named != result.hash => {
+                        return Err(StoreError::HashMismatch {
+                            expected: result.hash,
+                            actual: named,
+                        });
+                    }
+                    Ok(_) => rest.to_string(),
+                    Err(_) => file_name.clone(),
+                }
+            }
+            _ => file_name.clone(),
+        };
+
+        let store_path = StorePath::new(result.hash, name);
+        let dest = self.to_path(&store_path);
+        if !dest.exists() {
+            if result.path.is_dir() {
+                copy_dir_recursive(&result.path, &dest)?;
+            } else {
+                if let Some(parent) = dest.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::copy(&result.path, &dest)?;
+            }
+            make_readonly_recursive(&dest)?;
+        }
+
+        let size = if dest.is_dir() {
+            dir_size(&dest)?
+        } else {
+            fs::metadata(&dest)?.len()
+        };
+        let mut info = PathInfo::new(store_path.clone(), result.hash, size);
+        for reference in references {
+            info.add_reference(reference.clone());
+        }
+        Database::open(self.root.clone())?.register(info)?;
+
+        Ok(store_path)
+    }
+
+    /// Unpack a NAR archive at a given store path.
+    /// 将 NAR 归档解包到给定的存储路径。
+    ///
+    /// Used for paths whose name was decided elsewhere, such as outputs
+    /// downloaded from a substituter. The path is registered with the hash of
+    /// `nar` and its `references`; an existing path is left as it is.
+    /// 用于名称由其他地方决定的路径，例如从替代源下载的输出。路径会连同 `nar`
+    /// 的哈希及其 `references` 一起注册；已存在的路径保持不变。
+    pub fn add_nar(
+        &self,
+        nar: &[u8],
+        path: &StorePath,
+        references: &[StorePath],
+    ) -> Result<(), StoreError> {
+        let _lock = self.lock_shared()?;
+        let dest = self.to_path(path);
+
+        if !dest.exists() {
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            if let Err(e) = nar::extract_nar(nar, &dest) {
+                let _ = fs::remove_dir_all(&dest);
+                let _ = fs::remove_file(&dest);
+                return Err(e.into());
+            }
+            make_readonly_recursive(&dest)?;
+        }
+
+        let mut info = PathInfo::new(path.clone(), Hash::of(nar), nar.len() as u64);
+        for reference in references {
+            info.add_reference(reference.clone());
+        }
+        Database::open(self.root.clone())?.register(info)?;
+
+        Ok(())
+    }
+
     /// Add a derivation to the store.
     /// 将推导添加到存储。
     pub fn add_derivation(&mut self, drv: &Derivation) -> Result<StorePath, StoreError> {
+        let _lock = self.lock_shared()?;
         let drv_path = drv.drv_path();
         let dest = self.to_path(&drv_path);
 
@@ -285,6 +457,54 @@ fn hash_dir_recursive(path: &Path, hasher: &mut neve_derive::Hasher) -> Result<(
     Ok(())
 }
 
+/// Replace strings in every regular file under `dir`.
+/// 替换 `dir` 下每个普通文件中的字符串。
+///
+/// Each `(from, to)` pair is applied in order; files without a match are
+/// left untouched.
+/// 每个 `(from, to)` 对按顺序应用；没有匹配的文件保持不变。
+pub fn rewrite_references(dir: &Path, replacements: &[(String, String)]) -> Result<(), StoreError> {
+    let mut stack: Vec<PathBuf> = vec![dir.to_path_buf()];
+
+    while let Some(current) = stack.pop() {
+        for entry in fs::read_dir(&current)? {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                stack.push(entry.path());
+            } else if file_type.is_file() {
+                let content = fs::read(entry.path())?;
+                let mut rewritten = content.clone();
+                for (from, to) in replacements {
+                    rewritten = replace_bytes(&rewritten, from.as_bytes(), to.as_bytes());
+                }
+                if rewritten != content {
+                    fs::write(entry.path(), rewritten)?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Replace every occurrence of `from` in `haystack` with `to`.
+/// 将 `haystack` 中每次出现的 `from` 替换为 `to`。
+fn replace_bytes(haystack: &[u8], from: &[u8], to: &[u8]) -> Vec<u8> {
+    if from.is_empty() {
+        return haystack.to_vec();
+    }
+    let mut out = Vec::with_capacity(haystack.len());
+    let mut rest = haystack;
+    while let Some(pos) = rest.windows(from.len()).position(|w| w == from) {
+        out.extend_from_slice(&rest[..pos]);
+        out.extend_from_slice(to);
+        rest = &rest[pos + from.len()..];
+    }
+    out.extend_from_slice(rest);
+    out
+}
+
 /// Iteratively copy a directory (stack-safe for deep directories).
 /// 迭代式复制目录（对深层目录栈安全）。
 fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<(), StoreError> {
@@ -401,7 +621,7 @@ fn make_writable_recursive(path: &Path) -> Result<(), StoreError> {
 
 /// Calculate the size of a directory.
 /// 计算目录的大小。
-fn dir_size(path: &Path) -> Result<u64, StoreError> {
+pub(crate) fn dir_size(path: &Path) -> Result<u64, StoreError> {
     let mut size = 0;
 
     if !path.exists() {
@@ -3,6 +3,7 @@
 
 use crate::{Ident, Pattern, Type};
 use neve_common::Span;
+use std::sync::Arc;
 
 /// An expression.
 /// 表达式。
@@ -16,6 +17,87 @@ impl Expr {
     pub fn new(kind: ExprKind, span: Span) -> Self {
         Self { kind, span }
     }
+
+    /// The direct subexpressions of an expression.
+    /// 表达式的直接子表达式。
+    pub fn children(&self) -> Vec<&Expr> {
+        match &self.kind {
+            ExprKind::Int(_)
+            | ExprKind::Float(_)
+            | ExprKind::String(_)
+            | ExprKind::Char(_)
+            | ExprKind::Bool(_)
+            | ExprKind::Unit
+            | ExprKind::Var(_)
+            | ExprKind::Path(_)
+            | ExprKind::PathLit(_) => Vec::new(),
+            ExprKind::Interpolated(parts) => parts
+                .iter()
+                .filter_map(|part| match part {
+                    StringPart::Expr(e) => Some(e),
+                    StringPart::Literal(_) => None,
+                })
+                .collect(),
+            ExprKind::Record(fields) => fields.iter().filter_map(|f| f.value.as_ref()).collect(),
+            ExprKind::RecordUpdate { base, fields } => std::iter::once(base.as_ref())
+                .chain(fields.iter().filter_map(|f| f.value.as_ref()))
+                .collect(),
+            ExprKind::List(elements) | ExprKind::Tuple(elements) => elements.iter().collect(),
+            ExprKind::ListComp { body, generators } => std::iter::once(body.as_ref())
+                .chain(
+                    generators
+                        .iter()
+                        .flat_map(|g| std::iter::once(&g.iter).chain(g.condition.as_ref())),
+                )
+                .collect(),
+            ExprKind::Lambda { body, .. } | ExprKind::Lazy(body) => vec![body],
+            ExprKind::Call { func: base, args }
+            | ExprKind::MethodCall {
+                receiver: base,
+                args,
+                ..
+            } => std::iter::once(base.as_ref()).chain(args).collect(),
+            ExprKind::Field { base, .. }
+            | ExprKind::TupleIndex { base, .. }
+            | ExprKind::SafeField { base, .. }
+            | ExprKind::Try(base)
+            | ExprKind::Unary { operand: base, .. } => vec![base],
+            ExprKind::Index { base: a, index: b }
+            | ExprKind::Binary {
+                left: a, right: b, ..
+            }
+            | ExprKind::Coalesce {
+                value: a,
+                default: b,
+            }
+            | ExprKind::Let {
+                value: a, body: b, ..
+            } => vec![a, b],
+            ExprKind::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => [condition, then_branch]
+                .into_iter()
+                .chain(else_branch)
+                .map(|e| e.as_ref())
+                .collect(),
+            ExprKind::Match { scrutinee, arms } => std::iter::once(scrutinee.as_ref())
+                .chain(
+                    arms.iter()
+                        .flat_map(|arm| arm.guard.as_ref().into_iter().chain([&arm.body])),
+                )
+                .collect(),
+            ExprKind::Block { stmts, expr } => stmts
+                .iter()
+                .map(|stmt| match &stmt.kind {
+                    StmtKind::Let { value, .. } => value,
+                    StmtKind::Expr(e) => e,
+                })
+                .chain(expr.as_deref())
+                .collect(),
+        }
+    }
 }
 
 /// Expression kind.
@@ -109,16 +191,22 @@ pub enum ExprKind {
     },
 
     /// If expression `if cond then a else b` / 条件表达式
+    ///
+    /// Without `else`, the expression is `()` when the condition is false.
+    /// 没有 `else` 时，条件为假时表达式的值为 `()`。
     If {
         condition: Box<Expr>,
         then_branch: Box<Expr>,
-        else_branch: Box<Expr>,
+        else_branch: Option<Box<Expr>>,
     },
 
     /// Match expression / 模式匹配表达式
+    ///
+    /// The arms are shared so evaluators can cache per-match data keyed by
+    /// their address. / 分支是共享的，以便求值器按其地址缓存每个匹配的数据。
     Match {
         scrutinee: Box<Expr>,
-        arms: Vec<MatchArm>,
+        arms: Arc<[MatchArm]>,
     },
 
     /// Block `{ let x = 1; x + 1 }` / 块表达式
@@ -0,0 +1,77 @@
+//! Source annotation with inferred types.
+//! 使用推断类型注释源代码。
+//!
+//! Appends a `-- : Type` comment to the first line of every top-level item
+//! whose type was inferred, for learning and debugging.
+//! 为每个已推断类型的顶层项的第一行追加 `-- : Type` 注释，用于学习和调试。
+
+use crate::errors::format_type;
+use neve_common::Span;
+use neve_hir::Ty;
+use neve_lexer::{Lexer, TriviaKind};
+use std::collections::HashMap;
+
+/// Annotate `source` with the given item types.
+/// 使用给定的项类型注释 `source`。
+///
+/// Items starting on the same line share one annotation, separated by `;`.
+/// On a line that ends in a comment, the annotation goes before the comment.
+/// 从同一行开始的多个项共享一个注释，以 `;` 分隔。
+/// 在以注释结尾的行上，注释插入到该行注释之前。
+pub fn annotate(source: &str, item_types: &HashMap<Span, Ty>) -> String {
+    let mut items: Vec<_> = item_types.iter().collect();
+    items.sort_by_key(|(span, _)| span.start);
+
+    // Annotations by line index
+    // 按行索引的注释
+    let mut by_line: HashMap<usize, Vec<String>> = HashMap::new();
+    for (span, ty) in items {
+        let start = (span.start.0 as usize).min(source.len());
+        let line = source[..start].matches('\n').count();
+        by_line.entry(line).or_default().push(format_type(ty));
+    }
+
+    let comments = line_comment_starts(source);
+
+    let mut out = String::with_capacity(source.len());
+    let mut line_start = 0;
+    for (i, line) in source.split_inclusive('\n').enumerate() {
+        let text = line.trim_end_matches(['\n', '\r']);
+        let code_end = comments
+            .iter()
+            .find(|&&start| start >= line_start && start < line_start + text.len())
+            .map_or(text.len(), |&start| start - line_start);
+        let code = if code_end < text.len() {
+            text[..code_end].trim_end()
+        } else {
+            text
+        };
+        match by_line.get(&i) {
+            Some(types) => {
+                out.push_str(code);
+                out.push_str("  -- : ");
+                out.push_str(&types.join("; "));
+                if code_end < text.len() {
+                    out.push_str("  ");
+                    out.push_str(&text[code_end..]);
+                }
+            }
+            None => out.push_str(text),
+        }
+        out.push_str(&line[text.len()..]);
+        line_start += line.len();
+    }
+    out
+}
+
+/// Byte offsets where the line comments of `source` start, in order.
+/// `source` 中各行注释起始处的字节偏移，按顺序排列。
+fn line_comment_starts(source: &str) -> Vec<usize> {
+    let (tokens, _) = Lexer::new(source).tokenize();
+    tokens
+        .iter()
+        .flat_map(|token| token.leading_trivia().iter().chain(token.trailing_trivia()))
+        .filter(|trivia| trivia.kind == TriviaKind::LineComment)
+        .map(|trivia| trivia.span.start.0 as usize)
+        .collect()
+}
@@ -6,18 +6,26 @@
 //! 本模块实现 Neve 的主类型检查器。
 //! 采用带有 Hindley-Milner 推断的双向类型检查。
 
-use crate::errors::{TypeMismatchError, unbound_variable, unused_variable};
+use crate::errors::{
+    TypeMismatchError, binary_op_mismatch, find_similar_name, format_type, if_branch_mismatch,
+    if_without_else, infinite_type, match_arm_mismatch, non_bool_condition, non_exhaustive_match,
+    redundant_pattern, unary_op_mismatch, unbound_variable, unreachable_pattern, unused_parameter,
+    unused_variable, wrong_arity,
+};
+use crate::exhaustive::{VariantTable, check_match};
 use crate::infer::InferContext;
 use crate::traits::{TraitId, TraitResolver};
-use crate::unify::{Substitution, free_type_vars, generalize, instantiate, unify};
+use crate::unify::{
+    Substitution, UnifyError, UnifyReason, free_type_vars, generalize, instantiate, unify,
+};
 use neve_common::Span;
-use neve_diagnostic::{Diagnostic, DiagnosticKind, ErrorCode};
+use neve_diagnostic::{Diagnostic, DiagnosticKind, ErrorCode, Label, Severity};
 use neve_hir::{
     BinOp, DefId, EnumDef, Expr, ExprKind, FnDef, ImplDef, Item, ItemKind, Literal, LocalId,
     MatchArm, Module, Pattern, PatternKind, Stmt, StmtKind, StructDef, TraitDef, Ty, TyKind,
     TypeAlias, UnaryOp,
 };
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 /// Information about a local variable.
 /// 局部变量的信息。
@@ -31,22 +39,32 @@ struct LocalInfo {
     span: Span,
     /// Whether the variable has been used. / 变量是否被使用过。
     used: bool,
+    /// Whether the variable is a function parameter. / 变量是否为函数参数。
+    param: bool,
 }
 
 /// Information about a struct type definition.
 /// 结构体类型定义的信息。
 #[derive(Clone)]
 struct StructInfo {
+    /// Struct name. / 结构体名称。
+    name: String,
     /// Field types (name -> type). / 字段类型（名称 -> 类型）。
     fields: HashMap<String, Ty>,
+    /// Field names in declaration order. / 按声明顺序排列的字段名称。
+    order: Vec<String>,
+    /// Fields that have a default value. / 具有默认值的字段。
+    defaulted: HashSet<String>,
 }
 
 /// Information about an enum type definition.
 /// 枚举类型定义的信息。
 #[derive(Clone)]
 struct EnumInfo {
-    /// Variant constructors (name -> field types). / 变体构造函数（名称 -> 字段类型）。
-    variants: HashMap<String, Vec<Ty>>,
+    /// Variant constructors (name, field types) in declaration order. / 按声明顺序排列的变体构造函数（名称，字段类型）。
+    variants: Vec<(String, Vec<Ty>)>,
+    /// Definition IDs of the variants, in the same order. / 变体的定义 ID，顺序相同。
+    variant_ids: Vec<DefId>,
 }
 
 /// Information about a type alias.
@@ -72,6 +90,9 @@ pub struct TypeChecker {
     /// Span of global definitions for error reporting.
     /// 全局定义的位置信息，用于错误报告。
     global_spans: HashMap<DefId, Span>,
+    /// Parameter spans of global functions, for pointing at declarations.
+    /// 全局函数的参数位置，用于指向声明处。
+    param_spans: HashMap<DefId, Vec<Span>>,
     /// Types of local variables with usage tracking.
     /// 局部变量的类型及使用情况跟踪。
     locals: HashMap<LocalId, LocalInfo>,
@@ -93,6 +114,15 @@ pub struct TypeChecker {
     /// Whether to check for unused variables.
     /// 是否检查未使用的变量。
     check_unused: bool,
+    /// Inferred types of top-level bindings and functions, keyed by item span.
+    /// 顶层绑定和函数的推断类型，按项的位置信息索引。
+    item_types: HashMap<Span, Ty>,
+    /// Inferred types of every checked expression, keyed by expression span.
+    /// 每个已检查表达式的推断类型，按表达式的位置信息索引。
+    expr_types: HashMap<Span, Ty>,
+    /// Types of local bindings, keyed by the span they were defined at.
+    /// 局部绑定的类型，按其定义处的位置信息索引。
+    binding_types: HashMap<Span, Ty>,
 }
 
 impl TypeChecker {
@@ -102,6 +132,7 @@ impl TypeChecker {
             subst: Substitution::new(),
             globals: HashMap::new(),
             global_spans: HashMap::new(),
+            param_spans: HashMap::new(),
             locals: HashMap::new(),
             trait_resolver: TraitResolver::new(),
             trait_ids: HashMap::new(),
@@ -110,6 +141,9 @@ impl TypeChecker {
             type_aliases: HashMap::new(),
             diagnostics: Vec::new(),
             check_unused: true,
+            item_types: HashMap::new(),
+            expr_types: HashMap::new(),
+            binding_types: HashMap::new(),
         }
     }
 
@@ -227,17 +261,18 @@ impl TypeChecker {
     /// Get all struct field names.
     /// 获取所有结构体字段名称。
     pub fn struct_fields(&self, def_id: DefId) -> Option<Vec<String>> {
-        self.structs
-            .get(&def_id)
-            .map(|info| info.fields.keys().cloned().collect())
+        self.structs.get(&def_id).map(|info| info.order.clone())
     }
 
     /// Get enum variant field types by variant name.
     /// 通过变体名称获取枚举变体字段类型。
     pub fn enum_variant_types(&self, def_id: DefId, variant_name: &str) -> Option<Vec<Ty>> {
-        self.enums
-            .get(&def_id)
-            .and_then(|info| info.variants.get(variant_name).cloned())
+        self.enums.get(&def_id).and_then(|info| {
+            info.variants
+                .iter()
+                .find(|(name, _)| name == variant_name)
+                .map(|(_, fields)| fields.clone())
+        })
     }
 
     /// Get all enum variant names.
@@ -245,7 +280,7 @@ impl TypeChecker {
     pub fn enum_variants(&self, def_id: DefId) -> Option<Vec<String>> {
         self.enums
             .get(&def_id)
-            .map(|info| info.variants.keys().cloned().collect())
+            .map(|info| info.variants.iter().map(|(name, _)| name.clone()).collect())
     }
 
     /// Resolve a type alias to its target type.
@@ -256,6 +291,92 @@ impl TypeChecker {
             .map(|info| info.target.clone())
     }
 
+    /// Get the inferred types of top-level items, keyed by item span.
+    /// 获取顶层项的推断类型，按项的位置信息索引。
+    ///
+    /// Items without parameters, such as top-level `let` bindings, map to the
+    /// type of their value; functions map to a function type.
+    /// 无参数的项（如顶层 `let` 绑定）映射到其值的类型；函数映射到函数类型。
+    pub fn item_types(&self) -> &HashMap<Span, Ty> {
+        &self.item_types
+    }
+
+    /// Get the inferred type of the expression with exactly this span.
+    /// 获取位置信息恰好为此范围的表达式的推断类型。
+    ///
+    /// Only expressions visited by a previous [`check`](Self::check) or
+    /// [`infer_expr`](Self::infer_expr) are known.
+    /// 仅能查询之前由 [`check`](Self::check) 或 [`infer_expr`](Self::infer_expr) 访问过的表达式。
+    pub fn type_at(&self, span: Span) -> Option<Ty> {
+        self.expr_types.get(&span).map(|ty| self.apply(ty))
+    }
+
+    /// Get the inferred type of the local binding defined at exactly this span.
+    /// 获取恰好在此范围定义的局部绑定的推断类型。
+    ///
+    /// Parameters and pattern variables are keyed by their pattern's span,
+    /// `let` statements by the span of the whole statement.
+    /// 参数和模式变量按其模式的位置信息索引，`let` 语句按整个语句的位置信息索引。
+    pub fn binding_type(&self, span: Span) -> Option<Ty> {
+        self.binding_types.get(&span).map(|ty| self.apply(ty))
+    }
+
+    /// Get the inferred type of the innermost expression containing `offset`.
+    /// 获取包含 `offset` 的最内层表达式的推断类型。
+    pub fn type_at_offset(&self, offset: usize) -> Option<(Span, Ty)> {
+        self.expr_types
+            .iter()
+            .filter(|(span, _)| span.range().contains(&offset))
+            .min_by_key(|(span, _)| (span.len(), span.start))
+            .map(|(span, ty)| (*span, self.apply(ty)))
+    }
+
+    /// Infer the type of a single expression against the checked module.
+    /// 基于已检查的模块推断单个表达式的类型。
+    ///
+    /// Runs in this checker's inference context, so globals, structs and
+    /// locals from an earlier [`check`](Self::check) are in scope and type
+    /// variables keep their solutions. `env` supplies the types of any
+    /// additional locals the expression refers to. On failure the
+    /// diagnostics raised by this expression are returned; the checker's
+    /// own diagnostics, locals and expression types are left unchanged.
+    /// 在此检查器的推断上下文中运行，因此之前 [`check`](Self::check) 得到的全局定义、
+    /// 结构体和局部变量均在作用域中，类型变量保留其解。`env` 提供表达式引用的其他局部变量的类型。
+    /// 失败时返回此表达式产生的诊断；检查器自身的诊断、局部变量和表达式类型保持不变。
+    pub fn infer_expr(
+        &mut self,
+        expr: &Expr,
+        env: &HashMap<LocalId, Ty>,
+    ) -> Result<Ty, Vec<Diagnostic>> {
+        // Probing must not leave the expression's locals and types behind
+        // 探测不得留下该表达式的局部变量和类型
+        let locals = self.locals.clone();
+        let expr_types = self.expr_types.clone();
+        for (local_id, ty) in env {
+            self.locals.insert(
+                *local_id,
+                LocalInfo {
+                    ty: ty.clone(),
+                    name: String::new(),
+                    span: expr.span,
+                    used: true,
+                    param: false,
+                },
+            );
+        }
+
+        let before = self.diagnostics.len();
+        let ty = self.infer_expr_ty(expr);
+        self.locals = locals;
+        self.expr_types = expr_types;
+        let errors: Vec<Diagnostic> = self.diagnostics.drain(before..).collect();
+        if errors.iter().any(|d| d.severity == Severity::Error) {
+            Err(errors)
+        } else {
+            Ok(self.apply(&ty))
+        }
+    }
+
     /// Get the collected diagnostics.
     /// 获取收集的诊断信息。
     pub fn diagnostics(self) -> Vec<Diagnostic> {
@@ -281,8 +402,11 @@ impl TypeChecker {
         }
         for info in self.locals.values() {
             if !info.used && !info.name.starts_with('_') {
-                self.diagnostics
-                    .push(unused_variable(&info.name, info.span));
+                self.diagnostics.push(if info.param {
+                    unused_parameter(&info.name, info.span)
+                } else {
+                    unused_variable(&info.name, info.span)
+                });
             }
         }
     }
@@ -298,6 +422,7 @@ impl TypeChecker {
     /// Define a local variable.
     /// 定义局部变量。
     fn define_local(&mut self, local_id: LocalId, name: String, ty: Ty, span: Span) {
+        self.binding_types.insert(span, ty.clone());
         self.locals.insert(
             local_id,
             LocalInfo {
@@ -305,6 +430,7 @@ impl TypeChecker {
                 name,
                 span,
                 used: false,
+                param: false,
             },
         );
     }
@@ -347,16 +473,105 @@ impl TypeChecker {
         self.subst.get_param(idx).map(|ty| self.apply(ty))
     }
 
-    fn unify(&mut self, t1: &Ty, t2: &Ty, span: Span) -> bool {
-        match unify(t1, t2, &mut self.subst) {
+    /// Unify the actual type of the expression at `span` with the type it is
+    /// expected to have, reporting a mismatch at `span`.
+    /// 将 `span` 处表达式的实际类型与其期望类型合一，不匹配时在 `span` 处报告。
+    fn unify(&mut self, actual: &Ty, expected: &Ty, span: Span) -> bool {
+        self.unify_expecting(actual, expected, span, None)
+    }
+
+    /// Like `unify`, also pointing at `origin`, where the expected type came from.
+    /// 与 `unify` 相同，并额外指向期望类型的来源 `origin`。
+    fn unify_expecting(
+        &mut self,
+        actual: &Ty,
+        expected: &Ty,
+        span: Span,
+        origin: Option<Span>,
+    ) -> bool {
+        match self.try_unify(actual, expected, span) {
             Ok(()) => true,
-            Err(msg) => {
-                self.error(span, msg);
+            Err(err) => {
+                let mut mismatch = self.mismatch(actual, expected, err, span);
+                if let Some(origin) = origin {
+                    mismatch = mismatch.with_expected_span(origin);
+                }
+                self.emit(mismatch.build());
                 false
             }
         }
     }
 
+    /// Unify without reporting a mismatch, for callers with a more specific
+    /// diagnostic. An infinite type is reported here at `span`, since no
+    /// caller can say more about it; the offending variable is then bound
+    /// to `Unknown` and unification resumes, so the substitution never stays
+    /// half-applied and later uses do not cascade into further errors.
+    /// 合一但不报告类型不匹配，供有更具体诊断信息的调用者使用。
+    /// 无限类型在此处针对 `span` 报告，因为调用者无法提供更多信息；随后将该类型变量
+    /// 绑定为 `Unknown` 并继续合一，使替换不会停留在半完成状态，后续使用也不会引发连锁错误。
+    fn try_unify(&mut self, actual: &Ty, expected: &Ty, span: Span) -> Result<(), UnifyError> {
+        loop {
+            match unify(actual, expected, &mut self.subst, &mut self.infer) {
+                Err(UnifyError {
+                    reason: Some(UnifyReason::InfiniteType),
+                    expected,
+                    found,
+                }) => {
+                    let TyKind::Var(var) = found.kind else {
+                        unreachable!("infinite type reported for a non-variable");
+                    };
+                    let ty = self.apply(&expected);
+                    self.emit(infinite_type(var, &ty, span));
+                    // Poison the variable; each round binds one more, so this terminates
+                    // 污染该变量；每轮绑定一个变量，因此必然终止
+                    self.subst.extend(
+                        var,
+                        Ty {
+                            kind: TyKind::Unknown,
+                            span,
+                        },
+                    );
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Describe a failed unification of `actual` against `expected`.
+    /// 描述 `actual` 与 `expected` 合一失败的情况。
+    fn mismatch(
+        &self,
+        actual: &Ty,
+        expected: &Ty,
+        err: UnifyError,
+        span: Span,
+    ) -> TypeMismatchError {
+        let expected = self.apply(expected);
+        let found = self.apply(actual);
+        let inner_expected = format_type(&self.apply(&err.expected));
+        let inner_found = format_type(&self.apply(&err.found));
+        let mut mismatch = TypeMismatchError::new(expected.clone(), found.clone(), span);
+        if let Some(UnifyReason::Note(note)) = err.reason {
+            mismatch = mismatch.with_note(note);
+        }
+        // Point out which part differs when the types are compound
+        // 当类型是复合类型时，指出具体不同的部分
+        if inner_expected != format_type(&expected) || inner_found != format_type(&found) {
+            mismatch = mismatch.with_note(format!(
+                "`{}` does not match `{}`",
+                inner_found, inner_expected
+            ));
+        }
+        mismatch
+    }
+
+    /// The span of a type annotation, if the type was written out.
+    /// 类型注解的位置（如果写出了类型）。
+    fn annotation_span(ty: &Ty) -> Option<Span> {
+        (!matches!(ty.kind, TyKind::Unknown)).then_some(ty.span)
+    }
+
     // ===== First pass: collect signatures 第一遍：收集签名 =====
 
     fn collect_item(&mut self, item: &Item) {
@@ -368,6 +583,8 @@ impl TypeChecker {
             ItemKind::Fn(fn_def) => {
                 let fn_ty = self.fn_signature(fn_def);
                 self.globals.insert(item.id, fn_ty);
+                self.param_spans
+                    .insert(item.id, fn_def.params.iter().map(|p| p.span).collect());
             }
             ItemKind::Trait(trait_def) => {
                 self.collect_trait(item.id, trait_def);
@@ -400,11 +617,22 @@ impl TypeChecker {
     /// 收集结构体类型定义。
     fn collect_struct(&mut self, def_id: DefId, struct_def: &StructDef) {
         let mut fields = HashMap::new();
+        let mut order = Vec::new();
+        let mut defaulted = HashSet::new();
         for field in &struct_def.fields {
             fields.insert(field.name.clone(), field.ty.clone());
+            order.push(field.name.clone());
+            if field.default.is_some() {
+                defaulted.insert(field.name.clone());
+            }
         }
 
-        let info = StructInfo { fields };
+        let info = StructInfo {
+            name: struct_def.name.clone(),
+            fields,
+            order,
+            defaulted,
+        };
 
         self.structs.insert(def_id, info);
 
@@ -420,12 +648,17 @@ impl TypeChecker {
     /// Collect enum type definition.
     /// 收集枚举类型定义。
     fn collect_enum(&mut self, def_id: DefId, enum_def: &EnumDef) {
-        let mut variants = HashMap::new();
-        for variant in &enum_def.variants {
-            variants.insert(variant.name.clone(), variant.fields.clone());
-        }
+        let variants = enum_def
+            .variants
+            .iter()
+            .map(|variant| (variant.name.clone(), variant.fields.clone()))
+            .collect();
+        let variant_ids = enum_def.variants.iter().map(|variant| variant.id).collect();
 
-        let info = EnumInfo { variants };
+        let info = EnumInfo {
+            variants,
+            variant_ids,
+        };
 
         self.enums.insert(def_id, info);
 
@@ -436,6 +669,33 @@ impl TypeChecker {
             span: Span::DUMMY,
         };
         self.globals.insert(def_id, enum_ty);
+
+        // Nullary variants are values of the enum, the others construct it
+        // from their fields, generalized over the enum's type parameters
+        // 无字段变体是枚举的值，其余变体由其字段构造枚举，并对枚举的类型参数泛化
+        for variant in &enum_def.variants {
+            let result = Ty {
+                kind: TyKind::Named(
+                    def_id,
+                    enum_def.generics.iter().map(|_| self.fresh_var()).collect(),
+                ),
+                span: Span::DUMMY,
+            };
+            let ty = if variant.fields.is_empty() {
+                result
+            } else {
+                let fields = variant
+                    .fields
+                    .iter()
+                    .map(|f| self.resolve_type(f))
+                    .collect();
+                Ty {
+                    kind: TyKind::Fn(fields, Box::new(result)),
+                    span: Span::DUMMY,
+                }
+            };
+            self.globals.insert(variant.id, generalize(&ty, &[]));
+        }
     }
 
     /// Collect type alias definition.
@@ -461,6 +721,12 @@ impl TypeChecker {
 
         let ret_ty = self.resolve_type(&fn_def.return_ty);
 
+        // Top-level lets are values rather than functions
+        // 顶层 let 是值而不是函数
+        if fn_def.is_let {
+            return ret_ty;
+        }
+
         let fn_ty = Ty {
             kind: TyKind::Fn(param_tys, Box::new(ret_ty)),
             span: Span::DUMMY,
@@ -517,12 +783,100 @@ impl TypeChecker {
     // ===== Second pass: check bodies 第二遍：检查函数体 =====
 
     fn check_item(&mut self, item: &Item) {
-        if let ItemKind::Fn(fn_def) = &item.kind {
-            self.check_fn(item.id, fn_def);
+        match &item.kind {
+            ItemKind::Fn(fn_def) => self.check_fn(item.id, item.span, fn_def),
+            ItemKind::Struct(struct_def) => self.check_struct_defaults(struct_def),
+            _ => {}
+        }
+    }
+
+    /// Check that each field default matches the field's declared type.
+    /// 检查每个字段的默认值与字段声明的类型是否匹配。
+    fn check_struct_defaults(&mut self, struct_def: &StructDef) {
+        for field in &struct_def.fields {
+            if let Some(default) = &field.default {
+                let default_ty = self.infer_expr_ty(default);
+                let field_ty = self.resolve_type(&field.ty);
+                self.unify(&default_ty, &field_ty, default.span);
+            }
+        }
+    }
+
+    /// Infer a struct construction `Name(#{ ... })`.
+    /// 推断结构体构造 `Name(#{ ... })`。
+    ///
+    /// A record literal argument is checked field by field: unknown fields
+    /// and missing fields without a default are reported. The result is a
+    /// record with every declared field.
+    /// 记录字面量参数逐字段检查：报告未知字段和缺少且无默认值的字段。
+    /// 结果是包含所有声明字段的记录。
+    fn infer_struct_construction(&mut self, def_id: DefId, args: &[Expr], span: Span) -> Ty {
+        let info = self.structs[&def_id].clone();
+        let field_tys: Vec<(String, Ty)> = info
+            .order
+            .iter()
+            .map(|name| (name.clone(), self.resolve_type(&info.fields[name])))
+            .collect();
+        let struct_ty = Ty {
+            kind: TyKind::Record(field_tys.clone()),
+            span,
+        };
+
+        let [arg] = args else {
+            for arg in args {
+                self.infer_expr_ty(arg);
+            }
+            self.error(
+                span,
+                format!(
+                    "struct {} takes 1 argument (a record of fields), found {}",
+                    info.name,
+                    args.len()
+                ),
+            );
+            return struct_ty;
+        };
+
+        let ExprKind::Record(fields) = &arg.kind else {
+            let arg_ty = self.infer_expr_ty(arg);
+            self.unify(&arg_ty, &struct_ty, arg.span);
+            return struct_ty;
+        };
+
+        for (name, value) in fields {
+            let value_ty = self.infer_expr_ty(value);
+            match field_tys.iter().find(|(n, _)| n == name) {
+                Some((_, field_ty)) => {
+                    self.unify(&value_ty, field_ty, value.span);
+                }
+                None => {
+                    let mut diag = Diagnostic::error(
+                        DiagnosticKind::Type,
+                        value.span,
+                        format!("unknown field '{}' in struct {}", name, info.name),
+                    )
+                    .with_code(ErrorCode::TypeMismatch);
+                    if let Some(similar) = find_similar_name(name, &info.order) {
+                        diag = diag.with_help(format!("did you mean `{}`?", similar));
+                    }
+                    self.emit(diag);
+                }
+            }
+        }
+
+        for name in &info.order {
+            if !info.defaulted.contains(name) && !fields.iter().any(|(n, _)| n == name) {
+                self.error(
+                    arg.span,
+                    format!("missing field '{}' in struct {}", name, info.name),
+                );
+            }
         }
+
+        struct_ty
     }
 
-    fn check_fn(&mut self, _id: DefId, fn_def: &FnDef) {
+    fn check_fn(&mut self, id: DefId, span: Span, fn_def: &FnDef) {
         // Create fresh type variables for generic parameters
         let mut generic_vars: HashMap<String, Ty> = HashMap::new();
         for (idx, param) in fn_def.generics.iter().enumerate() {
@@ -532,33 +886,71 @@ impl TypeChecker {
         }
 
         // Bind parameter types (resolving generic references)
-        // Parameters are considered used by default (they're part of the function signature)
         for param in &fn_def.params {
             let ty = self.resolve_type_with_generics(&param.ty, &generic_vars);
+            self.binding_types.insert(param.span, ty.clone());
             self.locals.insert(
                 param.id,
                 LocalInfo {
                     ty,
                     name: param.name.clone(),
                     span: param.span,
-                    used: true, // Parameters are always "used"
+                    used: false,
+                    param: true,
                 },
             );
         }
 
         // Infer body type
-        let body_ty = self.infer_expr(&fn_def.body);
-
-        // Unify with declared return type
-        let ret_ty = self.resolve_type_with_generics(&fn_def.return_ty, &generic_vars);
-        if !self.unify(&body_ty, &ret_ty, fn_def.body.span) {
-            // Emit a more detailed error
-            self.emit(
-                TypeMismatchError::new(ret_ty, body_ty, fn_def.body.span)
-                    .with_context("function return type")
-                    .build(),
-            );
+        let body_ty = self.infer_expr_ty(&fn_def.body);
+
+        // Unify with declared return type. Top-level lets are values, so
+        // their body must match the type other items already see.
+        // 与声明的返回类型合一。顶层 let 是值，
+        // 因此其主体必须与其他项所看到的类型一致。
+        let ret_ty = match self.globals.get(&id) {
+            Some(ty) if fn_def.is_let => ty.clone(),
+            _ => self.resolve_type_with_generics(&fn_def.return_ty, &generic_vars),
+        };
+        if let Err(err) = self.try_unify(&body_ty, &ret_ty, fn_def.body.span) {
+            let mut mismatch = self.mismatch(&body_ty, &ret_ty, err, fn_def.body.span);
+            // Top-level lets are plain values
+            // 顶层 let 是普通值
+            if !fn_def.is_let {
+                mismatch = mismatch.with_context("function return type");
+            }
+            if let Some(origin) = Self::annotation_span(&fn_def.return_ty) {
+                mismatch = mismatch.with_expected_span(origin);
+            }
+            self.emit(mismatch.build());
+        }
+
+        // Record the inferred item type
+        let item_ty = if fn_def.is_let {
+            self.apply(&ret_ty)
+        } else {
+            let param_tys = fn_def
+                .params
+                .iter()
+                .map(|p| {
+                    let ty = self.get_local(&p.id).unwrap_or_else(|| p.ty.clone());
+                    self.apply(&ty)
+                })
+                .collect();
+            Ty {
+                kind: TyKind::Fn(param_tys, Box::new(self.apply(&ret_ty))),
+                span,
+            }
+        };
+
+        // Later uses see the inferred type, generalized so that e.g. a
+        // function reading `r.name` accepts any record with that field
+        // 后续使用看到的是推断出的类型，并经过泛化，
+        // 例如读取 `r.name` 的函数可以接受任何具有该字段的记录
+        if !fn_def.is_let && fn_def.generics.is_empty() {
+            self.globals.insert(id, generalize(&item_ty, &[]));
         }
+        self.item_types.insert(span, item_ty);
 
         // Check for unused variables before clearing
         self.check_unused_locals();
@@ -613,17 +1005,30 @@ impl TypeChecker {
         }
     }
 
-    fn infer_expr(&mut self, expr: &Expr) -> Ty {
+    /// Infer the type of an expression and record it by span.
+    /// 推断表达式的类型并按位置信息记录。
+    fn infer_expr_ty(&mut self, expr: &Expr) -> Ty {
+        let ty = self.infer_expr_kind(expr);
+        self.expr_types.insert(expr.span, ty.clone());
+        ty
+    }
+
+    fn infer_expr_kind(&mut self, expr: &Expr) -> Ty {
         let span = expr.span;
         match &expr.kind {
             ExprKind::Literal(lit) => self.infer_literal(lit),
 
             ExprKind::Var(local_id) => {
                 self.mark_used(*local_id);
-                self.get_local(local_id).unwrap_or_else(|| {
-                    self.emit(unbound_variable("variable", span, None));
-                    self.fresh_var()
-                })
+                match self.get_local(local_id) {
+                    // Instantiate let-polymorphic locals with fresh type variables
+                    // 用新类型变量实例化 let 多态的局部变量
+                    Some(ty) => instantiate(&ty, &mut || self.fresh_var()),
+                    None => {
+                        self.emit(unbound_variable("variable", span, None));
+                        self.fresh_var()
+                    }
+                }
             }
 
             ExprKind::Global(def_id) => {
@@ -635,7 +1040,15 @@ impl TypeChecker {
                         instantiate(&ty, &mut || self.fresh_var())
                     })
                     .unwrap_or_else(|| {
-                        self.error(span, "undefined global");
+                        // Names the resolver couldn't bind are reported by it
+                        // 解析器无法绑定的名称由解析器报告
+                        if def_id.0 != u32::MAX {
+                            self.emit(
+                                Diagnostic::error(DiagnosticKind::Type, span, "undefined global")
+                                    .with_code(ErrorCode::TypeMismatch)
+                                    .with_label(Label::new(span, "no type is known for this name")),
+                            );
+                        }
                         self.fresh_var()
                     })
             }
@@ -643,8 +1056,11 @@ impl TypeChecker {
             ExprKind::List(items) => {
                 let elem_ty = self.fresh_var();
                 for item in items {
-                    let item_ty = self.infer_expr(item);
-                    self.unify(&elem_ty, &item_ty, item.span);
+                    let item_ty = self.infer_expr_ty(item);
+                    // Later items are expected to match the first one
+                    // 后续元素应与第一个元素的类型一致
+                    let origin = items.first().map(|first| first.span);
+                    self.unify_expecting(&item_ty, &elem_ty, item.span, origin);
                 }
                 // For now, represent List<T> as a named type
                 Ty {
@@ -654,7 +1070,7 @@ impl TypeChecker {
             }
 
             ExprKind::Tuple(items) => {
-                let elem_tys: Vec<Ty> = items.iter().map(|e| self.infer_expr(e)).collect();
+                let elem_tys: Vec<Ty> = items.iter().map(|e| self.infer_expr_ty(e)).collect();
                 Ty {
                     kind: TyKind::Tuple(elem_tys),
                     span,
@@ -664,7 +1080,7 @@ impl TypeChecker {
             ExprKind::Record(fields) => {
                 let field_tys: Vec<(String, Ty)> = fields
                     .iter()
-                    .map(|(name, e)| (name.clone(), self.infer_expr(e)))
+                    .map(|(name, e)| (name.clone(), self.infer_expr_ty(e)))
                     .collect();
                 Ty {
                     kind: TyKind::Record(field_tys),
@@ -678,6 +1094,7 @@ impl TypeChecker {
                     .iter()
                     .map(|p| {
                         let ty = self.resolve_type(&p.ty);
+                        self.binding_types.insert(p.span, ty.clone());
                         self.locals.insert(
                             p.id,
                             LocalInfo {
@@ -685,6 +1102,7 @@ impl TypeChecker {
                                 name: p.name.clone(),
                                 span: p.span,
                                 used: true, // Lambda params considered used
+                                param: false,
                             },
                         );
                         ty
@@ -692,7 +1110,7 @@ impl TypeChecker {
                     .collect();
 
                 // Infer body
-                let body_ty = self.infer_expr(body);
+                let body_ty = self.infer_expr_ty(body);
 
                 // Remove locals
                 for p in params {
@@ -706,8 +1124,60 @@ impl TypeChecker {
             }
 
             ExprKind::Call(func, args) => {
-                let func_ty = self.infer_expr(func);
-                let arg_tys: Vec<Ty> = args.iter().map(|a| self.infer_expr(a)).collect();
+                if let ExprKind::Global(id) = &func.kind
+                    && self.structs.contains_key(id)
+                {
+                    return self.infer_struct_construction(*id, args, span);
+                }
+
+                let func_ty = self.infer_expr_ty(func);
+                let arg_tys: Vec<Ty> = args.iter().map(|a| self.infer_expr_ty(a)).collect();
+
+                // Check each argument against its parameter so a mismatch
+                // points at that argument and at the parameter's declaration
+                // 逐个将实参与形参比较，使不匹配指向该实参及形参声明处
+                let callee = match &func.kind {
+                    ExprKind::Global(id) => Some(*id),
+                    _ => None,
+                };
+                if let TyKind::Fn(param_tys, ret) = &self.apply(&func_ty).kind {
+                    // Supplying some but not all arguments curries the call
+                    // into a function of the remaining parameters, as in the
+                    // evaluator
+                    // 只提供部分参数时，调用被柯里化为接收剩余参数的函数，与求值器一致
+                    let supplied = args.len();
+                    if supplied > param_tys.len() || (supplied == 0 && !param_tys.is_empty()) {
+                        let fn_span = callee.and_then(|id| self.global_span(id));
+                        self.emit(wrong_arity(
+                            None,
+                            param_tys.len(),
+                            args.len(),
+                            span,
+                            fn_span,
+                        ));
+                        return self.apply(ret);
+                    }
+                    let param_spans = callee
+                        .and_then(|id| self.param_spans.get(&id).cloned())
+                        .unwrap_or_default();
+                    for (i, ((arg, arg_ty), param_ty)) in
+                        args.iter().zip(&arg_tys).zip(param_tys).enumerate()
+                    {
+                        let origin = param_spans.get(i).copied();
+                        self.unify_expecting(arg_ty, param_ty, arg.span, origin);
+                    }
+                    if supplied < param_tys.len() {
+                        let remaining = param_tys[supplied..]
+                            .iter()
+                            .map(|ty| self.apply(ty))
+                            .collect();
+                        return Ty {
+                            kind: TyKind::Fn(remaining, Box::new(self.apply(ret))),
+                            span,
+                        };
+                    }
+                    return self.apply(ret);
+                }
 
                 let ret_ty = self.fresh_var();
                 let expected_fn_ty = Ty {
@@ -720,7 +1190,7 @@ impl TypeChecker {
             }
 
             ExprKind::Field(base, field) => {
-                let base_ty = self.infer_expr(base);
+                let base_ty = self.infer_expr_ty(base);
                 let base_ty = self.apply(&base_ty);
 
                 match &base_ty.kind {
@@ -733,6 +1203,17 @@ impl TypeChecker {
                         self.error(span, format!("no field '{}' in record", field));
                         self.fresh_var()
                     }
+                    // The base only needs to have this field: constrain it to
+                    // an open record with the field and an unknown rest
+                    // 基值只需具有该字段：将其约束为包含该字段及未知剩余部分的开放记录
+                    TyKind::Var(_) | TyKind::OpenRecord(..) => {
+                        let field_ty = self.fresh_var();
+                        let row = self
+                            .infer
+                            .open_record(vec![(field.clone(), field_ty.clone())]);
+                        self.unify(&base_ty, &row, span);
+                        self.apply(&field_ty)
+                    }
                     _ => {
                         self.error(span, "field access on non-record type");
                         self.fresh_var()
@@ -741,7 +1222,7 @@ impl TypeChecker {
             }
 
             ExprKind::TupleIndex(base, index) => {
-                let base_ty = self.infer_expr(base);
+                let base_ty = self.infer_expr_ty(base);
                 let base_ty = self.apply(&base_ty);
 
                 match &base_ty.kind {
@@ -765,29 +1246,58 @@ impl TypeChecker {
             ExprKind::Unary(op, operand) => self.infer_unary(*op, operand, span),
 
             ExprKind::If(cond, then_br, else_br) => {
-                let cond_ty = self.infer_expr(cond);
-                self.unify(
-                    &cond_ty,
-                    &Ty {
-                        kind: TyKind::Bool,
-                        span: cond.span,
-                    },
-                    cond.span,
-                );
-
-                let then_ty = self.infer_expr(then_br);
-                let else_ty = self.infer_expr(else_br);
-                self.unify(&then_ty, &else_ty, span);
+                let cond_ty = self.infer_expr_ty(cond);
+                self.expect_bool(&cond_ty, "`if`", cond.span);
+
+                let then_ty = self.infer_expr_ty(then_br);
+                let else_ty = self.infer_expr_ty(else_br);
+                // The `()` standing in for a missing `else` spans nothing
+                // 代替缺失 `else` 的 `()` 跨度为空
+                let has_else = !(matches!(else_br.kind, ExprKind::Literal(Literal::Unit))
+                    && else_br.span.is_empty());
+                if self.try_unify(&else_ty, &then_ty, else_br.span).is_err() {
+                    let then_ty = self.apply(&then_ty);
+                    let else_ty = self.apply(&else_ty);
+                    if has_else {
+                        self.emit(if_branch_mismatch(
+                            &then_ty,
+                            &else_ty,
+                            span,
+                            then_br.span,
+                            else_br.span,
+                        ));
+                    } else {
+                        self.emit(if_without_else(&then_ty, span, then_br.span));
+                    }
+                }
 
                 self.apply(&then_ty)
             }
 
-            ExprKind::Match(scrutinee, arms) => {
-                let scrutinee_ty = self.infer_expr(scrutinee);
+            ExprKind::Match(scrutinee, arms, desugared) => {
+                let scrutinee_ty = self.infer_expr_ty(scrutinee);
                 let result_ty = self.fresh_var();
 
-                for arm in arms {
-                    self.check_arm(arm, &scrutinee_ty, &result_ty);
+                for (index, arm) in arms.iter().enumerate() {
+                    let body_ty = self.check_arm(arm, &scrutinee_ty);
+                    if self.try_unify(&body_ty, &result_ty, arm.body.span).is_err() {
+                        let first_ty = self.apply(&result_ty);
+                        let body_ty = self.apply(&body_ty);
+                        self.emit(match_arm_mismatch(
+                            &first_ty,
+                            &body_ty,
+                            span,
+                            arms[0].body.span,
+                            arm.body.span,
+                            index,
+                        ));
+                    }
+                }
+
+                // Matches desugared from `??` and `?` are partial on purpose
+                // 由 `??` 和 `?` 解糖得到的 match 有意不完整
+                if !desugared {
+                    self.check_match_coverage(arms, &scrutinee_ty, span);
                 }
 
                 self.apply(&result_ty)
@@ -799,7 +1309,7 @@ impl TypeChecker {
                 }
 
                 if let Some(e) = expr {
-                    self.infer_expr(e)
+                    self.infer_expr_ty(e)
                 } else {
                     Ty {
                         kind: TyKind::Unit,
@@ -814,7 +1324,7 @@ impl TypeChecker {
                     if let neve_hir::StringPart::Expr(e) = part {
                         // We don't constrain the type of interpolated expressions
                         // Any type can be converted to string
-                        let _ = self.infer_expr(e);
+                        let _ = self.infer_expr_ty(e);
                     }
                 }
                 // Interpolated strings always have type String
@@ -842,20 +1352,20 @@ impl TypeChecker {
     }
 
     fn infer_binary(&mut self, op: BinOp, left: &Expr, right: &Expr, span: Span) -> Ty {
-        let left_ty = self.infer_expr(left);
-        let right_ty = self.infer_expr(right);
+        let left_ty = self.infer_expr_ty(left);
+        let right_ty = self.infer_expr_ty(right);
 
         match op {
             // Arithmetic: Int -> Int -> Int or Float -> Float -> Float
             BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div | BinOp::Mod | BinOp::Pow => {
-                self.unify(&left_ty, &right_ty, span);
+                self.unify_operands(op, (&left_ty, left.span), (&right_ty, right.span), span);
                 // For now, assume numeric types
                 self.apply(&left_ty)
             }
 
             // Comparison: a -> a -> Bool
             BinOp::Eq | BinOp::Ne | BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge => {
-                self.unify(&left_ty, &right_ty, span);
+                self.unify_operands(op, (&left_ty, left.span), (&right_ty, right.span), span);
                 Ty {
                     kind: TyKind::Bool,
                     span,
@@ -888,7 +1398,7 @@ impl TypeChecker {
 
             // Concat: [a] -> [a] -> [a] or String -> String -> String
             BinOp::Concat => {
-                self.unify(&left_ty, &right_ty, span);
+                self.unify_operands(op, (&left_ty, left.span), (&right_ty, right.span), span);
                 self.apply(&left_ty)
             }
 
@@ -912,7 +1422,7 @@ impl TypeChecker {
     }
 
     fn infer_unary(&mut self, op: UnaryOp, operand: &Expr, span: Span) -> Ty {
-        let operand_ty = self.infer_expr(operand);
+        let operand_ty = self.infer_expr_ty(operand);
 
         match op {
             UnaryOp::Neg => {
@@ -920,14 +1430,14 @@ impl TypeChecker {
                 self.apply(&operand_ty)
             }
             UnaryOp::Not => {
-                self.unify(
-                    &operand_ty,
-                    &Ty {
-                        kind: TyKind::Bool,
-                        span: operand.span,
-                    },
-                    operand.span,
-                );
+                let bool_ty = Ty {
+                    kind: TyKind::Bool,
+                    span: operand.span,
+                };
+                if self.try_unify(&operand_ty, &bool_ty, operand.span).is_err() {
+                    let operand_ty = self.apply(&operand_ty);
+                    self.emit(unary_op_mismatch(&op, &operand_ty, span, operand.span));
+                }
                 Ty {
                     kind: TyKind::Bool,
                     span,
@@ -936,26 +1446,45 @@ impl TypeChecker {
         }
     }
 
-    fn check_arm(&mut self, arm: &MatchArm, scrutinee_ty: &Ty, result_ty: &Ty) {
+    /// Unify both operands of a binary operator, reporting a mismatch
+    /// against the operator.
+    /// 合一二元运算符的两个操作数，不匹配时针对该运算符报告。
+    fn unify_operands(&mut self, op: BinOp, left: (&Ty, Span), right: (&Ty, Span), span: Span) {
+        if self.try_unify(right.0, left.0, span).is_err() {
+            let left_ty = self.apply(left.0);
+            let right_ty = self.apply(right.0);
+            self.emit(binary_op_mismatch(
+                &op, &left_ty, &right_ty, span, left.1, right.1,
+            ));
+        }
+    }
+
+    /// Require a condition to be a Bool.
+    /// 要求条件为 Bool。
+    fn expect_bool(&mut self, ty: &Ty, context: &str, span: Span) {
+        let bool_ty = Ty {
+            kind: TyKind::Bool,
+            span,
+        };
+        if self.try_unify(ty, &bool_ty, span).is_err() {
+            let ty = self.apply(ty);
+            self.emit(non_bool_condition(&ty, context, span));
+        }
+    }
+
+    /// Check a match arm and return the type of its body.
+    /// 检查 match 分支并返回其主体的类型。
+    fn check_arm(&mut self, arm: &MatchArm, scrutinee_ty: &Ty) -> Ty {
         // Check pattern against scrutinee type
         self.check_pattern(&arm.pattern, scrutinee_ty);
 
         // Check guard if present
         if let Some(guard) = &arm.guard {
-            let guard_ty = self.infer_expr(guard);
-            self.unify(
-                &guard_ty,
-                &Ty {
-                    kind: TyKind::Bool,
-                    span: guard.span,
-                },
-                guard.span,
-            );
+            let guard_ty = self.infer_expr_ty(guard);
+            self.expect_bool(&guard_ty, "match guard", guard.span);
         }
 
-        // Check body and unify with result type
-        let body_ty = self.infer_expr(&arm.body);
-        self.unify(&body_ty, result_ty, arm.body.span);
+        self.infer_expr_ty(&arm.body)
     }
 
     fn check_pattern(&mut self, pattern: &Pattern, expected: &Ty) {
@@ -991,35 +1520,87 @@ impl TypeChecker {
             }
 
             PatternKind::Record(fields) => {
-                for (name, pat) in fields {
-                    let field_ty = match &expected.kind {
-                        TyKind::Record(field_tys) => field_tys
-                            .iter()
-                            .find(|(n, _)| n == name)
-                            .map(|(_, t)| t.clone()),
-                        _ => None,
-                    };
-
-                    if let Some(ty) = field_ty {
+                let expected = self.apply(expected);
+                if let TyKind::Record(field_tys) = &expected.kind {
+                    for (name, pat) in fields {
+                        match field_tys.iter().find(|(n, _)| n == name) {
+                            Some((_, ty)) => self.check_pattern(pat, &ty.clone()),
+                            None => {
+                                self.error(pattern.span, format!("no field '{}' in record", name))
+                            }
+                        }
+                    }
+                } else {
+                    // Record patterns ignore fields they do not name, so the
+                    // scrutinee only needs the named ones
+                    // 记录模式忽略未列出的字段，因此被匹配值只需具有所列字段
+                    let field_tys: Vec<(String, Ty)> = fields
+                        .iter()
+                        .map(|(name, _)| (name.clone(), self.fresh_var()))
+                        .collect();
+                    let row = self.infer.open_record(field_tys.clone());
+                    self.unify(&expected, &row, pattern.span);
+                    for ((_, pat), (_, ty)) in fields.iter().zip(&field_tys) {
+                        let ty = self.apply(ty);
                         self.check_pattern(pat, &ty);
-                    } else {
-                        self.error(pattern.span, format!("no field '{}' in record", name));
                     }
                 }
             }
 
-            PatternKind::Constructor(def_id, patterns) => {
-                // Look up constructor signature from enum definitions
-                // 从枚举定义中查找构造函数签名
+            PatternKind::ListRest(init, rest, tail) => {
+                let elem_ty = self.fresh_var();
+                for pat in init.iter().chain(tail) {
+                    self.check_pattern(pat, &elem_ty);
+                }
+                if let Some(rest) = rest {
+                    let rest_ty = self.fresh_var();
+                    self.check_pattern(rest, &rest_ty);
+                }
+            }
+
+            PatternKind::Or(patterns) => {
+                for pat in patterns {
+                    self.check_pattern(pat, expected);
+                }
+            }
+
+            PatternKind::Binding(local_id, name, inner) => {
+                self.define_local(*local_id, name.clone(), expected.clone(), pattern.span);
+                self.check_pattern(inner, expected);
+            }
+
+            PatternKind::Constructor(def_id, name, patterns) => {
+                // A variant's constructor type ties its fields to the matched enum
+                // 变体的构造器类型将其字段与被匹配的枚举关联
+                if let Some(ctor_ty) = self.variant_constructor(*def_id, name, expected) {
+                    let ctor_ty = instantiate(&ctor_ty, &mut || self.fresh_var());
+                    let (fields, result) = match ctor_ty.kind {
+                        TyKind::Fn(fields, result) => (fields, *result),
+                        _ => (Vec::new(), ctor_ty),
+                    };
+                    if fields.len() == patterns.len() {
+                        self.unify(expected, &result, pattern.span);
+                        for (pat, ty) in patterns.iter().zip(&fields) {
+                            self.check_pattern(pat, ty);
+                        }
+                        return;
+                    }
+                }
+
+                // Look up the variant by name in the enum the pattern resolved to
+                // 在模式解析到的枚举中按名称查找变体
                 // Clone field types to avoid borrow conflict
                 // 克隆字段类型以避免借用冲突
-                let field_types: Option<Vec<Ty>> = self.enums.get(def_id).and_then(|enum_info| {
-                    enum_info
-                        .variants
-                        .iter()
-                        .find(|(_, fields)| fields.len() == patterns.len())
-                        .map(|(_, fields)| fields.clone())
-                });
+                let field_types: Option<Vec<Ty>> = self
+                    .pattern_enum(*def_id)
+                    .and_then(|id| self.enums.get(&id))
+                    .and_then(|info| {
+                        info.variants
+                            .iter()
+                            .find(|(variant, _)| variant == name)
+                            .map(|(_, fields)| fields.clone())
+                    })
+                    .filter(|fields| fields.len() == patterns.len());
 
                 if let Some(types) = field_types {
                     for (pat, ty) in patterns.iter().zip(types.iter()) {
@@ -1037,12 +1618,137 @@ impl TypeChecker {
         }
     }
 
+    /// The constructor type of the variant named `name`, looked up in the
+    /// matched value's enum first and then in the enum the pattern resolved to.
+    /// 名为 `name` 的变体的构造器类型，先在被匹配值的枚举中查找，
+    /// 再在模式解析到的枚举中查找。
+    fn variant_constructor(&self, def_id: DefId, name: &str, expected: &Ty) -> Option<Ty> {
+        let scrutinee_enum = match self.apply(expected).kind {
+            TyKind::Named(id, _) => Some(id),
+            _ => None,
+        };
+        scrutinee_enum
+            .into_iter()
+            .chain(self.pattern_enum(def_id))
+            .filter_map(|id| self.enums.get(&id))
+            .find_map(|info| {
+                let index = info
+                    .variants
+                    .iter()
+                    .position(|(variant, _)| variant == name)?;
+                self.globals.get(info.variant_ids.get(index)?).cloned()
+            })
+    }
+
+    /// The enum a constructor pattern resolved to, either directly through
+    /// its path or through one of the enum's variants.
+    /// 构造器模式解析到的枚举，可直接通过其路径，也可通过该枚举的某个变体。
+    fn pattern_enum(&self, def_id: DefId) -> Option<DefId> {
+        if self.enums.contains_key(&def_id) {
+            return Some(def_id);
+        }
+        self.enums
+            .iter()
+            .find(|(_, info)| info.variant_ids.contains(&def_id))
+            .map(|(&id, _)| id)
+    }
+
+    /// Collect the enums that constructor patterns in `pattern` resolved to.
+    /// 收集 `pattern` 中构造器模式解析到的枚举。
+    fn collect_pattern_enums(&self, pattern: &Pattern, out: &mut Vec<DefId>) {
+        match &pattern.kind {
+            PatternKind::Constructor(def_id, _, patterns) => {
+                if let Some(id) = self.pattern_enum(*def_id)
+                    && !out.contains(&id)
+                {
+                    out.push(id);
+                }
+                for pat in patterns {
+                    self.collect_pattern_enums(pat, out);
+                }
+            }
+            PatternKind::Tuple(patterns)
+            | PatternKind::List(patterns)
+            | PatternKind::Or(patterns) => {
+                for pat in patterns {
+                    self.collect_pattern_enums(pat, out);
+                }
+            }
+            PatternKind::ListRest(init, rest, tail) => {
+                for pat in init.iter().chain(rest.as_deref()).chain(tail) {
+                    self.collect_pattern_enums(pat, out);
+                }
+            }
+            PatternKind::Record(fields) => {
+                for (_, pat) in fields {
+                    self.collect_pattern_enums(pat, out);
+                }
+            }
+            PatternKind::Binding(_, _, inner) => self.collect_pattern_enums(inner, out),
+            PatternKind::Wildcard | PatternKind::Var(..) | PatternKind::Literal(_) => {}
+        }
+    }
+
+    /// Warn about values no arm matches and about arms that can never match.
+    /// 对没有分支匹配的值以及永远无法匹配的分支发出警告。
+    ///
+    /// Only the enums the arms name are known to the analysis, with the
+    /// scrutinee's own enum registered last so its variants win over
+    /// same-named ones.
+    /// 分析只知道各分支所指的枚举，被匹配值自身的枚举最后注册，
+    /// 因此其变体优先于同名变体。
+    fn check_match_coverage(&mut self, arms: &[MatchArm], scrutinee_ty: &Ty, span: Span) {
+        let mut families = Vec::new();
+        for arm in arms {
+            self.collect_pattern_enums(&arm.pattern, &mut families);
+        }
+        if let TyKind::Named(def_id, _) = self.apply(scrutinee_ty).kind
+            && self.enums.contains_key(&def_id)
+        {
+            families.retain(|&id| id != def_id);
+            families.push(def_id);
+        }
+
+        let mut table = VariantTable::new();
+        for id in families {
+            table.add_enum(
+                self.enums[&id]
+                    .variants
+                    .iter()
+                    .map(|(name, fields)| (name.clone(), fields.len()))
+                    .collect(),
+            );
+        }
+
+        let rows: Vec<(&Pattern, bool)> = arms
+            .iter()
+            .map(|arm| (&arm.pattern, arm.guard.is_some()))
+            .collect();
+        let report = check_match(&rows, &table);
+
+        for (index, previous) in report.unreachable {
+            let pattern_span = arms[index].pattern.span;
+            self.emit(match previous {
+                Some(previous) => unreachable_pattern(pattern_span, arms[previous].pattern.span),
+                None => redundant_pattern(pattern_span),
+            });
+        }
+        if !report.missing.is_empty() {
+            self.emit(non_exhaustive_match(&report.missing, span));
+        }
+    }
+
     fn check_stmt(&mut self, stmt: &Stmt) {
         match &stmt.kind {
             StmtKind::Let(local_id, name, ty, value) => {
-                let value_ty = self.infer_expr(value);
+                let value_ty = self.infer_expr_ty(value);
                 let declared_ty = self.resolve_type(ty);
-                self.unify(&value_ty, &declared_ty, value.span);
+                self.unify_expecting(
+                    &value_ty,
+                    &declared_ty,
+                    value.span,
+                    Self::annotation_span(ty),
+                );
 
                 // Generalize the type for let-polymorphism
                 // Collect environment type variables that shouldn't be generalized
@@ -1055,7 +1761,7 @@ impl TypeChecker {
                 self.define_local(*local_id, name.clone(), generalized_ty, stmt.span);
             }
             StmtKind::Expr(e) => {
-                self.infer_expr(e);
+                self.infer_expr_ty(e);
             }
         }
     }
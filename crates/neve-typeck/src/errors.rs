@@ -9,6 +9,8 @@ use neve_common::Span;
 use neve_diagnostic::{Diagnostic, DiagnosticKind, ErrorCode, Label};
 use neve_hir::{BinOp, Ty, TyKind, UnaryOp};
 
+use crate::unify::{Substitution, free_type_vars};
+
 /// Format a type for display in error messages.
 /// 格式化类型以在错误信息中显示。
 pub fn format_type(ty: &Ty) -> String {
@@ -21,6 +23,11 @@ pub fn format_type(ty: &Ty) -> String {
         TyKind::Unit => "()".to_string(),
         TyKind::Var(id) => format!("?{}", id),
         TyKind::Param(_, name) => name.clone(),
+        // Lists are represented as a named type with a reserved id
+        // 列表表示为带有保留 ID 的命名类型
+        TyKind::Named(def_id, args) if def_id.0 == u32::MAX && args.len() == 1 => {
+            format!("[{}]", format_type(&args[0]))
+        }
         TyKind::Named(def_id, args) => {
             if args.is_empty() {
                 format!("Type#{}", def_id.0)
@@ -38,7 +45,15 @@ pub fn format_type(ty: &Ty) -> String {
                 .iter()
                 .map(|(name, ty)| format!("{}: {}", name, format_type(ty)))
                 .collect();
-            format!("{{ {} }}", parts.join(", "))
+            format!("#{{ {} }}", parts.join(", "))
+        }
+        TyKind::OpenRecord(fields, _) => {
+            let mut parts: Vec<_> = fields
+                .iter()
+                .map(|(name, ty)| format!("{}: {}", name, format_type(ty)))
+                .collect();
+            parts.push("..".to_string());
+            format!("#{{ {} }}", parts.join(", "))
         }
         TyKind::Fn(params, ret) => {
             let params_str: Vec<_> = params.iter().map(format_type).collect();
@@ -93,6 +108,7 @@ pub struct TypeMismatchError {
     context: Option<String>,
     expected_span: Option<Span>,
     found_span: Option<Span>,
+    notes: Vec<String>,
 }
 
 impl TypeMismatchError {
@@ -104,6 +120,7 @@ impl TypeMismatchError {
             context: None,
             expected_span: None,
             found_span: None,
+            notes: Vec::new(),
         }
     }
 
@@ -122,6 +139,11 @@ impl TypeMismatchError {
         self
     }
 
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
     pub fn build(self) -> Diagnostic {
         let expected_str = format_type(&self.expected);
         let found_str = format_type(&self.found);
@@ -155,6 +177,10 @@ impl TypeMismatchError {
             ));
         }
 
+        for note in self.notes {
+            diag = diag.with_note(note);
+        }
+
         // Add helpful notes based on types
         diag = add_type_mismatch_help(diag, &self.expected, &self.found);
 
@@ -190,24 +216,24 @@ fn add_type_mismatch_help(mut diag: Diagnostic, expected: &Ty, found: &Ty) -> Di
             diag = diag.with_note("this expression does not return a value");
         }
         // Function mismatch
-        (TyKind::Fn(exp_params, _), TyKind::Fn(found_params, _)) => {
-            if exp_params.len() != found_params.len() {
-                diag = diag.with_note(format!(
-                    "expected function with {} parameter(s), found function with {} parameter(s)",
-                    exp_params.len(),
-                    found_params.len()
-                ));
-            }
+        (TyKind::Fn(exp_params, _), TyKind::Fn(found_params, _))
+            if exp_params.len() != found_params.len() =>
+        {
+            diag = diag.with_note(format!(
+                "expected function with {} parameter(s), found function with {} parameter(s)",
+                exp_params.len(),
+                found_params.len()
+            ));
         }
         // Tuple size mismatch
-        (TyKind::Tuple(exp_elems), TyKind::Tuple(found_elems)) => {
-            if exp_elems.len() != found_elems.len() {
-                diag = diag.with_note(format!(
-                    "expected tuple with {} element(s), found tuple with {} element(s)",
-                    exp_elems.len(),
-                    found_elems.len()
-                ));
-            }
+        (TyKind::Tuple(exp_elems), TyKind::Tuple(found_elems))
+            if exp_elems.len() != found_elems.len() =>
+        {
+            diag = diag.with_note(format!(
+                "expected tuple with {} element(s), found tuple with {} element(s)",
+                exp_elems.len(),
+                found_elems.len()
+            ));
         }
         _ => {}
     }
@@ -248,6 +274,23 @@ pub fn if_branch_mismatch(
     )
 }
 
+/// Create an error for an `if` without `else` whose branch is not `()`.
+/// 创建不带 `else` 且分支类型不是 `()` 的 `if` 的错误。
+pub fn if_without_else(then_ty: &Ty, if_span: Span, then_span: Span) -> Diagnostic {
+    Diagnostic::error(
+        DiagnosticKind::Type,
+        if_span,
+        "`if` without `else` must have type `()`",
+    )
+    .with_code(ErrorCode::IfBranchMismatch)
+    .with_label(Label::new(
+        then_span,
+        format!("this branch has type `{}`", format_type(then_ty)),
+    ))
+    .with_note("without an `else`, the `if` evaluates to `()` when the condition is false")
+    .with_help("add an `else` branch that produces a value of the same type")
+}
+
 /// Create an error for match arm type mismatch.
 /// 创建 match 分支类型不匹配的错误。
 pub fn match_arm_mismatch(
@@ -513,17 +556,50 @@ pub fn missing_method(method: &str, trait_name: &str, impl_ty: &Ty, span: Span)
 
 /// Create an error for infinite type.
 /// 创建无限类型的错误（类型变量出现在自身类型中）。
+///
+/// Type variables are renamed to `a`, `b`, ..., starting with `var`, so the
+/// message reads like `a = (a) -> b`.
+/// 类型变量被重命名为 `a`、`b`……（从 `var` 开始），使信息形如 `a = (a) -> b`。
 pub fn infinite_type(var: u32, ty: &Ty, span: Span) -> Diagnostic {
-    let ty_str = format_type(ty);
+    let mut vars = vec![var];
+    vars.extend(free_type_vars(ty).into_iter().filter(|&v| v != var));
 
-    Diagnostic::error(DiagnosticKind::Type, span, "cannot construct infinite type")
-        .with_code(ErrorCode::InfiniteType)
-        .with_label(Label::new(
-            span,
-            format!("type variable `?{}` occurs in `{}`", var, ty_str),
-        ))
-        .with_note("this would create a recursive type that is infinite in size")
-        .with_help("consider using an explicit type annotation or restructuring your code")
+    let mut names = Substitution::new();
+    for (idx, &v) in vars.iter().enumerate() {
+        let name = if idx < 26 {
+            ((b'a' + idx as u8) as char).to_string()
+        } else {
+            format!("t{}", idx)
+        };
+        names.extend(
+            v,
+            Ty {
+                kind: TyKind::Param(idx as u32, name),
+                span: ty.span,
+            },
+        );
+    }
+    let var_str = format_type(&names.apply(&Ty {
+        kind: TyKind::Var(var),
+        span,
+    }));
+    let ty_str = format_type(&names.apply(ty));
+
+    Diagnostic::error(
+        DiagnosticKind::Type,
+        span,
+        format!(
+            "cannot construct the infinite type `{} = {}`",
+            var_str, ty_str
+        ),
+    )
+    .with_code(ErrorCode::InfiniteType)
+    .with_label(Label::new(
+        span,
+        format!("`{}` would have to contain itself", var_str),
+    ))
+    .with_note("this would create a recursive type that is infinite in size")
+    .with_help("consider using an explicit type annotation or restructuring your code")
 }
 
 /// Create an error for pattern type mismatch.
@@ -544,26 +620,39 @@ pub fn pattern_mismatch(expected: &Ty, pattern_ty: &Ty, span: Span) -> Diagnosti
         .with_note("the pattern must match the type of the value being matched")
 }
 
-/// Create an error for non-exhaustive pattern match.
-/// 创建模式匹配不完整的错误。
+/// Create a warning for non-exhaustive pattern match.
+/// 创建模式匹配不完整的警告。
 pub fn non_exhaustive_match(missing_patterns: &[String], span: Span) -> Diagnostic {
     let patterns_str = missing_patterns.join(", ");
 
-    Diagnostic::error(DiagnosticKind::Type, span, "non-exhaustive pattern match")
+    Diagnostic::warning(DiagnosticKind::Type, span, "non-exhaustive pattern match")
         .with_code(ErrorCode::NonExhaustiveMatch)
         .with_label(Label::new(span, "patterns not covered"))
         .with_note(format!("missing patterns: {}", patterns_str))
         .with_help("add a wildcard pattern `_` or handle all cases explicitly")
 }
 
-/// Create an error for unreachable pattern.
-/// 创建不可达模式的警告。
+/// Create a warning for a pattern subsumed by an earlier one.
+/// 创建被先前模式包含的不可达模式的警告。
 pub fn unreachable_pattern(span: Span, previous_span: Span) -> Diagnostic {
     Diagnostic::warning(DiagnosticKind::Type, span, "unreachable pattern")
+        .with_code(ErrorCode::UnreachablePattern)
         .with_label(Label::new(span, "this pattern will never be matched"))
         .with_label(Label::new(
             previous_span,
-            "previous pattern matches all values",
+            "previous pattern already matches these values",
+        ))
+        .with_help("remove this pattern or reorder the match arms")
+}
+
+/// Create a warning for a pattern covered by several earlier ones together.
+/// 创建被多个先前模式共同覆盖的冗余模式的警告。
+pub fn redundant_pattern(span: Span) -> Diagnostic {
+    Diagnostic::warning(DiagnosticKind::Type, span, "unreachable pattern")
+        .with_code(ErrorCode::UnreachablePattern)
+        .with_label(Label::new(
+            span,
+            "every value matched here is matched by earlier arms",
         ))
         .with_help("remove this pattern or reorder the match arms")
 }
@@ -614,6 +703,21 @@ pub fn unused_variable(name: &str, span: Span) -> Diagnostic {
     ))
 }
 
+/// Create a warning for an unused function parameter.
+/// 创建未使用函数参数的警告。
+pub fn unused_parameter(name: &str, span: Span) -> Diagnostic {
+    Diagnostic::warning(
+        DiagnosticKind::Type,
+        span,
+        format!("unused parameter: `{}`", name),
+    )
+    .with_label(Label::new(span, "this parameter is never used"))
+    .with_help(format!(
+        "if this is intentional, prefix the name with an underscore: `_{}`",
+        name
+    ))
+}
+
 /// Create an error for redundant type annotation.
 /// 创建冗余类型标注的警告。
 pub fn redundant_annotation(inferred: &Ty, span: Span) -> Diagnostic {
@@ -633,64 +737,7 @@ pub fn redundant_annotation(inferred: &Ty, span: Span) -> Diagnostic {
 /// Uses Levenshtein distance for fuzzy matching.
 /// 使用 Levenshtein 距离进行模糊匹配。
 pub fn find_similar_name<'a>(name: &str, candidates: &'a [String]) -> Option<&'a str> {
-    let max_distance = match name.len() {
-        0..=2 => 0, // Very short names: exact match only / 非常短的名称：仅精确匹配
-        3..=5 => 1, // Short names: 1 edit distance / 短名称：1 编辑距离
-        _ => 2,     // Longer names: 2 edit distance / 较长名称：2 编辑距离
-    };
-
-    candidates
-        .iter()
-        .filter_map(|candidate| {
-            let distance = levenshtein_distance(name, candidate);
-            if distance <= max_distance {
-                Some((candidate.as_str(), distance))
-            } else {
-                None
-            }
-        })
-        .min_by_key(|(_, d)| *d)
-        .map(|(s, _)| s)
-}
-
-/// Calculate the Levenshtein distance between two strings.
-/// 计算两个字符串之间的 Levenshtein 距离。
-fn levenshtein_distance(a: &str, b: &str) -> usize {
-    let a_chars: Vec<char> = a.chars().collect();
-    let b_chars: Vec<char> = b.chars().collect();
-    let a_len = a_chars.len();
-    let b_len = b_chars.len();
-
-    if a_len == 0 {
-        return b_len;
-    }
-    if b_len == 0 {
-        return a_len;
-    }
-
-    let mut matrix = vec![vec![0; b_len + 1]; a_len + 1];
-
-    for (i, row) in matrix.iter_mut().enumerate().take(a_len + 1) {
-        row[0] = i;
-    }
-    for (j, val) in matrix[0].iter_mut().enumerate().take(b_len + 1) {
-        *val = j;
-    }
-
-    for i in 1..=a_len {
-        for j in 1..=b_len {
-            let cost = if a_chars[i - 1] == b_chars[j - 1] {
-                0
-            } else {
-                1
-            };
-            matrix[i][j] = (matrix[i - 1][j] + 1)
-                .min(matrix[i][j - 1] + 1)
-                .min(matrix[i - 1][j - 1] + cost);
-        }
-    }
-
-    matrix[a_len][b_len]
+    neve_common::find_similar_name(name, candidates.iter().map(String::as_str))
 }
 
 /// Suggest a type conversion between two types.
@@ -889,6 +936,7 @@ pub fn return_outside_function(span: Span) -> Diagnostic {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use neve_common::levenshtein_distance;
 
     #[test]
     fn test_levenshtein_distance() {
@@ -0,0 +1,499 @@
+//! Exhaustiveness and redundancy checking for match expressions.
+//! match 表达式的穷尽性与冗余检查。
+//!
+//! This is the usefulness algorithm from Maranget's "Warnings for pattern
+//! matching": a pattern row is useful against a matrix of earlier rows if some
+//! value matches it but none of the rows. A match is exhaustive when a wildcard
+//! is not useful after all arms, and an arm is unreachable when its pattern is
+//! not useful after the arms before it.
+//! 本模块实现 Maranget《Warnings for pattern matching》中的有用性算法：
+//! 若存在某个值匹配某一模式行却不匹配矩阵中任何一行，则该行相对矩阵有用。
+//! 所有分支之后通配符不再有用时 match 是穷尽的；某分支的模式相对其之前的
+//! 分支没有用时该分支不可达。
+
+use neve_hir::{Literal, Pattern, PatternKind};
+use std::collections::HashMap;
+
+/// Maximum number of missing patterns collected for one match.
+/// 单个 match 收集的缺失模式的最大数量。
+const MAX_WITNESSES: usize = 8;
+
+/// Known constructor families, keyed by variant name.
+/// 已知的构造器族，以变体名为键。
+pub struct VariantTable {
+    /// Variant name -> all sibling variants with their arity.
+    /// 变体名 -> 所有同族变体及其元数。
+    siblings: HashMap<String, Vec<(String, usize)>>,
+}
+
+impl VariantTable {
+    /// Create a table holding the built-in `Option` and `Result` constructors.
+    /// 创建包含内置 `Option` 和 `Result` 构造器的表。
+    pub fn new() -> Self {
+        let mut table = Self {
+            siblings: HashMap::new(),
+        };
+        table.add_enum(vec![("None".to_string(), 0), ("Some".to_string(), 1)]);
+        table.add_enum(vec![("Ok".to_string(), 1), ("Err".to_string(), 1)]);
+        table
+    }
+
+    /// Register the variants of one enum, in declaration order.
+    /// 按声明顺序注册一个枚举的变体。
+    pub fn add_enum(&mut self, variants: Vec<(String, usize)>) {
+        for (name, _) in &variants {
+            self.siblings.insert(name.clone(), variants.clone());
+        }
+    }
+
+    fn arity(&self, name: &str) -> Option<usize> {
+        self.siblings
+            .get(name)?
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, arity)| *arity)
+    }
+}
+
+impl Default for VariantTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The problems found in a single match expression.
+/// 单个 match 表达式中发现的问题。
+#[derive(Debug, Default)]
+pub struct MatchReport {
+    /// Patterns describing values no arm matches.
+    /// 描述没有分支能匹配的值的模式。
+    pub missing: Vec<String>,
+    /// Unreachable arms: the arm index and, when a single earlier arm
+    /// covers it, that arm's index.
+    /// 不可达分支：分支索引，以及单独覆盖它的更早分支的索引（如有）。
+    pub unreachable: Vec<(usize, Option<usize>)>,
+}
+
+/// Analyze the arms of a match. Each arm is its pattern and whether it has a
+/// guard; guarded arms never count towards coverage.
+/// 分析 match 的分支。每个分支由模式和是否带守卫组成；带守卫的分支不计入覆盖。
+pub fn check_match(arms: &[(&Pattern, bool)], table: &VariantTable) -> MatchReport {
+    let mut report = MatchReport::default();
+    let mut matrix: Vec<Vec<Pat>> = Vec::new();
+    let mut covering: Vec<usize> = Vec::new();
+
+    for (index, (pattern, guarded)) in arms.iter().enumerate() {
+        let row = vec![Pat::from_hir(pattern)];
+        if !is_useful(&matrix, &row, table) {
+            let single = matrix
+                .iter()
+                .position(|prev| !is_useful(std::slice::from_ref(prev), &row, table))
+                .map(|i| covering[i]);
+            report.unreachable.push((index, single));
+        }
+        if !guarded {
+            matrix.push(row);
+            covering.push(index);
+        }
+    }
+
+    let mut witnesses = Vec::new();
+    missing(&matrix, 1, table, &mut witnesses);
+    report.missing = witnesses
+        .into_iter()
+        .filter_map(|mut w| w.pop())
+        .map(|w| w.to_string())
+        .collect();
+    report
+}
+
+/// A constructor of the pattern space.
+/// 模式空间中的构造器。
+#[derive(Debug, Clone, PartialEq)]
+enum Ctor {
+    Bool(bool),
+    Variant(String),
+    Tuple(usize),
+    /// A record with these fields, in column order.
+    /// 具有这些字段（按列顺序）的记录。
+    Record(Vec<String>),
+    /// A list of exactly this length.
+    /// 恰好为此长度的列表。
+    List(usize),
+    /// A list of at least this length.
+    /// 至少为此长度的列表。
+    ListAtLeast(usize),
+    /// A literal from an unbounded domain (ints, strings, ...).
+    /// 来自无界值域的字面量（整数、字符串等）。
+    Literal(String),
+}
+
+impl Ctor {
+    fn arity(&self, table: &VariantTable) -> usize {
+        match self {
+            Ctor::Bool(_) | Ctor::Literal(_) => 0,
+            Ctor::Variant(name) => table.arity(name).unwrap_or(0),
+            Ctor::Tuple(n) | Ctor::List(n) | Ctor::ListAtLeast(n) => *n,
+            Ctor::Record(fields) => fields.len(),
+        }
+    }
+}
+
+/// A pattern reduced to what matters for coverage.
+/// 化简为仅与覆盖相关的模式。
+#[derive(Debug, Clone)]
+enum Pat {
+    Wild,
+    Ctor(Ctor, Vec<Pat>),
+    Record(Vec<(String, Pat)>),
+    /// A list pattern with a rest: the patterns before and after it.
+    /// 带剩余部分的列表模式：剩余部分之前和之后的模式。
+    Slice(Vec<Pat>, Vec<Pat>),
+    Or(Vec<Pat>),
+}
+
+impl Pat {
+    fn from_hir(pattern: &Pattern) -> Self {
+        let all = |patterns: &[Pattern]| patterns.iter().map(Pat::from_hir).collect();
+        match &pattern.kind {
+            PatternKind::Wildcard | PatternKind::Var(..) => Pat::Wild,
+            PatternKind::Binding(_, _, inner) => Pat::from_hir(inner),
+            PatternKind::Literal(Literal::Bool(b)) => Pat::Ctor(Ctor::Bool(*b), Vec::new()),
+            PatternKind::Literal(Literal::Unit) => Pat::Ctor(Ctor::Tuple(0), Vec::new()),
+            PatternKind::Literal(lit) => Pat::Ctor(Ctor::Literal(format!("{lit:?}")), Vec::new()),
+            PatternKind::Tuple(patterns) => Pat::Ctor(Ctor::Tuple(patterns.len()), all(patterns)),
+            PatternKind::List(patterns) => Pat::Ctor(Ctor::List(patterns.len()), all(patterns)),
+            PatternKind::ListRest(init, _, tail) => Pat::Slice(all(init), all(tail)),
+            PatternKind::Record(fields) => Pat::Record(
+                fields
+                    .iter()
+                    .map(|(name, p)| (name.clone(), Pat::from_hir(p)))
+                    .collect(),
+            ),
+            PatternKind::Constructor(_, name, patterns) => {
+                Pat::Ctor(Ctor::Variant(name.clone()), all(patterns))
+            }
+            PatternKind::Or(patterns) => Pat::Or(all(patterns)),
+        }
+    }
+
+    /// The sub-patterns left after matching `ctor`, or `None` if this pattern
+    /// cannot match values built with it.
+    /// 匹配 `ctor` 之后剩下的子模式；若此模式不能匹配由其构造的值则为 `None`。
+    fn specialize(&self, ctor: &Ctor, arity: usize) -> Option<Vec<Pat>> {
+        match (self, ctor) {
+            (Pat::Wild, _) => Some(vec![Pat::Wild; arity]),
+            (Pat::Record(fields), Ctor::Record(names)) => Some(
+                names
+                    .iter()
+                    .map(|name| {
+                        fields
+                            .iter()
+                            .find(|(n, _)| n == name)
+                            .map(|(_, p)| p.clone())
+                            .unwrap_or(Pat::Wild)
+                    })
+                    .collect(),
+            ),
+            (Pat::Slice(init, tail), Ctor::List(len) | Ctor::ListAtLeast(len)) => {
+                let fixed = init.len() + tail.len();
+                (fixed <= *len).then(|| {
+                    let mut subs = init.clone();
+                    subs.extend(std::iter::repeat_n(Pat::Wild, len - fixed));
+                    subs.extend(tail.iter().cloned());
+                    subs
+                })
+            }
+            (Pat::Ctor(own, subs), _) if own == ctor => {
+                // A constructor used with the wrong number of arguments is
+                // already a type error; treat it as matching everything
+                // 参数数量错误的构造器已是类型错误；视其为匹配一切
+                if subs.len() == arity {
+                    Some(subs.clone())
+                } else {
+                    Some(vec![Pat::Wild; arity])
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A value shape reported as not covered.
+/// 报告为未覆盖的值形状。
+#[derive(Debug, Clone)]
+enum Witness {
+    Wild,
+    Ctor(Ctor, Vec<Witness>),
+}
+
+impl std::fmt::Display for Witness {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let join = |items: &[Witness]| {
+            items
+                .iter()
+                .map(|w| w.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        match self {
+            Witness::Wild | Witness::Ctor(Ctor::Literal(_), _) => write!(f, "_"),
+            Witness::Ctor(Ctor::Bool(b), _) => write!(f, "{b}"),
+            Witness::Ctor(Ctor::Variant(name), args) if args.is_empty() => write!(f, "{name}"),
+            Witness::Ctor(Ctor::Variant(name), args) => write!(f, "{name}({})", join(args)),
+            Witness::Ctor(Ctor::Tuple(_), args) => write!(f, "({})", join(args)),
+            Witness::Ctor(Ctor::List(_), args) => write!(f, "[{}]", join(args)),
+            Witness::Ctor(Ctor::ListAtLeast(_), args) if args.is_empty() => write!(f, "[..]"),
+            Witness::Ctor(Ctor::ListAtLeast(_), args) => write!(f, "[{}, ..]", join(args)),
+            Witness::Ctor(Ctor::Record(names), args) => {
+                let fields: Vec<String> = names
+                    .iter()
+                    .zip(args)
+                    .map(|(name, w)| format!("{name} = {w}"))
+                    .collect();
+                write!(f, "#{{ {} }}", fields.join(", "))
+            }
+        }
+    }
+}
+
+/// The constructors to consider for the first column.
+/// 第一列需要考虑的构造器。
+enum Signature {
+    /// Every value is built by one of these constructors.
+    /// 每个值都由这些构造器之一构造。
+    Complete(Vec<Ctor>),
+    /// Only the listed constructors appear; any other value is uncovered
+    /// unless a wildcard row catches it. `all` lists the whole family when it
+    /// is finite.
+    /// 仅出现所列构造器；除非通配行捕获，其余值均未覆盖。
+    /// 当族有限时 `all` 列出整个族。
+    Partial { all: Option<Vec<Ctor>> },
+}
+
+/// Work out the signature of the first column of `rows`.
+/// 计算 `rows` 第一列的签名。
+fn signature(rows: &[&[Pat]], table: &VariantTable) -> Signature {
+    let heads: Vec<&Pat> = rows.iter().filter_map(|row| row.first()).collect();
+
+    let mut record_fields: Vec<String> = Vec::new();
+    let mut has_record = false;
+    let mut longest: Option<usize> = None;
+    let mut present: Vec<&Ctor> = Vec::new();
+    for head in &heads {
+        match head {
+            Pat::Record(fields) => {
+                has_record = true;
+                for (name, _) in fields {
+                    if !record_fields.contains(name) {
+                        record_fields.push(name.clone());
+                    }
+                }
+            }
+            Pat::Slice(init, tail) => {
+                longest = longest.max(Some(init.len() + tail.len()));
+            }
+            Pat::Ctor(Ctor::List(len), _) => {
+                longest = longest.max(Some(*len));
+            }
+            Pat::Ctor(ctor, _) => present.push(ctor),
+            Pat::Wild | Pat::Or(_) => {}
+        }
+    }
+
+    if has_record {
+        return Signature::Complete(vec![Ctor::Record(record_fields)]);
+    }
+    if let Some(longest) = longest {
+        let mut ctors: Vec<Ctor> = (0..=longest).map(Ctor::List).collect();
+        ctors.push(Ctor::ListAtLeast(longest + 1));
+        return Signature::Complete(ctors);
+    }
+    let Some(first) = present.first() else {
+        return Signature::Partial { all: None };
+    };
+    let all = match first {
+        Ctor::Tuple(n) => return Signature::Complete(vec![Ctor::Tuple(*n)]),
+        Ctor::Bool(_) => vec![Ctor::Bool(false), Ctor::Bool(true)],
+        Ctor::Variant(name) => match table.siblings.get(name) {
+            Some(siblings) => siblings
+                .iter()
+                .map(|(n, _)| Ctor::Variant(n.clone()))
+                .collect(),
+            // Variants of an enum we know nothing about: assume the arms
+            // name all of them rather than guess at what is missing
+            // 对一无所知的枚举的变体：假定各分支已列出全部变体，而不去猜测缺失项
+            None => {
+                let mut ctors: Vec<Ctor> = Vec::new();
+                for ctor in &present {
+                    if !ctors.contains(ctor) {
+                        ctors.push((*ctor).clone());
+                    }
+                }
+                return Signature::Complete(ctors);
+            }
+        },
+        _ => return Signature::Partial { all: None },
+    };
+    if all.iter().all(|ctor| present.contains(&ctor)) {
+        Signature::Complete(all)
+    } else {
+        Signature::Partial { all: Some(all) }
+    }
+}
+
+/// Expand or-patterns at the head of each row into separate rows.
+/// 将每行开头的或模式展开为多行。
+fn expand_or(rows: &[Vec<Pat>]) -> Vec<Vec<Pat>> {
+    let mut out = Vec::with_capacity(rows.len());
+    for row in rows {
+        match row.first() {
+            Some(Pat::Or(alternatives)) => {
+                let alternatives: Vec<Vec<Pat>> = alternatives
+                    .iter()
+                    .map(|alt| {
+                        let mut expanded = vec![alt.clone()];
+                        expanded.extend(row[1..].iter().cloned());
+                        expanded
+                    })
+                    .collect();
+                out.extend(expand_or(&alternatives));
+            }
+            _ => out.push(row.clone()),
+        }
+    }
+    out
+}
+
+/// Rows of `matrix` that can match `ctor`, with the head replaced by its
+/// sub-patterns.
+/// `matrix` 中能匹配 `ctor` 的行，其开头替换为子模式。
+fn specialize_matrix(matrix: &[Vec<Pat>], ctor: &Ctor, arity: usize) -> Vec<Vec<Pat>> {
+    matrix
+        .iter()
+        .filter_map(|row| {
+            let mut subs = row[0].specialize(ctor, arity)?;
+            subs.extend(row[1..].iter().cloned());
+            Some(subs)
+        })
+        .collect()
+}
+
+/// Rows of `matrix` headed by a wildcard, without that head.
+/// `matrix` 中以通配符开头的行，去掉开头。
+fn default_matrix(matrix: &[Vec<Pat>]) -> Vec<Vec<Pat>> {
+    matrix
+        .iter()
+        .filter(|row| matches!(row[0], Pat::Wild))
+        .map(|row| row[1..].to_vec())
+        .collect()
+}
+
+/// Whether some value matches `row` but no row of `matrix`.
+/// 是否存在某个值匹配 `row` 却不匹配 `matrix` 中任何一行。
+fn is_useful(matrix: &[Vec<Pat>], row: &[Pat], table: &VariantTable) -> bool {
+    let Some(head) = row.first() else {
+        return matrix.is_empty();
+    };
+    if let Pat::Or(alternatives) = head {
+        return alternatives.iter().any(|alt| {
+            let mut expanded = vec![alt.clone()];
+            expanded.extend(row[1..].iter().cloned());
+            is_useful(matrix, &expanded, table)
+        });
+    }
+    let matrix = expand_or(matrix);
+
+    let useful_for = |ctor: &Ctor| {
+        let arity = ctor.arity(table);
+        match head.specialize(ctor, arity) {
+            Some(mut subs) => {
+                subs.extend(row[1..].iter().cloned());
+                is_useful(&specialize_matrix(&matrix, ctor, arity), &subs, table)
+            }
+            None => false,
+        }
+    };
+
+    let mut rows: Vec<&[Pat]> = matrix.iter().map(Vec::as_slice).collect();
+    rows.push(row);
+    match (head, signature(&rows, table)) {
+        (_, Signature::Complete(ctors)) => ctors.iter().any(useful_for),
+        (Pat::Ctor(ctor, _), Signature::Partial { .. }) => useful_for(ctor),
+        (_, Signature::Partial { .. }) => is_useful(&default_matrix(&matrix), &row[1..], table),
+    }
+}
+
+/// Collect rows of `width` witnesses describing values no row of `matrix`
+/// matches.
+/// 收集宽度为 `width` 的见证行，描述 `matrix` 中没有任何行能匹配的值。
+fn missing(matrix: &[Vec<Pat>], width: usize, table: &VariantTable, out: &mut Vec<Vec<Witness>>) {
+    if out.len() >= MAX_WITNESSES {
+        return;
+    }
+    if width == 0 {
+        if matrix.is_empty() {
+            out.push(Vec::new());
+        }
+        return;
+    }
+    let matrix = expand_or(matrix);
+    let rows: Vec<&[Pat]> = matrix.iter().map(Vec::as_slice).collect();
+
+    match signature(&rows, table) {
+        Signature::Complete(ctors) => {
+            for ctor in ctors {
+                let arity = ctor.arity(table);
+                let mut found = Vec::new();
+                missing(
+                    &specialize_matrix(&matrix, &ctor, arity),
+                    arity + width - 1,
+                    table,
+                    &mut found,
+                );
+                for mut witness in found {
+                    let rest = witness.split_off(arity);
+                    let mut row = vec![Witness::Ctor(ctor.clone(), witness)];
+                    row.extend(rest);
+                    out.push(row);
+                }
+                if out.len() >= MAX_WITNESSES {
+                    return;
+                }
+            }
+        }
+        Signature::Partial { all } => {
+            let mut found = Vec::new();
+            missing(&default_matrix(&matrix), width - 1, table, &mut found);
+            if found.is_empty() {
+                return;
+            }
+            // Name the absent constructors of a finite family, or fall back
+            // to a wildcard for unbounded ones
+            // 对有限族列出缺失的构造器，对无界族则退回为通配符
+            let heads: Vec<Witness> = match all {
+                Some(all) => all
+                    .into_iter()
+                    .filter(|ctor| {
+                        !rows
+                            .iter()
+                            .any(|row| matches!(&row[0], Pat::Ctor(c, _) if c == ctor))
+                    })
+                    .map(|ctor| {
+                        let arity = ctor.arity(table);
+                        Witness::Ctor(ctor, vec![Witness::Wild; arity])
+                    })
+                    .collect(),
+                None => vec![Witness::Wild],
+            };
+            for head in heads {
+                for rest in &found {
+                    let mut row = vec![head.clone()];
+                    row.extend(rest.iter().cloned());
+                    out.push(row);
+                }
+            }
+            out.truncate(MAX_WITNESSES);
+        }
+    }
+}
@@ -30,6 +30,16 @@ impl InferContext {
             span: Span::DUMMY,
         }
     }
+
+    /// Create an open record with the given fields and a fresh row variable.
+    /// 创建带有给定字段和新行变量的开放记录。
+    pub fn open_record(&mut self, fields: Vec<(String, Ty)>) -> Ty {
+        let rest = self.fresh_var();
+        Ty {
+            kind: TyKind::OpenRecord(fields, Box::new(rest)),
+            span: Span::DUMMY,
+        }
+    }
 }
 
 impl Default for InferContext {
@@ -14,14 +14,19 @@
 //! - Associated types / 关联类型
 //! - Generic parameters with bounds / 带约束的泛型参数
 
+pub mod annotate;
 mod check;
 pub mod errors;
+mod exhaustive;
 mod infer;
+pub mod pipeline;
 mod traits;
 mod unify;
 
+pub use annotate::annotate;
 pub use check::TypeChecker;
 pub use errors::format_type;
+pub use pipeline::{CompileOptions, CompileResult, compile, compile_with};
 pub use traits::{
     ConstraintSolver, ImplId, ImplInfo, ImplMethod, MethodResolution, TraitBound, TraitConstraint,
     TraitId, TraitInfo, TraitMethod, TraitResolver, UnsatisfiedConstraint,
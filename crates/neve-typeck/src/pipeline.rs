@@ -0,0 +1,140 @@
+//! The front-end pipeline in one call.
+//! 一次调用完成的前端流水线。
+//!
+//! `compile` runs source text through lexing, parsing, lowering and type
+//! checking, collecting the diagnostics of every stage. The CLI's `check`,
+//! `build` and `eval` commands all go through it.
+//! `compile` 将源文本依次进行词法分析、语法分析、降级和类型检查，并收集每个阶段的诊断信息。
+//! CLI 的 `check`、`build` 和 `eval` 命令都通过它运行。
+
+use crate::TypeChecker;
+use neve_common::Span;
+use neve_diagnostic::{Diagnostic, Severity};
+use neve_hir::{Module, Resolver, Ty};
+use neve_parser::parse;
+use neve_syntax::SourceFile;
+use std::collections::HashMap;
+
+/// Options controlling a front-end run.
+/// 控制前端运行的选项。
+#[derive(Debug, Clone)]
+pub struct CompileOptions {
+    /// Warn about unused imports, bindings and variables.
+    /// 对未使用的导入、绑定和变量发出警告。
+    pub warn_unused: bool,
+    /// Type check the lowered module. Builtins are only known at evaluation
+    /// time, so callers that go on to evaluate may stop after lowering.
+    /// 对降级后的模块进行类型检查。内置函数仅在求值时可知，因此之后要求值的调用者可以在降级后停止。
+    pub type_check: bool,
+}
+
+impl Default for CompileOptions {
+    fn default() -> Self {
+        Self {
+            warn_unused: true,
+            type_check: true,
+        }
+    }
+}
+
+/// The artifacts and diagnostics of a front-end run.
+/// 前端运行的产物和诊断信息。
+#[derive(Debug)]
+pub struct CompileResult {
+    /// The parsed syntax tree. / 解析得到的语法树。
+    pub file: SourceFile,
+    /// The lowered module, absent when parsing failed.
+    /// 降级后的模块，解析失败时为空。
+    pub hir: Option<Module>,
+    /// Diagnostics from every stage that ran, in order.
+    /// 所有已运行阶段的诊断信息，按顺序排列。
+    pub diagnostics: Vec<Diagnostic>,
+    /// Inferred type of each top-level item, keyed by the item's span.
+    /// 每个顶层项的推断类型，以项的跨度为键。
+    pub item_types: HashMap<Span, Ty>,
+}
+
+impl CompileResult {
+    /// Check whether any stage reported an error.
+    /// 检查是否有任何阶段报告了错误。
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|d| d.severity == Severity::Error)
+    }
+
+    /// Count the diagnostics that fail the run: every error, and every
+    /// warning too when `deny_warnings` is set.
+    /// 统计使运行失败的诊断：所有错误，以及在设置 `deny_warnings` 时的所有警告。
+    pub fn fatal_count(&self, deny_warnings: bool) -> usize {
+        self.diagnostics
+            .iter()
+            .filter(|d| d.is_fatal(deny_warnings))
+            .count()
+    }
+}
+
+/// Lex, parse, lower and type check `source`.
+/// 对 `source` 进行词法分析、语法分析、降级和类型检查。
+///
+/// Stops after parsing if it produced errors; otherwise the module is
+/// lowered to HIR and type checked.
+/// 如果解析产生错误则在解析后停止；否则将模块降级为 HIR 并进行类型检查。
+pub fn compile(source: &str) -> CompileResult {
+    compile_with(source, &CompileOptions::default())
+}
+
+/// Lex, parse, lower and type check `source` with the given options.
+/// 使用给定选项对 `source` 进行词法分析、语法分析、降级和类型检查。
+///
+/// Type checking is skipped when `options.type_check` is off.
+/// 当 `options.type_check` 关闭时跳过类型检查。
+pub fn compile_with(source: &str, options: &CompileOptions) -> CompileResult {
+    let (file, mut diagnostics) = parse(source);
+    if diagnostics.iter().any(|d| d.severity == Severity::Error) {
+        return CompileResult {
+            file,
+            hir: None,
+            diagnostics,
+            item_types: HashMap::new(),
+        };
+    }
+
+    let mut resolver = Resolver::new();
+    resolver.set_warn_unused(options.warn_unused);
+    let hir = resolver.resolve(&file);
+    diagnostics.extend(resolver.diagnostics().iter().cloned());
+    if !options.type_check {
+        return CompileResult {
+            file,
+            hir: Some(hir),
+            diagnostics,
+            item_types: HashMap::new(),
+        };
+    }
+
+    // Names left unbound by the resolver are errors once the module is type
+    // checked, where builtins are not in scope
+    // 在类型检查时，解析器未绑定的名称是错误，此时内置函数不在作用域中
+    diagnostics.extend(
+        resolver
+            .unresolved_names()
+            .iter()
+            .map(|name| name.to_diagnostic()),
+    );
+    let mut checker = if options.warn_unused {
+        TypeChecker::new()
+    } else {
+        TypeChecker::without_unused_check()
+    };
+    checker.check(&hir);
+    let item_types = checker.item_types().clone();
+    diagnostics.extend(checker.diagnostics());
+
+    CompileResult {
+        file,
+        hir: Some(hir),
+        diagnostics,
+        item_types,
+    }
+}
@@ -297,7 +297,7 @@ impl TraitResolver {
             TyKind::Unit => "()".to_string(),
             TyKind::Named(def_id, _) => format!("Named({})", def_id.0),
             TyKind::Tuple(elems) => format!("Tuple({})", elems.len()),
-            TyKind::Record(_) => "Record".to_string(),
+            TyKind::Record(_) | TyKind::OpenRecord(..) => "Record".to_string(),
             TyKind::Fn(_, _) => "Fn".to_string(),
             TyKind::Var(v) => format!("Var({})", v),
             TyKind::Param(idx, name) => format!("Param({}, {})", idx, name),
@@ -6,6 +6,8 @@
 //! 本模块实现类型推断的合一算法。
 //! 通过找到一个替换使两个类型相等来实现类型合一。
 
+use crate::infer::InferContext;
+use neve_common::Span;
 use neve_hir::{Ty, TyKind};
 use std::collections::HashMap;
 
@@ -70,6 +72,30 @@ impl Substitution {
                 ),
                 span: ty.span,
             },
+            TyKind::OpenRecord(fields, rest) => {
+                // Splice the fields the row variable has been bound to
+                // 拼接行变量已绑定到的字段
+                let mut fields: Vec<(String, Ty)> = fields
+                    .iter()
+                    .map(|(n, t)| (n.clone(), self.apply(t)))
+                    .collect();
+                let rest = self.apply(rest);
+                let kind = match rest.kind {
+                    TyKind::Record(more) => {
+                        fields.extend(more);
+                        TyKind::Record(fields)
+                    }
+                    TyKind::OpenRecord(more, rest) => {
+                        fields.extend(more);
+                        TyKind::OpenRecord(fields, rest)
+                    }
+                    _ => TyKind::OpenRecord(fields, Box::new(rest)),
+                };
+                Ty {
+                    kind,
+                    span: ty.span,
+                }
+            }
             TyKind::Forall(params, body) => Ty {
                 kind: TyKind::Forall(params.clone(), Box::new(self.apply(body))),
                 span: ty.span,
@@ -109,9 +135,56 @@ impl Default for Substitution {
     }
 }
 
-/// Unify two types, returning an error message if they don't match.
-/// 合一两个类型，如果不匹配则返回错误信息。
-pub fn unify(t1: &Ty, t2: &Ty, subst: &mut Substitution) -> Result<(), String> {
+/// Why two types could not be unified.
+/// 两个类型无法合一的原因。
+#[derive(Debug, Clone)]
+pub struct UnifyError {
+    /// The innermost expected type that did not match. / 不匹配的最内层期望类型。
+    pub expected: Ty,
+    /// The innermost actual type that did not match. / 不匹配的最内层实际类型。
+    pub found: Ty,
+    /// What went wrong beyond the two types differing. / 除类型不同之外的具体问题。
+    pub reason: Option<UnifyReason>,
+}
+
+/// What went wrong in a failed unification beyond the types differing.
+/// 合一失败时除类型不同之外的具体问题。
+#[derive(Debug, Clone)]
+pub enum UnifyReason {
+    /// `found` is a type variable occurring in `expected`. / `found` 是出现在 `expected` 中的类型变量。
+    InfiniteType,
+    /// A note explaining the mismatch. / 解释不匹配的说明。
+    Note(String),
+}
+
+impl UnifyError {
+    fn new(found: &Ty, expected: &Ty, reason: Option<String>) -> Self {
+        Self {
+            expected: expected.clone(),
+            found: found.clone(),
+            reason: reason.map(UnifyReason::Note),
+        }
+    }
+
+    fn infinite(var: &Ty, ty: &Ty) -> Self {
+        Self {
+            reason: Some(UnifyReason::InfiniteType),
+            ..Self::new(var, ty, None)
+        }
+    }
+}
+
+/// Unify the actual type `t1` with the expected type `t2`.
+/// 将实际类型 `t1` 与期望类型 `t2` 合一。
+///
+/// Fresh row variables needed to unify two open records come from `infer`.
+/// 合一两个开放记录所需的新行变量来自 `infer`。
+pub fn unify(
+    t1: &Ty,
+    t2: &Ty,
+    subst: &mut Substitution,
+    infer: &mut InferContext,
+) -> Result<(), UnifyError> {
     let t1 = subst.apply(t1);
     let t2 = subst.apply(t2);
 
@@ -120,7 +193,7 @@ pub fn unify(t1: &Ty, t2: &Ty, subst: &mut Substitution) -> Result<(), String> {
         (TyKind::Var(v1), TyKind::Var(v2)) if v1 == v2 => Ok(()),
         (TyKind::Var(v), _) => {
             if occurs_check(*v, &t2) {
-                Err("infinite type".to_string())
+                Err(UnifyError::infinite(&t1, &t2))
             } else {
                 subst.extend(*v, t2);
                 Ok(())
@@ -128,7 +201,7 @@ pub fn unify(t1: &Ty, t2: &Ty, subst: &mut Substitution) -> Result<(), String> {
         }
         (_, TyKind::Var(v)) => {
             if occurs_check(*v, &t1) {
-                Err("infinite type".to_string())
+                Err(UnifyError::infinite(&t2, &t1))
             } else {
                 subst.extend(*v, t1);
                 Ok(())
@@ -158,21 +231,29 @@ pub fn unify(t1: &Ty, t2: &Ty, subst: &mut Substitution) -> Result<(), String> {
         // Function types
         (TyKind::Fn(p1, r1), TyKind::Fn(p2, r2)) => {
             if p1.len() != p2.len() {
-                return Err("function arity mismatch".to_string());
+                return Err(UnifyError::new(
+                    &t1,
+                    &t2,
+                    Some("function arity mismatch".to_string()),
+                ));
             }
             for (a, b) in p1.iter().zip(p2.iter()) {
-                unify(a, b, subst)?;
+                unify(a, b, subst, infer)?;
             }
-            unify(r1, r2, subst)
+            unify(r1, r2, subst, infer)
         }
 
         // Tuple types
         (TyKind::Tuple(e1), TyKind::Tuple(e2)) => {
             if e1.len() != e2.len() {
-                return Err("tuple length mismatch".to_string());
+                return Err(UnifyError::new(
+                    &t1,
+                    &t2,
+                    Some("tuple length mismatch".to_string()),
+                ));
             }
             for (a, b) in e1.iter().zip(e2.iter()) {
-                unify(a, b, subst)?;
+                unify(a, b, subst, infer)?;
             }
             Ok(())
         }
@@ -180,41 +261,130 @@ pub fn unify(t1: &Ty, t2: &Ty, subst: &mut Substitution) -> Result<(), String> {
         // Named types with type arguments
         (TyKind::Named(id1, args1), TyKind::Named(id2, args2)) if id1 == id2 => {
             if args1.len() != args2.len() {
-                return Err("type argument count mismatch".to_string());
+                return Err(UnifyError::new(
+                    &t1,
+                    &t2,
+                    Some("type argument count mismatch".to_string()),
+                ));
             }
             for (a, b) in args1.iter().zip(args2.iter()) {
-                unify(a, b, subst)?;
+                unify(a, b, subst, infer)?;
             }
             Ok(())
         }
 
-        // Record types (structural)
+        // Record types (structural, with optional row variables)
         (TyKind::Record(f1), TyKind::Record(f2)) => {
-            if f1.len() != f2.len() {
-                return Err("record field count mismatch".to_string());
-            }
-            for ((n1, t1), (n2, t2)) in f1.iter().zip(f2.iter()) {
-                if n1 != n2 {
-                    return Err(format!("record field name mismatch: {} vs {}", n1, n2));
-                }
-                unify(t1, t2, subst)?;
-            }
-            Ok(())
+            unify_rows((&t1, f1, None), (&t2, f2, None), subst, infer)
+        }
+        (TyKind::OpenRecord(f1, r1), TyKind::Record(f2)) => {
+            unify_rows((&t1, f1, Some(r1)), (&t2, f2, None), subst, infer)
+        }
+        (TyKind::Record(f1), TyKind::OpenRecord(f2, r2)) => {
+            unify_rows((&t1, f1, None), (&t2, f2, Some(r2)), subst, infer)
+        }
+        (TyKind::OpenRecord(f1, r1), TyKind::OpenRecord(f2, r2)) => {
+            unify_rows((&t1, f1, Some(r1)), (&t2, f2, Some(r2)), subst, infer)
         }
 
         // Forall types (polymorphic)
         (TyKind::Forall(params1, body1), TyKind::Forall(params2, body2)) => {
             if params1.len() != params2.len() {
-                return Err("forall parameter count mismatch".to_string());
+                return Err(UnifyError::new(
+                    &t1,
+                    &t2,
+                    Some("forall parameter count mismatch".to_string()),
+                ));
             }
             // Unify the bodies (parameters are already bound)
-            unify(body1, body2, subst)
+            unify(body1, body2, subst, infer)
         }
 
         // Unknown types match anything (placeholder)
         (TyKind::Unknown, _) | (_, TyKind::Unknown) => Ok(()),
 
-        _ => Err(format!("type mismatch: {:?} vs {:?}", t1.kind, t2.kind)),
+        _ => Err(UnifyError::new(&t1, &t2, None)),
+    }
+}
+
+/// A record row: the record type, its fields and its rest variable, if open.
+/// 记录行：记录类型、其字段及其剩余变量（若为开放行）。
+type Row<'a> = (&'a Ty, &'a [(String, Ty)], Option<&'a Ty>);
+
+/// Unify two record rows. A row without a rest variable is closed and has
+/// exactly its fields; an open row can take on the other side's extra fields.
+/// 合一两个记录行。没有剩余变量的行是封闭的，恰好只有其所列字段；
+/// 开放的行可以接纳另一侧多出的字段。
+fn unify_rows(
+    (t1, f1, r1): Row<'_>,
+    (t2, f2, r2): Row<'_>,
+    subst: &mut Substitution,
+    infer: &mut InferContext,
+) -> Result<(), UnifyError> {
+    let missing =
+        |name: &str| UnifyError::new(t1, t2, Some(format!("record has no field '{}'", name)));
+    for (name, t1) in f1 {
+        if let Some((_, t2)) = f2.iter().find(|(n, _)| n == name) {
+            unify(t1, t2, subst, infer)?;
+        }
+    }
+    let only1: Vec<(String, Ty)> = f1
+        .iter()
+        .filter(|(n, _)| !f2.iter().any(|(m, _)| m == n))
+        .cloned()
+        .collect();
+    let only2: Vec<(String, Ty)> = f2
+        .iter()
+        .filter(|(n, _)| !f1.iter().any(|(m, _)| m == n))
+        .cloned()
+        .collect();
+
+    let record = |fields: Vec<(String, Ty)>| Ty {
+        kind: TyKind::Record(fields),
+        span: Span::DUMMY,
+    };
+    match (r1, r2) {
+        (None, None) => match (only1.first(), only2.first()) {
+            (Some((name, _)), _) | (_, Some((name, _))) => Err(missing(name)),
+            (None, None) => Ok(()),
+        },
+        (Some(r1), None) => match only1.first() {
+            Some((name, _)) => Err(missing(name)),
+            None => unify(r1, &record(only2), subst, infer),
+        },
+        (None, Some(r2)) => match only2.first() {
+            Some((name, _)) => Err(missing(name)),
+            None => unify(&record(only1), r2, subst, infer),
+        },
+        (Some(r1), Some(r2)) => {
+            if only1.is_empty() && only2.is_empty() {
+                return unify(r1, r2, subst, infer);
+            }
+            if let (TyKind::Var(v1), TyKind::Var(v2)) = (&r1.kind, &r2.kind)
+                && v1 == v2
+            {
+                return Err(UnifyError::new(
+                    t1,
+                    t2,
+                    Some("records have different fields".to_string()),
+                ));
+            }
+            // Each side gains the other's extra fields over a shared new rest
+            // 双方在共享的新剩余变量之上获得对方多出的字段
+            let rest = infer.fresh_var();
+            let open = |fields: Vec<(String, Ty)>| {
+                if fields.is_empty() {
+                    rest.clone()
+                } else {
+                    Ty {
+                        kind: TyKind::OpenRecord(fields, Box::new(rest.clone())),
+                        span: Span::DUMMY,
+                    }
+                }
+            };
+            unify(r1, &open(only2), subst, infer)?;
+            unify(r2, &open(only1), subst, infer)
+        }
     }
 }
 
@@ -230,6 +400,9 @@ fn occurs_check(var: u32, ty: &Ty) -> bool {
         TyKind::Tuple(elems) => elems.iter().any(|t| occurs_check(var, t)),
         TyKind::Named(_, args) => args.iter().any(|t| occurs_check(var, t)),
         TyKind::Record(fields) => fields.iter().any(|(_, t)| occurs_check(var, t)),
+        TyKind::OpenRecord(fields, rest) => {
+            fields.iter().any(|(_, t)| occurs_check(var, t)) || occurs_check(var, rest)
+        }
         TyKind::Forall(_, body) => occurs_check(var, body),
         _ => false,
     }
@@ -252,19 +425,32 @@ pub fn instantiate(ty: &Ty, fresh_var: &mut impl FnMut() -> Ty) -> Ty {
 
 /// Generalize a type by wrapping free type variables in Forall.
 /// 泛化类型，将自由类型变量包装在 Forall 中。
+///
+/// Each generalized variable becomes a type parameter numbered by its position
+/// in the `Forall`, which is what `instantiate` replaces.
+/// 每个被泛化的变量都成为按其在 `Forall` 中位置编号的类型参数，
+/// 这正是 `instantiate` 所替换的内容。
 pub fn generalize(ty: &Ty, env_vars: &[u32]) -> Ty {
-    let free_vars = free_type_vars(ty);
-    let params: Vec<String> = free_vars
-        .iter()
-        .filter(|v| !env_vars.contains(v))
-        .map(|v| format!("t{}", v))
-        .collect();
+    let mut subst = Substitution::new();
+    let mut params = Vec::new();
+    for v in free_type_vars(ty) {
+        if env_vars.contains(&v) {
+            continue;
+        }
+        let name = format!("t{}", v);
+        let param = Ty {
+            kind: TyKind::Param(params.len() as u32, name.clone()),
+            span: ty.span,
+        };
+        subst.extend(v, param);
+        params.push(name);
+    }
 
     if params.is_empty() {
         ty.clone()
     } else {
         Ty {
-            kind: TyKind::Forall(params, Box::new(ty.clone())),
+            kind: TyKind::Forall(params, Box::new(subst.apply(ty))),
             span: ty.span,
         }
     }
@@ -304,6 +490,12 @@ fn collect_free_vars(ty: &Ty, vars: &mut Vec<u32>) {
                 collect_free_vars(t, vars);
             }
         }
+        TyKind::OpenRecord(fields, rest) => {
+            for (_, t) in fields {
+                collect_free_vars(t, vars);
+            }
+            collect_free_vars(rest, vars);
+        }
         TyKind::Forall(_, body) => {
             collect_free_vars(body, vars);
         }
@@ -0,0 +1,56 @@
+//! The `neve analyze` command.
+//! `neve analyze` 命令。
+
+use crate::output;
+use neve_diagnostic::emit;
+use neve_eval::analysis::analyze_file;
+use neve_parser::parse;
+use std::fs;
+
+/// Print the pattern analysis of every match expression in a file.
+/// 打印文件中每个匹配表达式的模式分析。
+///
+/// This is a developer tool: it reports exhaustiveness by catch-all arm,
+/// unreachable arms, arm ordering hints (catch-all arms that shadow later
+/// ones, specific or literal arms placed after less specific or guarded ones)
+/// and evaluator fast paths.
+/// 这是一个开发者工具：报告由全捕获分支带来的穷尽性、无法到达的分支、分支排序提示
+/// （遮蔽后续分支的全捕获分支，以及位于不太具体或有守卫分支之后的具体或字面量分支）
+/// 以及求值器快速路径。
+pub fn run(file: &str) -> Result<(), String> {
+    let source =
+        fs::read_to_string(file).map_err(|e| format!("cannot read file '{}': {}", file, e))?;
+
+    let (ast, diagnostics) = parse(&source);
+    for diag in &diagnostics {
+        emit(&source, file, diag, output::color_choice());
+    }
+    if !diagnostics.is_empty() {
+        output::error(&format!("{} parse error(s) found", diagnostics.len()));
+        return Err("parse error".to_string());
+    }
+
+    let reports = analyze_file(&ast, &source);
+    if reports.is_empty() {
+        output::info("No match expressions found");
+        return Ok(());
+    }
+
+    for report in &reports {
+        print!("{}", report);
+    }
+
+    let unreachable = reports.iter().filter(|r| r.has_unreachable_arms()).count();
+    if unreachable > 0 {
+        output::warning(&format!(
+            "{} match {} with unreachable arms",
+            unreachable,
+            if unreachable == 1 {
+                "expression"
+            } else {
+                "expressions"
+            }
+        ));
+    }
+    Ok(())
+}
@@ -6,25 +6,60 @@
 
 use crate::output;
 use crate::platform::{BuildBackend, PlatformCapabilities, warn_limited_sandbox};
+use neve_builder::graph::BuildGraph;
 use neve_builder::{Builder, BuilderConfig};
 use neve_derive::Derivation;
 use neve_diagnostic::emit;
 use neve_eval::{AstEvaluator, Value};
-use neve_parser::parse;
 use neve_store::Store;
+use neve_syntax::SourceFile;
+use neve_typeck::{CompileOptions, compile_with};
 use std::fs;
 use std::path::Path;
 use std::time::Instant;
 
+/// Options of `neve build`.
+/// `neve build` 的选项。
+#[derive(Debug, Clone, Default)]
+pub struct BuildOptions {
+    /// Build backend: `native`, `docker`, `simple` or `auto`.
+    /// 构建后端：`native`、`docker`、`simple` 或 `auto`。
+    pub backend: String,
+    /// Fail on any warning from parsing or name resolution.
+    /// 解析或名称解析中的任何警告都会使构建失败。
+    pub deny_warnings: bool,
+    /// Keep building independent derivations after one fails; otherwise the
+    /// first failure stops the build.
+    /// 某个派生失败后继续构建独立的派生；否则第一个失败会停止构建。
+    pub keep_going: bool,
+    /// Leave the build directories of failed builds on disk.
+    /// 将失败构建的构建目录保留在磁盘上。
+    pub keep_failed: bool,
+    /// Binary caches whose outputs are downloaded instead of built.
+    /// 其输出会被下载而不是构建的二进制缓存。
+    pub substituters: Vec<String>,
+    /// Fail instead of looking an output up in the substituters.
+    /// 查询替代源时使构建失败，而不是进行查询。
+    pub frozen: bool,
+}
+
 /// Run the build command.
 /// 运行构建命令。
-pub fn run(package: Option<&str>, backend_arg: &str) -> Result<(), String> {
+pub fn run(package: Option<&str>, options: &BuildOptions) -> Result<(), String> {
+    let BuildOptions {
+        ref backend,
+        deny_warnings,
+        keep_going,
+        keep_failed,
+        ref substituters,
+        frozen,
+    } = *options;
     let start = Instant::now();
 
     // Detect platform and determine build backend
     // 检测平台并确定构建后端
     let caps = PlatformCapabilities::detect();
-    let backend = match backend_arg {
+    let backend = match backend.as_str() {
         "auto" => caps.recommended_backend(),
         "native" => BuildBackend::Native,
         "docker" => BuildBackend::Docker,
@@ -32,7 +67,7 @@ pub fn run(package: Option<&str>, backend_arg: &str) -> Result<(), String> {
         _ => {
             return Err(format!(
                 "unknown backend: {}. Use 'native', 'docker', 'simple', or 'auto'",
-                backend_arg
+                backend
             ));
         }
     };
@@ -48,6 +83,123 @@ pub fn run(package: Option<&str>, backend_arg: &str) -> Result<(), String> {
     output::debug(&format!("Build backend: {}", backend));
     output::info(&format!("Build backend: {}", backend));
 
+    let (source_path, target_attr) = build_target(package)?;
+    output::info(&format!("Evaluating {}", source_path));
+    let derivations = load_derivations(&source_path, target_attr.as_deref(), deny_warnings)?;
+
+    output::info(&format!(
+        "Found {} derivation(s) to build",
+        derivations.len()
+    ));
+
+    // Open the store
+    // 打开存储
+    let store = Store::open().map_err(|e| format!("cannot open store: {}", e))?;
+
+    // Create builder
+    // 创建构建器
+    let config = BuilderConfig {
+        keep_going,
+        keep_failed,
+        substituters: substituters.clone(),
+        offline: frozen,
+        cancel: crate::interrupt::cancel_token(),
+        ..BuilderConfig::default()
+    };
+    let mut builder = Builder::with_config(store, config);
+
+    // Build each derivation
+    // 构建每个派生
+    let total = derivations.len();
+    let mut progress = output::ProgressBar::new(total, "Building");
+
+    let summary = builder.build_all_with_progress(&derivations, &mut |drv, result| {
+        output::highlight(&format!("▶ Building {}-{}", drv.name, drv.version));
+        match result {
+            Ok(result) => {
+                let verb = if result.substituted {
+                    "Substituted"
+                } else {
+                    "Built"
+                };
+                for (output_name, store_path) in &result.outputs {
+                    let path_display = store_path.display_name();
+                    if output_name == "out" {
+                        output::success(&format!("{}: {}", verb, path_display));
+                    } else {
+                        output::success(&format!("{} {}: {}", verb, output_name, path_display));
+                    }
+                }
+
+                if result.duration_secs > 0.1 {
+                    output::info(&format!(
+                        "Build time: {}",
+                        output::format_duration(result.duration_secs as u64)
+                    ));
+                }
+            }
+            Err(e) => output::error(&format!("Failed to build {}: {}", drv.name, e)),
+        }
+        progress.inc();
+    });
+
+    progress.finish();
+
+    let elapsed = start.elapsed();
+
+    // Summary
+    // 总结
+    if builder.config().cancel.is_cancelled() {
+        return Err("build cancelled".to_string());
+    }
+    if summary.is_success() {
+        output::success(&format!(
+            "Successfully built {} derivation(s) in {:.2}s",
+            summary.built.len(),
+            elapsed.as_secs_f64()
+        ));
+        Ok(())
+    } else {
+        output::error(&format!(
+            "{} of {} build(s) failed",
+            summary.failed.len(),
+            total
+        ));
+        for e in &summary.failed {
+            output::error(&format!("  {}", e));
+        }
+        if summary.not_attempted > 0 {
+            output::info(&format!(
+                "{} build(s) not attempted; use --keep-going to build them anyway",
+                summary.not_attempted
+            ));
+        }
+        Err("build failed".to_string())
+    }
+}
+
+/// Print the dependency graph of each derivation in a package in Graphviz
+/// DOT format, without building anything.
+/// 以 Graphviz DOT 格式打印软件包中每个派生的依赖图，不进行任何构建。
+///
+/// Input derivations are read from the store, so they must have been added
+/// to it before.
+/// 输入派生从存储中读取，因此它们必须事先已加入存储。
+pub fn graph(package: Option<&str>, deny_warnings: bool) -> Result<(), String> {
+    let (source_path, target_attr) = build_target(package)?;
+    let derivations = load_derivations(&source_path, target_attr.as_deref(), deny_warnings)?;
+    let mut store = Store::open().map_err(|e| format!("cannot open store: {}", e))?;
+    for drv in &derivations {
+        let graph = BuildGraph::from_derivation(&mut store, drv)
+            .map_err(|e| format!("cannot build the graph of {}: {}", drv.name, e))?;
+        print!("{}", graph.to_dot());
+    }
+    Ok(())
+}
+
+/// Find the file to evaluate for `package` and the attribute to build in it.
+/// 查找 `package` 对应的待求值文件及其中要构建的属性。
+fn build_target(package: Option<&str>) -> Result<(String, Option<String>), String> {
     // Determine what to build
     // 确定要构建的内容
     let (source_path, target_attr) = match package {
@@ -75,27 +227,28 @@ pub fn run(package: Option<&str>, backend_arg: &str) -> Result<(), String> {
         }
     };
 
-    let path = Path::new(&source_path);
-    if !path.exists() {
+    if !Path::new(&source_path).exists() {
         return Err(format!("file not found: {}", source_path));
     }
+    Ok((source_path, target_attr))
+}
 
-    output::info(&format!("Evaluating {}", source_path));
+/// Evaluate `source_path` and extract the derivations it defines, or those
+/// of its `target_attr` attribute.
+/// 求值 `source_path` 并提取其定义的派生，或其 `target_attr` 属性的派生。
+fn load_derivations(
+    source_path: &str,
+    target_attr: Option<&str>,
+    deny_warnings: bool,
+) -> Result<Vec<Derivation>, String> {
+    let path = Path::new(source_path);
 
     // Parse and evaluate the file
     // 解析并求值文件
     let source = fs::read_to_string(path)
         .map_err(|e| format!("cannot read file '{}': {}", source_path, e))?;
 
-    let (ast, diagnostics) = parse(&source);
-
-    for diag in &diagnostics {
-        emit(&source, &source_path, diag);
-    }
-
-    if !diagnostics.is_empty() {
-        return Err("parse error".to_string());
-    }
+    let ast = compile_source(&source, source_path, deny_warnings)?;
 
     // Evaluate the file
     // 求值文件
@@ -111,86 +264,45 @@ pub fn run(package: Option<&str>, backend_arg: &str) -> Result<(), String> {
 
     // Extract derivation(s) from the result
     // 从结果中提取派生
-    let derivations = extract_derivations(&value, target_attr.as_deref())?;
+    let derivations = extract_derivations(&value, target_attr)?;
 
     if derivations.is_empty() {
         return Err("no derivations found to build".to_string());
     }
+    Ok(derivations)
+}
 
-    output::info(&format!(
-        "Found {} derivation(s) to build",
-        derivations.len()
-    ));
-
-    // Open the store
-    // 打开存储
-    let store = Store::open().map_err(|e| format!("cannot open store: {}", e))?;
-
-    // Create builder
-    // 创建构建器
-    let config = BuilderConfig::default();
-    let mut builder = Builder::with_config(store, config);
-
-    // Build each derivation
-    // 构建每个派生
-    let mut built_count = 0;
-    let mut failed_count = 0;
-    let total = derivations.len();
-
-    let mut progress = output::ProgressBar::new(total, "Building");
-
-    for drv in &derivations {
-        output::highlight(&format!("▶ Building {}-{}", drv.name, drv.version));
-
-        match builder.build(drv) {
-            Ok(result) => {
-                built_count += 1;
-
-                for (output_name, store_path) in &result.outputs {
-                    let path_display = store_path.display_name();
-                    if output_name == "out" {
-                        output::success(&format!("Built: {}", path_display));
-                    } else {
-                        output::success(&format!("Built {}: {}", output_name, path_display));
-                    }
-                }
+/// Parse and lower `source`, printing all of its diagnostics.
+/// 解析并降级 `source`，并打印其所有诊断。
+///
+/// Any error fails, and so does any warning with `deny_warnings`, whichever
+/// stage reported it.
+/// 任何错误都会导致失败；启用 `deny_warnings` 时任何警告也会导致失败，无论由哪个阶段报告。
+fn compile_source(
+    source: &str,
+    source_path: &str,
+    deny_warnings: bool,
+) -> Result<SourceFile, String> {
+    // Builtins are only known at evaluation time, so the file is lowered
+    // but not type checked
+    // 内置函数仅在求值时可知，因此文件只降级而不进行类型检查
+    let options = CompileOptions {
+        type_check: false,
+        ..CompileOptions::default()
+    };
+    let result = compile_with(source, &options);
 
-                if result.duration_secs > 0.1 {
-                    output::info(&format!(
-                        "Build time: {}",
-                        output::format_duration(result.duration_secs as u64)
-                    ));
-                }
-            }
-            Err(e) => {
-                failed_count += 1;
-                output::error(&format!("Failed to build {}: {}", drv.name, e));
-            }
-        }
-        progress.inc();
+    for diag in &result.diagnostics {
+        emit(source, source_path, diag, output::color_choice());
     }
 
-    progress.finish();
-
-    let elapsed = start.elapsed();
-
-    // Summary
-    // 总结
-    if failed_count == 0 {
-        output::success(&format!(
-            "Successfully built {} derivation(s) in {:.2}s",
-            built_count,
-            elapsed.as_secs_f64()
-        ));
-        Ok(())
-    } else {
-        output::error(&format!(
-            "{} of {} build(s) failed",
-            failed_count,
-            derivations.len()
-        ));
-        Err("build failed".to_string())
+    if result.has_errors() {
+        return Err("parse error".to_string());
     }
+    if result.fatal_count(deny_warnings) > 0 {
+        return Err("warnings denied (--deny-warnings)".to_string());
+    }
+    Ok(result.file)
 }
 
 /// Extract derivations from an evaluated value.
@@ -380,3 +492,25 @@ fn current_system() -> String {
     let os = std::env::consts::OS;
     format!("{}-{}", arch, os)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A build file whose only diagnostic is an unused function warning.
+    /// 唯一的诊断是未使用函数警告的构建文件。
+    const WARNING_ONLY: &str = "fn helper() = 1;\nlet pkg = #{ name = \"hello\" };\n";
+
+    #[test]
+    fn test_compile_source_denies_warnings_only_when_asked() {
+        assert!(compile_source(WARNING_ONLY, "default.neve", false).is_ok());
+        assert_eq!(
+            compile_source(WARNING_ONLY, "default.neve", true).unwrap_err(),
+            "warnings denied (--deny-warnings)"
+        );
+        assert_eq!(
+            compile_source("let pkg = ;", "default.neve", false).unwrap_err(),
+            "parse error"
+        );
+    }
+}
@@ -2,56 +2,191 @@
 //! `neve check` 命令。
 
 use crate::output;
-use neve_diagnostic::emit;
-use neve_hir::lower;
-use neve_parser::parse;
-use neve_typeck::check;
+use neve_diagnostic::{Diagnostic, Severity, emit, emit_json_all, to_sarif};
+use neve_hir::{ModuleLoader, ModulePath};
+use neve_syntax::ItemKind;
+use neve_typeck::{CompileOptions, annotate as annotate_types, compile_with};
 use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Options of `neve check`.
+/// `neve check` 的选项。
+#[derive(Debug, Clone, Default)]
+pub struct CheckOptions {
+    /// Print progress. / 打印进度。
+    pub verbose: bool,
+    /// Fail on warnings just like errors; all diagnostics are printed either way.
+    /// 警告与错误一样会使检查失败；无论哪种情况都会打印所有诊断。
+    pub deny_warnings: bool,
+    /// Once the file type checks, print it with a `-- : Type` comment on each
+    /// top-level item.
+    /// 类型检查通过后打印源代码，并在每个顶层项上附加 `-- : Type` 注释。
+    pub annotate: bool,
+    /// Print to stderr how each import resolves from the file's directory.
+    /// 将每个导入从文件所在目录开始的解析过程打印到 stderr。
+    pub trace_imports: bool,
+    /// Don't warn about unused imports, top-level bindings and local variables.
+    /// 不对未使用的导入、顶层绑定和局部变量发出警告。
+    pub allow_unused: bool,
+    /// Print the diagnostics to stdout as a JSON array instead of rendering
+    /// them, and print nothing else there.
+    /// 将诊断以 JSON 数组的形式打印到 stdout 而不是渲染出来，且 stdout 上不打印其他内容。
+    pub json: bool,
+    /// Also write the diagnostics to this file as a SARIF log.
+    /// 同时将诊断以 SARIF 日志的形式写入此文件。
+    pub sarif: Option<PathBuf>,
+}
 
 /// Run type checking on a Neve file.
 /// 对 Neve 文件运行类型检查。
-pub fn run(file: &str, verbose: bool) -> Result<(), String> {
+pub fn run(file: &str, options: &CheckOptions) -> Result<(), String> {
+    let CheckOptions {
+        verbose,
+        deny_warnings,
+        annotate,
+        trace_imports,
+        allow_unused,
+        json,
+        ref sarif,
+    } = *options;
     let source =
         fs::read_to_string(file).map_err(|e| format!("cannot read file '{}': {}", file, e))?;
+    let report = |diagnostics: &[Diagnostic]| -> Result<(), String> {
+        if let Some(path) = sarif {
+            write_sarif(path, file, &source, diagnostics)?;
+        }
+        if json {
+            println!("{}", emit_json_all(diagnostics));
+        } else {
+            for diag in diagnostics {
+                emit(&source, file, diag, output::color_choice());
+            }
+        }
+        Ok(())
+    };
 
-    // Parse
-    // 解析
-    let (ast, parse_diagnostics) = parse(&source);
-
-    for diag in &parse_diagnostics {
-        emit(&source, file, diag);
-    }
+    // Parse, lower and type check
+    // 解析、降级并进行类型检查
+    let compile_options = CompileOptions {
+        warn_unused: !allow_unused,
+        ..CompileOptions::default()
+    };
+    let result = compile_with(&source, &compile_options);
+    let ast = &result.file;
 
-    if !parse_diagnostics.is_empty() {
-        output::error(&format!("{} parse error(s) found", parse_diagnostics.len()));
+    let Some(hir) = &result.hir else {
+        report(&result.diagnostics)?;
+        let errors = result
+            .diagnostics
+            .iter()
+            .filter(|d| d.severity == Severity::Error)
+            .count();
+        output::error(&format!("{} parse error(s) found", errors));
         return Err("parse error".to_string());
-    }
+    };
 
-    if verbose {
+    if verbose && !json {
         output::info(&format!("Parsed {} items", ast.items.len()));
     }
 
-    // Lower to HIR
-    // 降级到 HIR
-    let hir = lower(&ast);
+    if trace_imports {
+        let root = Path::new(file).parent().unwrap_or(Path::new("."));
+        let loader = ModuleLoader::new(root);
+        for item in &ast.items {
+            if let ItemKind::Import(import_def) = &item.kind {
+                let path = ModulePath::from_import_def(import_def);
+                eprint!("{}", loader.trace_path(&path, Some(&[])));
+            }
+        }
+    }
 
-    if verbose {
+    if verbose && !json {
         output::info(&format!("Lowered to {} HIR items", hir.items.len()));
     }
 
-    // Type check
-    // 类型检查
-    let type_diagnostics = check(&hir);
+    let annotated = annotate.then(|| annotate_types(&source, &result.item_types));
 
-    for diag in &type_diagnostics {
-        emit(&source, file, diag);
-    }
+    report(&result.diagnostics)?;
+
+    let errors = result
+        .diagnostics
+        .iter()
+        .filter(|d| d.severity == Severity::Error)
+        .count();
+    let warnings = result
+        .diagnostics
+        .iter()
+        .filter(|d| d.severity == Severity::Warning)
+        .count();
 
-    if !type_diagnostics.is_empty() {
-        output::error(&format!("{} type error(s) found", type_diagnostics.len()));
+    if errors > 0 {
+        output::error(&format!("{} type error(s) found", errors));
         return Err("type error".to_string());
     }
 
-    output::success("OK - No errors found");
+    if warnings > 0 && deny_warnings {
+        output::error(&format!(
+            "{} warning(s) found and --deny-warnings is set",
+            warnings
+        ));
+        return Err("warnings denied".to_string());
+    }
+
+    if json {
+        return Ok(());
+    }
+
+    if let Some(annotated) = annotated {
+        print!("{}", annotated);
+        if !annotated.ends_with('\n') {
+            println!();
+        }
+    }
+
+    if warnings > 0 {
+        output::warning(&format!("OK - {} warning(s)", warnings));
+    } else {
+        output::success("OK - No errors found");
+    }
     Ok(())
 }
+
+/// Write diagnostics to `path` as a SARIF log.
+/// 将诊断以 SARIF 日志的形式写入 `path`。
+fn write_sarif(
+    path: &Path,
+    file: &str,
+    source: &str,
+    diagnostics: &[Diagnostic],
+) -> Result<(), String> {
+    let log = to_sarif(file, source, diagnostics);
+    let text = serde_json::to_string_pretty(&log)
+        .map_err(|e| format!("cannot serialize SARIF log: {}", e))?;
+    fs::write(path, text).map_err(|e| format!("cannot write '{}': {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_warning_only_file_fails_only_with_deny_warnings() {
+        let path =
+            std::env::temp_dir().join(format!("neve-check-warn-{}.neve", std::process::id()));
+        fs::write(&path, "fn helper() = 1;\nlet x = 2;\n").unwrap();
+        let file = path.to_str().unwrap();
+
+        let check = |deny_warnings| {
+            let options = CheckOptions {
+                deny_warnings,
+                json: true,
+                ..CheckOptions::default()
+            };
+            run(file, &options)
+        };
+        assert_eq!(check(false), Ok(()));
+        assert_eq!(check(true), Err("warnings denied".to_string()));
+
+        let _ = fs::remove_file(&path);
+    }
+}
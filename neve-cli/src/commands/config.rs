@@ -4,10 +4,12 @@
 use crate::output;
 use crate::platform::{PlatformCapabilities, warn_system_config_unavailable};
 use neve_config::{
+    ConfigOptions, SystemConfig,
     generate::Generator,
-    generation::{GenerationManager, GenerationMetadata},
+    generation::{Generation, GenerationManager, GenerationMetadata, diff},
     module::Module,
 };
+use neve_store::Store;
 use std::path::PathBuf;
 
 /// Get the default configuration file path.
@@ -94,9 +96,14 @@ pub fn build() -> Result<(), String> {
         .to_system_config()
         .map_err(|e| format!("Failed to parse configuration: {}", e))?;
 
-    // Generate configuration files
-    // 生成配置文件
+    // Generate configuration files into a clean build directory, so files
+    // dropped from the configuration don't linger in the output
+    // 在干净的构建目录中生成配置文件，使从配置中移除的文件不会残留在输出中
     let output_dir = build_dir();
+    if output_dir.exists() {
+        std::fs::remove_dir_all(&output_dir)
+            .map_err(|e| format!("Failed to clean build directory: {}", e))?;
+    }
     let generator = Generator::new(output_dir.clone());
     let generated = generator
         .generate(&system_config)
@@ -112,10 +119,23 @@ pub fn build() -> Result<(), String> {
     let gen_manager = GenerationManager::new(generations_dir())
         .map_err(|e| format!("Failed to initialize generation manager: {}", e))?;
 
-    // Create store path from derivation
-    // 从派生创建存储路径
-    let drv = generator.to_derivation(&system_config);
-    let store_path = drv.drv_path();
+    // Add the generated output to the store, pointing the activation script's
+    // build directory paths at the store copy
+    // 将生成的输出添加到存储，并将激活脚本中的构建目录路径指向存储中的副本
+    let mut store = Store::open().map_err(|e| format!("Failed to open store: {}", e))?;
+    // Hold the store lock until the output is rooted, so a concurrent
+    // garbage collection can't delete it in between
+    // 在输出获得 GC 根之前持有存储锁，以防并发的垃圾回收在此期间将其删除
+    let store_lock = store
+        .lock_shared()
+        .map_err(|e| format!("Failed to lock store: {}", e))?;
+    let store_path = store
+        .add_dir_with_self_references(
+            &output_dir,
+            &system_config.name,
+            &output_dir.to_string_lossy(),
+        )
+        .map_err(|e| format!("Failed to add configuration to the store: {}", e))?;
 
     let metadata = GenerationMetadata::new()
         .name(&system_config.name)
@@ -125,7 +145,23 @@ pub fn build() -> Result<(), String> {
         .create_generation(&store_path, metadata)
         .map_err(|e| format!("Failed to create generation: {}", e))?;
 
+    // Root the output so the generation can still be rolled back to after
+    // the store is garbage collected
+    // 为输出添加 GC 根，使存储被垃圾回收后仍可回滚到该代
+    gen_manager
+        .add_gc_root(&generation, &mut store)
+        .map_err(|e| {
+            format!(
+                "Failed to protect generation from garbage collection: {}",
+                e
+            )
+        })?;
+    drop(store_lock);
+
     system_config.generation = generation.number;
+    gen_manager
+        .save_options(generation.number, &system_config.options)
+        .map_err(|e| format!("Failed to record configuration options: {}", e))?;
 
     output::success(&format!("Created generation {}.", generation.number));
     output::success("Configuration built successfully.");
@@ -162,6 +198,8 @@ pub fn switch() -> Result<(), String> {
                 .load_generation(gen_num)
                 .map_err(|e| format!("Failed to load generation: {}", e))?;
 
+            print_changes(&gen_manager, &generation)?;
+
             // For now, just report what we would do
             // 目前，只报告我们将要做什么
             println!(
@@ -183,9 +221,44 @@ pub fn switch() -> Result<(), String> {
     }
 }
 
-/// Rollback to a previous configuration.
-/// 回滚到上一个配置。
-pub fn rollback() -> Result<(), String> {
+/// Print what changed since the generation that was current when
+/// `generation` was built.
+/// 打印自构建 `generation` 时的当前代以来的变化。
+fn print_changes(gen_manager: &GenerationManager, generation: &Generation) -> Result<(), String> {
+    let Some(previous) = generation.metadata.previous else {
+        return Ok(());
+    };
+
+    let load = |number: u64| {
+        gen_manager
+            .load_options(number)
+            .map_err(|e| format!("Failed to load generation {} options: {}", number, e))
+    };
+    // Generations built before options were recorded can't be compared
+    // 在记录选项之前构建的代无法比较
+    let (Some(old), Some(new)) = (load(previous)?, load(generation.number)?) else {
+        return Ok(());
+    };
+    let with_options = |options: ConfigOptions| {
+        let mut config = SystemConfig::new("");
+        config.options = options;
+        config
+    };
+
+    println!("Changes since generation {}:", previous);
+    for line in diff(&with_options(old), &with_options(new))
+        .to_string()
+        .lines()
+    {
+        println!("  {}", line);
+    }
+    println!();
+    Ok(())
+}
+
+/// Rollback to the previous configuration, or to generation `to`.
+/// 回滚到上一个配置，或回滚到第 `to` 代。
+pub fn rollback(to: Option<u64>) -> Result<(), String> {
     // Check platform support
     // 检查平台支持
     let caps = PlatformCapabilities::detect();
@@ -201,6 +274,21 @@ pub fn rollback() -> Result<(), String> {
         .current_generation()
         .map_err(|e| format!("Failed to get current generation: {}", e))?;
 
+    if let Some(target) = to {
+        if current == Some(target) {
+            return Err(format!("Already at generation {}.", target));
+        }
+        let store = Store::open().map_err(|e| format!("Failed to open store: {}", e))?;
+        let generation = gen_manager
+            .rollback_to(target, &store)
+            .map_err(|e| format!("Failed to roll back to generation {}: {}", target, e))?;
+
+        println!("Rolled back to generation {}.", generation.number);
+        println!();
+        println!("Note: Full activation requires running 'neve config switch'.");
+        return Ok(());
+    }
+
     match current {
         Some(gen_num) if gen_num > 1 => {
             let prev_gen = gen_num - 1;
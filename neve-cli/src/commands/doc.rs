@@ -47,11 +47,40 @@ fn create_skin() -> MadSkin {
     skin
 }
 
+/// Output format for rendered documentation.
+/// 渲染文档的输出格式。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Render {
+    /// ANSI-styled terminal text, shown in a pager. / 带 ANSI 样式的终端文本，在分页器中显示。
+    Terminal,
+    /// Raw markdown. / 原始 markdown。
+    Markdown,
+    /// A roff man page. / roff 格式的 man 手册页。
+    Man,
+    /// Plain text without markup. / 不带标记的纯文本。
+    Plain,
+}
+
+impl Render {
+    /// Parse a `--render` argument.
+    /// 解析 `--render` 参数。
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "terminal" => Ok(Render::Terminal),
+            "markdown" => Ok(Render::Markdown),
+            "man" => Ok(Render::Man),
+            "plain" => Ok(Render::Plain),
+            _ => Err(format!(
+                "unknown render format: {}. Use 'terminal', 'markdown', 'man', or 'plain'",
+                name
+            )),
+        }
+    }
+}
+
 /// List available documentation topics.
 /// 列出可用的文档主题。
-pub fn list() -> Result<(), String> {
-    let skin = create_skin();
-
+pub fn list(render: Render) -> Result<(), String> {
     let content = r#"
 # NEVE DOCUMENTATION
 
@@ -70,74 +99,179 @@ pub fn list() -> Result<(), String> {
 ## Usage:
 
 ```
-neve doc <topic>          View a topic
-neve doc <topic> --en     View English section only
-neve doc <topic> --zh     View Chinese section only
-neve doc --list           List all topics
+neve doc <topic>                    View a topic
+neve doc <topic> --en               View English section only
+neve doc <topic> --zh               View Chinese section only
+neve doc <topic> --render <format>  Output as terminal, markdown, man, or plain
+neve doc --list                     List all topics
 ```
 
 ## Examples:
 
 ```
-neve doc quickstart       Full quickstart guide
-neve doc api --en         API reference (English)
-neve doc spec --zh        Language spec (Chinese)
+neve doc quickstart                 Full quickstart guide
+neve doc api --en                   API reference (English)
+neve doc spec --zh                  Language spec (Chinese)
+neve doc api --render markdown      Raw markdown for a converter
 ```
 "#;
 
-    println!("{}", skin.term_text(content));
+    println!("{}", render_markdown(content, "doc", render));
     Ok(())
 }
 
 /// View a documentation topic.
 /// 查看文档主题。
-pub fn view(topic: &str, lang: Option<&str>) -> Result<(), String> {
-    // Find the topic
-    // 查找主题
+pub fn view(topic: &str, lang: Option<&str>, render: Render) -> Result<(), String> {
+    let Some(markdown) = load(topic, lang) else {
+        eprintln!("Unknown topic: {}", topic);
+        eprintln!();
+        eprintln!("Available topics:");
+        for (name, _, desc) in TOPICS {
+            eprintln!("  {:12} - {}", name, desc);
+        }
+        return Ok(());
+    };
+
+    let rendered = render_markdown(&markdown, topic, render);
+
+    // Only styled output goes through a pager; the other formats are meant
+    // for piping into another tool
+    // 仅带样式的输出使用分页器；其他格式用于通过管道传给其他工具
+    if render != Render::Terminal || try_pager(&rendered).is_err() {
+        println!("{}", rendered);
+    }
+
+    Ok(())
+}
+
+/// Load a topic as markdown, filtered by language if requested.
+/// 以 markdown 形式加载主题，如有请求则按语言过滤。
+fn load(topic: &str, lang: Option<&str>) -> Option<String> {
     let content = TOPICS
         .iter()
         .find(|(name, _, _)| *name == topic)
-        .map(|(_, content, _)| *content);
-
-    let content = match content {
-        Some(c) => c,
-        None => {
-            eprintln!("Unknown topic: {}", topic);
-            eprintln!();
-            eprintln!("Available topics:");
-            for (name, _, desc) in TOPICS {
-                eprintln!("  {:12} - {}", name, desc);
-            }
-            return Ok(());
-        }
-    };
+        .map(|(_, content, _)| *content)?;
 
-    // Filter by language if requested
-    // 如果请求，按语言过滤
     let output = match lang {
         Some("en") => extract_section(content, "english"),
         Some("zh") => extract_section(content, "chinese"),
         _ => content.to_string(),
     };
 
-    // Clean up HTML anchors and render
-    // 清理 HTML 锚点并渲染
-    let cleaned = clean_markdown(&output);
+    // Clean up HTML anchors
+    // 清理 HTML 锚点
+    Some(clean_markdown(&output))
+}
 
-    // Render with termimad
-    // 使用 termimad 渲染
-    let skin = create_skin();
-    let rendered = skin.term_text(&cleaned);
+/// Present markdown in the requested format.
+/// 以请求的格式呈现 markdown。
+fn render_markdown(markdown: &str, title: &str, render: Render) -> String {
+    match render {
+        Render::Terminal => create_skin().term_text(markdown).to_string(),
+        Render::Markdown => markdown.to_string(),
+        Render::Man => to_man(markdown, title),
+        Render::Plain => to_plain(markdown),
+    }
+}
 
-    // Try to use a pager for better reading experience
-    // 尝试使用分页器以获得更好的阅读体验
-    if try_pager(&rendered.to_string()).is_err() {
-        // Fallback to direct output
-        // 回退到直接输出
-        println!("{}", rendered);
+/// Strip markdown markup, leaving readable plain text.
+/// 去除 markdown 标记，保留可读的纯文本。
+fn to_plain(markdown: &str) -> String {
+    let mut lines = Vec::new();
+    let mut in_code = false;
+
+    for line in markdown.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+            continue;
+        }
+        if in_code {
+            lines.push(format!("    {}", line));
+        } else if let Some((_, text)) = heading(line) {
+            lines.push(strip_inline(text));
+        } else {
+            lines.push(strip_inline(line));
+        }
     }
 
-    Ok(())
+    lines.join("\n")
+}
+
+/// Convert markdown to a roff man page.
+/// 将 markdown 转换为 roff 格式的 man 手册页。
+fn to_man(markdown: &str, title: &str) -> String {
+    let mut out = format!(".TH NEVE-{} 7\n", title.to_uppercase());
+    let mut in_code = false;
+
+    for line in markdown.lines() {
+        if line.trim_start().starts_with("```") {
+            out.push_str(if in_code { ".fi\n" } else { ".nf\n" });
+            in_code = !in_code;
+            continue;
+        }
+        let text = if in_code {
+            escape_roff(line)
+        } else {
+            match heading(line) {
+                Some((1, text)) => {
+                    format!(".SH {}", escape_roff(&strip_inline(text)).to_uppercase())
+                }
+                Some((_, text)) => format!(".SS {}", escape_roff(&strip_inline(text))),
+                None if line.trim().is_empty() => ".PP".to_string(),
+                None => escape_roff(&strip_inline(line)),
+            }
+        };
+        out.push_str(&text);
+        out.push('\n');
+    }
+    if in_code {
+        out.push_str(".fi\n");
+    }
+
+    out
+}
+
+/// Split an ATX heading into its level and text.
+/// 将 ATX 标题拆分为级别和文本。
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    let text = line[level..].strip_prefix(' ')?;
+    (1..=6).contains(&level).then_some((level, text.trim()))
+}
+
+/// Remove inline markup: bold markers, code spans and link targets.
+/// 移除行内标记：粗体标记、代码片段和链接目标。
+fn strip_inline(text: &str) -> String {
+    let text = text.replace("**", "").replace('`', "");
+
+    // [label](target) -> label
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text.as_str();
+    while let Some(open) = rest.find('[') {
+        let Some(close) = rest[open..].find("](").map(|i| open + i) else {
+            break;
+        };
+        let Some(end) = rest[close..].find(')').map(|i| close + i) else {
+            break;
+        };
+        out.push_str(&rest[..open]);
+        out.push_str(&rest[open + 1..close]);
+        rest = &rest[end + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Escape a line of text for roff.
+/// 为 roff 转义一行文本。
+fn escape_roff(line: &str) -> String {
+    let escaped = line.replace('\\', "\\e");
+    if escaped.starts_with('.') || escaped.starts_with('\'') {
+        format!("\\&{}", escaped)
+    } else {
+        escaped
+    }
 }
 
 /// Clean up markdown for better terminal rendering.
@@ -237,3 +371,45 @@ fn try_pager(content: &str) -> Result<(), Box<dyn std::error::Error>> {
     // 未找到分页器，返回错误以触发回退
     Err("No pager available".into())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_markdown_keeps_headings() {
+        let markdown = load("api", Some("en")).unwrap();
+        let rendered = render_markdown(&markdown, "api", Render::Markdown);
+        assert!(rendered.contains("## Core Functions"));
+        assert!(!rendered.contains("# 中文"));
+    }
+
+    #[test]
+    fn test_render_plain_strips_headings() {
+        let markdown = load("api", Some("en")).unwrap();
+        let rendered = render_markdown(&markdown, "api", Render::Plain);
+        assert!(rendered.lines().any(|line| line == "Core Functions"));
+        assert!(!rendered.lines().any(|line| line.starts_with('#')));
+        assert!(!rendered.contains("```"));
+    }
+
+    #[test]
+    fn test_render_man_sections() {
+        let rendered = render_markdown(
+            "# Intro\n\nSee `neve`.\n\n## Usage\n.hidden",
+            "x",
+            Render::Man,
+        );
+        assert!(rendered.starts_with(".TH NEVE-X 7\n"));
+        assert!(rendered.contains(".SH INTRO\n"));
+        assert!(rendered.contains("See neve.\n"));
+        assert!(rendered.contains(".SS Usage\n"));
+        assert!(rendered.contains("\\&.hidden"));
+    }
+
+    #[test]
+    fn test_parse_render() {
+        assert_eq!(Render::parse("man"), Ok(Render::Man));
+        assert!(Render::parse("html").is_err());
+    }
+}
@@ -3,29 +3,46 @@
 
 use crate::output;
 use neve_diagnostic::emit;
-use neve_eval::AstEvaluator;
-use neve_parser::parse;
+use neve_eval::{AstEvaluator, Value};
+use neve_typeck::{CompileOptions, compile_with};
 
 /// Run the eval command.
 /// 运行 eval 命令。
-pub fn run(expr: &str, verbose: bool) -> Result<(), String> {
+///
+/// `format` is `text` for the debug rendering or `json` for the canonical
+/// JSON form of the result (see [`Value::to_json`]).
+/// `format` 为 `text` 时输出调试渲染，为 `json` 时输出结果的规范 JSON 形式（参见 [`Value::to_json`]）。
+pub fn run(expr: &str, format: &str, verbose: bool) -> Result<(), String> {
+    let json = match format {
+        "text" => false,
+        "json" => true,
+        _ => return Err(format!("unknown format: {}. Use 'text' or 'json'", format)),
+    };
+
     // Prepare source for parsing
     // 准备用于解析的源码
     // Strategy: if there's content after the last semicolon that looks like an expression,
     // wrap it in a let binding so it becomes a valid item
     let source = prepare_source(expr);
 
-    let (file, diagnostics) = parse(&source);
+    // Builtins are only known at evaluation time, so the input is lowered
+    // but not type checked
+    // 内置函数仅在求值时可知，因此输入只降级而不进行类型检查
+    let options = CompileOptions {
+        warn_unused: false,
+        type_check: false,
+    };
+    let result = compile_with(&source, &options);
 
-    for diag in &diagnostics {
-        emit(&source, "<eval>", diag);
+    for diag in &result.diagnostics {
+        emit(&source, "<eval>", diag, output::color_choice());
     }
 
-    if !diagnostics.is_empty() {
+    if result.has_errors() {
         return Err("parse error".to_string());
     }
 
-    eval_and_print(&file, &source, verbose)
+    eval_and_print(&result.file, &source, json, verbose)
 }
 
 /// Prepare the source for parsing by wrapping expressions appropriately.
@@ -75,6 +92,7 @@ fn prepare_source(expr: &str) -> String {
 fn eval_and_print(
     file: &neve_syntax::SourceFile,
     source: &str,
+    json: bool,
     verbose: bool,
 ) -> Result<(), String> {
     if verbose {
@@ -86,10 +104,16 @@ fn eval_and_print(
     let mut evaluator = AstEvaluator::new();
 
     match evaluator.eval_file(file) {
+        Ok(value) if json => {
+            let json = value.to_json()?;
+            let text = serde_json::to_string_pretty(&json)
+                .map_err(|e| format!("failed to serialize: {}", e))?;
+            println!("{}", text);
+        }
         Ok(value) => {
             // Don't print Unit for statements that don't return values
             // 对于不返回值的语句，不打印 Unit
-            if !matches!(value, neve_eval::Value::Unit) || source.starts_with("let __result__") {
+            if !matches!(value, Value::Unit) || source.starts_with("let __result__") {
                 output::success(&format!("{value:?}"));
             }
         }
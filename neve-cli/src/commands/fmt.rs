@@ -57,6 +57,11 @@ pub fn check(file: &str) -> Result<(), String> {
 
 /// Format all Neve files in a directory.
 /// 格式化目录中的所有 Neve 文件。
+///
+/// Without `write`, nothing is modified: every unformatted file is reported
+/// and an error is returned if any file would change, for use in CI.
+/// 不带 `write` 时不修改任何文件：报告每个未格式化的文件，
+/// 若有文件会被更改则返回错误，供 CI 使用。
 pub fn format_dir(dir: &str, write: bool) -> Result<(), String> {
     let path = Path::new(dir);
 
@@ -64,13 +69,37 @@ pub fn format_dir(dir: &str, write: bool) -> Result<(), String> {
         return Err(format!("Not a directory: {}", dir));
     }
 
+    if !write {
+        return check_dir(path);
+    }
+
     let mut errors = Vec::new();
     format_dir_recursive(path, write, &mut errors)?;
 
     if errors.is_empty() {
         Ok(())
     } else {
-        Err(format!("{} files would be reformatted", errors.len()))
+        Err(format!("{} files failed to format", errors.len()))
+    }
+}
+
+/// Check all Neve files in a directory without modifying them.
+/// 检查目录中的所有 Neve 文件而不修改它们。
+fn check_dir(dir: &Path) -> Result<(), String> {
+    let report = neve_fmt::check_dir(dir).map_err(|e| format!("Format error: {}", e))?;
+
+    for file in &report.unformatted {
+        output::warning(&format!("Would reformat: {}", file.display()));
+    }
+    for (file, e) in &report.errors {
+        output::error(&format!("{}: {}", file.display(), e));
+    }
+
+    if report.is_clean() {
+        output::success(&report.summary());
+        Ok(())
+    } else {
+        Err(report.summary())
     }
 }
 
@@ -83,7 +112,12 @@ fn format_dir_recursive(dir: &Path, write: bool, errors: &mut Vec<String>) -> Re
         let entry = entry.map_err(|e| format!("Failed to read entry: {}", e))?;
         let path = entry.path();
 
-        if path.is_dir() {
+        let metadata =
+            fs::symlink_metadata(&path).map_err(|e| format!("Failed to read entry: {}", e))?;
+        if metadata.is_symlink() && path.is_dir() {
+            continue;
+        }
+        if metadata.is_dir() {
             format_dir_recursive(&path, write, errors)?;
         } else if path.extension().is_some_and(|ext| ext == "neve")
             && let Err(e) = run(path.to_str().unwrap(), write)
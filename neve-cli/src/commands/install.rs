@@ -5,113 +5,192 @@
 //! 将软件包安装到用户环境。
 
 use crate::output;
+use neve_builder::plan::{InstallPlan, PlanAction, plan_install, store_registry};
+use neve_builder::{Builder, BuilderConfig};
+use neve_config::profile::Profile;
+use neve_derive::StorePath;
+use neve_derive::resolve::{PackageLock, PackageRegistry};
+use neve_store::Store;
 use std::fs;
-use std::os::unix::fs::symlink;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+/// How a package gets into the profile.
+/// 软件包进入配置文件的方式。
+enum InstallSource {
+    /// A package with a derivation in the store, resolved and planned.
+    /// 存储中有推导的软件包，已解析并规划。
+    Planned(InstallPlan),
+    /// A store path without a derivation, linked as it is.
+    /// 没有推导的存储路径，按原样链接。
+    Path(PathBuf),
+}
 
 /// Install a package to the user environment.
 /// 将软件包安装到用户环境。
-pub fn run(package: &str) -> Result<(), String> {
-    let store_dir = get_store_dir();
-    let profile_dir = get_profile_dir();
-
-    // Find the package in the store
-    // 在存储中查找软件包
-    let package_path = find_package(&store_dir, package)?;
-
-    // Create profile directory if it doesn't exist
-    // 如果配置目录不存在，则创建它
-    fs::create_dir_all(&profile_dir)
-        .map_err(|e| format!("Failed to create profile directory: {}", e))?;
-
-    // Create generation directory
-    // 创建代目录
-    let generation = get_next_generation(&profile_dir)?;
-    let gen_dir = profile_dir.join(format!("generation-{}", generation));
-    fs::create_dir_all(&gen_dir)
-        .map_err(|e| format!("Failed to create generation directory: {}", e))?;
-
-    // Copy current generation's packages
-    // 复制当前代的软件包
-    let current_link = profile_dir.join("current");
-    if current_link.exists() {
-        let current_gen = fs::read_link(&current_link)
-            .map_err(|e| format!("Failed to read current link: {}", e))?;
-
-        // Copy manifest from current generation
-        // 从当前代复制清单
-        let manifest_src = current_gen.join("manifest");
-        if manifest_src.exists() {
-            let manifest_dst = gen_dir.join("manifest");
-            fs::copy(&manifest_src, &manifest_dst)
-                .map_err(|e| format!("Failed to copy manifest: {}", e))?;
+///
+/// Executes the plan [`dry_run`] prints: the package's derivation closure is
+/// realized, then its outputs are linked into a new generation. The new
+/// generation is written completely before `current` is switched to it, so
+/// an interrupted install leaves the previous generation active.
+/// 执行 [`dry_run`] 打印的计划：实现软件包的推导闭包，然后将其输出链接到新代中。
+/// 新代完整写入后才将 `current` 切换过去，因此中断的安装会保留上一代为活动代。
+///
+/// The first install of a package writes a lock file pinning its resolved
+/// versions, and later installs resolve only to those. With `frozen`, nothing
+/// is substituted and a package without a lock file is refused.
+/// 首次安装软件包时会写入固定其解析版本的锁文件，之后的安装只会解析到这些版本。
+/// 启用 `frozen` 时不会使用替代源，并且拒绝没有锁文件的软件包。
+pub fn run(package: &str, frozen: bool) -> Result<(), String> {
+    let config = BuilderConfig {
+        offline: frozen,
+        cancel: crate::interrupt::cancel_token(),
+        ..BuilderConfig::default()
+    };
+    let lock = load_lock(package)?;
+    let (mut builder, source) = prepare(package, config, lock.as_ref())?;
+    let profile = open_profile()?;
+
+    let new_paths = match source {
+        InstallSource::Planned(plan) => {
+            if lock.is_none() && frozen {
+                return Err(format!(
+                    "'{}' has no lock file and --frozen forbids creating one",
+                    package
+                ));
+            }
+            if !plan.build.is_noop() {
+                output::info(&format!(
+                    "Realizing {} derivation(s)",
+                    plan.build.steps().len()
+                ));
+            }
+            let drv = builder
+                .store_mut()
+                .read_derivation(&plan.drv_path)
+                .map_err(|e| format!("cannot read derivation: {}", e))?;
+            builder
+                .build(&drv)
+                .map_err(|e| format!("cannot build '{}': {}", package, e))?;
+            if lock.is_none() {
+                save_lock(package, &plan.lock)?;
+            }
+            plan.profile_add
+                .iter()
+                .map(|p| builder.store().to_path(p))
+                .collect()
         }
-    }
-
-    // Add the new package to the manifest
-    // 将新软件包添加到清单
-    let manifest_path = gen_dir.join("manifest");
-    let mut manifest = if manifest_path.exists() {
-        fs::read_to_string(&manifest_path).map_err(|e| format!("Failed to read manifest: {}", e))?
-    } else {
-        String::new()
+        InstallSource::Path(path) => vec![path],
     };
 
+    let mut packages = profile
+        .current_manifest()
+        .map_err(|e| format!("Failed to read manifest: {}", e))?;
+
     // Check if already installed
     // 检查是否已安装
-    if manifest
-        .lines()
-        .any(|line| line == package_path.to_string_lossy())
-    {
+    let added: Vec<PathBuf> = new_paths
+        .into_iter()
+        .filter(|p| !packages.contains(p))
+        .collect();
+    if added.is_empty() {
         output::info(&format!("Package '{package}' is already installed"));
-        // Clean up empty generation
-        // 清理空的代
-        let _ = fs::remove_dir_all(&gen_dir);
         return Ok(());
     }
 
-    manifest.push_str(&format!("{}\n", package_path.display()));
-    fs::write(&manifest_path, manifest).map_err(|e| format!("Failed to write manifest: {}", e))?;
-
-    // Create bin directory with symlinks
-    // 创建带有符号链接的 bin 目录
-    let bin_dir = gen_dir.join("bin");
-    fs::create_dir_all(&bin_dir).map_err(|e| format!("Failed to create bin directory: {}", e))?;
-
-    // Link binaries from the package
-    // 从软件包链接二进制文件
-    let pkg_bin = package_path.join("bin");
-    if pkg_bin.exists() {
-        for entry in
-            fs::read_dir(&pkg_bin).map_err(|e| format!("Failed to read package bin: {}", e))?
-        {
-            let entry = entry.map_err(|e| format!("Failed to read entry: {}", e))?;
-            let src = entry.path();
-            let dst = bin_dir.join(entry.file_name());
+    packages.extend(added.iter().cloned());
+    let generation = profile
+        .create_generation(&packages)
+        .map_err(|e| format!("Failed to create generation: {}", e))?;
 
-            if dst.exists() {
-                fs::remove_file(&dst)
-                    .map_err(|e| format!("Failed to remove existing symlink: {}", e))?;
+    output::success(&format!("Installed '{package}' to generation {generation}"));
+    for path in &added {
+        println!("  {package} -> {}", path.display());
+    }
+
+    Ok(())
+}
+
+/// Show what installing a package would do, without touching the store or profile.
+/// 显示安装软件包将执行的操作，而不修改存储或配置文件。
+///
+/// Packages with a derivation in the store are resolved and planned the same
+/// way as a real install; other store paths are linked as they are.
+/// 存储中有推导的软件包会像实际安装一样解析并规划；其他存储路径按原样链接。
+pub fn dry_run(package: &str) -> Result<(), String> {
+    let lock = load_lock(package)?;
+    let (builder, source) = prepare(package, BuilderConfig::default(), lock.as_ref())?;
+    let profile = Profile::open(get_profile_dir());
+
+    let link_paths = match source {
+        InstallSource::Planned(plan) => {
+            output::header("Resolved Packages");
+            for id in &plan.packages {
+                println!("  {} {}", id.name, id.version);
             }
 
-            symlink(&src, &dst).map_err(|e| format!("Failed to create symlink: {}", e))?;
+            output::header("Realization Plan");
+            let mut table = output::Table::new(vec!["Action", "Derivation"]);
+            for step in plan.build.steps() {
+                table.add_row(vec![&step.action.to_string(), &step.label]);
+            }
+            table.print();
+            println!(
+                "  {} to build, {} to fetch, {} cached",
+                plan.build.with_action(PlanAction::Build).count(),
+                plan.build.with_action(PlanAction::Fetch).count(),
+                plan.build.with_action(PlanAction::Cached).count()
+            );
+
+            plan.profile_add
+                .iter()
+                .map(|p| builder.store().to_path(p))
+                .collect()
         }
+        InstallSource::Path(path) => vec![path],
+    };
+
+    // Describe the profile change
+    // 描述配置文件的更改
+    let generation = profile
+        .next_generation()
+        .map_err(|e| format!("Failed to read profile: {}", e))?;
+    output::header("Profile Change");
+    for path in &link_paths {
+        println!("  + {}", path.display());
     }
+    output::info(&format!(
+        "Would create generation {generation} (dry run, nothing changed)"
+    ));
+
+    Ok(())
+}
 
-    // Update current symlink
-    // 更新当前符号链接
-    if current_link.exists() {
-        fs::remove_file(&current_link)
-            .map_err(|e| format!("Failed to remove current link: {}", e))?;
+/// Resolve and plan the installation of a package, without changing anything.
+/// 解析并规划软件包的安装，不做任何更改。
+fn prepare(
+    package: &str,
+    config: BuilderConfig,
+    lock: Option<&PackageLock>,
+) -> Result<(Builder, InstallSource), String> {
+    let store_dir = get_store_dir();
+    if !store_dir.exists() {
+        return Err(format!("Package '{}' not found in store", package));
     }
 
-    symlink(&gen_dir, &current_link)
-        .map_err(|e| format!("Failed to create current link: {}", e))?;
+    let store =
+        Store::open_at(store_dir.clone()).map_err(|e| format!("cannot open store: {}", e))?;
+    let mut builder = Builder::with_config(store, config);
+    let registry =
+        store_registry(&mut builder, package).map_err(|e| format!("cannot read store: {}", e))?;
 
-    output::success(&format!("Installed '{package}' to generation {generation}"));
-    println!("  {package} -> {}", package_path.display());
+    if registry.get_versions(package).is_empty() {
+        let path = find_package(&store_dir, package)?;
+        return Ok((builder, InstallSource::Path(path)));
+    }
 
-    Ok(())
+    let plan = plan_install(&mut builder, &registry, package, lock)
+        .map_err(|e| format!("cannot plan install of '{}': {}", package, e))?;
+    Ok((builder, InstallSource::Planned(plan)))
 }
 
 /// Get the store directory.
@@ -129,13 +208,60 @@ fn get_profile_dir() -> PathBuf {
     PathBuf::from(home).join(".neve").join("profile")
 }
 
+/// Get the lock file pinning the versions `package` resolves to.
+/// 获取固定 `package` 解析版本的锁文件。
+fn get_lock_path(package: &str) -> PathBuf {
+    let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+    PathBuf::from(home)
+        .join(".neve")
+        .join("locks")
+        .join(format!("{}.lock", package))
+}
+
+/// Load the lock file of `package`, if it has one.
+/// 加载 `package` 的锁文件（如果存在）。
+fn load_lock(package: &str) -> Result<Option<PackageLock>, String> {
+    let path = get_lock_path(package);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let json =
+        fs::read_to_string(&path).map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+    PackageLock::from_json(&json)
+        .map(Some)
+        .map_err(|e| format!("invalid lock file {}: {}", path.display(), e))
+}
+
+/// Write the lock file of `package`.
+/// 写入 `package` 的锁文件。
+fn save_lock(package: &str, lock: &PackageLock) -> Result<(), String> {
+    let path = get_lock_path(package);
+    let json = lock
+        .to_json()
+        .map_err(|e| format!("cannot serialize lock file: {}", e))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("cannot create {}: {}", dir.display(), e))?;
+    }
+    fs::write(&path, json).map_err(|e| format!("cannot write {}: {}", path.display(), e))
+}
+
+/// Open the user's package profile.
+/// 打开用户的软件包配置文件。
+pub(crate) fn open_profile() -> Result<Profile, String> {
+    Profile::new(get_profile_dir()).map_err(|e| format!("Failed to open profile: {}", e))
+}
+
 /// Find a package in the store.
 /// 在存储中查找软件包。
-fn find_package(store_dir: &PathBuf, package: &str) -> Result<PathBuf, String> {
+///
+/// `package` is either a store entry's file name or its exact name without
+/// the hash prefix; derivations are never matched.
+/// `package` 是存储条目的文件名，或不含哈希前缀的确切名称；永远不会匹配推导。
+fn find_package(store_dir: &Path, package: &str) -> Result<PathBuf, String> {
     // Direct path
     // 直接路径
     let direct = store_dir.join(package);
-    if direct.exists() {
+    if direct.exists() && !package.ends_with(".drv") {
         return Ok(direct);
     }
 
@@ -145,11 +271,12 @@ fn find_package(store_dir: &PathBuf, package: &str) -> Result<PathBuf, String> {
         for entry in fs::read_dir(store_dir).map_err(|e| format!("Failed to read store: {}", e))? {
             let entry = entry.map_err(|e| format!("Failed to read entry: {}", e))?;
             let name = entry.file_name();
-            let name_str = name.to_string_lossy();
 
             // Match by name (hash-name format)
             // 按名称匹配（hash-name 格式）
-            if name_str.contains(package) {
+            let matches = StorePath::parse_name(&name.to_string_lossy())
+                .is_some_and(|p| p.name() == package && !p.name().ends_with(".drv"));
+            if matches {
                 return Ok(entry.path());
             }
         }
@@ -158,69 +285,31 @@ fn find_package(store_dir: &PathBuf, package: &str) -> Result<PathBuf, String> {
     Err(format!("Package '{}' not found in store", package))
 }
 
-/// Get the next generation number.
-/// 获取下一个代编号。
-fn get_next_generation(profile_dir: &PathBuf) -> Result<u32, String> {
-    let mut max_gen = 0;
-
-    if profile_dir.exists() {
-        for entry in
-            fs::read_dir(profile_dir).map_err(|e| format!("Failed to read profile: {}", e))?
-        {
-            let entry = entry.map_err(|e| format!("Failed to read entry: {}", e))?;
-            let name = entry.file_name();
-            let name_str = name.to_string_lossy();
-
-            if let Some(num_str) = name_str.strip_prefix("generation-")
-                && let Ok(num) = num_str.parse::<u32>()
-            {
-                max_gen = max_gen.max(num);
-            }
-        }
-    }
-
-    Ok(max_gen + 1)
-}
-
 /// List installed packages.
 /// 列出已安装的软件包。
 pub fn list() -> Result<(), String> {
-    let profile_dir = get_profile_dir();
-    let current_link = profile_dir.join("current");
-
-    if !current_link.exists() {
-        output::info("No packages installed");
-        return Ok(());
-    }
-
-    let current_gen =
-        fs::read_link(&current_link).map_err(|e| format!("Failed to read current link: {}", e))?;
+    let profile = open_profile()?;
+    let packages = profile
+        .current_manifest()
+        .map_err(|e| format!("Failed to read manifest: {}", e))?;
 
-    let manifest_path = current_gen.join("manifest");
-    if !manifest_path.exists() {
+    if packages.is_empty() {
         output::info("No packages installed");
         return Ok(());
     }
 
-    let manifest = fs::read_to_string(&manifest_path)
-        .map_err(|e| format!("Failed to read manifest: {}", e))?;
-
     output::header("Installed Packages");
 
     let mut table = output::Table::new(vec!["#", "Package"]);
-    let mut count = 0;
-
-    for line in manifest.lines() {
-        if !line.is_empty() {
-            count += 1;
-            // Extract package name from path
-            // 从路径中提取软件包名称
-            let name = PathBuf::from(line)
-                .file_name()
-                .map(|n| n.to_string_lossy().to_string())
-                .unwrap_or_else(|| line.to_string());
-            table.add_row(vec![&count.to_string(), &name]);
-        }
+
+    for (count, path) in (1..).zip(&packages) {
+        // Extract package name from path
+        // 从路径中提取软件包名称
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.display().to_string());
+        table.add_row(vec![&count.to_string(), &name]);
     }
 
     table.print();
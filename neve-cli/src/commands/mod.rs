@@ -3,6 +3,7 @@
 
 // Cross-platform commands (language features)
 // 跨平台命令（语言功能）
+pub mod analyze;
 pub mod check;
 pub mod doc;
 pub mod eval;
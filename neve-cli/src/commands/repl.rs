@@ -4,7 +4,7 @@
 use crate::output;
 use neve_diagnostic::emit;
 use neve_eval::{AstEnv, AstEvaluator, Value, builtins};
-use neve_parser::parse;
+use neve_parser::{has_unclosed_delimiters, parse};
 use neve_syntax::PatternKind;
 use rustyline::DefaultEditor;
 use rustyline::error::ReadlineError;
@@ -36,6 +36,14 @@ pub fn run() -> Result<(), String> {
         let readline = rl.readline(prompt);
         match readline {
             Ok(line) => {
+                // A blank continuation line cancels the pending input
+                // 空白的续行会取消待处理的输入
+                if in_multiline && line.trim().is_empty() {
+                    input_buffer.clear();
+                    in_multiline = false;
+                    continue;
+                }
+
                 // Handle multi-line input
                 // 处理多行输入
                 // If line ends with backslash, continue on next line
@@ -52,11 +60,21 @@ pub fn run() -> Result<(), String> {
                 // 如果处于多行模式，追加此行并处理
                 if in_multiline {
                     input_buffer.push_str(&line);
-                    in_multiline = false;
                 } else {
                     input_buffer = line.to_string();
                 }
 
+                // Keep reading while brackets are unbalanced
+                // 括号不平衡时继续读取
+                if !input_buffer.trim_start().starts_with(':')
+                    && has_unclosed_delimiters(&input_buffer)
+                {
+                    input_buffer.push('\n');
+                    in_multiline = true;
+                    continue;
+                }
+                in_multiline = false;
+
                 let input = input_buffer.trim();
 
                 if input.is_empty() {
@@ -89,6 +107,8 @@ pub fn run() -> Result<(), String> {
                             println!("  - Use 'fn name(...) = ...' to define functions");
                             println!("  - All definitions persist across inputs");
                             println!("  - End line with \\ for multi-line input");
+                            println!("  - Unclosed brackets continue onto the next line");
+                            println!("  - A blank line or Ctrl-C cancels a continued input");
                             input_buffer.clear();
                             continue;
                         }
@@ -149,7 +169,7 @@ pub fn run() -> Result<(), String> {
                                     let (ast, diagnostics) = parse(&content);
                                     if !diagnostics.is_empty() {
                                         for diag in &diagnostics {
-                                            emit(&content, file_path, diag);
+                                            emit(&content, file_path, diag, output::color_choice());
                                         }
                                         continue;
                                     }
@@ -247,7 +267,7 @@ pub fn run() -> Result<(), String> {
 
                 if !diagnostics.is_empty() {
                     for diag in &diagnostics {
-                        emit(input, "<repl>", diag);
+                        emit(input, "<repl>", diag, output::color_choice());
                     }
                     input_buffer.clear();
                     continue;
@@ -327,6 +347,8 @@ pub fn run() -> Result<(), String> {
             }
             Err(ReadlineError::Interrupted) => {
                 println!("^C");
+                input_buffer.clear();
+                in_multiline = false;
                 continue;
             }
             Err(ReadlineError::Eof) => {
@@ -10,14 +10,18 @@ use std::path::Path;
 
 /// Run a Neve file.
 /// 运行 Neve 文件。
-pub fn run(file: &str, verbose: bool) -> Result<(), String> {
+///
+/// With `trace_imports`, how each import was resolved is printed to stderr
+/// once evaluation finishes, whether or not it succeeded.
+/// 启用 `trace_imports` 时，求值结束后（无论成功与否）将每个导入的解析过程打印到 stderr。
+pub fn run(file: &str, verbose: bool, trace_imports: bool) -> Result<(), String> {
     let path = Path::new(file);
     let source = fs::read_to_string(path).map_err(|e| format!("cannot read file '{file}': {e}"))?;
 
     let (ast, diagnostics) = parse(&source);
 
     for diag in &diagnostics {
-        emit(&source, file, diag);
+        emit(&source, file, diag, output::color_choice());
     }
 
     if !diagnostics.is_empty() {
@@ -36,7 +40,16 @@ pub fn run(file: &str, verbose: bool) -> Result<(), String> {
         AstEvaluator::new()
     };
 
-    match evaluator.eval_file(&ast) {
+    if trace_imports {
+        evaluator = evaluator.with_import_trace();
+    }
+
+    let result = evaluator.eval_file(&ast);
+    for trace in evaluator.import_trace() {
+        eprint!("{trace}");
+    }
+
+    match result {
         Ok(value) => {
             // Only print non-unit values
             // 只打印非 unit 值
@@ -44,6 +57,10 @@ pub fn run(file: &str, verbose: bool) -> Result<(), String> {
                 output::success(&format!("{value:?}"));
             }
         }
+        Err(neve_eval::EvalError::ImportConflict(diag)) => {
+            emit(&source, file, &diag, output::color_choice());
+            return Err("evaluation error".to_string());
+        }
         Err(e) => {
             output::error(&format!("{e:?}"));
             return Err("evaluation error".to_string());
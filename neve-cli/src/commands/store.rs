@@ -2,7 +2,7 @@
 //! `neve store` 命令。
 
 use crate::output;
-use neve_store::{Store, gc::GarbageCollector};
+use neve_store::{Database, Store, StoreStats, gc::GarbageCollector};
 
 /// Run garbage collection.
 /// 运行垃圾回收。
@@ -72,33 +72,57 @@ pub fn gc() -> Result<(), String> {
 
 /// Show store information.
 /// 显示存储信息。
-pub fn info() -> Result<(), String> {
-    let store = Store::open().map_err(|e| format!("Failed to open store: {}", e))?;
-
-    let paths = store
-        .list_paths()
-        .map_err(|e| format!("Failed to list paths: {}", e))?;
-
-    let size = store
-        .size()
-        .map_err(|e| format!("Failed to get store size: {}", e))?;
+pub fn info(top: usize, json: bool) -> Result<(), String> {
+    let mut store = Store::open().map_err(|e| format!("Failed to open store: {}", e))?;
+    let mut db = Database::open(store.root().to_path_buf())
+        .map_err(|e| format!("Failed to open store database: {}", e))?;
+
+    let stats = StoreStats::collect(&mut store, &mut db, top)
+        .map_err(|e| format!("Failed to collect store statistics: {}", e))?;
+
+    if json {
+        let largest: Vec<_> = stats
+            .largest
+            .iter()
+            .map(|p| serde_json::json!({ "path": p.path.display_name(), "size": p.size }))
+            .collect();
+        let report = serde_json::json!({
+            "location": store.root().display().to_string(),
+            "paths": stats.path_count,
+            "size": stats.total_size,
+            "gc_roots": stats.gc_roots,
+            "largest": largest,
+        });
+        let text = serde_json::to_string_pretty(&report)
+            .map_err(|e| format!("Failed to serialize store info: {}", e))?;
+        println!("{}", text);
+        return Ok(());
+    }
 
     output::header("Neve Store Information");
     output::kv("Location", &store.root().display().to_string());
-    output::kv("Paths", &paths.len().to_string());
-    output::kv("Size", &output::format_size(size));
+    output::kv("Paths", &stats.path_count.to_string());
+    output::kv("Size", &output::format_size(stats.total_size));
+    output::kv("GC roots", &stats.gc_roots.to_string());
     println!();
 
-    if !paths.is_empty() {
-        output::section("Recent paths");
-        let mut table = output::Table::new(vec!["#", "Path"]);
-        for (i, path) in paths.iter().take(10).enumerate() {
-            table.add_row(vec![&(i + 1).to_string(), &path.display_name()]);
+    if !stats.largest.is_empty() {
+        output::section("Largest paths");
+        let mut table = output::Table::new(vec!["#", "Size", "Path"]);
+        for (i, entry) in stats.largest.iter().enumerate() {
+            table.add_row(vec![
+                &(i + 1).to_string(),
+                &output::format_size(entry.size),
+                &entry.path.display_name(),
+            ]);
         }
         table.print();
 
-        if paths.len() > 10 {
-            output::info(&format!("... and {} more", paths.len() - 10));
+        if stats.path_count > stats.largest.len() {
+            output::info(&format!(
+                "... and {} more",
+                stats.path_count - stats.largest.len()
+            ));
         }
     }
 
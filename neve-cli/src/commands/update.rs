@@ -5,12 +5,19 @@
 //! 更新 flake 输入和依赖。
 
 use crate::output;
-use neve_config::flake::{Flake, FlakeLock};
+use neve_config::flake::{Flake, FlakeLock, FlakeLockEntry};
+use neve_derive::{VersionChange, diff_versions};
+use std::collections::BTreeMap;
+use std::fmt;
 use std::path::Path;
 
 /// Run the update command.
 /// 运行更新命令。
-pub fn run() -> Result<(), String> {
+///
+/// With `frozen`, inputs that need the network are not contacted and the
+/// command fails instead of writing a changed flake.lock.
+/// 启用 `frozen` 时，不会联系需要网络的输入；若 flake.lock 会改变，命令将失败而不写入。
+pub fn run(frozen: bool) -> Result<(), String> {
     // Find flake in current directory
     // 在当前目录中查找 flake
     let flake_path = Path::new("flake.neve");
@@ -42,9 +49,9 @@ pub fn run() -> Result<(), String> {
         output::info("Creating new lock file...");
     }
 
-    // Clear existing lock to force re-resolution
-    // 清除现有锁以强制重新解析
-    flake.lock = FlakeLock::new();
+    // Clear existing lock to force re-resolution, keeping it to report changes
+    // 清除现有锁以强制重新解析，保留旧锁以报告变化
+    let previous = std::mem::replace(&mut flake.lock, FlakeLock::new());
 
     // Resolve and lock all inputs
     // 解析并锁定所有输入
@@ -57,13 +64,23 @@ pub fn run() -> Result<(), String> {
     for (i, (name, input)) in flake.inputs.iter().enumerate() {
         output::numbered_item(i + 1, &format!("Updating '{}'", name));
 
-        match update_input(&input.url, input.rev.as_deref(), input.branch.as_deref()) {
+        match update_input(
+            &input.url,
+            input.rev.as_deref(),
+            input.branch.as_deref(),
+            frozen,
+        ) {
             Ok(entry) => {
                 flake.lock.inputs.insert(name.clone(), entry);
                 updated_count += 1;
                 output::success(&format!("  Updated: {}", name));
             }
             Err(e) => {
+                // Keep the previous lock entry for inputs that failed
+                // 对更新失败的输入保留之前的锁条目
+                if let Some(entry) = previous.inputs.get(name) {
+                    flake.lock.inputs.insert(name.clone(), entry.clone());
+                }
                 failed_inputs.push((name.clone(), e.clone()));
                 output::warning(&format!("  Failed to update '{}': {}", name, e));
             }
@@ -76,16 +93,31 @@ pub fn run() -> Result<(), String> {
         updated_count, total_inputs
     ));
 
-    // Save the lock file
-    // 保存锁文件
-    if updated_count > 0 {
+    // Report what moved, and save the lock file only if something did
+    // 报告变化的内容，仅在有变化时保存锁文件
+    let changes = lock_changes(&previous, &flake.lock);
+    if changes.is_empty() {
+        output::info("All inputs are up to date; flake.lock is unchanged");
+    } else {
+        output::info("Changes:");
+        for change in &changes {
+            println!("{}", change);
+        }
+
+        if frozen {
+            return Err(format!(
+                "flake.lock is out of date ({} input(s) changed) and --frozen forbids updating it",
+                changes.len()
+            ));
+        }
+
         flake
             .save_lock()
             .map_err(|e| format!("failed to save lock file: {}", e))?;
 
         output::success(&format!(
-            "Updated {} input(s), lock file written to flake.lock",
-            updated_count
+            "{} input(s) changed, lock file written to flake.lock",
+            changes.len()
         ));
     }
 
@@ -110,22 +142,70 @@ pub fn run() -> Result<(), String> {
     }
 }
 
+/// Describe how the locked inputs moved between two lock files, sorted by
+/// input name.
+/// 描述锁定的输入在两个锁文件之间的变化，按输入名称排序。
+fn lock_changes<'a>(old: &'a FlakeLock, new: &'a FlakeLock) -> Vec<VersionChange<LockedInput<'a>>> {
+    let locked = |lock: &'a FlakeLock| -> BTreeMap<String, LockedInput<'a>> {
+        lock.inputs
+            .iter()
+            .map(|(name, entry)| (name.clone(), LockedInput(entry)))
+            .collect()
+    };
+    diff_versions(&locked(old), &locked(new))
+}
+
+/// The locked version of a flake input.
+/// flake 输入的锁定版本。
+///
+/// Inputs are identified by revision and content hash, and shown by revision
+/// when they have one and by content hash otherwise.
+/// 输入以修订版本和内容哈希标识；有修订版本时显示修订版本，否则显示内容哈希。
+#[derive(Clone, Copy)]
+struct LockedInput<'a>(&'a FlakeLockEntry);
+
+impl PartialEq for LockedInput<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.rev == other.0.rev && self.0.hash == other.0.hash
+    }
+}
+
+impl fmt::Display for LockedInput<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let version = self.0.rev.as_deref().unwrap_or(&self.0.hash);
+        f.write_str(version.get(..12).unwrap_or(version))
+    }
+}
+
 /// Update a single input and return its lock entry.
 /// 更新单个输入并返回其锁条目。
+///
+/// With `frozen`, only local path inputs are updated; any other input needs
+/// the network and is refused.
+/// 启用 `frozen` 时仅更新本地路径输入；其他输入需要网络，将被拒绝。
 fn update_input(
     url: &str,
     rev: Option<&str>,
     branch: Option<&str>,
+    frozen: bool,
 ) -> Result<neve_config::flake::FlakeLockEntry, String> {
     use std::time::SystemTime;
 
+    let is_path = url.starts_with("path:") || url.starts_with("./") || url.starts_with("/");
+    if frozen && !is_path {
+        return Err(format!(
+            "network access is disabled (--frozen): cannot fetch {}",
+            url
+        ));
+    }
+
     // Parse the URL to determine the type
     // 解析 URL 以确定类型
     let (resolved_url, resolved_rev, hash) = if url.starts_with("github:") {
         update_github_input(url, rev, branch)?
     } else if url.starts_with("git+") || url.ends_with(".git") {
         update_git_input(url, rev, branch)?
-    } else if url.starts_with("path:") || url.starts_with("./") || url.starts_with("/") {
+    } else if is_path {
         update_path_input(url)?
     } else if url.starts_with("http://") || url.starts_with("https://") {
         update_url_input(url)?
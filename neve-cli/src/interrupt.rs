@@ -0,0 +1,41 @@
+//! Ctrl-C handling.
+//! Ctrl-C 处理。
+//!
+//! The first Ctrl-C cancels the running downloads and builds, which stop
+//! promptly and clean up after themselves; a second one kills the process
+//! as usual.
+//! 第一次 Ctrl-C 会取消正在进行的下载和构建，它们会及时停止并自行清理；
+//! 第二次 Ctrl-C 会照常终止进程。
+
+use neve_fetch::CancelToken;
+use std::sync::OnceLock;
+
+/// Token cancelled by Ctrl-C. / 被 Ctrl-C 取消的令牌。
+static TOKEN: OnceLock<CancelToken> = OnceLock::new();
+
+/// Get the token cancelled by Ctrl-C, installing the handler on first use.
+/// 获取被 Ctrl-C 取消的令牌，首次使用时安装处理程序。
+pub fn cancel_token() -> CancelToken {
+    TOKEN
+        .get_or_init(|| {
+            unsafe {
+                libc::signal(libc::SIGINT, on_sigint as *const () as libc::sighandler_t);
+            }
+            CancelToken::new()
+        })
+        .clone()
+}
+
+/// Cancel the token, then restore the default handler for the next Ctrl-C.
+/// 取消令牌，然后为下一次 Ctrl-C 恢复默认处理程序。
+///
+/// Only atomic stores and `signal` are used, which are async-signal-safe.
+/// 只使用原子存储和 `signal`，它们是异步信号安全的。
+extern "C" fn on_sigint(_: libc::c_int) {
+    if let Some(token) = TOKEN.get() {
+        token.cancel();
+    }
+    unsafe {
+        libc::signal(libc::SIGINT, libc::SIG_DFL);
+    }
+}
@@ -2,10 +2,14 @@
 //! Neve CLI - Neve 语言的命令行界面。
 
 mod commands;
+#[cfg(unix)]
+mod interrupt;
 mod output;
 mod platform;
 
 use clap::{Parser, Subcommand};
+use neve_diagnostic::ColorChoice;
+use std::path::PathBuf;
 
 /// Main CLI structure.
 /// 主 CLI 结构体。
@@ -23,6 +27,14 @@ struct Cli {
     /// Suppress output. / 抑制输出。
     #[arg(short, long, global = true)]
     quiet: bool,
+
+    /// Forbid network access and lock file changes. / 禁止网络访问和锁文件更改。
+    #[arg(long, global = true, alias = "offline")]
+    frozen: bool,
+
+    /// When to color output (auto, always, never). / 何时为输出着色（auto、always、never）。
+    #[arg(long, global = true, value_name = "WHEN", default_value = "auto")]
+    color: ColorChoice,
 }
 
 /// Available CLI commands.
@@ -33,18 +45,56 @@ enum Commands {
     Eval {
         /// The expression to evaluate. / 要求值的表达式。
         expr: String,
+
+        /// Output format (text, json). / 输出格式（text, json）。
+        #[arg(long, default_value = "text")]
+        format: String,
     },
 
     /// Run a Neve file. / 运行 Neve 文件。
     Run {
         /// The file to run. / 要运行的文件。
         file: String,
+
+        /// Print how each import is resolved. / 打印每个导入的解析过程。
+        #[arg(long)]
+        trace_imports: bool,
     },
 
     /// Type check a file. / 类型检查文件。
     Check {
         /// The file to check. / 要检查的文件。
         file: String,
+
+        /// Treat warnings as errors. / 将警告视为错误。
+        #[arg(long)]
+        deny_warnings: bool,
+
+        /// Print the source annotated with inferred types. / 打印带有推断类型注释的源代码。
+        #[arg(long)]
+        annotate: bool,
+
+        /// Print how each import is resolved. / 打印每个导入的解析过程。
+        #[arg(long)]
+        trace_imports: bool,
+
+        /// Don't warn about unused imports, bindings and variables. / 不对未使用的导入、绑定和变量发出警告。
+        #[arg(long)]
+        allow_unused: bool,
+
+        /// Print diagnostics to stdout as a JSON array. / 将诊断以 JSON 数组形式打印到标准输出。
+        #[arg(long, conflicts_with = "annotate")]
+        json: bool,
+
+        /// Also write diagnostics to this file as SARIF. / 同时将诊断以 SARIF 格式写入此文件。
+        #[arg(long, value_name = "FILE")]
+        sarif: Option<PathBuf>,
+    },
+
+    /// Analyze the match expressions in a file. / 分析文件中的匹配表达式。
+    Analyze {
+        /// The file to analyze. / 要分析的文件。
+        file: String,
     },
 
     /// Format a file or directory. / 格式化文件或目录。
@@ -73,6 +123,10 @@ enum Commands {
         /// List all available topics. / 列出所有可用主题。
         #[arg(long, short)]
         list: bool,
+
+        /// Output format (terminal, markdown, man, plain). / 输出格式（terminal, markdown, man, plain）。
+        #[arg(long, default_value = "terminal")]
+        render: String,
     },
 
     /// Build a package (Unix only). / 构建软件包（仅限 Unix）。
@@ -84,6 +138,26 @@ enum Commands {
         /// Build backend (native, docker, simple). / 构建后端（native, docker, simple）。
         #[arg(long, default_value = "auto")]
         backend: String,
+
+        /// Treat warnings as errors. / 将警告视为错误。
+        #[arg(long)]
+        deny_warnings: bool,
+
+        /// Keep building independent derivations after a failure. / 失败后继续构建独立的派生。
+        #[arg(long, short = 'k')]
+        keep_going: bool,
+
+        /// Keep the build directories of failed builds. / 保留失败构建的构建目录。
+        #[arg(long)]
+        keep_failed: bool,
+
+        /// Binary cache to try before building (repeatable). / 构建前尝试的二进制缓存（可重复）。
+        #[arg(long = "substituter", value_name = "URL")]
+        substituters: Vec<String>,
+
+        /// Print the dependency graph in DOT format instead of building. / 以 DOT 格式打印依赖图而不进行构建。
+        #[arg(long)]
+        graph: bool,
     },
 
     /// Package management commands (Unix only). / 软件包管理命令（仅限 Unix）。
@@ -146,7 +220,8 @@ enum FmtAction {
         /// The file to check. / 要检查的文件。
         file: String,
     },
-    /// Format all files in a directory. / 格式化目录中的所有文件。
+    /// Format all files in a directory, or check them without `--write`.
+    /// 格式化目录中的所有文件，不带 `--write` 时仅检查。
     Dir {
         /// The directory to format. / 要格式化的目录。
         dir: String,
@@ -165,6 +240,11 @@ enum PackageAction {
     Install {
         /// Package to install. / 要安装的软件包。
         package: String,
+
+        /// Show what would be fetched, built and linked without changing anything.
+        /// 显示将要获取、构建和链接的内容，而不做任何更改。
+        #[arg(long)]
+        dry_run: bool,
     },
     /// Remove a package. / 移除软件包。
     Remove {
@@ -189,7 +269,12 @@ enum ConfigAction {
     /// Interactively switch to a specific generation. / 交互式切换到特定代。
     SwitchTo,
     /// Rollback to previous configuration. / 回滚到上一个配置。
-    Rollback,
+    Rollback {
+        /// Generation to roll back to instead of the previous one.
+        /// 要回滚到的代，而不是上一代。
+        #[arg(long, value_name = "N")]
+        to: Option<u64>,
+    },
     /// List configuration generations. / 列出配置代。
     List,
 }
@@ -202,20 +287,51 @@ enum StoreAction {
     /// Run garbage collection. / 运行垃圾回收。
     Gc,
     /// Show store information. / 显示存储信息。
-    Info,
+    Info {
+        /// Number of largest paths to list. / 列出的最大路径数量。
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+        /// Print machine-readable JSON. / 输出机器可读的 JSON。
+        #[arg(long)]
+        json: bool,
+    },
 }
 
 /// Main entry point.
 /// 主入口点。
 fn main() {
     let cli = Cli::parse();
+    output::set_color_choice(cli.color);
 
     let result = match cli.command {
         // Cross-platform commands (language features)
         // 跨平台命令（语言功能）
-        Commands::Eval { expr } => commands::eval::run(&expr, cli.verbose),
-        Commands::Run { file } => commands::run::run(&file, cli.verbose),
-        Commands::Check { file } => commands::check::run(&file, cli.verbose),
+        Commands::Eval { expr, format } => commands::eval::run(&expr, &format, cli.verbose),
+        Commands::Run {
+            file,
+            trace_imports,
+        } => commands::run::run(&file, cli.verbose, trace_imports),
+        Commands::Check {
+            file,
+            deny_warnings,
+            annotate,
+            trace_imports,
+            allow_unused,
+            json,
+            sarif,
+        } => commands::check::run(
+            &file,
+            &commands::check::CheckOptions {
+                verbose: cli.verbose,
+                deny_warnings,
+                annotate,
+                trace_imports,
+                allow_unused,
+                json,
+                sarif,
+            },
+        ),
+        Commands::Analyze { file } => commands::analyze::run(&file),
         Commands::Fmt { action } => match action {
             FmtAction::File { file, write } => commands::fmt::run(&file, write),
             FmtAction::Check { file } => commands::fmt::check(&file),
@@ -227,9 +343,10 @@ fn main() {
             en,
             zh,
             list,
-        } => {
+            render,
+        } => commands::doc::Render::parse(&render).and_then(|render| {
             if list || topic.is_none() {
-                commands::doc::list()
+                commands::doc::list(render)
             } else {
                 let lang = if en {
                     Some("en")
@@ -238,9 +355,9 @@ fn main() {
                 } else {
                     None
                 };
-                commands::doc::view(topic.as_deref().unwrap(), lang)
+                commands::doc::view(topic.as_deref().unwrap(), lang, render)
             }
-        }
+        }),
         Commands::Info { package, platform } => {
             if platform || package.is_none() {
                 commands::info::platform_info()
@@ -261,10 +378,41 @@ fn main() {
         // Unix-only commands (package management)
         // 仅限 Unix 的命令（软件包管理）
         #[cfg(unix)]
-        Commands::Build { package, backend } => commands::build::run(package.as_deref(), &backend),
+        Commands::Build {
+            package,
+            deny_warnings,
+            graph: true,
+            ..
+        } => commands::build::graph(package.as_deref(), deny_warnings),
+        #[cfg(unix)]
+        Commands::Build {
+            package,
+            backend,
+            deny_warnings,
+            keep_going,
+            keep_failed,
+            substituters,
+            graph: false,
+        } => commands::build::run(
+            package.as_deref(),
+            &commands::build::BuildOptions {
+                backend,
+                deny_warnings,
+                keep_going,
+                keep_failed,
+                substituters,
+                frozen: cli.frozen,
+            },
+        ),
         #[cfg(unix)]
         Commands::Package { action } => match action {
-            PackageAction::Install { package } => commands::install::run(&package),
+            PackageAction::Install { package, dry_run } => {
+                if dry_run {
+                    commands::install::dry_run(&package)
+                } else {
+                    commands::install::run(&package, cli.frozen)
+                }
+            }
             PackageAction::Remove { package } => commands::remove::run(&package),
             PackageAction::List => commands::install::list(),
             PackageAction::Rollback => commands::remove::rollback(),
@@ -272,19 +420,19 @@ fn main() {
         #[cfg(unix)]
         Commands::Search { query } => commands::search::run(&query),
         #[cfg(unix)]
-        Commands::Update => commands::update::run(),
+        Commands::Update => commands::update::run(cli.frozen),
         #[cfg(unix)]
         Commands::Config { action } => match action {
             ConfigAction::Build => commands::config::build(),
             ConfigAction::Switch => commands::config::switch(),
             ConfigAction::SwitchTo => commands::config::switch_interactive(),
-            ConfigAction::Rollback => commands::config::rollback(),
+            ConfigAction::Rollback { to } => commands::config::rollback(to),
             ConfigAction::List => commands::config::list_generations(),
         },
         #[cfg(unix)]
         Commands::Store { action } => match action {
             StoreAction::Gc => commands::store::gc(),
-            StoreAction::Info => commands::store::info(),
+            StoreAction::Info { top, json } => commands::store::info(top, json),
         },
     };
 
@@ -6,9 +6,10 @@
 //! 为 Neve CLI 提供彩色终端输出、进度指示器、spinner、
 //! 表格和结构化格式化。
 
+use neve_diagnostic::ColorChoice;
 use std::io::{self, Write};
-use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
 use std::thread;
 use std::time::Duration;
 
@@ -23,12 +24,34 @@ const BLUE: &str = "\x1b[34m";
 const MAGENTA: &str = "\x1b[35m";
 const CYAN: &str = "\x1b[36m";
 
+/// The `--color` choice, set once at startup.
+/// `--color` 选项，在启动时设置一次。
+static COLOR_CHOICE: OnceLock<ColorChoice> = OnceLock::new();
+
+/// Set the color choice from the command line.
+/// 从命令行设置颜色选项。
+pub fn set_color_choice(choice: ColorChoice) {
+    let _ = COLOR_CHOICE.set(choice);
+}
+
+/// The color choice for diagnostics and other output.
+/// 诊断和其他输出的颜色选项。
+pub fn color_choice() -> ColorChoice {
+    COLOR_CHOICE.get().copied().unwrap_or_default()
+}
+
 /// Check if the terminal supports colors.
 /// 检查终端是否支持颜色。
 pub fn supports_color() -> bool {
+    match color_choice() {
+        ColorChoice::Always => return true,
+        ColorChoice::Never => return false,
+        ColorChoice::Auto => {}
+    }
+
     // Check NO_COLOR environment variable
     // 检查 NO_COLOR 环境变量
-    if std::env::var("NO_COLOR").is_ok() {
+    if std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) {
         return false;
     }
 
@@ -242,11 +265,7 @@ impl ProgressBar {
     /// Render the progress bar.
     /// 渲染进度条。
     fn render(&self) {
-        let percent = if self.total > 0 {
-            (self.current * 100) / self.total
-        } else {
-            0
-        };
+        let percent = (self.current * 100).checked_div(self.total).unwrap_or(0);
         let filled = (self.current * self.width) / self.total.max(1);
         let empty = self.width - filled;
 
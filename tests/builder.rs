@@ -1,10 +1,20 @@
 //! Integration tests for neve-builder crate.
 
-use neve_builder::BuilderConfig;
+use neve_builder::executor::claim_build_dir;
+use neve_builder::graph::{BuildGraph, NodeKind};
 use neve_builder::output::{format_size, output_size};
+use neve_builder::plan::{PlanAction, plan_install, store_registry};
 use neve_builder::sandbox::{IsolationLevel, Sandbox, SandboxConfig};
+use neve_builder::{BuildError, Builder, BuilderConfig};
+use neve_derive::resolve::{
+    Dependency, MemoryRegistry, PackageId, PackageMetadata, PackageRegistry, Version,
+    VersionConstraint,
+};
+use neve_derive::{Derivation, Hash, Output, StorePath};
+use neve_store::Store;
 use std::env;
 use std::fs;
+use std::path::Path;
 
 // Config tests
 
@@ -13,6 +23,9 @@ fn test_builder_config_default() {
     let config = BuilderConfig::default();
     assert!(config.cores >= 1);
     assert_eq!(config.max_jobs, 1);
+    assert!(config.env_allowlist.is_empty());
+    assert_eq!(config.memory_limit_bytes, None);
+    assert_eq!(config.cpu_quota, None);
 }
 
 #[test]
@@ -426,3 +439,917 @@ fn test_sandbox_rapid_create_cleanup() {
         assert!(!root.exists());
     }
 }
+
+// ============================================================================
+// BuildGraph 测试
+// ============================================================================
+
+#[test]
+fn test_build_graph_diamond() {
+    let root = env::temp_dir().join(format!("neve-graph-diamond-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    let mut store = Store::open_at(root.clone()).unwrap();
+
+    // top -> left, right; left, right -> base; base -> src
+    let src = store.add_content(b"source", "src.tar").unwrap();
+    let base = Derivation::builder("base", "1.0")
+        .input_src(src.clone())
+        .build();
+    let base_path = store.add_derivation(&base).unwrap();
+    let left = Derivation::builder("left", "1.0")
+        .input_drv(base_path.clone(), vec!["out".to_string()])
+        .build();
+    let right = Derivation::builder("right", "1.0")
+        .input_drv(base_path.clone(), vec!["out".to_string()])
+        .build();
+    let left_path = store.add_derivation(&left).unwrap();
+    let right_path = store.add_derivation(&right).unwrap();
+    let top = Derivation::builder("top", "1.0")
+        .input_drv(left_path.clone(), vec!["out".to_string()])
+        .input_drv(right_path.clone(), vec!["out".to_string()])
+        .build();
+
+    let graph = BuildGraph::from_derivation(&mut store, &top).unwrap();
+
+    // The shared base and its source appear once
+    assert_eq!(graph.nodes().len(), 5);
+    assert_eq!(graph.edges().len(), 5);
+    assert_eq!(graph.root().unwrap().label, "top-1.0");
+    assert_eq!(graph.node(&src).unwrap().kind, NodeKind::Source);
+    assert_eq!(graph.dependencies(&top.drv_path()).len(), 2);
+    assert_eq!(graph.dependencies(&base_path).len(), 1);
+
+    let dot = graph.to_dot();
+    assert!(dot.starts_with("digraph build {\n"));
+    assert!(dot.trim_end().ends_with('}'));
+    assert_eq!(dot.matches(" -> ").count(), 5);
+    assert_eq!(dot.matches("shape=box").count(), 4);
+    assert_eq!(dot.matches("shape=ellipse").count(), 1);
+    assert!(dot.contains(&format!(
+        "\"{}\" -> \"{}\";",
+        left_path.display_name(),
+        base_path.display_name()
+    )));
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_build_graph_missing_input() {
+    let root = env::temp_dir().join(format!("neve-graph-missing-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    let mut store = Store::open_at(root.clone()).unwrap();
+
+    let dep = Derivation::builder("dep", "1.0").build();
+    let top = Derivation::builder("top", "1.0")
+        .input_drv(dep.drv_path(), vec!["out".to_string()])
+        .build();
+
+    assert!(BuildGraph::from_derivation(&mut store, &top).is_err());
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+// ============================================================================
+// 构建计划测试
+// ============================================================================
+
+#[test]
+fn test_plan_install_with_dependency() {
+    let root = env::temp_dir().join(format!("neve-plan-install-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    let store = Store::open_at(root.clone()).unwrap();
+
+    // lib is already realized, app has to be built
+    let lib_out = store.add_content(b"lib", "lib-1.0").unwrap();
+    let mut lib_output = Output::new("out");
+    lib_output.path = Some(lib_out);
+    let lib = Derivation::builder("lib", "1.0").output(lib_output).build();
+
+    let mut app_output = Output::new("out");
+    app_output.path = Some(StorePath::new(Hash::of(b"app-out"), "app-1.0".to_string()));
+    let mut builder = Builder::new(store);
+    let lib_path = builder.store_mut().add_derivation(&lib).unwrap();
+    let app = Derivation::builder("app", "1.0")
+        .input_drv(lib_path.clone(), vec!["out".to_string()])
+        .output(app_output.clone())
+        .build();
+    let app_path = builder.store_mut().add_derivation(&app).unwrap();
+
+    let mut registry = MemoryRegistry::new();
+    registry.add(PackageMetadata {
+        id: PackageId::new("lib", Version::new(1, 0, 0)),
+        dependencies: vec![],
+        derivation_path: Some(lib_path.clone()),
+    });
+    registry.add(PackageMetadata {
+        id: PackageId::new("app", Version::new(1, 0, 0)),
+        dependencies: vec![Dependency::new(
+            "lib",
+            VersionConstraint::Compatible(Version::new(1, 0, 0)),
+        )],
+        derivation_path: Some(app_path.clone()),
+    });
+
+    let plan = plan_install(&mut builder, &registry, "app", None).unwrap();
+
+    let names: Vec<_> = plan.packages.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, vec!["lib", "app"]);
+
+    let steps = plan.build.steps();
+    assert_eq!(steps.len(), 2);
+    assert_eq!(steps[0].drv_path, lib_path);
+    assert_eq!(steps[0].action, PlanAction::Cached);
+    assert_eq!(steps[1].drv_path, app_path);
+    assert_eq!(steps[1].label, "app-1.0");
+    assert_eq!(steps[1].action, PlanAction::Build);
+    assert!(!plan.build.is_noop());
+    assert_eq!(plan.drv_path, app_path);
+    assert_eq!(plan.profile_add, vec![app_output.path.clone().unwrap()]);
+
+    // A newer release is picked up unless the first plan's lock pins app
+    registry.add(PackageMetadata {
+        id: PackageId::new("app", Version::new(1, 1, 0)),
+        dependencies: vec![],
+        derivation_path: Some(app_path.clone()),
+    });
+    let unlocked = plan_install(&mut builder, &registry, "app", None).unwrap();
+    assert_eq!(unlocked.lock.packages["app"], Version::new(1, 1, 0));
+    let locked = plan_install(&mut builder, &registry, "app", Some(&plan.lock)).unwrap();
+    assert_eq!(locked.lock, plan.lock);
+
+    // With a binary cache holding app, nothing is built
+    let out = app_output.path.unwrap();
+    let plan = builder.plan_with(&app, |p| *p == out).unwrap();
+    assert_eq!(plan.steps().len(), 1);
+    assert_eq!(plan.with_action(PlanAction::Fetch).count(), 1);
+    assert_eq!(plan.with_action(PlanAction::Build).count(), 0);
+
+    // Planning does not realize anything
+    assert!(!builder.store().path_exists(&out));
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_plan_install_from_store_registry() {
+    let root = env::temp_dir().join(format!("neve-plan-store-registry-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    let mut builder = Builder::new(Store::open_at(root.clone()).unwrap());
+
+    let lib = Derivation::builder("lib", "1.2.0")
+        .output(Output::new("out"))
+        .build();
+    let lib_path = builder.store_mut().add_derivation(&lib).unwrap();
+    let app = Derivation::builder("app", "2.0.0")
+        .input_drv(lib_path.clone(), vec!["out".to_string()])
+        .output(Output::new("out"))
+        .build();
+    let app_path = builder.store_mut().add_derivation(&app).unwrap();
+    // Not a version number, so not a package
+    let dev = Derivation::builder("app", "unstable").build();
+    builder.store_mut().add_derivation(&dev).unwrap();
+    // Unrelated to app, so not read
+    let other = Derivation::builder("other", "1.0.0").build();
+    builder.store_mut().add_derivation(&other).unwrap();
+
+    let registry = store_registry(&mut builder, "app").unwrap();
+    assert_eq!(registry.get_versions("lib"), vec![Version::new(1, 2, 0)]);
+    assert_eq!(registry.get_versions("app"), vec![Version::new(2, 0, 0)]);
+    assert!(registry.get_versions("other").is_empty());
+    let app_meta = registry
+        .get_metadata("app", &Version::new(2, 0, 0))
+        .unwrap();
+    assert_eq!(app_meta.derivation_path, Some(app_path.clone()));
+    assert_eq!(app_meta.dependencies.len(), 1);
+    assert_eq!(app_meta.dependencies[0].name, "lib");
+
+    let plan = plan_install(&mut builder, &registry, "app", None).unwrap();
+    let names: Vec<_> = plan.packages.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, vec!["lib", "app"]);
+    let steps: Vec<_> = plan.build.steps().iter().map(|s| &s.drv_path).collect();
+    assert_eq!(steps, vec![&lib_path, &app_path]);
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+// Build environment tests
+
+#[test]
+fn test_build_env_exposes_input_paths() {
+    let root = env::temp_dir().join(format!("neve-build-env-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    let store = Store::open_at(root.join("store")).unwrap();
+    let src = store.add_content(b"hello", "src").unwrap();
+
+    let config = BuilderConfig {
+        temp_dir: root.join("tmp"),
+        ..Default::default()
+    };
+    let mut builder = Builder::with_config(store, config);
+    let drv = Derivation::builder("env-test", "1.0")
+        .builder_path("/bin/sh")
+        .args([
+            "-c",
+            "echo \"$src\" > \"$out/src-path\" && cat \"$src\" > \"$out/src-content\"",
+        ])
+        .input_src(src.clone())
+        .output(Output::new("out"))
+        .build();
+
+    let result = builder.build(&drv).unwrap();
+    let out = builder.store().to_path(&result.outputs["out"]);
+    let written = fs::read_to_string(out.join("src-path")).unwrap();
+    assert!(written.trim().ends_with(&src.display_name()), "{}", written);
+    assert_eq!(
+        fs::read_to_string(out.join("src-content")).unwrap(),
+        "hello"
+    );
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_build_streams_log_lines() {
+    let root = env::temp_dir().join(format!("neve-build-log-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    let temp_dir = root.join("tmp");
+    let store = Store::open_at(root.join("store")).unwrap();
+    let config = BuilderConfig {
+        temp_dir: temp_dir.clone(),
+        ..Default::default()
+    };
+    let mut builder = Builder::with_config(store, config);
+
+    // The builder only finishes once the first line has reached the sink
+    let drv = Derivation::builder("log-test", "1.0")
+        .builder_path("/bin/sh")
+        .args([
+            "-c",
+            "echo ready; i=0; while [ ! -e \"$TMPDIR/go\" ]; do \
+             i=$((i+1)); [ $i -gt 100 ] && exit 1; sleep 0.05; done; \
+             echo warning >&2; echo done > \"$out/result\"",
+        ])
+        .output(Output::new("out"))
+        .build();
+    let go = temp_dir.join(format!("{}-0/build/tmp/go", drv.hash().to_short_hex()));
+
+    let mut lines = Vec::new();
+    let result = builder
+        .build_with_log(&drv, &mut |line| {
+            if line == "ready" {
+                fs::write(&go, "").unwrap();
+            }
+            lines.push(line.to_string());
+        })
+        .unwrap();
+
+    assert_eq!(lines, ["ready", "warning"]);
+    assert!(result.log.contains("ready\n"), "{}", result.log);
+    assert!(result.log.contains("warning\n"), "{}", result.log);
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_build_timeout_kills_builder() {
+    let root = env::temp_dir().join(format!("neve-build-timeout-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    let store = Store::open_at(root.join("store")).unwrap();
+    let config = BuilderConfig {
+        temp_dir: root.join("tmp"),
+        timeout: 1,
+        ..Default::default()
+    };
+    let mut builder = Builder::with_config(store, config);
+    let drv = Derivation::builder("sleepy", "1.0")
+        .builder_path("/bin/sh")
+        .args(["-c", "sleep 10"])
+        .output(Output::new("out"))
+        .build();
+
+    let start = std::time::Instant::now();
+    let err = builder.build(&drv).unwrap_err();
+    assert!(start.elapsed() < std::time::Duration::from_secs(5));
+    let BuildError::BuildFailed(msg) = &err else {
+        panic!("unexpected error: {err}");
+    };
+    assert_eq!(msg, "timed out after 1 seconds");
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_cancel_kills_running_build() {
+    use neve_fetch::CancelToken;
+
+    let root = env::temp_dir().join(format!("neve-build-cancel-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    let store = Store::open_at(root.join("store")).unwrap();
+    let cancel = CancelToken::new();
+    let config = BuilderConfig {
+        temp_dir: root.join("tmp"),
+        cancel: cancel.clone(),
+        ..Default::default()
+    };
+    let mut builder = Builder::with_config(store, config);
+    let drv = Derivation::builder("sleepy", "1.0")
+        .builder_path("/bin/sh")
+        .args(["-c", "sleep 10"])
+        .output(Output::new("out"))
+        .build();
+
+    let canceller = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(200));
+        cancel.cancel();
+    });
+    let start = std::time::Instant::now();
+    let err = builder.build(&drv).unwrap_err();
+    assert!(start.elapsed() < std::time::Duration::from_secs(5));
+    assert!(matches!(err, BuildError::Cancelled), "{err}");
+    canceller.join().unwrap();
+
+    // Once cancelled, nothing new is built
+    let summary = builder.build_all(&[drv]);
+    assert!(!summary.is_success());
+    assert!(summary.built.is_empty());
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_keep_failed_reports_build_dir() {
+    let root = env::temp_dir().join(format!("neve-keep-failed-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    let temp_dir = root.join("tmp");
+    let drv = Derivation::builder("kept", "1.0")
+        .builder_path("/bin/sh")
+        .args(["-c", "echo partial > \"$TMPDIR/partial\"; exit 1"])
+        .output(Output::new("out"))
+        .build();
+
+    let builder_with = |keep_failed| {
+        let store = Store::open_at(root.join("store")).unwrap();
+        let config = BuilderConfig {
+            temp_dir: temp_dir.clone(),
+            keep_failed,
+            ..Default::default()
+        };
+        Builder::with_config(store, config)
+    };
+
+    // The failure names the kept directory, which still holds the build's files
+    let err = builder_with(true).build(&drv).unwrap_err();
+    let kept = temp_dir.join(format!("{}-0", drv.hash().to_short_hex()));
+    let BuildError::BuildFailed(msg) = &err else {
+        panic!("unexpected error: {err}");
+    };
+    let first_line = msg.lines().next().unwrap();
+    assert!(
+        first_line.contains(&kept.display().to_string()),
+        "{first_line}"
+    );
+    assert_eq!(
+        fs::read_to_string(kept.join("build/tmp/partial")).unwrap(),
+        "partial\n"
+    );
+
+    // Without keep_failed the directory is removed
+    fs::remove_dir_all(&temp_dir).unwrap();
+    let err = builder_with(false).build(&drv).unwrap_err();
+    assert!(!err.to_string().contains("kept at"), "{err}");
+    assert_eq!(fs::read_dir(&temp_dir).unwrap().count(), 0);
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_build_dirs_are_distinct_and_cleaned_up() {
+    let root = env::temp_dir().join(format!("neve-build-dirs-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    let store = Store::open_at(root.join("store")).unwrap();
+    let temp_dir = root.join("tmp");
+    let config = BuilderConfig {
+        temp_dir: temp_dir.clone(),
+        keep_failed: true,
+        ..Default::default()
+    };
+    let mut builder = Builder::with_config(store, config);
+
+    let failing = |name: &str| {
+        Derivation::builder(name, "1.0")
+            .builder_path("/bin/sh")
+            .args(["-c", "exit 1"])
+            .output(Output::new("out"))
+            .build()
+    };
+    let dir_names = || {
+        let mut names: Vec<String> = fs::read_dir(&temp_dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    };
+
+    // Kept failures of different derivations get distinct directories
+    let (a, b) = (failing("dir-a"), failing("dir-b"));
+    assert!(builder.build(&a).is_err());
+    assert!(builder.build(&b).is_err());
+    let mut expected = vec![
+        format!("{}-0", a.hash().to_short_hex()),
+        format!("{}-0", b.hash().to_short_hex()),
+    ];
+    expected.sort();
+    assert_eq!(dir_names(), expected);
+
+    // A directory still held by the same derivation is skipped
+    let next = claim_build_dir(&temp_dir, &a).unwrap();
+    assert_eq!(
+        next.file_name().unwrap().to_string_lossy(),
+        format!("{}-1", a.hash().to_short_hex())
+    );
+    fs::remove_dir(&next).unwrap();
+
+    // A successful build leaves nothing behind
+    fs::remove_dir_all(&temp_dir).unwrap();
+    let ok = Derivation::builder("dir-ok", "1.0")
+        .builder_path("/bin/sh")
+        .args(["-c", "echo ok > \"$out/ok\""])
+        .output(Output::new("out"))
+        .build();
+    builder.build(&ok).unwrap();
+    assert!(dir_names().is_empty());
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_keep_going_builds_independent_derivations() {
+    let root = env::temp_dir().join(format!("neve-keep-going-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    let store = Store::open_at(root.join("store")).unwrap();
+    let config = BuilderConfig {
+        temp_dir: root.join("tmp"),
+        keep_going: true,
+        ..Default::default()
+    };
+    let mut builder = Builder::with_config(store, config);
+
+    let broken = Derivation::builder("broken", "1.0")
+        .builder_path("/bin/sh")
+        .args(["-c", "exit 1"])
+        .output(Output::new("out"))
+        .build();
+    let broken_path = builder.store_mut().add_derivation(&broken).unwrap();
+    let dependent = Derivation::builder("dependent", "1.0")
+        .builder_path("/bin/sh")
+        .args(["-c", "echo dependent > \"$out/file\""])
+        .input_drv(broken_path, vec!["out".to_string()])
+        .output(Output::new("out"))
+        .build();
+    let fine = Derivation::builder("fine", "1.0")
+        .builder_path("/bin/sh")
+        .args(["-c", "echo fine > \"$out/file\""])
+        .output(Output::new("out"))
+        .build();
+    let targets = [broken, dependent, fine];
+
+    // The independent target still builds; the dependent one is skipped
+    let summary = builder.build_all(&targets);
+    assert!(!summary.is_success());
+    assert_eq!(summary.built.len(), 1);
+    let out = builder.store().to_path(&summary.built[0].outputs["out"]);
+    assert_eq!(fs::read_to_string(out.join("file")).unwrap(), "fine\n");
+    assert_eq!(summary.failed.len(), 2);
+    assert!(matches!(summary.failed[0], BuildError::BuildFailed(_)));
+    assert!(matches!(summary.failed[1], BuildError::DependencyFailed(_)));
+
+    // Without keep-going the first failure stops the run
+    let store = Store::open_at(root.join("store")).unwrap();
+    let config = BuilderConfig {
+        temp_dir: root.join("tmp"),
+        ..Default::default()
+    };
+    let summary = Builder::with_config(store, config).build_all(&targets);
+    assert!(summary.built.is_empty());
+    assert_eq!(summary.failed.len(), 1);
+    assert_eq!(summary.not_attempted, 2);
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_build_all_runs_independent_derivations_in_parallel() {
+    let root = env::temp_dir().join(format!("neve-parallel-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    let store = Store::open_at(root.join("store")).unwrap();
+    let config = BuilderConfig {
+        temp_dir: root.join("tmp"),
+        max_jobs: 2,
+        ..Default::default()
+    };
+    let mut builder = Builder::with_config(store, config);
+
+    let left = Derivation::builder("left", "1.0")
+        .builder_path("/bin/sh")
+        .args(["-c", "sleep 1; echo left > \"$out/file\""])
+        .output(Output::new("out"))
+        .build();
+    let right = Derivation::builder("right", "1.0")
+        .builder_path("/bin/sh")
+        .args(["-c", "sleep 1; echo right > \"$out/file\""])
+        .output(Output::new("out"))
+        .build();
+    let left_path = builder.store_mut().add_derivation(&left).unwrap();
+    let right_path = builder.store_mut().add_derivation(&right).unwrap();
+    let both = Derivation::builder("both", "1.0")
+        .builder_path("/bin/sh")
+        .args(["-c", "cat \"$left/file\" \"$right/file\" > \"$out/file\""])
+        .input_drv(left_path, vec!["out".to_string()])
+        .input_drv(right_path, vec!["out".to_string()])
+        .output(Output::new("out"))
+        .build();
+
+    // Only the combined target is requested; its inputs run side by side
+    let start = std::time::Instant::now();
+    let summary = builder.build_all(std::slice::from_ref(&both));
+    let elapsed = start.elapsed().as_secs_f64();
+    assert!(summary.is_success(), "{:?}", summary.failed);
+    assert_eq!(summary.built.len(), 1);
+    let out = builder.store().to_path(&summary.built[0].outputs["out"]);
+    assert_eq!(
+        fs::read_to_string(out.join("file")).unwrap(),
+        "left\nright\n"
+    );
+    assert!(
+        elapsed < 1.9,
+        "inputs were built one at a time ({elapsed:.2}s)"
+    );
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_output_self_reference_placeholder() {
+    let root = env::temp_dir().join(format!("neve-placeholder-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    let store = Store::open_at(root.join("store")).unwrap();
+    let config = BuilderConfig {
+        temp_dir: root.join("tmp"),
+        ..Default::default()
+    };
+    let mut builder = Builder::with_config(store, config);
+
+    let drv = Derivation::builder("selfref", "1.0");
+    let placeholder = drv.placeholder("out");
+    let drv = drv
+        .builder_path("/bin/sh")
+        .env("self", placeholder.clone())
+        .args([
+            "-c",
+            "printf %s \"$out\" > \"$out/from-out\"; printf %s \"$self\" > \"$out/from-env\"",
+        ])
+        .output(Output::new("out"))
+        .build();
+    assert_eq!(drv.placeholder("out"), placeholder);
+
+    let result = builder.build(&drv).unwrap();
+    let out = &result.outputs["out"];
+    let realized = builder.store().to_path(out);
+    let expected = realized.to_string_lossy();
+
+    // Both references end up as the realized store path
+    let from_out = fs::read_to_string(realized.join("from-out")).unwrap();
+    let from_env = fs::read_to_string(realized.join("from-env")).unwrap();
+    assert_eq!(from_out, expected);
+    assert_eq!(from_env, expected);
+    assert!(!from_out.contains(&placeholder));
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+/// Serve `files` over HTTP by request path, answering 404 for anything else.
+fn serve_files(files: std::collections::HashMap<String, Vec<u8>>) -> String {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { break };
+            let mut request = [0u8; 1024];
+            let n = stream.read(&mut request).unwrap_or(0);
+            let request = String::from_utf8_lossy(&request[..n]);
+            let path = request.split_whitespace().nth(1).unwrap_or("");
+            let (status, body) = match files.get(path) {
+                Some(body) => ("200 OK", body.as_slice()),
+                None => ("404 Not Found", &b""[..]),
+            };
+            let header = format!(
+                "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                status,
+                body.len()
+            );
+            let _ = stream.write_all(header.as_bytes());
+            let _ = stream.write_all(body);
+        }
+    });
+    format!("http://{}", addr)
+}
+
+#[test]
+fn test_build_substitutes_cached_outputs() {
+    use neve_builder::substitute::narinfo_name;
+    use neve_store::nar::create_nar;
+
+    let root = env::temp_dir().join(format!("neve-substitute-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+
+    // A derivation that can't be built locally, with an input that can't be
+    // built either, and a cached output for it
+    let broken = Derivation::builder("broken", "1.0")
+        .builder_path("/bin/sh")
+        .args(["-c", "exit 1"])
+        .output(Output::new("out"))
+        .build();
+    let cached = Derivation::builder("cached", "1.0")
+        .builder_path("/bin/sh")
+        .args(["-c", "exit 1"])
+        .input_drv(broken.drv_path(), vec!["out".to_string()])
+        .output(Output::new("out"))
+        .build();
+    let content = root.join("content");
+    fs::create_dir_all(&content).unwrap();
+    fs::write(content.join("file"), "from the cache\n").unwrap();
+    let nar = create_nar(&content).unwrap();
+    let out_path = StorePath::new(Hash::of(b"cached output"), "cached-1.0".to_string());
+    // Caches may publish sha256 NAR hashes
+    let narinfo = format!(
+        "StorePath: {}\nURL: nar/cached.nar\nCompression: none\nNarHash: {}\n",
+        out_path,
+        Hash::of_with(neve_derive::HashAlgo::Sha256, &nar).to_hex()
+    );
+    // The same narinfo, for a derivation expecting another output path
+    let mut pinned_output = Output::new("out");
+    pinned_output.path = Some(StorePath::new(
+        Hash::of(b"pinned output"),
+        "cached-1.0".to_string(),
+    ));
+    let pinned = Derivation::builder("cached", "1.0")
+        .builder_path("/bin/sh")
+        .args(["-c", "exit 1"])
+        .output(pinned_output)
+        .build();
+    let mut files = std::collections::HashMap::new();
+    files.insert(
+        format!("/{}.narinfo", narinfo_name(&pinned, "out")),
+        narinfo.clone().into_bytes(),
+    );
+    files.insert(
+        format!("/{}.narinfo", narinfo_name(&cached, "out")),
+        narinfo.into_bytes(),
+    );
+    files.insert("/nar/cached.nar".to_string(), nar);
+    let cache_url = serve_files(files);
+
+    // An unreachable substituter is skipped
+    let unreachable = {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        format!("http://{}", listener.local_addr().unwrap())
+    };
+    let store = Store::open_at(root.join("store")).unwrap();
+    let config = BuilderConfig {
+        temp_dir: root.join("tmp"),
+        substituters: vec![unreachable, cache_url],
+        ..Default::default()
+    };
+    let mut builder = Builder::with_config(store, config);
+    builder.store_mut().add_derivation(&broken).unwrap();
+
+    // Substituting needs none of the inputs
+    let result = builder.build(&cached).unwrap();
+    assert!(result.substituted);
+    assert_eq!(
+        result.outputs["out"].display_name(),
+        out_path.display_name()
+    );
+    let out = builder.store().to_path(&result.outputs["out"]);
+    assert_eq!(
+        fs::read_to_string(out.join("file")).unwrap(),
+        "from the cache\n"
+    );
+
+    // Nor does the scheduler build them
+    let config = BuilderConfig {
+        temp_dir: root.join("tmp"),
+        substituters: builder.config().substituters.clone(),
+        ..Default::default()
+    };
+    let mut scheduled = Builder::with_config(Store::open_at(root.join("store2")).unwrap(), config);
+    scheduled.store_mut().add_derivation(&broken).unwrap();
+    let summary = scheduled.build_all(std::slice::from_ref(&cached));
+    assert!(summary.is_success());
+    assert!(summary.built[0].substituted);
+
+    // A narinfo for another store path than the expected output is rejected
+    assert!(builder.build(&pinned).is_err());
+
+    // A miss falls back to building locally
+    let local = Derivation::builder("local", "1.0")
+        .builder_path("/bin/sh")
+        .args(["-c", "echo local > \"$out/file\""])
+        .output(Output::new("out"))
+        .build();
+    let result = builder.build(&local).unwrap();
+    assert!(!result.substituted);
+    let out = builder.store().to_path(&result.outputs["out"]);
+    assert_eq!(fs::read_to_string(out.join("file")).unwrap(), "local\n");
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_offline_build_fails_on_substituter_miss() {
+    use neve_fetch::FetchError;
+
+    let root = env::temp_dir().join(format!("neve-substitute-offline-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    let store = Store::open_at(root.join("store")).unwrap();
+    let config = BuilderConfig {
+        temp_dir: root.join("tmp"),
+        substituters: vec![serve_files(std::collections::HashMap::new())],
+        offline: true,
+        ..Default::default()
+    };
+    let mut builder = Builder::with_config(store, config);
+
+    let drv = Derivation::builder("offline", "1.0")
+        .builder_path("/bin/sh")
+        .args(["-c", "echo local > \"$out/file\""])
+        .output(Output::new("out"))
+        .build();
+    let err = builder.build(&drv).unwrap_err();
+    assert!(
+        matches!(err, BuildError::Fetch(FetchError::Offline(_))),
+        "{err}"
+    );
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_env_allowlist_and_extra() {
+    let root = env::temp_dir().join(format!("neve-env-allowlist-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    let store = Store::open_at(root.join("store")).unwrap();
+    let config = BuilderConfig {
+        temp_dir: root.join("tmp"),
+        env_allowlist: vec!["CARGO_MANIFEST_DIR".to_string()],
+        env_extra: vec![("LANG".to_string(), "C.UTF-8".to_string())],
+        ..Default::default()
+    };
+    let mut builder = Builder::with_config(store, config);
+
+    let drv = Derivation::builder("env-allowlist", "1.0")
+        .builder_path("/bin/sh")
+        .env("LANG", "en_US.UTF-8")
+        .args([
+            "-c",
+            "printf %s \"$CARGO_MANIFEST_DIR\" > \"$out/allowed\"; \
+             printf %s \"$CARGO_PKG_NAME\" > \"$out/scrubbed\"; \
+             printf %s \"$LANG\" > \"$out/extra\"",
+        ])
+        .output(Output::new("out"))
+        .build();
+    let result = builder.build(&drv).unwrap();
+    let out = builder.store().to_path(&result.outputs["out"]);
+
+    // Only the allowlisted variable comes through; extras override the derivation
+    assert_eq!(
+        fs::read_to_string(out.join("allowed")).unwrap(),
+        env::var("CARGO_MANIFEST_DIR").unwrap()
+    );
+    assert_eq!(fs::read_to_string(out.join("scrubbed")).unwrap(), "");
+    assert_eq!(fs::read_to_string(out.join("extra")).unwrap(), "C.UTF-8");
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_fixed_output_derivation_is_verified() {
+    use neve_derive::HashMode;
+    use neve_fetch::verify::hash_dir;
+
+    let root = env::temp_dir().join(format!("neve-fixed-output-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    let store = Store::open_at(root.join("store")).unwrap();
+    let config = BuilderConfig {
+        temp_dir: root.join("tmp"),
+        ..Default::default()
+    };
+    let mut builder = Builder::with_config(store, config);
+
+    // Directory outputs are hashed the same way fetched sources are verified
+    let expected = root.join("expected");
+    fs::create_dir_all(&expected).unwrap();
+    fs::write(expected.join("file"), "pinned\n").unwrap();
+    let hash = hash_dir(&expected).unwrap();
+
+    let fixed = |hash| {
+        Derivation::builder("pinned", "1.0")
+            .builder_path("/bin/sh")
+            .args(["-c", "echo pinned > \"$out/file\""])
+            .output_hash(hash, HashMode::Recursive)
+            .build()
+    };
+
+    let result = builder.build(&fixed(hash)).unwrap();
+    let out = builder.store().to_path(&result.outputs["out"]);
+    assert_eq!(fs::read_to_string(out.join("file")).unwrap(), "pinned\n");
+
+    // An output that doesn't match the declared hash is rejected
+    let err = builder.build(&fixed(Hash::of(b"other"))).unwrap_err();
+    assert!(
+        matches!(err, BuildError::OutputHashMismatch { ref output, .. } if output == "out"),
+        "{err}"
+    );
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_cgroup_helpers() {
+    use neve_builder::cgroup::{cpu_max, oom_kills, parse_proc_cgroup};
+
+    assert_eq!(cpu_max(1.5), "150000 100000");
+    assert_eq!(cpu_max(0.001), "1000 100000");
+    assert_eq!(
+        parse_proc_cgroup("0::/user.slice/session-1.scope\n"),
+        Some(std::path::PathBuf::from("/user.slice/session-1.scope"))
+    );
+    assert_eq!(parse_proc_cgroup("4:memory:/user.slice\n"), None);
+    assert_eq!(oom_kills("low 0\nhigh 0\nmax 3\noom 1\noom_kill 1\n"), 1);
+    assert_eq!(oom_kills("low 0\n"), 0);
+}
+
+#[test]
+fn test_resource_limits_build() {
+    let root = env::temp_dir().join(format!("neve-resource-limits-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    let store = Store::open_at(root.join("store")).unwrap();
+    let config = BuilderConfig {
+        temp_dir: root.join("tmp"),
+        memory_limit_bytes: Some(256 * 1024 * 1024),
+        cpu_quota: Some(1.0),
+        ..Default::default()
+    };
+    let mut builder = Builder::with_config(store, config);
+
+    // Builds within the limits succeed, with or without cgroup v2
+    let drv = Derivation::builder("limited", "1.0")
+        .builder_path("/bin/sh")
+        .args(["-c", "echo ok > \"$out/file\""])
+        .output(Output::new("out"))
+        .build();
+    let result = builder.build(&drv).unwrap();
+    let out = builder.store().to_path(&result.outputs["out"]);
+    assert_eq!(fs::read_to_string(out.join("file")).unwrap(), "ok\n");
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_docker_resource_limits() {
+    use neve_builder::docker::DockerConfig;
+
+    let config = DockerConfig::default().with_resource_limits(Some(512 * 1024 * 1024), Some(1.5));
+    assert_eq!(config.memory_limit.as_deref(), Some("536870912"));
+    assert_eq!(config.cpu_limit.as_deref(), Some("1.5"));
+
+    let config = DockerConfig::default().with_resource_limits(None, None);
+    assert_eq!(config.memory_limit, None);
+    assert_eq!(config.cpu_limit, None);
+}
+
+#[test]
+fn test_container_sandbox_uses_mount_paths() {
+    let root = env::temp_dir().join(format!("neve-container-paths-{}", std::process::id()));
+    let mut config = SandboxConfig::new(root.clone());
+    config.store_dir = root.join("store");
+    config.container = true;
+    let sandbox = Sandbox::new(config).unwrap();
+
+    assert_eq!(
+        sandbox.inner_path(&root.join("store").join("abc-hello")),
+        Path::new("/neve/store/abc-hello")
+    );
+    assert_eq!(
+        sandbox.inner_path(&sandbox.output_dir().join("out")),
+        Path::new("/output/out")
+    );
+    assert_eq!(sandbox.inner_path(sandbox.build_dir()), Path::new("/build"));
+
+    let _ = fs::remove_dir_all(&root);
+}
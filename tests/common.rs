@@ -1,6 +1,6 @@
 //! Integration tests for neve-common crate.
 
-use neve_common::{BytePos, Interner, Span};
+use neve_common::{BytePos, Interner, InternerData, Span};
 
 #[test]
 fn test_span_merge() {
@@ -11,6 +11,25 @@ fn test_span_merge() {
     assert_eq!(merged.end.0, 30);
 }
 
+#[test]
+fn test_span_merge_with_dummy() {
+    let span = Span::from_usize(10, 20);
+    assert_eq!(span.merge(Span::DUMMY), span);
+    assert_eq!(Span::DUMMY.merge(span), span);
+    assert_eq!(Span::DUMMY.merge(Span::DUMMY), Span::DUMMY);
+}
+
+#[test]
+fn test_span_merge_normalizes_reversed_bounds() {
+    let reversed = Span::from_usize(20, 10);
+    assert_eq!(reversed.merge(Span::DUMMY), Span::from_usize(10, 20));
+    assert_eq!(
+        reversed.merge(Span::from_usize(25, 30)),
+        Span::from_usize(10, 30)
+    );
+    assert_eq!(reversed.len(), 0);
+}
+
 #[test]
 fn test_span_len() {
     let span = Span::from_usize(5, 15);
@@ -59,3 +78,28 @@ fn test_symbol_as_u32() {
     let sym2 = interner.intern("another");
     assert_eq!(sym2.as_u32(), 1);
 }
+
+#[test]
+fn test_interner_snapshot_roundtrip() {
+    let mut interner = Interner::new();
+    let symbols: Vec<_> = ["foo", "bar", "baz", "你好"]
+        .iter()
+        .map(|s| (interner.intern(s), *s))
+        .collect();
+
+    let json = serde_json::to_string(&interner.snapshot()).unwrap();
+    assert_eq!(json, r#"["foo","bar","baz","你好"]"#);
+
+    let data: InternerData = serde_json::from_str(&json).unwrap();
+    let mut restored = Interner::from_snapshot(data);
+    assert_eq!(restored.len(), symbols.len());
+
+    for (sym, s) in &symbols {
+        assert_eq!(restored.get(*sym), *s);
+        assert_eq!(restored.intern(s), *sym);
+    }
+
+    // New strings continue after the restored ones
+    let qux = restored.intern("qux");
+    assert_eq!(qux.as_u32(), symbols.len() as u32);
+}
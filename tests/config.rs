@@ -4,7 +4,8 @@ use neve_config::activate::{Activator, TestResult};
 use neve_config::generate::{GeneratedConfig, Generator};
 use neve_config::generation::{GenerationManager, GenerationMetadata};
 use neve_config::module::{Module, OptionDecl, OptionType};
-use neve_config::{SystemConfig, UserConfig};
+use neve_config::profile::Profile;
+use neve_config::{ConfigError, ConfigOptions, ServiceDef, SystemConfig, UserConfig};
 use neve_derive::{Hash, StorePath};
 use neve_eval::Value;
 use std::env;
@@ -103,6 +104,87 @@ fn test_module_to_system_config() {
     assert_eq!(config.options.packages, vec!["vim", "git"]);
 }
 
+fn env_pair(name: &str, value: &str) -> (String, String) {
+    (name.to_string(), value.to_string())
+}
+
+#[test]
+fn test_environment_duplicates_last_wins() {
+    let mut options = ConfigOptions {
+        environment: vec![
+            env_pair("EDITOR", "vi"),
+            env_pair("PAGER", "less"),
+            env_pair("EDITOR", "nvim"),
+        ],
+        ..Default::default()
+    };
+    options.normalize_environment().unwrap();
+    assert_eq!(
+        options.environment,
+        vec![env_pair("EDITOR", "nvim"), env_pair("PAGER", "less")]
+    );
+
+    options
+        .merge_environment(&[env_pair("PAGER", "most"), env_pair("LANG", "C")])
+        .unwrap();
+    assert_eq!(
+        options.environment,
+        vec![
+            env_pair("EDITOR", "nvim"),
+            env_pair("PAGER", "most"),
+            env_pair("LANG", "C"),
+        ]
+    );
+}
+
+#[test]
+fn test_environment_invalid_name_rejected() {
+    let mut options = ConfigOptions::default();
+    assert!(matches!(
+        options.set_env("FOO=BAR", "x"),
+        Err(ConfigError::Invalid(_))
+    ));
+    assert!(matches!(
+        options.merge_environment(&[env_pair("NUL\0", "x")]),
+        Err(ConfigError::Invalid(_))
+    ));
+    for name in ["A;rm -rf /;B", "1ABC", "MY-VAR", "$(id)"] {
+        assert!(
+            matches!(options.set_env(name, "x"), Err(ConfigError::Invalid(_))),
+            "{}",
+            name
+        );
+    }
+    assert!(options.environment.is_empty());
+    options.set_env("_MY_VAR2", "x").unwrap();
+}
+
+#[test]
+fn test_environment_values_are_shell_quoted() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut config = SystemConfig::new("test");
+    config.options.set_env("GREETING", "say \"hi\"").unwrap();
+    config
+        .options
+        .set_env("CMD", "$(rm -rf /) `id` it's")
+        .unwrap();
+
+    let generated = Generator::new(dir.path().to_path_buf())
+        .generate(&config)
+        .unwrap();
+    let script = fs::read_to_string(dir.path().join("environment")).unwrap();
+    assert_eq!(
+        script,
+        "export GREETING='say \"hi\"'\nexport CMD='$(rm -rf /) `id` it'\\''s'\n"
+    );
+    assert!(
+        generated
+            .files
+            .iter()
+            .any(|f| f.source.ends_with("environment"))
+    );
+}
+
 // Generator tests
 
 fn temp_dir(suffix: &str) -> PathBuf {
@@ -823,3 +905,623 @@ fn test_system_config_stress() {
     assert_eq!(config.options.packages.len(), 200);
     assert_eq!(config.options.services.len(), 50);
 }
+
+// Profile tests
+
+/// Create a fake package with one binary.
+fn fake_package(dir: &std::path::Path, name: &str) -> PathBuf {
+    let pkg = dir.join("store").join(name);
+    fs::create_dir_all(pkg.join("bin")).unwrap();
+    fs::write(pkg.join("bin").join(name), "#!/bin/sh\n").unwrap();
+    pkg
+}
+
+#[test]
+fn test_profile_install_and_rollback() {
+    let dir = temp_dir("profile");
+    let _ = fs::remove_dir_all(&dir);
+    let profile = Profile::new(dir.join("profile")).unwrap();
+    let hello = fake_package(&dir, "hello");
+    let world = fake_package(&dir, "world");
+
+    assert_eq!(profile.current_generation().unwrap(), None);
+    assert!(profile.current_manifest().unwrap().is_empty());
+
+    assert_eq!(
+        profile
+            .create_generation(std::slice::from_ref(&hello))
+            .unwrap(),
+        1
+    );
+    assert_eq!(
+        profile
+            .create_generation(&[hello.clone(), world.clone()])
+            .unwrap(),
+        2
+    );
+    assert_eq!(profile.current_generation().unwrap(), Some(2));
+    assert!(profile.dir().join("current/bin/world").exists());
+
+    assert_eq!(profile.rollback().unwrap(), 1);
+    assert_eq!(profile.current_manifest().unwrap(), vec![hello]);
+    assert!(!profile.dir().join("current/bin/world").exists());
+    assert!(profile.rollback().is_err());
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_profile_open_creates_nothing() {
+    let dir = temp_dir("profile-open");
+    let _ = fs::remove_dir_all(&dir);
+    let profile = Profile::open(dir.join("profile"));
+
+    assert_eq!(profile.generations().unwrap(), Vec::<u64>::new());
+    assert_eq!(profile.next_generation().unwrap(), 1);
+    assert!(profile.current_manifest().unwrap().is_empty());
+    assert!(!dir.exists());
+}
+
+#[test]
+fn test_profile_survives_interrupted_install() {
+    let dir = temp_dir("profile-crash");
+    let _ = fs::remove_dir_all(&dir);
+    let profile = Profile::new(dir.join("profile")).unwrap();
+    let hello = fake_package(&dir, "hello");
+    let world = fake_package(&dir, "world");
+    profile
+        .create_generation(std::slice::from_ref(&hello))
+        .unwrap();
+
+    // Crash after the new generation is built but before it is linked
+    let built = profile
+        .build_generation(&[hello.clone(), world.clone()])
+        .unwrap();
+    assert_eq!(built, 2);
+    assert_eq!(profile.current_generation().unwrap(), Some(1));
+    assert_eq!(profile.current_manifest().unwrap(), vec![hello.clone()]);
+    assert!(profile.dir().join("current/bin/hello").exists());
+
+    // Crash while a generation is half written
+    let staging = profile.dir().join(".generation-3.tmp");
+    fs::create_dir_all(&staging).unwrap();
+    fs::write(staging.join("manifest"), "garbage").unwrap();
+    assert_eq!(profile.generations().unwrap(), vec![1, 2]);
+    assert_eq!(profile.current_generation().unwrap(), Some(1));
+
+    // The next install still works and replaces the leftovers
+    let next = profile
+        .create_generation(std::slice::from_ref(&world))
+        .unwrap();
+    assert_eq!(next, 3);
+    assert_eq!(profile.current_manifest().unwrap(), vec![world]);
+    assert!(!staging.exists());
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+// File import tests
+
+#[test]
+fn test_system_config_from_toml() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("web-1.toml");
+    fs::write(
+        &path,
+        r#"
+hostname = "web-1"
+timezone = "UTC"
+services = ["nginx", "sshd"]
+packages = ["git"]
+
+[environment]
+EDITOR = "vim"
+PAGER = "less"
+
+[[users]]
+name = "alice"
+shell = "/bin/zsh"
+groups = ["wheel"]
+"#,
+    )
+    .unwrap();
+
+    let config = SystemConfig::from_toml(&path).unwrap();
+    assert_eq!(config.name, "web-1");
+    let options = &config.options;
+    assert_eq!(options.hostname.as_deref(), Some("web-1"));
+    assert_eq!(options.timezone.as_deref(), Some("UTC"));
+    assert_eq!(options.services, vec!["nginx", "sshd"]);
+    assert_eq!(options.packages, vec!["git"]);
+    assert_eq!(
+        options.environment,
+        vec![
+            ("EDITOR".to_string(), "vim".to_string()),
+            ("PAGER".to_string(), "less".to_string()),
+        ]
+    );
+    assert_eq!(
+        options.users,
+        vec![UserConfig::new("alice").shell("/bin/zsh").group("wheel")]
+    );
+}
+
+#[test]
+fn test_system_config_from_json() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.json");
+    fs::write(
+        &path,
+        r#"{"hostname": "db", "users": [{"name": "bob", "home": "/srv/bob"}]}"#,
+    )
+    .unwrap();
+
+    let config = SystemConfig::from_json(&path).unwrap();
+    assert_eq!(config.name, "db");
+    assert_eq!(config.options.hostname.as_deref(), Some("db"));
+    assert_eq!(config.options.users[0].home, PathBuf::from("/srv/bob"));
+    assert!(config.options.services.is_empty());
+}
+
+#[test]
+fn test_config_options_round_trip() {
+    let mut options = SystemConfig::new("host")
+        .hostname("host")
+        .timezone("Europe/Berlin")
+        .service("nginx")
+        .package("git")
+        .user(UserConfig::new("alice").group("wheel").package("htop"))
+        .user(UserConfig::new("bob"))
+        .options;
+    options.locale = Some("en_US.UTF-8".to_string());
+    options.set_env("PATH", "/usr/bin").unwrap();
+    options.set_env("EDITOR", "vim").unwrap();
+
+    let toml = options.to_toml_string().unwrap();
+    assert_eq!(ConfigOptions::from_toml_str(&toml).unwrap(), options);
+    let json = options.to_json_string().unwrap();
+    assert_eq!(ConfigOptions::from_json_str(&json).unwrap(), options);
+    assert_eq!(
+        ConfigOptions::from_toml_str(&ConfigOptions::default().to_toml_string().unwrap()).unwrap(),
+        ConfigOptions::default()
+    );
+}
+
+#[test]
+fn test_config_import_errors() {
+    let dir = tempfile::tempdir().unwrap();
+
+    // Unknown keys are reported, not dropped
+    let path = dir.path().join("typo.toml");
+    fs::write(&path, "hostnme = \"web-1\"\n").unwrap();
+    match SystemConfig::from_toml(&path) {
+        Err(ConfigError::Invalid(msg)) => {
+            assert!(msg.contains("typo.toml"), "{}", msg);
+            assert!(msg.contains("unknown field `hostnme`"), "{}", msg);
+        }
+        other => panic!("expected an invalid configuration, got {:?}", other),
+    }
+    assert!(matches!(
+        ConfigOptions::from_json_str(r#"{"users": [{"name": "a", "uid": 1}]}"#),
+        Err(ConfigError::Invalid(_))
+    ));
+
+    // Malformed input and invalid values
+    assert!(matches!(
+        ConfigOptions::from_toml_str("hostname = "),
+        Err(ConfigError::Invalid(_))
+    ));
+    assert!(matches!(
+        ConfigOptions::from_json_str(r#"{"services": "nginx"}"#),
+        Err(ConfigError::Invalid(_))
+    ));
+    assert!(matches!(
+        ConfigOptions::from_toml_str("[environment]\n\"A=B\" = \"x\"\n"),
+        Err(ConfigError::Invalid(_))
+    ));
+
+    // Unreadable files
+    assert!(matches!(
+        SystemConfig::from_json(dir.path().join("missing.json")),
+        Err(ConfigError::Io(_))
+    ));
+}
+
+// Generation diff tests
+
+#[test]
+fn test_generation_diff() {
+    use neve_config::generation::{UserPackagesDiff, ValueChange, diff};
+
+    let old = SystemConfig::new("old")
+        .hostname("web-1")
+        .service("sshd")
+        .service("nginx")
+        .package("git")
+        .package("vim")
+        .user(UserConfig::new("alice").package("htop"))
+        .user(UserConfig::new("bob"));
+    let new = SystemConfig::new("new")
+        .hostname("web-2")
+        .timezone("UTC")
+        .service("nginx")
+        .service("postgres")
+        .package("git")
+        .package("curl")
+        .user(UserConfig::new("alice").package("btop"))
+        .user(UserConfig::new("carol").package("tmux"))
+        .service_def(ServiceDef::new("nginx").user("www"));
+
+    let changes = diff(&old, &new);
+    assert_eq!(
+        changes.hostname,
+        Some(ValueChange {
+            old: Some("web-1".into()),
+            new: Some("web-2".into()),
+        })
+    );
+    assert_eq!(
+        changes.timezone,
+        Some(ValueChange {
+            old: None,
+            new: Some("UTC".into()),
+        })
+    );
+    assert_eq!(changes.locale, None);
+    assert_eq!(changes.services_added, vec!["postgres"]);
+    assert_eq!(changes.services_removed, vec!["sshd"]);
+    assert_eq!(changes.service_defs_changed, vec!["nginx"]);
+    assert_eq!(changes.packages_added, vec!["curl"]);
+    assert_eq!(changes.packages_removed, vec!["vim"]);
+    assert_eq!(changes.users_added, vec!["carol"]);
+    assert_eq!(changes.users_removed, vec!["bob"]);
+    assert_eq!(
+        changes.user_packages,
+        vec![
+            UserPackagesDiff {
+                user: "alice".into(),
+                added: vec!["btop".into()],
+                removed: vec!["htop".into()],
+            },
+            UserPackagesDiff {
+                user: "carol".into(),
+                added: vec!["tmux".into()],
+                removed: vec![],
+            },
+        ]
+    );
+
+    let summary = changes.to_string();
+    assert!(
+        summary.contains("hostname: web-1 -> web-2\n"),
+        "{}",
+        summary
+    );
+    assert!(
+        summary.contains("timezone: (unset) -> UTC\n"),
+        "{}",
+        summary
+    );
+    assert!(
+        summary.contains("services added: postgres\n"),
+        "{}",
+        summary
+    );
+    assert!(
+        summary.contains("user alice packages removed: htop\n"),
+        "{}",
+        summary
+    );
+}
+
+#[test]
+fn test_generation_diff_ignores_order_and_duplicates() {
+    use neve_config::generation::diff;
+
+    let old = SystemConfig::new("old")
+        .service("nginx")
+        .service("sshd")
+        .package("git");
+    let new = SystemConfig::new("new")
+        .service("sshd")
+        .service("nginx")
+        .service("sshd")
+        .package("git")
+        .package("git");
+
+    let changes = diff(&old, &new);
+    assert!(changes.is_empty());
+    assert_eq!(changes.to_string(), "no changes\n");
+}
+
+#[test]
+fn test_generation_records_the_current_generation() {
+    let dir = tempfile::tempdir().unwrap();
+    let manager = GenerationManager::new(dir.path().to_path_buf()).unwrap();
+    let store_path = StorePath::new(Hash::of(b"system"), "system".to_string());
+    let create = || {
+        manager
+            .create_generation(&store_path, GenerationMetadata::new())
+            .unwrap()
+    };
+
+    assert_eq!(create().metadata.previous, None);
+    assert_eq!(create().metadata.previous, Some(1));
+
+    // After a rollback, the next generation follows the newest one but is
+    // compared against the generation rolled back to
+    manager.switch_to(1).unwrap();
+    let generation = create();
+    assert_eq!(generation.number, 3);
+    assert_eq!(generation.metadata.previous, Some(1));
+    assert_eq!(
+        manager.load_generation(3).unwrap().metadata.previous,
+        Some(1)
+    );
+}
+
+#[test]
+fn test_generation_options_recorded() {
+    let dir = tempfile::tempdir().unwrap();
+    let manager = GenerationManager::new(dir.path().to_path_buf()).unwrap();
+    let store_path = StorePath::new(Hash::of(b"system"), "system".to_string());
+    let generation = manager
+        .create_generation(&store_path, GenerationMetadata::new())
+        .unwrap();
+
+    assert_eq!(manager.load_options(generation.number).unwrap(), None);
+    let options = SystemConfig::new("host")
+        .hostname("host")
+        .service("sshd")
+        .options;
+    manager.save_options(generation.number, &options).unwrap();
+    assert_eq!(
+        manager.load_options(generation.number).unwrap(),
+        Some(options)
+    );
+    assert!(matches!(
+        manager.save_options(99, &ConfigOptions::default()),
+        Err(ConfigError::NotFound(_))
+    ));
+}
+
+#[test]
+fn test_generation_rollback_to() {
+    use neve_derive::Derivation;
+    use neve_store::Store;
+
+    let dir = tempfile::tempdir().unwrap();
+    let mut store = Store::open_at(dir.path().join("store")).unwrap();
+    let manager = GenerationManager::new(dir.path().join("state")).unwrap();
+
+    let mut paths = Vec::new();
+    for i in 1..=3 {
+        let drv = Derivation::builder(format!("system-{}", i), "1.0")
+            .builder_path("/bin/sh")
+            .build();
+        let path = store.add_derivation(&drv).unwrap();
+        manager
+            .create_generation(&path, GenerationMetadata::new())
+            .unwrap();
+        paths.push(path);
+    }
+    assert_eq!(manager.current_generation().unwrap(), Some(3));
+
+    // Skip straight back past generation 2
+    let generation = manager.rollback_to(1, &store).unwrap();
+    assert_eq!(generation.number, 1);
+    assert_eq!(manager.current_generation().unwrap(), Some(1));
+
+    // Unknown generations
+    assert!(matches!(
+        manager.rollback_to(7, &store),
+        Err(ConfigError::NotFound(_))
+    ));
+
+    // Garbage collected configurations can't be activated
+    fs::remove_file(store.to_path(&paths[1])).unwrap();
+    match manager.rollback_to(2, &store) {
+        Err(ConfigError::NotFound(msg)) => assert!(msg.contains("rebuild"), "{}", msg),
+        other => panic!("expected a missing store path, got {:?}", other),
+    }
+    assert_eq!(manager.current_generation().unwrap(), Some(1));
+}
+
+#[test]
+fn test_generation_gc_root_survives_store_gc() {
+    use neve_store::{GarbageCollector, Store};
+
+    let dir = tempfile::tempdir().unwrap();
+    let mut store = Store::open_at(dir.path().join("store")).unwrap();
+    let manager = GenerationManager::new(dir.path().join("state")).unwrap();
+
+    let output = dir.path().join("output");
+    fs::create_dir_all(output.join("etc")).unwrap();
+    fs::write(output.join("etc/hostname"), "neve\n").unwrap();
+    let rooted = store.add_dir(&output, "system").unwrap();
+    let generation = manager
+        .create_generation(&rooted, GenerationMetadata::new())
+        .unwrap();
+    manager.add_gc_root(&generation, &mut store).unwrap();
+
+    fs::write(output.join("etc/hostname"), "other\n").unwrap();
+    let unrooted = store.add_dir(&output, "system").unwrap();
+    manager
+        .create_generation(&unrooted, GenerationMetadata::new())
+        .unwrap();
+
+    GarbageCollector::new(&mut store).collect().unwrap();
+    assert!(store.path_exists(&rooted));
+    assert!(!store.path_exists(&unrooted));
+    assert_eq!(manager.rollback_to(1, &store).unwrap().number, 1);
+}
+
+// Service unit tests
+
+#[test]
+fn test_generator_service_units() {
+    use neve_config::RestartPolicy;
+
+    let dir = tempfile::tempdir().unwrap();
+    let config = SystemConfig::new("test").service("sshd").service_def(
+        ServiceDef::new("app")
+            .description("My App")
+            .exec_start("/opt/app/bin/server --port 8080")
+            .after("postgresql.service")
+            .requires("postgresql.service")
+            .restart(RestartPolicy::Always)
+            .user("app"),
+    );
+    assert_eq!(config.options.services, vec!["sshd", "app"]);
+
+    let generated = Generator::new(dir.path().to_path_buf())
+        .generate(&config)
+        .unwrap();
+    assert_eq!(generated.services, vec!["sshd", "app"]);
+    let names: Vec<&str> = generated.units.iter().map(|u| u.name.as_str()).collect();
+    assert_eq!(names, vec!["sshd.service", "app.service"]);
+
+    // Plain services use the default template
+    let sshd = fs::read_to_string(dir.path().join("etc/systemd/system/sshd.service")).unwrap();
+    assert!(sshd.contains("ExecStart=/usr/bin/sshd -D\n"), "{}", sshd);
+
+    let app = fs::read_to_string(dir.path().join("etc/systemd/system/app.service")).unwrap();
+    assert!(app.contains("Description=My App\n"), "{}", app);
+    assert!(
+        app.contains("After=network.target postgresql.service\n"),
+        "{}",
+        app
+    );
+    assert!(app.contains("Requires=postgresql.service\n"), "{}", app);
+    assert!(
+        app.contains("ExecStart=/opt/app/bin/server --port 8080\n"),
+        "{}",
+        app
+    );
+    assert!(app.contains("User=app\n"), "{}", app);
+    assert!(app.contains("Restart=always\n"), "{}", app);
+    assert!(app.contains("WantedBy=multi-user.target\n"), "{}", app);
+
+    let script = fs::read_to_string(generated.activation_script.unwrap()).unwrap();
+    assert!(script.contains("systemctl daemon-reload"), "{}", script);
+    assert!(
+        script.contains(&format!(
+            "ln -sfn '{}' '/etc/systemd/system/app.service'\n",
+            dir.path().join("etc/systemd/system/app.service").display()
+        )),
+        "{}",
+        script
+    );
+    assert!(
+        script.contains("mkdir -p '/etc/systemd/system/multi-user.target.wants'\n"),
+        "{}",
+        script
+    );
+}
+
+#[test]
+fn test_activator_links_service_units() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().join("root");
+    let config =
+        SystemConfig::new("test").service_def(ServiceDef::new("app").exec_start("/bin/app"));
+    let mut generated = Generator::new(dir.path().join("out"))
+        .generate(&config)
+        .unwrap();
+    generated.activation_script = None;
+
+    let result = Activator::new().root(&root).activate(&generated).unwrap();
+    assert_eq!(result.services_enabled, 1);
+    // Another root's systemd is not reloaded
+    assert!(!result.daemon_reloaded);
+
+    let unit = root.join("etc/systemd/system/app.service");
+    assert_eq!(fs::read_link(&unit).unwrap(), generated.units[0].source);
+    assert_eq!(
+        fs::read_link(root.join("etc/systemd/system/multi-user.target.wants/app.service")).unwrap(),
+        PathBuf::from("/etc/systemd/system/app.service")
+    );
+
+    // Activating again replaces the links
+    Activator::new().root(&root).activate(&generated).unwrap();
+    assert_eq!(fs::read_link(&unit).unwrap(), generated.units[0].source);
+}
+
+#[test]
+fn test_service_defs_import_and_derivation() {
+    use neve_config::RestartPolicy;
+
+    let options = ConfigOptions::from_toml_str(
+        r#"
+services = ["sshd"]
+
+[[service_defs]]
+name = "app"
+exec_start = "/bin/app"
+restart = "on-failure"
+"#,
+    )
+    .unwrap();
+    assert_eq!(options.service_defs[0].name, "app");
+    assert_eq!(
+        options.service_defs[0].restart,
+        Some(RestartPolicy::OnFailure)
+    );
+
+    let toml = options.to_toml_string().unwrap();
+    assert_eq!(ConfigOptions::from_toml_str(&toml).unwrap(), options);
+
+    // Changing a unit changes the configuration's derivation
+    let generator = Generator::new(env::temp_dir());
+    let mut config = SystemConfig::new("test");
+    config.options = options.clone();
+    let mut changed = config.clone();
+    changed.options.service_defs[0].exec_start = Some("/bin/other".into());
+    assert_ne!(
+        generator.to_derivation(&config).drv_path(),
+        generator.to_derivation(&changed).drv_path()
+    );
+}
+
+#[test]
+fn test_service_names_must_be_unit_names() {
+    for name in ["../../etc/foo", "a b", ""] {
+        let toml = format!("[[service_defs]]\nname = {:?}\n", name);
+        match ConfigOptions::from_toml_str(&toml) {
+            Err(ConfigError::Invalid(msg)) => {
+                assert!(msg.contains("invalid service name"), "{}", msg)
+            }
+            other => panic!("expected an invalid service name, got {:?}", other),
+        }
+    }
+    assert!(matches!(
+        ConfigOptions::from_json_str(r#"{"services": ["nginx/../x"]}"#),
+        Err(ConfigError::Invalid(_))
+    ));
+    let module = Module::new("test").set(
+        "services",
+        Value::List(Rc::new(vec![Value::String(Rc::new("../sshd".to_string()))])),
+    );
+    assert!(matches!(
+        module.to_system_config(),
+        Err(ConfigError::Invalid(_))
+    ));
+
+    assert!(ConfigOptions::from_toml_str("services = [\"getty@tty1\", \"my-app.v2\"]\n").is_ok());
+}
+
+#[test]
+fn test_service_settings_reject_line_breaks() {
+    let json =
+        r#"{"service_defs": [{"name": "app", "exec_start": "/bin/app\nExecStartPre=/bin/evil"}]}"#;
+    match ConfigOptions::from_json_str(json) {
+        Err(ConfigError::Invalid(msg)) => assert!(msg.contains("line break"), "{}", msg),
+        other => panic!("expected an invalid service, got {:?}", other),
+    }
+    assert!(matches!(
+        ConfigOptions::from_toml_str(
+            "[[service_defs]]\nname = \"app\"\ndescription = \"x\\r\\nUser=root\"\n"
+        ),
+        Err(ConfigError::Invalid(_))
+    ));
+}
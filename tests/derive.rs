@@ -1,8 +1,9 @@
 //! Integration tests for neve-derive crate.
 
 use neve_derive::{
-    Dependency, Derivation, Hash, HashMode, Hasher, MemoryRegistry, Output, PackageId,
-    PackageMetadata, ResolveError, Resolver, StorePath, Version, VersionConstraint,
+    Dependency, Derivation, Hash, HashAlgo, HashError, HashMode, Hasher, MemoryRegistry, Output,
+    PackageId, PackageLock, PackageMetadata, ResolveError, Resolver, StorePath, Version,
+    VersionChange, VersionConstraint,
 };
 
 // Hash tests
@@ -11,7 +12,38 @@ use neve_derive::{
 fn test_hash_data() {
     let hash = Hash::of(b"hello world");
     assert!(!hash.is_null());
-    assert_eq!(hash.to_hex().len(), 64);
+    assert_eq!(hash.algo(), HashAlgo::Blake3);
+    assert_eq!(hash.digest_hex().len(), 64);
+    assert_eq!(hash.to_hex(), format!("blake3-{}", hash.digest_hex()));
+}
+
+#[test]
+fn test_hash_with_sha256() {
+    let hash = Hash::of_with(HashAlgo::Sha256, b"abc");
+    assert_eq!(
+        hash.to_hex(),
+        "sha256-ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+    assert_eq!(Hash::from_hex(&hash.to_hex()).unwrap(), hash);
+    assert_ne!(hash, Hash::of(b"abc"));
+
+    // The same digest under another algorithm is a different hash
+    let relabeled = Hash::from_bytes_with(HashAlgo::Blake3, *hash.as_bytes());
+    assert_ne!(relabeled, hash);
+}
+
+#[test]
+fn test_hash_bare_hex_is_blake3() {
+    let hash = Hash::of(b"test data");
+    assert_eq!(Hash::from_hex(&hash.digest_hex()).unwrap(), hash);
+
+    // BLAKE3 hashes serialize exactly as they did before algorithms were tagged
+    let json = serde_json::to_string(&hash).unwrap();
+    assert!(!json.contains("algo"));
+    assert_eq!(serde_json::from_str::<Hash>(&json).unwrap(), hash);
+    let sha = Hash::of_with(HashAlgo::Sha256, b"test data");
+    let json = serde_json::to_string(&sha).unwrap();
+    assert_eq!(serde_json::from_str::<Hash>(&json).unwrap(), sha);
 }
 
 #[test]
@@ -22,6 +54,55 @@ fn test_hash_roundtrip() {
     assert_eq!(hash, parsed);
 }
 
+#[test]
+fn test_hash_display_roundtrip() {
+    for hash in [
+        Hash::of(b"test data"),
+        Hash::of_with(HashAlgo::Sha256, b"test data"),
+    ] {
+        assert_eq!(hash.to_string(), hash.to_hex());
+        assert_eq!(hash.to_string().parse::<Hash>().unwrap(), hash);
+    }
+}
+
+#[test]
+fn test_hash_parse_rejects_malformed_hex() {
+    let hex = Hash::of(b"test data").digest_hex();
+    assert_eq!(hex.parse::<Hash>().unwrap(), Hash::from_hex(&hex).unwrap());
+
+    assert!(matches!(
+        Hash::from_hex(&hex[..32]),
+        Err(HashError::InvalidLength(32))
+    ));
+    assert!(matches!(
+        Hash::from_hex(&hex[..63]),
+        Err(HashError::InvalidLength(63))
+    ));
+    let bad = format!("{}g", &hex[..63]);
+    assert!(matches!(
+        Hash::from_hex(&bad),
+        Err(HashError::InvalidHex {
+            ch: 'g',
+            position: 63
+        })
+    ));
+    assert!(Hash::from_hex("é").is_err());
+
+    // Positions count from the start of the prefixed form
+    let prefixed = format!("blake3-{}g", &hex[..63]);
+    assert!(matches!(
+        Hash::from_hex(&prefixed),
+        Err(HashError::InvalidHex {
+            ch: 'g',
+            position: 70
+        })
+    ));
+    assert!(matches!(
+        Hash::from_hex(&format!("md5-{}", hex)),
+        Err(HashError::UnsupportedAlgo(_))
+    ));
+}
+
 #[test]
 fn test_hasher_incremental() {
     let mut hasher = Hasher::new();
@@ -100,6 +181,20 @@ fn test_derivation_json() {
     assert_eq!(drv.env.get("FOO"), parsed.env.get("FOO"));
 }
 
+#[test]
+fn test_derivation_json_with_input_drvs() {
+    let dep = Derivation::builder("dep", "1.0").build();
+    let drv = Derivation::builder("test", "1.0")
+        .input_drv(dep.drv_path(), vec!["out".to_string()])
+        .build();
+
+    let json = drv.to_json().unwrap();
+    let parsed = Derivation::from_json(&json).unwrap();
+
+    assert_eq!(parsed.input_drvs, drv.input_drvs);
+    assert_eq!(parsed.hash(), drv.hash());
+}
+
 // StorePath and Output tests
 
 #[test]
@@ -128,6 +223,56 @@ fn test_output() {
     assert!(fixed.is_fixed());
 }
 
+#[test]
+fn test_fixed_output_derivation() {
+    let plain = Derivation::builder("src", "1.0").build();
+    assert!(!plain.is_fixed_output());
+    assert!(plain.output_hash().is_none());
+
+    let hash = Hash::of(b"tarball");
+    let fixed = Derivation::builder("src", "1.0")
+        .output_hash(hash, HashMode::Recursive)
+        .build();
+    assert!(fixed.is_fixed_output());
+    assert_eq!(fixed.output_hash(), Some(hash));
+    assert_eq!(fixed.outputs.len(), 1);
+    assert_ne!(fixed.hash(), plain.hash());
+
+    // The declared hash survives a round trip through JSON
+    let parsed = Derivation::from_json(&fixed.to_json().unwrap()).unwrap();
+    assert_eq!(parsed.output_hash(), fixed.output_hash());
+    assert_eq!(parsed.hash(), fixed.hash());
+
+    // Blake3 fixed outputs hash as they did before algorithms were recorded
+    let mut hasher = Hasher::new();
+    hasher
+        .update_str("src")
+        .update_str("1.0")
+        .update_str(&fixed.system)
+        .update_str(&fixed.builder)
+        .update_str("out")
+        .update(hash.as_bytes());
+    assert_eq!(fixed.hash(), hasher.finalize());
+
+    // The algorithm comes from the hash, and tells apart equal digests
+    let sha = Hash::of_with(HashAlgo::Sha256, b"tarball");
+    let fixed_sha = Derivation::builder("src", "1.0")
+        .output_hash(sha, HashMode::Recursive)
+        .build();
+    assert_eq!(fixed_sha.output_hash().unwrap().algo(), HashAlgo::Sha256);
+    let same_bytes = Derivation::builder("src", "1.0")
+        .output_hash(Hash::from_bytes(*sha.as_bytes()), HashMode::Recursive)
+        .build();
+    assert_ne!(fixed_sha.hash(), same_bytes.hash());
+
+    assert_eq!("blake3".parse::<HashAlgo>().unwrap(), HashAlgo::Blake3);
+    assert_eq!("sha256".parse::<HashAlgo>().unwrap(), HashAlgo::Sha256);
+    assert!(matches!(
+        "md5".parse::<HashAlgo>(),
+        Err(HashError::UnsupportedAlgo(_))
+    ));
+}
+
 // Version and resolution tests
 
 fn make_pkg(name: &str, version: &str, deps: Vec<(&str, &str)>) -> PackageMetadata {
@@ -229,6 +374,110 @@ fn test_version_selection() {
     );
 }
 
+#[test]
+fn test_lock_update_reports_version_changes() {
+    let mut registry = MemoryRegistry::new();
+    registry.add(make_pkg("foo", "1.0.0", vec![]));
+    registry.add(make_pkg("old", "1.0.0", vec![]));
+    registry.add(make_pkg(
+        "bar",
+        "2.0.0",
+        vec![("foo", "^1.0"), ("old", "^1.0")],
+    ));
+    let deps = vec![Dependency::new(
+        "bar",
+        VersionConstraint::parse("^2.0").unwrap(),
+    )];
+    let mut lock = PackageLock::from_resolution(&Resolver::new(&registry).resolve(&deps).unwrap());
+
+    // A newer foo, and a bar release that swaps `old` for `new`
+    registry.add(make_pkg("foo", "1.1.0", vec![]));
+    registry.add(make_pkg("new", "0.1.0", vec![]));
+    registry.add(make_pkg(
+        "bar",
+        "2.1.0",
+        vec![("foo", "^1.0"), ("new", "^0.1")],
+    ));
+    let resolution = Resolver::new(&registry).resolve(&deps).unwrap();
+
+    let changes = lock.update(&resolution);
+    let v = |s: &str| Version::parse(s).unwrap();
+    assert_eq!(
+        changes,
+        vec![
+            VersionChange::Updated {
+                name: "bar".into(),
+                from: v("2.0.0"),
+                to: v("2.1.0"),
+            },
+            VersionChange::Updated {
+                name: "foo".into(),
+                from: v("1.0.0"),
+                to: v("1.1.0"),
+            },
+            VersionChange::Added {
+                name: "new".into(),
+                version: v("0.1.0"),
+            },
+            VersionChange::Removed {
+                name: "old".into(),
+                version: v("1.0.0"),
+            },
+        ]
+    );
+    assert_eq!(changes[1].to_string(), "  foo 1.0.0 -> 1.1.0");
+    assert_eq!(lock.packages["foo"], v("1.1.0"));
+    assert!(!lock.packages.contains_key("old"));
+
+    // The lock round-trips, and a second update is a no-op
+    assert_eq!(
+        PackageLock::from_json(&lock.to_json().unwrap()).unwrap(),
+        lock
+    );
+    assert!(lock.update(&resolution).is_empty());
+}
+
+#[test]
+fn test_locked_resolution_keeps_pinned_versions() {
+    let mut registry = MemoryRegistry::new();
+    registry.add(make_pkg("foo", "1.0.0", vec![]));
+    let deps = vec![Dependency::new(
+        "foo",
+        VersionConstraint::parse("^1.0").unwrap(),
+    )];
+    let lock = PackageLock::from_resolution(&Resolver::new(&registry).resolve(&deps).unwrap());
+
+    // A newer release does not move a locked resolution
+    registry.add(make_pkg("foo", "1.1.0", vec![]));
+    let resolution = Resolver::new(&registry)
+        .locked(&lock)
+        .resolve(&deps)
+        .unwrap();
+    assert_eq!(
+        resolution.packages["foo"].version,
+        Version::parse("1.0.0").unwrap()
+    );
+
+    // A new dependency would change the lock, so it is refused
+    registry.add(make_pkg("bar", "1.0.0", vec![]));
+    let deps = vec![
+        Dependency::new("foo", VersionConstraint::Any),
+        Dependency::new("bar", VersionConstraint::Any),
+    ];
+    match Resolver::new(&registry).locked(&lock).resolve(&deps) {
+        Err(ResolveError::LockOutOfDate(changes)) => {
+            assert_eq!(
+                changes,
+                vec![VersionChange::Added {
+                    name: "bar".into(),
+                    version: Version::parse("1.0.0").unwrap(),
+                }]
+            );
+        }
+        other => panic!("expected LockOutOfDate, got {:?}", other),
+    }
+}
+
 #[test]
 fn test_build_order() {
     let mut registry = MemoryRegistry::new();
@@ -0,0 +1,201 @@
+//! Integration tests for neve-diagnostic crate.
+
+use neve_common::{BytePos, Span};
+use neve_diagnostic::{
+    ColorChoice, Diagnostic, DiagnosticKind, ErrorCode, Label, Suggestion, apply_suggestions,
+    emit_json, emit_json_all, render, to_sarif,
+};
+use serde_json::json;
+
+fn span(start: u32, end: u32) -> Span {
+    Span::new(BytePos(start), BytePos(end))
+}
+
+#[test]
+fn test_emit_json_full() {
+    let diagnostic = Diagnostic::error(DiagnosticKind::Type, span(8, 12), "type mismatch")
+        .with_code(ErrorCode::TypeMismatch)
+        .with_label(Label::new(span(8, 10), "expected Int"))
+        .with_note("Int and String are different types")
+        .with_help("convert the value first");
+
+    assert_eq!(
+        emit_json(&diagnostic),
+        json!({
+            "severity": "error",
+            "kind": "type",
+            "code": ErrorCode::TypeMismatch.as_str(),
+            "message": "type mismatch",
+            "span": { "start": 8, "end": 12 },
+            "labels": [{ "span": { "start": 8, "end": 10 }, "message": "expected Int" }],
+            "notes": ["Int and String are different types"],
+            "help": "convert the value first",
+            "suggestions": [],
+        })
+    );
+}
+
+#[test]
+fn test_emit_json_minimal() {
+    let diagnostic = Diagnostic::warning(DiagnosticKind::Parser, span(0, 1), "odd");
+    let value = emit_json(&diagnostic);
+
+    assert_eq!(value["severity"], "warning");
+    assert_eq!(value["kind"], "parser");
+    assert!(value["code"].is_null());
+    assert!(value["help"].is_null());
+    assert_eq!(value["labels"], json!([]));
+    assert_eq!(value["notes"], json!([]));
+}
+
+#[test]
+fn test_emit_json_all() {
+    let (_, diagnostics) = neve_parser::parse("let x = ;\nlet y = ;");
+    assert!(!diagnostics.is_empty());
+
+    let value = emit_json_all(&diagnostics);
+    let array = value.as_array().unwrap();
+    assert_eq!(array.len(), diagnostics.len());
+    for (json, diagnostic) in array.iter().zip(&diagnostics) {
+        assert_eq!(json["message"], diagnostic.message.as_str());
+        assert_eq!(json["span"]["start"], diagnostic.span.start.0);
+    }
+
+    assert_eq!(emit_json_all(&[]), json!([]));
+}
+
+fn with_fix(start: u32, end: u32, replacement: &str) -> Diagnostic {
+    Diagnostic::error(DiagnosticKind::Type, span(start, end), "fixable")
+        .with_suggestion(Suggestion::new(span(start, end), replacement, "fix it"))
+}
+
+#[test]
+fn test_suggestion_describe() {
+    let source = "let x = recrod.nmae";
+    assert_eq!(
+        Suggestion::new(span(15, 19), "name", "fix the field").describe(source),
+        "fix the field: replace `nmae` with `name`"
+    );
+    assert_eq!(
+        Suggestion::new(span(19, 19), ";", "add a semicolon").describe(source),
+        "add a semicolon: insert `;`"
+    );
+    assert_eq!(
+        Suggestion::new(span(0, 4), "", "drop it").describe(source),
+        "drop it: remove `let `"
+    );
+}
+
+#[test]
+fn test_apply_suggestions() {
+    let source = "let a = 1\nlet b = rec.nmae";
+    let diagnostics = [with_fix(22, 26, "name"), with_fix(9, 9, ";")];
+    assert_eq!(
+        apply_suggestions(source, &diagnostics),
+        "let a = 1;\nlet b = rec.name"
+    );
+}
+
+#[test]
+fn test_apply_suggestions_skips_overlapping() {
+    let source = "abcdef";
+    let diagnostics = [
+        with_fix(1, 3, "X"),
+        with_fix(2, 4, "Y"),
+        with_fix(1, 2, "Z"),
+        with_fix(4, 4, "+"),
+        with_fix(10, 12, "out of range"),
+    ];
+    assert_eq!(apply_suggestions(source, &diagnostics), "aZY+ef");
+    assert_eq!(apply_suggestions(source, &[]), source);
+}
+
+#[test]
+fn test_sarif_log() {
+    let source = "let a = 1;\nlet é = \"x\" + a;\n";
+    let error = Diagnostic::error(
+        DiagnosticKind::Type,
+        span(20, 27),
+        "cannot add String and Int",
+    )
+    .with_code(ErrorCode::BinaryOpTypeMismatch)
+    .with_label(Label::new(span(26, 27), "this is an Int"));
+    let warning = Diagnostic::warning(DiagnosticKind::Parser, span(9, 10), "uncoded")
+        .with_suggestion(Suggestion::new(span(9, 10), "", "remove it"));
+    let log = to_sarif("src/main.neve", source, &[error, warning]);
+
+    assert_eq!(log["version"], "2.1.0");
+    let run = &log["runs"][0];
+    assert_eq!(run["tool"]["driver"]["name"], "neve");
+    assert_eq!(run["columnKind"], "unicodeCodePoints");
+
+    // Every error code is a rule with its code as a stable ID
+    let rules = run["tool"]["driver"]["rules"].as_array().unwrap();
+    assert_eq!(rules.len(), ErrorCode::ALL.len());
+    assert_eq!(rules[0]["id"], "E0001");
+    assert!(
+        rules
+            .iter()
+            .any(|r| r["id"] == "E0215" && r["name"] == "BinaryOpTypeMismatch")
+    );
+
+    let results = run["results"].as_array().unwrap();
+    assert_eq!(results.len(), 2);
+    let error = &results[0];
+    assert_eq!(error["level"], "error");
+    assert_eq!(error["ruleId"], "E0215");
+    assert_eq!(
+        rules[error["ruleIndex"].as_u64().unwrap() as usize]["id"],
+        "E0215"
+    );
+    assert_eq!(error["message"]["text"], "cannot add String and Int");
+    let location = &error["locations"][0]["physicalLocation"];
+    assert_eq!(location["artifactLocation"]["uri"], "src/main.neve");
+    // Line 2 starts at byte 11; `é` is two bytes but one column
+    assert_eq!(
+        location["region"],
+        json!({ "startLine": 2, "startColumn": 9, "endLine": 2, "endColumn": 16 })
+    );
+    assert_eq!(
+        error["relatedLocations"][0]["message"]["text"],
+        "this is an Int"
+    );
+
+    let warning = &results[1];
+    assert_eq!(warning["level"], "warning");
+    assert!(warning.get("ruleId").is_none());
+    let replacement = &warning["fixes"][0]["artifactChanges"][0]["replacements"][0];
+    assert_eq!(replacement["insertedContent"]["text"], "");
+    assert_eq!(
+        replacement["deletedRegion"],
+        json!({ "startLine": 1, "startColumn": 10, "endLine": 1, "endColumn": 11 })
+    );
+}
+
+#[test]
+fn test_color_choice() {
+    assert_eq!("auto".parse(), Ok(ColorChoice::Auto));
+    assert_eq!("always".parse(), Ok(ColorChoice::Always));
+    assert_eq!("never".parse(), Ok(ColorChoice::Never));
+    assert!("sometimes".parse::<ColorChoice>().is_err());
+    assert_eq!(ColorChoice::default(), ColorChoice::Auto);
+
+    assert!(ColorChoice::Always.enabled());
+    assert!(!ColorChoice::Never.enabled());
+}
+
+#[test]
+fn test_render_color() {
+    let source = "let x = 1 + true;";
+    let diagnostic = Diagnostic::error(DiagnosticKind::Type, span(8, 16), "type mismatch")
+        .with_code(ErrorCode::TypeMismatch)
+        .with_label(Label::new(span(12, 16), "expected Int"));
+
+    let plain = render(source, "main.neve", &diagnostic, ColorChoice::Never);
+    assert!(!plain.contains('\x1b'));
+    assert!(plain.contains("type mismatch"));
+    assert!(plain.contains("expected Int"));
+
+    let colored = render(source, "main.neve", &diagnostic, ColorChoice::Always);
+    assert!(colored.contains('\x1b'));
+}
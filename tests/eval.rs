@@ -2,9 +2,13 @@
 //!
 //! This file contains extensive edge case tests for the evaluator.
 
-use neve_eval::{AstEvaluator, EvalError, Evaluator, Value};
+use neve_eval::{
+    AstEnv, AstEvaluator, BuiltinFn, BuiltinSet, EvalError, Evaluator, Value, eval_str,
+    eval_str_with_env,
+};
 use neve_hir::lower;
 use neve_parser::parse;
+use std::rc::Rc;
 
 fn eval_source(source: &str) -> Result<Value, EvalError> {
     let (ast, _) = parse(source);
@@ -15,12 +19,7 @@ fn eval_source(source: &str) -> Result<Value, EvalError> {
 
 /// Evaluate source with builtins available (using AstEvaluator).
 fn eval_with_builtins(source: &str) -> Result<Value, String> {
-    let (ast, errors) = parse(source);
-    if !errors.is_empty() {
-        return Err(format!("parse error: {:?}", errors));
-    }
-    let mut eval = AstEvaluator::new();
-    eval.eval_file(&ast).map_err(|e| e.to_string())
+    eval_str(source).map_err(|e| e.to_string())
 }
 
 // ============================================================================
@@ -552,6 +551,26 @@ fn test_eval_if_nested() {
     ));
 }
 
+#[test]
+fn test_eval_if_without_else() {
+    assert!(matches!(
+        eval_source("let x = if true then 1;"),
+        Ok(Value::Int(1))
+    ));
+    assert!(matches!(
+        eval_source("let x = if false then 1;"),
+        Ok(Value::Unit)
+    ));
+    assert!(matches!(
+        eval_with_builtins("let x = if 1 < 2 then 1;"),
+        Ok(Value::Int(1))
+    ));
+    assert!(matches!(
+        eval_with_builtins("let x = if 1 > 2 then 1;"),
+        Ok(Value::Unit)
+    ));
+}
+
 #[test]
 fn test_eval_if_deeply_nested() {
     assert!(matches!(
@@ -819,6 +838,42 @@ fn test_eval_lazy_function_call() {
     assert!(matches!(result, Ok(Value::Int(42))));
 }
 
+#[test]
+fn test_eval_lazy_param_is_not_forced_unless_used() {
+    // the argument for an unused lazy parameter is never evaluated
+    let result = eval_with_builtins(
+        "
+        fn spin(n) = spin(n);
+        fn pick(cond, lazy other) = if cond then 1 else other;
+        let x = pick(true, spin(0));
+    ",
+    );
+    assert!(matches!(result, Ok(Value::Int(1))));
+
+    // a used lazy parameter is forced on access
+    let result = eval_with_builtins(
+        "
+        fn pick(cond, lazy other) = if cond then 1 else other + other;
+        let x = pick(false, 20 + 1);
+    ",
+    );
+    assert!(matches!(result, Ok(Value::Int(42))));
+}
+
+#[test]
+fn test_eval_closure_display_shows_params() {
+    let ast_closure = eval_with_builtins("let f = fn(a, b) a + b;").unwrap();
+    assert_eq!(format!("{:?}", ast_closure), "<fn(a, b)>");
+
+    let hir_closure = eval_source("let f = fn(a, b) a + b;").unwrap();
+    assert_eq!(format!("{:?}", hir_closure), "<fn(a, b)>");
+
+    match eval_with_builtins("toString(fn(a, b) a + b)") {
+        Ok(Value::String(s)) => assert_eq!(s.as_str(), "<fn(a, b)>"),
+        other => panic!("expected string, got {:?}", other),
+    }
+}
+
 #[test]
 fn test_eval_record_multiple_fields() {
     match eval_source("let x = #{ a = 1, b = 2, c = 3 };") {
@@ -925,6 +980,31 @@ fn test_eval_function_three_params() {
     assert!(matches!(result, Ok(Value::Int(6))));
 }
 
+#[test]
+fn test_eval_trailing_record_argument() {
+    let result = eval_source(
+        "
+        fn service(name, cfg) = if cfg.enable then name else \"off\";
+        let y = service(\"nginx\") #{ enable = true };
+    ",
+    );
+    match result {
+        Ok(Value::String(s)) => assert_eq!(s.as_str(), "nginx"),
+        other => panic!("expected string, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_eval_trailing_block_argument() {
+    let result = eval_source(
+        "
+        fn add(a, b) = a + b;
+        let y = add(3) { let b = 4; b };
+    ",
+    );
+    assert!(matches!(result, Ok(Value::Int(7))));
+}
+
 #[test]
 fn test_eval_function_returns_bool() {
     let result = eval_source(
@@ -986,6 +1066,89 @@ fn test_eval_function_composition() {
     assert!(matches!(result, Ok(Value::Int(11))));
 }
 
+/// An environment with builtins plus a two-argument `add` builtin.
+fn env_with_add() -> Rc<AstEnv> {
+    let mut env = AstEnv::with_builtins();
+    env.define(
+        "add".to_string(),
+        Value::Builtin(BuiltinFn {
+            name: "add",
+            arity: 2,
+            func: |args| match (&args[0], &args[1]) {
+                (Value::Int(a), Value::Int(b)) => Ok(Value::Int(a + b)),
+                _ => Err("add expects two integers".to_string()),
+            },
+        }),
+    );
+    Rc::new(env)
+}
+
+#[test]
+fn test_eval_builtin_partial_application() {
+    let source = "let inc = add(1); let r = [inc(41), add(2)(3), add(1, 1)];";
+    match eval_str_with_env(source, env_with_add()) {
+        Ok(Value::List(items)) => assert_eq!(
+            items.as_slice(),
+            &[Value::Int(42), Value::Int(5), Value::Int(2)]
+        ),
+        other => panic!("expected list, got {:?}", other),
+    }
+
+    assert!(matches!(
+        eval_str_with_env("let inc = add(1); let t = typeOf(inc);", env_with_add()),
+        Ok(Value::String(ref s)) if s.as_str() == "function"
+    ));
+}
+
+#[test]
+fn test_eval_builtin_partial_higher_order() {
+    let source = r#"
+        let doubleAll = map(fn(x) x * 2);
+        let sum = foldl(fn(acc, x) acc + x, 0);
+        let r = sum(doubleAll([1, 2, 3]));
+    "#;
+    assert!(matches!(eval_with_builtins(source), Ok(Value::Int(12))));
+}
+
+#[test]
+fn test_eval_builtin_foldl1_foldr1() {
+    assert!(matches!(
+        eval_with_builtins("let r = foldl1(fn(a, b) a - b, [10, 3, 2]);"),
+        Ok(Value::Int(5))
+    ));
+    assert!(matches!(
+        eval_with_builtins("let r = foldr1(fn(a, b) a - b, [10, 3, 2]);"),
+        Ok(Value::Int(9))
+    ));
+
+    let err = eval_with_builtins("let r = foldl1(fn(a, b) a + b, []);").unwrap_err();
+    assert!(err.contains("foldl1 of an empty list"), "{err}");
+    let err = eval_with_builtins("let r = foldr1(fn(a, b) a + b, []);").unwrap_err();
+    assert!(err.contains("foldr1 of an empty list"), "{err}");
+}
+
+#[test]
+fn test_eval_builtin_scanl_running_sum() {
+    match eval_with_builtins("let r = scanl(fn(acc, x) acc + x, 0, [1, 2, 3, 4]);") {
+        Ok(Value::List(items)) => {
+            let sums: Vec<_> = items
+                .iter()
+                .map(|v| match v {
+                    Value::Int(n) => *n,
+                    other => panic!("expected Int, got {other:?}"),
+                })
+                .collect();
+            assert_eq!(sums, vec![0, 1, 3, 6, 10]);
+        }
+        other => panic!("expected a list, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_eval_builtin_too_many_args_still_fails() {
+    assert!(eval_str_with_env("let r = add(1, 2, 3);", env_with_add()).is_err());
+}
+
 // ============================================================================
 // 递归函数
 // ============================================================================
@@ -1154,6 +1317,205 @@ fn test_eval_match_with_binding() {
     ));
 }
 
+/// An environment holding user enum variants, as an enum definition would create them.
+fn env_with_variants() -> Rc<AstEnv> {
+    let variant = |tag: &str, payload| Value::Variant(tag.to_string(), Box::new(payload));
+    let size = [("w", 5), ("h", 6)]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), Value::Int(v)))
+        .collect();
+    let mut env = AstEnv::with_builtins();
+    env.define_many([
+        ("circle".to_string(), variant("Circle", Value::Int(2))),
+        (
+            "rect".to_string(),
+            variant(
+                "Rect",
+                Value::Tuple(Rc::new(vec![Value::Int(3), Value::Int(4)])),
+            ),
+        ),
+        (
+            "sized".to_string(),
+            variant("Sized", Value::Record(Rc::new(size))),
+        ),
+        ("Point".to_string(), variant("Point", Value::Unit)),
+        ("Red".to_string(), variant("Red", Value::Unit)),
+        ("Green".to_string(), variant("Green", Value::Unit)),
+    ]);
+    Rc::new(env)
+}
+
+#[test]
+fn test_eval_match_variant_payload() {
+    let source = r#"
+        fn area(s) = match s {
+            Circle(r) -> 3 * r * r,
+            Rect(w, h) -> w * h,
+            Sized(#{ w, h }) -> w * h,
+            Point() -> 0,
+        };
+        let r = [area(circle), area(rect), area(sized), area(Point)];
+    "#;
+    match eval_str_with_env(source, env_with_variants()) {
+        Ok(Value::List(items)) => assert_eq!(
+            items.as_slice(),
+            &[
+                Value::Int(12),
+                Value::Int(12),
+                Value::Int(30),
+                Value::Int(0)
+            ]
+        ),
+        other => panic!("expected a list, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_eval_match_unit_variant_by_name() {
+    let source = r#"
+        fn code(c) = match c {
+            Red -> 1,
+            Green -> 2,
+            other -> 0,
+        };
+        let r = [code(Green), code(Red), code(Point), code(42)];
+    "#;
+    match eval_str_with_env(source, env_with_variants()) {
+        Ok(Value::List(items)) => assert_eq!(
+            items.as_slice(),
+            &[Value::Int(2), Value::Int(1), Value::Int(0), Value::Int(0)]
+        ),
+        other => panic!("expected a list, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_eval_match_user_enum_payload() {
+    let source = r#"
+        enum Shape { Circle(Int), Rect(Int, Int), Sized #{ w: Int, h: Int }, Point };
+        fn area(s) = match s {
+            Circle(r) -> 3 * r * r,
+            Rect(w, h) -> w * h,
+            Sized(#{ w, h }) -> w * h,
+            Point() -> 0,
+        };
+        let r = [area(Circle(2)), area(Shape.Rect(3, 4)), area(Sized(#{ w = 5, h = 6 })), area(Point)];
+    "#;
+    match eval_with_builtins(source) {
+        Ok(Value::List(items)) => {
+            let areas: Vec<_> = items
+                .iter()
+                .map(|v| match v {
+                    Value::Int(n) => *n,
+                    other => panic!("expected Int, got {other:?}"),
+                })
+                .collect();
+            assert_eq!(areas, vec![12, 12, 30, 0]);
+        }
+        other => panic!("expected a list, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_eval_user_enum_variants_by_name() {
+    let source = r#"
+        enum Color { Red, Green, Rgb(Int, Int, Int) };
+        fn code(c) = match c {
+            Red -> 1,
+            Green -> 2,
+            Rgb(r, g, b) -> r + g + b,
+        };
+        let r = [code(Green), code(Red), code(Rgb(10, 20, 30))];
+    "#;
+    match eval_with_builtins(source) {
+        Ok(Value::List(items)) => {
+            let codes: Vec<_> = items
+                .iter()
+                .map(|v| match v {
+                    Value::Int(n) => *n,
+                    other => panic!("expected Int, got {other:?}"),
+                })
+                .collect();
+            assert_eq!(codes, vec![2, 1, 60]);
+        }
+        other => panic!("expected a list, got {other:?}"),
+    }
+
+    match eval_with_builtins("enum Color { Red, Rgb(Int, Int, Int) }; let c = Rgb(1, 2, 3);") {
+        Ok(Value::Variant(tag, payload)) => {
+            assert_eq!(tag, "Rgb");
+            assert!(matches!(*payload, Value::Tuple(ref fields) if fields.len() == 3));
+        }
+        other => panic!("expected a variant, got {other:?}"),
+    }
+    assert!(matches!(
+        eval_with_builtins("enum Color { Red, Green }; let c = Red;"),
+        Ok(Value::Variant(ref tag, _)) if tag == "Red"
+    ));
+}
+
+#[test]
+fn test_eval_variant_constructor_is_named() {
+    let constructor = eval_with_builtins("enum Shape { Circle(Int) }; let c = Circle;").unwrap();
+    assert_eq!(format!("{constructor:?}"), "<constructor:Circle>");
+    assert!(matches!(
+        eval_with_builtins("enum Shape { Circle(Int) }; let t = typeOf(Circle);"),
+        Ok(Value::String(ref t)) if t.as_str() == "function"
+    ));
+
+    let err = eval_with_builtins("enum Shape { Circle(Int) }; let c = Circle(1, 2);").unwrap_err();
+    assert!(err.contains("Circle expects 1 argument(s), got 2"), "{err}");
+}
+
+#[test]
+fn test_eval_struct_constructor_is_named() {
+    let constructor = eval_with_builtins("struct Point { x: Int }; let c = Point;").unwrap();
+    assert_eq!(format!("{constructor:?}"), "<constructor:Point>");
+    assert!(matches!(
+        eval_with_builtins("struct Point { x: Int }; let t = typeOf(Point);"),
+        Ok(Value::String(ref t)) if t.as_str() == "function"
+    ));
+
+    let err = eval_with_builtins("struct Point { x: Int }; let p = Point(1);").unwrap_err();
+    assert!(err.contains("Point expects a record of fields"), "{err}");
+}
+
+#[test]
+fn test_eval_struct_construction() {
+    let source = r#"
+        struct Server { host: String, port: Int = 80 };
+        let s = Server(#{ host = "localhost" });
+        let r = [s.host, toString(s.port)];
+    "#;
+    match eval_with_builtins(source) {
+        Ok(Value::List(items)) => {
+            assert!(matches!(&items[0], Value::String(h) if h.as_str() == "localhost"));
+            assert!(matches!(&items[1], Value::String(p) if p.as_str() == "80"));
+        }
+        other => panic!("expected a list, got {other:?}"),
+    }
+
+    let err = eval_with_builtins("struct Point { x: Int, y: Int }; let p = Point(#{ x = 1 });")
+        .unwrap_err();
+    assert!(err.contains("missing field 'y' in struct Point"), "{err}");
+    let err = eval_with_builtins("struct Point { x: Int }; let p = Point(#{ x = 1, w = 2 });")
+        .unwrap_err();
+    assert!(err.contains("unknown field 'w'"), "{err}");
+}
+
+#[test]
+fn test_eval_match_option_result_constructors() {
+    let source = r#"
+        fn describe(o) = match o {
+            Some(Ok(n)) -> n,
+            Some(Err(e)) -> e,
+            None -> 0,
+        };
+        let r = describe(Some(Ok(1))) + describe(Some(Err(10))) + describe(None);
+    "#;
+    assert!(matches!(eval_with_builtins(source), Ok(Value::Int(11))));
+}
+
 #[test]
 fn test_eval_match_tuple() {
     assert!(matches!(
@@ -1195,6 +1557,228 @@ fn test_eval_match_multiple_arms_second() {
     ));
 }
 
+/// Extract the arms of the first `match` bound by a top-level `let`.
+fn match_arms(source: &str) -> Vec<neve_syntax::MatchArm> {
+    let (ast, errors) = parse(source);
+    assert!(errors.is_empty(), "parse error: {:?}", errors);
+    for item in ast.items {
+        if let neve_syntax::ItemKind::Let(def) = item.kind
+            && let neve_syntax::ExprKind::Match { arms, .. } = def.value.kind
+        {
+            return arms.to_vec();
+        }
+    }
+    panic!("no match expression found");
+}
+
+#[test]
+fn test_eval_match_decision_tree_same_as_linear() {
+    // The match has enough arms to be compiled to a decision tree; the
+    // if-chain spells out linear first-match semantics for comparison.
+    let source = r#"
+        fn tree(x) = match x {
+            0 -> "zero",
+            1 | 2 -> "small",
+            n if n > 100 -> "big",
+            3 -> "three",
+            4 if false -> "never",
+            4 -> "four",
+            5 -> "five",
+            6 -> "six",
+            m if m < 0 -> "negative",
+            _ -> "other",
+        };
+        fn linear(x) =
+            if x == 0 then "zero"
+            else if x == 1 || x == 2 then "small"
+            else if x > 100 then "big"
+            else if x == 3 then "three"
+            else if x == 4 then "four"
+            else if x == 5 then "five"
+            else if x == 6 then "six"
+            else if x < 0 then "negative"
+            else "other";
+        let inputs = [-5, 0, 1, 2, 3, 4, 5, 6, 7, 99, 101, 500];
+        let same = all(fn(x) tree(x) == linear(x), inputs);
+    "#;
+    assert!(matches!(eval_with_builtins(source), Ok(Value::Bool(true))));
+}
+
+#[test]
+fn test_eval_match_decision_tree_first_match_wins() {
+    // A catch-all arm in the middle must shadow later literal arms
+    let source = r#"
+        fn f(x) = match x {
+            0 -> 0, 1 -> 1, 2 -> 2, 3 -> 3,
+            _ -> 100,
+            4 -> 4, 5 -> 5, 6 -> 6, 7 -> 7,
+        };
+        let result = [f(2), f(5), f(9)];
+    "#;
+    match eval_with_builtins(source) {
+        Ok(Value::List(items)) => assert_eq!(
+            items.as_slice(),
+            &[Value::Int(2), Value::Int(100), Value::Int(100)]
+        ),
+        other => panic!("expected list, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_eval_match_decision_tree_constructors() {
+    let source = r#"
+        fn f(x) = match x {
+            Some(0) -> "some zero",
+            Some(n) if n > 10 -> "some big",
+            Ok(v) -> "ok",
+            Err(e) -> "err",
+            Some(n) -> "some",
+            1 -> "one", 2 -> "two", 3 -> "three",
+            _ -> "other",
+        };
+        let result = [f(head([0])), f(head([42])), f(head([5])), f(head([])), f(2), f(9)];
+    "#;
+    let expected = ["some zero", "some big", "some", "other", "two", "other"];
+    match eval_with_builtins(source) {
+        Ok(Value::List(items)) => {
+            let got: Vec<String> = items
+                .iter()
+                .map(|v| v.as_string().unwrap_or_default().to_string())
+                .collect();
+            assert_eq!(got, expected);
+        }
+        other => panic!("expected list, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_compile_match_candidates_keep_source_order() {
+    use neve_eval::compile_match;
+
+    let arms =
+        match_arms("let m = match x { 1 -> 10, n if n > 5 -> 0, 2 -> 20, 1 | 3 -> 30, _ -> 40 };");
+    let tree = compile_match(&arms);
+
+    assert_eq!(tree.switch_count(), 3);
+    assert_eq!(tree.candidates(&Value::Int(1)), &[0, 1, 3, 4]);
+    assert_eq!(tree.candidates(&Value::Int(2)), &[1, 2, 4]);
+    assert_eq!(tree.candidates(&Value::Int(3)), &[1, 3, 4]);
+    // Unknown heads only try arms that can match anything
+    assert_eq!(tree.candidates(&Value::Int(9)), &[1, 4]);
+    assert_eq!(tree.candidates(&Value::Unit), &[1, 4]);
+}
+
+#[test]
+fn test_match_analysis_reports_redundant_arm() {
+    use neve_eval::analysis::analyze_file;
+
+    let source =
+        "let m = match x {\n  Some(n) -> n,\n  Some(1) -> 1,\n  0 -> 0,\n  _ -> 2,\n  0 -> 3,\n};";
+    let (file, diags) = parse(source);
+    assert!(diags.is_empty());
+
+    let reports = analyze_file(&file, source);
+    assert_eq!(reports.len(), 1);
+    let report = &reports[0];
+    assert_eq!(report.catch_all, Some(3));
+    assert_eq!(report.arms[4].unreachable_after, Some(3));
+    assert!(
+        report.arms[..4]
+            .iter()
+            .all(|a| a.unreachable_after.is_none())
+    );
+    assert_eq!(report.reorder, vec![(1, 0)]);
+
+    assert_eq!(
+        report.to_string(),
+        "match at line 1: 5 arms, exhaustive (catch-all arm 4)\n\
+         \x20 arm 1: Some(n)  [Constructor, specificity 101]\n\
+         \x20 arm 2: Some(1)  [Constructor, specificity 1100]\n\
+         \x20 arm 3: 0  [Literal, specificity 1000]\n\
+         \x20 arm 4: _  [Irrefutable, specificity 0]\n\
+         \x20 arm 5: 0  [Literal, specificity 1000] unreachable after arm 4\n\
+         \x20 hint: catch-all arm 4 shadows the 1 arm after it; consider moving it last\n\
+         \x20 hint: arm 2 is more specific than arm 1; consider moving it earlier\n\
+         \x20 fast path: direct comparison for 2 literal arms\n"
+    );
+}
+
+#[test]
+fn test_match_analysis_reports_literal_after_guard() {
+    use neve_eval::analysis::analyze_file;
+
+    let source = "let m = match x {\n  n if expensive(n) -> n,\n  0 -> 0,\n  1 if x > 0 -> 1,\n  1 -> 2,\n  _ -> 3,\n};";
+    let (file, diags) = parse(source);
+    assert!(diags.is_empty());
+
+    let report = &analyze_file(&file, source)[0];
+    assert_eq!(report.guard_before_literal, vec![(1, 0), (3, 0)]);
+    assert_eq!(
+        report.to_string(),
+        "match at line 1: 5 arms, exhaustive (catch-all arm 5)\n\
+         \x20 arm 1: n  [Irrefutable, specificity 1, guarded]\n\
+         \x20 arm 2: 0  [Literal, specificity 1000]\n\
+         \x20 arm 3: 1  [Literal, specificity 1000, guarded]\n\
+         \x20 arm 4: 1  [Literal, specificity 1000]\n\
+         \x20 arm 5: _  [Irrefutable, specificity 0]\n\
+         \x20 hint: literal arm 2 comes after guarded arm 1; if the guard cannot hold for it, moving it earlier skips the guard\n\
+         \x20 hint: literal arm 4 comes after guarded arm 1; if the guard cannot hold for it, moving it earlier skips the guard\n\
+         \x20 fast path: direct comparison for 3 literal arms\n"
+    );
+
+    let source = "let m = match x {\n  _ -> 0,\n  1 -> 1,\n};";
+    let (file, _) = parse(source);
+    let report = analyze_file(&file, source)[0].to_string();
+    assert!(report.starts_with("match at line 1: 2 arms, exhaustive (catch-all arm 1)\n"));
+    assert!(
+        report.contains(
+            "hint: catch-all arm 1 shadows the 1 arm after it; consider moving it last\n"
+        )
+    );
+    assert!(report.ends_with("fast path: direct comparison for 1 literal arm\n"));
+}
+
+#[test]
+fn test_eval_match_decision_tree_many_arms() {
+    use neve_eval::compile_match;
+    use std::fmt::Write;
+
+    const ARMS: i64 = 256;
+    let mut arms = String::new();
+    for i in 0..ARMS {
+        write!(arms, "Some({i}) -> {}, {i} -> {}, ", i * 2, i * 3).unwrap();
+    }
+    let source = format!(
+        "fn f(x) = match x {{ {arms} _ -> 0 }};
+        let x = 0;
+        let m = match x {{ {arms} _ -> 0 }};
+        let total = foldl(fn(acc, i) acc + f(i) + f(head([i])), 0, genList(fn(i) i, {ARMS}));"
+    );
+
+    // An integer only has to try its own arm plus the catch-all, and an
+    // option only the `Some` arms
+    let tree = compile_match(&match_arms(&source));
+    assert_eq!(tree.switch_count(), ARMS as usize + 1);
+    assert_eq!(tree.candidates(&Value::Int(200)).len(), 2);
+    assert_eq!(
+        tree.candidates(&Value::Some(Box::new(Value::Int(200))))
+            .len(),
+        ARMS as usize + 1
+    );
+
+    // Both matches go through their decision tree, each compiled once even
+    // though `f`'s match runs for every call
+    let (file, errors) = parse(&source);
+    assert!(errors.is_empty(), "parse error: {:?}", errors);
+    let mut evaluator = AstEvaluator::new();
+    let expected: i64 = (0..ARMS).map(|i| i * 5).sum();
+    match evaluator.eval_file(&file) {
+        Ok(Value::Int(n)) => assert_eq!(n, expected),
+        other => panic!("expected Int, got {:?}", other),
+    }
+    assert_eq!(evaluator.decision_tree_count(), 2);
+}
+
 // ============================================================================
 // 变量绑定和作用域
 // ============================================================================
@@ -1340,6 +1924,43 @@ fn test_eval_complex_record() {
     assert!(matches!(result, Ok(Value::Int(5))));
 }
 
+// ============================================================================
+// eval_str 便捷函数
+// ============================================================================
+
+#[test]
+fn test_eval_str_simple_expression() {
+    assert!(matches!(eval_str("1 + 2"), Ok(Value::Int(3))));
+}
+
+#[test]
+fn test_eval_str_parse_error() {
+    match eval_str("let x = ;") {
+        Err(EvalError::ParseError(diagnostics)) => assert!(!diagnostics.is_empty()),
+        other => panic!("expected ParseError, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_eval_str_expression_with_trailing_input() {
+    // Input after a bare expression is an error, not part of a larger program
+    assert!(matches!(eval_str("1 + 2;"), Ok(Value::Int(3))));
+    match eval_str("1 + 2; 3") {
+        Err(EvalError::ParseError(diagnostics)) => assert!(!diagnostics.is_empty()),
+        other => panic!("expected ParseError, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_eval_str_with_env() {
+    let mut env = AstEnv::with_builtins();
+    env.define("base".to_string(), Value::Int(40));
+    assert!(matches!(
+        eval_str_with_env("base + len([1, 2])", Rc::new(env)),
+        Ok(Value::Int(42))
+    ));
+}
+
 // ============================================================================
 // 错误处理测试
 // ============================================================================
@@ -1376,6 +1997,34 @@ fn test_eval_pattern_match_failure() {
     }
 }
 
+#[test]
+fn test_eval_undefined_variable_suggests_similar_name() {
+    let err = eval_with_builtins("let food = 1; let x = foo + 1;").unwrap_err();
+    assert!(err.contains("undefined variable: foo"), "{}", err);
+    assert!(err.contains("did you mean `food`?"), "{}", err);
+}
+
+#[test]
+fn test_eval_undefined_variable_no_suggestion_for_distant_name() {
+    let err = eval_with_builtins("let food = 1; let x = zzqqxxww;").unwrap_err();
+    assert!(err.contains("undefined variable: zzqqxxww"), "{}", err);
+    assert!(!err.contains("did you mean"), "{}", err);
+}
+
+#[test]
+fn test_eval_missing_field_suggests_similar_field() {
+    let err = eval_with_builtins("let r = #{ count = 1 }; let x = r.cout;").unwrap_err();
+    assert!(err.contains("did you mean `count`?"), "{}", err);
+
+    let err = eval_with_builtins("let r = #{ count = 1 }; let x = r.banana;").unwrap_err();
+    assert!(!err.contains("did you mean"), "{}", err);
+
+    match eval_source("let r = #{ count = 1 }; let x = r.cout;") {
+        Err(EvalError::TypeError(msg)) => assert!(msg.contains("did you mean `count`?")),
+        other => panic!("expected TypeError, got {:?}", other),
+    }
+}
+
 // ============================================================================
 // Lambda 表达式测试
 // ============================================================================
@@ -1541,3 +2190,415 @@ fn test_eval_path_lit_absolute() {
         other => panic!("expected String, got {:?}", other),
     }
 }
+
+#[test]
+fn test_eval_pure_builtin_set() {
+    let pure = || Rc::new(AstEnv::with_builtin_set(BuiltinSet::Pure));
+
+    assert!(eval_str_with_env("readFile(\"/etc/passwd\")", pure()).is_err());
+    assert!(eval_str_with_env("print(1)", pure()).is_err());
+    assert!(matches!(
+        eval_str_with_env("len(map(fn(x) x + 1, [1, 2, 3]))", pure()),
+        Ok(Value::Int(3))
+    ));
+    assert!(matches!(eval_str("print(1)"), Ok(Value::Unit)));
+
+    let (file, _) = parse("import foo.bar; let x = 1;");
+    let err = AstEvaluator::with_builtin_set(BuiltinSet::Pure)
+        .eval_file(&file)
+        .unwrap_err();
+    assert!(err.to_string().contains("imports are not allowed"), "{err}");
+
+    // A builtin smuggled into a pure evaluator's environment is still refused.
+    let mut env = AstEnv::with_builtin_set(BuiltinSet::Pure);
+    if let Some((_, print)) = neve_eval::builtins()
+        .into_iter()
+        .find(|(n, _)| *n == "print")
+    {
+        env.define("p".to_string(), print);
+    }
+    let (file, _) = parse("let x = p(1);");
+    let err = AstEvaluator::with_env(Rc::new(env))
+        .restricted_to(BuiltinSet::Pure)
+        .eval_file(&file)
+        .unwrap_err();
+    assert!(err.to_string().contains("not available"), "{err}");
+
+    // Function bodies are evaluated under the same restriction.
+    let (file, _) = parse("fn f(p) = print(p); let r = f(\"/etc/hostname\");");
+    let err = AstEvaluator::new()
+        .restricted_to(BuiltinSet::Pure)
+        .eval_file(&file)
+        .unwrap_err();
+    assert!(err.to_string().contains("not available"), "{err}");
+}
+
+// ============================================================================
+// JSON 表示
+// ============================================================================
+
+#[test]
+fn test_value_to_json_nested() {
+    let source = r#"
+        enum Shape { Circle(Int), Point };
+        let r = #{
+            name = "box",
+            tags = ["a", "b"],
+            size = Some(#{ w = 1.5, h = 2 }),
+            missing = None,
+            shapes = [Circle(3), Point],
+            pair = ('x', ()),
+        };
+    "#;
+    let value = eval_with_builtins(source).unwrap();
+    assert_eq!(
+        value.to_json().unwrap(),
+        serde_json::json!({
+            "name": "box",
+            "tags": ["a", "b"],
+            "size": { "tag": "Some", "value": { "h": 2, "w": 1.5 } },
+            "missing": { "tag": "None", "value": null },
+            "shapes": [
+                { "tag": "Circle", "value": 3 },
+                { "tag": "Point", "value": null },
+            ],
+            "pair": ["x", null],
+        })
+    );
+
+    // Record keys come out sorted, so the text form is stable
+    let text = serde_json::to_string(&value.to_json().unwrap()).unwrap();
+    assert!(text.starts_with(r#"{"missing":"#), "{}", text);
+
+    let err = eval_with_builtins("let f = fn(x) x;")
+        .unwrap()
+        .to_json()
+        .unwrap_err();
+    assert!(err.contains("function"), "{}", err);
+}
+
+// ============================================================================
+// 递归深度限制
+// ============================================================================
+
+#[test]
+fn test_recursion_limit() {
+    let (file, _) = parse("fn down(n) = 1 + down(n + 1); let x = down(0);");
+    let err = AstEvaluator::new()
+        .with_recursion_limit(50)
+        .eval_file(&file)
+        .unwrap_err();
+    assert!(matches!(err, EvalError::RecursionLimit(50)), "{err}");
+
+    // Calls in tail position do not nest, so they never reach the limit.
+    let (file, _) = parse(
+        "fn count(n, acc) = if n == 0 then acc else count(n - 1, acc + 1); let x = count(500, 0);",
+    );
+    let value = AstEvaluator::new()
+        .with_recursion_limit(50)
+        .eval_file(&file)
+        .unwrap();
+    assert!(matches!(value, Value::Int(500)));
+
+    // Unbounded recursion under the default limit is an error rather than a stack overflow.
+    let err = eval_str("fn down(n) = 1 + down(n + 1); let x = down(0);").unwrap_err();
+    assert!(
+        err.to_string().contains("recursion limit of 10000"),
+        "{err}"
+    );
+}
+
+// ============================================================================
+// 求值燃料
+// ============================================================================
+
+#[test]
+fn test_fuel_limit() {
+    assert_eq!(AstEvaluator::new().remaining_fuel(), None);
+
+    let (file, _) = parse("let xs = [x * 2 | x <- range(0, 1000000)];");
+    let err = AstEvaluator::new()
+        .with_fuel(1000)
+        .eval_file(&file)
+        .unwrap_err();
+    assert!(matches!(err, EvalError::StepLimitExceeded), "{err}");
+
+    // Steps taken inside closures, blocks and matches draw from the same budget.
+    let (file, _) = parse(
+        "fn pick(n) = match n { 0 -> { let a = 1; a }, _ -> n }; let x = map(fn(n) pick(n), [0, 1, 2]);",
+    );
+    let mut eval = AstEvaluator::new().with_fuel(1000);
+    eval.eval_file(&file).unwrap();
+    let left = eval.remaining_fuel().unwrap();
+    assert!(left < 1000 - 10, "{left}");
+
+    let mut eval = AstEvaluator::new().with_fuel(10);
+    assert!(matches!(
+        eval.eval_file(&file),
+        Err(EvalError::StepLimitExceeded)
+    ));
+    assert_eq!(eval.remaining_fuel(), Some(0));
+
+    // Generated lists are charged per element before they are allocated.
+    for source in [
+        "let xs = [x | x <- range(0, 1000000000)];",
+        "let xs = replicate(1000000000000, 0);",
+    ] {
+        let (file, _) = parse(source);
+        let err = AstEvaluator::new()
+            .with_fuel(100)
+            .eval_file(&file)
+            .unwrap_err();
+        assert!(
+            matches!(err, EvalError::StepLimitExceeded),
+            "{source}: {err}"
+        );
+    }
+    let (file, _) = parse("let xs = range(0, 50);");
+    let mut eval = AstEvaluator::new().with_fuel(100);
+    eval.eval_file(&file).unwrap();
+    assert!(eval.remaining_fuel().unwrap() <= 50);
+}
+
+// ============================================================================
+// 列表与元组比较
+// ============================================================================
+
+#[test]
+fn test_compare_lists_and_tuples() {
+    for (source, expected) in [
+        ("[1, 2, 3] < [1, 3]", true),
+        ("[1, 2] < [1, 2, 0]", true),
+        ("[1, 2] <= [1, 2]", true),
+        ("[] < [0]", true),
+        ("(1, \"b\") > (1, \"a\")", true),
+        ("(2, \"a\") < (1, \"z\")", false),
+        ("[[1, 2], [0]] < [[1, 2], [1]]", true),
+        ("false < true", true),
+        ("true >= true", true),
+    ] {
+        match eval_str(source) {
+            Ok(Value::Bool(b)) => assert_eq!(b, expected, "{source}"),
+            other => panic!("{source}: {:?}", other.map_err(|e| e.to_string())),
+        }
+    }
+
+    let err = eval_str("[1, 2] < [1, \"a\"]").unwrap_err();
+    assert!(err.to_string().contains("cannot compare"), "{err}");
+    let err = eval_str("[1] < (1, 2)").unwrap_err();
+    assert!(err.to_string().contains("cannot compare"), "{err}");
+}
+
+// ============================================================================
+// tryEval
+// ============================================================================
+
+#[test]
+fn test_try_eval() {
+    let field = |value: &Value, name: &str| match value {
+        Value::Record(fields) => fields[name].clone(),
+        other => panic!("expected record, got {other:?}"),
+    };
+
+    let ok = eval_str("tryEval(1 + 2)").unwrap();
+    assert_eq!(field(&ok, "success"), Value::Bool(true));
+    assert_eq!(field(&ok, "value"), Value::Int(3));
+
+    for source in [
+        "tryEval(1 / 0)",
+        "tryEval(throw(\"boom\"))",
+        "tryEval(undefinedName)",
+    ] {
+        let failed = eval_str(source).unwrap();
+        assert_eq!(field(&failed, "success"), Value::Bool(false), "{source}");
+        assert_eq!(field(&failed, "value"), Value::None, "{source}");
+    }
+
+    let recovered =
+        eval_str("let r = tryEval(throw(\"missing\")); let x = if r.success then r.value else 42;")
+            .unwrap();
+    assert_eq!(recovered, Value::Int(42));
+
+    // Resource guards are not swallowed.
+    let (file, _) = parse("fn down(n) = 1 + down(n + 1); let r = tryEval(down(0));");
+    let err = AstEvaluator::new()
+        .with_recursion_limit(20)
+        .eval_file(&file)
+        .unwrap_err();
+    assert!(matches!(err, EvalError::RecursionLimit(20)), "{err}");
+
+    let (file, _) = parse("let r = tryEval([x * 2 | x <- range(0, 1000)]);");
+    let err = AstEvaluator::new()
+        .with_fuel(100)
+        .eval_file(&file)
+        .unwrap_err();
+    assert!(matches!(err, EvalError::StepLimitExceeded), "{err}");
+}
+
+// ============================================================================
+// throw 与 assert
+// ============================================================================
+
+#[test]
+fn test_throw_and_assert() {
+    let err = eval_str("let x = 1 + throw(\"boom\");").unwrap_err();
+    assert_eq!(err.to_string(), "boom");
+    match err {
+        EvalError::Thrown {
+            span: Some(span), ..
+        } => assert_eq!(span.start.0, 12),
+        other => panic!("expected a located throw, got {other:?}"),
+    }
+
+    // A throw in tail position still records where it was called.
+    let err = eval_str("fn fail(msg) = throw(msg); let x = fail(\"deep\");").unwrap_err();
+    assert!(
+        matches!(&err, EvalError::Thrown { message, span: Some(span) } if message == "deep" && span.start.0 == 15),
+        "{err:?}"
+    );
+
+    assert_eq!(
+        eval_str("assert(1 < 2, \"nope\", 7)").unwrap(),
+        Value::Int(7)
+    );
+    let err = eval_str("let x = 3; let y = assert(false, \"nope\", x);").unwrap_err();
+    assert!(
+        matches!(&err, EvalError::Thrown { message, .. } if message == "nope"),
+        "{err:?}"
+    );
+    assert!(matches!(
+        eval_str("assert(1, \"nope\", 7)"),
+        Err(EvalError::TypeError(_))
+    ));
+
+    // Both integrate with tryEval.
+    let caught =
+        eval_str("let r = tryEval(assert(false, \"nope\", 1)); let ok = r.success;").unwrap();
+    assert_eq!(caught, Value::Bool(false));
+}
+
+#[test]
+fn test_to_json_builtin() {
+    assert_eq!(
+        eval_str("toJSON(#{ b = [1, 'c'], a = (true, Some(2)) })").unwrap(),
+        Value::String(Rc::new(r#"{"a":[true,2],"b":[1,"c"]}"#.to_string()))
+    );
+    let err = eval_str("toJSON([fn(x) x])").unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("cannot serialize function to JSON"),
+        "{err}"
+    );
+}
+
+// ============================================================================
+// seq 与 deepSeq
+// ============================================================================
+
+#[test]
+fn test_seq_and_deep_seq() {
+    let message = |source: &str| eval_str(source).unwrap_err().to_string();
+
+    // seq forces its first argument only to the outermost value.
+    assert_eq!(eval_str("seq(lazy 1 + 1, 7)").unwrap(), Value::Int(7));
+    assert_eq!(message("seq(lazy throw(\"outer\"), 7)"), "outer");
+    assert_eq!(
+        eval_str("seq(#{ x = lazy throw(\"inner\") }, 7)").unwrap(),
+        Value::Int(7)
+    );
+
+    // deepSeq reaches thunks nested in records, lists, tuples and options.
+    for source in [
+        "deepSeq(#{ x = lazy throw(\"late\") }, 7)",
+        "deepSeq([1, lazy throw(\"late\")], 7)",
+        "deepSeq((1, #{ y = [lazy throw(\"late\")] }), 7)",
+        "deepSeq(Some(lazy throw(\"late\")), 7)",
+    ] {
+        assert_eq!(message(source), "late", "{source}");
+    }
+}
+
+// ============================================================================
+// typeOf
+// ============================================================================
+
+#[test]
+fn test_type_of() {
+    for (source, expected) in [
+        ("typeOf(1)", "int"),
+        ("typeOf(1.5)", "float"),
+        ("typeOf(true)", "bool"),
+        ("typeOf(\"s\")", "string"),
+        ("typeOf('c')", "char"),
+        ("typeOf(())", "unit"),
+        ("typeOf([1])", "list"),
+        ("typeOf((1, 2))", "tuple"),
+        ("typeOf(#{ a = 1 })", "record"),
+        ("typeOf(fn(x) x)", "function"),
+        ("typeOf(map)", "function"),
+        ("typeOf(Some(1))", "option"),
+        ("typeOf(None)", "option"),
+        ("typeOf(Ok(1))", "result"),
+        ("typeOf(Err(1))", "result"),
+        ("typeOf(lazy 1)", "int"),
+        ("typeOf(lazy [1])", "list"),
+    ] {
+        match eval_str(source) {
+            Ok(Value::String(s)) => assert_eq!(s.as_str(), expected, "{source}"),
+            other => panic!("{source}: {:?}", other.map_err(|e| e.to_string())),
+        }
+    }
+}
+
+// ============================================================================
+// 柯里化
+// ============================================================================
+
+#[test]
+fn test_closure_currying() {
+    for (source, expected) in [
+        ("fn add(a, b) = a + b; let x = add(1)(2);", Value::Int(3)),
+        (
+            "let add3 = fn(a, b, c) a * 100 + b * 10 + c; let x = add3(1)(2, 3);",
+            Value::Int(123),
+        ),
+        (
+            "let add3 = fn(a, b, c) a * 100 + b * 10 + c; let x = add3(1, 2)(3);",
+            Value::Int(123),
+        ),
+        (
+            "fn add(a, b) = a + b; let x = 2 |> add(40);",
+            Value::Int(42),
+        ),
+        // Over-application applies the result to the remaining arguments.
+        (
+            "let k = fn(a) fn(b) a - b; let x = k(10, 3);",
+            Value::Int(7),
+        ),
+        // Recursion still works through a partially applied named function.
+        (
+            "fn sum(acc, n) = if n == 0 then acc else sum(acc + n)(n - 1); let x = sum(0)(4);",
+            Value::Int(10),
+        ),
+    ] {
+        match eval_str(source) {
+            Ok(value) => assert_eq!(value, expected, "{source}"),
+            Err(e) => panic!("{source}: {e}"),
+        }
+    }
+
+    match eval_str("fn add(a, b) = a + b; let xs = map(add(1), [1, 2, 3]);") {
+        Ok(Value::List(items)) => assert_eq!(
+            items.as_slice(),
+            &[Value::Int(2), Value::Int(3), Value::Int(4)]
+        ),
+        other => panic!("expected list, got {:?}", other.map_err(|e| e.to_string())),
+    }
+
+    let partial = eval_str("fn add(a, b) = a + b; let f = add(1);").unwrap();
+    assert_eq!(format!("{partial:?}"), "<fn(b)>");
+    assert!(matches!(
+        eval_str("fn one(a) = a; let x = one(1)(2);"),
+        Err(EvalError::NotAFunction)
+    ));
+}
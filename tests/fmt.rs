@@ -1,9 +1,14 @@
 //! Integration tests for neve-fmt crate.
 
+use neve_common::BytePos;
 use neve_fmt::printer::Printer;
-use neve_fmt::{FormatConfig, Formatter, check, format};
+use neve_fmt::{
+    FormatConfig, Formatter, check, check_dir, format, format_range, format_with_config,
+    normalize_whitespace,
+};
 use neve_lexer::Lexer;
 use neve_parser::Parser;
+use proptest::prelude::*;
 
 fn format_code(source: &str) -> String {
     let lexer = Lexer::new(source);
@@ -89,6 +94,95 @@ fn test_format_list() {
 fn test_format_if() {
     let formatted = format_code("let x = if true then 1 else 2;");
     assert!(formatted.contains("if true then"));
+
+    let formatted = format_code("let x = if true then ();");
+    assert!(formatted.contains("if true then ();"));
+    assert!(!formatted.contains("else"));
+}
+
+#[test]
+fn test_blank_lines_between_items_collapse() {
+    let source = "\n\nlet a = 1;\n\n\n\nlet b = 2;\nlet c = 3;\n\n\n";
+    assert_eq!(
+        format(source).unwrap(),
+        "let a = 1;\n\nlet b = 2;\n\nlet c = 3;\n"
+    );
+
+    let config = FormatConfig::new().blank_lines_between_items(2);
+    assert_eq!(
+        format_with_config(source, &config).unwrap(),
+        "let a = 1;\n\n\nlet b = 2;\n\n\nlet c = 3;\n"
+    );
+
+    let config = FormatConfig::new().blank_lines_between_items(0);
+    assert_eq!(
+        format_with_config(source, &config).unwrap(),
+        "let a = 1;\nlet b = 2;\nlet c = 3;\n"
+    );
+}
+
+#[test]
+fn test_preserve_groups_in_blocks() {
+    let source = "let x = {\n\n  let a = 1;\n\n\n  let b = 2;\n  let c = 3;\n\n  a + b + c\n\n};\n";
+
+    // Without preserve_groups, blocks have no blank lines at all
+    assert_eq!(
+        format(source).unwrap(),
+        "let x = {\n  let a = 1;\n  let b = 2;\n  let c = 3;\n  a + b + c\n};\n"
+    );
+
+    // With it, each run of blank lines between statements becomes one, and
+    // blank lines at the start and end of the block are still dropped
+    let config = FormatConfig::new().preserve_groups(true);
+    assert_eq!(
+        format_with_config(source, &config).unwrap(),
+        "let x = {\n  let a = 1;\n\n  let b = 2;\n  let c = 3;\n\n  a + b + c\n};\n"
+    );
+}
+
+// Whitespace normalization tests
+
+#[test]
+fn test_normalize_trailing_whitespace() {
+    let config = FormatConfig::default();
+    assert_eq!(
+        normalize_whitespace("let x = 1;   \nlet y = 2;\t\n", &config),
+        "let x = 1;\nlet y = 2;\n"
+    );
+}
+
+#[test]
+fn test_normalize_final_newline() {
+    let config = FormatConfig::default();
+    assert_eq!(normalize_whitespace("let x = 1;", &config), "let x = 1;\n");
+    assert_eq!(
+        normalize_whitespace("let x = 1;\n\n\n", &config),
+        "let x = 1;\n"
+    );
+}
+
+#[test]
+fn test_normalize_indentation() {
+    let source = "let x = {\n\t  1\n};\n";
+    let spaces = FormatConfig::default();
+    assert_eq!(
+        normalize_whitespace(source, &spaces),
+        "let x = {\n    1\n};\n"
+    );
+    let tabs = FormatConfig::default().use_tabs(true);
+    assert_eq!(
+        normalize_whitespace(source, &tabs),
+        "let x = {\n\t\t1\n};\n"
+    );
+}
+
+#[test]
+fn test_normalize_keeps_string_whitespace() {
+    let source = "let s = \"a  \n\t  b\";  \n";
+    assert_eq!(
+        normalize_whitespace(source, &FormatConfig::default()),
+        "let s = \"a  \n\t  b\";\n"
+    );
 }
 
 // Printer tests
@@ -132,3 +226,417 @@ fn test_printer_newline() {
     let output = printer.finish();
     assert!(output.contains("a\nb"));
 }
+
+// Directory check tests
+
+#[test]
+fn test_check_dir_reports_unformatted_files() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let formatted = format("let x = 1;").unwrap();
+    std::fs::write(dir.path().join("good.neve"), &formatted).unwrap();
+    std::fs::write(dir.path().join("bad.neve"), "let   y =2 ;").unwrap();
+    std::fs::write(dir.path().join("notes.txt"), "not neve").unwrap();
+
+    let report = check_dir(dir.path()).unwrap();
+
+    assert_eq!(report.checked.len(), 2);
+    assert_eq!(report.unformatted, vec![dir.path().join("bad.neve")]);
+    assert!(!report.is_clean());
+    assert_ne!(report.exit_code(), 0);
+    assert_eq!(report.summary(), "1 of 2 files would be reformatted");
+
+    // Nothing is modified
+    let bad = std::fs::read_to_string(dir.path().join("bad.neve")).unwrap();
+    assert_eq!(bad, "let   y =2 ;");
+}
+
+#[test]
+fn test_check_dir_clean() {
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::create_dir(dir.path().join("sub")).unwrap();
+    let formatted = format("let x = 1;").unwrap();
+    std::fs::write(dir.path().join("sub/good.neve"), &formatted).unwrap();
+
+    let report = check_dir(dir.path()).unwrap();
+
+    assert_eq!(report.checked.len(), 1);
+    assert!(report.is_clean());
+    assert_eq!(report.exit_code(), 0);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_check_dir_skips_symlinked_directories() {
+    let dir = tempfile::TempDir::new().unwrap();
+    std::fs::create_dir(dir.path().join("sub")).unwrap();
+    let formatted = format("let x = 1;").unwrap();
+    std::fs::write(dir.path().join("sub/good.neve"), &formatted).unwrap();
+    std::os::unix::fs::symlink(dir.path(), dir.path().join("sub/loop")).unwrap();
+
+    let report = check_dir(dir.path()).unwrap();
+
+    assert_eq!(report.checked, vec![dir.path().join("sub/good.neve")]);
+}
+
+// Idempotence tests
+
+/// Sample sources covering comments, blank lines, escapes and operators.
+const IDEMPOTENCE_CORPUS: &[&str] = &[
+    "let x = 1;",
+    "let x = 1;\n\n\n\nlet y = 2;\n",
+    "-- leading comment\nlet x = 1; -- trailing comment\n\n-- between\nlet y = 2;",
+    "let x = 1;\n\n\n",
+    "fn f(x: Int) -> Int = {\n  let a = x;\n\n  -- comment\n  let b = a;\n\n\n  a + b\n};",
+    "let x = (1 + 2) * 3 - (4 - 5) / (6 % 7);",
+    "let x = 2 ^ 3 ^ 4 + (2 ^ 3) ^ 4;",
+    "let x = -(-y) + -(1) - !(!b);",
+    "let x = ((0)?)? ?? (a ?? b) ?? c;",
+    "let x = (y?).field + (1).0 + (2.5).0;",
+    "let x = (fn(a) a)(1) + (if a then b else c) * 2;",
+    "let x = if a then (if b then c) else d;",
+    "let x = xs |> (fn(v) v + 1) |> f;",
+    "let x = (a || b) && c == (d < e);",
+    "let x = [1, 2, 3] ++ ([4] ++ [5]);",
+    "let x = 1.0 + 2.50 + 0.25;",
+    "let x = \"tab\\there \\\"quoted\\\" back\\\\slash\\n\";",
+    "let x = ['\\n', '\\'', '\\\\'];",
+    "let x = `a{y}b \\{literal\\} back\\\\slash\\t`;",
+    "let x = #{ a = 1, b = #{ c = [1, 2], d = (3, 4) } };",
+    "let x = match y { 1 => (a + b) * c, _ => -(-d) };",
+    "let x = [v * 2 | v <- xs, v > 1];",
+];
+
+/// Assert that formatting `source` a second time changes nothing.
+fn assert_idempotent(source: &str) {
+    let first = format(source).unwrap_or_else(|e| panic!("{:?}\n{}", e, source));
+    let second = format(&first)
+        .unwrap_or_else(|e| panic!("formatted output does not parse: {:?}\n{}", e, first));
+    assert_eq!(
+        first, second,
+        "formatting is not idempotent for:\n{}",
+        source
+    );
+}
+
+#[test]
+fn test_format_idempotent_corpus() {
+    for source in IDEMPOTENCE_CORPUS {
+        assert!(
+            format(source).is_ok(),
+            "corpus entry does not parse: {}",
+            source
+        );
+        assert_idempotent(source);
+    }
+}
+
+#[test]
+fn test_format_keeps_precedence() {
+    assert_eq!(
+        format("let x = (1 + 2) * 3;").unwrap(),
+        "let x = (1 + 2) * 3;\n"
+    );
+    assert_eq!(
+        format("let x = 1 - (2 - 3);").unwrap(),
+        "let x = 1 - (2 - 3);\n"
+    );
+    assert_eq!(
+        format("let x = (2 ^ 3) ^ 4;").unwrap(),
+        "let x = (2 ^ 3) ^ 4;\n"
+    );
+    assert_eq!(format("let x = ((0)?)?;").unwrap(), "let x = (0?)?;\n");
+    assert_eq!(format("let x = -(-y);").unwrap(), "let x = -(-y);\n");
+    assert_eq!(format("let x = 2.0;").unwrap(), "let x = 2.0;\n");
+    assert_eq!(format("let x = a<-1;").unwrap(), "let x = a < -1;\n");
+}
+
+/// Random expressions, fully parenthesized so any nesting can be written.
+fn arb_expr() -> impl Strategy<Value = String> {
+    let leaf = prop::sample::select(vec![
+        "0",
+        "42",
+        "1.5",
+        "2.0",
+        "x",
+        "a.b",
+        "./p",
+        "true",
+        "()",
+        "#{}",
+        "[]",
+        "\"s\\n\\\"\"",
+        "'\\''",
+        "`a{x}\\{b\\}`",
+    ])
+    .prop_map(String::from);
+    leaf.prop_recursive(4, 48, 4, |inner| {
+        prop_oneof![
+            (
+                inner.clone(),
+                prop::sample::select(vec![
+                    "+", "-", "*", "/", "^", "==", "<", "&&", "||", "++", "//", "|>", "??",
+                ]),
+                inner.clone()
+            )
+                .prop_map(|(a, op, b)| format!("({} {} {})", a, op, b)),
+            (prop::sample::select(vec!["-", "!"]), inner.clone())
+                .prop_map(|(op, a)| format!("{}({})", op, a)),
+            inner.clone().prop_map(|a| format!("({})?", a)),
+            inner.clone().prop_map(|a| format!("({}).f", a)),
+            (inner.clone(), inner.clone()).prop_map(|(a, b)| format!("({})({})", a, b)),
+            (inner.clone(), inner.clone()).prop_map(|(a, b)| format!("({})[{}]", a, b)),
+            (inner.clone(), inner.clone()).prop_map(|(a, b)| format!("(fn(z) {})({})", a, b)),
+            (inner.clone(), inner.clone(), inner.clone())
+                .prop_map(|(a, b, c)| format!("(if {} then {} else {})", a, b, c)),
+            (inner.clone(), inner.clone()).prop_map(|(a, b)| format!("(let w = {}; {})", a, b)),
+            prop::collection::vec(inner.clone(), 0..4).prop_map(|v| format!("[{}]", v.join(", "))),
+            (inner.clone(), inner.clone())
+                .prop_map(|(a, b)| format!("{{ let q = {};\n\n-- c\n{} }}", a, b)),
+        ]
+    })
+}
+
+proptest! {
+    #[test]
+    fn prop_format_idempotent(
+        exprs in prop::collection::vec(arb_expr(), 1..4),
+        sep in prop::sample::select(vec!["\n", "\n\n\n", "\n-- c\n", " -- t\n"]),
+    ) {
+        let source: String = exprs
+            .iter()
+            .enumerate()
+            .map(|(i, e)| format!("let v{} = {};{}", i, e, sep))
+            .collect();
+        assert_idempotent(&source);
+    }
+}
+
+// Line width tests
+
+fn format_narrow(source: &str) -> String {
+    format_with_config(source, &FormatConfig::default().max_width(30)).unwrap()
+}
+
+#[test]
+fn test_call_that_fits_stays_inline() {
+    assert_eq!(
+        format_narrow("let x = f(a, b, c);"),
+        "let x = f(a, b, c);\n"
+    );
+}
+
+#[test]
+fn test_long_call_wraps() {
+    assert_eq!(
+        format_narrow("let x = compute(alpha, beta, gamma, delta);"),
+        "let x = compute(\n  alpha,\n  beta,\n  gamma,\n  delta\n);\n"
+    );
+}
+
+#[test]
+fn test_long_record_and_list_wrap() {
+    assert_eq!(
+        format_narrow("let r = #{ name = \"neve\", version = 12345 };"),
+        "let r = #{\n  name = \"neve\",\n  version = 12345\n};\n"
+    );
+    assert_eq!(format_narrow("let l = [1, 2, 3];"), "let l = [1, 2, 3];\n");
+    assert_eq!(
+        format_narrow("let l = [100000, 200000, 300000];"),
+        "let l = [\n  100000,\n  200000,\n  300000\n];\n"
+    );
+}
+
+#[test]
+fn test_long_signature_wraps() {
+    assert_eq!(
+        format_narrow("fn add(first: Int, second: Int) -> Int = first + second;"),
+        "fn add(\n  first: Int,\n  second: Int\n) -> Int = first + second;\n"
+    );
+    assert_eq!(
+        format_narrow("fn id(x: Int) -> Int = x;"),
+        "fn id(x: Int) -> Int = x;\n"
+    );
+}
+
+#[test]
+fn test_nested_groups_break_outermost_first() {
+    assert_eq!(
+        format_narrow("let x = outer(inner(a, b), [1, 2], other);"),
+        "let x = outer(\n  inner(a, b),\n  [1, 2],\n  other\n);\n"
+    );
+}
+
+// Range formatting tests
+
+/// Format the part of `source` between the first two `|` markers and splice
+/// the edit back in.
+fn format_marked(source: &str) -> String {
+    let start = source.find('|').unwrap();
+    let end = source[start + 1..].find('|').unwrap() + start;
+    let source = source.replace('|', "");
+    let edit = format_range(
+        &source,
+        BytePos(start as u32),
+        BytePos(end as u32),
+        &FormatConfig::default(),
+    )
+    .unwrap();
+    let mut result = source.clone();
+    result.replace_range(
+        edit.span.start.0 as usize..edit.span.end.0 as usize,
+        &edit.text,
+    );
+    result
+}
+
+#[test]
+fn test_format_range_items() {
+    assert_eq!(
+        format_marked("let a   =1;\nlet |b   =2;\nlet c=|3;\nlet d   =4;\n"),
+        "let a   =1;\nlet b = 2;\n\nlet c = 3;\nlet d   =4;\n"
+    );
+}
+
+#[test]
+fn test_format_range_statements_keep_indentation() {
+    let source =
+        "fn f() -> Int = {\n    let x   =  1;\n    |let y=x+1;|\n    let z   =  2;\n    z\n};\n";
+    assert_eq!(
+        format_marked(source),
+        "fn f() -> Int = {\n    let x   =  1;\n    let y = x + 1;\n    let z   =  2;\n    z\n};\n"
+    );
+}
+
+#[test]
+fn test_format_range_nested_block_reindents_only_its_own_lines() {
+    let source = "let v = {\n  let a = {\n      let b=|#{x=1,y=2}|;\n      b\n  };\n  a\n};\n";
+    assert_eq!(
+        format_marked(source),
+        "let v = {\n  let a = {\n      let b = #{ x = 1, y = 2 };\n      b\n  };\n  a\n};\n"
+    );
+}
+
+#[test]
+fn test_format_range_outside_code_changes_nothing() {
+    let source = "let a=1;\n\n\nlet b=2;\n";
+    let edit = format_range(source, BytePos(9), BytePos(10), &FormatConfig::default()).unwrap();
+    assert_eq!(edit.text, &source[9..10]);
+}
+
+#[test]
+fn test_format_range_rejects_invalid_source() {
+    assert!(
+        format_range(
+            "let a = ;",
+            BytePos(0),
+            BytePos(3),
+            &FormatConfig::default()
+        )
+        .is_err()
+    );
+}
+
+// Import sorting tests
+
+const UNSORTED_IMPORTS: &str = "\
+import self.local;
+let x = 1;
+import zeta;
+import std.list (map);
+import crate.util (b);
+import alpha.beta as ab;
+import super.parent;
+import std.io;
+import crate.util (a, b);
+fn f() -> Int = x;
+";
+
+#[test]
+fn test_sort_imports() {
+    let config = FormatConfig::default().sort_imports(true);
+    assert_eq!(
+        format_with_config(UNSORTED_IMPORTS, &config).unwrap(),
+        "\
+import std.io;
+import std.list (map);
+import alpha.beta as ab;
+import zeta;
+import crate.util (a, b);
+import super.parent;
+import self.local;
+
+let x = 1;
+
+fn f() -> Int = x;
+"
+    );
+}
+
+#[test]
+fn test_group_imports() {
+    let config = FormatConfig::default()
+        .sort_imports(true)
+        .group_imports(true);
+    assert_eq!(
+        format_with_config(UNSORTED_IMPORTS, &config).unwrap(),
+        "\
+import std.io;
+import std.list (map);
+
+import alpha.beta as ab;
+import zeta;
+
+import crate.util (a, b);
+
+import super.parent;
+
+import self.local;
+
+let x = 1;
+
+fn f() -> Int = x;
+"
+    );
+}
+
+#[test]
+fn test_imports_left_in_place_without_sorting() {
+    // Sorting is opt-in
+    let formatted = format("let x = 1;\nimport b;\nimport pub_a;").unwrap();
+    assert_eq!(formatted, "let x = 1;\n\nimport b;\n\nimport pub_a;\n");
+}
+
+#[test]
+fn test_sort_imports_keeps_comments() {
+    let config = FormatConfig::default().sort_imports(true);
+    let source = "\
+-- zeta is vendored
+import zeta; -- pinned
+let x = 1;
+-- utilities
+-- (two lines)
+import alpha (b);
+import alpha (a); -- also a
+";
+    assert_eq!(
+        format_with_config(source, &config).unwrap(),
+        "\
+-- utilities
+-- (two lines)
+import alpha (a, b); -- also a
+-- zeta is vendored
+import zeta; -- pinned
+
+let x = 1;
+"
+    );
+}
+
+#[test]
+fn test_import_prefix_and_visibility_kept() {
+    let config = FormatConfig::default().sort_imports(true);
+    assert_eq!(
+        format_with_config("pub import self.a (y, x);", &config).unwrap(),
+        "pub import self.a (x, y);\n"
+    );
+}
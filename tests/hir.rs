@@ -1,6 +1,6 @@
 //! Integration tests for neve-hir crate.
 
-use neve_hir::{BinOp, ExprKind, ItemKind, lower};
+use neve_hir::{BinOp, ExprKind, ItemKind, PatternKind, Resolver, lower};
 use neve_parser::parse;
 
 #[test]
@@ -87,7 +87,7 @@ fn test_lower_match_expr() {
 
     match &hir.items[0].kind {
         ItemKind::Fn(fn_def) => match &fn_def.body.kind {
-            ExprKind::Match(_, arms) => {
+            ExprKind::Match(_, arms, _) => {
                 assert_eq!(arms.len(), 2);
             }
             other => panic!("expected Match, got {:?}", other),
@@ -155,3 +155,181 @@ fn test_lower_record() {
         _ => panic!("expected function"),
     }
 }
+
+#[test]
+fn test_resolve_unknown_variable_suggests_similar_name() {
+    let source = "fn total(count) = cont + 1;";
+    let (ast, diagnostics) = parse(source);
+    assert!(diagnostics.is_empty(), "parse errors: {:?}", diagnostics);
+
+    let mut resolver = Resolver::new();
+    resolver.resolve(&ast);
+
+    let unresolved = resolver.unresolved_names();
+    assert_eq!(unresolved.len(), 1);
+    assert_eq!(unresolved[0].name, "cont");
+    assert_eq!(unresolved[0].suggestion.as_deref(), Some("count"));
+
+    let diag = unresolved[0].to_diagnostic();
+    assert!(
+        diag.help
+            .iter()
+            .any(|h| h.contains("did you mean `count`?"))
+    );
+}
+
+#[test]
+fn test_resolve_unknown_variable_without_close_match() {
+    let source = "let count = 1; let x = zzqqxxww;";
+    let (ast, diagnostics) = parse(source);
+    assert!(diagnostics.is_empty(), "parse errors: {:?}", diagnostics);
+
+    let mut resolver = Resolver::new();
+    resolver.resolve(&ast);
+
+    let unresolved = resolver.unresolved_names();
+    assert_eq!(unresolved.len(), 1);
+    assert_eq!(unresolved[0].name, "zzqqxxww");
+    assert_eq!(unresolved[0].suggestion, None);
+}
+
+/// Messages of the unused-name warnings for `source`, and where each starts.
+fn unused_warnings(source: &str, warn_unused: bool) -> Vec<(String, usize)> {
+    let (ast, diagnostics) = parse(source);
+    assert!(diagnostics.is_empty(), "parse errors: {:?}", diagnostics);
+
+    let mut resolver = Resolver::new();
+    resolver.set_warn_unused(warn_unused);
+    resolver.resolve(&ast);
+    resolver
+        .diagnostics()
+        .iter()
+        .map(|d| (d.message.clone(), d.span.start.0 as usize))
+        .collect()
+}
+
+#[test]
+fn test_resolve_warns_unused_imports_and_bindings() {
+    let source = "import std.list (map, filter);\nimport std.option;\nimport std.string as str;\n\
+                  fn helper(x) = x;\nlet unused = 1;\nlet used = map(str.len, [1]);\nlet result = used;\n";
+    let warnings = unused_warnings(source, true);
+    assert_eq!(
+        warnings,
+        vec![
+            (
+                "unused import: `filter`".to_string(),
+                source.find("filter").unwrap()
+            ),
+            (
+                "unused import: `option`".to_string(),
+                source.find("option").unwrap()
+            ),
+            (
+                "unused function: `helper`".to_string(),
+                source.find("helper").unwrap()
+            ),
+            (
+                "unused binding: `unused`".to_string(),
+                source.find("unused").unwrap()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_resolve_unused_warnings_skip_public_and_underscore() {
+    let source =
+        "pub import std.list (map);\npub fn api(x) = x;\nlet _scratch = 1;\nlet result = 1;\n";
+    assert!(unused_warnings(source, true).is_empty());
+}
+
+#[test]
+fn test_resolve_unused_warnings_can_be_disabled() {
+    let source = "import std.list (filter);\nlet unused = 1;\nlet result = 2;\n";
+    assert_eq!(unused_warnings(source, true).len(), 2);
+    assert!(unused_warnings(source, false).is_empty());
+
+    let (ast, _) = parse(source);
+    let mut resolver = Resolver::new();
+    resolver.resolve(&ast);
+    assert!(resolver.diagnostics().is_empty(), "off by default");
+}
+
+#[test]
+fn test_resolve_unused_ignores_self_reference_and_method_names() {
+    let source = "fn count(n) = if n == 0 then 0 else count(n - 1);
+                  fn len(x) = 0;
+let result = [1].len();
+";
+    let warnings: Vec<_> = unused_warnings(source, true)
+        .into_iter()
+        .map(|(message, _)| message)
+        .collect();
+    assert_eq!(
+        warnings,
+        vec![
+            "unused function: `count`".to_string(),
+            "unused function: `len`".to_string(),
+        ]
+    );
+}
+
+#[test]
+fn test_lower_match_patterns() {
+    let source = "
+        enum Color { Red, Green };
+        fn f(c) = match c { Red -> 0, Green -> 1 };
+        fn g(o) = match o { Some(x) | Ok(x) -> x, all @ None -> 0, [a, ..rest] -> a };
+    ";
+    let (ast, diagnostics) = parse(source);
+    assert!(diagnostics.is_empty(), "parse errors: {:?}", diagnostics);
+
+    let hir = lower(&ast);
+    let arms = |name: &str| {
+        let body = hir
+            .items
+            .iter()
+            .find_map(|item| match &item.kind {
+                ItemKind::Fn(def) if def.name == name => Some(&def.body),
+                _ => None,
+            })
+            .expect("function not found");
+        match &body.kind {
+            ExprKind::Match(_, arms, _) => arms.clone(),
+            _ => panic!("expected match"),
+        }
+    };
+
+    // Bare variant names match the variant instead of binding a variable
+    let color_arms = arms("f");
+    assert!(matches!(
+        &color_arms[0].pattern.kind,
+        PatternKind::Constructor(_, name, args) if name == "Red" && args.is_empty()
+    ));
+
+    let arms = arms("g");
+    let PatternKind::Or(alternatives) = &arms[0].pattern.kind else {
+        panic!("expected or-pattern");
+    };
+    let bound: Vec<_> = alternatives
+        .iter()
+        .map(|alt| match &alt.kind {
+            PatternKind::Constructor(_, _, args) => match &args[0].kind {
+                PatternKind::Var(id, _) => *id,
+                _ => panic!("expected variable"),
+            },
+            _ => panic!("expected constructor"),
+        })
+        .collect();
+    assert_eq!(bound[0], bound[1], "alternatives bind the same local");
+
+    assert!(matches!(
+        &arms[1].pattern.kind,
+        PatternKind::Binding(_, name, inner)
+            if name == "all" && matches!(&inner.kind, PatternKind::Constructor(_, n, _) if n == "None")
+    ));
+    assert!(matches!(
+        &arms[2].pattern.kind,
+        PatternKind::ListRest(init, Some(_), tail) if init.len() == 1 && tail.is_empty()
+    ));
+}
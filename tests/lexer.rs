@@ -1,6 +1,6 @@
 //! Integration tests for neve-lexer crate.
 
-use neve_lexer::{Lexer, TokenKind};
+use neve_lexer::{Lexer, TokenKind, TriviaKind};
 
 fn lex(source: &str) -> Vec<TokenKind> {
     let lexer = Lexer::new(source);
@@ -113,6 +113,65 @@ fn test_comments() {
     );
 }
 
+#[test]
+fn test_comment_trivia() {
+    let source = "1 -- one\n-- two\n2";
+    let (tokens, _) = Lexer::new(source).tokenize();
+
+    let trailing: Vec<_> = tokens[0].trailing_trivia().iter().map(|t| t.kind).collect();
+    assert_eq!(
+        trailing,
+        vec![TriviaKind::Whitespace, TriviaKind::LineComment]
+    );
+    assert_eq!(
+        &source[tokens[0].trailing_trivia()[1].span.range()],
+        "-- one"
+    );
+
+    assert_eq!(tokens[1].kind, TokenKind::Int(2));
+    let comments: Vec<_> = tokens[1]
+        .leading_trivia()
+        .iter()
+        .filter(|t| t.is_comment())
+        .collect();
+    assert_eq!(comments.len(), 1);
+    assert_eq!(&source[comments[0].span.range()], "-- two");
+}
+
+#[test]
+fn test_bom_is_skipped() {
+    let source = "\u{FEFF}let x = 1;";
+    let (tokens, errors) = Lexer::new(source).tokenize();
+    assert!(errors.is_empty());
+    assert_eq!(tokens[0].kind, TokenKind::Let);
+    // Spans stay byte offsets into the original source
+    assert_eq!(tokens[0].span.range(), 3..6);
+    assert_eq!(&source[tokens[1].span.range()], "x");
+    assert!(tokens[0].leading_trivia().is_empty());
+}
+
+#[test]
+fn test_crlf_line_endings() {
+    let source = "let s = \"a\r\nb\"; -- note\r\nlet t = `c\r\nd`;\r\n";
+    let (tokens, errors) = Lexer::new(source).tokenize();
+    assert!(errors.is_empty());
+
+    let kinds: Vec<_> = tokens.iter().map(|t| t.kind.clone()).collect();
+    assert!(kinds.contains(&TokenKind::String("a\nb".to_string())));
+    assert!(kinds.contains(&TokenKind::InterpolatedPart("c\nd".to_string())));
+
+    // The line comment stops before the \r\n
+    let semi = &tokens[4];
+    assert_eq!(semi.kind, TokenKind::Semicolon);
+    let comment = &semi.trailing_trivia()[1];
+    assert_eq!(comment.kind, TriviaKind::LineComment);
+    assert_eq!(&source[comment.span.range()], "-- note");
+
+    let second_let = &tokens[5];
+    assert_eq!(second_let.kind, TokenKind::Let);
+    assert_eq!(&source[second_let.span.range()], "let");
+}
+
 #[test]
 fn test_identifiers() {
     assert_eq!(
@@ -370,6 +429,45 @@ fn test_logical_operators() {
     assert!(tokens.contains(&TokenKind::Bang));
 }
 
+#[test]
+fn test_operators_sharing_prefixes() {
+    use TokenKind::*;
+    let ident = |name: &str| Ident(name.to_string());
+
+    let cases: Vec<(&str, Vec<TokenKind>)> = vec![
+        ("a|b", vec![ident("a"), Pipe, ident("b")]),
+        ("a||b", vec![ident("a"), OrOr, ident("b")]),
+        ("a|>b", vec![ident("a"), PipeGt, ident("b")]),
+        ("a| |b", vec![ident("a"), Pipe, Pipe, ident("b")]),
+        ("a/b", vec![ident("a"), Slash, ident("b")]),
+        ("a/2", vec![ident("a"), Slash, Int(2)]),
+        (
+            "f(x)/y",
+            vec![ident("f"), LParen, ident("x"), RParen, Slash, ident("y")],
+        ),
+        ("a//b", vec![ident("a"), SlashSlash, ident("b")]),
+        ("a?", vec![ident("a"), Question]),
+        ("a??b", vec![ident("a"), QuestionQuestion, ident("b")]),
+        ("a?.b", vec![ident("a"), QuestionDot, ident("b")]),
+        ("a? ?b", vec![ident("a"), Question, Question, ident("b")]),
+        ("a<b", vec![ident("a"), Lt, ident("b")]),
+        ("a<=b", vec![ident("a"), LtEq, ident("b")]),
+        ("a<-b", vec![ident("a"), LArrow, ident("b")]),
+        ("a < -b", vec![ident("a"), Lt, Minus, ident("b")]),
+        ("a-b", vec![ident("a"), Minus, ident("b")]),
+        ("a->b", vec![ident("a"), Arrow, ident("b")]),
+        ("a- >b", vec![ident("a"), Minus, Gt, ident("b")]),
+        ("a..b", vec![ident("a"), DotDot, ident("b")]),
+        ("a..=b", vec![ident("a"), DotDotEq, ident("b")]),
+        ("a.. =b", vec![ident("a"), DotDot, Eq, ident("b")]),
+    ];
+
+    for (source, mut expected) in cases {
+        expected.push(Eof);
+        assert_eq!(lex(source), expected, "lexing {:?}", source);
+    }
+}
+
 #[test]
 fn test_consecutive_operators() {
     let tokens = lex("++--");
@@ -1037,9 +1135,8 @@ fn test_record_with_shorthand() {
 fn test_list_comprehension_tokens() {
     let tokens = lex("[x * 2 | x <- xs, x > 0]");
     assert!(tokens.contains(&TokenKind::Pipe));
-    // <- is tokenized as Lt + Minus
-    assert!(tokens.contains(&TokenKind::Lt));
-    assert!(tokens.contains(&TokenKind::Minus));
+    assert!(tokens.contains(&TokenKind::LArrow));
+    assert!(!tokens.contains(&TokenKind::Lt));
 }
 
 #[test]
@@ -1,8 +1,12 @@
 //! Integration tests for neve-lsp crate.
 
 use neve_lexer::Lexer;
-use neve_lsp::{Document, SymbolIndex, generate_semantic_tokens};
+use neve_lsp::{
+    Document, InlayHintConfig, OutlineSymbol, StdlibIndex, SymbolIndex, SymbolKind, TypeHintKind,
+    document_outline, generate_semantic_tokens, imports_from, is_valid_identifier, member_receiver,
+};
 use neve_parser::parse;
+use std::path::Path;
 
 // Document tests
 
@@ -123,3 +127,377 @@ fn test_nested_references() {
     let x_refs = index.get_references("x");
     assert!(x_refs.len() >= 2);
 }
+
+/// The source text of every occurrence of the symbol at the `n`th `needle`.
+fn occurrences(source: &str, needle: &str, n: usize) -> Vec<(usize, usize)> {
+    let (ast, _) = parse(source);
+    let index = SymbolIndex::from_ast(&ast);
+    let offset = source.match_indices(needle).nth(n).unwrap().0;
+    let mut spans: Vec<(usize, usize)> = index
+        .occurrences_at(offset)
+        .unwrap_or_default()
+        .iter()
+        .map(|r| (r.span.start.into(), r.span.end.into()))
+        .collect();
+    spans.sort();
+    spans
+}
+
+#[test]
+fn test_rename_binding_and_use_agree() {
+    let source = "fn f(x) = x + 1; let y = f(2); let z = f(y);";
+    let from_def = occurrences(source, "f(", 0);
+    let from_use = occurrences(source, "f(", 1);
+    assert_eq!(from_def, from_use);
+    assert_eq!(from_def.len(), 3);
+    for (start, end) in from_def {
+        assert_eq!(&source[start..end], "f");
+    }
+}
+
+#[test]
+fn test_rename_respects_shadowing() {
+    let source = "fn f(x) = { let y = x; let x = y * 2; x };";
+    // The parameter and its one use
+    let param = occurrences(source, "x", 0);
+    assert_eq!(param.len(), 2);
+    assert_eq!(param[1].0, source.find("= x;").unwrap() + 2);
+    // The inner binding and its use as the block's result
+    let inner = occurrences(source, "x", 3);
+    assert_eq!(inner, occurrences(source, "x", 2));
+    assert_eq!(inner.len(), 2);
+    assert!(inner.iter().all(|span| !param.contains(span)));
+}
+
+#[test]
+fn test_rename_variant_in_pattern() {
+    let source =
+        "enum Color { Red, Green };\nfn f(c) = match c { Red -> 1, other -> 2 };\nlet r = Red;";
+    let spans = occurrences(source, "Red", 1);
+    assert_eq!(spans, occurrences(source, "Red", 0));
+    assert_eq!(spans.len(), 3);
+    // A name that is not a variant still binds a local
+    assert_eq!(occurrences(source, "other", 0).len(), 1);
+}
+
+#[test]
+fn test_rename_undefined_symbol() {
+    let source = "let x = map(y);";
+    let (ast, _) = parse(source);
+    let index = SymbolIndex::from_ast(&ast);
+    assert!(index.occurrences_at(source.find("map").unwrap()).is_none());
+    assert_eq!(index.unresolved_references("map").len(), 1);
+}
+
+#[test]
+fn test_rename_methods_by_name() {
+    let source = "trait Show { fn show(self) -> String; };\nimpl Show for Int { fn show(self) -> String = \"i\"; };\nlet s = 1.show();";
+    let spans = occurrences(source, "show()", 0);
+    assert_eq!(spans.len(), 3);
+}
+
+#[test]
+fn test_rename_members_by_receiver_type() {
+    let source = "struct Point { x: Int };\nstruct Size { x: Int };\nimpl Point { fn get(self) -> Int = self.x; };\nfn f(p: Point) = p.x;\nfn g(s: Size) = s.x;";
+    let point = occurrences(source, "x", 0);
+    assert_eq!(point.len(), 3);
+    let at = |needle: &str| source.find(needle).unwrap() + needle.len() - 1;
+    assert!(point.contains(&(at("self.x"), at("self.x") + 1)));
+    assert!(point.contains(&(at("p.x"), at("p.x") + 1)));
+    let size = occurrences(source, "x", 1);
+    assert_eq!(size.len(), 2);
+    assert!(size.iter().all(|span| !point.contains(span)));
+
+    let (ast, _) = parse(source);
+    let index = SymbolIndex::from_ast(&ast);
+    let definition = index
+        .reference_at(source.find("x").unwrap())
+        .unwrap()
+        .definition;
+    assert!(!index.is_ambiguous_member(definition.unwrap()));
+}
+
+#[test]
+fn test_ambiguous_member_use() {
+    let source = "struct Point { x: Int };\nstruct Size { x: Int };\nfn f(p) = p.x;";
+    let (ast, _) = parse(source);
+    let index = SymbolIndex::from_ast(&ast);
+    let use_site = source.rfind("x").unwrap();
+    assert!(index.reference_at(use_site).unwrap().definition.is_none());
+    let definition = index
+        .reference_at(source.find("x").unwrap())
+        .unwrap()
+        .definition;
+    assert!(index.is_ambiguous_member(definition.unwrap()));
+}
+
+#[test]
+fn test_imports_from_module() {
+    let module = Path::new("/work/src/utils/helpers.neve");
+    let imports = |source: &str, name: &str| imports_from(&parse(source).0, module, name);
+    assert!(imports("import utils.helpers (greet);", "greet"));
+    assert!(!imports("import utils.helpers (other);", "greet"));
+    assert!(imports("import utils.helpers (*);", "greet"));
+    assert!(imports("import utils.helpers;", "greet"));
+    assert!(!imports("import other.helpers (greet);", "greet"));
+    assert!(!imports("let greet = 1;", "greet"));
+}
+
+#[test]
+fn test_is_valid_identifier() {
+    assert!(is_valid_identifier("total"));
+    assert!(is_valid_identifier("_tmp2"));
+    assert!(!is_valid_identifier(""));
+    assert!(!is_valid_identifier("let"));
+    assert!(!is_valid_identifier("fn"));
+    assert!(!is_valid_identifier("a b"));
+    assert!(!is_valid_identifier("+"));
+    assert!(!is_valid_identifier("1x"));
+}
+
+#[test]
+fn test_references_of_shadowed_local() {
+    let source = "let x = 1;\nfn f(y) = { let x = y; x + x };\nlet z = x;";
+    let (ast, _) = parse(source);
+    let index = SymbolIndex::from_ast(&ast);
+    let spans = |offset: usize, include_declaration: bool| {
+        let mut spans: Vec<usize> = index
+            .find_references_at(offset, include_declaration)
+            .iter()
+            .map(|r| r.span.start.into())
+            .collect();
+        spans.sort();
+        spans
+    };
+
+    let global = source.find("x").unwrap();
+    let last_use = source.rfind("x").unwrap();
+    assert_eq!(spans(global, true), vec![global, last_use]);
+    assert_eq!(spans(last_use, false), vec![last_use]);
+
+    let local = source.find("let x = y").unwrap() + 4;
+    let uses = spans(local, false);
+    assert_eq!(uses.len(), 2);
+    let body = source.find('{').unwrap()..source.find('}').unwrap();
+    assert!(uses.iter().all(|start| body.contains(start)));
+    assert_eq!(spans(uses[0], true), spans(local, true));
+    assert_eq!(spans(local, true).len(), 3);
+}
+
+#[test]
+fn test_references_of_undefined_name() {
+    let source = "let a = len([1]); let b = len([]);";
+    let (ast, _) = parse(source);
+    let index = SymbolIndex::from_ast(&ast);
+    assert_eq!(
+        index
+            .find_references_at(source.find("len").unwrap(), false)
+            .len(),
+        2
+    );
+}
+
+#[test]
+fn test_definition_of_shadowed_local() {
+    let source = "fn f(x) = { let x = x + 1; x };";
+    let (ast, _) = parse(source);
+    let index = SymbolIndex::from_ast(&ast);
+
+    let result = source.rfind("x").unwrap();
+    let inner = source.find("let x").unwrap() + 4;
+    let param = source.find("x").unwrap();
+    assert_eq!(
+        usize::from(index.find_definition_at(result).unwrap().def_span.start),
+        inner
+    );
+    // The right-hand side of the inner `let` still sees the parameter
+    assert_eq!(
+        usize::from(index.find_definition_at(inner + 4).unwrap().def_span.start),
+        param
+    );
+}
+
+fn outline(source: &str) -> Vec<OutlineSymbol> {
+    let (ast, diagnostics) = parse(source);
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    let index = SymbolIndex::from_ast(&ast);
+    document_outline(&ast, &index, source)
+}
+
+fn names(symbols: &[OutlineSymbol]) -> Vec<(&str, SymbolKind)> {
+    symbols.iter().map(|s| (s.name.as_str(), s.kind)).collect()
+}
+
+#[test]
+fn test_document_outline_nesting() {
+    let source = r#"
+struct Point { x: Int, y: Int };
+impl Point {
+    fn norm(self) -> Int = self.x + self.y;
+};
+impl Show for Int {
+    fn show(self) -> String = "int";
+};
+fn origin() -> Point = Point(#{ x = 0, y = 0 });
+fn main() = origin().norm();
+let (a, b) = (1, 2);
+"#;
+    let symbols = outline(source);
+    assert_eq!(
+        names(&symbols),
+        vec![
+            ("Point", SymbolKind::Struct),
+            ("impl Show for Int", SymbolKind::Impl),
+            ("origin", SymbolKind::Function),
+            ("main", SymbolKind::Function),
+            ("a", SymbolKind::Variable),
+            ("b", SymbolKind::Variable),
+        ]
+    );
+
+    let point = &symbols[0];
+    assert_eq!(
+        names(&point.children),
+        vec![
+            ("x", SymbolKind::Field),
+            ("y", SymbolKind::Field),
+            ("norm", SymbolKind::Method),
+        ]
+    );
+    assert_eq!(&source[point.selection_span.range()], "Point");
+    assert!(source[point.span.range()].starts_with("struct Point"));
+    assert_eq!(
+        names(&symbols[1].children),
+        vec![("show", SymbolKind::Method)]
+    );
+    assert!(symbols[2].children.is_empty());
+}
+
+#[test]
+fn test_document_outline_enum_and_trait() {
+    let source =
+        "enum Shape { Circle(Float), Square(Float) };\ntrait Area { fn area(self) -> Float; };";
+    let symbols = outline(source);
+    assert_eq!(
+        names(&symbols[0].children),
+        vec![
+            ("Circle", SymbolKind::Variant),
+            ("Square", SymbolKind::Variant)
+        ]
+    );
+    assert_eq!(
+        names(&symbols[1].children),
+        vec![("area", SymbolKind::Method)]
+    );
+    assert_eq!(
+        &source[symbols[1].children[0].selection_span.range()],
+        "area"
+    );
+}
+
+/// The type hints of a document as `(text before the hint, type)`.
+fn hints(source: &str) -> Vec<(String, String, TypeHintKind)> {
+    let doc = Document::new("file:///test.neve".to_string(), source.to_string());
+    doc.type_hints
+        .iter()
+        .map(|hint| {
+            let before = source[..hint.offset]
+                .rsplit(|c: char| !c.is_alphanumeric() && c != '_')
+                .next()
+                .unwrap()
+                .to_string();
+            (before, hint.ty.clone(), hint.kind)
+        })
+        .collect()
+}
+
+#[test]
+fn test_inlay_hints_for_inferred_types() {
+    let source = "let n = 1;\nfn twice(x) = {\n  let y = x * 2;\n  let s: String = \"a\";\n  y\n};\nlet f = fn(b) if b then 1.0 else 2.0;";
+    assert_eq!(
+        hints(source),
+        vec![
+            ("n".to_string(), "Int".to_string(), TypeHintKind::Binding),
+            ("x".to_string(), "Int".to_string(), TypeHintKind::Parameter),
+            ("y".to_string(), "Int".to_string(), TypeHintKind::Binding),
+            (
+                "f".to_string(),
+                "(Bool) -> Float".to_string(),
+                TypeHintKind::Binding
+            ),
+            ("b".to_string(), "Bool".to_string(), TypeHintKind::Parameter),
+        ]
+    );
+}
+
+#[test]
+fn test_inlay_hints_skip_annotated_and_generic() {
+    // `id`'s parameter has no concrete type and `m` is annotated
+    let source = "fn id(a) = a;\nlet m: Int = 2;\nfn g(k: Int) = k;";
+    assert!(hints(source).is_empty());
+}
+
+#[test]
+fn test_inlay_hint_config() {
+    let config = InlayHintConfig::from_initialization_options(Some(&serde_json::json!({
+        "inlayHints": { "parameterTypes": false }
+    })));
+    assert!(config.enabled(TypeHintKind::Binding));
+    assert!(!config.enabled(TypeHintKind::Parameter));
+    assert_eq!(
+        InlayHintConfig::from_initialization_options(None),
+        InlayHintConfig::default()
+    );
+}
+
+#[test]
+fn test_stdlib_index_namespaces() {
+    let stdlib = StdlibIndex::load();
+    let namespaces: Vec<&str> = stdlib.namespaces().collect();
+    for namespace in ["Map", "Set", "list", "math", "string"] {
+        assert!(namespaces.contains(&namespace), "missing {}", namespace);
+    }
+
+    let list: Vec<&str> = stdlib
+        .members("list")
+        .iter()
+        .map(|i| i.name.as_str())
+        .collect();
+    for name in ["head", "tail", "map", "filter"] {
+        assert!(list.contains(&name), "missing list.{}", name);
+    }
+    assert!(stdlib.members("nothing").is_empty());
+}
+
+#[test]
+fn test_stdlib_item_snippets() {
+    let stdlib = StdlibIndex::load();
+    let find = |namespace: &str, name: &str| {
+        stdlib
+            .members(namespace)
+            .iter()
+            .find(|item| item.name == name)
+            .cloned()
+            .unwrap()
+    };
+
+    let head = find("list", "head");
+    assert!(head.is_function);
+    assert_eq!(head.arity, Some(1));
+    assert_eq!(head.snippet("head"), "head(${1:arg1})");
+    assert_eq!(head.signature(), "list.head(arg1)");
+
+    let pi = find("math", "pi");
+    assert!(!pi.is_function);
+    assert_eq!(pi.snippet("math.pi"), "math.pi");
+}
+
+#[test]
+fn test_member_receiver() {
+    assert_eq!(member_receiver("let x = list."), Some("list"));
+    assert_eq!(member_receiver("let x = list.he"), Some("list"));
+    assert_eq!(member_receiver("  Map.ins"), Some("Map"));
+    assert_eq!(member_receiver("let x = list"), None);
+    assert_eq!(member_receiver("(1)."), None);
+    assert_eq!(member_receiver("."), None);
+}
@@ -3,9 +3,11 @@
 // Tests the module system including circular dependency detection,
 // path resolution, and import chains.
 
+use neve_eval::{AstEnv, AstEvaluator, BuiltinSet, EvalError, Value};
 use neve_hir::{ModuleLoadError, ModuleLoader};
 use std::fs;
 use std::path::Path;
+use std::rc::Rc;
 use tempfile::TempDir;
 
 /// Helper to create a test module file
@@ -374,3 +376,118 @@ fn test_diamond_dependency() {
     // Diamond dependencies are fine, not circular
     assert!(result.is_ok());
 }
+
+#[test]
+fn test_trace_imports() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    create_test_module(root, &["math"], "pub fn add(x, y) = x + y;");
+
+    let (file, diagnostics) = neve_parser::parse("import math (add);\nimport missing;\n");
+    assert!(diagnostics.is_empty());
+
+    let mut evaluator = AstEvaluator::new()
+        .with_base_path(root.to_path_buf())
+        .with_import_trace();
+    let err = evaluator.eval_file(&file).unwrap_err();
+    assert!(err.to_string().contains("cannot find module 'missing'"));
+
+    let traces = evaluator.import_trace();
+    assert_eq!(traces.len(), 2);
+
+    // Successful resolution stops at the first candidate that exists
+    let found = &traces[0];
+    assert_eq!(found.resolved(), Some(root.join("math.neve").as_path()));
+    assert_eq!(
+        found.to_string(),
+        format!(
+            "import math\n  prefix: absolute, from the root directory\n  module: math\n  tried {}: found\n  resolved: {}\n",
+            root.join("math.neve").display(),
+            root.join("math.neve").display(),
+        )
+    );
+
+    // Failed resolution lists every candidate, including the fallback ones
+    let missing = &traces[1];
+    assert_eq!(missing.resolved(), None);
+    let text = missing.to_string();
+    assert!(text.starts_with(
+        "import missing\n  prefix: absolute, from the root directory\n  module: missing\n"
+    ));
+    for candidate in [
+        root.join("missing.neve"),
+        root.join("missing").join("mod.neve"),
+        root.join("src").join("missing.neve"),
+    ] {
+        assert!(
+            text.contains(&format!("  tried {}: not found\n", candidate.display())),
+            "{text}"
+        );
+    }
+    assert!(text.ends_with("  unresolved\n"));
+}
+
+#[test]
+fn test_conflicting_imports() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    create_test_module(root, &["a"], "pub fn foo() = 1;");
+    create_test_module(root, &["b"], "pub fn foo() = 2;\npub fn bar() = 3;");
+
+    let source = "import a (foo);\nimport b (*);\n";
+    let (file, diagnostics) = neve_parser::parse(source);
+    assert!(diagnostics.is_empty());
+
+    let mut evaluator = AstEvaluator::new().with_base_path(root.to_path_buf());
+    let err = evaluator.eval_file(&file).unwrap_err();
+    let EvalError::ImportConflict(diagnostic) = err else {
+        panic!("expected an import conflict, got {err:?}");
+    };
+    assert!(diagnostic.message.contains("`foo`"));
+    assert!(diagnostic.help.iter().any(|h| h.contains("as")));
+
+    let spans: Vec<&str> = diagnostic
+        .labels
+        .iter()
+        .map(|label| &source[label.span.range()])
+        .collect();
+    assert_eq!(spans, ["import a (foo);", "import b (*);"]);
+}
+
+#[test]
+fn test_repeated_identical_import_is_allowed() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    create_test_module(root, &["a"], "pub fn foo() = 1;");
+
+    let (file, diagnostics) =
+        neve_parser::parse("import a (foo);\nimport a (*);\nimport a;\nimport a;\nlet x = foo();");
+    assert!(diagnostics.is_empty());
+
+    let mut evaluator = AstEvaluator::new().with_base_path(root.to_path_buf());
+    assert!(matches!(evaluator.eval_file(&file), Ok(Value::Int(1))));
+}
+
+#[test]
+fn test_pure_evaluator_restricts_imported_functions() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    create_test_module(root, &["util"], "pub fn show(x) = print(x);");
+
+    let (file, diagnostics) = neve_parser::parse("import util (show);\nlet f = show;");
+    assert!(diagnostics.is_empty());
+    let show = AstEvaluator::new()
+        .with_base_path(root.to_path_buf())
+        .eval_file(&file)
+        .unwrap();
+
+    // The imported function still can't print from a pure evaluator
+    let mut env = AstEnv::with_builtin_set(BuiltinSet::Pure);
+    env.define("show".to_string(), show);
+    let (file, _) = neve_parser::parse("let r = show(\"/etc/hostname\");");
+    let err = AstEvaluator::with_env(Rc::new(env))
+        .restricted_to(BuiltinSet::Pure)
+        .eval_file(&file)
+        .unwrap_err();
+    assert!(err.to_string().contains("not available"), "{err}");
+}
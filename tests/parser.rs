@@ -1,6 +1,9 @@
 //! Integration tests for neve-parser crate.
 
-use neve_parser::parse;
+use neve_diagnostic::{ErrorCode, apply_suggestions};
+use neve_parser::{has_unclosed_delimiters, parse, parse_expr};
+use neve_syntax::{ExprKind, ItemKind};
+use proptest::prelude::*;
 
 // ============================================================================
 // Basic Parsing Tests
@@ -20,6 +23,43 @@ fn test_parse_fn() {
     assert_eq!(file.items.len(), 1);
 }
 
+/// Get the argument count of the call bound by `let x = ...;`.
+fn call_arg_count(source: &str) -> usize {
+    let (file, diags) = parse(source);
+    assert!(diags.is_empty(), "{:?}", diags);
+    let ItemKind::Let(def) = &file.items[0].kind else {
+        panic!("expected let");
+    };
+    match &def.value.kind {
+        ExprKind::Call { args, .. } => args.len(),
+        other => panic!("expected call, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_parse_trailing_record_argument() {
+    assert_eq!(call_arg_count("let x = f(1) #{ a = 2 };"), 2);
+    assert_eq!(call_arg_count("let x = f() #{ a = 2 };"), 1);
+}
+
+#[test]
+fn test_parse_trailing_block_argument() {
+    assert_eq!(call_arg_count("let x = f(1) { let y = 2; y };"), 2);
+}
+
+#[test]
+fn test_parse_match_scrutinee_call_has_no_trailing_block() {
+    let (file, diags) = parse("let x = match f(1) { _ -> 0 };");
+    assert!(diags.is_empty(), "{:?}", diags);
+    let ItemKind::Let(def) = &file.items[0].kind else {
+        panic!("expected let");
+    };
+    assert!(matches!(def.value.kind, ExprKind::Match { .. }));
+
+    // Parentheses re-enable trailing blocks
+    assert!(parse("let x = match (f(1) { 2 }) { _ -> 0 };").1.is_empty());
+}
+
 #[test]
 fn test_parse_record() {
     let (file, diags) = parse("let r = #{ x = 1, y = 2 };");
@@ -507,6 +547,26 @@ fn test_recovery_missing_semicolon() {
     assert!(!file.items.is_empty());
 }
 
+#[test]
+fn test_missing_semicolon_suggestion_fixes_source() {
+    let source = "let x = 42\nlet y = x + 1\nlet z = y;";
+    let (_, diags) = parse(source);
+    assert_eq!(diags.len(), 2, "{:?}", diags);
+    assert!(
+        diags
+            .iter()
+            .all(|d| d.code == Some(ErrorCode::MissingSemicolon))
+    );
+    let suggestion = &diags[0].suggestions[0];
+    assert_eq!(suggestion.replacement, ";");
+    assert_eq!(suggestion.span.start.0, 10);
+    assert!(suggestion.span.is_empty());
+
+    let fixed = apply_suggestions(source, &diags);
+    assert_eq!(fixed, "let x = 42;\nlet y = x + 1;\nlet z = y;");
+    assert!(parse(&fixed).1.is_empty());
+}
+
 #[test]
 fn test_recovery_invalid_expression() {
     let (file, diags) = parse("let x = @@@; let y = 10;");
@@ -984,6 +1044,40 @@ fn test_list_comprehension_multiple_generators() {
     assert!(diags.is_empty());
 }
 
+#[test]
+fn test_less_than_negative_without_spaces() {
+    use neve_syntax::{BinOp, UnaryOp};
+
+    let (expr, diags) = parse_expr("x<-1");
+    assert!(diags.is_empty(), "{diags:?}");
+    match expr.kind {
+        ExprKind::Binary {
+            op: BinOp::Lt,
+            right,
+            ..
+        } => assert!(
+            matches!(
+                right.kind,
+                ExprKind::Unary {
+                    op: UnaryOp::Neg,
+                    ..
+                }
+            ),
+            "{right:?}"
+        ),
+        other => panic!("expected a comparison, got {other:?}"),
+    }
+
+    let (_, diags) = parse("let ok = if x<-1 then 0 else 1;");
+    assert!(diags.is_empty(), "{diags:?}");
+}
+
+#[test]
+fn test_list_comprehension_condition_then_generator() {
+    let (_, diags) = parse("let pairs = [(x, y) | x <- xs, x > 0, y <- ys];");
+    assert!(diags.is_empty());
+}
+
 #[test]
 fn test_list_nested_matrix() {
     let (_, diags) = parse("let matrix = [[1, 2], [3, 4], [5, 6]];");
@@ -1362,6 +1456,47 @@ fn test_if_with_blocks() {
     assert!(diags.is_empty());
 }
 
+#[test]
+fn test_if_without_else() {
+    let (file, diags) = parse("let x = if a then 1;");
+    assert!(diags.is_empty());
+    let ItemKind::Let(def) = &file.items[0].kind else {
+        panic!("expected let");
+    };
+    assert!(matches!(
+        def.value.kind,
+        ExprKind::If {
+            else_branch: None,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn test_if_without_else_dangling() {
+    // The else belongs to the inner if
+    let (file, diags) = parse("let x = if a then if b then 1 else 2;");
+    assert!(diags.is_empty());
+    let ItemKind::Let(def) = &file.items[0].kind else {
+        panic!("expected let");
+    };
+    let ExprKind::If {
+        then_branch,
+        else_branch: None,
+        ..
+    } = &def.value.kind
+    else {
+        panic!("expected else-less if");
+    };
+    assert!(matches!(
+        then_branch.kind,
+        ExprKind::If {
+            else_branch: Some(_),
+            ..
+        }
+    ));
+}
+
 // ============================================================================
 // Additional Edge Cases - Error Recovery
 // ============================================================================
@@ -1524,3 +1659,95 @@ fn test_pipeline_heavy() {
     );
     assert!(diags.is_empty());
 }
+
+#[test]
+fn test_unclosed_delimiters_multiline_input() {
+    let lines = ["fn area(r) = {", "    let sq = r * r;", "    sq * 3", "};"];
+
+    let mut buffer = String::new();
+    let mut inputs = Vec::new();
+    for line in lines {
+        buffer.push_str(line);
+        buffer.push('\n');
+        if !has_unclosed_delimiters(&buffer) {
+            inputs.push(std::mem::take(&mut buffer));
+        }
+    }
+
+    assert_eq!(inputs.len(), 1);
+    let (file, diags) = parse(&inputs[0]);
+    assert!(diags.is_empty(), "{:?}", diags);
+    assert_eq!(file.items.len(), 1);
+    assert!(matches!(file.items[0].kind, ItemKind::Fn(_)));
+
+    assert!(has_unclosed_delimiters("#{ a = [1, 2"));
+    assert!(!has_unclosed_delimiters("let s = \"({[\"; -- ({["));
+}
+
+#[test]
+fn test_parse_standalone_expression() {
+    let (expr, diags) = parse_expr("1 + 2;");
+    assert!(diags.is_empty(), "{:?}", diags);
+    assert!(matches!(expr.kind, ExprKind::Binary { .. }));
+
+    // Anything after the expression is reported
+    let (_, diags) = parse_expr("1 + 2; let x = 3;");
+    assert_eq!(diags.len(), 1, "{:?}", diags);
+    assert!(diags[0].message.contains("end of expression"));
+}
+
+// ============================================================================
+// Robustness Tests
+// ============================================================================
+
+/// Parse on a separate thread, failing if it panics or does not finish.
+fn parse_terminates(source: String) -> usize {
+    let (tx, rx) = std::sync::mpsc::channel();
+    let len = source.len();
+    std::thread::Builder::new()
+        .stack_size(2 << 20)
+        .spawn(move || {
+            let (_, diags) = parse(&source);
+            let _ = tx.send(diags.len());
+        })
+        .unwrap();
+    let count = rx
+        .recv_timeout(std::time::Duration::from_secs(10))
+        .expect("parser panicked or did not terminate");
+    assert!(
+        count <= 8 * len + 8,
+        "{} diagnostics for {} bytes",
+        count,
+        len
+    );
+    count
+}
+
+const FRAGMENTS: &[&str] = &[
+    "let", "fn", "if", "then", "else", "match", "struct", "enum", "trait", "impl", "import", "x",
+    "Foo", "1", "2.5", "\"s\"", "\"${", "}", "{", "(", ")", "[", "]", "#{", "=", "==", "->", "=>",
+    "|>", "|", ",", ";", ":", ".", "?", "-", "!", "+", "*", "<", ">", "_", " ", "\n", "--",
+];
+
+proptest! {
+    #[test]
+    fn prop_parse_arbitrary_text(source in "\\PC{0,200}") {
+        parse_terminates(source);
+    }
+
+    #[test]
+    fn prop_parse_token_soup(parts in prop::collection::vec(prop::sample::select(FRAGMENTS), 0..120)) {
+        parse_terminates(parts.join(" "));
+    }
+}
+
+#[test]
+fn test_parse_deep_nesting_is_an_error() {
+    for open in ["(", "[", "-", "fn(x) ", "if true then ", "#{a = "] {
+        let source = format!("let x = {}1;", open.repeat(10_000));
+        assert!(parse_terminates(source) >= 1, "no error for {:?}", open);
+    }
+
+    let source = format!("let x: {}Int;", "(".repeat(10_000));
+    assert!(parse_terminates(source) >= 1);
+}
@@ -47,6 +47,58 @@ fn test_map_singleton() {
     }
 }
 
+fn string(s: &str) -> Value {
+    Value::String(Rc::new(s.to_string()))
+}
+
+fn unordered_map() -> Value {
+    let pairs = ["delta", "alpha", "charlie", "bravo"]
+        .iter()
+        .enumerate()
+        .map(|(i, k)| Value::Tuple(Rc::new(vec![string(k), Value::Int(i as i64)])))
+        .collect();
+    let from_list = get_builtin("Map.fromList").unwrap();
+    call_builtin_fn(&from_list, vec![Value::List(Rc::new(pairs))]).unwrap()
+}
+
+#[test]
+fn test_map_keys_are_sorted() {
+    let keys = get_builtin("Map.keys").unwrap();
+    let result = call_builtin_fn(&keys, vec![unordered_map()]).unwrap();
+    let Value::List(keys) = result else {
+        panic!("Expected List");
+    };
+    let keys: Vec<String> = keys
+        .iter()
+        .map(|k| k.as_string().unwrap().to_string())
+        .collect();
+    let mut sorted = keys.clone();
+    sorted.sort();
+    assert_eq!(keys, sorted);
+    assert_eq!(keys.len(), 4);
+}
+
+#[test]
+fn test_map_to_json_is_sorted_and_stable() {
+    let to_json = neve_eval::builtins()
+        .into_iter()
+        .find(|(n, _)| *n == "toJSON")
+        .map(|(_, v)| v)
+        .unwrap();
+    let Value::Builtin(to_json) = to_json else {
+        panic!("Expected Builtin");
+    };
+
+    let json = (to_json.func)(&[unordered_map()]).unwrap();
+    assert_eq!(
+        json.as_string().unwrap(),
+        r#"{"alpha":1,"bravo":3,"charlie":2,"delta":0}"#
+    );
+    for _ in 0..3 {
+        assert_eq!((to_json.func)(&[unordered_map()]).unwrap(), json);
+    }
+}
+
 // Set tests
 
 #[test]
@@ -1052,6 +1104,29 @@ fn test_list_sort_with_duplicates() {
     }
 }
 
+#[test]
+fn test_list_sort_tuples() {
+    let Some(Value::Builtin(builtin)) = get_builtin("list.sort") else {
+        panic!("Expected Builtin");
+    };
+    let key = |a: i64, b: &str| {
+        Value::Tuple(Rc::new(vec![
+            Value::Int(a),
+            Value::String(Rc::new(b.to_string())),
+        ]))
+    };
+    let list = Value::List(Rc::new(vec![key(2, "a"), key(1, "b"), key(1, "a")]));
+    let result = (builtin.func)(&[list]).unwrap();
+    assert_eq!(
+        result,
+        Value::List(Rc::new(vec![key(1, "a"), key(1, "b"), key(2, "a")]))
+    );
+
+    let mixed = Value::List(Rc::new(vec![Value::Int(1), Value::Bool(true)]));
+    let err = (builtin.func)(&[mixed]).unwrap_err();
+    assert!(err.contains("cannot compare"), "{err}");
+}
+
 #[test]
 fn test_list_sort_strings() {
     let sort_fn = get_builtin("list.sort").unwrap();
@@ -2099,3 +2174,189 @@ fn test_set_insert_wrong_arity() {
     let result = call_builtin_fn(&insert, vec![Value::Int(1)]);
     assert!(result.is_err());
 }
+
+// Math bitwise and number theory tests
+
+fn call_math(name: &str, args: &[Value]) -> Result<Value, String> {
+    match get_builtin(name) {
+        Some(Value::Builtin(builtin)) => (builtin.func)(args),
+        _ => panic!("{} not found", name),
+    }
+}
+
+#[test]
+fn test_math_bitwise_ops() {
+    let (a, b) = (Value::Int(0b1100), Value::Int(0b1010));
+    assert_eq!(
+        call_math("math.band", &[a.clone(), b.clone()]),
+        Ok(Value::Int(0b1000))
+    );
+    assert_eq!(
+        call_math("math.bor", &[a.clone(), b.clone()]),
+        Ok(Value::Int(0b1110))
+    );
+    assert_eq!(call_math("math.bxor", &[a, b]), Ok(Value::Int(0b0110)));
+    assert_eq!(call_math("math.bnot", &[Value::Int(0)]), Ok(Value::Int(-1)));
+    assert!(call_math("math.band", &[Value::Int(1), Value::Float(1.0)]).is_err());
+}
+
+#[test]
+fn test_math_shifts() {
+    assert_eq!(
+        call_math("math.shl", &[Value::Int(1), Value::Int(4)]),
+        Ok(Value::Int(16))
+    );
+    assert_eq!(
+        call_math("math.shr", &[Value::Int(16), Value::Int(4)]),
+        Ok(Value::Int(1))
+    );
+    // Arithmetic right shift keeps the sign
+    assert_eq!(
+        call_math("math.shr", &[Value::Int(-16), Value::Int(2)]),
+        Ok(Value::Int(-4))
+    );
+    assert_eq!(
+        call_math("math.shl", &[Value::Int(1), Value::Int(63)]),
+        Ok(Value::Int(i64::MIN))
+    );
+}
+
+#[test]
+fn test_math_shift_count_out_of_range() {
+    for count in [64, 100, -1] {
+        assert!(call_math("math.shl", &[Value::Int(1), Value::Int(count)]).is_err());
+        assert!(call_math("math.shr", &[Value::Int(1), Value::Int(count)]).is_err());
+    }
+}
+
+#[test]
+fn test_math_gcd_lcm() {
+    assert_eq!(
+        call_math("math.gcd", &[Value::Int(12), Value::Int(18)]),
+        Ok(Value::Int(6))
+    );
+    assert_eq!(
+        call_math("math.gcd", &[Value::Int(-12), Value::Int(18)]),
+        Ok(Value::Int(6))
+    );
+    assert_eq!(
+        call_math("math.gcd", &[Value::Int(0), Value::Int(0)]),
+        Ok(Value::Int(0))
+    );
+    assert_eq!(
+        call_math("math.lcm", &[Value::Int(4), Value::Int(6)]),
+        Ok(Value::Int(12))
+    );
+    assert_eq!(
+        call_math("math.lcm", &[Value::Int(0), Value::Int(6)]),
+        Ok(Value::Int(0))
+    );
+    assert!(call_math("math.lcm", &[Value::Int(i64::MAX), Value::Int(2)]).is_err());
+    assert!(call_math("math.gcd", &[Value::Int(i64::MIN), Value::Int(0)]).is_err());
+}
+
+// JSON tests
+
+fn call_json(name: &str, arg: Value) -> Result<Value, String> {
+    match get_builtin(name) {
+        Some(Value::Builtin(builtin)) => (builtin.func)(&[arg]),
+        _ => panic!("{} not found", name),
+    }
+}
+
+#[test]
+fn test_json_to_json() {
+    let string = |s: &str| Value::String(Rc::new(s.to_string()));
+    let record = Value::Record(Rc::new(
+        [
+            ("name".to_string(), string("neve")),
+            ("port".to_string(), Value::Int(8080)),
+            ("debug".to_string(), Value::Bool(false)),
+            ("ratio".to_string(), Value::Float(0.5)),
+            ("tls".to_string(), Value::None),
+            ("user".to_string(), Value::Some(Box::new(string("root")))),
+            (
+                "tags".to_string(),
+                Value::List(Rc::new(vec![string("a"), Value::Unit])),
+            ),
+        ]
+        .into_iter()
+        .collect(),
+    ));
+    assert_eq!(
+        call_json("json.toJSON", record),
+        Ok(string(
+            r#"{"debug":false,"name":"neve","port":8080,"ratio":0.5,"tags":["a",null],"tls":null,"user":"root"}"#
+        ))
+    );
+
+    let Some(function) = get_builtin("list.sort") else {
+        panic!("list.sort not found");
+    };
+    let nested = Value::List(Rc::new(vec![Value::Int(1), function]));
+    let err = call_json("json.toJSON", nested).unwrap_err();
+    assert_eq!(err, "cannot serialize function to JSON");
+}
+
+#[test]
+fn test_json_map_keys_and_set_elements() {
+    let map = Value::Map(Rc::new(
+        [
+            ("\"a\tb\"".to_string(), Value::Int(1)),
+            ("2".to_string(), Value::Bool(true)),
+        ]
+        .into_iter()
+        .collect(),
+    ));
+    let set = Value::Set(Rc::new(
+        ["\"x\"", "3", "'c'"]
+            .into_iter()
+            .map(str::to_string)
+            .collect(),
+    ));
+    let record = Value::Record(Rc::new(
+        [("m".to_string(), map), ("s".to_string(), set)]
+            .into_iter()
+            .collect(),
+    ));
+    assert_eq!(
+        call_json("json.toJSON", record),
+        Ok(Value::String(Rc::new(
+            r#"{"m":{"2":true,"a\tb":1},"s":["x","c",3]}"#.to_string()
+        )))
+    );
+
+    // Only options are flattened; results keep their tag
+    assert_eq!(
+        call_json(
+            "json.toJSON",
+            Value::Ok(Box::new(Value::Some(Box::new(Value::Int(1)))))
+        ),
+        Ok(Value::String(Rc::new(
+            r#"{"tag":"Ok","value":1}"#.to_string()
+        )))
+    );
+}
+
+#[test]
+fn test_json_from_json() {
+    let value = call_json(
+        "json.fromJSON",
+        Value::String(Rc::new(r#"{"a": [1, 2.5, true], "b": null}"#.to_string())),
+    )
+    .unwrap();
+    let Value::Record(fields) = value else {
+        panic!("Expected Record");
+    };
+    assert_eq!(
+        fields["a"],
+        Value::List(Rc::new(vec![
+            Value::Int(1),
+            Value::Float(2.5),
+            Value::Bool(true)
+        ]))
+    );
+    assert_eq!(fields["b"], Value::None);
+
+    assert!(call_json("json.fromJSON", Value::Int(1)).is_err());
+}
@@ -1,9 +1,13 @@
 //! Integration tests for neve-store crate.
 
 use neve_derive::{Derivation, Hash, Output, StorePath};
-use neve_store::{Database, GcResult, PathInfo, Store, store_dir};
+use neve_store::stats::largest_paths;
+use neve_store::{Database, GarbageCollector, GcResult, PathInfo, Store, StoreStats, store_dir};
 use std::env;
 use std::fs;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
 
 fn temp_store(suffix: &str) -> Store {
     let dir = env::temp_dir().join(format!("neve-store-test-{}-{}", std::process::id(), suffix));
@@ -75,6 +79,131 @@ fn test_store_content_addressable() {
     let _ = fs::remove_dir_all(store.root());
 }
 
+#[test]
+fn test_largest_paths() {
+    let store = temp_store("largest");
+    let mut db = Database::open(store.root().to_path_buf()).unwrap();
+
+    let mut paths = Vec::new();
+    for (name, size) in [("small", 10), ("big", 5000), ("medium", 300), ("tie", 300)] {
+        let hash = Hash::of(name.as_bytes());
+        let path = StorePath::new(hash, name.to_string());
+        db.register(PathInfo::new(path.clone(), hash, size))
+            .unwrap();
+        paths.push(path);
+    }
+
+    let largest = largest_paths(&store, &mut db, &paths, 3).unwrap();
+    let sizes: Vec<u64> = largest.iter().map(|p| p.size).collect();
+    assert_eq!(sizes, vec![5000, 300, 300]);
+    assert_eq!(largest[0].path.name(), "big");
+    // Equal sizes are ordered by path
+    assert!(largest[1].path < largest[2].path);
+
+    let _ = fs::remove_dir_all(store.root());
+}
+
+#[test]
+fn test_store_stats() {
+    let mut store = temp_store("stats");
+    let mut db = Database::open(store.root().to_path_buf()).unwrap();
+
+    store.add_content(b"a", "small.txt").unwrap();
+    let big = store.add_content(&[0u8; 4096], "big.bin").unwrap();
+    GarbageCollector::new(&mut store)
+        .add_root("big", &big)
+        .unwrap();
+
+    let stats = StoreStats::collect(&mut store, &mut db, 1).unwrap();
+    assert_eq!(stats.path_count, 2);
+    assert_eq!(stats.gc_roots, 1);
+    assert!(stats.total_size >= 4097);
+    assert_eq!(stats.largest.len(), 1);
+    assert_eq!(stats.largest[0].path.display_name(), big.display_name());
+
+    let _ = fs::remove_dir_all(store.root());
+}
+
+#[test]
+fn test_register_fetched() {
+    use neve_fetch::{FetchResult, Fetcher, Source};
+
+    let store = temp_store("fetched");
+    let work = tempfile::TempDir::new().unwrap();
+    let src = work.path().join("hello.txt");
+    fs::write(&src, b"hello").unwrap();
+
+    let fetcher = Fetcher::new(work.path().join("cache")).unwrap();
+    let result = fetcher.fetch(&Source::path(&src)).unwrap();
+    let dep = store.add_content(b"dep", "dep.txt").unwrap();
+    let path = store
+        .register_fetched(&result, std::slice::from_ref(&dep))
+        .unwrap();
+
+    assert_eq!(path.hash(), &result.hash);
+    assert_eq!(path.name(), "hello.txt");
+    assert_eq!(fs::read(store.to_path(&path)).unwrap(), b"hello");
+    let mut db = Database::open(store.root().to_path_buf()).unwrap();
+    assert!(db.get_references(&path).unwrap().contains(&dep));
+
+    // A cache entry whose name disagrees with the claimed hash is refused
+    let cached = work
+        .path()
+        .join(format!("{}-hello.txt", Hash::of(b"other").digest_hex()));
+    fs::write(&cached, b"hello").unwrap();
+    let mismatched = FetchResult {
+        path: cached,
+        hash: result.hash,
+        cached: true,
+    };
+    assert!(store.register_fetched(&mismatched, &[]).is_err());
+
+    let _ = fs::remove_dir_all(store.root());
+}
+
+#[test]
+fn test_register_fetched_sha256() {
+    use neve_derive::HashAlgo;
+    use neve_fetch::FetchResult;
+    use neve_store::StoreError;
+
+    let store = temp_store("fetched-sha256");
+    let work = tempfile::TempDir::new().unwrap();
+    let hash = Hash::of_with(HashAlgo::Sha256, b"hello");
+
+    let cached = work.path().join(format!("{}-hello.txt", hash.digest_hex()));
+    fs::write(&cached, b"hello").unwrap();
+    let result = FetchResult {
+        path: cached,
+        hash,
+        cached: true,
+    };
+    let path = store.register_fetched(&result, &[]).unwrap();
+    assert_eq!(path.hash(), &hash);
+    assert_eq!(path.name(), "hello.txt");
+
+    // A mismatched name is reported in the fetch's algorithm
+    let other = Hash::of_with(HashAlgo::Sha256, b"other");
+    let cached = work
+        .path()
+        .join(format!("{}-other.txt", other.digest_hex()));
+    fs::write(&cached, b"hello").unwrap();
+    let mismatched = FetchResult {
+        path: cached,
+        hash,
+        cached: true,
+    };
+    match store.register_fetched(&mismatched, &[]) {
+        Err(StoreError::HashMismatch { expected, actual }) => {
+            assert_eq!(expected, hash);
+            assert_eq!(actual, other);
+        }
+        other => panic!("expected HashMismatch, got {:?}", other),
+    }
+
+    let _ = fs::remove_dir_all(store.root());
+}
+
 // Database tests
 
 #[test]
@@ -143,6 +272,43 @@ fn test_gc_result_zero() {
     assert!(result.freed_human().contains("0"));
 }
 
+#[test]
+fn test_gc_keeps_path_registered_during_collection() {
+    let mut store = temp_store("gc-lock");
+    let root = store.root().to_path_buf();
+    let garbage = store.add_content(b"unreferenced", "garbage").unwrap();
+
+    let (registered_tx, registered_rx) = mpsc::channel();
+    let builder = {
+        let root = root.clone();
+        thread::spawn(move || {
+            let mut store = Store::open_at(root).unwrap();
+            let lock = store.lock_shared().unwrap();
+            let path = store.add_content(b"fresh build output", "output").unwrap();
+            registered_tx.send(()).unwrap();
+
+            // The collector starts while the new path is not yet rooted
+            thread::sleep(Duration::from_millis(200));
+            GarbageCollector::new(&mut store)
+                .add_root("result", &path)
+                .unwrap();
+            drop(lock);
+            path
+        })
+    };
+
+    registered_rx.recv().unwrap();
+    let result = GarbageCollector::new(&mut store).collect().unwrap();
+    let output = builder.join().unwrap();
+
+    assert!(store.path_exists(&output));
+    assert!(!store.path_exists(&garbage));
+    assert_eq!(result.deleted, 1);
+
+    // Cleanup
+    let _ = fs::remove_dir_all(&root);
+}
+
 // ============================================================================
 // Hash 边缘测试
 // ============================================================================
@@ -2,10 +2,11 @@
 //!
 //! This file contains extensive edge case tests for type checking.
 
-use neve_diagnostic::Diagnostic;
-use neve_hir::lower;
+use neve_diagnostic::{Diagnostic, DiagnosticKind, ErrorCode, Severity};
+use neve_hir::{BinOp, Expr, ExprKind, ItemKind, Ty, TyKind, lower};
 use neve_parser::parse;
-use neve_typeck::TypeChecker;
+use neve_typeck::{CompileOptions, TypeChecker, annotate, compile, compile_with, format_type};
+use std::collections::HashMap;
 
 fn check_source(source: &str) -> Vec<Diagnostic> {
     let (ast, parse_diags) = parse(source);
@@ -235,6 +236,30 @@ fn test_typeck_if_nested() {
     check_no_errors("let x = if true then if false then 1 else 2 else 3;");
 }
 
+#[test]
+fn test_typeck_if_without_else() {
+    // Without else the expression is Unit, so the branch must be too
+    check_no_errors("let x = if true then ();");
+    check_has_errors("let x = if true then 1;");
+}
+
+#[test]
+fn test_typeck_if_without_else_diagnostic() {
+    let source = "let x = if true then 1;";
+    let diagnostics = check_source(source);
+    let diag = diagnostics
+        .iter()
+        .find(|d| d.severity == Severity::Error)
+        .expect("expected an error");
+    assert_eq!(diag.message, "`if` without `else` must have type `()`");
+    assert!(diag.help.as_deref().unwrap_or("").contains("`else`"));
+    assert!(
+        diag.labels
+            .iter()
+            .any(|l| &source[l.span.range()] == "1" && l.message.contains("`Int`"))
+    );
+}
+
 #[test]
 fn test_typeck_if_deeply_nested() {
     check_no_errors("let x = if true then if true then if false then 1 else 2 else 3 else 4;");
@@ -260,6 +285,104 @@ fn test_typeck_if_condition_not_bool_string() {
     check_has_errors("let x = if \"true\" then 1 else 0;");
 }
 
+/// Byte offsets where each label of the only error in `source` starts.
+fn error_label_starts(source: &str) -> (Diagnostic, Vec<usize>) {
+    let mut errors: Vec<Diagnostic> = check_source(source)
+        .into_iter()
+        .filter(|d| d.severity == Severity::Error)
+        .collect();
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    let diag = errors.remove(0);
+    let starts = diag
+        .labels
+        .iter()
+        .map(|l| l.span.start.0 as usize)
+        .collect();
+    (diag, starts)
+}
+
+#[test]
+fn test_typeck_if_branch_mismatch_labels_both_branches() {
+    let source = "fn f(c: Bool) = if c then 1 else \"x\";";
+    let (diag, starts) = error_label_starts(source);
+    assert_eq!(diag.message, "`if` and `else` have incompatible types");
+    assert_eq!(
+        starts,
+        vec![source.find('1').unwrap(), source.find("\"x\"").unwrap()]
+    );
+    assert_eq!(diag.labels[0].message, "this branch has type `Int`");
+    assert_eq!(diag.labels[1].message, "this branch has type `String`");
+}
+
+#[test]
+fn test_typeck_mismatch_points_at_expected_type_origin() {
+    // Declared return type
+    let source = "fn f(x: Int) -> String = x + 1;";
+    let (diag, starts) = error_label_starts(source);
+    assert_eq!(diag.message, "type mismatch in function return type");
+    assert_eq!(diag.labels[0].message, "expected `String`, found `Int`");
+    assert_eq!(
+        starts,
+        vec![
+            source.find("x + 1").unwrap(),
+            source.find("String").unwrap()
+        ]
+    );
+
+    // Parameter of the called function
+    let source = "fn add(a: Int, b: Int) = a + b; let y = add(1, \"two\");";
+    let (diag, starts) = error_label_starts(source);
+    assert_eq!(diag.labels[0].message, "expected `Int`, found `String`");
+    assert_eq!(
+        starts,
+        vec![source.find("\"two\"").unwrap(), source.find("b:").unwrap()]
+    );
+
+    // First element of a list
+    let source = "let xs = [1, 2, \"three\"];";
+    let (_, starts) = error_label_starts(source);
+    assert_eq!(
+        starts,
+        vec![source.find("\"three\"").unwrap(), source.find('1').unwrap()]
+    );
+}
+
+#[test]
+fn test_typeck_mismatch_notes_the_differing_part() {
+    let (diag, _) = error_label_starts("fn f(p: (Int, Int)) = p; let y = f((1, \"a\"));");
+    assert_eq!(
+        diag.labels[0].message,
+        "expected `(Int, Int)`, found `(Int, String)`"
+    );
+    assert!(
+        diag.notes
+            .iter()
+            .any(|n| n == "`String` does not match `Int`"),
+        "{:?}",
+        diag
+    );
+}
+
+#[test]
+fn test_typeck_self_application_is_infinite_type() {
+    let source = "fn f(x) = x(x);";
+    let (diag, starts) = error_label_starts(source);
+    assert_eq!(diag.code, Some(ErrorCode::InfiniteType));
+    assert_eq!(
+        diag.message,
+        "cannot construct the infinite type `a = (a) -> b`"
+    );
+    assert_eq!(starts, vec![source.find("x(x)").unwrap()]);
+}
+
+#[test]
+fn test_typeck_infinite_type_reported_once() {
+    // The failed binding is poisoned, so later uses of `x` add no errors
+    let errors = check_source("fn f(x) = { let _y = x(x); x + 1 };");
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert_eq!(errors[0].code, Some(ErrorCode::InfiniteType));
+}
+
 // ============================================================================
 // 元组
 // ============================================================================
@@ -373,6 +496,45 @@ fn test_typeck_record_with_expressions() {
     check_no_errors("let x = #{ sum = 1 + 2, product = 3 * 4 };");
 }
 
+#[test]
+fn test_typeck_struct_construction() {
+    check_no_errors(
+        "struct Point { x: Int, y: Int, z: Int = 0 }; let s = Point(#{ x = 1, y = 2 }).z + 1;",
+    );
+    check_has_errors("struct Point { x: Int, y: Int }; let p = Point(#{ x = 1 });");
+    check_has_errors("struct Point { x: Int, y: Int }; let p = Point(#{ x = 1, y = \"2\" });");
+    check_has_errors("struct Point { x: Int }; let p = Point(#{ x = 1, w = 2 });");
+}
+
+#[test]
+fn test_typeck_record_field_access_is_row_polymorphic() {
+    check_no_errors(
+        "
+        fn getName(r) = r.name;
+        let a = getName(#{ name = \"alice\", age = 30 });
+        let b = getName(#{ name = \"bob\", email = \"b@example.com\" });
+    ",
+    );
+    check_no_errors(
+        "fn f() = { let get = fn(r) r.x; get(#{ x = 1 }) + get(#{ x = 2, y = true }) };",
+    );
+
+    let diags = check_source("fn both(r) = r.a + r.b; let x = both(#{ a = 1 });");
+    assert!(
+        diags
+            .iter()
+            .any(|d| d.notes.iter().any(|n| n == "record has no field 'b'")),
+        "{:?}",
+        diags
+    );
+}
+
+#[test]
+fn test_typeck_record_pattern_is_row_polymorphic() {
+    check_no_errors("fn f(p) = match p { #{ on = true, n } -> n, #{ on = false } -> 0 };");
+    check_no_errors("let x = match #{ a = 1, b = 2 } { #{ a } -> a };");
+}
+
 // ============================================================================
 // 函数定义
 // ============================================================================
@@ -636,6 +798,129 @@ fn test_typeck_match_returns_consistent_type() {
     );
 }
 
+/// Notes of the non-exhaustive match warnings for `source`.
+fn missing_patterns(source: &str) -> Vec<String> {
+    check_source(source)
+        .into_iter()
+        .filter(|d| d.message == "non-exhaustive pattern match")
+        .inspect(|d| assert_eq!(d.severity, Severity::Warning))
+        .flat_map(|d| d.notes)
+        .collect()
+}
+
+fn unreachable_arms(source: &str) -> usize {
+    check_source(source)
+        .iter()
+        .filter(|d| d.message == "unreachable pattern")
+        .count()
+}
+
+#[test]
+fn test_typeck_match_missing_enum_variant() {
+    let source = "
+        enum Shape { Circle(Float), Square(Float), Point };
+        fn size(s: Shape) = match s { Circle(r) -> r, Square(x) -> x };
+    ";
+    assert_eq!(missing_patterns(source), vec!["missing patterns: Point"]);
+
+    check_no_errors(
+        "
+        enum Shape { Circle(Float), Square(Float), Point };
+        fn size(s: Shape) = match s { Circle(r) -> r, Square(x) -> x, Point -> 0.0 };
+    ",
+    );
+}
+
+#[test]
+fn test_typeck_enum_variant_constructors() {
+    let source = "
+        enum Color { Red, Rgb(Int, Int, Int) };
+        fn total(c: Color) = match c { Red -> 0, Rgb(r, g, b) -> r + g + b };
+        let a: Int = total(Rgb(1, 2, 3));
+        let b: Color = Red;
+    ";
+    check_no_errors(source);
+
+    // Fields are checked against the variant's declaration
+    check_has_errors("enum Color { Red, Rgb(Int, Int, Int) }; let c = Rgb(1, true, 3);");
+    check_has_errors(
+        "enum Color { Red, Rgb(Int, Int, Int) }; fn f(c: Color) = match c { Rgb(r, g, b) -> r && true, Red -> false };",
+    );
+}
+
+#[test]
+fn test_typeck_match_shared_variant_names() {
+    // The scrutinee's enum decides the family, whichever enum declares a
+    // same-named variant last
+    let source = "
+        enum Job { Running, Done };
+        enum Task { Waiting, Done };
+        fn f(j: Job) = match j { Running -> 1, Done -> 2 };
+        fn g(j: Job) = match j { Done -> 2 };
+    ";
+    for _ in 0..8 {
+        assert_eq!(missing_patterns(source), vec!["missing patterns: Running"]);
+        assert_eq!(unreachable_arms(source), 0);
+    }
+}
+
+#[test]
+fn test_typeck_match_missing_option_result_bool() {
+    assert_eq!(
+        missing_patterns("fn f(o) = match o { Some(x) -> x };"),
+        vec!["missing patterns: None"]
+    );
+    assert_eq!(
+        missing_patterns("fn f(r) = match r { Ok(x) -> x };"),
+        vec!["missing patterns: Err(_)"]
+    );
+    assert_eq!(
+        missing_patterns("fn f(b) = match b { true -> 1 };"),
+        vec!["missing patterns: false"]
+    );
+    // A guarded arm does not cover its pattern
+    assert_eq!(
+        missing_patterns("fn f(o) = match o { Some(x) if x > 1 -> x, None -> 0 };"),
+        vec!["missing patterns: Some(_)"]
+    );
+}
+
+#[test]
+fn test_typeck_match_nested_and_or_patterns() {
+    check_no_errors("fn f(o) = match o { Some(true) -> 1, Some(false) -> 2, None -> 3 };");
+    check_no_errors("fn f(o) = match o { Some(1) | Some(2) -> 1, Some(_) | None -> 2 };");
+    check_no_errors("fn f(o) = match o { all @ Some(_) -> all, None -> o };");
+    assert_eq!(
+        missing_patterns("fn f(o) = match o { Some(true) -> 1, None -> 3 };"),
+        vec!["missing patterns: Some(false)"]
+    );
+    assert_eq!(
+        missing_patterns("fn f(l) = match l { [] -> 0, [_] -> 1 };"),
+        vec!["missing patterns: [_, _, ..]"]
+    );
+}
+
+#[test]
+fn test_typeck_match_unreachable_arms() {
+    assert_eq!(
+        unreachable_arms("fn f(b) = match b { _ -> 1, true -> 2 };"),
+        1
+    );
+    assert_eq!(
+        unreachable_arms("fn f(o) = match o { Some(_) -> 1, None -> 2, Some(3) -> 4 };"),
+        1
+    );
+    // Covered only by the two earlier arms together
+    assert_eq!(
+        unreachable_arms("fn f(b) = match b { true -> 1, false -> 2, _ -> 3 };"),
+        1
+    );
+    assert_eq!(
+        unreachable_arms("fn f(o) = match o { Some(x) if x > 0 -> 1, Some(_) -> 2, None -> 3 };"),
+        0
+    );
+}
+
 // ============================================================================
 // 一元运算符
 // ============================================================================
@@ -751,6 +1036,56 @@ fn test_typeck_let_uses_previous() {
     );
 }
 
+#[test]
+fn test_typeck_let_annotations_agree() {
+    check_no_errors("let n: Int = 1; let m = n + 1;");
+    check_no_errors("fn f(x: Int) = { let y: Int = x; y + 1 };");
+    check_no_errors("let g = fn(x: Int) x + 1; let y = g(2);");
+}
+
+#[test]
+fn test_typeck_zero_argument_fn_call() {
+    check_no_errors("fn f() = 1; let x = f();");
+    check_no_errors("fn f() -> Int = 1; let x: Int = f() + 1;");
+    check_has_errors("fn f() = 1; let x = f; let y: Int = x;");
+}
+
+#[test]
+fn test_typeck_partial_application() {
+    let add = "fn add(a: Int, b: Int) -> Int = a + b;";
+    check_no_errors(&format!("{add} let inc = add(1); let x: Int = inc(2);"));
+    check_has_errors(&format!("{add} let x: Int = add(1);"));
+    check_has_errors(&format!("{add} let inc = add(\"one\");"));
+    check_has_errors(&format!("{add} let x = add(1, 2, 3);"));
+    check_has_errors(&format!("{add} let x = add();"));
+}
+
+#[test]
+fn test_typeck_let_annotation_conflict_labels_annotation() {
+    let source = "let x: Int = \"hi\";";
+    let (diag, starts) = error_label_starts(source);
+    assert_eq!(diag.message, "mismatched types");
+    assert_eq!(
+        starts,
+        vec![source.find('"').unwrap(), source.find("Int").unwrap()]
+    );
+
+    let source = "fn f() = { let y: Bool = 1; y };";
+    let (_, starts) = error_label_starts(source);
+    assert_eq!(
+        starts,
+        vec![source.find('1').unwrap(), source.find("Bool").unwrap()]
+    );
+}
+
+#[test]
+fn test_typeck_annotations_constrain_inference() {
+    // 注解约束了原本未知的类型
+    check_has_errors("fn f(x) = { let y: Int = x; y }; let z = f(\"a\");");
+    check_has_errors("let g = fn(x: Int) x; let y = g(true);");
+    check_has_errors("let n = 1; let b: Bool = n;");
+}
+
 // ============================================================================
 // 复杂表达式
 // ============================================================================
@@ -825,12 +1160,17 @@ fn test_typeck_polymorphic_identity() {
 #[test]
 fn test_typeck_polymorphic_const() {
     // 单次调用多态函数是可以的
-    check_no_errors(
+    // The unused `y` only draws a warning
+    let errors: Vec<_> = check_source(
         "
         fn const_val(x, y) = x;
         let a = const_val(1, true);
     ",
-    );
+    )
+    .into_iter()
+    .filter(|d| d.severity == Severity::Error)
+    .collect();
+    assert!(errors.is_empty(), "unexpected errors: {:?}", errors);
 }
 
 #[test]
@@ -951,3 +1291,280 @@ fn test_typeck_detects_match_arm_mismatch() {
     ",
     );
 }
+
+// ============================================================================
+// 警告与 deny-warnings
+// ============================================================================
+
+#[test]
+fn test_typeck_warning_only_is_not_fatal_unless_denied() {
+    let diags = check_source("fn f(x) = { let unused = 1; x };");
+
+    assert!(!diags.is_empty(), "expected an unused variable warning");
+    assert!(diags.iter().all(|d| d.severity == Severity::Warning));
+    assert!(!diags.iter().any(|d| d.is_fatal(false)));
+    assert!(diags.iter().any(|d| d.is_fatal(true)));
+}
+
+#[test]
+fn test_compile_warning_only_file_is_fatal_only_when_denied() {
+    // Warnings from name resolution count just like type checker warnings
+    let result = compile("fn helper() = 1;\nfn f(x) = { let unused = 1; x };\nlet y = f(2);");
+    assert!(!result.has_errors(), "{:?}", result.diagnostics);
+    assert!(
+        result
+            .diagnostics
+            .iter()
+            .any(|d| d.kind == DiagnosticKind::Module)
+    );
+    assert!(
+        result
+            .diagnostics
+            .iter()
+            .any(|d| d.kind == DiagnosticKind::Type)
+    );
+    assert_eq!(result.fatal_count(false), 0);
+    assert_eq!(result.fatal_count(true), result.diagnostics.len());
+}
+
+fn unused_params(source: &str) -> Vec<Diagnostic> {
+    let diags = check_source(source);
+    assert!(
+        diags.iter().all(|d| d.severity == Severity::Warning),
+        "{:?}",
+        diags
+    );
+    diags
+        .into_iter()
+        .filter(|d| d.message.starts_with("unused parameter"))
+        .collect()
+}
+
+#[test]
+fn test_typeck_unused_parameter_warns() {
+    let source = "fn f(x, y) = x;";
+    let diags = unused_params(source);
+    assert_eq!(diags.len(), 1, "{:?}", diags);
+    assert_eq!(diags[0].message, "unused parameter: `y`");
+    assert_eq!(diags[0].severity, Severity::Warning);
+    assert_eq!(&source[diags[0].span.range()], "y");
+}
+
+#[test]
+fn test_typeck_used_parameter_does_not_warn() {
+    assert!(unused_params("fn f(x) = x + 1;").is_empty());
+    // Used only inside a nested closure
+    assert!(unused_params("fn f(x) = fn(y) x + y;").is_empty());
+    // Underscore-prefixed parameters are exempt
+    assert!(unused_params("fn f(_ignored, x) = x;").is_empty());
+}
+
+#[test]
+fn test_typeck_shadowed_parameter_warns() {
+    let diags = unused_params("fn f(x) = { let x = 1; x };");
+    assert_eq!(diags.len(), 1, "{:?}", diags);
+    assert_eq!(diags[0].message, "unused parameter: `x`");
+}
+
+// ============================================================================
+// 类型注释
+// ============================================================================
+
+fn annotate_source(source: &str) -> String {
+    let (ast, parse_diags) = parse(source);
+    assert!(parse_diags.is_empty(), "{:?}", parse_diags);
+    let hir = lower(&ast);
+    let mut checker = TypeChecker::new();
+    checker.check(&hir);
+    annotate(source, checker.item_types())
+}
+
+#[test]
+fn test_annotate_let_binding() {
+    assert_eq!(
+        annotate_source("let x = 1 + 2;"),
+        "let x = 1 + 2;  -- : Int"
+    );
+}
+
+#[test]
+fn test_annotate_open_record() {
+    let annotated = annotate_source("fn getName(r) = r.name;");
+    assert!(
+        annotated.contains("-- : (#{ name: ") && annotated.contains(", .. }) -> "),
+        "{annotated}"
+    );
+}
+
+#[test]
+fn test_annotate_function_signature() {
+    let annotated =
+        annotate_source("fn add(a: Int, b: Int) -> Int =\n    a + b;\nlet s = \"hi\";\n");
+    assert_eq!(
+        annotated,
+        "fn add(a: Int, b: Int) -> Int =  -- : (Int, Int) -> Int\n    a + b;\nlet s = \"hi\";  -- : String\n"
+    );
+}
+
+#[test]
+fn test_annotate_before_trailing_comment() {
+    let annotated = annotate_source("let x = 1; -- the answer\nlet s = \"a -- b\";\n-- done\n");
+    assert_eq!(
+        annotated,
+        "let x = 1;  -- : Int  -- the answer\nlet s = \"a -- b\";  -- : String\n-- done\n"
+    );
+}
+
+// ============================================================================
+// 编译流水线
+// ============================================================================
+
+#[test]
+fn test_compile_reports_unresolved_names_with_suggestions() {
+    let source = "let count = 1;\nlet y = cont + 1;\n";
+    let result = compile(source);
+    let errors: Vec<_> = result
+        .diagnostics
+        .iter()
+        .filter(|d| d.severity == Severity::Error)
+        .collect();
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert_eq!(errors[0].message, "cannot find value `cont` in this scope");
+    assert_eq!(&source[errors[0].span.range()], "cont");
+    assert_eq!(errors[0].help.as_deref(), Some("did you mean `count`?"));
+
+    // Unbound method names and record shorthands are reported the same way
+    let result = compile("let r = #{ missing };\nlet n = [1].size();\n");
+    let messages: Vec<_> = result.diagnostics.iter().map(|d| &d.message).collect();
+    assert!(
+        messages.contains(&&"cannot find value `missing` in this scope".to_string()),
+        "{:?}",
+        messages
+    );
+    assert!(
+        messages.contains(&&"cannot find value `size` in this scope".to_string()),
+        "{:?}",
+        messages
+    );
+    assert!(!messages.iter().any(|m| m.as_str() == "undefined global"));
+}
+
+#[test]
+fn test_compile_pipeline_stages() {
+    let ok = compile("let x = 1 + 2;");
+    assert!(!ok.has_errors(), "{:?}", ok.diagnostics);
+    assert_eq!(ok.file.items.len(), 1);
+    assert!(ok.hir.is_some());
+
+    // Parse errors stop the pipeline before lowering.
+    let parse_err = compile("let x = ;");
+    assert!(parse_err.has_errors());
+    assert!(parse_err.hir.is_none());
+
+    // Type errors surface with the lowered module.
+    let type_err = compile("let x = 1 + \"a\";");
+    assert!(type_err.has_errors());
+    assert!(type_err.hir.is_some());
+    assert!(
+        type_err
+            .diagnostics
+            .iter()
+            .any(|d| d.kind == DiagnosticKind::Type)
+    );
+
+    // Name resolution warnings surface too, unless turned off.
+    let unused = compile("fn helper() = 1;\nlet x = 2;");
+    assert!(!unused.has_errors(), "{:?}", unused.diagnostics);
+    assert!(
+        unused
+            .diagnostics
+            .iter()
+            .any(|d| d.kind == DiagnosticKind::Module && d.message.contains("helper"))
+    );
+    let quiet = CompileOptions {
+        warn_unused: false,
+        ..CompileOptions::default()
+    };
+    assert!(
+        compile_with("fn helper() = 1;\nlet x = 2;", &quiet)
+            .diagnostics
+            .is_empty()
+    );
+
+    // Without type checking, the module is only lowered.
+    let lowered_only = CompileOptions {
+        type_check: false,
+        ..CompileOptions::default()
+    };
+    let unchecked = compile_with("let x = 1 + \"a\";", &lowered_only);
+    assert!(!unchecked.has_errors(), "{:?}", unchecked.diagnostics);
+    assert!(unchecked.hir.is_some());
+}
+
+#[test]
+fn test_infer_sub_expression_in_checked_module() {
+    let source = "let b = { let a = 1; (a + 2) * 3 };\nlet c = \"x\";\nfn f(n: Int) -> Bool = n > 0;\nlet d = f(1);\n";
+    let (ast, parse_diags) = parse(source);
+    assert!(parse_diags.is_empty());
+    let hir = lower(&ast);
+    let mut checker = TypeChecker::new();
+    checker.check(&hir);
+
+    // The innermost expression under the cursor, as for hover
+    let (span, ty) = checker.type_at_offset(source.find('+').unwrap()).unwrap();
+    assert_eq!(&source[span.range()], "a + 2");
+    assert_eq!(format_type(&ty), "Int");
+
+    let bodies: Vec<&Expr> = hir
+        .items
+        .iter()
+        .filter_map(|item| match &item.kind {
+            ItemKind::Fn(def) => Some(&def.body),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        format_type(&checker.type_at(bodies[1].span).unwrap()),
+        "String"
+    );
+
+    // Re-inferring reuses the module's globals
+    let ty = checker.infer_expr(bodies[3], &HashMap::new());
+    assert_eq!(format_type(&ty.unwrap()), "Bool");
+
+    let binary = |left: &Expr, right: &Expr| Expr {
+        kind: ExprKind::Binary(BinOp::Add, Box::new(left.clone()), Box::new(right.clone())),
+        ty: left.ty.clone(),
+        span: left.span.merge(right.span),
+    };
+    let sum = checker.infer_expr(&binary(bodies[0], bodies[0]), &HashMap::new());
+    assert_eq!(format_type(&sum.unwrap()), "Int");
+    let errors = checker
+        .infer_expr(&binary(bodies[0], bodies[1]), &HashMap::new())
+        .unwrap_err();
+    assert!(!errors.is_empty());
+
+    // Locals can be supplied by the caller
+    let ExprKind::Binary(_, n, _) = &bodies[2].kind else {
+        panic!("expected a comparison");
+    };
+    let ExprKind::Var(id) = n.kind else {
+        panic!("expected a local variable");
+    };
+    let float = Ty {
+        kind: TyKind::Float,
+        span: n.span,
+    };
+    let ty = checker.infer_expr(n, &HashMap::from([(id, float)]));
+    assert_eq!(format_type(&ty.unwrap()), "Float");
+
+    // Probing leaves the checked module's types as they were
+    assert_eq!(format_type(&checker.type_at(n.span).unwrap()), "Int");
+    assert!(
+        checker
+            .type_at(bodies[0].span.merge(bodies[1].span))
+            .is_none()
+    );
+
+    assert!(checker.diagnostics().is_empty());
+}